@@ -1,10 +1,12 @@
-//! Foundation: stable ids, interning, and side-tables.
+//! Foundation: stable ids, interning, side-tables, and stack growth.
 //!
 //! Side-tables are how analysis results stay *outside* the IR core ("decorate,
 //! don't embed"): a pass produces a `SideTable<NodeId, T>` rather than mutating nodes.
 use std::collections::HashMap;
 use std::hash::Hash;
 
+pub mod stack;
+
 /// Stable identity of an IR node; the key type for [`SideTable`].
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
 pub struct NodeId(pub u32);
@@ -28,6 +30,13 @@ impl<T: Eq + Hash + Clone> Interner<T> {
         self.forward.insert(value, id);
         id
     }
+    /// Add `value` under an id of its own even if it is already interned;
+    /// [`Interner::intern`] keeps answering with the first id.
+    pub fn push(&mut self, value: T) -> u32 {
+        let id = self.backward.len() as u32;
+        self.backward.push(value);
+        id
+    }
     pub fn resolve(&self, id: u32) -> Option<&T> {
         self.backward.get(id as usize)
     }
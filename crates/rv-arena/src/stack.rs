@@ -0,0 +1,124 @@
+//! Stack growth for the passes whose recursion depth the input decides.
+//!
+//! The parser, lowering, the printers, and symbolic execution recurse once per
+//! level of nesting, and a debug build spends kilobytes of stack on each level.
+//! [`grow`] lets them go as deep as the parser's nesting limit admits on any
+//! thread: once a thread has used up its share, the recursion continues on a
+//! fresh thread with a stack of its own while the caller waits. Ordinary code
+//! never leaves the first segment.
+//!
+//! A segment is another thread, so the caller's thread-locals are not there. The
+//! ones the passes rely on, the `tracing` dispatcher `rvc --log` installs and
+//! the span it is in, are carried over; a `thread_local!` cache starts out empty
+//! in each segment.
+
+use std::cell::Cell;
+
+/// How much of a thread's own stack [`grow`] uses, measured from where it is
+/// first called on the thread. It must fit in what any caller has left (a test
+/// thread has 2 MiB in all).
+const FIRST_SEGMENT: usize = 1 << 20;
+
+/// The stack of each further segment.
+const SEGMENT_STACK: usize = 8 << 20;
+
+/// What a segment keeps free for the work between two calls to [`grow`].
+const HEADROOM: usize = 1 << 20;
+
+thread_local! {
+    /// The address below which this thread's segment is used up (stacks grow
+    /// down); `0` until `grow` first runs on the thread.
+    static LIMIT: Cell<usize> = const { Cell::new(0) };
+}
+
+/// An address in the caller's frame.
+#[inline(always)]
+fn stack_pointer() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+/// Run `f`, on a new stack segment if the current one is used up. Call it at
+/// each level of a recursion whose depth the input decides. A panic in `f`
+/// propagates to the caller, and its events go to the caller's `tracing`
+/// dispatcher, inside the caller's current span.
+pub fn grow<R: Send>(f: impl FnOnce() -> R + Send) -> R {
+    let sp = stack_pointer();
+    let limit = LIMIT.with(|limit| {
+        if limit.get() == 0 {
+            limit.set(sp.saturating_sub(FIRST_SEGMENT));
+        }
+        limit.get()
+    });
+    if sp >= limit {
+        return f();
+    }
+    let dispatch = tracing::dispatcher::get_default(|dispatch| dispatch.clone());
+    let span = tracing::Span::current();
+    std::thread::scope(|scope| {
+        let segment = std::thread::Builder::new()
+            .name("rv stack segment".to_string())
+            .stack_size(SEGMENT_STACK)
+            .spawn_scoped(scope, || {
+                let budget = SEGMENT_STACK - HEADROOM;
+                LIMIT.with(|limit| limit.set(stack_pointer().saturating_sub(budget)));
+                tracing::dispatcher::with_default(&dispatch, || span.in_scope(f))
+            })
+            .expect("could not start a thread for a deeper stack segment");
+        segment.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn depth(n: u32) -> u32 {
+        // A frame large enough that 10k of them overflow any default stack.
+        let pad = std::hint::black_box([0u8; 2048]);
+        match n {
+            0 => {
+                tracing::info!("bottom reached");
+                u32::from(pad[0])
+            }
+            n => grow(|| depth(n - 1)) + 1,
+        }
+    }
+
+    #[test]
+    fn recursion_deeper_than_the_thread_stack_completes() {
+        assert_eq!(depth(10_000), 10_000);
+    }
+
+    /// A `Write` handle onto a shared buffer, so a test can read back what the
+    /// subscriber printed.
+    #[derive(Clone, Default)]
+    struct Capture(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn segments_trace_to_the_callers_subscriber_and_span() {
+        let out = Capture::default();
+        let writer = out.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .without_time()
+            .with_ansi(false)
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            let _walk = tracing::info_span!("walk").entered();
+            depth(10_000)
+        });
+        let log = String::from_utf8(out.0.lock().unwrap().clone()).unwrap();
+        assert!(log.contains("walk: rv_arena::stack::tests: bottom reached"), "{log}");
+    }
+}
@@ -0,0 +1,341 @@
+//! Escape analysis: no reference to a function's own storage outlives its frame.
+//!
+//! Each local that can hold a reference gets the set of places it may point
+//! into: a local of this frame ([`Pt::Frame`]), or whatever the caller passed in
+//! a parameter ([`Pt::Caller`]). The sets grow to a fixpoint, flow-insensitively,
+//! so every path is covered at once. A frame place escapes when it reaches the
+//! returned value or is stored through a parameter.
+//!
+//! Calls are interprocedural. Each function's [`Summary`] says which parameters'
+//! pointees reach its result and which are stored through another parameter.
+//! Summaries are computed callees first over the [`CallGraph`], repeating a
+//! recursive component until it is stable. A callee with no body, such as a
+//! builtin, is assumed to return a value that may point into any argument.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use rv_core::{Sym, Symbols, Ty};
+use rv_ir::callgraph::CallGraph;
+use rv_ir::{Function, LocalId, Lowerable, Operand, Place, Program, Proj, RValue, Stmt, Terminator};
+use rv_ir::{TypeDef, VariantDef};
+
+use crate::BorrowError;
+
+/// A place a reference may point into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Pt {
+    /// The storage of a local (by index) of the function being analysed.
+    Frame(u32),
+    /// Storage the caller owns, reached through the parameter at this index.
+    Caller(usize),
+}
+
+/// Where a function lets each parameter's pointees go.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Summary {
+    /// The parameters whose pointees the result may point into.
+    returned: BTreeSet<usize>,
+    /// `(from, into)`: parameter `from`'s pointees are stored through `into`.
+    stored: BTreeSet<(usize, usize)>,
+}
+
+/// Report every local whose address outlives its function.
+pub(crate) fn check(prog: &Program<Lowerable>, syms: &Symbols) -> Vec<BorrowError> {
+    let types: HashMap<Sym, &TypeDef> = prog.types.iter().map(|d| (d.name(), d)).collect();
+    let funcs: HashMap<Sym, &Function<Lowerable>> =
+        prog.funcs.iter().map(|f| (f.name, f)).collect();
+    let graph = CallGraph::build(&prog.funcs);
+    let mut summaries: HashMap<Sym, Summary> = HashMap::new();
+    let mut errors = Vec::new();
+    for scc in graph.sccs() {
+        let members: Vec<&Function<Lowerable>> =
+            scc.iter().filter_map(|name| funcs.get(name).copied()).collect();
+        // A recursive component's summaries feed each other: starting from
+        // empty ones, recompute until none changes. Each pass only adds, so
+        // this terminates.
+        for f in &members {
+            summaries.insert(f.name, Summary::default());
+        }
+        loop {
+            let mut changed = false;
+            for f in &members {
+                let sum = Flow::solve(f, &types, &summaries).summary(f);
+                changed |= summaries.get(&f.name) != Some(&sum);
+                summaries.insert(f.name, sum);
+            }
+            if !changed {
+                break;
+            }
+        }
+        for f in members {
+            let flow = Flow::solve(f, &types, &summaries);
+            let func = syms.resolve(f.name).to_string();
+            for message in flow.escapes(f, syms) {
+                errors.push(BorrowError { func: func.clone(), message, site: None });
+            }
+        }
+    }
+    errors
+}
+
+/// A store of a value that may point into the second set, through a
+/// reference that may point into the first.
+type Store = (BTreeSet<Pt>, BTreeSet<Pt>);
+
+/// One function's points-to sets, solved.
+struct Flow<'a> {
+    /// What each local may point into; empty for one that holds no reference.
+    pts: Vec<BTreeSet<Pt>>,
+    /// Which locals can hold a reference at all.
+    holds_ref: Vec<bool>,
+    /// Whether the return type can hold a reference.
+    ret_holds_ref: bool,
+    summaries: &'a HashMap<Sym, Summary>,
+    /// Frame places stored through a parameter, with that parameter.
+    stored_out: Vec<(u32, usize)>,
+    /// Parameters' pointees stored through another parameter.
+    stored: BTreeSet<(usize, usize)>,
+}
+
+impl<'a> Flow<'a> {
+    fn solve(
+        f: &Function<Lowerable>,
+        types: &HashMap<Sym, &TypeDef>,
+        summaries: &'a HashMap<Sym, Summary>,
+    ) -> Flow<'a> {
+        let holds = |ty: &Ty| may_hold_ref(ty, true, types, &mut HashSet::new());
+        let holds_ref: Vec<bool> = f.locals.iter().map(|l| holds(&l.ty)).collect();
+        let mut pts = vec![BTreeSet::new(); f.locals.len()];
+        for (i, p) in f.params.iter().enumerate() {
+            if holds_ref[p.0 as usize] {
+                pts[p.0 as usize].insert(Pt::Caller(i));
+            }
+        }
+        let mut flow = Flow {
+            pts,
+            holds_ref,
+            ret_holds_ref: holds(&f.ret),
+            summaries,
+            stored_out: Vec::new(),
+            stored: BTreeSet::new(),
+        };
+        while flow.pass(f) {}
+        flow
+    }
+
+    /// One sweep over every statement; whether any set grew.
+    fn pass(&mut self, f: &Function<Lowerable>) -> bool {
+        let mut changed = false;
+        for stmt in f.blocks.iter().flat_map(|b| &b.stmts) {
+            match stmt {
+                Stmt::Assign(place, rv) => {
+                    let (value, through) = self.rvalue(rv);
+                    changed |= self.store(place, &value);
+                    for (targets, stored) in through {
+                        changed |= self.store_through(&targets, &stored);
+                    }
+                }
+                Stmt::Eval(rv) => {
+                    for (targets, stored) in self.rvalue(rv).1 {
+                        changed |= self.store_through(&targets, &stored);
+                    }
+                }
+                _ => {}
+            }
+        }
+        changed
+    }
+
+    /// What `rv` may point into, and the stores a call makes through its
+    /// arguments.
+    fn rvalue(&self, rv: &RValue) -> (BTreeSet<Pt>, Vec<Store>) {
+        let mut out = BTreeSet::new();
+        let mut through = Vec::new();
+        match rv {
+            RValue::Ref(_, place) => match last_deref(place) {
+                // `&(*r).f` points wherever `r` does.
+                Some(_) => out.extend(self.of(place.local)),
+                None => {
+                    out.insert(Pt::Frame(place.local.0));
+                }
+            },
+            RValue::Use(op) | RValue::Cast(op, _) => out.extend(self.operand(op)),
+            RValue::Aggregate(_, ops) | RValue::Closure(_, ops) => {
+                ops.iter().for_each(|op| out.extend(self.operand(op)));
+            }
+            RValue::Call(callee, args) => match self.summaries.get(callee) {
+                Some(sum) => {
+                    for &i in &sum.returned {
+                        out.extend(args.get(i).map(|a| self.operand(a)).unwrap_or_default());
+                    }
+                    for &(from, into) in &sum.stored {
+                        if let (Some(from), Some(into)) = (args.get(from), args.get(into)) {
+                            through.push((self.operand(into), self.operand(from)));
+                        }
+                    }
+                }
+                None => args.iter().for_each(|a| out.extend(self.operand(a))),
+            },
+            RValue::CallClosure(callee, args) => {
+                out.extend(self.operand(callee));
+                args.iter().for_each(|a| out.extend(self.operand(a)));
+            }
+            RValue::VecPush(v, x) => {
+                out.extend(self.operand(v));
+                out.extend(self.operand(x));
+            }
+            RValue::Bin(..) | RValue::WrappingBin(..) | RValue::Un(..) | RValue::VecLen(_) => {}
+        }
+        (out, through)
+    }
+
+    fn of(&self, local: LocalId) -> BTreeSet<Pt> {
+        self.pts[local.0 as usize].clone()
+    }
+
+    /// What the value `op` reads may point into. Read through a reference, it
+    /// is what the pointee holds: a frame local's set, or more of the caller's.
+    fn operand(&self, op: &Operand) -> BTreeSet<Pt> {
+        let Operand::Copy(place) = op else { return BTreeSet::new() };
+        if last_deref(place).is_none() {
+            return self.of(place.local);
+        }
+        let pointees = self.pts[place.local.0 as usize].iter();
+        pointees
+            .flat_map(|p| match *p {
+                Pt::Frame(local) => self.pts[local as usize].clone(),
+                Pt::Caller(_) => BTreeSet::from([*p]),
+            })
+            .collect()
+    }
+
+    /// `place = value`: into the local itself, or through the reference it
+    /// holds when the place reads through one.
+    fn store(&mut self, place: &Place, value: &BTreeSet<Pt>) -> bool {
+        if value.is_empty() {
+            return false;
+        }
+        match last_deref(place) {
+            Some(_) => {
+                let targets = self.of(place.local);
+                self.store_through(&targets, value)
+            }
+            None => self.add(place.local, value),
+        }
+    }
+
+    /// Store `value` into each of `targets`: a frame local's set grows, and a
+    /// store into the caller's storage is an escape.
+    fn store_through(&mut self, targets: &BTreeSet<Pt>, value: &BTreeSet<Pt>) -> bool {
+        let mut changed = false;
+        for t in targets {
+            match *t {
+                Pt::Frame(local) => changed |= self.add(LocalId(local), value),
+                Pt::Caller(into) => {
+                    for v in value {
+                        changed |= match *v {
+                            Pt::Frame(local) if !self.stored_out.contains(&(local, into)) => {
+                                self.stored_out.push((local, into));
+                                true
+                            }
+                            Pt::Caller(from) => self.stored.insert((from, into)),
+                            Pt::Frame(_) => false,
+                        };
+                    }
+                }
+            }
+        }
+        changed
+    }
+
+    fn add(&mut self, local: LocalId, value: &BTreeSet<Pt>) -> bool {
+        if !self.holds_ref[local.0 as usize] {
+            return false;
+        }
+        let set = &mut self.pts[local.0 as usize];
+        let before = set.len();
+        set.extend(value.iter().copied());
+        set.len() != before
+    }
+
+    /// What `f`'s returned values may point into.
+    fn returned(&self, f: &Function<Lowerable>) -> BTreeSet<Pt> {
+        if !self.ret_holds_ref {
+            return BTreeSet::new();
+        }
+        let rets = f.blocks.iter().filter_map(|b| match &b.term {
+            Terminator::Return(op) => Some(self.operand(op)),
+            _ => None,
+        });
+        rets.flatten().collect()
+    }
+
+    fn summary(&self, f: &Function<Lowerable>) -> Summary {
+        let returned = self.returned(f).into_iter().filter_map(|p| match p {
+            Pt::Caller(i) => Some(i),
+            Pt::Frame(_) => None,
+        });
+        Summary { returned: returned.collect(), stored: self.stored.clone() }
+    }
+
+    /// One message per frame local whose address leaves `f`.
+    fn escapes(&self, f: &Function<Lowerable>, syms: &Symbols) -> Vec<String> {
+        let name = |local: u32| match f.locals[local as usize].name {
+            Some(n) => format!("local variable `{}`", syms.resolve(n)),
+            None => "a temporary value".to_string(),
+        };
+        let mut out = Vec::new();
+        for p in self.returned(f) {
+            if let Pt::Frame(local) = p {
+                out.push(format!("cannot return a reference to {}", name(local)));
+            }
+        }
+        for &(local, into) in &self.stored_out {
+            let param = f.locals[f.params[into].0 as usize].name.map_or("_", |n| syms.resolve(n));
+            out.push(format!(
+                "cannot store a reference to {} through parameter `{param}`, which outlives it",
+                name(local)
+            ));
+        }
+        out
+    }
+}
+
+/// The position of `place`'s last dereference, if it reads through a reference.
+fn last_deref(place: &Place) -> Option<usize> {
+    place.proj.iter().rposition(|p| matches!(p, Proj::Deref))
+}
+
+/// Whether a value of type `ty` can contain a reference. A function's own type
+/// parameter can be instantiated with one (`params`); a type definition's
+/// stands for the arguments, already looked at. `seen` guards recursive types.
+fn may_hold_ref(
+    ty: &Ty,
+    params: bool,
+    types: &HashMap<Sym, &TypeDef>,
+    seen: &mut HashSet<Sym>,
+) -> bool {
+    match ty {
+        Ty::Ref { .. } | Ty::Fn(..) => true,
+        Ty::Param(_) => params,
+        Ty::Tuple(items) => items.iter().any(|t| may_hold_ref(t, params, types, seen)),
+        Ty::Array(item, _) | Ty::Vec(item) => may_hold_ref(item, params, types, seen),
+        Ty::Adt(name, args) => {
+            if args.iter().any(|a| may_hold_ref(a, params, types, seen)) {
+                return true;
+            }
+            if !seen.insert(*name) {
+                return false;
+            }
+            let fields: Vec<&Ty> = match types.get(name) {
+                Some(TypeDef::Struct { fields, .. }) => fields.iter().map(|f| &f.ty).collect(),
+                Some(TypeDef::Enum { variants, .. }) => {
+                    variants.iter().flat_map(|v: &VariantDef| &v.fields).collect()
+                }
+                None => return true,
+            };
+            fields.into_iter().any(|t| may_hold_ref(t, false, types, seen))
+        }
+        Ty::Int | Ty::IntN(_) | Ty::Float | Ty::Str | Ty::Bool | Ty::Unit | Ty::Never => false,
+    }
+}
@@ -39,7 +39,11 @@
 //!      * creating *any* borrow of a local that already has an active `&mut` borrow,
 //!      * moving or assigning a local while it is borrowed.
 //!
-//! 3. **CFG awareness.** Each function is analysed by a forward walk over its
+//! 3. **Escaping references.** A reference to a local may not outlive the
+//!    function: returned, directly or through a callee that hands it back, or
+//!    stored through a parameter. This one is interprocedural (the `escape` module).
+//!
+//! 4. **CFG awareness.** Each function is analysed by a forward walk over its
 //!    blocks starting from `entry`, recursing into successors. A visited-set
 //!    terminates the walk on loops/back-edges (mirroring `rv-infer`).
 //!
@@ -84,7 +88,8 @@
 //! * At a control-flow join reached by two paths the block is analysed once, with
 //!   the first arriving path's borrow environment (mirrors the move-state
 //!   approximation above) — the second path's borrows are not re-checked there.
-//! * No interprocedural ownership (call effects on arguments beyond "moved").
+//! * No interprocedural ownership (call effects on arguments beyond "moved"),
+//!   except where references to locals escape.
 //! * `Drop`'s strategy field is ignored; we treat `Drop { place, .. }` as not
 //!   moving (it consumes a value already accounted for by ownership).
 
@@ -92,12 +97,14 @@
 
 use std::collections::{HashMap, HashSet};
 
+mod escape;
+
 use rv_borrow::{affine_ok, FracPerm, Mult, Perm, UsageSemiring};
-use rv_core::{Symbols, Ty};
+use rv_core::{BinOp, Symbols, Ty};
 use rv_logic::{Grades, ResourceAlgebra};
 use rv_ir::{
-    BlockId, BorrowKind, Function, Lowerable, LocalId, Operand, Place, Program, Proj, RValue, Stmt,
-    Terminator,
+    BlockId, BorrowKind, Function, Lowerable, LocalId, Operand, Place, Program, Proj, RValue, Site,
+    Stmt, Terminator, ASSERT_EQ, ASSERT_NE, FLOAT_BITS, TO_STRING,
 };
 
 /// A single borrow/ownership violation. `func` is the (resolved) function name;
@@ -107,18 +114,25 @@ use rv_ir::{
 pub struct BorrowError {
     pub func: String,
     pub message: String,
+    /// The statement or terminator the violation is at, which a
+    /// [`rv_ir::SourceLines`] table maps to a source line. `None` for an escaping
+    /// reference, which is a fact about the whole function.
+    pub site: Option<Site>,
 }
 
 /// Check every function in `prog`; return all borrow/ownership violations found.
 /// An empty vector means the program passed the (first-pass) borrow checker.
 pub fn check(prog: &Program<Lowerable>, syms: &Symbols) -> Vec<BorrowError> {
+    let _span = tracing::debug_span!("borrowck").entered();
     let mut errors = Vec::new();
     for func in &prog.funcs {
         let fname = syms.resolve(func.name).to_string();
         let mut fc = FuncChecker::new(func, fname, syms);
         fc.run();
+        tracing::debug!(func = %fc.fname, errors = fc.errors.len(), "checked");
         errors.append(&mut fc.errors);
     }
+    errors.extend(escape::check(prog, syms));
     errors
 }
 
@@ -142,7 +156,7 @@ fn is_copy(ty: &Ty) -> bool {
         // `&T` shared refs are freely copyable; `&mut T` is not.
         Ty::Ref { mutable, .. } => !mutable,
         // Non-Copy: ADTs move by value.
-        Ty::Adt(_) => false,
+        Ty::Adt(..) => false,
         // Conservatively non-Copy (see doc comment). A generic `Ty::Param` is
         // opaque — assume non-Copy so moves are tracked (can only add a sound
         // error, never hide one).
@@ -265,21 +279,6 @@ fn algebra_kind(kind: BorrowKind) -> rv_borrow::BorrowKind {
 // Liveness (backward dataflow) — drives NLL-style borrow ends.
 // ===========================================================================
 
-/// The successor blocks of a terminator (the CFG edges out of a block).
-fn successors(term: &Terminator<Lowerable>) -> Vec<BlockId> {
-    match term {
-        Terminator::Goto(b) => vec![*b],
-        Terminator::Branch { then_blk, else_blk, .. } => vec![*then_blk, *else_blk],
-        Terminator::Match { arms, otherwise, .. } => {
-            let mut s: Vec<BlockId> = arms.iter().map(|a| a.target).collect();
-            s.extend(otherwise.iter().copied());
-            s
-        }
-        Terminator::Drop { next, .. } => vec![*next],
-        Terminator::Return(_) | Terminator::Panic => vec![],
-    }
-}
-
 /// Add the locals *read* by a place: its root, plus any operands inside `Index`
 /// projections (`a[i]` reads `i`).
 fn place_uses(place: &Place, out: &mut Vec<LocalId>) {
@@ -299,7 +298,9 @@ fn operand_uses(op: &Operand, out: &mut Vec<LocalId>) {
 
 fn rvalue_uses(rv: &RValue, out: &mut Vec<LocalId>) {
     match rv {
-        RValue::Use(a) | RValue::Un(_, a) | RValue::VecLen(a) => operand_uses(a, out),
+        RValue::Use(a) | RValue::Un(_, a) | RValue::VecLen(a) | RValue::Cast(a, _) => {
+            operand_uses(a, out)
+        }
         RValue::Bin(_, a, b) | RValue::WrappingBin(_, a, b) | RValue::VecPush(a, b) => {
             operand_uses(a, out);
             operand_uses(b, out);
@@ -323,11 +324,15 @@ fn rvalue_uses(rv: &RValue, out: &mut Vec<LocalId>) {
 /// Locals read by a statement. A *projected* assign destination (`x.f = …`,
 /// `*p = …`) reads its path; a bare destination is a def, not a use.
 fn stmt_uses(s: &Stmt, out: &mut Vec<LocalId>) {
-    if let Stmt::Assign(dest, rv) = s {
-        rvalue_uses(rv, out);
-        if !dest.proj.is_empty() {
-            place_uses(dest, out);
+    match s {
+        Stmt::Assign(dest, rv) => {
+            rvalue_uses(rv, out);
+            if !dest.proj.is_empty() {
+                place_uses(dest, out);
+            }
         }
+        Stmt::Eval(rv) => rvalue_uses(rv, out),
+        Stmt::Assert(_) | Stmt::Assume(_) | Stmt::Invariant(_) => {}
     }
     // Ghost statements (Assert/Assume/Invariant) carry only Props — no value uses.
 }
@@ -392,13 +397,16 @@ impl Liveness {
         let mut live_in: HashMap<BlockId, HashSet<LocalId>> =
             f.blocks.iter().map(|b| (b.id, HashSet::new())).collect();
 
-        // Backward fixpoint: live_in[b] = gen[b] ∪ (live_out[b] − kill[b]).
+        // Backward fixpoint: live_in[b] = gen[b] ∪ (live_out[b] − kill[b]). Blocks
+        // come in source order, so sweeping them last to first sees most successors
+        // before their predecessors and settles in a few sweeps, however deep the
+        // nesting.
         let mut changed = true;
         while changed {
             changed = false;
-            for b in &f.blocks {
+            for b in f.blocks.iter().rev() {
                 let mut live_out = HashSet::new();
-                for s in successors(&b.term) {
+                for s in b.term.successors() {
                     if let Some(li) = live_in.get(&s) {
                         live_out.extend(li.iter().copied());
                     }
@@ -419,7 +427,7 @@ impl Liveness {
     fn live_out(&self, f: &Function<Lowerable>, block: &rv_ir::Block<Lowerable>) -> HashSet<LocalId> {
         let _ = f;
         let mut out = HashSet::new();
-        for s in successors(&block.term) {
+        for s in block.term.successors() {
             if let Some(li) = self.live_in.get(&s) {
                 out.extend(li.iter().copied());
             }
@@ -463,6 +471,8 @@ struct FuncChecker<'a> {
     fname: String,
     syms: &'a Symbols,
     errors: Vec<BorrowError>,
+    /// The statement or terminator being checked, for the errors it raises.
+    at: Option<Site>,
     /// Blocks already visited on the *current* walk; terminates loops/back-edges.
     visited: HashSet<BlockId>,
     /// Liveness of locals, precomputed; borrows end when their reference dies.
@@ -472,7 +482,7 @@ struct FuncChecker<'a> {
 impl<'a> FuncChecker<'a> {
     fn new(f: &'a Function<Lowerable>, fname: String, syms: &'a Symbols) -> Self {
         let live = Liveness::compute(f);
-        Self { f, fname, syms, errors: Vec::new(), visited: HashSet::new(), live }
+        Self { f, fname, syms, errors: Vec::new(), at: None, visited: HashSet::new(), live }
     }
 
     fn run(&mut self) {
@@ -481,7 +491,7 @@ impl<'a> FuncChecker<'a> {
     }
 
     fn emit(&mut self, message: String) {
-        self.errors.push(BorrowError { func: self.fname.clone(), message });
+        self.errors.push(BorrowError { func: self.fname.clone(), message, site: self.at });
     }
 
     /// Resolve a local's display name (`name` if present, else `_<id>`).
@@ -513,7 +523,12 @@ impl<'a> FuncChecker<'a> {
     /// Forward-walk a block: process its statements then its terminator,
     /// recursing into successors. A back-edge into an already-visited block
     /// stops the path (loop termination).
-    fn walk(&mut self, id: BlockId, mut env: Env) {
+    fn walk(&mut self, id: BlockId, env: Env) {
+        // A path through nested branches is as long as their nesting is deep.
+        rv_arena::stack::grow(|| self.walk_here(id, env))
+    }
+
+    fn walk_here(&mut self, id: BlockId, mut env: Env) {
         if !self.visited.insert(id) {
             // Already seen on this walk: a back-edge. Stop (see precision note).
             return;
@@ -522,7 +537,7 @@ impl<'a> FuncChecker<'a> {
         // `&mut self` check calls below.
         let Some(block) = self.block(id) else { return };
         let stmts = block.stmts.clone();
-        let term = clone_term(&block.term);
+        let term = block.term.clone();
 
         // Liveness-driven borrow ends: a borrow lives exactly as long as the
         // reference local that holds it. `live_out` is what survives to
@@ -531,6 +546,7 @@ impl<'a> FuncChecker<'a> {
         let live_after = live_after_each(&stmts, &term, &live_out);
 
         for (i, stmt) in stmts.iter().enumerate() {
+            self.at = Some(Site { func: self.f.name, block: id, stmt: Some(i as u32) });
             self.check_stmt(stmt, &mut env);
             // End any borrow whose reference local is no longer live (NLL end),
             // including one just created here that is dead-on-arrival.
@@ -538,6 +554,7 @@ impl<'a> FuncChecker<'a> {
         }
         // Borrows still live at the terminator can conflict with its reads; then
         // only those live *out* of the block carry into successors.
+        self.at = Some(Site { func: self.f.name, block: id, stmt: None });
         self.check_terminator(&term, &mut env);
         env.prune_dead(&live_out);
 
@@ -567,6 +584,9 @@ impl<'a> FuncChecker<'a> {
     fn check_stmt(&mut self, stmt: &Stmt, env: &mut Env) {
         match stmt {
             Stmt::Assign(place, rvalue) => self.check_assign(place, rvalue, env),
+            // A discarded call reads (and moves) its operands like an assigned one,
+            // but writes nothing.
+            Stmt::Eval(rvalue) => self.check_rvalue(rvalue, None, env),
             // Ghost statements carry only `Prop`s (no value operands); nothing
             // to move or borrow.
             Stmt::Assert(_) | Stmt::Assume(_) | Stmt::Invariant(_) => {}
@@ -581,16 +601,52 @@ impl<'a> FuncChecker<'a> {
         let dest_local = dest.local;
         let dest_is_bare = dest.proj.is_empty();
 
+        self.check_rvalue(rvalue, Some(dest_local), env);
+
+        // Now perform the write to the destination.
+        if dest_is_bare {
+            // Cannot assign to a local while it is borrowed.
+            if env.has_any_borrow(dest_local) {
+                let n = self.local_name(dest_local);
+                self.emit(format!("cannot assign `{n}` while borrowed"));
+            }
+            // Reassignment revives a previously-moved local (grade back to Zero).
+            env.revive(dest_local);
+        } else {
+            // A projected write (e.g. `x.f = ...`, `*p = ...`) reads `x`/`p`'s
+            // path; treat it as a use of the root for move purposes, but it does
+            // not revive a moved local (it is a partial write into a live place).
+            self.use_local_for_read(dest_local, env);
+        }
+    }
+
+    /// Check the reads an rvalue performs. `reference` is the local the value is
+    /// stored to, which holds the borrow a `Ref` creates; `None` for a discarded
+    /// value.
+    fn check_rvalue(&mut self, rvalue: &RValue, reference: Option<LocalId>, env: &mut Env) {
         match rvalue {
             RValue::Use(op) => {
                 // Assigning one local to another consumes the source by value.
                 self.consume_operand(op, env);
             }
+            // A comparison inspects its operands in place (as Rust's `==` takes
+            // `&self`), so comparing a string does not move it.
+            RValue::Bin(
+                BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge,
+                a,
+                b,
+            ) => {
+                for op in [a, b] {
+                    if let Operand::Copy(p) = op {
+                        self.use_local_for_read(p.local, env);
+                    }
+                }
+            }
             RValue::Bin(_, a, b) | RValue::WrappingBin(_, a, b) => {
                 self.consume_operand(a, env);
                 self.consume_operand(b, env);
             }
-            RValue::Un(_, a) => {
+            RValue::Un(_, a) | RValue::Cast(a, _) => {
                 self.consume_operand(a, env);
             }
             RValue::VecLen(_a) => {
@@ -602,6 +658,19 @@ impl<'a> FuncChecker<'a> {
                 // value `b` is consumed (moved into the vector).
                 self.consume_operand(b, env);
             }
+            // A `format` argument is rendered, not moved, `assert_eq`/`assert_ne`
+            // only compare their operands, and a float match reads its scrutinee's
+            // bits: all only read.
+            RValue::Call(callee, args)
+                if [TO_STRING, ASSERT_EQ, ASSERT_NE, FLOAT_BITS]
+                    .contains(&self.syms.resolve(*callee)) =>
+            {
+                for a in args {
+                    if let Operand::Copy(p) = a {
+                        self.use_local_for_read(p.local, env);
+                    }
+                }
+            }
             RValue::Call(_, args) => {
                 for a in args {
                     self.consume_operand(a, env);
@@ -629,24 +698,12 @@ impl<'a> FuncChecker<'a> {
                 // The reference local (`dest`) holds the borrow; its liveness
                 // determines when the borrow ends. A projected ref destination
                 // is not a plain reference local, so fall back to the root.
-                self.check_borrow(*kind, borrowed, dest_local, env);
-            }
-        }
-
-        // Now perform the write to the destination.
-        if dest_is_bare {
-            // Cannot assign to a local while it is borrowed.
-            if env.has_any_borrow(dest_local) {
-                let n = self.local_name(dest_local);
-                self.emit(format!("cannot assign `{n}` while borrowed"));
+                match reference {
+                    Some(reference) => self.check_borrow(*kind, borrowed, reference, env),
+                    // A borrow nothing holds ends at once: only its read remains.
+                    None => self.use_local_for_read(borrowed.local, env),
+                }
             }
-            // Reassignment revives a previously-moved local (grade back to Zero).
-            env.revive(dest_local);
-        } else {
-            // A projected write (e.g. `x.f = ...`, `*p = ...`) reads `x`/`p`'s
-            // path; treat it as a use of the root for move purposes, but it does
-            // not revive a moved local (it is a partial write into a live place).
-            self.use_local_for_read(dest_local, env);
         }
     }
 
@@ -746,7 +803,7 @@ impl<'a> FuncChecker<'a> {
             let n = self.local_name(local);
             // Borrow `self` immutably above, then mutate via emit: collect first.
             let msg = format!("use of moved value `{n}`");
-            self.errors.push(BorrowError { func: self.fname.clone(), message: msg });
+            self.errors.push(BorrowError { func: self.fname.clone(), message: msg, site: self.at });
         }
     }
 
@@ -778,32 +835,6 @@ impl<'a> FuncChecker<'a> {
     }
 }
 
-/// `Terminator<Lowerable>` is not `Clone` (it carries `P::Strategy`), so we
-/// reconstruct a shallow copy of the fields we traverse. `Drop`'s strategy is a
-/// `DisciplineId` (`Copy`), so this is cheap and total.
-fn clone_term(t: &Terminator<Lowerable>) -> Terminator<Lowerable> {
-    match t {
-        Terminator::Goto(b) => Terminator::Goto(*b),
-        Terminator::Branch { cond, then_blk, else_blk } => Terminator::Branch {
-            cond: cond.clone(),
-            then_blk: *then_blk,
-            else_blk: *else_blk,
-        },
-        Terminator::Match { scrutinee, arms, otherwise } => Terminator::Match {
-            scrutinee: scrutinee.clone(),
-            arms: arms.clone(),
-            otherwise: *otherwise,
-        },
-        Terminator::Return(op) => Terminator::Return(op.clone()),
-        Terminator::Drop { place, strategy, next } => Terminator::Drop {
-            place: place.clone(),
-            strategy: *strategy,
-            next: *next,
-        },
-        Terminator::Panic => Terminator::Panic,
-    }
-}
-
 // ===========================================================================
 // Tests
 // ===========================================================================
@@ -811,59 +842,14 @@ fn clone_term(t: &Terminator<Lowerable>) -> Terminator<Lowerable> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rv_ir::{AggKind, Block, Const, DisciplineId, LocalDecl, Proj};
+    use rv_ir::builder::{copy, int, unit, FunctionBuilder};
+    use rv_ir::{AggKind, Const, DisciplineId, Proj};
 
     // -- Builders -----------------------------------------------------------
 
-    /// A minimal single-block function builder.
-    struct Build {
-        syms: Symbols,
-        name: rv_core::Sym,
-        locals: Vec<LocalDecl<Lowerable>>,
-    }
-
-    impl Build {
-        fn new(fname: &str) -> Self {
-            let mut syms = Symbols::new();
-            let name = syms.intern(fname);
-            Build { syms, name, locals: Vec::new() }
-        }
-
-        /// Declare a local with `name` and `ty`, returning its id.
-        fn local(&mut self, name: &str, ty: Ty) -> LocalId {
-            let s = self.syms.intern(name);
-            let id = LocalId(self.locals.len() as u32);
-            self.locals.push(LocalDecl { name: Some(s), ty });
-            id
-        }
-
-        /// Finish into a single-block function with terminator `term`.
-        fn finish(
-            self,
-            params: Vec<LocalId>,
-            stmts: Vec<Stmt>,
-            term: Terminator<Lowerable>,
-        ) -> (Program<Lowerable>, Symbols) {
-            let entry = BlockId(0);
-            let func = Function {
-                name: self.name,
-                type_params: Vec::new(),
-                generic_bounds: vec![],
-                params,
-                ret: Ty::Unit,
-                pre: rv_core::Prop::True,
-                post: rv_core::Prop::True,
-                locals: self.locals,
-                blocks: vec![Block { id: entry, stmts, term }],
-                entry,
-            };
-            let prog = Program { types: Vec::new(), trait_impls: vec![], funcs: vec![func] };
-            (prog, self.syms)
-        }
-    }
-
-    fn copy(local: LocalId) -> Operand {
-        Operand::Copy(Place::local(local))
+    /// A builder for `fn f()`, the function every test checks.
+    fn build(syms: &mut Symbols) -> FunctionBuilder<'_, Lowerable> {
+        FunctionBuilder::new(syms, "f", Ty::Unit)
     }
 
     /// Read through a reference local (`*r`) — a *use* of `r` that keeps its
@@ -872,21 +858,46 @@ mod tests {
         Operand::Copy(Place { local: r, proj: vec![Proj::Deref] })
     }
 
+    fn borrow(kind: BorrowKind, local: LocalId) -> RValue {
+        RValue::Ref(kind, Place::local(local))
+    }
+
+    fn ref_ty(mutable: bool, inner: Ty) -> Ty {
+        Ty::Ref { mutable, inner: Box::new(inner) }
+    }
+
     // -- (a) clean program → no errors --------------------------------------
 
     #[test]
     fn clean_program_has_no_errors() {
         // fn f() { let a: Int = 1; let b: Int = a + a; return; }
         // Int is Copy, so using `a` twice is fine.
-        let mut b = Build::new("f");
+        let mut syms = Symbols::new();
+        let mut b = build(&mut syms);
         let a = b.local("a", Ty::Int);
         let bb = b.local("b", Ty::Int);
-        let stmts = vec![
-            Stmt::Assign(Place::local(a), RValue::Use(Operand::Const(Const::Int(1)))),
-            Stmt::Assign(Place::local(bb), RValue::Bin(rv_core::BinOp::Add, copy(a), copy(a))),
-        ];
-        let (prog, syms) = b.finish(vec![], stmts, Terminator::Return(Operand::Const(Const::Unit)));
-        let errs = check(&prog, &syms);
+        b.assign(a, RValue::Use(int(1)))
+            .assign(bb, RValue::Bin(rv_core::BinOp::Add, copy(a), copy(a)))
+            .ret(unit());
+        let errs = check(&b.program(), &syms);
+        assert!(errs.is_empty(), "expected no errors, got {errs:?}");
+    }
+
+    #[test]
+    fn comparing_a_string_does_not_move_it() {
+        // fn f(s: Str) { let t = s == "a"; let u = s == "b"; let v = s; return; }
+        let mut syms = Symbols::new();
+        let mut b = build(&mut syms);
+        let s = b.param("s", Ty::Str);
+        let t = b.local("t", Ty::Bool);
+        let u = b.local("u", Ty::Bool);
+        let v = b.local("v", Ty::Str);
+        let lit = |x: &str| Operand::Const(Const::Str(x.to_string()));
+        b.assign(t, RValue::Bin(rv_core::BinOp::Eq, copy(s), lit("a")))
+            .assign(u, RValue::Bin(rv_core::BinOp::Eq, copy(s), lit("b")))
+            .assign(v, RValue::Use(copy(s)))
+            .ret(unit());
+        let errs = check(&b.program(), &syms);
         assert!(errs.is_empty(), "expected no errors, got {errs:?}");
     }
 
@@ -895,19 +906,17 @@ mod tests {
     #[test]
     fn use_after_move_of_adt() {
         // fn f() { let a: Adt(S); let b = a; (move) let c = a; (use after move) }
-        let mut b = Build::new("f");
-        let s = b.syms.intern("S");
-        let a = b.local("a", Ty::Adt(s));
-        let bb = b.local("b", Ty::Adt(s));
-        let cc = b.local("c", Ty::Adt(s));
-        let stmts = vec![
-            // b = a  -> moves a
-            Stmt::Assign(Place::local(bb), RValue::Use(copy(a))),
-            // c = a  -> use of moved value `a`
-            Stmt::Assign(Place::local(cc), RValue::Use(copy(a))),
-        ];
-        let (prog, syms) = b.finish(vec![], stmts, Terminator::Return(Operand::Const(Const::Unit)));
-        let errs = check(&prog, &syms);
+        let mut syms = Symbols::new();
+        let mut b = build(&mut syms);
+        let s = b.sym("S");
+        let a = b.local("a", Ty::Adt(s, vec![]));
+        let bb = b.local("b", Ty::Adt(s, vec![]));
+        let cc = b.local("c", Ty::Adt(s, vec![]));
+        // b = a  -> moves a
+        b.assign(bb, RValue::Use(copy(a)));
+        // c = a  -> use of moved value `a`
+        b.assign(cc, RValue::Use(copy(a))).ret(unit());
+        let errs = check(&b.program(), &syms);
         assert_eq!(errs.len(), 1, "expected exactly one error, got {errs:?}");
         assert!(errs[0].message.contains("use of moved value `a`"), "{:?}", errs[0]);
     }
@@ -918,19 +927,16 @@ mod tests {
     fn double_mut_borrow() {
         // let r1 = &mut a; let r2 = &mut a; use r1  — r1 is live across r2's
         // borrow (it is read afterward), so the two `&mut` genuinely overlap.
-        let mut b = Build::new("f");
+        let mut syms = Symbols::new();
+        let mut b = build(&mut syms);
         let a = b.local("a", Ty::Int);
-        let r1 = b.local("r1", Ty::Ref { mutable: true, inner: Box::new(Ty::Int) });
-        let r2 = b.local("r2", Ty::Ref { mutable: true, inner: Box::new(Ty::Int) });
+        let r1 = b.local("r1", ref_ty(true, Ty::Int));
+        let r2 = b.local("r2", ref_ty(true, Ty::Int));
         let t = b.local("t", Ty::Int);
-        let stmts = vec![
-            Stmt::Assign(Place::local(r1), RValue::Ref(BorrowKind::Mut, Place::local(a))),
-            Stmt::Assign(Place::local(r2), RValue::Ref(BorrowKind::Mut, Place::local(a))),
-            // Keep r1 live past r2's creation → real conflict.
-            Stmt::Assign(Place::local(t), RValue::Use(deref_use(r1))),
-        ];
-        let (prog, syms) = b.finish(vec![], stmts, Terminator::Return(Operand::Const(Const::Unit)));
-        let errs = check(&prog, &syms);
+        b.assign(r1, borrow(BorrowKind::Mut, a)).assign(r2, borrow(BorrowKind::Mut, a));
+        // Keep r1 live past r2's creation → real conflict.
+        b.assign(t, RValue::Use(deref_use(r1))).ret(unit());
+        let errs = check(&b.program(), &syms);
         assert_eq!(errs.len(), 1, "expected exactly one error, got {errs:?}");
         assert!(errs[0].message.contains("as mutable"), "{:?}", errs[0]);
     }
@@ -942,19 +948,16 @@ mod tests {
         // let r1 = &mut a; let r2 = &mut a;  with r1 never used afterward. Under
         // NLL r1's borrow has already ended, so this is *not* a conflict — the
         // precision the block-scoped approximation lacked.
-        let mut b = Build::new("f");
+        let mut syms = Symbols::new();
+        let mut b = build(&mut syms);
         let a = b.local("a", Ty::Int);
-        let r1 = b.local("r1", Ty::Ref { mutable: true, inner: Box::new(Ty::Int) });
-        let r2 = b.local("r2", Ty::Ref { mutable: true, inner: Box::new(Ty::Int) });
+        let r1 = b.local("r1", ref_ty(true, Ty::Int));
+        let r2 = b.local("r2", ref_ty(true, Ty::Int));
         let t = b.local("t", Ty::Int);
-        let stmts = vec![
-            Stmt::Assign(Place::local(r1), RValue::Ref(BorrowKind::Mut, Place::local(a))),
-            Stmt::Assign(Place::local(r2), RValue::Ref(BorrowKind::Mut, Place::local(a))),
-            // Only r2 is used → r1 was already dead when r2 was created.
-            Stmt::Assign(Place::local(t), RValue::Use(deref_use(r2))),
-        ];
-        let (prog, syms) = b.finish(vec![], stmts, Terminator::Return(Operand::Const(Const::Unit)));
-        let errs = check(&prog, &syms);
+        b.assign(r1, borrow(BorrowKind::Mut, a)).assign(r2, borrow(BorrowKind::Mut, a));
+        // Only r2 is used → r1 was already dead when r2 was created.
+        b.assign(t, RValue::Use(deref_use(r2))).ret(unit());
+        let errs = check(&b.program(), &syms);
         assert!(errs.is_empty(), "unused first borrow should not conflict, got {errs:?}");
     }
 
@@ -964,18 +967,17 @@ mod tests {
     fn shared_borrow_then_read_is_ok() {
         // fn f() { let a: Int; let r1 = &a; let r2 = &a; let x = a; }
         // Multiple shared borrows + a Copy read of an Int: all fine.
-        let mut b = Build::new("f");
+        let mut syms = Symbols::new();
+        let mut b = build(&mut syms);
         let a = b.local("a", Ty::Int);
-        let r1 = b.local("r1", Ty::Ref { mutable: false, inner: Box::new(Ty::Int) });
-        let r2 = b.local("r2", Ty::Ref { mutable: false, inner: Box::new(Ty::Int) });
+        let r1 = b.local("r1", ref_ty(false, Ty::Int));
+        let r2 = b.local("r2", ref_ty(false, Ty::Int));
         let x = b.local("x", Ty::Int);
-        let stmts = vec![
-            Stmt::Assign(Place::local(r1), RValue::Ref(BorrowKind::Shared, Place::local(a))),
-            Stmt::Assign(Place::local(r2), RValue::Ref(BorrowKind::Shared, Place::local(a))),
-            Stmt::Assign(Place::local(x), RValue::Use(copy(a))),
-        ];
-        let (prog, syms) = b.finish(vec![], stmts, Terminator::Return(Operand::Const(Const::Unit)));
-        let errs = check(&prog, &syms);
+        b.assign(r1, borrow(BorrowKind::Shared, a))
+            .assign(r2, borrow(BorrowKind::Shared, a))
+            .assign(x, RValue::Use(copy(a)))
+            .ret(unit());
+        let errs = check(&b.program(), &syms);
         assert!(errs.is_empty(), "expected no errors, got {errs:?}");
     }
 
@@ -984,19 +986,17 @@ mod tests {
     #[test]
     fn reassignment_clears_moved() {
         // let a: Adt; b = a (move); a = S{} (revive); c = a (ok)
-        let mut b = Build::new("f");
-        let s = b.syms.intern("S");
-        let a = b.local("a", Ty::Adt(s));
-        let bb = b.local("b", Ty::Adt(s));
-        let cc = b.local("c", Ty::Adt(s));
-        let stmts = vec![
-            Stmt::Assign(Place::local(bb), RValue::Use(copy(a))),
-            // a = S{}  -> revives a
-            Stmt::Assign(Place::local(a), RValue::Aggregate(AggKind::Struct(s), vec![])),
-            Stmt::Assign(Place::local(cc), RValue::Use(copy(a))),
-        ];
-        let (prog, syms) = b.finish(vec![], stmts, Terminator::Return(Operand::Const(Const::Unit)));
-        let errs = check(&prog, &syms);
+        let mut syms = Symbols::new();
+        let mut b = build(&mut syms);
+        let s = b.sym("S");
+        let a = b.local("a", Ty::Adt(s, vec![]));
+        let bb = b.local("b", Ty::Adt(s, vec![]));
+        let cc = b.local("c", Ty::Adt(s, vec![]));
+        b.assign(bb, RValue::Use(copy(a)));
+        // a = S{}  -> revives a
+        b.assign(a, RValue::Aggregate(AggKind::Struct(s), vec![]));
+        b.assign(cc, RValue::Use(copy(a))).ret(unit());
+        let errs = check(&b.program(), &syms);
         assert!(errs.is_empty(), "expected no errors after revive, got {errs:?}");
     }
 
@@ -1004,19 +1004,16 @@ mod tests {
 
     #[test]
     fn mut_while_shared_borrowed() {
-        let mut b = Build::new("f");
+        let mut syms = Symbols::new();
+        let mut b = build(&mut syms);
         let a = b.local("a", Ty::Int);
-        let r1 = b.local("r1", Ty::Ref { mutable: false, inner: Box::new(Ty::Int) });
-        let r2 = b.local("r2", Ty::Ref { mutable: true, inner: Box::new(Ty::Int) });
+        let r1 = b.local("r1", ref_ty(false, Ty::Int));
+        let r2 = b.local("r2", ref_ty(true, Ty::Int));
         let t = b.local("t", Ty::Int);
-        let stmts = vec![
-            Stmt::Assign(Place::local(r1), RValue::Ref(BorrowKind::Shared, Place::local(a))),
-            Stmt::Assign(Place::local(r2), RValue::Ref(BorrowKind::Mut, Place::local(a))),
-            // r1 live across the &mut → conflict.
-            Stmt::Assign(Place::local(t), RValue::Use(deref_use(r1))),
-        ];
-        let (prog, syms) = b.finish(vec![], stmts, Terminator::Return(Operand::Const(Const::Unit)));
-        let errs = check(&prog, &syms);
+        b.assign(r1, borrow(BorrowKind::Shared, a)).assign(r2, borrow(BorrowKind::Mut, a));
+        // r1 live across the &mut → conflict.
+        b.assign(t, RValue::Use(deref_use(r1))).ret(unit());
+        let errs = check(&b.program(), &syms);
         assert_eq!(errs.len(), 1, "{errs:?}");
         assert!(errs[0].message.contains("as mutable"), "{:?}", errs[0]);
     }
@@ -1025,20 +1022,18 @@ mod tests {
 
     #[test]
     fn move_while_borrowed() {
-        let mut b = Build::new("f");
-        let s = b.syms.intern("S");
-        let a = b.local("a", Ty::Adt(s));
-        let r = b.local("r", Ty::Ref { mutable: false, inner: Box::new(Ty::Adt(s)) });
-        let bb = b.local("b", Ty::Adt(s));
-        let t = b.local("t", Ty::Adt(s));
-        let stmts = vec![
-            Stmt::Assign(Place::local(r), RValue::Ref(BorrowKind::Shared, Place::local(a))),
-            // b = a while a is borrowed -> error (r is still live: used below)
-            Stmt::Assign(Place::local(bb), RValue::Use(copy(a))),
-            Stmt::Assign(Place::local(t), RValue::Use(deref_use(r))),
-        ];
-        let (prog, syms) = b.finish(vec![], stmts, Terminator::Return(Operand::Const(Const::Unit)));
-        let errs = check(&prog, &syms);
+        let mut syms = Symbols::new();
+        let mut b = build(&mut syms);
+        let s = b.sym("S");
+        let a = b.local("a", Ty::Adt(s, vec![]));
+        let r = b.local("r", ref_ty(false, Ty::Adt(s, vec![])));
+        let bb = b.local("b", Ty::Adt(s, vec![]));
+        let t = b.local("t", Ty::Adt(s, vec![]));
+        b.assign(r, borrow(BorrowKind::Shared, a));
+        // b = a while a is borrowed -> error (r is still live: used below)
+        b.assign(bb, RValue::Use(copy(a)));
+        b.assign(t, RValue::Use(deref_use(r))).ret(unit());
+        let errs = check(&b.program(), &syms);
         assert_eq!(errs.len(), 1, "{errs:?}");
         assert!(errs[0].message.contains("cannot move `a` while borrowed"), "{:?}", errs[0]);
     }
@@ -1050,50 +1045,20 @@ mod tests {
         // b0: cond branch on c -> b1 / b2
         // b1: x = a (move a)        b2: y = a (move a)   — independent paths
         // Neither path re-uses a after its own move, so no error.
-        let mut b = Build::new("f");
-        let s = b.syms.intern("S");
-        let a = b.local("a", Ty::Adt(s));
+        let mut syms = Symbols::new();
+        let mut b = build(&mut syms);
+        let s = b.sym("S");
+        let a = b.local("a", Ty::Adt(s, vec![]));
         let c = b.local("c", Ty::Bool);
-        let x = b.local("x", Ty::Adt(s));
-        let y = b.local("y", Ty::Adt(s));
-
-        let entry = BlockId(0);
-        let b1 = BlockId(1);
-        let b2 = BlockId(2);
-        let exit = BlockId(3);
-
-        let blocks = vec![
-            Block {
-                id: entry,
-                stmts: vec![],
-                term: Terminator::Branch { cond: copy(c), then_blk: b1, else_blk: b2 },
-            },
-            Block {
-                id: b1,
-                stmts: vec![Stmt::Assign(Place::local(x), RValue::Use(copy(a)))],
-                term: Terminator::Goto(exit),
-            },
-            Block {
-                id: b2,
-                stmts: vec![Stmt::Assign(Place::local(y), RValue::Use(copy(a)))],
-                term: Terminator::Goto(exit),
-            },
-            Block { id: exit, stmts: vec![], term: Terminator::Return(Operand::Const(Const::Unit)) },
-        ];
-        let func = Function {
-            name: b.name,
-            type_params: Vec::new(),
-            generic_bounds: vec![],
-            params: vec![],
-            ret: Ty::Unit,
-            pre: rv_core::Prop::True,
-            post: rv_core::Prop::True,
-            locals: b.locals,
-            blocks,
-            entry,
-        };
-        let prog = Program { types: Vec::new(), trait_impls: vec![], funcs: vec![func] };
-        let errs = check(&prog, &b.syms);
+        let x = b.local("x", Ty::Adt(s, vec![]));
+        let y = b.local("y", Ty::Adt(s, vec![]));
+
+        let (b1, b2, exit) = (b.block(), b.block(), b.block());
+        b.branch(copy(c), b1, b2);
+        b.switch_to(b1).assign(x, RValue::Use(copy(a))).goto(exit);
+        b.switch_to(b2).assign(y, RValue::Use(copy(a))).goto(exit);
+        b.switch_to(exit).ret(unit());
+        let errs = check(&b.program(), &syms);
         assert!(errs.is_empty(), "expected no errors, got {errs:?}");
     }
 
@@ -1101,40 +1066,18 @@ mod tests {
 
     #[test]
     fn drop_terminator_is_handled() {
-        let mut b = Build::new("f");
-        let s = b.syms.intern("S");
-        let a = b.local("a", Ty::Adt(s));
-        let entry = BlockId(0);
-        let exit = BlockId(1);
-        let blocks = vec![
-            Block {
-                id: entry,
-                stmts: vec![Stmt::Assign(
-                    Place::local(a),
-                    RValue::Aggregate(AggKind::Struct(s), vec![]),
-                )],
-                term: Terminator::Drop {
-                    place: Place::local(a),
-                    strategy: DisciplineId(0),
-                    next: exit,
-                },
-            },
-            Block { id: exit, stmts: vec![], term: Terminator::Return(Operand::Const(Const::Unit)) },
-        ];
-        let func = Function {
-            name: b.name,
-            type_params: Vec::new(),
-            generic_bounds: vec![],
-            params: vec![],
-            ret: Ty::Unit,
-            pre: rv_core::Prop::True,
-            post: rv_core::Prop::True,
-            locals: b.locals,
-            blocks,
-            entry,
-        };
-        let prog = Program { types: Vec::new(), trait_impls: vec![], funcs: vec![func] };
-        let errs = check(&prog, &b.syms);
+        let mut syms = Symbols::new();
+        let mut b = build(&mut syms);
+        let s = b.sym("S");
+        let a = b.local("a", Ty::Adt(s, vec![]));
+        let exit = b.block();
+        b.assign(a, RValue::Aggregate(AggKind::Struct(s), vec![])).terminate(Terminator::Drop {
+            place: Place::local(a),
+            strategy: DisciplineId(0),
+            next: exit,
+        });
+        b.switch_to(exit).ret(unit());
+        let errs = check(&b.program(), &syms);
         assert!(errs.is_empty(), "{errs:?}");
     }
 
@@ -1143,15 +1086,15 @@ mod tests {
     #[test]
     fn many_shared_borrows_stay_valid() {
         // Eight `&a` in one block: fractions ½ + ¼ + … always compose validly.
-        let mut b = Build::new("f");
+        let mut syms = Symbols::new();
+        let mut b = build(&mut syms);
         let a = b.local("a", Ty::Int);
-        let mut stmts = Vec::new();
         for i in 0..8 {
-            let r = b.local(&format!("r{i}"), Ty::Ref { mutable: false, inner: Box::new(Ty::Int) });
-            stmts.push(Stmt::Assign(Place::local(r), RValue::Ref(BorrowKind::Shared, Place::local(a))));
+            let r = b.local(&format!("r{i}"), ref_ty(false, Ty::Int));
+            b.assign(r, borrow(BorrowKind::Shared, a));
         }
-        let (prog, syms) = b.finish(vec![], stmts, Terminator::Return(Operand::Const(Const::Unit)));
-        let errs = check(&prog, &syms);
+        b.ret(unit());
+        let errs = check(&b.program(), &syms);
         assert!(errs.is_empty(), "shared borrows must compose, got {errs:?}");
     }
 
@@ -1160,24 +1103,24 @@ mod tests {
     #[test]
     fn mut_after_many_shared_borrows_fails() {
         // Several `&a` then one `&mut a`: lent < 1 but full no longer fits.
-        let mut b = Build::new("f");
+        let mut syms = Symbols::new();
+        let mut b = build(&mut syms);
         let a = b.local("a", Ty::Int);
-        let mut stmts = Vec::new();
         let mut shared = Vec::new();
         for i in 0..3 {
-            let r = b.local(&format!("r{i}"), Ty::Ref { mutable: false, inner: Box::new(Ty::Int) });
+            let r = b.local(&format!("r{i}"), ref_ty(false, Ty::Int));
             shared.push(r);
-            stmts.push(Stmt::Assign(Place::local(r), RValue::Ref(BorrowKind::Shared, Place::local(a))));
+            b.assign(r, borrow(BorrowKind::Shared, a));
         }
-        let m = b.local("m", Ty::Ref { mutable: true, inner: Box::new(Ty::Int) });
-        stmts.push(Stmt::Assign(Place::local(m), RValue::Ref(BorrowKind::Mut, Place::local(a))));
+        let m = b.local("m", ref_ty(true, Ty::Int));
+        b.assign(m, borrow(BorrowKind::Mut, a));
         // Keep every shared borrow live past the &mut so they genuinely overlap.
         for (i, r) in shared.into_iter().enumerate() {
             let t = b.local(&format!("t{i}"), Ty::Int);
-            stmts.push(Stmt::Assign(Place::local(t), RValue::Use(deref_use(r))));
+            b.assign(t, RValue::Use(deref_use(r)));
         }
-        let (prog, syms) = b.finish(vec![], stmts, Terminator::Return(Operand::Const(Const::Unit)));
-        let errs = check(&prog, &syms);
+        b.ret(unit());
+        let errs = check(&b.program(), &syms);
         assert_eq!(errs.len(), 1, "{errs:?}");
         assert!(errs[0].message.contains("as mutable"), "{:?}", errs[0]);
     }
@@ -1188,38 +1131,15 @@ mod tests {
     fn borrow_released_at_block_end_allows_mut() {
         // b0: r = &a; goto b1.  b1: m = &mut a.  The shared fraction is returned
         // at the end of b0, so the full permission is available in b1.
-        let mut b = Build::new("f");
+        let mut syms = Symbols::new();
+        let mut b = build(&mut syms);
         let a = b.local("a", Ty::Int);
-        let r = b.local("r", Ty::Ref { mutable: false, inner: Box::new(Ty::Int) });
-        let m = b.local("m", Ty::Ref { mutable: true, inner: Box::new(Ty::Int) });
-        let entry = BlockId(0);
-        let b1 = BlockId(1);
-        let blocks = vec![
-            Block {
-                id: entry,
-                stmts: vec![Stmt::Assign(Place::local(r), RValue::Ref(BorrowKind::Shared, Place::local(a)))],
-                term: Terminator::Goto(b1),
-            },
-            Block {
-                id: b1,
-                stmts: vec![Stmt::Assign(Place::local(m), RValue::Ref(BorrowKind::Mut, Place::local(a)))],
-                term: Terminator::Return(Operand::Const(Const::Unit)),
-            },
-        ];
-        let func = Function {
-            name: b.name,
-            type_params: Vec::new(),
-            generic_bounds: vec![],
-            params: vec![],
-            ret: Ty::Unit,
-            pre: rv_core::Prop::True,
-            post: rv_core::Prop::True,
-            locals: b.locals,
-            blocks,
-            entry,
-        };
-        let prog = Program { types: Vec::new(), trait_impls: vec![], funcs: vec![func] };
-        let errs = check(&prog, &b.syms);
+        let r = b.local("r", ref_ty(false, Ty::Int));
+        let m = b.local("m", ref_ty(true, Ty::Int));
+        let b1 = b.block();
+        b.assign(r, borrow(BorrowKind::Shared, a)).goto(b1);
+        b.switch_to(b1).assign(m, borrow(BorrowKind::Mut, a)).ret(unit());
+        let errs = check(&b.program(), &syms);
         assert!(errs.is_empty(), "{errs:?}");
     }
 
@@ -1228,39 +1148,16 @@ mod tests {
     #[test]
     fn move_grade_persists_across_blocks() {
         // b0: b = a (move); goto b1.  b1: c = a  → use of moved value.
-        let mut bd = Build::new("f");
-        let s = bd.syms.intern("S");
-        let a = bd.local("a", Ty::Adt(s));
-        let bb = bd.local("b", Ty::Adt(s));
-        let cc = bd.local("c", Ty::Adt(s));
-        let entry = BlockId(0);
-        let b1 = BlockId(1);
-        let blocks = vec![
-            Block {
-                id: entry,
-                stmts: vec![Stmt::Assign(Place::local(bb), RValue::Use(copy(a)))],
-                term: Terminator::Goto(b1),
-            },
-            Block {
-                id: b1,
-                stmts: vec![Stmt::Assign(Place::local(cc), RValue::Use(copy(a)))],
-                term: Terminator::Return(Operand::Const(Const::Unit)),
-            },
-        ];
-        let func = Function {
-            name: bd.name,
-            type_params: Vec::new(),
-            generic_bounds: vec![],
-            params: vec![],
-            ret: Ty::Unit,
-            pre: rv_core::Prop::True,
-            post: rv_core::Prop::True,
-            locals: bd.locals,
-            blocks,
-            entry,
-        };
-        let prog = Program { types: Vec::new(), trait_impls: vec![], funcs: vec![func] };
-        let errs = check(&prog, &bd.syms);
+        let mut syms = Symbols::new();
+        let mut b = build(&mut syms);
+        let s = b.sym("S");
+        let a = b.local("a", Ty::Adt(s, vec![]));
+        let bb = b.local("b", Ty::Adt(s, vec![]));
+        let cc = b.local("c", Ty::Adt(s, vec![]));
+        let b1 = b.block();
+        b.assign(bb, RValue::Use(copy(a))).goto(b1);
+        b.switch_to(b1).assign(cc, RValue::Use(copy(a))).ret(unit());
+        let errs = check(&b.program(), &syms);
         assert_eq!(errs.len(), 1, "{errs:?}");
         assert!(errs[0].message.contains("use of moved value `a`"), "{:?}", errs[0]);
     }
@@ -1273,42 +1170,19 @@ mod tests {
         // its shared borrow is live *out* of b0 and into b1 — so the &mut in b1
         // conflicts. The old block-scoped checker released r at b0's end and
         // missed this; liveness carries it across the edge.
-        let mut b = Build::new("f");
+        let mut syms = Symbols::new();
+        let mut b = build(&mut syms);
         let a = b.local("a", Ty::Int);
-        let r = b.local("r", Ty::Ref { mutable: false, inner: Box::new(Ty::Int) });
-        let m = b.local("m", Ty::Ref { mutable: true, inner: Box::new(Ty::Int) });
+        let r = b.local("r", ref_ty(false, Ty::Int));
+        let m = b.local("m", ref_ty(true, Ty::Int));
         let t = b.local("t", Ty::Int);
-        let entry = BlockId(0);
-        let b1 = BlockId(1);
-        let blocks = vec![
-            Block {
-                id: entry,
-                stmts: vec![Stmt::Assign(Place::local(r), RValue::Ref(BorrowKind::Shared, Place::local(a)))],
-                term: Terminator::Goto(b1),
-            },
-            Block {
-                id: b1,
-                stmts: vec![
-                    Stmt::Assign(Place::local(m), RValue::Ref(BorrowKind::Mut, Place::local(a))),
-                    Stmt::Assign(Place::local(t), RValue::Use(deref_use(r))),
-                ],
-                term: Terminator::Return(Operand::Const(Const::Unit)),
-            },
-        ];
-        let func = Function {
-            name: b.name,
-            type_params: Vec::new(),
-            generic_bounds: vec![],
-            params: vec![],
-            ret: Ty::Unit,
-            pre: rv_core::Prop::True,
-            post: rv_core::Prop::True,
-            locals: b.locals,
-            blocks,
-            entry,
-        };
-        let prog = Program { types: Vec::new(), trait_impls: vec![], funcs: vec![func] };
-        let errs = check(&prog, &b.syms);
+        let b1 = b.block();
+        b.assign(r, borrow(BorrowKind::Shared, a)).goto(b1);
+        b.switch_to(b1)
+            .assign(m, borrow(BorrowKind::Mut, a))
+            .assign(t, RValue::Use(deref_use(r)))
+            .ret(unit());
+        let errs = check(&b.program(), &syms);
         assert_eq!(errs.len(), 1, "cross-block borrow should conflict, got {errs:?}");
         assert!(errs[0].message.contains("as mutable"), "{:?}", errs[0]);
     }
@@ -1318,18 +1192,17 @@ mod tests {
     #[test]
     fn projected_read_after_move() {
         // b = a (move a); c = a.0 (use of moved a via projection)
-        let mut b = Build::new("f");
-        let s = b.syms.intern("S");
-        let a = b.local("a", Ty::Adt(s));
-        let bb = b.local("b", Ty::Adt(s));
+        let mut syms = Symbols::new();
+        let mut b = build(&mut syms);
+        let s = b.sym("S");
+        let a = b.local("a", Ty::Adt(s, vec![]));
+        let bb = b.local("b", Ty::Adt(s, vec![]));
         let cc = b.local("c", Ty::Int);
         let proj_place = Place { local: a, proj: vec![Proj::Field(0)] };
-        let stmts = vec![
-            Stmt::Assign(Place::local(bb), RValue::Use(copy(a))),
-            Stmt::Assign(Place::local(cc), RValue::Use(Operand::Copy(proj_place))),
-        ];
-        let (prog, syms) = b.finish(vec![], stmts, Terminator::Return(Operand::Const(Const::Unit)));
-        let errs = check(&prog, &syms);
+        b.assign(bb, RValue::Use(copy(a)))
+            .assign(cc, RValue::Use(Operand::Copy(proj_place)))
+            .ret(unit());
+        let errs = check(&b.program(), &syms);
         assert_eq!(errs.len(), 1, "{errs:?}");
         assert!(errs[0].message.contains("use of moved value `a`"), "{:?}", errs[0]);
     }
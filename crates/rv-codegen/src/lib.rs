@@ -8,11 +8,41 @@
 //! Ghost statements (`Stmt::Assert` / `Stmt::Assume`) are erased — they emit no
 //! code. `Terminator::Drop` lowers to a plain jump (no runtime memory management
 //! in this slice).
+//!
+//! # Integer semantics
+//!
+//! The VM computes on 128-bit words; codegen makes every integer operator behave
+//! as in its type's width `W` (64 for the default `Int`, `N` for `iN`/`uN`), two's
+//! complement:
+//!
+//! * `wrapping_*` arithmetic and `<<` wrap to `W` bits (see [`FnBuilder::narrow_reg`]);
+//! * a shift amount is taken modulo `W`, as Rust's `wrapping_shl`/`wrapping_shr`
+//!   do: `x << 65` on an `i64` is `x << 1`, and a negative amount never escapes
+//!   the range. `>>` is arithmetic on signed types and logical on unsigned ones
+//!   (whose values are never negative);
+//! * `&`, `|`, `^` and `>>` cannot leave the range and need nothing;
+//! * checked `+`, `-`, `*`, `/` and unary `-` are proven in range by the verifier,
+//!   and [`CodegenOptions::overflow_checks`] decides what bytecode run without
+//!   verification does when one leaves it anyway. With checks on (the default),
+//!   `+`, `-`, `*` and `-MIN` trap ("attempt to add with overflow"); with them off
+//!   they wrap like their `wrapping_*` forms. `MIN / -1` traps in both modes, as
+//!   in Rust. The IR itself is the same in both modes. Checks cover widths below
+//!   128 bits: the VM's own word wraps silently at 128.
+//!
+//! # Flag enums
+//!
+//! An enum of exactly two variants, neither carrying a payload (`Off`/`On`,
+//! `Left`/`Right`), is a boolean in disguise. With
+//! [`CodegenOptions::bool_enums`] on (the default) its values are `Bool`s, not
+//! `Adt`s: variant 0 is `false` and variant 1 is `true`. Building one is a
+//! constant, a `match` on one is a [`Instr::Branch`], and `==` compares two
+//! booleans. [`TypeMeta`] still names the variants, so rendering is unchanged.
 
-use rv_core::{BinOp, IntTy, Symbols, Ty, UnOp};
+use rv_core::{BinOp, IntTy, Sym, Symbols, Ty, UnOp};
 use rv_ir::{
     AggKind, BlockId, BorrowKind, Function, LocalDecl, LocalId, Lowerable, Operand, Place, Proj,
-    Program, RValue, Stmt, Terminator,
+    Program, RValue, Stmt, Terminator, TypeDef, ASSERT_EQ, ASSERT_NE, FLOAT_BITS, STR_CONCAT,
+    TO_STRING,
 };
 use std::collections::HashSet;
 
@@ -38,10 +68,23 @@ pub enum Instr {
     Bin(u32, BinOp, u32, u32),
     /// `dst <- <unop> src`.
     Un(u32, UnOp, u32),
+    /// `dst <- src as ty`. The VM converts by the runtime value's shape, with the
+    /// semantics of [`Const::cast`] (wrapping integer narrowing, saturating float
+    /// to integer).
+    Cast(u32, Ty, u32),
     /// `dst <- callee(args...)`. `callee` indexes [`Bytecode::funcs`].
     Call(u32, usize, Vec<u32>),
     /// Built-in `print`: write the value in `src` to stdout; `dst <- Unit`.
     Print(u32, u32),
+    /// `dst <-` the integer, float, bool or string in `src`, as a string.
+    ToStr(u32, u32),
+    /// `dst <-` the string in `a` followed by the one in `b`.
+    Concat(u32, u32, u32),
+    /// `dst <-` the bit pattern of the `f64` in `src`, as an `i64`.
+    FloatBits(u32, u32),
+    /// `assert_eq` (`true`) or `assert_ne` of the values in `a` and `b`: fail
+    /// with both, rendered as `ty`, if they differ (or match); `dst <- Unit`.
+    AssertCmp(u32, bool, u32, u32, Ty),
     /// `dst <- closure of fn `fn_idx` capturing the values in `capture_regs``.
     /// Builds a first-class `Value::Closure`; `fn_idx` indexes [`Bytecode::funcs`].
     MakeClosure(u32, usize, Vec<u32>),
@@ -52,6 +95,13 @@ pub enum Instr {
     Jump(usize),
     /// If `cond` register is true jump to `then_off`, else to `else_off`.
     Branch(u32, usize, usize),
+    /// If the `tag` of the `Adt` value in `src` equals `tag` jump to `then_off`,
+    /// else to `else_off`. The two-target specialization of [`Instr::Switch`]:
+    /// emitted for a `match` with one arm plus `otherwise`, or with exactly two
+    /// arms, so the common `Some`/`None`, `Ok`/`Err` dispatch is a single test
+    /// rather than a table scan. A flag enum's `Bool` has tag 0 (`false`) or 1
+    /// (`true`), for the matches whose scrutinee type codegen cannot see.
+    BranchTag(u32, u32, usize, usize),
     /// Return the value in `src`.
     Ret(u32),
     /// `dst <- Adt { tag, fields: [src...] }`. Builds an algebraic data value from
@@ -60,6 +110,10 @@ pub enum Instr {
     /// `dst <- src.fields[field]`. Reads one field out of an `Adt` value. Nested
     /// projections are emitted as a chain of `Field` instructions through temps.
     Field(u32, u32, u32),
+    /// `base.fields[field] <- val`. Writes one field of the `Adt` value in `base`,
+    /// mutating the register in place. A store into a projected place is a chain
+    /// of these, innermost first, read-modify-writing each aggregate on the path.
+    SetField(u32, u32, u32),
     /// `dst <- base.fields[idx]`. Reads one element out of an aggregate `Adt` value
     /// (a tuple or array) where the element position is the runtime integer in the
     /// `idx` register. The dynamic-index analogue of `Field`; emitted for
@@ -81,7 +135,8 @@ pub enum Instr {
     VecPush(u32, u32, u32),
     /// Switch on the `tag` of the `Adt` value in `src`. For each `(tag, off)` in the
     /// table, jump to `off` if `src.tag == tag`. If none match, jump to `otherwise`
-    /// when present, else trap with a runtime error.
+    /// when present, else trap with a runtime error. A `Bool` is tagged as in
+    /// [`Instr::BranchTag`].
     Switch(u32, Vec<(u32, usize)>, Option<usize>),
 
     // --- References (a heap of cells) ---
@@ -102,10 +157,15 @@ pub enum Instr {
     /// that realizes writing a boxed local (`l = v` when `l` is boxed) or storing
     /// through a reference (`*r = v`).
     Store(u32, u32),
+    /// `dst <- &(*src).fields[field]`: the reference in `src`, narrowed to one
+    /// field of its referent. Realizes a field borrow (`&x.f`, `&r.f`), so a later
+    /// `Load`/`Store` through `dst` reads or writes just that field.
+    FieldRef(u32, u32, u32),
     /// Unconditionally fail at runtime with a fixed message. Emitted by codegen for
-    /// reference forms this slice does not support (e.g. borrowing a sub-place), so
+    /// reference forms this slice does not support (e.g. borrowing an element), so
     /// that `compile` stays infallible and the program traps cleanly if it reaches
-    /// the unsupported construct.
+    /// the unsupported construct, and behind the overflow checks of signed division
+    /// and negation.
     Trap(String),
 }
 
@@ -114,7 +174,8 @@ pub enum Instr {
 pub struct CompiledFn {
     /// Function name (for entry-point lookup / diagnostics).
     pub name: String,
-    /// Number of parameters; arguments bind to registers `0..nparams`.
+    /// Number of parameters; arguments bind to registers `0..nparams`, and the
+    /// function's prelude moves them on if its parameters are other locals.
     pub nparams: usize,
     /// Total number of register slots a frame needs.
     pub nregs: usize,
@@ -129,6 +190,10 @@ pub struct CompiledFn {
 #[derive(Clone, Debug)]
 pub struct Bytecode {
     pub funcs: Vec<CompiledFn>,
+    /// Reflection metadata for the program's structs and enums. A type's id is
+    /// its index here. Runtime values do not refer to it; it is for hosts that
+    /// render values by name.
+    pub types: Vec<TypeMeta>,
 }
 
 impl Bytecode {
@@ -138,8 +203,103 @@ impl Bytecode {
     }
 }
 
-/// Compile a lowerable program to bytecode.
+/// The names behind a user-defined type's runtime `Adt { tag, fields }` values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeMeta {
+    /// The interned name a `Ty::Adt` refers to the type by.
+    pub sym: Sym,
+    pub name: String,
+    pub type_params: Vec<Sym>,
+    /// A struct has one shape, tag 0, named after the struct.
+    pub is_struct: bool,
+    /// The variants, indexed by tag.
+    pub variants: Vec<VariantMeta>,
+}
+
+impl TypeMeta {
+    /// Whether this is a flag enum: two variants and no payload, represented at
+    /// runtime as a `Bool` when [`CodegenOptions::bool_enums`] is on.
+    pub fn is_flag(&self) -> bool {
+        let no_payload = self.variants.iter().all(|v| v.fields.is_empty());
+        !self.is_struct && self.variants.len() == 2 && no_payload
+    }
+}
+
+/// One variant (or a struct's only shape): its name and its fields in order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VariantMeta {
+    pub name: String,
+    /// Each field's name (`None` for a positional variant field) and declared
+    /// type, which may mention the type's parameters.
+    pub fields: Vec<(Option<String>, Ty)>,
+}
+
+/// The reflection metadata of `types`, in order: entry `i` describes `types[i]`.
+pub fn type_metadata(types: &[TypeDef], syms: &Symbols) -> Vec<TypeMeta> {
+    let name = |s: Sym| syms.resolve(s).to_string();
+    types
+        .iter()
+        .map(|def| match def {
+            TypeDef::Struct { name: s, type_params, fields } => TypeMeta {
+                sym: *s,
+                name: name(*s),
+                type_params: type_params.clone(),
+                is_struct: true,
+                variants: vec![VariantMeta {
+                    name: name(*s),
+                    fields: fields.iter().map(|f| (Some(name(f.name)), f.ty.clone())).collect(),
+                }],
+            },
+            TypeDef::Enum { name: s, type_params, variants } => TypeMeta {
+                sym: *s,
+                name: name(*s),
+                type_params: type_params.clone(),
+                is_struct: false,
+                variants: variants
+                    .iter()
+                    .map(|v| VariantMeta {
+                        name: name(v.name),
+                        fields: v.fields.iter().map(|t| (None, t.clone())).collect(),
+                    })
+                    .collect(),
+            },
+        })
+        .collect()
+}
+
+/// How [`compile_with`] lowers the parts of the program whose runtime behavior is
+/// a build choice rather than a property of the IR.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CodegenOptions {
+    /// Trap when checked arithmetic overflows its type (a debug build); when
+    /// `false` it wraps (a release build). See the module docs.
+    pub overflow_checks: bool,
+    /// Represent flag enums as booleans (see the module docs). Off, every enum
+    /// value is an `Adt`; the program behaves the same either way.
+    pub bool_enums: bool,
+}
+
+impl Default for CodegenOptions {
+    fn default() -> Self {
+        CodegenOptions { overflow_checks: true, bool_enums: true }
+    }
+}
+
+impl CodegenOptions {
+    /// The options of a release build: overflow wraps.
+    pub fn release() -> Self {
+        CodegenOptions { overflow_checks: false, ..CodegenOptions::default() }
+    }
+}
+
+/// Compile a lowerable program to bytecode with the default options.
 pub fn compile(prog: &Program<Lowerable>, syms: &Symbols) -> Bytecode {
+    compile_with(prog, syms, &CodegenOptions::default())
+}
+
+/// Compile a lowerable program to bytecode.
+pub fn compile_with(prog: &Program<Lowerable>, syms: &Symbols, opts: &CodegenOptions) -> Bytecode {
+    let _span = tracing::debug_span!("codegen", funcs = prog.funcs.len()).entered();
     // First pass: assign every function a stable index and resolve callee names.
     let name_to_index: std::collections::HashMap<&str, usize> = prog
         .funcs
@@ -148,13 +308,29 @@ pub fn compile(prog: &Program<Lowerable>, syms: &Symbols) -> Bytecode {
         .map(|(i, f)| (syms.resolve(f.name), i))
         .collect();
 
+    let types = type_metadata(&prog.types, syms);
+    let flags: HashSet<Sym> = if opts.bool_enums {
+        types.iter().filter(|m| m.is_flag()).map(|m| m.sym).collect()
+    } else {
+        HashSet::new()
+    };
     let funcs = prog
         .funcs
         .iter()
-        .map(|f| compile_fn(f, syms, &name_to_index))
+        .map(|f| compile_fn(f, syms, &name_to_index, &flags, opts))
         .collect();
 
-    Bytecode { funcs }
+    Bytecode { funcs, types }
+}
+
+/// The width an integer type computes in: the default `Int` is a signed 64-bit
+/// word. `None` for a non-integer.
+fn int_width(ty: &Ty) -> Option<IntTy> {
+    match ty {
+        Ty::Int => Some(IntTy { signed: true, bits: 64 }),
+        Ty::IntN(w) => Some(*w),
+        _ => None,
+    }
 }
 
 /// Per-function lowering state.
@@ -174,21 +350,24 @@ struct FnBuilder<'a> {
     /// width can be recovered to narrow (mask / sign-extend) an arithmetic
     /// result at its assignment. See [`FnBuilder::narrow_reg`].
     locals: &'a [LocalDecl<Lowerable>],
-    /// Locals that are address-taken (ever borrowed as a whole local). Their
+    /// Locals that are address-taken (ever borrowed, whole or in part). Their
     /// register holds a `Value::Ref(addr)` to a store cell; reads/writes go through
     /// the cell. See [`boxed_locals`].
     boxed: HashSet<u32>,
+    /// The flag enums, whose values are `Bool`s (see the module docs).
+    flags: &'a HashSet<Sym>,
+    opts: CodegenOptions,
 }
 
-/// Compute the set of locals that must be boxed: those that are ever the target of
-/// a whole-local borrow (`RValue::Ref(_, place)` with no projections). Sub-place
-/// borrows (`&x.f`, `&*r`) are not boxed here — codegen rejects them as unsupported.
+/// Compute the set of locals that must be boxed: those that are ever the root of
+/// a borrow's place (`&x`, `&x.f`). A borrow through a reference (`&*r`, `&r.f`)
+/// points into the referent's cell, so it leaves `r` itself unboxed.
 fn boxed_locals(f: &Function<Lowerable>) -> HashSet<u32> {
     let mut set = HashSet::new();
     for blk in &f.blocks {
         for stmt in &blk.stmts {
             if let Stmt::Assign(_, RValue::Ref(_, place)) = stmt {
-                if place.proj.is_empty() {
+                if !place.proj.iter().any(|p| matches!(p, Proj::Deref)) {
                     set.insert(place.local.0);
                 }
             }
@@ -219,7 +398,12 @@ fn compile_fn(
     f: &Function<Lowerable>,
     syms: &Symbols,
     name_to_index: &std::collections::HashMap<&str, usize>,
+    flags: &HashSet<Sym>,
+    opts: &CodegenOptions,
 ) -> CompiledFn {
+    if let Err(e) = f.validate_params() {
+        panic!("rv-codegen: malformed `{}`: {e}", syms.resolve(f.name));
+    }
     let nlocals = f.locals.len();
     let boxed = boxed_locals(f);
     let mut b = FnBuilder {
@@ -231,16 +415,40 @@ fn compile_fn(
         name_to_index,
         locals: &f.locals,
         boxed,
+        flags,
+        opts: *opts,
     };
 
+    // The VM binds argument `i` to register `i`. When the parameters are not
+    // exactly the locals `0..nparams`, move each argument into its parameter's
+    // register (through temporaries, since the two ranges can overlap) and reset
+    // the argument registers that belong to ordinary locals back to `Unit`.
+    let params = f.param_locals();
+    let positional = params.iter().enumerate().all(|(i, p)| p.0 as usize == i);
+    if !positional {
+        let temps: Vec<u32> = (0..params.len() as u32).map(|_| b.fresh()).collect();
+        for (i, &t) in temps.iter().enumerate() {
+            b.code.push(Instr::Move(t, i as u32));
+        }
+        for r in 0..params.len() as u32 {
+            if !params.contains(&LocalId(r)) {
+                b.code.push(Instr::Const(r, Const::Unit));
+            }
+        }
+        for (p, &t) in params.iter().zip(&temps) {
+            b.code.push(Instr::Move(p.0, t));
+        }
+    }
+
     // Box every address-taken local at function entry: allocate a store cell from
     // its current register value (a parameter, or the default `Unit`) and replace
-    // the register with a `Ref` to that cell. The `Alloc`s form a prelude at offset
-    // 0 followed by a `Jump` to the entry block, so they execute exactly once no
-    // matter which storage slot holds the entry block (and even if the entry block
-    // is a back-edge target, the jump lands *past* the prelude). When there are no
-    // boxed locals the prelude is empty and `entry_off` points straight at entry.
-    let has_prelude = !b.boxed.is_empty();
+    // the register with a `Ref` to that cell. Together with the argument moves
+    // above, the `Alloc`s form a prelude at offset 0 followed by a `Jump` to the
+    // entry block, so they execute exactly once no matter which storage slot holds
+    // the entry block (and even if the entry block is a back-edge target, the jump
+    // lands *past* the prelude). When there is nothing to move or box the prelude
+    // is empty and `entry_off` points straight at entry.
+    let has_prelude = !positional || !b.boxed.is_empty();
     if has_prelude {
         let mut prelude: Vec<u32> = b.boxed.iter().copied().collect();
         prelude.sort_unstable(); // deterministic order
@@ -292,7 +500,7 @@ fn compile_fn(
     }
 }
 
-impl FnBuilder<'_> {
+impl<'a> FnBuilder<'a> {
     /// Allocate a fresh temporary register.
     fn fresh(&mut self) -> u32 {
         let r = self.next_reg;
@@ -300,27 +508,42 @@ impl FnBuilder<'_> {
         r
     }
 
-    /// The fixed-integer width to narrow an assignment's result to, if any.
+    /// The type of the value stored at `place`: known for a whole local and for a
+    /// store through a reference (`*r`), `None` for any other projection.
+    fn place_ty(&self, place: &Place) -> Option<&'a Ty> {
+        let ty = &self.locals.get(place.local.0 as usize)?.ty;
+        match (place.proj.as_slice(), ty) {
+            ([], _) => Some(ty),
+            ([Proj::Deref], Ty::Ref { inner, .. }) => Some(inner),
+            _ => None,
+        }
+    }
+
+    /// The fixed-integer width to narrow an assignment's result to, if any, given
+    /// the destination's type.
     ///
-    /// Narrowing is needed only when (a) the destination local has a sized `IntN`
-    /// type strictly narrower than the 128-bit machine word, and (b) the value
-    /// comes from arithmetic that can leave that range — `+`, `-`, `*` (checked or
-    /// `wrapping_*`) and unary negation. A copy, call, or comparison already
-    /// yields an in-range value (established by the callee's width contract or the
-    /// operands themselves), so it needs no mask. 128-bit widths are the native
-    /// representation (the VM's `Value::Int` is `i128`) and never narrow.
-    fn narrowing_width(&self, local: LocalId, rvalue: &RValue) -> Option<IntTy> {
-        let affects_width = matches!(
+    /// Narrowing is needed only when (a) the destination is an integer strictly
+    /// narrower than the 128-bit machine word, and (b) the value comes from
+    /// arithmetic that can leave that range: `wrapping_*` `+`, `-`, `*` and `<<`,
+    /// and, when overflow checks are off, checked `+`, `-`, `*` and unary
+    /// negation (with checks on those trap instead of leaving it). A copy, call,
+    /// or comparison already yields an in-range value (established by the callee's
+    /// width contract or the operands themselves), so it needs no mask. 128-bit
+    /// widths are the native representation (the VM's `Value::Int` is `i128`) and
+    /// never narrow.
+    fn narrowing_width(&self, ty: Option<&Ty>, rvalue: &RValue) -> Option<IntTy> {
+        let wraps = matches!(
             rvalue,
-            RValue::Bin(BinOp::Add | BinOp::Sub | BinOp::Mul, _, _)
-                | RValue::WrappingBin(BinOp::Add | BinOp::Sub | BinOp::Mul, _, _)
-                | RValue::Un(UnOp::Neg, _)
+            RValue::WrappingBin(BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Shl, _, _)
+                | RValue::Bin(BinOp::Shl, _, _)
         );
-        if !affects_width {
-            return None;
-        }
-        match self.locals.get(local.0 as usize).map(|d| &d.ty) {
-            Some(Ty::IntN(w)) if w.bits < 128 => Some(*w),
+        let checked = matches!(
+            rvalue,
+            RValue::Bin(BinOp::Add | BinOp::Sub | BinOp::Mul, _, _) | RValue::Un(UnOp::Neg, _)
+        );
+        let unchecked = checked && !self.opts.overflow_checks;
+        match int_width(ty?) {
+            Some(w) if w.bits < 128 && (wraps || unchecked) => Some(w),
             _ => None,
         }
     }
@@ -437,6 +660,11 @@ impl FnBuilder<'_> {
             // Ghost statements are erased.
             Stmt::Assert(_) | Stmt::Assume(_) | Stmt::Invariant(_) => {}
             Stmt::Assign(place, rvalue) => self.lower_assign(place, rvalue),
+            // A discarded call: the VM still writes a result, into a scratch register.
+            Stmt::Eval(rvalue) => {
+                let dst = self.fresh();
+                self.lower_rvalue(dst, rvalue, None);
+            }
         }
     }
 
@@ -444,83 +672,154 @@ impl FnBuilder<'_> {
     ///
     /// * no projection, plain local      -> compute into the local's register;
     /// * no projection, boxed local      -> compute into a temp, `Store` to its cell;
-    /// * place ends in `Deref` (`*r = v`)-> evaluate the reference, `Store` through it;
-    /// * any other projected store       -> unsupported in this slice (`Trap`).
+    /// * any projected place             -> compute into a temp, then [`Self::store`].
     fn lower_assign(&mut self, place: &Place, rvalue: &RValue) {
-        // Whole-local assignment (no projection).
-        if place.proj.is_empty() {
-            // A sized-integer local narrows an overflowing arithmetic result to
-            // its width at runtime (two's-complement wraparound), so the value
-            // actually stored respects the type's range — matching the verifier's
-            // width contract on a `wrapping_*` op and giving fixed-width `iN`/`uN`
-            // real execution semantics instead of running as bare `i64`.
-            let width = self.narrowing_width(place.local, rvalue);
-            if self.boxed.contains(&place.local.0) {
-                // Boxed local: compute the value, then write it into the store cell.
-                let val = self.rvalue_reg(rvalue);
-                let val = self.narrow_reg(val, width);
-                self.code.push(Instr::Store(place.local.0, val));
-            } else {
-                // Plain register local: the original fast path.
-                self.lower_rvalue(place.local.0, rvalue);
-                let narrowed = self.narrow_reg(place.local.0, width);
-                if narrowed != place.local.0 {
-                    self.code.push(Instr::Move(place.local.0, narrowed));
-                }
+        // A sized-integer destination narrows an overflowing arithmetic result to
+        // its width at runtime (two's-complement wraparound), so the value actually
+        // stored respects the type's range — matching the verifier's width contract
+        // on a `wrapping_*` op and giving fixed-width `iN`/`uN` real execution
+        // semantics instead of running as bare `i64`.
+        let ty = self.place_ty(place);
+        let width = self.narrowing_width(ty, rvalue);
+        if place.proj.is_empty() && !self.boxed.contains(&place.local.0) {
+            // Plain register local: the original fast path.
+            self.lower_rvalue(place.local.0, rvalue, ty);
+            let narrowed = self.narrow_reg(place.local.0, width);
+            if narrowed != place.local.0 {
+                self.code.push(Instr::Move(place.local.0, narrowed));
             }
             return;
         }
+        let val = self.rvalue_reg(rvalue, ty);
+        let val = self.narrow_reg(val, width);
+        self.store(place, val);
+    }
 
-        // Store through a reference: the place is `*r` (optionally with the deref as
-        // the final projection). Evaluate the reference, then store into its cell.
-        if matches!(place.proj.last(), Some(Proj::Deref)) {
-            // The reference value is the place with the trailing `Deref` removed.
-            let base = Place {
-                local: place.local,
-                proj: place.proj[..place.proj.len() - 1].to_vec(),
-            };
-            let ref_reg = self.place_reg(&base);
-            let val = self.rvalue_reg(rvalue);
-            self.code.push(Instr::Store(ref_reg, val));
+    /// Write the value in `val` to `place`. A whole local is written directly (or
+    /// through its cell, when boxed) and `*r` through the reference `r`. Any other
+    /// place is one element of an aggregate: read the aggregate, update the
+    /// element in the copy, and store the copy back to where the aggregate lives,
+    /// recursively, so `a.b.c = v` writes `a` and `(*r).f = v` writes `*r`.
+    fn store(&mut self, place: &Place, val: u32) {
+        let Some((last, rest)) = place.proj.split_last() else {
+            if self.boxed.contains(&place.local.0) {
+                self.code.push(Instr::Store(place.local.0, val));
+            } else if val != place.local.0 {
+                self.code.push(Instr::Move(place.local.0, val));
+            }
             return;
-        }
-
-        // Indexed store into an array held directly by a local: `local[i] = v`. We
-        // support only a single `Index` projection off a bare (unboxed) local; the
-        // element is written in place, analogous to a whole-local `Store`.
-        if let [Proj::Index(idx_operand)] = place.proj.as_slice() {
-            if !self.boxed.contains(&place.local.0) {
+        };
+        let base = Place { local: place.local, proj: rest.to_vec() };
+        match last {
+            Proj::Deref => {
+                let ref_reg = self.place_reg(&base);
+                self.code.push(Instr::Store(ref_reg, val));
+            }
+            // A downcast only reinterprets the enum value, so the write is to it.
+            Proj::Downcast(_) => self.store(&base, val),
+            Proj::Field(n) => {
+                let agg = self.place_reg(&base);
+                self.code.push(Instr::SetField(agg, *n, val));
+                self.store(&base, agg);
+            }
+            Proj::Index(idx_operand) => {
                 let idx = self.operand_reg(idx_operand);
-                let val = self.rvalue_reg(rvalue);
-                self.code
-                    .push(Instr::IndexSet(place.local.0, idx, val));
-                return;
+                let agg = self.place_reg(&base);
+                self.code.push(Instr::IndexSet(agg, idx, val));
+                self.store(&base, agg);
             }
         }
-
-        // Projected stores that are not a whole-pointee `*r = v` (e.g. `l.f = v`,
-        // `*r.f = v`) need read-modify-write of an aggregate, which this slice does
-        // not implement; trap cleanly rather than silently miscompile.
-        self.code.push(Instr::Trap(
-            "codegen: unsupported store into a projected place (only `l = v` and \
-             `*r = v` are supported)"
-                .to_string(),
-        ));
     }
 
     /// Evaluate an `RValue` into a fresh register and return it. Used where we need
     /// the value materialized somewhere other than a destination local (stores).
-    fn rvalue_reg(&mut self, rvalue: &RValue) -> u32 {
+    fn rvalue_reg(&mut self, rvalue: &RValue, ty: Option<&Ty>) -> u32 {
         // `Use` of an operand can reuse that operand's register directly.
         if let RValue::Use(op) = rvalue {
             return self.operand_reg(op);
         }
         let dst = self.fresh();
-        self.lower_rvalue(dst, rvalue);
+        self.lower_rvalue(dst, rvalue, ty);
         dst
     }
 
-    fn lower_rvalue(&mut self, dst: u32, rvalue: &RValue) {
+    /// The type of an operand, where [`Self::place_ty`] knows it.
+    fn operand_ty(&self, op: &Operand) -> Option<&'a Ty> {
+        match op {
+            Operand::Const(Const::Int(_)) => Some(&Ty::Int),
+            Operand::Const(_) => None,
+            Operand::Copy(place) => self.place_ty(place),
+        }
+    }
+
+    /// A register holding the shift amount `amount` (in `reg`) taken modulo
+    /// `width`'s bit count. 128-bit shifts need no mask: the VM's own shifts wrap
+    /// the amount at its 128-bit word.
+    fn shift_amount_reg(&mut self, amount: &Operand, reg: u32, width: Option<IntTy>) -> u32 {
+        let bits = width.map_or(128, |w| w.bits as i128);
+        if bits >= 128 {
+            return reg;
+        }
+        let out = self.fresh();
+        if let Operand::Const(Const::Int(n)) = amount {
+            self.code.push(Instr::Const(out, Const::Int(n & (bits - 1))));
+        } else {
+            let mask = self.fresh();
+            self.code.push(Instr::Const(mask, Const::Int(bits - 1)));
+            self.code.push(Instr::Bin(out, BinOp::BitAnd, reg, mask));
+        }
+        out
+    }
+
+    /// A register holding whether `reg` equals the constant `value`.
+    fn eq_const(&mut self, reg: u32, value: i128) -> u32 {
+        let k = self.fresh();
+        self.code.push(Instr::Const(k, Const::Int(value)));
+        let out = self.fresh();
+        self.code.push(Instr::Bin(out, BinOp::Eq, reg, k));
+        out
+    }
+
+    /// Trap with `msg` if the boolean in `cond` is true; fall through otherwise.
+    fn trap_if(&mut self, cond: u32, msg: &str) {
+        let at = self.code.len();
+        self.code.push(Instr::Branch(cond, at + 1, at + 2));
+        self.code.push(Instr::Trap(msg.to_string()));
+    }
+
+    /// Trap with "attempt to `verb` with overflow" unless the integer in `reg`
+    /// lies in `w`'s range.
+    fn check_range(&mut self, reg: u32, w: IntTy, verb: &str) {
+        let (lo, hi) = (self.fresh(), self.fresh());
+        self.code.push(Instr::Const(lo, Const::Int(w.min())));
+        self.code.push(Instr::Const(hi, Const::Int(w.max())));
+        let (below, above, out) = (self.fresh(), self.fresh(), self.fresh());
+        self.code.push(Instr::Bin(below, BinOp::Lt, reg, lo));
+        self.code.push(Instr::Bin(above, BinOp::Gt, reg, hi));
+        self.code.push(Instr::Bin(out, BinOp::Or, below, above));
+        self.trap_if(out, &format!("attempt to {verb} with overflow"));
+    }
+
+    /// Emit the runtime overflow check of the checked signed division `a / b`
+    /// (registers `ra`/`rb`): it traps on `MIN / -1`. Nothing is emitted when an
+    /// operand is a literal that rules the case out.
+    fn check_division(&mut self, (a, ra): (&Operand, u32), (b, rb): (&Operand, u32), w: IntTy) {
+        let ruled_out = matches!(b, Operand::Const(Const::Int(d)) if *d != -1)
+            || matches!(a, Operand::Const(Const::Int(n)) if *n != w.min());
+        if ruled_out {
+            return;
+        }
+        let is_min = self.eq_const(ra, w.min());
+        let is_neg_one = self.eq_const(rb, -1);
+        let both = self.fresh();
+        self.code.push(Instr::Bin(both, BinOp::And, is_min, is_neg_one));
+        self.trap_if(both, "attempt to divide with overflow");
+    }
+
+    /// Lower `rvalue` into register `dst`. `ty` is the type of the place the value
+    /// is stored to, when known: it fixes the width integer operators compute in
+    /// (see the module docs).
+    fn lower_rvalue(&mut self, dst: u32, rvalue: &RValue, ty: Option<&Ty>) {
         match rvalue {
             RValue::Use(op) => match op {
                 Operand::Const(c) => self.code.push(Instr::Const(dst, c.clone())),
@@ -532,16 +831,50 @@ impl FnBuilder<'_> {
                 }
             },
             // Checked and wrapping binary ops generate identical machine
-            // arithmetic; they differ only in which obligations the verifier emits.
+            // arithmetic; they differ in which obligations the verifier emits, and
+            // in the overflow checks. Results that can leave the width without a
+            // check are narrowed by the caller (`narrowing_width`).
             RValue::Bin(op, a, bb) | RValue::WrappingBin(op, a, bb) => {
+                let width = ty.or_else(|| self.operand_ty(a)).and_then(int_width);
                 let ra = self.operand_reg(a);
-                let rb = self.operand_reg(bb);
+                let mut rb = self.operand_reg(bb);
+                if matches!(op, BinOp::Shl | BinOp::Shr) {
+                    rb = self.shift_amount_reg(bb, rb, width);
+                }
+                if let (RValue::Bin(BinOp::Div, ..), Some(w)) = (rvalue, width) {
+                    if w.signed {
+                        self.check_division((a, ra), (bb, rb), w);
+                    }
+                }
                 self.code.push(Instr::Bin(dst, *op, ra, rb));
+                let verb = match op {
+                    BinOp::Add => "add",
+                    BinOp::Sub => "subtract",
+                    BinOp::Mul => "multiply",
+                    _ => return,
+                };
+                if let (RValue::Bin(..), Some(w)) = (rvalue, width) {
+                    if self.opts.overflow_checks && w.bits < 128 {
+                        self.check_range(dst, w, verb);
+                    }
+                }
             }
             RValue::Un(op, a) => {
                 let ra = self.operand_reg(a);
+                let width = ty.or_else(|| self.operand_ty(a)).and_then(int_width);
+                // Checked negation overflows only at a signed type's minimum.
+                if let (UnOp::Neg, Operand::Copy(_), Some(w)) = (op, a, width) {
+                    if w.signed && self.opts.overflow_checks {
+                        let is_min = self.eq_const(ra, w.min());
+                        self.trap_if(is_min, "attempt to negate with overflow");
+                    }
+                }
                 self.code.push(Instr::Un(dst, *op, ra));
             }
+            RValue::Cast(a, ty) => {
+                let ra = self.operand_reg(a);
+                self.code.push(Instr::Cast(dst, ty.clone(), ra));
+            }
             RValue::Call(callee, args) => {
                 let arg_regs: Vec<u32> = args.iter().map(|a| self.operand_reg(a)).collect();
                 // The built-in `print(x)` writes its argument and evaluates to `()`.
@@ -549,6 +882,27 @@ impl FnBuilder<'_> {
                     self.code.push(Instr::Print(dst, arg_regs[0]));
                     return;
                 }
+                // The string builtins a `format` call expands into.
+                match (self.syms.resolve(*callee), &arg_regs[..]) {
+                    (TO_STRING, &[src]) => return self.code.push(Instr::ToStr(dst, src)),
+                    (STR_CONCAT, &[a, b]) => return self.code.push(Instr::Concat(dst, a, b)),
+                    (FLOAT_BITS, &[src]) => return self.code.push(Instr::FloatBits(dst, src)),
+                    (name @ (ASSERT_EQ | ASSERT_NE), &[a, b]) => {
+                        let ty = match &args[0] {
+                            Operand::Const(Const::Int(_)) => Ty::Int,
+                            Operand::Const(Const::Float(_)) => Ty::Float,
+                            Operand::Const(Const::Str(_)) => Ty::Str,
+                            Operand::Const(Const::Bool(_)) => Ty::Bool,
+                            Operand::Const(Const::Unit) => Ty::Unit,
+                            Operand::Copy(place) => {
+                                self.place_ty(place).cloned().unwrap_or(Ty::Unit)
+                            }
+                        };
+                        let eq = name == ASSERT_EQ;
+                        return self.code.push(Instr::AssertCmp(dst, eq, a, b, ty));
+                    }
+                    _ => {}
+                }
                 let idx = *self
                     .name_to_index
                     .get(self.syms.resolve(*callee))
@@ -573,6 +927,10 @@ impl FnBuilder<'_> {
                 let arg_regs: Vec<u32> = args.iter().map(|a| self.operand_reg(a)).collect();
                 self.code.push(Instr::CallClosure(dst, closure_reg, arg_regs));
             }
+            // A flag enum's variant is the boolean of its index.
+            RValue::Aggregate(AggKind::Variant(e, idx), _) if self.flags.contains(e) => {
+                self.code.push(Instr::Const(dst, Const::Bool(*idx == 1)));
+            }
             RValue::Aggregate(kind, operands) => {
                 // Evaluate each field operand into a register, then build the Adt.
                 let field_regs: Vec<u32> =
@@ -606,22 +964,44 @@ impl FnBuilder<'_> {
     /// `Ref(addr)` to that local's store cell. Because every borrowed local is boxed
     /// (its register already holds the `Ref`), `&local` is just a register copy —
     /// shared and mutable borrows are represented identically at runtime (the
-    /// distinction is enforced earlier, by verification, not by the VM). Borrows of
-    /// sub-places (`&x.f`, `&*r`) would need cell addresses this slice does not
-    /// compute, so they trap.
+    /// distinction is enforced earlier, by verification, not by the VM). A borrow
+    /// through the place's last `Deref` starts from the reference stored there
+    /// instead. Each field projection after that narrows the reference with a
+    /// `FieldRef`. Borrowing an array element would need a runtime index in the
+    /// path, which this slice does not support, so it traps.
     fn lower_ref(&mut self, dst: u32, _kind: BorrowKind, place: &Place) {
-        if place.proj.is_empty() {
-            // The local is boxed (the pre-pass guarantees this), so its register
-            // holds a `Ref` to its cell. Copy it.
-            debug_assert!(self.boxed.contains(&place.local.0));
-            self.code.push(Instr::Move(dst, place.local.0));
-        } else {
-            self.code.push(Instr::Trap(
-                "codegen: unsupported borrow of a sub-place (only whole-local \
-                 `&x` / `&mut x` are supported)"
-                    .to_string(),
-            ));
+        let split = place.proj.iter().rposition(|p| matches!(p, Proj::Deref));
+        let (mut cur, fields) = match split {
+            Some(i) => {
+                let base = Place { local: place.local, proj: place.proj[..i].to_vec() };
+                (self.place_reg(&base), &place.proj[i + 1..])
+            }
+            None => {
+                // The local is boxed (the pre-pass guarantees this), so its register
+                // holds a `Ref` to its cell.
+                debug_assert!(self.boxed.contains(&place.local.0));
+                (place.local.0, &place.proj[..])
+            }
+        };
+        for p in fields {
+            match p {
+                Proj::Field(n) => {
+                    let next = self.fresh();
+                    self.code.push(Instr::FieldRef(next, cur, *n));
+                    cur = next;
+                }
+                Proj::Downcast(_) => {}
+                Proj::Index(_) | Proj::Deref => {
+                    self.code.push(Instr::Trap(
+                        "codegen: unsupported borrow of an element (only `&x`, `&x.f` \
+                         and borrows through a reference are supported)"
+                            .to_string(),
+                    ));
+                    return;
+                }
+            }
         }
+        self.code.push(Instr::Move(dst, cur));
     }
 
     fn lower_terminator(&mut self, term: &Terminator<Lowerable>) {
@@ -649,6 +1029,43 @@ impl FnBuilder<'_> {
             Terminator::Match { scrutinee, arms, otherwise } => {
                 // Evaluate the scrutinee into a register; the VM reads its tag.
                 let src = self.operand_reg(scrutinee);
+                // A two-target match tests one tag and branches. With two arms and
+                // no `otherwise` the match is exhaustive over a two-variant enum
+                // (checked in `rv-infer`), so a miss on the first arm's tag is
+                // exactly the second arm.
+                let two_target = match (arms.as_slice(), otherwise) {
+                    ([arm], Some(other)) => Some((arm.variant, arm.target, *other)),
+                    ([first, second], None) => Some((first.variant, first.target, second.target)),
+                    _ => None,
+                };
+                let flag = matches!(
+                    self.operand_ty(scrutinee),
+                    Some(Ty::Adt(e, _)) if self.flags.contains(e)
+                );
+                if let Some((tag, then_blk, else_blk)) = two_target {
+                    let instr = self.code.len();
+                    // A flag enum's scrutinee is the boolean "is variant 1".
+                    let (then_blk, else_blk) = match (flag, tag) {
+                        (true, 0) => (else_blk, then_blk),
+                        _ => (then_blk, else_blk),
+                    };
+                    self.code.push(if flag {
+                        Instr::Branch(src, usize::MAX, usize::MAX)
+                    } else {
+                        Instr::BranchTag(src, tag, usize::MAX, usize::MAX)
+                    });
+                    self.fixups.push(Fixup {
+                        instr,
+                        slot: FixupSlot::BranchThen,
+                        target: then_blk,
+                    });
+                    self.fixups.push(Fixup {
+                        instr,
+                        slot: FixupSlot::BranchElse,
+                        target: else_blk,
+                    });
+                    return;
+                }
                 let instr = self.code.len();
                 // Placeholder table: tags are known now, offsets are back-patched.
                 let table: Vec<(u32, usize)> =
@@ -697,6 +1114,8 @@ impl FnBuilder<'_> {
                 (Instr::Jump(t), FixupSlot::Jump) => *t = off,
                 (Instr::Branch(_, t, _), FixupSlot::BranchThen) => *t = off,
                 (Instr::Branch(_, _, e), FixupSlot::BranchElse) => *e = off,
+                (Instr::BranchTag(_, _, t, _), FixupSlot::BranchThen) => *t = off,
+                (Instr::BranchTag(_, _, _, e), FixupSlot::BranchElse) => *e = off,
                 (Instr::Switch(_, table, _), FixupSlot::SwitchArm(i)) => table[*i].1 = off,
                 (Instr::Switch(_, _, other), FixupSlot::SwitchOtherwise) => *other = Some(off),
                 _ => unreachable!("fixup slot/instr mismatch"),
@@ -709,35 +1128,17 @@ impl FnBuilder<'_> {
 mod tests {
     use super::*;
     use rv_core::Prop;
-    use rv_ir::{Block, Function, LocalDecl, Terminator};
+    use rv_ir::builder::{copy, int, FunctionBuilder};
+    use rv_ir::{Block, Function, LocalDecl, MatchArm, Terminator, VariantDef};
 
     /// Build a single-block function `f() -> ret_ty` that assigns
     /// `local0 = <rv>` and returns it. `local0` is declared with `dst_ty` so the
     /// narrowing pass can see its width.
     fn one_assign_fn(dst_ty: Ty, rv: RValue, syms: &mut Symbols) -> Program<Lowerable> {
-        let l0 = LocalId(0);
-        let func = Function {
-            name: syms.intern("f"),
-            type_params: vec![],
-            generic_bounds: vec![],
-            params: vec![],
-            ret: dst_ty.clone(),
-            pre: Prop::True,
-            post: Prop::True,
-            locals: vec![LocalDecl { name: None, ty: dst_ty }],
-            blocks: vec![Block {
-                id: BlockId(0),
-                stmts: vec![Stmt::Assign(Place::local(l0), rv)],
-                term: Terminator::Return(Operand::Copy(Place::local(l0))),
-            }],
-            entry: BlockId(0),
-        };
-        Program { types: vec![], trait_impls: vec![], funcs: vec![func] }
-    }
-
-    /// An operand for the integer literal `n`.
-    fn imm(n: i128) -> Operand {
-        Operand::Const(Const::Int(n))
+        let mut b = FunctionBuilder::new(syms, "f", dst_ty.clone());
+        let l0 = b.temp(dst_ty);
+        b.assign(l0, rv).ret(copy(l0));
+        b.program()
     }
 
     /// A `wrapping_add` into a `u8` local emits a low-8-bits mask (`& 255`) so the
@@ -746,7 +1147,7 @@ mod tests {
     fn wrapping_add_into_u8_masks_low_bits() {
         let mut syms = Symbols::new();
         let u8_ty = Ty::IntN(IntTy { signed: false, bits: 8 });
-        let rv = RValue::WrappingBin(BinOp::Add, imm(200), imm(100));
+        let rv = RValue::WrappingBin(BinOp::Add, int(200), int(100));
         let bc = compile(&one_assign_fn(u8_ty, rv, &mut syms), &syms);
         let code = &bc.funcs[0].code;
         // The mask constant 255 is materialized and BitAnd'd.
@@ -767,7 +1168,7 @@ mod tests {
     fn wrapping_add_into_i8_sign_extends() {
         let mut syms = Symbols::new();
         let i8_ty = Ty::IntN(IntTy { signed: true, bits: 8 });
-        let rv = RValue::WrappingBin(BinOp::Add, imm(100), imm(100));
+        let rv = RValue::WrappingBin(BinOp::Add, int(100), int(100));
         let bc = compile(&one_assign_fn(i8_ty, rv, &mut syms), &syms);
         let code = &bc.funcs[0].code;
         assert!(
@@ -786,7 +1187,7 @@ mod tests {
     #[test]
     fn native_width_is_not_narrowed() {
         let mut syms = Symbols::new();
-        let rv = RValue::Bin(BinOp::Add, imm(2), imm(3));
+        let rv = RValue::Bin(BinOp::Add, int(2), int(3));
         let bc = compile(&one_assign_fn(Ty::Int, rv, &mut syms), &syms);
         let code = &bc.funcs[0].code;
         assert!(
@@ -804,7 +1205,7 @@ mod tests {
     fn copy_into_u8_is_not_narrowed() {
         let mut syms = Symbols::new();
         let u8_ty = Ty::IntN(IntTy { signed: false, bits: 8 });
-        let rv = RValue::Use(imm(42));
+        let rv = RValue::Use(int(42));
         let bc = compile(&one_assign_fn(u8_ty, rv, &mut syms), &syms);
         let code = &bc.funcs[0].code;
         assert!(
@@ -821,7 +1222,7 @@ mod tests {
     fn i128_result_is_not_narrowed_and_does_not_panic() {
         let mut syms = Symbols::new();
         let i128_ty = Ty::IntN(IntTy { signed: true, bits: 128 });
-        let rv = RValue::WrappingBin(BinOp::Add, imm(2), imm(3));
+        let rv = RValue::WrappingBin(BinOp::Add, int(2), int(3));
         let bc = compile(&one_assign_fn(i128_ty, rv, &mut syms), &syms);
         let code = &bc.funcs[0].code;
         assert!(
@@ -834,7 +1235,7 @@ mod tests {
 
         // Same for u128.
         let u128_ty = Ty::IntN(IntTy { signed: false, bits: 128 });
-        let rv = RValue::WrappingBin(BinOp::Add, imm(2), imm(3));
+        let rv = RValue::WrappingBin(BinOp::Add, int(2), int(3));
         let bc = compile(&one_assign_fn(u128_ty, rv, &mut syms), &syms);
         let code = &bc.funcs[0].code;
         assert!(
@@ -842,4 +1243,158 @@ mod tests {
             "a 128-bit result must not be masked on the 64-bit VM: {code:?}"
         );
     }
+
+    /// A function that matches on local 0 with the given arms / `otherwise` and
+    /// returns the index of the block taken. Blocks `1..=4` each return their id.
+    fn match_fn(
+        arms: Vec<MatchArm>,
+        otherwise: Option<BlockId>,
+        syms: &mut Symbols,
+    ) -> Program<Lowerable> {
+        let mut b = FunctionBuilder::new(syms, "f", Ty::Int);
+        let enum_name = b.sym("E");
+        let scrutinee = b.param("e", Ty::Adt(enum_name, vec![]));
+        let arms = arms.into_iter().map(|a| (a.variant, a.target));
+        let returns: Vec<BlockId> = (1..=4).map(|_| b.block()).collect();
+        b.match_(copy(scrutinee), arms, otherwise);
+        for ret in returns {
+            b.switch_to(ret).ret(int(ret.0 as i128));
+        }
+        b.program()
+    }
+
+    /// A two-arm match (the shape `?` and an `Option`/`Result` match lower to)
+    /// becomes a single `BranchTag` with both targets resolved, and no `Switch`.
+    #[test]
+    fn two_arm_match_emits_branch_tag() {
+        let mut syms = Symbols::new();
+        let arms = vec![
+            MatchArm { variant: 0, target: BlockId(1) },
+            MatchArm { variant: 1, target: BlockId(2) },
+        ];
+        let bc = compile(&match_fn(arms, None, &mut syms), &syms);
+        let code = &bc.funcs[0].code;
+        assert!(
+            !code.iter().any(|i| matches!(i, Instr::Switch(..))),
+            "a two-target match must not build a switch table: {code:?}"
+        );
+        let Some(Instr::BranchTag(0, 0, then_off, else_off)) =
+            code.iter().find(|i| matches!(i, Instr::BranchTag(..)))
+        else {
+            panic!("expected a BranchTag on tag 0: {code:?}");
+        };
+        assert!(matches!(code[*then_off], Instr::Const(_, Const::Int(1))), "{code:?}");
+        assert!(matches!(code[*else_off], Instr::Const(_, Const::Int(2))), "{code:?}");
+    }
+
+    /// One arm plus `otherwise` is also a two-target branch.
+    #[test]
+    fn single_arm_with_otherwise_emits_branch_tag() {
+        let mut syms = Symbols::new();
+        let arms = vec![MatchArm { variant: 2, target: BlockId(3) }];
+        let bc = compile(&match_fn(arms, Some(BlockId(4)), &mut syms), &syms);
+        let code = &bc.funcs[0].code;
+        assert!(
+            code.iter().any(|i| matches!(i, Instr::BranchTag(0, 2, _, _))),
+            "expected a BranchTag on tag 2: {code:?}"
+        );
+        assert!(!code.iter().any(|i| matches!(i, Instr::Switch(..))), "{code:?}");
+    }
+
+    /// A match on a flag enum branches on its boolean, variant 1 taking the
+    /// `then` side whichever order the arms come in.
+    #[test]
+    fn flag_enum_match_branches_on_a_bool() {
+        let mut syms = Symbols::new();
+        let arms = vec![
+            MatchArm { variant: 0, target: BlockId(1) },
+            MatchArm { variant: 1, target: BlockId(2) },
+        ];
+        let mut prog = match_fn(arms, None, &mut syms);
+        let variants = ["Off", "On"].map(|v| VariantDef { name: syms.intern(v), fields: vec![] });
+        let name = syms.intern("E");
+        prog.types.push(TypeDef::Enum { name, type_params: vec![], variants: variants.into() });
+        let code = &compile(&prog, &syms).funcs[0].code;
+        let Some(Instr::Branch(0, then_off, else_off)) =
+            code.iter().find(|i| matches!(i, Instr::Branch(..)))
+        else {
+            panic!("expected a Branch on the scrutinee: {code:?}");
+        };
+        assert!(matches!(code[*then_off], Instr::Const(_, Const::Int(2))), "{code:?}");
+        assert!(matches!(code[*else_off], Instr::Const(_, Const::Int(1))), "{code:?}");
+        assert!(!code.iter().any(|i| matches!(i, Instr::BranchTag(..))), "{code:?}");
+
+        let opts = CodegenOptions { bool_enums: false, ..CodegenOptions::default() };
+        let code = &compile_with(&prog, &syms, &opts).funcs[0].code;
+        assert!(code.iter().any(|i| matches!(i, Instr::BranchTag(0, 0, _, _))), "{code:?}");
+    }
+
+    /// A real multi-way match keeps the general `Switch` path.
+    #[test]
+    fn multi_way_match_keeps_switch() {
+        let mut syms = Symbols::new();
+        let arms = (0..3).map(|v| MatchArm { variant: v, target: BlockId(v + 1) }).collect();
+        let bc = compile(&match_fn(arms, None, &mut syms), &syms);
+        let code = &bc.funcs[0].code;
+        assert!(code.iter().any(|i| matches!(i, Instr::Switch(..))), "{code:?}");
+        assert!(!code.iter().any(|i| matches!(i, Instr::BranchTag(..))), "{code:?}");
+    }
+
+    /// `f(a, b) { t = a; return t; }` with `b` unused, as a DCE-style pass would
+    /// leave it after deleting the locals `drop` lists and renumbering the rest.
+    /// `rewrite_params` picks the parameter list the pass produced.
+    fn dce_fn(
+        drop: &[u32],
+        rewrite_params: impl Fn(&dyn Fn(u32) -> Option<LocalId>) -> Vec<LocalId>,
+        syms: &mut Symbols,
+    ) -> Function<Lowerable> {
+        let names = ["a", "b", "t"].map(|n| Some(syms.intern(n)));
+        let kept: Vec<u32> = (0..3).filter(|l| !drop.contains(l)).collect();
+        let renumber = |l: u32| kept.iter().position(|&k| k == l).map(|i| LocalId(i as u32));
+        let local = |l: u32| Place::local(renumber(l).unwrap());
+        Function {
+            name: syms.intern("f"),
+            type_params: vec![],
+            generic_bounds: vec![],
+            params: rewrite_params(&renumber),
+            ret: Ty::Int,
+            pre: Prop::True,
+            post: Prop::True,
+            locals: kept
+                .iter()
+                .map(|&l| LocalDecl { name: names[l as usize], mutable: false, ty: Ty::Int })
+                .collect(),
+            blocks: vec![Block {
+                id: BlockId(0),
+                stmts: vec![Stmt::Assign(local(2), RValue::Use(Operand::Copy(local(0))))],
+                term: Terminator::Return(Operand::Copy(local(2))),
+            }],
+            entry: BlockId(0),
+        }
+    }
+
+    /// Dropping an unused parameter's local changes the calling convention, so the
+    /// validator rejects it however the pass patched up the parameter list.
+    #[test]
+    fn dropping_an_unused_parameter_is_rejected() {
+        let mut syms = Symbols::new();
+        let original = dce_fn(&[], |r| vec![r(0).unwrap(), r(1).unwrap()], &mut syms);
+        assert_eq!(original.validate_params_kept(&original), Ok(()));
+
+        // The pass removed `b` from the parameter list along with its local.
+        let dropped = dce_fn(&[1], |r| vec![r(0).unwrap()], &mut syms);
+        let err = dropped.validate_params_kept(&original).unwrap_err();
+        assert!(err.contains("had 2 parameters but now has 1"), "{err}");
+
+        // The pass left the list positional, so `b` now names `t`.
+        let stale = dce_fn(&[1], |_| vec![LocalId(0), LocalId(1)], &mut syms);
+        let err = stale.validate_params_kept(&original).unwrap_err();
+        assert!(err.contains("parameter 1 no longer names its original local"), "{err}");
+
+        // A list naming a local twice, or one past the end, is malformed outright.
+        let twice = dce_fn(&[1], |_| vec![LocalId(0), LocalId(0)], &mut syms);
+        assert!(twice.validate_params().unwrap_err().contains("repeats local 0"));
+        let past = dce_fn(&[1], |_| vec![LocalId(0), LocalId(2)], &mut syms);
+        assert!(past.validate_params().unwrap_err().contains("only 2 locals are declared"));
+    }
 }
@@ -28,6 +28,12 @@ impl Symbols {
     pub fn resolve(&self, s: Sym) -> &str {
         self.0.resolve(s.0).map(String::as_str).unwrap_or("?")
     }
+    /// A symbol distinct from every other, `name` included, that prints as
+    /// `name`: a binder that must not be confused with others spelled the same.
+    pub fn scoped(&mut self, name: Sym) -> Sym {
+        let text = self.resolve(name).to_string();
+        Sym(self.0.push(text))
+    }
 }
 
 /// A fixed-width integer type: its signedness and bit width.
@@ -191,10 +197,16 @@ pub enum Ty {
     /// guarded against a *symbolic* length term rather than a static size.
     Vec(Box<Ty>),
     Fn(Vec<Ty>, Box<Ty>),
+    /// `!`: the type of an expression that never produces a value (a `return`
+    /// in expression position). It is compatible with every expected type.
     Never,
-    /// A user-defined algebraic data type (struct or enum), referenced by name.
-    /// Its field/variant structure lives in the IR's `TypeDef` table.
-    Adt(Sym),
+    /// A user-defined algebraic data type (struct or enum), referenced by name,
+    /// with its type arguments in declaration order (`Option<i64>` is
+    /// `Adt(Option, [Int])`). Its field/variant structure lives in the IR's
+    /// `TypeDef` table. The arguments are empty for a non-generic type, and also
+    /// when they are unknown (a bare `Option` annotation), which checking treats
+    /// as compatible with any instantiation.
+    Adt(Sym, Vec<Ty>),
     /// A reference `&T` (`mutable == false`) or `&mut T` (`mutable == true`).
     Ref { mutable: bool, inner: Box<Ty> },
     /// A generic type parameter (`T` inside `fn f<T>(..)`), opaque to checking.
@@ -216,9 +228,11 @@ pub enum BinOp {
     Le,
     Gt,
     Ge,
-    /// Bitwise/shift integer operators (`& | ^ << >>`). Their runtime semantics
-    /// are exact i64 bit operations; to the linear solver they are *uninterpreted*
-    /// (opaque atoms — sound but incomplete: no bit-level reasoning).
+    /// Bitwise/shift integer operators (`& | ^ << >>`). At runtime they are two's
+    /// complement bit operations in the operand type's width, with the shift amount
+    /// taken modulo that width (see `rv-codegen`'s integer semantics); to the linear
+    /// solver they are *uninterpreted* (opaque atoms — sound but incomplete: no
+    /// bit-level reasoning).
     BitAnd,
     BitOr,
     BitXor,
@@ -424,6 +438,31 @@ pub fn subst_prop(p: &Prop, var: Sym, value: &Term) -> Prop {
     }
 }
 
+/// Every variable `p` mentions, bound or free.
+pub fn prop_vars(p: &Prop, out: &mut Vec<Sym>) {
+    fn term_vars(t: &Term, out: &mut Vec<Sym>) {
+        match t {
+            Term::Var(s) => out.push(*s),
+            Term::Int(_) | Term::Bool(_) => {}
+            Term::Un(_, a) | Term::Field(a, _) => term_vars(a, out),
+            Term::Bin(_, a, b) => {
+                term_vars(a, out);
+                term_vars(b, out);
+            }
+            Term::App(_, args) => args.iter().for_each(|a| term_vars(a, out)),
+        }
+    }
+    match p {
+        Prop::True | Prop::False => {}
+        Prop::Holds(t) => term_vars(t, out),
+        Prop::Not(a) | Prop::Forall(_, a) | Prop::Exists(_, a) => prop_vars(a, out),
+        Prop::And(a, b) | Prop::Or(a, b) | Prop::Implies(a, b) => {
+            prop_vars(a, out);
+            prop_vars(b, out);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
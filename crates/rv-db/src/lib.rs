@@ -16,12 +16,19 @@
 //!         └────────────┬─────────────┘
 //!                      │ reads text
 //!         ┌────────────▼─────────────┐
-//!  query  │ parse_and_lower(src)     │   parse → lower; memoizes a Frontend
-//!         │   summary (function names + parse/lower Ok/Err)
-//!         └────────────┬─────────────┘
-//!                      │
-//!         ┌────────────▼─────────────┐
-//!  query  │ elaborate(src)           │   infer types/phases + generate obligations
+//!  query  │ parse_program(src)       │   parse → lower, once per edit
+//!         │   memoizes: ParsedProgram  (Arc<AST + IR<Parsed> + Symbols>)
+//!         └──────┬──────────────┬────┘
+//!                │              │ for each function f
+//!         ┌──────▼───────┐  ┌───▼──────────────────────┐
+//!  query  │ parse_and_   │  │ function_input(src, f)   │   f's IR; equal across edits
+//!         │ lower(src)   │  └───┬──────────────────────┘   to other functions' bodies
+//!         │   Frontend   │  ┌───▼──────────────────────┐
+//!         │   summary    │  │ infer_function_types     │   reads each callee's
+//!         └──────┬───────┘  │   (src, f)               │   function_signature_types
+//!                │          └───┬──────────────────────┘
+//!         ┌──────▼──────────────▼────┐
+//!  query  │ elaborate(src)           │   collect the typed functions + generate obligations
 //!         │   memoizes: ElaboratedProgram  (Arc<IR<Lowerable> + Vec<Obligation>>)
 //!         └────────────┬─────────────┘
 //!                      │
@@ -31,9 +38,10 @@
 //!         └──────────────────────────┘
 //! ```
 //!
-//! Each box is a salsa **tracked function** keyed on the `SourceProgram` input. Because
-//! every query reads (directly or via a callee) `SourceProgram::text`, changing the text
-//! invalidates all three; re-running with the same text re-executes none of them.
+//! Each box is a salsa **tracked function** keyed on the `SourceProgram` input (and, per
+//! function, the function's name). Because every query reads (directly or via a callee)
+//! `SourceProgram::text`, changing the text re-validates all of them; re-running with the
+//! same text re-executes none of them.
 //!
 //! # Handling the non-salsa-friendly IR
 //!
@@ -42,27 +50,83 @@
 //! and `rv_infer::Elaborated` — are *not* `Eq`/`Update` and we are not allowed to change
 //! the leaf crates. We therefore:
 //!
-//! * have the front-end query [`parse_and_lower`] return only a salsa-friendly [`Frontend`]
-//!   *summary* (the un-cloneable, by-value-consumed `IR<Parsed>` never crosses a query edge),
-//! * carry the elaboration bundle behind an [`Arc`] inside a thin newtype
-//!   ([`ElaboratedProgram`]),
-//! * hand-write an `unsafe impl salsa::Update` for that newtype that always reports the
+//! * carry the parsed and elaborated bundles behind an [`Arc`] inside thin newtypes
+//!   ([`ParsedProgram`], [`ElaboratedProgram`]),
+//! * hand-write an `unsafe impl salsa::Update` for those newtypes that always reports the
 //!   value as "changed" (a conservative, always-sound choice — it can only cause *extra*
 //!   downstream recomputation, never a stale cache),
+//! * compare smaller values derived from them where an edit should stop early: the
+//!   [`Frontend`] function list, and each function's [`FunctionInput`] fingerprint and
+//!   signature,
 //! * and have the *top* query [`analyze`] return a fully salsa-friendly
 //!   [`AnalysisResult`] (`Clone + PartialEq + Eq + Debug`), which is what callers and the
 //!   driver consume. So the memoization that matters — "same source ⇒ no work" — is exact,
 //!   while the un-comparable IR never has to be compared.
 //!
-//! `Symbols` (needed mutably by parse/lower) is threaded *inside* each query and stashed in
-//! the `Arc` bundle so the next stage can reuse it, never crossing a salsa boundary as a
-//! bare value.
+//! Parsing interns names into the [`Database`]'s symbol table, which only ever grows: a
+//! name keeps its `Sym` across edits, so comparing `Sym`s compares names, and a value
+//! derived from an earlier parse stays valid against a later one. [`parse_program`] stashes
+//! a snapshot of the table in its `Arc` bundle for the later stages.
+//!
+//! An editor reports changes through [`Database::edit`] rather than setting the text
+//! outright. [`parse_program`] then replays the edits made since the last parse on that
+//! parse, reparsing only the items around them (see [`rv_syntax::reparse`]); the result
+//! is the same as parsing the new text from scratch.
+//!
+//! # Per-function inference
+//!
+//! [`infer_function_types`] types one function on its own. It reads the function's
+//! [`function_input`] and, for each call, the callee's [`function_signature_types`] —
+//! never a callee's body. Both are re-derived from the shared parse on an edit, but
+//! compare equal when the edit was to another function's body, so salsa's early cutoff
+//! leaves the caller's inference cached. [`elaborate`] assembles the program from these
+//! queries, so [`analyze`] re-infers only the functions an edit could have changed.
+//!
+//! # Query log
+//!
+//! With the `query-log` feature, every query execution and cache hit is recorded in
+//! a ring buffer on the [`Database`] ([`Database::query_log`]), along with what each
+//! execution read. [`Database::explain_rebuild`] then answers "why did this edit
+//! recompute that?" directly, tracing each re-run query back to the input. Without
+//! the feature the hooks compile to nothing.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
 
-use std::sync::Arc;
+use rv_core::{Sym, Symbols};
+use rv_infer::{CalleeSignatures, Elaborated, SigTypes};
+use rv_ir::{Function, Lowerable, Parsed, Program, SourceLines, TypeDef};
+use rv_syntax::ParsedFile;
+pub use rv_syntax::{CfgSet, TextEdit};
+pub use rv_codegen::CodegenOptions;
 
-use rv_core::Symbols;
-use rv_infer::Elaborated;
-use rv_ir::{Parsed, Program};
+#[cfg(feature = "query-log")]
+pub mod query_log;
+#[cfg(feature = "query-log")]
+pub use query_log::{explain_rebuild, QueryEvent, QueryEventKind, QueryGraph, QueryLog};
+
+/// Start timing a tracked query's execution on `$src` (and for function `$func`)
+/// (feature `query-log`); expands to nothing otherwise.
+macro_rules! instrument {
+    ($db:expr, $query:literal, $src:expr $(, $func:expr)?) => {
+        #[cfg(feature = "query-log")]
+        let _timer = query_log::Timer::start($db, $query, $src, None $(.or(Some($func)))?);
+    };
+}
+
+/// Record that the running query reads query `$query` on `$src` (and for
+/// function `$func`), or with `input` the [`SourceProgram`] itself (feature
+/// `query-log`); expands to nothing otherwise.
+macro_rules! depends {
+    ($db:expr, input, $src:expr) => {
+        #[cfg(feature = "query-log")]
+        query_log::depends($db, query_log::QueryNode::new(query_log::INPUT, $src, None));
+    };
+    ($db:expr, $query:literal, $src:expr $(, $func:expr)?) => {
+        #[cfg(feature = "query-log")]
+        query_log::depends($db, query_log::QueryNode::new($query, $src, None $(.or(Some($func)))?));
+    };
+}
 
 // ---------------------------------------------------------------------------
 // Database
@@ -78,28 +142,141 @@ use rv_ir::{Parsed, Program};
 #[derive(Clone)]
 pub struct Database {
     storage: salsa::Storage<Self>,
+    /// Every name any parse has interned (see [`Db::symbols`]).
+    symbols: Arc<Mutex<Symbols>>,
+    parsed_files: Arc<Mutex<ParsedFiles>>,
+    #[cfg(feature = "query-log")]
+    query_log: Arc<Mutex<QueryLog>>,
 }
 
 impl Default for Database {
+    #[cfg(not(feature = "query-log"))]
     fn default() -> Self {
-        Self { storage: salsa::Storage::new(None) }
+        Self {
+            storage: salsa::Storage::new(None),
+            symbols: Arc::default(),
+            parsed_files: Arc::default(),
+        }
+    }
+
+    #[cfg(feature = "query-log")]
+    fn default() -> Self {
+        Self::with_hook(|_| {})
     }
 }
 
 #[salsa::db]
 impl salsa::Database for Database {}
 
+/// The database interface the tracked queries take. Beyond salsa's own, it gives
+/// the queries a symbol table shared across edits, the previous parse of each
+/// source, and somewhere to record themselves when the query log is enabled.
+#[salsa::db]
+pub trait Db: salsa::Database {
+    /// The symbol table every parse interns into. Names are only ever added, so a
+    /// name's `Sym` is the same in every parse this database runs.
+    fn symbols(&self) -> &Mutex<Symbols>;
+
+    /// Each source's last parse and the edits made to it since.
+    fn parsed_files(&self) -> &Mutex<ParsedFiles>;
+
+    #[cfg(feature = "query-log")]
+    fn query_log_sink(&self) -> &Mutex<QueryLog>;
+}
+
+#[salsa::db]
+impl Db for Database {
+    fn symbols(&self) -> &Mutex<Symbols> {
+        &self.symbols
+    }
+
+    fn parsed_files(&self) -> &Mutex<ParsedFiles> {
+        &self.parsed_files
+    }
+
+    #[cfg(feature = "query-log")]
+    fn query_log_sink(&self) -> &Mutex<QueryLog> {
+        &self.query_log
+    }
+}
+
 impl Database {
     /// Build a database that records the name of every tracked function salsa
     /// *executes* (a `WillExecute` event) into `log`. Used by the incrementality
     /// test to prove that a re-run with unchanged input does no work.
-    pub fn with_logger(log: Arc<std::sync::Mutex<Vec<String>>>) -> Self {
-        let storage = salsa::Storage::new(Some(Box::new(move |event| {
+    pub fn with_logger(log: Arc<Mutex<Vec<String>>>) -> Self {
+        Self::with_hook(move |event| {
             if let salsa::EventKind::WillExecute { .. } = event.kind {
                 log.lock().unwrap().push(format!("{:?}", event.kind));
             }
+        })
+    }
+
+    /// A database whose salsa event hook runs `hook` (after feeding the query log,
+    /// when enabled).
+    fn with_hook(hook: impl Fn(&salsa::Event) + Send + Sync + 'static) -> Self {
+        #[cfg(feature = "query-log")]
+        let query_log = Arc::new(Mutex::new(QueryLog::default()));
+        #[cfg(feature = "query-log")]
+        let sink = query_log.clone();
+        let storage = salsa::Storage::new(Some(Box::new(move |event: salsa::Event| {
+            #[cfg(feature = "query-log")]
+            query_log::lock(&sink).observe(&event);
+            hook(&event);
         })));
-        Self { storage }
+        Self {
+            storage,
+            symbols: Arc::default(),
+            parsed_files: Arc::default(),
+            #[cfg(feature = "query-log")]
+            query_log,
+        }
+    }
+
+    /// Apply `edit` to `src`'s text, as an editor reports a change. Unlike
+    /// setting the text outright, this lets the next [`parse_program`] reparse
+    /// only the items around the edits (see [`rv_syntax::reparse`]).
+    pub fn edit(&mut self, src: SourceProgram, edit: TextEdit) -> Result<(), String> {
+        use salsa::Setter;
+        let text = src.text(self);
+        let range = edit.range.clone();
+        if text.get(range.clone()).is_none() {
+            return Err(format!("edit range {range:?} is not within the {}-byte source", text.len()));
+        }
+        let text = [&text[..range.start], &edit.text, &text[range.end..]].concat();
+        let mut files = self.parsed_files.lock().unwrap_or_else(PoisonError::into_inner);
+        files.edits.entry(src).or_default().push(edit);
+        drop(files);
+        src.set_text(self).to(text);
+        Ok(())
+    }
+
+    /// The most recent query executions and cache hits, oldest first (at most
+    /// [`query_log::QUERY_LOG_CAPACITY`]).
+    #[cfg(feature = "query-log")]
+    pub fn query_log(&self) -> Vec<QueryEvent> {
+        query_log::lock(&self.query_log).events()
+    }
+
+    /// Forget the logged events, so the next read shows only what runs after
+    /// this call. The recorded dependency edges are kept.
+    #[cfg(feature = "query-log")]
+    pub fn clear_query_log(&self) {
+        query_log::lock(&self.query_log).clear();
+    }
+
+    /// What each query read the last time it executed.
+    #[cfg(feature = "query-log")]
+    pub fn query_graph(&self) -> QueryGraph {
+        query_log::lock(&self.query_log).graph()
+    }
+
+    /// Why each query since the log was last cleared executed (see
+    /// [`explain_rebuild`]), traced for function `func` alone if given.
+    #[cfg(feature = "query-log")]
+    pub fn explain_rebuild(&self, func: Option<&str>) -> String {
+        let log = query_log::lock(&self.query_log);
+        explain_rebuild(&log.events(), &log.graph(), func)
     }
 }
 
@@ -107,12 +284,25 @@ impl Database {
 // Input
 // ---------------------------------------------------------------------------
 
-/// The single salsa **input**: the program's source text. Mutating it (via the
-/// generated `set_text` setter) is what drives incremental recomputation.
+/// The single salsa **input**: the program's source text and the cfg options it
+/// is compiled under. Mutating either (via the generated `set_text` / `set_cfg`
+/// setters) is what drives incremental recomputation.
 #[salsa::input]
 pub struct SourceProgram {
     #[returns(ref)]
     pub text: String,
+    /// The active `#[cfg(...)]` options; items they rule out are dropped at parse.
+    #[returns(ref)]
+    pub cfg: CfgSet,
+}
+
+/// What [`parse_program`] keeps between runs to parse incrementally: each
+/// source's last successful parse, and the edits made through
+/// [`Database::edit`] since.
+#[derive(Default)]
+pub struct ParsedFiles {
+    last: HashMap<SourceProgram, ParsedFile>,
+    edits: HashMap<SourceProgram, Vec<TextEdit>>,
 }
 
 // ---------------------------------------------------------------------------
@@ -120,18 +310,42 @@ pub struct SourceProgram {
 // ---------------------------------------------------------------------------
 
 /// A salsa-friendly summary of a successful parse+lower: the lowered function
-/// names. `Program<Parsed>` is *not* `Clone`/`Eq` and `rv_infer::elaborate`
-/// consumes it *by value*, so the un-cloneable IR cannot be moved across a query
-/// edge and cached. We therefore make [`parse_and_lower`] return this lightweight,
-/// fully `Clone + Eq` summary; it still reads `SourceProgram::text` and so is a
-/// genuine memoized dependency, and downstream queries reuse its cached
-/// validation (and short-circuit on its `Err`) before re-deriving owned IR.
+/// names, in program order. Unlike the [`ParsedProgram`] it summarizes, it compares
+/// by value, so an edit that keeps the program's functions leaves its readers cached.
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Frontend {
     /// Names of the functions the program lowered to (a cheap fingerprint).
     pub functions: Vec<String>,
 }
 
+/// The source parsed and lowered, behind an `Arc`: produced once per edit by
+/// [`parse_program`] and shared by every query that needs the AST or the IR.
+#[derive(Clone)]
+pub struct ParsedProgram(pub Arc<ParsedInner>);
+pub struct ParsedInner {
+    pub module: rv_syntax::ast::Module,
+    pub prog: Program<Parsed>,
+    pub lines: SourceLines,
+    /// The database's symbol table as of this parse.
+    pub syms: Symbols,
+}
+
+// SAFETY: as for `ElaboratedProgram` below: always reporting "changed" is
+// conservative. The queries that read it compare their own outputs instead.
+unsafe impl salsa::Update for ParsedProgram {
+    unsafe fn maybe_update(old: *mut Self, new: Self) -> bool {
+        unsafe { *old = new };
+        true
+    }
+}
+
+impl PartialEq for ParsedProgram {
+    fn eq(&self, _: &Self) -> bool {
+        false
+    }
+}
+impl Eq for ParsedProgram {}
+
 /// `IR<Lowerable>` + obligations (the elaboration result) plus its `Symbols`,
 /// behind an `Arc`. Produced (owned) by [`elaborate`] and consumed by [`analyze`]
 /// for borrow-checking, discharge, and (in the driver) codegen+run. The `Arc`
@@ -141,6 +355,7 @@ pub struct ElaboratedProgram(pub Arc<ElaboratedInner>);
 pub struct ElaboratedInner {
     pub elaborated: Elaborated,
     pub syms: Symbols,
+    pub lines: SourceLines,
 }
 
 // SAFETY: `maybe_update` may conservatively report "changed". This bundle wraps
@@ -196,8 +411,11 @@ pub enum AnalysisResult {
 pub struct Analysis {
     /// (origin, ok) for every verification obligation, in order.
     pub obligations: Vec<ObligationOutcome>,
-    /// Borrow/ownership violation strings (empty = clean).
+    /// Borrow/ownership violation strings (empty = clean), deduplicated by
+    /// [`rv_diagnostics::normalize`].
     pub borrow_errors: Vec<String>,
+    /// Follow-on borrow errors that repeat one in `borrow_errors` at another line.
+    pub secondary_borrow_errors: Vec<String>,
     /// Did every obligation discharge AND the borrow checker pass?
     pub all_verified: bool,
 }
@@ -206,61 +424,104 @@ pub struct Analysis {
 // Tracked queries
 // ---------------------------------------------------------------------------
 
-/// Parse + lower the source into owned `IR<Parsed>` and its `Symbols`. Shared by
-/// the [`parse_and_lower`] query (which summarizes it) and [`elaborate`] (which
-/// consumes it). Not a query itself — the IR it produces is not salsa-friendly.
-fn do_parse_and_lower(text: &str) -> Result<(Program<Parsed>, Symbols), String> {
-    let mut syms = Symbols::new();
-    let module = rv_syntax::parse(text, &mut syms)?;
-    let prog = rv_lower::lower(&module, &mut syms)?;
-    Ok((prog, syms))
+/// **Query 1.** parse → lower. Reads `SourceProgram::text` and `cfg`; memoizes the
+/// [`ParsedProgram`], interning names into the database's [`Db::symbols`]. `Err` for a
+/// front-end parse/lower error.
+#[salsa::tracked]
+pub fn parse_program(db: &dyn Db, src: SourceProgram) -> Result<ParsedProgram, String> {
+    instrument!(db, "parse_program", src);
+    depends!(db, input, src);
+    let mut syms = db.symbols().lock().unwrap_or_else(PoisonError::into_inner);
+    let module = parse_file(db, src, &mut syms)?;
+    let (prog, lines) = rv_lower::lower_with_lines(&module, &mut syms)?;
+    Ok(ParsedProgram(Arc::new(ParsedInner { module, prog, lines, syms: syms.clone() })))
 }
 
-/// **Query 1.** parse → lower. Reads `SourceProgram::text`; memoizes a
-/// salsa-friendly [`Frontend`] summary (the lowered function names). Returns
-/// `Err(String)` for a front-end parse/lower error. The un-cloneable
-/// `IR<Parsed>` itself is *not* cached (it can't cross a query edge); [`elaborate`]
-/// re-derives it on demand. This query exists so the front end is a first-class
-/// memoized stage and so callers can validate parsing in isolation.
+/// Parse `src`: replay the edits made since its last parse on that parse, when
+/// there is one, so that only the items around them are reparsed. The replay must
+/// arrive at the current text; when it does not (the text was set outright, or an
+/// edit left the file unparseable) the text is parsed from scratch.
+fn parse_file(
+    db: &dyn Db,
+    src: SourceProgram,
+    syms: &mut Symbols,
+) -> Result<rv_syntax::ast::Module, String> {
+    let mut files = db.parsed_files().lock().unwrap_or_else(PoisonError::into_inner);
+    let edits = files.edits.remove(&src).unwrap_or_default();
+    let (text, cfg) = (src.text(db), src.cfg(db));
+    let replayed = files.last.remove(&src).filter(|old| old.cfg() == cfg).and_then(|old| {
+        edits.iter().try_fold(old, |file, edit| rv_syntax::reparse(&file, edit, syms).ok())
+    });
+    let file = match replayed.filter(|file| file.src == *text) {
+        Some(file) => file,
+        None => ParsedFile::parse(text.clone(), syms, cfg)?,
+    };
+    let module = file.module.clone();
+    files.last.insert(src, file);
+    Ok(module)
+}
+
+/// **Query 2.** The [`Frontend`] summary of [`parse_program`]: the lowered function
+/// names. The queries that walk the whole program read this rather than the parse,
+/// so they are cut off when an edit keeps the program's functions.
 #[salsa::tracked]
-pub fn parse_and_lower(db: &dyn salsa::Database, src: SourceProgram) -> Result<Frontend, String> {
-    let (prog, syms) = do_parse_and_lower(src.text(db))?;
+pub fn parse_and_lower(db: &dyn Db, src: SourceProgram) -> Result<Frontend, String> {
+    instrument!(db, "parse_and_lower", src);
+    depends!(db, "parse_program", src);
+    let parsed = parse_program(db, src)?;
+    let ParsedInner { prog, syms, .. } = &*parsed.0;
     let functions = prog.funcs.iter().map(|f| syms.resolve(f.name).to_string()).collect();
     Ok(Frontend { functions })
 }
 
-/// **Query 2.** elaborate. Depends on [`parse_and_lower`] (to reuse its memoized
-/// validation and short-circuit on parse/lower errors), then produces the owned
-/// `IR<Lowerable>` + obligations bundle that it memoizes behind an `Arc`. `Err`
-/// for a static type error.
+/// **Query 3.** elaborate. Types each function of [`parse_and_lower`]'s summary with
+/// [`infer_function_types`], then checks and generates the obligations of the typed
+/// program (see [`rv_infer::generate_obligations`]); memoizes the owned
+/// `IR<Lowerable>` + obligations bundle behind an `Arc`. `Err` for a static type error.
 #[salsa::tracked]
-pub fn elaborate(db: &dyn salsa::Database, src: SourceProgram) -> Result<ElaboratedProgram, String> {
-    // Reuse the memoized front-end result: surfaces parse/lower errors and makes
-    // `elaborate` a genuine dependent of `parse_and_lower` in the query graph.
-    parse_and_lower(db, src)?;
-    // `rv_infer::elaborate` consumes `Program<Parsed>` by value and the IR isn't
-    // `Clone`, so we obtain a fresh owned copy here rather than across a query edge.
-    let (prog, syms) = do_parse_and_lower(src.text(db))?;
-    let elaborated = rv_infer::elaborate(prog, &syms)?;
-    Ok(ElaboratedProgram(Arc::new(ElaboratedInner { elaborated, syms })))
-}
-
-/// **Query 3 (top).** borrow-check + discharge every obligation. Depends on
+pub fn elaborate(db: &dyn Db, src: SourceProgram) -> Result<ElaboratedProgram, String> {
+    instrument!(db, "elaborate", src);
+    depends!(db, "parse_and_lower", src);
+    let frontend = parse_and_lower(db, src)?;
+    let mut funcs = Vec::with_capacity(frontend.functions.len());
+    for func in &frontend.functions {
+        depends!(db, "infer_function_types", src, func);
+        funcs.push(infer_function_types(db, src, func.clone())?.0.as_ref().clone());
+    }
+    depends!(db, "parse_program", src);
+    let parsed = parse_program(db, src)?;
+    let ParsedInner { prog, syms, lines, .. } = &*parsed.0;
+    let obligations = rv_infer::generate_obligations(prog, &funcs, syms)?;
+    let prog = Program { types: prog.types.clone(), trait_impls: prog.trait_impls.clone(), funcs };
+    let elaborated = Elaborated { prog, obligations };
+    let (syms, lines) = (syms.clone(), lines.clone());
+    Ok(ElaboratedProgram(Arc::new(ElaboratedInner { elaborated, syms, lines })))
+}
+
+/// **Query 4 (top).** borrow-check + discharge every obligation. Depends on
 /// [`elaborate`]; memoizes the salsa-friendly [`AnalysisResult`]. This is the
 /// query the driver and `compile_source` invoke.
 #[salsa::tracked]
-pub fn analyze(db: &dyn salsa::Database, src: SourceProgram) -> AnalysisResult {
+pub fn analyze(db: &dyn Db, src: SourceProgram) -> AnalysisResult {
+    instrument!(db, "analyze", src);
+    depends!(db, "elaborate", src);
     let elaborated = match elaborate(db, src) {
         Ok(e) => e,
         Err(e) => return AnalysisResult::FrontendError(e),
     };
-    let ElaboratedInner { elaborated, syms } = &*elaborated.0;
+    let ElaboratedInner { elaborated, syms, lines } = &*elaborated.0;
 
-    // Borrow / ownership check over the typed program.
-    let borrow_errors = rv_borrowck::check(&elaborated.prog, syms)
-        .into_iter()
-        .map(|e| format!("{}: {}", e.func, e.message))
-        .collect::<Vec<_>>();
+    // Borrow / ownership check over the typed program. Normalizing here, rather
+    // than in each consumer, keeps every front end's error list identical; the
+    // line an error is at is what tells a follow-on error from its root.
+    let borrow_errors = rv_borrowck::check(&elaborated.prog, syms).into_iter().map(|e| {
+        match e.site.and_then(|site| lines.get(site)) {
+            Some(span) => format!("line {}: {}: {}", span.start, e.func, e.message),
+            None => format!("{}: {}", e.func, e.message),
+        }
+    });
+    let rv_diagnostics::Normalized { primary: borrow_errors, secondary: secondary_borrow_errors } =
+        rv_diagnostics::normalize(borrow_errors);
 
     // Discharge each obligation with the built-in solvers.
     let registry = rv_solve::default_registry();
@@ -269,12 +530,185 @@ pub fn analyze(db: &dyn salsa::Database, src: SourceProgram) -> AnalysisResult {
         .iter()
         .map(|ob| {
             let outcome = registry.discharge(ob);
-            ObligationOutcome { origin: ob.origin.clone(), ok: outcome.checks(ob) }
+            let ok = outcome.checks(ob);
+            tracing::debug!(origin = %ob.origin, ok, "discharged");
+            ObligationOutcome { origin: ob.origin.clone(), ok }
         })
         .collect();
 
     let all_verified = borrow_errors.is_empty() && obligations.iter().all(|o| o.ok);
-    AnalysisResult::Analyzed(Analysis { obligations, borrow_errors, all_verified })
+    AnalysisResult::Analyzed(Analysis {
+        obligations,
+        borrow_errors,
+        secondary_borrow_errors,
+        all_verified,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Per-function inference
+// ---------------------------------------------------------------------------
+
+/// What inferring one function reads besides its callees' signatures: its lowered
+/// body and the program-wide tables it is typed against, all from one
+/// [`ParsedProgram`].
+#[derive(Clone)]
+pub struct FunctionInput(pub Arc<FunctionInputInner>);
+pub struct FunctionInputInner {
+    pub parsed: ParsedProgram,
+    /// The function's index in `parsed`'s program.
+    pub index: usize,
+    pub types: HashMap<Sym, TypeDef>,
+    /// The source the input derives from, with every *other* function's body
+    /// left out (see [`function_input`]). Equal fingerprints mean equal inputs.
+    fingerprint: String,
+}
+
+impl FunctionInputInner {
+    /// The function itself.
+    pub fn func(&self) -> &Function<Parsed> {
+        &self.parsed.0.prog.funcs[self.index]
+    }
+}
+
+impl PartialEq for FunctionInput {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.fingerprint == other.0.fingerprint
+    }
+}
+impl Eq for FunctionInput {}
+
+// SAFETY: `old` is valid and exclusively ours for the call. An input equal to the
+// old one (by fingerprint) keeps the old value and reports "unchanged", which is
+// what lets a caller's inference survive an edit to another function's body.
+unsafe impl salsa::Update for FunctionInput {
+    unsafe fn maybe_update(old: *mut Self, new: Self) -> bool {
+        let old = unsafe { &mut *old };
+        if *old == new {
+            return false;
+        }
+        *old = new;
+        true
+    }
+}
+
+/// One function's inferred (`Lowerable`) form. Like [`ElaboratedProgram`], it is
+/// never reported equal to a previous value.
+#[derive(Clone)]
+pub struct InferredFunction(pub Arc<Function<Lowerable>>);
+
+// SAFETY: as for `ElaboratedProgram`: always reporting "changed" is conservative.
+unsafe impl salsa::Update for InferredFunction {
+    unsafe fn maybe_update(old: *mut Self, new: Self) -> bool {
+        unsafe { *old = new };
+        true
+    }
+}
+
+impl PartialEq for InferredFunction {
+    fn eq(&self, _: &Self) -> bool {
+        false
+    }
+}
+impl Eq for InferredFunction {}
+
+/// **Query.** The signature of function `func` ([`SigTypes`]): its declared parameter
+/// and return types, or the ones its body implies if the return type is left open.
+/// Reads only `func`'s [`function_input`]. `None` if the source names no such
+/// function, does not lower, or the implied signature does not type-check.
+#[salsa::tracked]
+pub fn function_signature_types(
+    db: &dyn Db,
+    src: SourceProgram,
+    func: String,
+) -> Option<Arc<SigTypes>> {
+    instrument!(db, "function_signature_types", src, &func);
+    depends!(db, "function_input", src, &func);
+    let input = function_input(db, src, func).ok()?;
+    let ParsedInner { prog, syms, lines, .. } = &*input.0.parsed.0;
+    rv_infer::signature(prog, input.0.func(), syms, lines).ok().map(Arc::new)
+}
+
+/// **Query.** The [`FunctionInput`] of function `func`, taken from the shared
+/// [`parse_program`]. Its fingerprint is the parsed module with every other top-level
+/// function's body emptied (a lifted closure's input keeps them all, as its body lies in
+/// one of them), and the lowered function names (lifted closures are numbered
+/// program-wide). A `Sym` stands for the same name in every parse (see
+/// [`Db::symbols`]), so the fingerprint compares names.
+#[salsa::tracked]
+pub fn function_input(
+    db: &dyn Db,
+    src: SourceProgram,
+    func: String,
+) -> Result<FunctionInput, String> {
+    instrument!(db, "function_input", src, &func);
+    depends!(db, "parse_program", src);
+    let parsed = parse_program(db, src)?;
+    let ParsedInner { module, prog, syms, .. } = &*parsed.0;
+    let index = prog
+        .funcs
+        .iter()
+        .position(|f| syms.resolve(f.name) == func)
+        .ok_or_else(|| format!("no function named `{func}`"))?;
+
+    let is_func = |f: &rv_syntax::ast::FnDecl| syms.resolve(f.name) == func;
+    let mut items = module.items.clone();
+    if items.iter().any(|item| matches!(item, rv_syntax::ast::Item::Fn(f) if is_func(f))) {
+        for item in &mut items {
+            if let rv_syntax::ast::Item::Fn(f) = item {
+                if !is_func(f) {
+                    f.body.stmts.clear();
+                    f.body.spans.clear();
+                }
+            }
+        }
+    }
+    let names: Vec<&str> = prog.funcs.iter().map(|f| syms.resolve(f.name)).collect();
+    let fingerprint = format!("{items:?}{names:?}");
+
+    let types = prog.types.iter().map(|t| (t.name(), t.clone())).collect();
+    Ok(FunctionInput(Arc::new(FunctionInputInner { parsed, index, types, fingerprint })))
+}
+
+/// **Query.** Infer the types of function `func` alone, typing each call by the
+/// callee's [`function_signature_types`]. `Err` for a static type error.
+#[salsa::tracked]
+pub fn infer_function_types(
+    db: &dyn Db,
+    src: SourceProgram,
+    func: String,
+) -> Result<InferredFunction, String> {
+    instrument!(db, "infer_function_types", src, &func);
+    depends!(db, "function_input", src, &func);
+    let input = function_input(db, src, func)?;
+    let FunctionInputInner { parsed, types, .. } = &*input.0;
+    let ParsedInner { prog, syms, lines, .. } = &*parsed.0;
+    let callees = DbSignatures { db, src, syms };
+    let typed = rv_infer::infer_function_types(
+        input.0.func(),
+        types,
+        &prog.trait_impls,
+        &callees,
+        syms,
+        lines,
+    )?;
+    Ok(InferredFunction(Arc::new(typed)))
+}
+
+/// Answers [`CalleeSignatures`] lookups with the [`function_signature_types`] query,
+/// so each callee a caller names becomes a tracked dependency of the caller.
+struct DbSignatures<'a> {
+    db: &'a dyn Db,
+    src: SourceProgram,
+    syms: &'a Symbols,
+}
+
+impl CalleeSignatures for DbSignatures<'_> {
+    fn signature(&self, func: Sym) -> Option<Arc<SigTypes>> {
+        let func = self.syms.resolve(func);
+        depends!(self.db, "function_signature_types", self.src, func);
+        function_signature_types(self.db, self.src, func.to_string())
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -286,10 +720,20 @@ pub fn analyze(db: &dyn salsa::Database, src: SourceProgram) -> AnalysisResult {
 /// salsa-memoized [`AnalysisResult`].
 pub fn compile_source(text: &str) -> AnalysisResult {
     let db = Database::default();
-    let src = SourceProgram::new(&db, text.to_string());
+    let src = SourceProgram::new(&db, text.to_string(), CfgSet::new());
     analyze(&db, src)
 }
 
+/// The inferred types of every function in `text` under `cfg` (see
+/// [`rv_infer::dump_types`]). A debugging view of inference, so it runs the front
+/// end directly rather than through the memoized queries.
+pub fn dump_types(text: &str, cfg: &CfgSet) -> Result<String, String> {
+    let mut syms = Symbols::new();
+    let module = rv_syntax::parse_with_cfg(text, &mut syms, cfg)?;
+    let (prog, lines) = rv_lower::lower_with_lines(&module, &mut syms)?;
+    rv_infer::dump_types(&prog, &syms, &lines)
+}
+
 /// Like [`compile_source`], but if the program verifies clean (all solver
 /// obligations discharged and no borrow errors) and `entry` is `Some`, also
 /// compile to bytecode and run that entry point.
@@ -299,9 +743,52 @@ pub fn compile_source(text: &str) -> AnalysisResult {
 /// leaf the driver wants on demand. We reuse the memoized [`elaborate`] result so
 /// no front-end work is repeated.
 pub fn compile_and_run(text: &str, entry: Option<&str>) -> (AnalysisResult, Option<Result<rv_vm::Value, String>>) {
+    compile_and_run_with_cfg(text, &CfgSet::new(), entry)
+}
+
+/// [`compile_and_run`] under the cfg options `cfg`.
+pub fn compile_and_run_with_cfg(
+    text: &str,
+    cfg: &CfgSet,
+    entry: Option<&str>,
+) -> (AnalysisResult, Option<Result<rv_vm::Value, String>>) {
+    compile_and_run_with_options(text, cfg, &CodegenOptions::default(), entry)
+}
+
+/// [`compile_and_run_with_cfg`], compiling the entry with the codegen options
+/// `opts`. They only affect the bytecode, so they are not part of any query.
+pub fn compile_and_run_with_options(
+    text: &str,
+    cfg: &CfgSet,
+    opts: &CodegenOptions,
+    entry: Option<&str>,
+) -> (AnalysisResult, Option<Result<rv_vm::Value, String>>) {
+    compile_and_run_in(&Database::default(), text, cfg, opts, entry)
+}
+
+/// [`compile_and_run_with_options`], also returning the query log of the compile
+/// and the dependency edges it recorded.
+#[cfg(feature = "query-log")]
+pub fn compile_and_run_logged(
+    text: &str,
+    cfg: &CfgSet,
+    opts: &CodegenOptions,
+    entry: Option<&str>,
+) -> (AnalysisResult, Option<Result<rv_vm::Value, String>>, Vec<QueryEvent>, QueryGraph) {
     let db = Database::default();
-    let src = SourceProgram::new(&db, text.to_string());
-    let analysis = analyze(&db, src);
+    let (analysis, run) = compile_and_run_in(&db, text, cfg, opts, entry);
+    (analysis, run, db.query_log(), db.query_graph())
+}
+
+fn compile_and_run_in(
+    db: &Database,
+    text: &str,
+    cfg: &CfgSet,
+    opts: &CodegenOptions,
+    entry: Option<&str>,
+) -> (AnalysisResult, Option<Result<rv_vm::Value, String>>) {
+    let src = SourceProgram::new(db, text.to_string(), cfg.clone());
+    let analysis = analyze(db, src);
 
     let run = match (entry, &analysis) {
         // Execution is a continuation of successful checking, not a separate
@@ -309,10 +796,12 @@ pub fn compile_and_run(text: &str, entry: Option<&str>) -> (AnalysisResult, Opti
         // prevent bytecode from being emitted and run.
         (Some(e), AnalysisResult::Analyzed(a)) if a.all_verified => {
             // Reuse the memoized elaboration (no re-parse/-lower/-elaborate).
-            let elaborated = elaborate(&db, src).expect("analyze already proved front-end ok");
-            let ElaboratedInner { elaborated, syms } = &*elaborated.0;
-            let bytecode = rv_codegen::compile(&elaborated.prog, syms);
-            Some(rv_vm::run(&bytecode, e, &[]))
+            let elaborated = elaborate(db, src).expect("analyze already proved front-end ok");
+            let ElaboratedInner { elaborated, syms, .. } = &*elaborated.0;
+            let bytecode = rv_codegen::compile_with(&elaborated.prog, syms, opts);
+            // Isolated, so a program that never halts cannot hang the caller.
+            let result = rv_vm::run_isolated(&bytecode, e, &[], rv_vm::DEFAULT_RUN_TIMEOUT);
+            Some(result.map_err(|err| err.to_string()))
         }
         _ => None,
     };
@@ -0,0 +1,288 @@
+//! Opt-in query instrumentation (the `query-log` feature).
+//!
+//! Every tracked query records one [`QueryEvent`] per run: an *execution* (with
+//! its wall-clock duration) from a timer the query body starts on entry, or a
+//! *cache hit* from salsa's `DidValidateMemoizedValue` event, fired when a memo
+//! from an older revision is re-validated instead of recomputed. The events land
+//! in a bounded ring buffer on the [`Database`](crate::Database), so the log of a
+//! long session keeps only the most recent [`QUERY_LOG_CAPACITY`] entries.
+//!
+//! The buffer outlives any one query, so a panic while its lock is held (an ICE
+//! in a query body dropping its [`Timer`]) must not take the log down with it:
+//! every access goes through [`lock`], which recovers a poisoned lock instead of
+//! failing every later query, and logs a warning.
+//!
+//! Each execution also records what it read: the queries it called and the
+//! [`SourceProgram`] input, declared by the query body with `depends!`. The
+//! edges of a query's latest execution make up the [`QueryGraph`], from which
+//! [`explain_rebuild`] traces every query a compile ran back to the input
+//! change that caused it.
+//!
+//! Without the feature, none of this module is compiled and the queries carry
+//! no hooks at all.
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use salsa::plumbing::AsId;
+
+use crate::{Db, SourceProgram};
+
+/// How many events the ring buffer keeps before dropping the oldest.
+pub const QUERY_LOG_CAPACITY: usize = 1024;
+
+/// Whether a query ran its body or was served from the memo table.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum QueryEventKind {
+    Executed,
+    CacheHit,
+}
+
+/// One recorded query run.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct QueryEvent {
+    /// The tracked function's name (`elaborate`, `analyze`, ...).
+    pub query: String,
+    /// The key the query ran on: the `Debug` form of the input's salsa id.
+    pub key: String,
+    /// The function a per-function query ran for. `None` for a whole-program
+    /// query, and for a cache hit, whose salsa key does not say.
+    pub func: Option<String>,
+    pub kind: QueryEventKind,
+    /// Time spent in the query body, including the queries it called. `None` for
+    /// a cache hit.
+    pub duration: Option<Duration>,
+}
+
+impl fmt::Display for QueryEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.func {
+            Some(func) => write!(f, "{}({}, {func})", self.query, self.key)?,
+            None => write!(f, "{}({})", self.query, self.key)?,
+        }
+        match (self.kind, self.duration) {
+            (QueryEventKind::Executed, Some(d)) => write!(f, " executed in {d:?}"),
+            (QueryEventKind::Executed, None) => write!(f, " executed"),
+            (QueryEventKind::CacheHit, _) => write!(f, " cache hit"),
+        }
+    }
+}
+
+/// One query on one key, or (with `query` [`INPUT`]) the input itself: a node
+/// of the [`QueryGraph`].
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct QueryNode {
+    pub query: String,
+    pub key: String,
+    pub func: Option<String>,
+}
+
+/// The [`QueryNode::query`] of the [`SourceProgram`] input.
+pub const INPUT: &str = "SourceProgram";
+
+impl QueryNode {
+    pub fn new(query: &str, src: SourceProgram, func: Option<&str>) -> Self {
+        QueryNode { query: query.to_string(), key: key_of(src), func: func.map(str::to_string) }
+    }
+
+    fn of(event: &QueryEvent) -> Self {
+        QueryNode { query: event.query.clone(), key: event.key.clone(), func: event.func.clone() }
+    }
+}
+
+/// `query` or `query(func)`: a node as a rebuild chain names it, within its key's
+/// group.
+impl fmt::Display for QueryNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.func {
+            Some(func) => write!(f, "{}({func})", self.query),
+            None => f.write_str(&self.query),
+        }
+    }
+}
+
+/// What each query read in its latest execution, in the order it read it.
+pub type QueryGraph = HashMap<QueryNode, Vec<QueryNode>>;
+
+/// The ring buffer behind [`Database::query_log`](crate::Database::query_log),
+/// and the dependency edges behind [`Database::query_graph`](crate::Database::query_graph).
+#[derive(Debug, Default)]
+pub struct QueryLog {
+    events: VecDeque<QueryEvent>,
+    /// Unlike the events, kept across [`QueryLog::clear`]: a query that did not
+    /// run since still read what it read.
+    graph: QueryGraph,
+    /// The queries executing now, innermost last.
+    running: Vec<QueryNode>,
+}
+
+impl QueryLog {
+    fn push(&mut self, event: QueryEvent) {
+        if self.events.len() == QUERY_LOG_CAPACITY {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+
+    /// The recorded events, oldest first.
+    pub fn events(&self) -> Vec<QueryEvent> {
+        self.events.iter().cloned().collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    pub fn graph(&self) -> QueryGraph {
+        self.graph.clone()
+    }
+
+    /// Restore the buffer's one invariant after a panic may have interrupted an
+    /// update: it never holds more than [`QUERY_LOG_CAPACITY`] events. Returns how
+    /// many of the oldest events were dropped to get there.
+    fn repair(&mut self) -> usize {
+        let excess = self.events.len().saturating_sub(QUERY_LOG_CAPACITY);
+        self.events.drain(..excess);
+        excess
+    }
+
+    /// Record a cache hit if `event` reports a re-validated memo. Executions are
+    /// recorded by [`Timer`] instead, which also knows how long they took.
+    pub(crate) fn observe(&mut self, event: &salsa::Event) {
+        if let salsa::EventKind::DidValidateMemoizedValue { database_key } = &event.kind {
+            // The key index's `Debug` form names the query while a database is
+            // attached (`elaborate(Id(0))`); fall back to the whole string.
+            let debug = format!("{database_key:?}");
+            let query = debug.split_once('(').map_or(debug.as_str(), |(q, _)| q).to_string();
+            self.push(QueryEvent {
+                query,
+                key: format!("{:?}", database_key.key_index()),
+                func: None,
+                kind: QueryEventKind::CacheHit,
+                duration: None,
+            });
+        }
+    }
+}
+
+/// Lock the log behind `sink`. A lock poisoned by a panicking holder is taken
+/// over rather than reported: the events recorded so far are kept, the buffer is
+/// repaired, and the poison is cleared so later callers lock it normally.
+pub(crate) fn lock(sink: &Mutex<QueryLog>) -> MutexGuard<'_, QueryLog> {
+    sink.lock().unwrap_or_else(|poisoned| {
+        let mut log = poisoned.into_inner();
+        let dropped = log.repair();
+        // A panic unwound every query that was running.
+        log.running.clear();
+        sink.clear_poison();
+        tracing::warn!(kept = log.events.len(), dropped, "recovered the poisoned query log");
+        log
+    })
+}
+
+/// Times one execution of a tracked query, recording it when dropped (so every
+/// early return is covered). While it runs, the query is the one `depends!`
+/// records reads for.
+pub(crate) struct Timer<'db> {
+    db: &'db dyn Db,
+    node: QueryNode,
+    start: Instant,
+}
+
+impl<'db> Timer<'db> {
+    pub(crate) fn start(
+        db: &'db dyn Db,
+        query: &'static str,
+        src: SourceProgram,
+        func: Option<&str>,
+    ) -> Self {
+        let node = QueryNode::new(query, src, func);
+        let mut log = lock(db.query_log_sink());
+        log.graph.insert(node.clone(), Vec::new());
+        log.running.push(node.clone());
+        Timer { db, node, start: Instant::now() }
+    }
+}
+
+impl Drop for Timer<'_> {
+    fn drop(&mut self) {
+        let QueryNode { query, key, func } = self.node.clone();
+        let event = QueryEvent {
+            query,
+            key,
+            func,
+            kind: QueryEventKind::Executed,
+            duration: Some(self.start.elapsed()),
+        };
+        let mut log = lock(self.db.query_log_sink());
+        if log.running.last() == Some(&self.node) {
+            log.running.pop();
+        }
+        log.push(event);
+    }
+}
+
+/// Record that the innermost running query read `dep`.
+pub(crate) fn depends(db: &dyn Db, dep: QueryNode) {
+    let mut log = lock(db.query_log_sink());
+    let Some(reader) = log.running.last().cloned() else { return };
+    let deps = log.graph.entry(reader).or_default();
+    if !deps.contains(&dep) {
+        deps.push(dep);
+    }
+}
+
+/// Why each query `events` executed, grouped by key: the chain of reads that
+/// leads from the input to it, as `SourceProgram -> parse_and_lower -> elaborate`.
+/// A query read by another that also executed is shown only within that one's
+/// chain. With `func`, only the queries run for that function are traced.
+///
+/// A query's cause is the first thing it read that may have changed: the
+/// input, or a query that executed too (its value may still have compared equal,
+/// which salsa does not report). A query with no such read ran for the first time.
+pub fn explain_rebuild(events: &[QueryEvent], graph: &QueryGraph, func: Option<&str>) -> String {
+    let mut executed: Vec<QueryNode> = Vec::new();
+    for e in events.iter().filter(|e| e.kind == QueryEventKind::Executed) {
+        let node = QueryNode::of(e);
+        if !executed.contains(&node) {
+            executed.push(node);
+        }
+    }
+    let changed: HashSet<&QueryNode> = executed.iter().collect();
+    let cause = |node: &QueryNode| {
+        let deps = graph.get(node).map_or(&[][..], Vec::as_slice);
+        deps.iter().find(|d| d.query == INPUT || changed.contains(d))
+    };
+    let traced = |node: &&QueryNode| func.is_none() || node.func.as_deref() == func;
+    // The queries another traced query's chain already passes through.
+    let covered: HashSet<&QueryNode> = executed.iter().filter(traced).filter_map(cause).collect();
+
+    let mut groups: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for node in executed.iter().filter(traced).filter(|n| !covered.contains(n)) {
+        let mut chain = vec![node];
+        let mut seen = HashSet::from([node]);
+        while let Some(dep) = cause(chain[chain.len() - 1]) {
+            if !seen.insert(dep) {
+                break;
+            }
+            chain.push(dep);
+        }
+        let steps: Vec<String> = chain.iter().rev().map(|n| n.to_string()).collect();
+        groups.entry(&node.key).or_default().push(steps.join(" -> "));
+    }
+    let mut out = String::new();
+    for (key, chains) in groups {
+        out += &format!("{key}:\n");
+        for chain in chains {
+            out += &format!("  {chain}\n");
+        }
+    }
+    out
+}
+
+/// The [`QueryEvent::key`] queries on `src` are logged under.
+pub fn key_of(src: SourceProgram) -> String {
+    format!("{:?}", src.as_id())
+}
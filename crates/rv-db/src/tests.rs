@@ -12,7 +12,7 @@ const RECIP_BAD: &str = include_str!("fixtures/tests_recip_bad.rv");
 #[test]
 fn recompute_after_mutation() {
     let mut db = Database::default();
-    let src = SourceProgram::new(&db, RECIP_OK.to_string());
+    let src = SourceProgram::new(&db, RECIP_OK.to_string(), CfgSet::new());
 
     let before = analyze(&db, src);
     assert!(matches!(&before, AnalysisResult::Analyzed(a) if a.all_verified));
@@ -36,7 +36,7 @@ fn recompute_after_mutation() {
 fn unchanged_input_is_not_recomputed() {
     let log = Arc::new(Mutex::new(Vec::<String>::new()));
     let db = Database::with_logger(log.clone());
-    let src = SourceProgram::new(&db, RECIP_OK.to_string());
+    let src = SourceProgram::new(&db, RECIP_OK.to_string(), CfgSet::new());
 
     // First run: the three tracked queries execute.
     let _ = analyze(&db, src);
@@ -50,6 +50,59 @@ fn unchanged_input_is_not_recomputed() {
     assert_eq!(second_executions, 0, "re-running with unchanged input must be fully memoized");
 }
 
+/// Edits made through `Database::edit` are replayed on the previous parse; every
+/// step analyzes exactly as the edited text does from scratch, including after the
+/// text was set outright and after an edit that leaves it unparseable.
+#[test]
+fn edits_analyze_like_the_edited_text() {
+    let mut db = Database::default();
+    let text = "fn two() -> i64 { return 2; }\nfn main() -> i64 { return two(); }\n";
+    let src = SourceProgram::new(&db, text.to_string(), CfgSet::new());
+    assert!(matches!(analyze(&db, src), AnalysisResult::Analyzed(a) if a.all_verified));
+
+    let edit = |db: &mut Database, at: &str, len: usize, with: &str| {
+        let start = src.text(db).find(at).expect("the edit's anchor is in the text");
+        let range = start..start + len;
+        db.edit(src, TextEdit { range, text: with.to_string() }).expect("the edit is in range");
+        let (result, text) = (analyze(db, src), src.text(db).clone());
+        assert_eq!(result, compile_source(&text), "after editing to {text:?}");
+        let files = db.parsed_files.lock().unwrap();
+        assert!(files.edits.is_empty());
+        result
+    };
+    // Inside a body, then two edits between runs, then one that breaks the parse.
+    edit(&mut db, "2;", 1, "7");
+    db.edit(src, TextEdit { range: 0..0, text: "\n".to_string() }).unwrap();
+    let fixed = edit(&mut db, "two();", 6, "two() / 0;");
+    assert!(matches!(fixed, AnalysisResult::Analyzed(a) if !a.all_verified));
+    assert!(matches!(edit(&mut db, "{ return 7", 1, ""), AnalysisResult::FrontendError(_)));
+    edit(&mut db, "i64  return 7", 0, "{");
+    src.set_text(&mut db).to(text.replace('2', "3"));
+    edit(&mut db, "3;", 1, "4");
+    assert_eq!(db.parsed_files.lock().unwrap().last[&src].src, *src.text(&db));
+
+    let out_of_range = TextEdit { range: 0..text.len() * 2, text: String::new() };
+    assert!(db.edit(src, out_of_range).is_err());
+}
+
+/// The cfg options are part of the input: changing them re-parses, and an item
+/// they rule out is gone from the program.
+#[test]
+fn changing_the_cfg_recomputes() {
+    let mut db = Database::default();
+    let text = "#[cfg(test)] fn helper() -> i64 { return 1; } fn main() -> i64 { return helper(); }";
+    let mut cfg = CfgSet::new();
+    cfg.enable("test");
+    let src = SourceProgram::new(&db, text.to_string(), cfg);
+    assert!(matches!(analyze(&db, src), AnalysisResult::Analyzed(a) if a.all_verified));
+
+    src.set_cfg(&mut db).to(CfgSet::new());
+    match analyze(&db, src) {
+        AnalysisResult::FrontendError(e) => assert!(e.contains("[E0001]"), "got: {e}"),
+        other => panic!("expected an unresolved call, got {other:?}"),
+    }
+}
+
 /// The `compile_source` convenience entry behaves like the old verify path.
 #[test]
 fn compile_source_entry() {
@@ -157,3 +210,227 @@ fn compile_and_run_refuses_unverified_program() {
     assert!(matches!(analysis, AnalysisResult::Analyzed(a) if !a.all_verified));
     assert_eq!(run, None);
 }
+
+/// The query log turns the incrementality guarantee into an assertion about
+/// individual queries: after editing one of two sources in the same database,
+/// `elaborate` re-executes for the edited source, while everything on its
+/// neighbor is a re-validated cache hit.
+#[cfg(feature = "query-log")]
+#[test]
+fn query_log_pinpoints_the_edited_source() {
+    use query_log::{key_of, QueryEventKind};
+
+    let mut db = Database::default();
+    let edited = SourceProgram::new(&db, RECIP_OK.to_string(), CfgSet::new());
+    let neighbor_text = include_str!("fixtures/tests_src1.rv").to_string();
+    let neighbor = SourceProgram::new(&db, neighbor_text, CfgSet::new());
+    let _ = analyze(&db, edited);
+    let _ = analyze(&db, neighbor);
+    let first = db.query_log();
+    assert!(first.iter().all(|e| e.kind == QueryEventKind::Executed), "{first:#?}");
+    assert!(first.iter().all(|e| e.duration.is_some()));
+
+    db.clear_query_log();
+    edited.set_text(&mut db).to(RECIP_BAD.to_string());
+    let _ = analyze(&db, edited);
+    let _ = analyze(&db, neighbor);
+    let log = db.query_log();
+    let ran = |key: &str, kind| log.iter().any(|e| e.key == key && e.kind == kind);
+
+    let (edited, neighbor) = (key_of(edited), key_of(neighbor));
+    assert!(
+        log.iter().any(|e| e.query == "elaborate"
+            && e.key == edited
+            && e.kind == QueryEventKind::Executed),
+        "elaborate must re-run for the edited source: {log:#?}"
+    );
+    assert!(ran(&neighbor, QueryEventKind::CacheHit), "{log:#?}");
+    assert!(!ran(&neighbor, QueryEventKind::Executed), "the neighbor must not re-run: {log:#?}");
+}
+
+/// The log is a ring buffer: past its capacity, the oldest entries go first.
+#[cfg(feature = "query-log")]
+#[test]
+fn query_log_keeps_only_the_most_recent_events() {
+    let db = Database::default();
+    // Three queries run per source; enough sources to overflow by more than one.
+    let sources: Vec<_> = (0..query_log::QUERY_LOG_CAPACITY / 3 + 2)
+        .map(|i| {
+            SourceProgram::new(&db, format!("fn f{i}() -> i64 {{ return {i}; }}"), CfgSet::new())
+        })
+        .collect();
+    for &src in &sources {
+        let _ = analyze(&db, src);
+    }
+    let log = db.query_log();
+    assert_eq!(log.len(), query_log::QUERY_LOG_CAPACITY);
+    let first = query_log::key_of(sources[0]);
+    assert!(log.iter().all(|e| e.key != first));
+    let last = query_log::key_of(*sources.last().unwrap());
+    assert_eq!(log.last().map(|e| (e.query.as_str(), &e.key)), Some(("analyze", &last)));
+}
+
+/// A panic while the query log's lock is held poisons it; the database recovers
+/// the log (keeping what it had recorded) instead of failing every later query.
+#[cfg(feature = "query-log")]
+#[test]
+fn poisoned_query_log_is_recovered() {
+    let db = Database::default();
+    let first = SourceProgram::new(&db, RECIP_OK.to_string(), CfgSet::new());
+    let _ = analyze(&db, first);
+    let recorded = db.query_log();
+    assert!(!recorded.is_empty());
+
+    let sink = db.query_log.clone();
+    let panicked = std::thread::spawn(move || {
+        let _guard = sink.lock().unwrap();
+        panic!("internal compiler error while the log is locked");
+    })
+    .join();
+    assert!(panicked.is_err() && db.query_log.is_poisoned());
+
+    let second = SourceProgram::new(&db, RECIP_BAD.to_string(), CfgSet::new());
+    assert!(matches!(analyze(&db, second), AnalysisResult::Analyzed(a) if !a.all_verified));
+    assert!(!db.query_log.is_poisoned());
+    let log = db.query_log();
+    assert_eq!(log[..recorded.len()], recorded[..], "events before the panic are kept");
+    let second = query_log::key_of(second);
+    assert!(log[recorded.len()..].iter().any(|e| e.query == "analyze" && e.key == second));
+}
+
+/// A caller's inference depends on its callees' signatures only: editing a
+/// callee's body re-derives the callee's signature (which compares equal) but does
+/// not re-run inference for the caller; changing the signature does.
+#[cfg(feature = "query-log")]
+#[test]
+fn callee_body_edits_do_not_rerun_caller_inference() {
+    use query_log::QueryEventKind;
+
+    let program = |ret: &str, body: &str| {
+        format!(
+            "fn callee(x: i64) -> {ret} {{ {body} }}\n\
+             fn caller() -> i64 {{ return callee(1); }}\n"
+        )
+    };
+    let executed = |db: &Database, query: &str| {
+        db.query_log().iter().any(|e| e.query == query && e.kind == QueryEventKind::Executed)
+    };
+    let mut db = Database::default();
+    let src = SourceProgram::new(&db, program("i64", "return x;"), CfgSet::new());
+    assert!(infer_function_types(&db, src, "caller".to_string()).is_ok());
+
+    db.clear_query_log();
+    src.set_text(&mut db).to(program("i64", "return 7;"));
+    assert!(infer_function_types(&db, src, "caller".to_string()).is_ok());
+    assert!(executed(&db, "function_signature_types"), "{:#?}", db.query_log());
+    assert!(!executed(&db, "infer_function_types"), "caller re-ran: {:#?}", db.query_log());
+
+    db.clear_query_log();
+    src.set_text(&mut db).to(program("bool", "return true;"));
+    match infer_function_types(&db, src, "caller".to_string()) {
+        Err(e) => assert!(e.contains("[E0308]"), "got: {e}"),
+        Ok(_) => panic!("a `bool` callee cannot feed an `i64` return"),
+    }
+    assert!(executed(&db, "infer_function_types"), "{:#?}", db.query_log());
+}
+
+/// `analyze` types the program one function at a time: an edit to one body, even
+/// one that introduces a new name, re-infers that function and none of its callers.
+#[cfg(feature = "query-log")]
+#[test]
+fn analyze_reinfers_only_the_edited_function() {
+    use query_log::QueryEventKind;
+
+    let program = |body: &str| {
+        format!(
+            "fn edited() -> i64 {{ {body} }}\n\
+             fn caller() -> i64 {{ let total = edited(); return total; }}\n"
+        )
+    };
+    let mut db = Database::default();
+    let src = SourceProgram::new(&db, program("return 1;"), CfgSet::new());
+    assert!(matches!(analyze(&db, src), AnalysisResult::Analyzed(a) if a.all_verified));
+
+    db.clear_query_log();
+    src.set_text(&mut db).to(program("let fresh = 2; return fresh;"));
+    assert!(matches!(analyze(&db, src), AnalysisResult::Analyzed(a) if a.all_verified));
+    let inferred = |func: &str| {
+        db.query_log().iter().any(|e| {
+            e.query == "infer_function_types"
+                && e.func.as_deref() == Some(func)
+                && e.kind == QueryEventKind::Executed
+        })
+    };
+    assert!(inferred("edited"), "{}", db.explain_rebuild(None));
+    assert!(!inferred("caller"), "{}", db.explain_rebuild(None));
+}
+
+/// The rebuild explanation traces an edited function's re-inference back to the
+/// source through exactly the queries that carried the edit, and names no query
+/// the untouched neighbor re-ran beyond re-deriving its input.
+#[cfg(feature = "query-log")]
+#[test]
+fn explain_rebuild_traces_the_edited_function() {
+    let program = |body: &str| {
+        format!(
+            "fn edited() -> i64 {{ {body} }}\n\
+             fn neighbor() -> i64 {{ return 2; }}\n"
+        )
+    };
+    let infer_both = |db: &Database, src| {
+        for func in ["edited", "neighbor"] {
+            assert!(infer_function_types(db, src, func.to_string()).is_ok());
+        }
+    };
+    let mut db = Database::default();
+    let src = SourceProgram::new(&db, program("return 1;"), CfgSet::new());
+    infer_both(&db, src);
+    let key = query_log::key_of(src);
+    assert_eq!(
+        db.explain_rebuild(None),
+        format!(
+            "{key}:\n  SourceProgram -> parse_program -> function_input(edited) -> infer_function_types(edited)\n  \
+             SourceProgram -> parse_program -> function_input(neighbor) -> infer_function_types(neighbor)\n"
+        )
+    );
+
+    db.clear_query_log();
+    src.set_text(&mut db).to(program("return 3;"));
+    infer_both(&db, src);
+    assert_eq!(
+        db.explain_rebuild(Some("edited")),
+        format!("{key}:\n  SourceProgram -> parse_program -> function_input(edited) -> infer_function_types(edited)\n")
+    );
+    // The neighbor's input is re-derived from the new text, but compares equal, so
+    // its inference is not re-run.
+    assert_eq!(
+        db.explain_rebuild(Some("neighbor")),
+        format!("{key}:\n  SourceProgram -> parse_program -> function_input(neighbor)\n")
+    );
+    assert!(!db.explain_rebuild(None).contains("infer_function_types(neighbor)"));
+}
+
+/// Whole-program queries chain through the queries they call, and a caller's
+/// inference records the callee signatures it read.
+#[cfg(feature = "query-log")]
+#[test]
+fn query_graph_records_what_each_query_read() {
+    let db = Database::default();
+    let text = "fn callee() -> i64 { return 1; }\nfn caller() -> i64 { return callee(); }";
+    let src = SourceProgram::new(&db, text.to_string(), CfgSet::new());
+    let _ = analyze(&db, src);
+    assert!(infer_function_types(&db, src, "caller".to_string()).is_ok());
+    let key = query_log::key_of(src);
+    let whole = db.explain_rebuild(None);
+    assert!(
+        whole.contains("  SourceProgram -> parse_program -> parse_and_lower -> elaborate -> analyze\n"),
+        "{whole}"
+    );
+    let node = |query: &str, func| query_log::QueryNode::new(query, src, func);
+    let reads = &db.query_graph()[&node("infer_function_types", Some("caller"))];
+    assert_eq!(
+        reads[..],
+        [node("function_input", Some("caller")), node("function_signature_types", Some("callee"))]
+    );
+    assert!(whole.starts_with(&format!("{key}:\n")));
+}
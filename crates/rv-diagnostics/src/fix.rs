@@ -0,0 +1,235 @@
+//! Quick fixes: edits that make a diagnostic go away, recovered from its text.
+//!
+//! A fix is derived from the rendered message and the source it was reported
+//! against, so every producer that already writes `; did you mean `x`?` or
+//! "add a `_` arm" gets a machine-applicable fix for free. Edits are located by
+//! searching the diagnostic's line (or the whole file, when it names none) for
+//! the text the message quotes.
+
+use crate::{edit_distance, Diagnostic};
+
+/// Replace the `char` columns `start..end` (0-based) of the 1-based `line` with
+/// `text`. An empty range inserts.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Edit {
+    pub line: u32,
+    pub start: u32,
+    pub end: u32,
+    pub text: String,
+}
+
+/// A named set of non-overlapping edits that resolves one diagnostic.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct QuickFix {
+    pub title: String,
+    pub edits: Vec<Edit>,
+}
+
+impl QuickFix {
+    /// `source` with every edit applied.
+    pub fn apply(&self, source: &str) -> String {
+        let mut lines: Vec<String> = source.split_inclusive('\n').map(str::to_string).collect();
+        let mut edits: Vec<&Edit> = self.edits.iter().collect();
+        // Right to left, so an edit never shifts the columns of one still to come.
+        edits.sort_by_key(|e| std::cmp::Reverse((e.line, e.start)));
+        for e in edits {
+            let Some(line) = lines.get_mut(e.line as usize - 1) else { continue };
+            let mut chars: Vec<char> = line.chars().collect();
+            chars.splice(e.start as usize..e.end as usize, e.text.chars());
+            *line = chars.into_iter().collect();
+        }
+        lines.concat()
+    }
+}
+
+impl Diagnostic {
+    /// The fixes for this diagnostic in `source`, the text it was reported
+    /// against: one per suggested name, and a `_` arm for a non-exhaustive match.
+    pub fn fixes(&self, source: &str) -> Vec<QuickFix> {
+        let mut fixes = self.renames(source);
+        if self.code == Some("E0004") && self.message.contains("add a `_` arm") {
+            fixes.extend(self.line.and_then(|l| wildcard_arm(source, l)));
+        }
+        fixes
+    }
+
+    /// For `... `wrong` ...; did you mean `a`?`, replace `wrong` with each
+    /// suggestion. `wrong` is the quoted name closest to the suggestions; when it
+    /// is one segment of a quoted `A::wrong` path, only that path's uses change.
+    fn renames(&self, source: &str) -> Vec<QuickFix> {
+        let Some((head, tail)) = self.message.split_once("; did you mean ") else {
+            return Vec::new();
+        };
+        let (quoted, suggested) = (backquoted(head), backquoted(tail));
+        let Some(first) = suggested.first() else { return Vec::new() };
+        let Some(wrong) = quoted.iter().min_by_key(|q| edit_distance(q, first)) else {
+            return Vec::new();
+        };
+        let path = quoted.iter().find(|q| q.contains("::") && q.split("::").any(|s| s == *wrong));
+        let target = path.unwrap_or(wrong);
+        let sites = occurrences(source, target, self.line);
+        if sites.is_empty() {
+            return Vec::new();
+        }
+        suggested
+            .iter()
+            .map(|s| {
+                let with = match path {
+                    Some(p) => {
+                        let segs: Vec<&str> =
+                            p.split("::").map(|seg| if seg == *wrong { s } else { seg }).collect();
+                        segs.join("::")
+                    }
+                    None => s.to_string(),
+                };
+                let edits = sites
+                    .iter()
+                    .map(|&(line, start)| Edit {
+                        line,
+                        start,
+                        end: start + target.chars().count() as u32,
+                        text: with.clone(),
+                    })
+                    .collect();
+                QuickFix { title: format!("replace `{target}` with `{with}`"), edits }
+            })
+            .collect()
+    }
+}
+
+/// The texts between pairs of backquotes in `s`.
+fn backquoted(s: &str) -> Vec<&str> {
+    s.split('`').skip(1).step_by(2).collect()
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// The `(line, column)` of each whole-token occurrence of `name`: on the first
+/// line from `from` on that has any, or anywhere when `from` is unknown.
+fn occurrences(source: &str, name: &str, from: Option<u32>) -> Vec<(u32, u32)> {
+    let name: Vec<char> = name.chars().collect();
+    let mut found = Vec::new();
+    for (i, text) in source.lines().enumerate() {
+        let line = i as u32 + 1;
+        if from.is_some_and(|f| line < f) {
+            continue;
+        }
+        let chars: Vec<char> = text.chars().collect();
+        for start in 0..chars.len() {
+            let end = start + name.len();
+            if chars.get(start..end) == Some(&name[..])
+                && (start == 0 || !is_ident(chars[start - 1]))
+                && chars.get(end).is_none_or(|c| !is_ident(*c))
+            {
+                found.push((line, start as u32));
+            }
+        }
+        if from.is_some() && !found.is_empty() {
+            break;
+        }
+    }
+    found
+}
+
+/// Insert `_ => {}` before the closing brace of the first `match` from `line`
+/// on: on a line of its own, indented like the arms, or inline when the match
+/// closes on a line with other text.
+fn wildcard_arm(source: &str, line: u32) -> Option<QuickFix> {
+    let lines: Vec<Vec<char>> = source.lines().map(|l| l.chars().collect()).collect();
+    let mut l = line as usize - 1;
+    let word: Vec<char> = "match".chars().collect();
+    // Find the `match` keyword, then the `{` opening its arms.
+    let mut c = loop {
+        let text = lines.get(l)?;
+        let hit = (0..text.len()).find(|&i| {
+            text.get(i..i + word.len()) == Some(&word[..])
+                && (i == 0 || !is_ident(text[i - 1]))
+                && text.get(i + word.len()).is_none_or(|c| !is_ident(*c))
+        });
+        if let Some(i) = hit {
+            break i + word.len();
+        }
+        l += 1;
+    };
+    let (open_line, mut depth, mut in_str) = (l, 0usize, false);
+    loop {
+        let text = lines.get(l)?;
+        while c < text.len() {
+            match text[c] {
+                '\\' if in_str => c += 1,
+                '"' => in_str = !in_str,
+                '/' if !in_str && text.get(c + 1) == Some(&'/') => break,
+                '{' if !in_str => depth += 1,
+                '}' if !in_str => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(arm_before(&lines, open_line, l, c));
+                    }
+                }
+                _ => {}
+            }
+            c += 1;
+        }
+        (l, c) = (l + 1, 0);
+    }
+}
+
+fn arm_before(lines: &[Vec<char>], open: usize, close: usize, col: usize) -> QuickFix {
+    let title = "add a `_ => {}` arm".to_string();
+    let indent = |l: &[char]| -> String { l.iter().take_while(|c| c.is_whitespace()).collect() };
+    let edit = if lines[close][..col].iter().all(|c| c.is_whitespace()) {
+        // Indent like the first arm, or one level in from the brace.
+        let arm = lines[open + 1..close].iter().find(|l| l.iter().any(|c| !c.is_whitespace()));
+        let pad = arm.map_or_else(|| format!("{}    ", indent(&lines[close])), |a| indent(a));
+        Edit { line: close as u32 + 1, start: 0, end: 0, text: format!("{pad}_ => {{}}\n") }
+    } else {
+        let space = if lines[close][col - 1].is_whitespace() { "" } else { " " };
+        let at = col as u32;
+        Edit { line: close as u32 + 1, start: at, end: at, text: format!("{space}_ => {{}} ") }
+    };
+    QuickFix { title, edits: vec![edit] }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Diagnostic;
+
+    fn fixed(err: &str, src: &str) -> Vec<String> {
+        Diagnostic::parse(err).fixes(src).iter().map(|f| f.apply(src)).collect()
+    }
+
+    #[test]
+    fn a_suggestion_renames_the_quoted_name() {
+        let src = "fn f() -> i64 {\n    let total = 1;\n    totl + totl\n}\n";
+        let err = "line 3: [E0001] use of unbound variable `totl`; did you mean `total`?";
+        let fixes = Diagnostic::parse(err).fixes(src);
+        assert_eq!(fixes.len(), 1);
+        assert_eq!(fixes[0].title, "replace `totl` with `total`");
+        assert_eq!(fixes[0].apply(src), src.replace("totl + totl", "total + total"));
+    }
+
+    #[test]
+    fn a_path_suggestion_renames_one_segment() {
+        let src = "fn f() -> Shape { Shape::Circel }\nfn Circel() {}\n";
+        let err = "[E0433] failed to resolve `Shape::Circel`: no variant or associated \
+                   function `Circel` in `Shape`; did you mean one of `Circle`, `Cercle`?";
+        let got = fixed(err, src);
+        assert_eq!(got.len(), 2);
+        assert_eq!(got[0], "fn f() -> Shape { Shape::Circle }\nfn Circel() {}\n");
+        assert!(got[1].contains("Shape::Cercle"));
+        assert!(fixed("line 1: [E0001] use of unbound variable `q`", src).is_empty());
+    }
+
+    #[test]
+    fn a_wildcard_arm_goes_before_the_closing_brace() {
+        let src = "fn f(s: String) -> i64 {\n    match s {\n        \"a\" => { 1 }\n    }\n}\n";
+        let err = "line 2: [E0004] non-exhaustive string match: add a `_` arm";
+        let want = "        \"a\" => { 1 }\n        _ => {}\n    }\n";
+        assert!(fixed(err, src)[0].contains(want), "{:?}", fixed(err, src));
+        let inline = "fn f(n: i64) { match n { 0 => { \"}\" } } }\n";
+        let err = "line 1: [E0004] non-exhaustive integer match: add a `_` arm";
+        assert_eq!(fixed(err, inline), ["fn f(n: i64) { match n { 0 => { \"}\" } _ => {} } }\n"]);
+    }
+}
@@ -0,0 +1,945 @@
+//! Stable error codes for front-end diagnostics.
+//!
+//! Errors travel through the pipeline as `String`s. A producer tags its message
+//! with a registered code ([`ErrorCode::tag`] renders `[E0308] msg`), and a
+//! consumer recovers the structure with [`Diagnostic::parse`]. Every code lives in
+//! [`REGISTRY`] together with a long-form explanation, which `rvc explain` prints
+//! and which JSON consumers can link to through the code. A code is a `pub const`
+//! named after it, listed once in `registry!`, which builds both [`REGISTRY`] and
+//! [`ALL`] from that list.
+//!
+//! A code's meaning is permanent. To retire one, remove it from the registry and
+//! add it to [`TOMBSTONES`]; never reuse it for a different error.
+//!
+//! Locations are line-granular: a [`LineSpan`] is the range of source lines a
+//! construct covers, recorded by the parser and carried through lowering so a
+//! diagnostic can name where a multi-line statement starts and ends.
+//!
+//! [`Diagnostic::fixes`] turns a diagnostic that suggests a change (a close
+//! match for a misspelled name, a missing `_` arm) into [`QuickFix`]es: edits a
+//! tool can apply to the source.
+
+mod fix;
+
+pub use fix::{Edit, QuickFix};
+
+/// A registered error code and its documentation.
+#[derive(Debug)]
+pub struct ErrorCode {
+    /// The code itself: `E` followed by four digits.
+    pub code: &'static str,
+    /// A one-line summary.
+    pub title: &'static str,
+    /// The long-form explanation, ending with a small example.
+    pub explanation: &'static str,
+}
+
+impl ErrorCode {
+    /// Prefix `msg` with this code: `[E0308] msg`.
+    pub fn tag(&self, msg: impl std::fmt::Display) -> String {
+        format!("[{}] {msg}", self.code)
+    }
+}
+
+pub const E0001: ErrorCode = ErrorCode {
+    code: "E0001",
+    title: "unresolved name",
+    explanation: "\
+A name was used that nothing in scope declares: a variable that was never bound
+by `let` or a parameter, or a struct/enum that the module does not define.
+
+    fn main() -> i64 {
+        return total; // `total` was never declared
+    }",
+};
+
+pub const E0004: ErrorCode = ErrorCode {
+    code: "E0004",
+    title: "non-exhaustive literal match",
+    explanation: "\
+A `match` on a string or float tests its literal arms one after another, and no
+finite set of literals covers every string or float, so it needs a `_` arm to
+fall back on.
+
+    fn rank(s: String) -> i64 {
+        match s {
+            \"gold\" => { return 1; }
+            \"silver\" => { return 2; }
+            _ => { return 3; } // required
+        }
+    }",
+};
+
+pub const E0034: ErrorCode = ErrorCode {
+    code: "E0034",
+    title: "ambiguous method call",
+    explanation: "\
+Two trait impls for the receiver's type both provide a method with the called
+name, and the type has no inherent method of that name to prefer. Name the trait
+in a qualified call to pick one.
+
+    trait Canvas { fn draw(self) -> i64; }
+    trait Plotter { fn draw(self) -> i64; }
+    struct P { x: i64 }
+    impl Canvas for P { fn draw(self) -> i64 { return 1; } }
+    impl Plotter for P { fn draw(self) -> i64 { return 2; } }
+    fn main() -> i64 {
+        let p = P { x: 0 };
+        return Canvas::draw(p); // not `p.draw()`
+    }",
+};
+
+pub const E0046: ErrorCode = ErrorCode {
+    code: "E0046",
+    title: "missing trait method in impl",
+    explanation: "\
+An `impl Trait for Type` block must provide every method the trait declares.
+Traits have no default method bodies, so each one needs an implementation.
+
+    trait Shape { fn area(self) -> i64; fn sides(self) -> i64; }
+    struct Square { w: i64 }
+    impl Shape for Square { // `sides` is missing
+        fn area(self) -> i64 { return self.w * self.w; }
+    }",
+};
+
+pub const E0053: ErrorCode = ErrorCode {
+    code: "E0053",
+    title: "impl method does not match the trait's signature",
+    explanation: "\
+A method in an `impl Trait for Type` block must have the signature the trait
+declares, with `Self` standing for `Type`: a `self` receiver exactly when the
+trait's has one, the same number of parameters with the same types, the same
+return type, and as many type parameters, each with the same trait bounds.
+
+    trait Shape { fn scale(self, by: i64) -> Self; }
+    struct Square { w: i64 }
+    impl Shape for Square {
+        fn scale(self, by: bool) -> Square { // `by` must be an `i64`
+            return self;
+        }
+    }",
+};
+
+pub const E0061: ErrorCode = ErrorCode {
+    code: "E0061",
+    title: "wrong number of arguments",
+    explanation: "\
+A function was called with a different number of arguments than its signature
+declares.
+
+    fn add(a: i64, b: i64) -> i64 { return a + b; }
+    fn main() -> i64 {
+        return add(1); // `add` takes two arguments
+    }",
+};
+
+pub const E0080: ErrorCode = ErrorCode {
+    code: "E0080",
+    title: "integer literal out of range",
+    explanation: "\
+An integer literal does not fit the type its context gives it: the declared type
+of the variable it initializes, the parameter it is passed to, the other operand
+of an operator, or the function's return type. An unannotated integer is an
+`i64`. A leading `-` is part of the literal, so `-128` is a valid `i8`.
+
+    fn main() -> i64 {
+        let small: u8 = 256; // `u8` holds 0 through 255
+        return 0;
+    }",
+};
+
+pub const E0107: ErrorCode = ErrorCode {
+    code: "E0107",
+    title: "wrong number of type arguments",
+    explanation: "\
+A generic function was named with explicit type arguments (`f::<..>`), but their
+number differs from the type parameters the function declares.
+
+    fn pair<A, B>(a: A, b: B) -> A { return a; }
+    fn main() -> i64 {
+        let p = pair::<i64>; // `pair` takes two type arguments
+        return 0;
+    }",
+};
+
+pub const E0252: ErrorCode = ErrorCode {
+    code: "E0252",
+    title: "a name is imported twice",
+    explanation: "\
+Two `use` items import variants of the same name from different enums, so the
+bare name could mean either. Import one of them, and name the other by its path.
+
+    enum Shape { Circle(i64), Square(i64) }
+    enum Mark { Circle, Cross }
+    use Shape::Circle;
+    use Mark::Circle; // `Circle` is already imported from `Shape`
+    fn main() -> i64 { return 0; }",
+};
+
+pub const E0268: ErrorCode = ErrorCode {
+    code: "E0268",
+    title: "`break` or `continue` outside of a loop",
+    explanation: "\
+`break` and `continue` only make sense inside a `loop` or `while` body. A closure
+body is a separate function, so it cannot leave a loop that encloses the closure.
+
+    fn main() -> i64 {
+        break; // there is no loop to leave
+    }",
+};
+
+pub const E0277: ErrorCode = ErrorCode {
+    code: "E0277",
+    title: "`?` in a function that cannot return the failure",
+    explanation: "\
+`e?` returns early with `e`'s failure (`None`, or `Err(..)`) when `e` holds one,
+so it can only appear in a function that returns the same enum as `e`: `?` on an
+`Option` needs a function returning `Option`, and on a `Result` one returning
+`Result`. Handle the failure with a `match` instead.
+
+    fn first(v: Option<i64>) -> i64 {
+        let x = v?; // `first` returns `i64`, not `Option`
+        return x;
+    }",
+};
+
+pub const E0282: ErrorCode = ErrorCode {
+    code: "E0282",
+    title: "type arguments cannot be inferred",
+    explanation: "\
+A generic function was used as a value, but nothing around it fixes its type
+parameters: it is not passed where a `fn(..)` type is expected, not stored in an
+annotated variable, and never called. Name the type arguments explicitly.
+
+    fn identity<T>(x: T) -> T { return x; }
+    fn main() -> i64 {
+        let f = identity; // write `identity::<i64>`
+        return 0;
+    }",
+};
+
+pub const E0308: ErrorCode = ErrorCode {
+    code: "E0308",
+    title: "mismatched types",
+    explanation: "\
+A value's type does not match the type its context requires: an argument against
+its parameter, a struct field against its declaration, a returned value against
+the signature, or an assignment against the type the variable already has.
+
+    fn main() -> i64 {
+        let flag: bool = 1; // `1` is an integer, not a `bool`
+        return 0;
+    }",
+};
+
+pub const E0381: ErrorCode = ErrorCode {
+    code: "E0381",
+    title: "use of a possibly-uninitialized binding",
+    explanation: "\
+A variable is read on some path before anything is assigned to it. Every path
+from the start of the function to a read must assign the variable first;
+borrowing it with `&` or writing one of its fields counts as a read. A `let`
+without an initializer must be assigned on every path before its first use:
+
+    fn pick(flag: bool) -> i64 {
+        let y;
+        if flag {
+            y = 1;
+        }
+        return y; // `y` is unassigned when `flag` is false
+    }",
+};
+
+pub const E0415: ErrorCode = ErrorCode {
+    code: "E0415",
+    title: "identifier bound more than once",
+    explanation: "\
+One declaration binds the same name twice in a single list: two parameters of a
+function, two type parameters, two fields of a struct, two variants of an enum,
+or two methods of one `impl` block or trait. Rebinding a parameter with `let` in
+the body is shadowing, not a duplicate, and is allowed.
+
+    fn add(x: i64, x: i64) -> i64 { // `x` is bound twice
+        return x;
+    }",
+};
+
+pub const E0407: ErrorCode = ErrorCode {
+    code: "E0407",
+    title: "method is not a member of the trait",
+    explanation: "\
+An `impl Trait for Type` block defines a method the trait does not declare. Move
+it to an inherent `impl Type` block, or declare it in the trait.
+
+    trait Shape { fn area(self) -> i64; }
+    struct Square { w: i64 }
+    impl Shape for Square {
+        fn area(self) -> i64 { return self.w * self.w; }
+        fn perimeter(self) -> i64 { return 4 * self.w; } // not in `Shape`
+    }",
+};
+
+pub const E0426: ErrorCode = ErrorCode {
+    code: "E0426",
+    title: "undeclared loop label",
+    explanation: "\
+A `break 'label` or `continue 'label` names a label that no enclosing loop
+declares.
+
+    fn main() -> i64 {
+        loop {
+            break 'outer; // no enclosing loop is labeled `'outer`
+        }
+        return 0;
+    }",
+};
+
+pub const E0432: ErrorCode = ErrorCode {
+    code: "E0432",
+    title: "unresolved import",
+    explanation: "\
+A `use` names an enum the module does not have, or a variant its enum does not
+declare. Only enum variants can be imported: `use Enum::Variant;` brings in one,
+and `use Enum::*;` all of them.
+
+    enum Shape { Circle(i64), Square(i64) }
+    use Shape::Triangle; // `Shape` has no variant `Triangle`
+    fn main() -> i64 { return 0; }",
+};
+
+pub const E0433: ErrorCode = ErrorCode {
+    code: "E0433",
+    title: "failed to resolve a path",
+    explanation: "\
+A `Head::name` path names nothing. Either `Head` is not a struct, enum, or trait
+declared in this module, or nothing called `name` lives under it: no variant of
+the enum and no associated function of the type. The error names the segment
+that failed and suggests close matches.
+
+    enum Shape { Circle(i64), Square(i64) }
+    fn main() -> i64 {
+        let s = Shape::Circel(1); // did you mean `Circle`?
+        return 0;
+    }",
+};
+
+pub const E0562: ErrorCode = ErrorCode {
+    code: "E0562",
+    title: "`impl Trait` outside of a return type",
+    explanation: "\
+An opaque `impl Trait` type stands for the one concrete type a function's body
+returns, so it is only allowed as the return type of a function or method. A
+parameter that accepts any type implementing a trait is written as a bounded
+type parameter instead.
+
+    trait Area { fn area(self) -> i64; }
+    fn total(shape: impl Area) -> i64 { // write `fn total<T: Area>(shape: T)`
+        return shape.area();
+    }",
+};
+
+pub const E0571: ErrorCode = ErrorCode {
+    code: "E0571",
+    title: "`break` with a value outside of `loop`",
+    explanation: "\
+Only a `loop` produces a value, taken from the `break` that leaves it. A `while`
+loop always has type `()`, so a `break` targeting it cannot carry a value.
+
+    fn main() -> i64 {
+        let i = 0;
+        while i < 3 {
+            break 5; // use `loop { .. }` to produce a value
+        }
+        return i;
+    }",
+};
+
+pub const E0580: ErrorCode = ErrorCode {
+    code: "E0580",
+    title: "entry point with an invalid signature",
+    explanation: "\
+The entry point is called with no arguments, so it may take no parameters and
+have no type parameters. It may return any type, or nothing.
+
+    fn main(n: i64) -> i64 { // write `fn main() -> i64`
+        return n;
+    }",
+};
+
+pub const E0601: ErrorCode = ErrorCode {
+    code: "E0601",
+    title: "no entry point",
+    explanation: "\
+Running a module calls its entry point, the function named `main` unless
+another name is given (`rvc --entry NAME`). The module declares no function by
+that name.
+
+    fn start() -> i64 { return 0; } // rename to `main`, or run with `--entry start`",
+};
+
+pub const E0605: ErrorCode = ErrorCode {
+    code: "E0605",
+    title: "invalid `as` cast",
+    explanation: "\
+`as` converts only between numeric types (integers of any width and `f64`) and
+from `bool` to an integer. Other conversions must be written out explicitly.
+
+    struct P { a: i64 }
+    fn main() -> i64 {
+        let p = P { a: 1 };
+        return p as i64; // read the field instead: `p.a`
+    }",
+};
+
+pub const E0609: ErrorCode = ErrorCode {
+    code: "E0609",
+    title: "no such field on an enum",
+    explanation: "\
+A field was read directly from an enum value. The fields belong to a variant, and
+which variant the value holds is only known by matching on it. The exception is
+an enum with a single variant: its positional fields read as `.0`, `.1`, ...
+
+    enum Shape { Circle(i64), Square(i64) }
+    fn main() -> i64 {
+        let s = Shape::Circle(3);
+        match s {
+            Shape::Circle(r) => { return r; } // not `s.0`
+            Shape::Square(w) => { return w; }
+        }
+    }",
+};
+
+pub const E0659: ErrorCode = ErrorCode {
+    code: "E0659",
+    title: "ambiguous name",
+    explanation: "\
+A bare name is a variant of two enums that are both glob-imported (`use E::*`),
+and no explicit import says which one is meant. The imports themselves are fine;
+only a use of the name is an error. Write the variant's full path, or import it
+by name, which takes precedence over the globs.
+
+    enum Shape { Circle(i64), Square(i64) }
+    enum Mark { Circle, Cross }
+    use Shape::*;
+    use Mark::*;
+    fn main() -> i64 {
+        let c = Circle(1); // `Shape::Circle` or `Mark::Circle`?
+        return 0;
+    }",
+};
+
+/// Declare [`REGISTRY`] and [`ALL`] from one list of the codes above, so a code
+/// is registered exactly when it is listed.
+macro_rules! registry {
+    ($($code:ident),* $(,)?) => {
+        /// Every code in use. Lookups, `rvc explain`, and [`Diagnostic::parse`] only
+        /// recognize codes listed here.
+        pub const REGISTRY: &[&ErrorCode] = &[$(&$code),*];
+
+        /// The name of each [`REGISTRY`] entry's constant, in the same order.
+        pub const ALL: &[&str] = &[$(stringify!($code)),*];
+    };
+}
+
+registry![
+    E0001, E0004, E0034, E0046, E0053, E0061, E0080, E0107, E0252, E0268, E0277, E0282, E0308,
+    E0381, E0407, E0415, E0426, E0432, E0433, E0562, E0571, E0580, E0601, E0605, E0609, E0659,
+];
+
+/// Retired codes. They must never be registered again, even for a similar error,
+/// because external documentation and tooling may still refer to the old meaning.
+pub const TOMBSTONES: &[&str] = &[];
+
+/// The registered entry for `code`, if any.
+pub fn lookup(code: &str) -> Option<&'static ErrorCode> {
+    REGISTRY.iter().copied().find(|c| c.code == code)
+}
+
+/// The long-form text `rvc explain <code>` prints.
+pub fn explain(code: &str) -> Result<String, String> {
+    if let Some(c) = lookup(code) {
+        return Ok(format!("{}: {}\n\n{}\n", c.code, c.title, c.explanation));
+    }
+    if TOMBSTONES.contains(&code) {
+        Err(format!("error code {code} has been retired and is no longer emitted"))
+    } else {
+        Err(format!("unknown error code `{code}`"))
+    }
+}
+
+/// The (1-based, inclusive) range of source lines a construct covers, from the
+/// line of its first token to the line of its last. A span always covers at
+/// least one line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LineSpan {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl LineSpan {
+    pub fn new(start: u32, end: u32) -> Self {
+        LineSpan { start, end }
+    }
+
+    /// Whether `other` lies entirely within this span.
+    pub fn contains(&self, other: LineSpan) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+}
+
+impl std::fmt::Display for LineSpan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.start == self.end {
+            write!(f, "line {}", self.start)
+        } else {
+            write!(f, "lines {}-{}", self.start, self.end)
+        }
+    }
+}
+
+/// A pipeline error split into its parts.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    /// The registered code, if the message carried one.
+    pub code: Option<&'static str>,
+    /// The (1-based) source line, if the message named one.
+    pub line: Option<u32>,
+    /// The message with the line prefix and code tag removed.
+    pub message: String,
+    /// Secondary remarks, each on its own line (see [`with_notes`]).
+    pub notes: Vec<Note>,
+}
+
+/// A secondary remark on a diagnostic, pointing at a line of its own: one
+/// step of how the error came about.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Note {
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+impl Note {
+    pub fn new(line: Option<u32>, message: impl Into<String>) -> Note {
+        Note { line, message: message.into() }
+    }
+}
+
+/// How many notes a diagnostic shows by default before summarizing the rest as
+/// "…and N more steps".
+pub const DEFAULT_NOTE_LIMIT: usize = 3;
+
+/// `msg` followed by at most `limit` of `notes`, each on a line of its own as
+/// `  note: line N: ...`, and a last note standing in for any cut.
+pub fn with_notes(msg: impl std::fmt::Display, notes: &[Note], limit: usize) -> String {
+    let mut out = msg.to_string();
+    for n in notes.iter().take(limit) {
+        out.push_str(NOTE);
+        if let Some(line) = n.line {
+            out.push_str(&format!("line {line}: "));
+        }
+        out.push_str(&n.message);
+    }
+    if let Some(more) = notes.len().checked_sub(limit).filter(|&more| more > 0) {
+        let noun = if more == 1 { "step" } else { "steps" };
+        out.push_str(&format!("{NOTE}…and {more} more {noun}"));
+    }
+    out
+}
+
+/// What starts each note of a rendered diagnostic.
+const NOTE: &str = "\n  note: ";
+
+/// Split an optional `line N: ` prefix off `text`.
+fn split_line(text: &str) -> (Option<u32>, &str) {
+    let prefix = text.strip_prefix("line ").and_then(|r| r.split_once(": "));
+    match prefix.and_then(|(n, tail)| Some((n.parse().ok()?, tail))) {
+        Some((n, tail)) => (Some(n), tail),
+        None => (None, text),
+    }
+}
+
+impl Diagnostic {
+    /// Recover the structure of an error string: an optional `line N: ` prefix,
+    /// then an optional `[EXXXX] ` tag, then the message and its notes. A tag
+    /// naming an unregistered code is left in the message rather than trusted.
+    pub fn parse(err: &str) -> Diagnostic {
+        let mut notes = err.split(NOTE);
+        let (line, mut rest) = split_line(notes.next().unwrap_or_default());
+        let notes = notes
+            .map(|n| {
+                let (line, message) = split_line(n);
+                Note::new(line, message)
+            })
+            .collect();
+        let mut code = None;
+        if let Some((tag, tail)) = rest.strip_prefix('[').and_then(|r| r.split_once("] ")) {
+            if let Some(c) = lookup(tag) {
+                code = Some(c.code);
+                rest = tail;
+            }
+        }
+        Diagnostic { code, line, message: rest.to_string(), notes }
+    }
+
+    /// Render as a single-line JSON object with `code`, `line`, and `message`
+    /// fields (`null` when absent), and a `notes` array of `line` and `message`
+    /// objects.
+    pub fn to_json(&self) -> String {
+        let code = self.code.map_or("null".to_string(), json_str);
+        let line = self.line.map_or("null".to_string(), |n| n.to_string());
+        let message = json_str(&self.message);
+        let notes = self.notes_json();
+        format!(r#"{{"code":{code},"line":{line},"message":{message},"notes":{notes}}}"#)
+    }
+
+    /// This diagnostic as a `diagnostic` [`Message`] about `file`, at `severity`
+    /// (`error`, `warning`, or `note`).
+    pub fn to_message(&self, file: &str, severity: &str) -> Message {
+        Message::diagnostic(file, severity, self.code, self.line, &self.message)
+            .field("notes", &self.notes_json())
+    }
+
+    fn notes_json(&self) -> String {
+        let notes: Vec<String> = (self.notes.iter())
+            .map(|n| {
+                let line = n.line.map_or("null".to_string(), |n| n.to_string());
+                format!(r#"{{"line":{line},"message":{}}}"#, json_str(&n.message))
+            })
+            .collect();
+        format!("[{}]", notes.join(","))
+    }
+}
+
+/// The version of the `--message-format=json` stream, carried by every message.
+/// It is bumped when a field is renamed, removed, or changes meaning; a new field
+/// or a new `reason` leaves it alone, so consumers must ignore what they don't
+/// know.
+pub const MESSAGE_SCHEMA: u32 = 1;
+
+/// One line of a `--message-format=json` stream: a JSON object that opens with
+/// the kind of message (`reason`) and the [`MESSAGE_SCHEMA`] version, followed by
+/// the fields added, in order.
+#[derive(Clone, Debug)]
+pub struct Message {
+    json: String,
+}
+
+impl Message {
+    pub fn new(reason: &str) -> Message {
+        Message { json: format!(r#"{{"reason":{},"schema":{MESSAGE_SCHEMA}"#, json_str(reason)) }
+    }
+
+    /// A `diagnostic` message. `code` is a registered error code, or a lint's
+    /// rule name for a warning.
+    pub fn diagnostic(
+        file: &str,
+        severity: &str,
+        code: Option<&str>,
+        line: Option<u32>,
+        message: &str,
+    ) -> Message {
+        let msg = Message::new("diagnostic").str("file", file).str("severity", severity);
+        let msg = msg.str("code", code);
+        let msg = match line {
+            Some(n) => msg.num("line", n),
+            None => msg.null("line"),
+        };
+        msg.str("message", message)
+    }
+
+    /// Add a string field; `None` renders as `null`.
+    pub fn str<'a>(self, key: &str, value: impl Into<Option<&'a str>>) -> Message {
+        let value = value.into().map_or("null".to_string(), json_str);
+        self.field(key, &value)
+    }
+
+    /// Add a number or boolean field.
+    pub fn num(self, key: &str, value: impl std::fmt::Display) -> Message {
+        self.field(key, &value.to_string())
+    }
+
+    /// Add a `null` field.
+    pub fn null(self, key: &str) -> Message {
+        self.field(key, "null")
+    }
+
+    fn field(mut self, key: &str, value: &str) -> Message {
+        self.json.push(',');
+        self.json.push_str(&json_str(key));
+        self.json.push(':');
+        self.json.push_str(value);
+        self
+    }
+
+    /// The finished object, on one line.
+    pub fn finish(self) -> String {
+        self.json + "}"
+    }
+}
+
+/// How many errors a terminal report shows by default before summarizing the
+/// rest as "and N more errors".
+pub const DEFAULT_ERROR_LIMIT: usize = 50;
+
+/// A batch of errors after [`normalize`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Normalized {
+    /// The errors to show, in their original order.
+    pub primary: Vec<String>,
+    /// Follow-on errors: each repeats the code and message of a primary error at
+    /// another line, so it almost certainly stems from the same root mistake.
+    /// Hidden by default, but kept for JSON consumers.
+    pub secondary: Vec<String>,
+}
+
+/// Deduplicate and de-cascade `errors`. An exact duplicate (same code, line, and
+/// message) is dropped; a repeat of an earlier error's code and message at a
+/// different line is demoted to [`Normalized::secondary`].
+pub fn normalize(errors: impl IntoIterator<Item = String>) -> Normalized {
+    let mut out = Normalized::default();
+    let mut seen = std::collections::HashSet::new();
+    let mut roots = std::collections::HashSet::new();
+    for err in errors {
+        let d = Diagnostic::parse(&err);
+        if !seen.insert(d.clone()) {
+            continue;
+        }
+        if roots.insert((d.code, d.message)) {
+            out.primary.push(err);
+        } else {
+            out.secondary.push(err);
+        }
+    }
+    out
+}
+
+/// The `candidates` close enough to `name` to be what was meant, closest first:
+/// at most a third of `name`'s length in edits (but at least one) away.
+pub fn close_matches<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Vec<&'a str> {
+    let limit = (name.chars().count() / 3).max(1);
+    let mut found: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .filter(|c| *c != name)
+        .map(|c| (edit_distance(name, c), c))
+        .filter(|(d, _)| *d <= limit)
+        .collect();
+    found.sort();
+    found.dedup();
+    found.into_iter().map(|(_, c)| c).collect()
+}
+
+/// The suffix suggesting `matches` for an unresolved name: `; did you mean `a`?`,
+/// or `; did you mean one of `a`, `b`?`, or nothing when there are none.
+pub fn did_you_mean(matches: &[&str]) -> String {
+    match matches {
+        [] => String::new(),
+        [one] => format!("; did you mean `{one}`?"),
+        many => {
+            let names: Vec<String> = many.iter().map(|m| format!("`{m}`")).collect();
+            format!("; did you mean one of {}?", names.join(", "))
+        }
+    }
+}
+
+/// Edit distance over `char`s, counting an insertion, deletion, substitution, or
+/// swap of two adjacent characters (the usual typo, `nwe` for `new`) as one edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    // d[i][j]: the distance between the first `i` chars of `a` and `j` of `b`.
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    d[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(d[i - 2][j - 2] + 1);
+            }
+            d[i][j] = best;
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// At most `limit` of `errors`, plus the "and N more errors" line that stands in
+/// for the rest when some were cut.
+pub fn truncate(errors: &[String], limit: usize) -> (&[String], Option<String>) {
+    if errors.len() <= limit {
+        return (errors, None);
+    }
+    let more = errors.len() - limit;
+    let noun = if more == 1 { "error" } else { "errors" };
+    (&errors[..limit], Some(format!("and {more} more {noun}")))
+}
+
+/// A JSON string literal for `s`.
+fn json_str(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each listed constant's entry carries the constant's own name as its code,
+    /// so `E0308.tag(..)` renders `[E0308]` and that tag explains as `E0308`.
+    #[test]
+    fn every_listed_code_is_its_entrys_code() {
+        assert_eq!(ALL.len(), REGISTRY.len());
+        for (name, entry) in ALL.iter().zip(REGISTRY) {
+            assert_eq!(*name, entry.code, "{name} is registered under another code");
+            assert!(explain(name).unwrap().starts_with(&format!("{name}: {}\n", entry.title)));
+        }
+    }
+
+    #[test]
+    fn registry_codes_are_unique_and_never_tombstoned() {
+        for (i, c) in REGISTRY.iter().enumerate() {
+            assert!(c.code.len() == 5 && c.code.starts_with('E'), "malformed code {}", c.code);
+            assert!(c.code[1..].bytes().all(|b| b.is_ascii_digit()), "malformed code {}", c.code);
+            assert!(
+                REGISTRY[i + 1..].iter().all(|d| d.code != c.code),
+                "{} is registered twice",
+                c.code
+            );
+            assert!(!TOMBSTONES.contains(&c.code), "{} reuses a retired code", c.code);
+            assert!(c.explanation.contains("fn "), "{} has no example", c.code);
+        }
+    }
+
+    #[test]
+    fn explain_prints_the_registered_text() {
+        let text = explain("E0308").unwrap();
+        assert!(text.starts_with("E0308: mismatched types\n"));
+        assert!(text.contains(E0308.explanation));
+        assert!(explain("E9999").unwrap_err().contains("unknown error code"));
+    }
+
+    #[test]
+    fn parses_line_and_code() {
+        let d = Diagnostic::parse("line 3: [E0308] type error in return type");
+        assert_eq!(d.code, Some("E0308"));
+        assert_eq!(d.line, Some(3));
+        assert_eq!(d.message, "type error in return type");
+        // An unregistered tag is not trusted as a code.
+        let d = Diagnostic::parse("[E9999] something");
+        assert_eq!((d.code, d.line, d.message.as_str()), (None, None, "[E9999] something"));
+    }
+
+    #[test]
+    fn normalize_drops_duplicates_and_demotes_repeats() {
+        let errs = [
+            "main: use of moved value `s`",
+            "line 4: [E0308] type error in return type",
+            "main: use of moved value `s`",
+            "line 4: [E0308] type error in return type",
+            "line 9: [E0308] type error in return type",
+            "main: use of moved value `t`",
+        ];
+        let n = normalize(errs.map(String::from));
+        assert_eq!(
+            n.primary,
+            [
+                "main: use of moved value `s`",
+                "line 4: [E0308] type error in return type",
+                "main: use of moved value `t`"
+            ]
+        );
+        assert_eq!(n.secondary, ["line 9: [E0308] type error in return type"]);
+    }
+
+    #[test]
+    fn truncate_summarizes_the_rest() {
+        let errs: Vec<String> = (0..53).map(|i| format!("error {i}")).collect();
+        let (shown, more) = truncate(&errs, DEFAULT_ERROR_LIMIT);
+        assert_eq!(shown.len(), 50);
+        assert_eq!(more.as_deref(), Some("and 3 more errors"));
+        assert_eq!(truncate(&errs[..51], 50).1.as_deref(), Some("and 1 more error"));
+        assert_eq!(truncate(&errs[..50], 50), (&errs[..50], None));
+    }
+
+    #[test]
+    fn json_round_trips() {
+        let d = Diagnostic::parse("line 7: [E0605] cannot cast \"P\" to i64\n(see docs)");
+        let v: serde_json::Value = serde_json::from_str(&d.to_json()).unwrap();
+        assert_eq!(v["code"], "E0605");
+        assert_eq!(v["line"], 7);
+        assert_eq!(v["message"], d.message.as_str());
+        let untagged: serde_json::Value =
+            serde_json::from_str(&Diagnostic::parse("oops").to_json()).unwrap();
+        assert!(untagged["code"].is_null() && untagged["line"].is_null());
+    }
+
+    #[test]
+    fn notes_render_one_to_a_line_and_parse_back() {
+        let notes: Vec<Note> =
+            (1..=5).map(|i| Note::new(Some(i), format!("step {i}"))).collect();
+        let err = with_notes("line 9: [E0308] type error", &notes[..1], DEFAULT_NOTE_LIMIT);
+        assert_eq!(err, "line 9: [E0308] type error\n  note: line 1: step 1");
+        let err = with_notes("line 9: [E0308] type error", &notes, DEFAULT_NOTE_LIMIT);
+        let d = Diagnostic::parse(&err);
+        assert_eq!((d.code, d.line, d.message.as_str()), (Some("E0308"), Some(9), "type error"));
+        let shown: Vec<(Option<u32>, &str)> =
+            d.notes.iter().map(|n| (n.line, n.message.as_str())).collect();
+        assert_eq!(
+            shown,
+            [
+                (Some(1), "step 1"),
+                (Some(2), "step 2"),
+                (Some(3), "step 3"),
+                (None, "…and 2 more steps")
+            ]
+        );
+        let v: serde_json::Value = serde_json::from_str(&d.to_json()).unwrap();
+        assert_eq!(v["notes"][1]["line"], 2);
+        assert_eq!(v["notes"][3]["message"], "…and 2 more steps");
+        assert!(with_notes("m", &notes[..4], 3).ends_with("note: …and 1 more step"));
+    }
+
+    #[test]
+    fn close_matches_are_ranked_by_distance() {
+        let names = ["new", "nw", "news", "renew", "Circle", "new"];
+        assert_eq!(close_matches("nwe", names), ["new", "nw"]);
+        assert_eq!(close_matches("Circel", names), ["Circle"]);
+        assert!(close_matches("xyz", names).is_empty());
+        assert_eq!(did_you_mean(&["a"]), "; did you mean `a`?");
+        assert_eq!(did_you_mean(&["a", "b"]), "; did you mean one of `a`, `b`?");
+        assert_eq!(did_you_mean(&[]), "");
+    }
+
+    #[test]
+    fn messages_lead_with_reason_and_schema() {
+        let d = Diagnostic::parse("line 2: [E0605] bad \"cast\"");
+        let line = d.to_message("a.rv", "error").num("extra", true).finish();
+        assert!(line.starts_with(r#"{"reason":"diagnostic","schema":1,"#), "got: {line}");
+        let v: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(v["schema"], MESSAGE_SCHEMA);
+        assert_eq!((v["file"].as_str(), v["severity"].as_str()), (Some("a.rv"), Some("error")));
+        assert_eq!((v["code"].as_str(), v["line"].as_u64()), (Some("E0605"), Some(2)));
+        assert_eq!(v["message"].as_str(), Some("bad \"cast\""));
+        assert_eq!(v["extra"].as_bool(), Some(true));
+        let none = Message::new("x").null("n").str("s", None).finish();
+        assert_eq!(none, r#"{"reason":"x","schema":1,"n":null,"s":null}"#);
+    }
+}
@@ -0,0 +1,446 @@
+//! Completion: the names an editor can offer at a cursor.
+//!
+//! The cursor's line is usually mid-edit and does not parse, so the module is read
+//! with that line blanked out, and what is being completed is taken from the raw
+//! text of the line: after `recv.` the fields and methods of `recv`'s type, and
+//! anywhere else the locals, functions and types in scope. Types are worked out on
+//! the syntax tree, from annotations and the shape of initializers, so completion
+//! needs no part of the program to type-check.
+
+use rv_core::{Sym, Symbols};
+use rv_diagnostics::Edit;
+use rv_syntax::ast::{Block, Expr, Item, MethodDecl, Module, Param, PatBind, Pattern, Stmt, Ty};
+
+/// What a completion names; items are listed in this order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CompletionKind {
+    Field,
+    Method,
+    Local,
+    Function,
+    Type,
+}
+
+/// One entry of a completion list.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CompletionItem {
+    pub label: String,
+    pub kind: CompletionKind,
+    /// The declaration, as `x: i64` or `fn length(self) -> i64`.
+    pub detail: String,
+    /// Replaces the partly typed name before the cursor: a method is inserted as a
+    /// call, `length()`.
+    pub edit: Edit,
+}
+
+/// The completions at `column` (0-based, in `char`s) of the 1-based `line` of
+/// `src`. Names starting with what is typed before the cursor come first, then
+/// those merely containing its letters in order; within each, fields precede
+/// methods and locals precede functions and types.
+pub fn completions(src: &str, line: u32, column: u32) -> Vec<CompletionItem> {
+    let Some(text) = src.lines().nth(line as usize - 1) else { return Vec::new() };
+    let chars: Vec<char> = text.chars().collect();
+    let end = (column as usize).min(chars.len());
+    let start = ident_start(&chars, end);
+    let typed: String = chars[start..end].iter().collect();
+    let mut syms = Symbols::new();
+    let Some(module) = parse_around(src, line, &mut syms) else { return Vec::new() };
+    let scope = Scope::at(&module, line, &mut syms);
+    let mut found = match start.checked_sub(1).map(|i| chars[i]) {
+        Some('.') => match receiver(&chars[..start - 1]) {
+            Some(path) => {
+                let path: Vec<Sym> = path.iter().map(|seg| syms.intern(seg)).collect();
+                members(&module, &scope, &path, &syms)
+            }
+            None => Vec::new(),
+        },
+        Some(':') => Vec::new(),
+        _ => names(&module, &scope, &syms),
+    };
+    found.retain(|c| rank(&c.0, &typed).is_some());
+    found.sort_by_key(|c| (rank(&c.0, &typed), c.1));
+    let edit = |insert: String| Edit { line, start: start as u32, end: end as u32, text: insert };
+    found
+        .into_iter()
+        .map(|(label, kind, detail, insert)| {
+            CompletionItem { label, kind, detail, edit: edit(insert) }
+        })
+        .collect()
+}
+
+/// A candidate before ranking: label, kind, detail, and the text to insert.
+type Candidate = (String, CompletionKind, String, String);
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn ident_start(chars: &[char], end: usize) -> usize {
+    (0..end).rev().take_while(|&i| is_ident(chars[i])).last().unwrap_or(end)
+}
+
+/// The `a.b.c` chain of names that ends `before`, outermost first.
+fn receiver(before: &[char]) -> Option<Vec<String>> {
+    let mut path = Vec::new();
+    let mut end = before.len();
+    loop {
+        let start = ident_start(before, end);
+        if start == end {
+            return None;
+        }
+        path.push(before[start..end].iter().collect());
+        if start == 0 || before[start - 1] != '.' {
+            path.reverse();
+            return Some(path);
+        }
+        end = start - 1;
+    }
+}
+
+/// `src` parsed as is or, when that fails, with `line` blanked out.
+fn parse_around(src: &str, line: u32, syms: &mut Symbols) -> Option<Module> {
+    if let Ok(module) = rv_syntax::parse(src, syms) {
+        return Some(module);
+    }
+    let blanked: Vec<&str> = src
+        .lines()
+        .enumerate()
+        .map(|(i, text)| if i + 1 == line as usize { "" } else { text })
+        .collect();
+    rv_syntax::parse(&blanked.join("\n"), syms).ok()
+}
+
+/// `0` when `label` starts with `typed`, `1` when it contains `typed`'s letters in
+/// order (ignoring case), and `None` when it is no match.
+fn rank(label: &str, typed: &str) -> Option<u8> {
+    if label.starts_with(typed) {
+        return Some(0);
+    }
+    let mut rest = label.chars().flat_map(char::to_lowercase);
+    let ordered = typed.chars().flat_map(char::to_lowercase).all(|c| rest.any(|l| l == c));
+    ordered.then_some(1)
+}
+
+/// A name bound in the body around the cursor, with what is known of its type.
+struct Binding<'m> {
+    name: Sym,
+    ty: Option<Ty>,
+    init: Option<&'m Expr>,
+}
+
+/// The bindings visible at the cursor, in binding order: the parameters of the
+/// function around it (`self` first), then what its body binds before it.
+#[derive(Default)]
+struct Scope<'m> {
+    bindings: Vec<Binding<'m>>,
+}
+
+impl<'m> Scope<'m> {
+    fn at(module: &'m Module, line: u32, syms: &mut Symbols) -> Scope<'m> {
+        let mut scope = Scope::default();
+        let params = |scope: &mut Scope<'m>, params: &'m [Param]| {
+            let bind = |p: &Param| Binding { name: p.name, ty: Some(p.ty.clone()), init: None };
+            scope.bindings.extend(params.iter().map(bind));
+        };
+        for item in &module.items {
+            match item {
+                Item::Fn(f) if f.body.span.contains(line_span(line)) => {
+                    params(&mut scope, &f.params);
+                    scope.visit(&f.body, line);
+                }
+                Item::Impl(im) => {
+                    let inside = |m: &&MethodDecl| m.body.span.contains(line_span(line));
+                    if let Some(m) = im.methods.iter().find(inside) {
+                        if m.has_self {
+                            let self_ty = Some(Ty::Adt(im.type_name));
+                            let name = syms.intern("self");
+                            scope.bindings.push(Binding { name, ty: self_ty, init: None });
+                        }
+                        params(&mut scope, &m.params);
+                        scope.visit(&m.body, line);
+                    }
+                }
+                _ => {}
+            }
+        }
+        scope
+    }
+
+    /// Bind what `block` declares before `line`, and what the statement holding
+    /// `line` binds around it.
+    fn visit(&mut self, block: &'m Block, line: u32) {
+        rv_arena::stack::grow(|| self.visit_here(block, line))
+    }
+
+    fn visit_here(&mut self, block: &'m Block, line: u32) {
+        for (stmt, span) in block.stmts.iter().zip(&block.spans) {
+            if span.end < line {
+                if let Stmt::Let { name, ty, init, .. } = stmt {
+                    let (ty, init) = (ty.clone(), init.as_ref());
+                    self.bindings.push(Binding { name: *name, ty, init });
+                }
+                continue;
+            }
+            if span.start > line {
+                break;
+            }
+            let inner = |b: &&Block| b.span.contains(line_span(line));
+            match stmt {
+                Stmt::If { then_blk, else_blk, .. } => {
+                    if let Some(b) = [then_blk].into_iter().chain(else_blk).find(inner) {
+                        self.visit(b, line);
+                    }
+                }
+                Stmt::Let { init: Some(Expr::If { then_blk, else_blk, .. }), .. } => {
+                    if let Some(b) = [then_blk, else_blk].into_iter().find(inner) {
+                        self.visit(b, line);
+                    }
+                }
+                Stmt::While { body, .. }
+                | Stmt::Expr(Expr::Loop { body, .. })
+                | Stmt::Let { init: Some(Expr::Loop { body, .. }), .. }
+                    if inner(&body) =>
+                {
+                    self.visit(body, line);
+                }
+                Stmt::Match { arms, .. } => {
+                    if let Some(arm) = arms.iter().find(|a| inner(&&a.body)) {
+                        if let Pattern::Variant { binds, .. } | Pattern::Bare { binds, .. } =
+                            &arm.pat
+                        {
+                            let names = binds.iter().filter_map(|b| match b {
+                                PatBind::Name(name) => Some(*name),
+                                PatBind::Wildcard => None,
+                            });
+                            let bind = |name| Binding { name, ty: None, init: None };
+                            self.bindings.extend(names.map(bind));
+                        }
+                        self.visit(&arm.body, line);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The type of the latest binding of `name` among the first `upto`.
+    fn type_of_name(&self, module: &Module, name: Sym, upto: usize, syms: &Symbols) -> Option<Ty> {
+        let i = self.bindings[..upto].iter().rposition(|b| b.name == name)?;
+        let b = &self.bindings[i];
+        match (&b.ty, b.init) {
+            (Some(ty), _) => Some(ty.clone()),
+            (None, Some(init)) => self.type_of(module, init, i, syms),
+            (None, None) => None,
+        }
+    }
+
+    /// The type of `e`, read where only the first `upto` bindings are in scope.
+    fn type_of(&self, module: &Module, e: &Expr, upto: usize, syms: &Symbols) -> Option<Ty> {
+        rv_arena::stack::grow(|| self.type_of_here(module, e, upto, syms))
+    }
+
+    fn type_of_here(&self, module: &Module, e: &Expr, upto: usize, syms: &Symbols) -> Option<Ty> {
+        match e {
+            Expr::Int(_) => Some(Ty::I64),
+            Expr::SuffixedInt(_, ty) => Some(ty.clone()),
+            Expr::Float(_) => Some(Ty::F64),
+            Expr::Str(_) => Some(Ty::String),
+            Expr::Bool(_) => Some(Ty::Bool),
+            Expr::Unit => Some(Ty::Unit),
+            Expr::Var(name) => self.type_of_name(module, *name, upto, syms),
+            Expr::StructLit { name, .. } => Some(Ty::Adt(*name)),
+            // `Type::name(..)` is a variant, or else an associated function.
+            Expr::EnumCtor { enum_name, variant, .. } => {
+                match methods(module, *enum_name).find(|m| m.name == *variant) {
+                    Some(m) => Some(returns(m, *enum_name, syms)),
+                    None => Some(Ty::Adt(*enum_name)),
+                }
+            }
+            Expr::Cast { ty, .. } => Some(ty.clone()),
+            Expr::Call { func, .. } => module.items.iter().find_map(|item| match item {
+                Item::Fn(f) if f.name == *func => Some(f.ret.clone().unwrap_or(Ty::Unit)),
+                _ => None,
+            }),
+            Expr::Field { base, field } => {
+                let adt = adt(&self.type_of(module, base, upto, syms)?)?;
+                field_ty(module, adt, *field)
+            }
+            Expr::MethodCall { recv, method, .. } => {
+                let adt = adt(&self.type_of(module, recv, upto, syms)?)?;
+                let m = methods(module, adt).find(|m| m.name == *method)?;
+                Some(returns(m, adt, syms))
+            }
+            Expr::Ref { mutable, expr } => {
+                let inner = Box::new(self.type_of(module, expr, upto, syms)?);
+                Some(Ty::Ref { mutable: *mutable, inner })
+            }
+            Expr::Deref(inner) => match self.type_of(module, inner, upto, syms)? {
+                Ty::Ref { inner, .. } => Some(*inner),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// What `m`, a method of `adt`, returns, with `Self` read as `adt`.
+fn returns(m: &MethodDecl, adt: Sym, syms: &Symbols) -> Ty {
+    match m.ret.clone().unwrap_or(Ty::Unit) {
+        Ty::Adt(s) if syms.resolve(s) == "Self" => Ty::Adt(adt),
+        ret => ret,
+    }
+}
+
+fn line_span(line: u32) -> rv_diagnostics::LineSpan {
+    rv_diagnostics::LineSpan::new(line, line)
+}
+
+/// The struct or enum `ty` names, through any references.
+fn adt(ty: &Ty) -> Option<Sym> {
+    match ty {
+        Ty::Adt(name) | Ty::Generic { base: name, .. } => Some(*name),
+        Ty::Ref { inner, .. } => adt(inner),
+        _ => None,
+    }
+}
+
+fn field_ty(module: &Module, adt: Sym, field: Sym) -> Option<Ty> {
+    module.items.iter().find_map(|item| match item {
+        Item::Struct(s) if s.name == adt => {
+            s.fields.iter().find(|f| f.name == field).map(|f| f.ty.clone())
+        }
+        _ => None,
+    })
+}
+
+/// Every method of `adt`, from its inherent and trait impls alike.
+fn methods(module: &Module, adt: Sym) -> impl Iterator<Item = &MethodDecl> {
+    let impls = module.items.iter().filter_map(move |item| match item {
+        Item::Impl(im) if im.type_name == adt => Some(im),
+        _ => None,
+    });
+    impls.flat_map(|im| &im.methods)
+}
+
+/// The fields and methods of the type `path` has.
+fn members(module: &Module, scope: &Scope, path: &[Sym], syms: &Symbols) -> Vec<Candidate> {
+    let mut ty = scope.type_of_name(module, path[0], scope.bindings.len(), syms);
+    for &field in &path[1..] {
+        let Some(adt) = ty.as_ref().and_then(adt) else { return Vec::new() };
+        ty = field_ty(module, adt, field);
+    }
+    let Some(adt) = ty.as_ref().and_then(adt) else { return Vec::new() };
+    let mut found = Vec::new();
+    for item in &module.items {
+        if let Item::Struct(s) = item {
+            if s.name == adt {
+                for f in &s.fields {
+                    let name = syms.resolve(f.name).to_string();
+                    let detail = format!("{name}: {}", f.ty.show(syms));
+                    found.push((name.clone(), CompletionKind::Field, detail, name));
+                }
+            }
+        }
+    }
+    for m in methods(module, adt).filter(|m| m.has_self) {
+        let name = syms.resolve(m.name).to_string();
+        let params = m.params.iter().map(|p| param(p, syms));
+        let params = ["self".to_string()].into_iter().chain(params);
+        let detail = signature(&name, params, m.ret.as_ref(), syms);
+        found.push((name.clone(), CompletionKind::Method, detail, format!("{name}()")));
+    }
+    found
+}
+
+fn param(p: &Param, syms: &Symbols) -> String {
+    format!("{}: {}", syms.resolve(p.name), p.ty.show(syms))
+}
+
+fn signature(
+    name: &str,
+    params: impl Iterator<Item = String>,
+    ret: Option<&Ty>,
+    syms: &Symbols,
+) -> String {
+    let params = params.collect::<Vec<_>>().join(", ");
+    match ret {
+        Some(r) => format!("fn {name}({params}) -> {}", r.show(syms)),
+        None => format!("fn {name}({params})"),
+    }
+}
+
+/// The locals visible in `scope`, then the module's functions and types.
+fn names(module: &Module, scope: &Scope, syms: &Symbols) -> Vec<Candidate> {
+    let mut found: Vec<Candidate> = Vec::new();
+    let upto = scope.bindings.len();
+    // Latest first, so a shadowed name is offered once, as the binding in effect.
+    for name in scope.bindings.iter().rev().map(|b| b.name) {
+        let label = syms.resolve(name).to_string();
+        if found.iter().any(|c| c.0 == label) {
+            continue;
+        }
+        let detail = match scope.type_of_name(module, name, upto, syms) {
+            Some(ty) => format!("{label}: {}", ty.show(syms)),
+            None => label.clone(),
+        };
+        found.push((label.clone(), CompletionKind::Local, detail, label));
+    }
+    let ty = |name: Sym, what: &str| {
+        (name, CompletionKind::Type, format!("{what} {}", syms.resolve(name)))
+    };
+    for item in &module.items {
+        let (name, kind, detail) = match item {
+            Item::Fn(f) => {
+                let params = f.params.iter().map(|p| param(p, syms));
+                let detail = signature(syms.resolve(f.name), params, f.ret.as_ref(), syms);
+                (f.name, CompletionKind::Function, detail)
+            }
+            Item::Struct(s) => ty(s.name, "struct"),
+            Item::Enum(e) => ty(e.name, "enum"),
+            Item::TypeAlias(a) => ty(a.name, "type"),
+            Item::Trait(t) => ty(t.name, "trait"),
+            _ => continue,
+        };
+        let label = syms.resolve(name).to_string();
+        found.push((label.clone(), kind, detail, label));
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POINT: &str = "struct Point { x: i64, y: i64 }
+impl Point {
+    fn length(self) -> i64 { return self.x + self.y; }
+    fn origin() -> Point { return Point { x: 0, y: 0 }; }
+}
+";
+
+    fn labels(src: &str, line: u32, column: u32) -> Vec<String> {
+        completions(src, line, column).into_iter().map(|c| c.label).collect()
+    }
+
+    #[test]
+    fn members_of_a_call_result_and_a_field_chain() {
+        let src = format!(
+            "{POINT}struct Seg {{ a: Point, b: Point }}
+fn f(s: Seg) -> i64 {{
+    let o = Point::origin();
+    let p = &o;
+    let n = s.a.
+    return 0;
+}}"
+        );
+        assert_eq!(labels(&src, 10, 16), ["x", "y", "length"]);
+        let src = src.replace("s.a.", "p.");
+        assert_eq!(labels(&src, 10, 14), ["x", "y", "length"]);
+    }
+
+    #[test]
+    fn prefix_matches_rank_before_scattered_ones() {
+        let src = "fn length() {}\nfn main() -> i64 {\n    let gl = 1;\n    let lag = 2;\n    l\n}";
+        assert_eq!(labels(src, 5, 5), ["lag", "length", "gl"]);
+    }
+}
@@ -32,8 +32,10 @@ use std::collections::HashMap;
 pub fn run_entry_on_vm(env: &Env, entry: &str) -> Result<Value, String> {
     let mut c = Compiler::new(env);
     c.ensure_def(entry)?;
-    let bc = Bytecode { funcs: c.funcs };
-    rv_vm::run(&bc, entry, &[])
+    // Kernel inductives have no rv-ir type definitions, so there is no metadata
+    // to record: their values render raw.
+    let bc = Bytecode { funcs: c.funcs, types: Vec::new() };
+    rv_vm::run_isolated(&bc, entry, &[], rv_vm::DEFAULT_RUN_TIMEOUT).map_err(|e| e.to_string())
 }
 
 struct Compiler<'a> {
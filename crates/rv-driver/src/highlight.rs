@@ -0,0 +1,104 @@
+//! Semantic highlighting: what kind of thing each token of a file is.
+//!
+//! Highlighting reads the lexer's lossless token stream, so it still works on a
+//! file that does not parse. An identifier is a type if the module declares one
+//! by that name (when the module parses) or it names a primitive, a function if
+//! a call or a `fn` header shows it is one, and a variable otherwise.
+
+use rv_core::Symbols;
+use rv_syntax::ast::{Item, Ty};
+use rv_syntax::lexer::{tokenize, Token, TokenKind};
+
+/// What a highlighted token is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HighlightKind {
+    Keyword,
+    Type,
+    Function,
+    Variable,
+    Label,
+    Number,
+    String,
+    Comment,
+    Operator,
+}
+
+/// One highlighted span, within a single line: `start..end` are 0-based `char`
+/// columns of the 1-based `line`, as in [`crate::Edit`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SemanticToken {
+    pub line: u32,
+    pub start: u32,
+    pub end: u32,
+    pub kind: HighlightKind,
+}
+
+/// The highlighted spans of `src`, in order. Whitespace and text the lexer
+/// cannot read get none; a token spanning lines is split at each newline.
+pub fn semantic_tokens(src: &str) -> Vec<SemanticToken> {
+    let types = declared_types(src);
+    let toks: Vec<Token> = tokenize(src)
+        .into_iter()
+        .filter(|t| t.kind != TokenKind::Whitespace)
+        .collect();
+    let text = |i: usize| toks.get(i).map(|t: &Token| &src[t.range.clone()]);
+    let mut out = Vec::new();
+    let (mut line, mut line_start) = (1, 0);
+    for (i, tok) in toks.iter().enumerate() {
+        let kind = match tok.kind {
+            TokenKind::Whitespace | TokenKind::Error => None,
+            TokenKind::Comment => Some(HighlightKind::Comment),
+            TokenKind::Keyword => Some(HighlightKind::Keyword),
+            TokenKind::Label => Some(HighlightKind::Label),
+            TokenKind::Int | TokenKind::Float => Some(HighlightKind::Number),
+            TokenKind::Str => Some(HighlightKind::String),
+            TokenKind::Punct => Some(HighlightKind::Operator),
+            TokenKind::Ident => {
+                let name = &src[tok.range.clone()];
+                let after_fn = i.checked_sub(1).is_some_and(|p| text(p) == Some("fn"));
+                Some(if types.iter().any(|t| t == name) || Ty::primitive(name).is_some() {
+                    HighlightKind::Type
+                } else if after_fn || text(i + 1) == Some("(") {
+                    HighlightKind::Function
+                } else {
+                    HighlightKind::Variable
+                })
+            }
+        };
+        // Catch the line count up to the token, across the whitespace skipped.
+        let gap = &src[line_start..tok.range.start];
+        if let Some(last) = gap.rfind('\n') {
+            line += gap.matches('\n').count() as u32;
+            line_start += last + 1;
+        }
+        let mut from = tok.range.start;
+        for piece in src[tok.range.clone()].split_inclusive('\n') {
+            let body = piece.strip_suffix('\n').unwrap_or(piece);
+            let start = src[line_start..from].chars().count() as u32;
+            if let (Some(kind), false) = (kind, body.is_empty()) {
+                let end = start + body.chars().count() as u32;
+                out.push(SemanticToken { line, start, end, kind });
+            }
+            from += piece.len();
+            if piece.ends_with('\n') {
+                (line, line_start) = (line + 1, from);
+            }
+        }
+    }
+    out
+}
+
+/// The names of the structs, enums, traits and type aliases `src` declares, if it
+/// parses.
+fn declared_types(src: &str) -> Vec<String> {
+    let mut syms = Symbols::new();
+    let Ok(module) = rv_syntax::parse(src, &mut syms) else { return Vec::new() };
+    let names = module.items.iter().filter_map(|item| match item {
+        Item::Struct(s) => Some(s.name),
+        Item::Enum(e) => Some(e.name),
+        Item::Trait(t) => Some(t.name),
+        Item::TypeAlias(a) => Some(a.name),
+        _ => None,
+    });
+    names.map(|n| syms.resolve(n).to_string()).collect()
+}
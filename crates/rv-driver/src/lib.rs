@@ -17,7 +17,13 @@
 //! into the public [`Report`] shape and to drive optional execution. The public
 //! API and behavior are unchanged.
 
-pub use rv_vm::Value;
+pub use rv_codegen::CodegenOptions;
+#[cfg(feature = "query-log")]
+pub use rv_db::explain_rebuild;
+pub use rv_diagnostics::{Diagnostic, Edit, LineSpan, QuickFix};
+pub use rv_syntax::source::{decode as decode_source, Source};
+pub use rv_syntax::CfgSet;
+pub use rv_vm::{Value, ValueRenderer};
 
 // Untrusted schema-installer methods (`install_quot`/`install_trunc`/`install_funext`/
 // `check_usage`/`declare_inductive`/...) on `rv_kernel::Kernel` come from this
@@ -26,7 +32,12 @@ pub use rv_vm::Value;
 use rv_kernel::KernelExt as _;
 
 pub mod unify;
+mod complete;
 mod erased_vm;
+mod highlight;
+
+pub use complete::{completions, CompletionItem, CompletionKind};
+pub use highlight::{semantic_tokens, HighlightKind, SemanticToken};
 
 /// The outcome of one verification obligation.
 #[derive(Debug)]
@@ -47,6 +58,11 @@ pub struct Report {
     pub obligations: Vec<ObligationResult>,
     /// Borrow/ownership violations (use-after-move, borrow conflicts). Empty = clean.
     pub borrow_errors: Vec<String>,
+    /// Follow-on borrow errors, hidden by default: each repeats an error in
+    /// `borrow_errors` at another line (see `rv_diagnostics::normalize`).
+    pub secondary_borrow_errors: Vec<String>,
+    /// Lint warnings ([`rv_syntax::lint`]). They never affect verification.
+    pub warnings: Vec<String>,
     /// Proof-fragment `fn`s whose dependent-kernel obligation discharged.
     pub proof_verified: Vec<String>,
     /// Proof-fragment `fn`s whose obligation is still open (failed to verify).
@@ -54,6 +70,9 @@ pub struct Report {
     /// `Some` if an executable entry point was requested: the value it returned, or a
     /// runtime error (the VM path).
     pub run: Option<Result<Value, String>>,
+    /// The value in [`Report::run`] in source syntax (`Shape::Circle(3)`), when
+    /// an executable entry returned one ([`ValueRenderer`]).
+    pub run_display: Option<String>,
     /// `Some` if a *proof-fragment* entry point was evaluated through the kernel: its
     /// rendered value, or an eval error. (Stage D will fold this into [`Report::run`].)
     pub proof_run: Option<Result<String, String>>,
@@ -61,6 +80,12 @@ pub struct Report {
     pub proofs_erased: Vec<String>,
     /// Proof-fragment declarations that survive QTT erasure as runtime code.
     pub runtime_defs: Vec<String>,
+    /// The salsa queries the executable backend ran or served from cache.
+    #[cfg(feature = "query-log")]
+    pub query_log: Vec<rv_db::QueryEvent>,
+    /// What each of those queries read, for [`explain_rebuild`].
+    #[cfg(feature = "query-log")]
+    pub query_graph: rv_db::QueryGraph,
 }
 impl Report {
     /// Did every obligation discharge — executable (`rv-solve`) *and* proof (kernel) —
@@ -88,6 +113,13 @@ pub fn run_pipeline(src: &str, entry: Option<&str>) -> Result<Report, String> {
     // `rv-db`. `compile_and_run` builds a `Database`, sets the `SourceProgram`
     // input, runs the memoized `analyze` query, and (re-using the memoized
     // elaboration) optionally compiles + runs the requested entry point.
+    // A bad entry point is reported before anything runs.
+    if let Some(name) = entry {
+        let mut syms = rv_core::Symbols::new();
+        if let Ok(module) = rv_syntax::parse(src, &mut syms) {
+            find_entry_point(&module, &rv_syntax::classify(&module), &syms, name)?;
+        }
+    }
     let (analysis, run) = rv_db::compile_and_run(src, entry);
 
     // A front-end (parse / lower / type) failure surfaces as `Err`, exactly as
@@ -104,7 +136,89 @@ pub fn run_pipeline(src: &str, entry: Option<&str>) -> Result<Report, String> {
         .map(|o| ObligationResult { origin: o.origin, discharged: o.ok })
         .collect();
 
-    Ok(Report { obligations, borrow_errors: analysis.borrow_errors, run, ..Default::default() })
+    // The source parsed and lowered inside `rv-db`, so neither step can fail.
+    let mut syms = rv_core::Symbols::new();
+    let (warnings, run_display) = match rv_syntax::parse(src, &mut syms) {
+        Ok(module) => {
+            let lowered = rv_lower::lower_with_lines(&module, &mut syms).ok();
+            let warnings = lint_warnings(&module, lowered.as_ref(), &syms);
+            (warnings, render_run(lowered.as_ref(), &syms, entry, &run))
+        }
+        Err(_) => Default::default(),
+    };
+
+    Ok(Report {
+        obligations,
+        borrow_errors: analysis.borrow_errors,
+        secondary_borrow_errors: analysis.secondary_borrow_errors,
+        warnings,
+        run,
+        run_display,
+        ..Default::default()
+    })
+}
+
+/// A module lowered by the driver itself, for the checks that need the IR.
+type Lowered = (rv_ir::Program<rv_ir::Parsed>, rv_ir::SourceLines);
+
+/// The rendered lint warnings for `module`: the surface lints and the IR lints of
+/// its lowered functions, in line order.
+///
+/// The IR lints run over the typed program when `module` type-checks (some rules
+/// need operand types), and over `lowered` otherwise.
+fn lint_warnings(
+    module: &rv_syntax::ast::Module,
+    lowered: Option<&Lowered>,
+    syms: &rv_core::Symbols,
+) -> Vec<String> {
+    let mut lints = rv_syntax::lint::check(module, syms);
+    if let Some((prog, lines)) = lowered {
+        // Typing consumes its input, so type a fresh lowering of `module`.
+        let mut typed_syms = syms.clone();
+        let typed = rv_lower::lower_with_lines(module, &mut typed_syms)
+            .ok()
+            .and_then(|(p, l)| rv_infer::typecheck_with_lines(p, &typed_syms, &l).ok());
+        match typed {
+            Some(typed) => ir_lints(&typed, lines, &typed_syms, &mut lints),
+            None => ir_lints(prog, lines, syms, &mut lints),
+        }
+    }
+    lints.sort_by_key(|l| l.line);
+    lints.dedup();
+    lints.iter().map(ToString::to_string).collect()
+}
+
+/// Append the IR lints of every function in `prog`. A lint without a source line
+/// (compiler-generated code) has nowhere to point and is dropped.
+fn ir_lints<P: rv_ir::Phase>(
+    prog: &rv_ir::Program<P>,
+    lines: &rv_ir::SourceLines,
+    syms: &rv_core::Symbols,
+    out: &mut Vec<rv_syntax::lint::Lint>,
+) {
+    for f in &prog.funcs {
+        for lint in rv_ir::lint::check(f, syms) {
+            let site = rv_ir::Site { func: f.name, block: lint.block, stmt: lint.stmt };
+            let Some(span) = lines.get(site) else { continue };
+            let rv_ir::lint::IrLint { rule, message, help, .. } = lint;
+            out.push(rv_syntax::lint::Lint { rule, line: span.start, message, help });
+        }
+    }
+}
+
+/// The value the executable entry `entry` returned, rendered against the
+/// program's types.
+fn render_run(
+    lowered: Option<&Lowered>,
+    syms: &rv_core::Symbols,
+    entry: Option<&str>,
+    run: &Option<Result<Value, String>>,
+) -> Option<String> {
+    let ((prog, _), entry, Some(Ok(value))) = (lowered?, entry?, run) else { return None };
+    let f = prog.funcs.iter().find(|f| syms.resolve(f.name) == entry)?;
+    let ret = f.ret.clone().unwrap_or(rv_core::Ty::Unit);
+    let types = rv_codegen::type_metadata(&prog.types, syms);
+    Some(ValueRenderer::new(&types).render(value, &ret))
 }
 
 /// Convenience: verify only (no execution).
@@ -112,6 +226,40 @@ pub fn verify(src: &str) -> Result<Report, String> {
     run_pipeline(src, None)
 }
 
+/// Every diagnostic `src` produces, in report order: the front-end error that
+/// stopped the pipeline, or the borrow errors and lint warnings of a program
+/// that got through it.
+pub fn diagnostics(src: &str) -> Vec<Diagnostic> {
+    let errs = match verify(src) {
+        Err(e) => vec![e],
+        Ok(r) => r.borrow_errors.into_iter().chain(r.warnings).collect(),
+    };
+    errs.iter().map(|e| Diagnostic::parse(e)).collect()
+}
+
+/// The quick fixes for `src`'s diagnostics (see [`Diagnostic::fixes`]) that
+/// edit some line within `lines`.
+pub fn code_actions(src: &str, lines: LineSpan) -> Vec<QuickFix> {
+    let touches =
+        |f: &QuickFix| f.edits.iter().any(|e| lines.contains(LineSpan::new(e.line, e.line)));
+    diagnostics(src).iter().flat_map(|d| d.fixes(src)).filter(touches).collect()
+}
+
+/// What type inference decided for every local of every function in `src`, for
+/// `rvc --emit types`.
+pub fn dump_types(src: &str, cfg: &CfgSet) -> Result<String, String> {
+    rv_db::dump_types(src, cfg)
+}
+
+/// `src`'s executable items after desugaring, as source text, for
+/// `rvc --emit desugared`.
+pub fn dump_desugared(src: &str, cfg: &CfgSet) -> Result<String, String> {
+    let mut syms = rv_core::Symbols::new();
+    let module = rv_syntax::parse_with_cfg(src, &mut syms, cfg)?;
+    let module = rv_lower::desugar(&module, &mut syms)?;
+    Ok(rv_lower::print(&module, &syms))
+}
+
 // ---------------------------------------------------------------------------
 // The unified path: one `.rv` file, both backends, one merged report.
 // ---------------------------------------------------------------------------
@@ -126,21 +274,47 @@ pub fn verify(src: &str) -> Result<Report, String> {
 /// runs on the VM ([`Report::run`]); a proof-fragment entry is evaluated by the kernel
 /// ([`Report::proof_run`]).
 pub fn analyze_unified(src: &str, entry: Option<&str>) -> Result<Report, String> {
+    analyze_unified_with_cfg(src, entry, &CfgSet::new())
+}
+
+/// [`analyze_unified`] under the cfg options `cfg`: both backends see only the
+/// items whose `#[cfg(...)]` holds.
+pub fn analyze_unified_with_cfg(
+    src: &str,
+    entry: Option<&str>,
+    cfg: &CfgSet,
+) -> Result<Report, String> {
+    analyze_unified_with_options(src, entry, cfg, &CodegenOptions::default())
+}
+
+/// [`analyze_unified_with_cfg`], compiling an executable entry with the codegen
+/// options `opts` (e.g. [`CodegenOptions::release`] to wrap on overflow).
+pub fn analyze_unified_with_options(
+    src: &str,
+    entry: Option<&str>,
+    cfg: &CfgSet,
+    opts: &CodegenOptions,
+) -> Result<Report, String> {
     use rv_syntax::Fragment;
 
     // Parse once to classify items and to locate the entry point's fragment.
     let mut syms = rv_core::Symbols::new();
-    let module = rv_syntax::parse(src, &mut syms)?;
+    let module = rv_syntax::parse_with_cfg(src, &mut syms, cfg)?;
     let frags = rv_syntax::classify(&module);
     let has_proof = frags.iter().any(|f| matches!(f, Fragment::Proof));
-    let entry_frag = entry.and_then(|name| entry_fragment(&module, &frags, &syms, name));
+    let entry_frag =
+        entry.map(|name| find_entry_point(&module, &frags, &syms, name)).transpose()?;
 
     // Executable backend: the salsa pipeline over the executable fragment (rv-lower
     // already skips proof items). Run the entry only if it is an executable `fn`.
     let exec_entry = matches!(entry_frag, Some(Fragment::Exec) | Some(Fragment::Shared))
         .then_some(entry)
         .flatten();
-    let (analysis, run) = rv_db::compile_and_run(src, exec_entry);
+    #[cfg(feature = "query-log")]
+    let (analysis, run, query_log, query_graph) =
+        rv_db::compile_and_run_logged(src, cfg, opts, exec_entry);
+    #[cfg(not(feature = "query-log"))]
+    let (analysis, run) = rv_db::compile_and_run_with_options(src, cfg, opts, exec_entry);
     let analysis = match analysis {
         rv_db::AnalysisResult::Analyzed(a) => a,
         rv_db::AnalysisResult::FrontendError(e) => return Err(e),
@@ -150,13 +324,15 @@ pub fn analyze_unified(src: &str, entry: Option<&str>) -> Result<Report, String>
         .into_iter()
         .map(|o| ObligationResult { origin: o.origin, discharged: o.ok })
         .collect();
+    let lowered = rv_lower::lower_with_lines(&module, &mut syms).ok();
+    let run_display = render_run(lowered.as_ref(), &syms, exec_entry, &run);
 
     // Proof backend: only spin up the kernel when there is a proof fragment to check.
     let mut run = run;
     let (proof_verified, proof_open, proof_run, proofs_erased, runtime_defs) = if has_proof {
         let kernel_entry =
             matches!(entry_frag, Some(Fragment::Proof)).then_some(entry).flatten();
-        let rep = verify_rv(src, kernel_entry)?;
+        let rep = verify_rv_with_cfg(src, kernel_entry, cfg)?;
         // Stage D — a proof-fragment entry yields a real `rv_vm::Value` through the SAME
         // `run` channel as the executable backend (one value model). The rendered string
         // stays available in `proof_run` for display / non-data results.
@@ -187,12 +363,19 @@ pub fn analyze_unified(src: &str, entry: Option<&str>) -> Result<Report, String>
     Ok(Report {
         obligations,
         borrow_errors: analysis.borrow_errors,
+        secondary_borrow_errors: analysis.secondary_borrow_errors,
+        warnings: lint_warnings(&module, lowered.as_ref(), &syms),
         proof_verified,
         proof_open,
         run,
+        run_display,
         proof_run,
         proofs_erased,
         runtime_defs,
+        #[cfg(feature = "query-log")]
+        query_log,
+        #[cfg(feature = "query-log")]
+        query_graph,
     })
 }
 
@@ -223,9 +406,13 @@ fn proof_decl_names(
 /// never, erased used relevantly) is rejected here with a clear message. Ungraded (`ω`,
 /// the default) code is untouched — `check_usage` always passes it — so this never
 /// rejects a proof that doesn't opt into grades.
-fn check_graded_usage(session: &rv_kernel::verify::Session, src: &str) -> Result<(), String> {
+fn check_graded_usage(
+    session: &rv_kernel::verify::Session,
+    src: &str,
+    cfg: &CfgSet,
+) -> Result<(), String> {
     let mut syms = rv_core::Symbols::new();
-    let Ok(module) = rv_syntax::parse(src, &mut syms) else { return Ok(()) };
+    let Ok(module) = rv_syntax::parse_with_cfg(src, &mut syms, cfg) else { return Ok(()) };
     let frags = rv_syntax::classify(&module);
     for name in proof_decl_names(&module, &frags, &syms) {
         session.k.check_usage(&name)?;
@@ -233,20 +420,44 @@ fn check_graded_usage(session: &rv_kernel::verify::Session, src: &str) -> Result
     Ok(())
 }
 
-/// Find the [`Fragment`](rv_syntax::Fragment) of the top-level `fn` named `name`, if any.
-fn entry_fragment(
+/// The [`Fragment`](rv_syntax::Fragment) of the entry point `name`: a top-level
+/// `fn` (or proof declaration) by that name. Err if there is none (E0601), or if
+/// the `fn` cannot be called with no arguments (E0580).
+pub fn find_entry_point(
     module: &rv_syntax::ast::Module,
     frags: &[rv_syntax::Fragment],
     syms: &rv_core::Symbols,
     name: &str,
-) -> Option<rv_syntax::Fragment> {
+) -> Result<rv_syntax::Fragment, String> {
+    use rv_diagnostics::{E0580, E0601};
     use rv_syntax::ast::Item;
-    module.items.iter().zip(frags).find_map(|(item, frag)| match item {
-        Item::Fn(f) if syms.resolve(f.name) == name => Some(*frag),
+    let found = module.items.iter().zip(frags).find_map(|(item, frag)| match item {
+        Item::Fn(f) if syms.resolve(f.name) == name => Some((Some(f), *frag)),
         // `def`/`instance` are always proof-fragment; a matching name is a kernel entry.
-        Item::Def(d) | Item::Instance(d) if syms.resolve(d.name) == name => Some(*frag),
+        Item::Def(d) | Item::Instance(d) if syms.resolve(d.name) == name => Some((None, *frag)),
         _ => None,
-    })
+    });
+    let Some((decl, frag)) = found else {
+        return Err(E0601.tag(format!("no function `{name}` to run as the entry point")));
+    };
+    if let Some(f) = decl {
+        let why = if !f.params.is_empty() {
+            "an entry point takes no parameters"
+        } else if !f.generics.is_empty() {
+            "an entry point has no type parameters"
+        } else {
+            return Ok(frag);
+        };
+        let params: Vec<String> = f.params.iter().map(|p| p.ty.show(syms)).collect();
+        let ret = f.ret.as_ref().map_or(String::new(), |r| format!(" -> {}", r.show(syms)));
+        let sig = format!("fn({}){ret}", params.join(", "));
+        return Err(format!(
+            "line {}: {}",
+            f.body.span.start,
+            E0580.tag(format!("`{name}` has invalid signature `{sig}`: {why}"))
+        ));
+    }
+    Ok(frag)
 }
 
 // ---------------------------------------------------------------------------
@@ -288,6 +499,11 @@ impl RavenReport {
 /// independent re-check harness ([`rv_kernel::recheck_all_definitions`]), which re-verifies
 /// every stored definition from scratch, ignoring how `Session` produced it.
 pub fn verify_rv_session(src: &str) -> Result<rv_kernel::verify::Session, String> {
+    kernel_session(src, &CfgSet::new())
+}
+
+/// [`verify_rv_session`] under the cfg options `cfg`.
+fn kernel_session(src: &str, cfg: &CfgSet) -> Result<rv_kernel::verify::Session, String> {
     let mut session = rv_kernel::verify::Session::new();
     rv_kernel::logic::declare_logic(&mut session.k)?;
     session.k.install_quot()?;
@@ -307,9 +523,10 @@ pub fn verify_rv_session(src: &str) -> Result<rv_kernel::verify::Session, String
     session.k.install_fiber2()?;
     session.k.install_equiv_algebra()?;
     session.k.declare_coinductive(rv_kernel::coinductive::stream_spec())?;
-    run_unified(&mut session, RAVEN_PRELUDE).map_err(|e| format!("in the standard prelude: {e}"))?;
-    run_unified(&mut session, src)?;
-    check_graded_usage(&session, src)?;
+    run_unified(&mut session, RAVEN_PRELUDE, cfg)
+        .map_err(|e| format!("in the standard prelude: {e}"))?;
+    run_unified(&mut session, src, cfg)?;
+    check_graded_usage(&session, src, cfg)?;
     Ok(session)
 }
 
@@ -318,10 +535,19 @@ pub fn verify_rv_session(src: &str) -> Result<rv_kernel::verify::Session, String
 /// is self-contained and brings its own data types (`enum`s) and proofs. This is the unified
 /// `.rv` surface's proof/verification path; obligations are discharged by the kernel.
 pub fn verify_rv(src: &str, entry: Option<&str>) -> Result<RavenReport, String> {
+    verify_rv_with_cfg(src, entry, &CfgSet::new())
+}
+
+/// [`verify_rv`] under the cfg options `cfg`.
+pub fn verify_rv_with_cfg(
+    src: &str,
+    entry: Option<&str>,
+    cfg: &CfgSet,
+) -> Result<RavenReport, String> {
     // The proof path now runs entirely through the **single** `rv-syntax` parser: the
     // prelude and the program are parsed by one lexer+parser and translated to kernel
     // Commands (see `unify`). The kernel re-checks every term.
-    let session = verify_rv_session(src)?;
+    let session = kernel_session(src, cfg)?;
 
     // Grade-driven split (QTT erasure): partition the proof-fragment definitions into the
     // proofs that erase to nothing and the computational definitions that survive as
@@ -331,7 +557,7 @@ pub fn verify_rv(src: &str, entry: Option<&str>) -> Result<RavenReport, String>
     let mut runtime_defs = Vec::new();
     {
         let mut syms = rv_core::Symbols::new();
-        if let Ok(module) = rv_syntax::parse(src, &mut syms) {
+        if let Ok(module) = rv_syntax::parse_with_cfg(src, &mut syms, cfg) {
             let frags = rv_syntax::classify(&module);
             for name in proof_decl_names(&module, &frags, &syms) {
                 match rv_kernel::erase::erase_def(session.k.env(), &name) {
@@ -390,8 +616,10 @@ pub fn vm_eval(src: &str, entry: &str) -> Result<Value, String> {
     session.k.install_fiber2()?;
     session.k.install_equiv_algebra()?;
     session.k.declare_coinductive(rv_kernel::coinductive::stream_spec())?;
-    run_unified(&mut session, RAVEN_PRELUDE).map_err(|e| format!("in the standard prelude: {e}"))?;
-    run_unified(&mut session, src)?;
+    let cfg = CfgSet::new();
+    run_unified(&mut session, RAVEN_PRELUDE, &cfg)
+        .map_err(|e| format!("in the standard prelude: {e}"))?;
+    run_unified(&mut session, src, &cfg)?;
     erased_vm::run_entry_on_vm(session.k.env(), entry)
 }
 
@@ -418,8 +646,10 @@ pub fn nbe_eval(src: &str, entry: &str) -> Result<Value, String> {
     session.k.install_fiber2()?;
     session.k.install_equiv_algebra()?;
     session.k.declare_coinductive(rv_kernel::coinductive::stream_spec())?;
-    run_unified(&mut session, RAVEN_PRELUDE).map_err(|e| format!("in the standard prelude: {e}"))?;
-    run_unified(&mut session, src)?;
+    let cfg = CfgSet::new();
+    run_unified(&mut session, RAVEN_PRELUDE, &cfg)
+        .map_err(|e| format!("in the standard prelude: {e}"))?;
+    run_unified(&mut session, src, &cfg)?;
     let t = session.eval(entry)?;
     term_to_value(session.k.env(), &t)
 }
@@ -450,9 +680,13 @@ fn term_to_value(env: &rv_kernel::Env, t: &rv_kernel::Term) -> Result<Value, Str
 
 /// Parse `src` with the single `rv-syntax` parser, translate to kernel commands, and run
 /// them on `session` (setting the source for span diagnostics).
-fn run_unified(session: &mut rv_kernel::verify::Session, src: &str) -> Result<(), String> {
+fn run_unified(
+    session: &mut rv_kernel::verify::Session,
+    src: &str,
+    cfg: &CfgSet,
+) -> Result<(), String> {
     let mut syms = rv_core::Symbols::new();
-    let module = rv_syntax::parse(src, &mut syms)?;
+    let module = rv_syntax::parse_with_cfg(src, &mut syms, cfg)?;
     let cmds = unify::module_to_commands(&module, &syms)?;
     session.set_source(src);
     session.run_commands(cmds)
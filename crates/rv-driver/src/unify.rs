@@ -90,7 +90,11 @@ impl Tr<'_> {
                 let members = enums.iter().map(|e| self.enum_decl(e)).collect::<Result<_, _>>()?;
                 Ok(Command::Mutual(members))
             }
-            Item::Struct(_) | Item::TypeAlias(_) | Item::Trait(_) | Item::Impl(_) => Err(format!(
+            Item::Struct(_)
+            | Item::TypeAlias(_)
+            | Item::Trait(_)
+            | Item::Impl(_)
+            | Item::Use(_) => Err(format!(
                 "this item form is not yet supported in the unified proof front-end: {item:?}"
             )),
         }
@@ -225,10 +229,14 @@ impl Tr<'_> {
     }
 
     fn stmts_to_expr(&self, stmts: &[Stmt]) -> Result<KExpr, String> {
+        rv_arena::stack::grow(|| self.stmts_to_expr_here(stmts))
+    }
+
+    fn stmts_to_expr_here(&self, stmts: &[Stmt]) -> Result<KExpr, String> {
         match stmts {
             [Stmt::Return(Some(e))] => self.expr(e),
             [Stmt::Expr(e)] => self.expr(e),
-            [Stmt::Let { name, ty, init }, rest @ ..] => {
+            [Stmt::Let { name, ty, init: Some(init), .. }, rest @ ..] => {
                 let ty = ty.as_ref().map(|t| self.ty(t)).transpose()?;
                 Ok(KExpr::Let(
                     self.name(*name),
@@ -246,6 +254,10 @@ impl Tr<'_> {
     // ---- types (which are expressions in the dependent setting) -------------
 
     fn ty(&self, t: &Ty) -> Result<KExpr, String> {
+        rv_arena::stack::grow(|| self.ty_here(t))
+    }
+
+    fn ty_here(&self, t: &Ty) -> Result<KExpr, String> {
         Ok(match t {
             Ty::Adt(s) | Ty::Param(s) => KExpr::Var(self.name(*s), None),
             Ty::Generic { base, args } => {
@@ -256,7 +268,15 @@ impl Tr<'_> {
                 e
             }
             Ty::Term(e) => self.expr(e)?,
-            Ty::I64 | Ty::IntN(_) | Ty::F64 | Ty::Bool | Ty::String | Ty::Unit | Ty::Ref { .. } => {
+            Ty::I64
+            | Ty::IntN(_)
+            | Ty::F64
+            | Ty::Bool
+            | Ty::String
+            | Ty::Unit
+            | Ty::Ref { .. }
+            | Ty::Fn { .. }
+            | Ty::ImplTrait(_) => {
                 return Err(format!("this type is not part of the proof fragment: {t:?}"))
             }
         })
@@ -265,6 +285,10 @@ impl Tr<'_> {
     // ---- expressions --------------------------------------------------------
 
     fn expr(&self, e: &Expr) -> Result<KExpr, String> {
+        rv_arena::stack::grow(|| self.expr_here(e))
+    }
+
+    fn expr_here(&self, e: &Expr) -> Result<KExpr, String> {
         Ok(match e {
             // The cubical layer's interval literals: reserved names, not ordinary
             // variables (see `rv_kernel::surface::Expr::{IZero,IOne}`).
@@ -308,7 +332,7 @@ impl Tr<'_> {
             Expr::MatchExpr { scrut, arms } => {
                 let arms = arms
                     .iter()
-                    .map(|(p, body)| Ok(MatchArm { pat: self.pat(p), body: self.expr(body)? }))
+                    .map(|(p, body)| Ok(MatchArm { pat: self.pat(p)?, body: self.expr(body)? }))
                     .collect::<Result<Vec<_>, String>>()?;
                 KExpr::Match(Box::new(self.expr(scrut)?), arms)
             }
@@ -454,8 +478,8 @@ impl Tr<'_> {
         Ok(head)
     }
 
-    fn pat(&self, p: &Pattern) -> KPat {
-        match p {
+    fn pat(&self, p: &Pattern) -> Result<KPat, String> {
+        Ok(match p {
             Pattern::Wildcard => KPat::Var("_".to_string()),
             Pattern::Variant { enum_name, variant, binds } => {
                 let subs = binds
@@ -467,6 +491,15 @@ impl Tr<'_> {
                     .collect();
                 KPat::Ctor(self.dotted(*enum_name, *variant), subs)
             }
-        }
+            Pattern::Str(_) | Pattern::Float(_) | Pattern::Or(_) => {
+                return Err("literal and or-patterns are not supported in proof terms".to_string())
+            }
+            Pattern::Bare { variant, .. } => {
+                return Err(format!(
+                    "imported variant `{}` in a proof term: write its `Enum::` path",
+                    self.syms.resolve(*variant)
+                ))
+            }
+        })
     }
 }
@@ -1,5 +1,9 @@
 //! End-to-end pipeline tests: source text → verified → compiled → run.
-use rv_driver::{run_pipeline, verify, Value};
+use rv_driver::{
+    analyze_unified_with_options, code_actions, completions, diagnostics, dump_desugared,
+    dump_types, run_pipeline, semantic_tokens, verify, CfgSet, CodegenOptions, CompletionKind,
+    HighlightKind, LineSpan, Value,
+};
 
 /// A program whose call-site precondition and assertion are discharged from
 /// concrete values, and which runs to a known result.
@@ -7,14 +11,14 @@ use rv_driver::{run_pipeline, verify, Value};
 fn div_main_verifies_and_runs() {
     let src = r#"
         fn div(x: i64, y: i64) -> i64
-          requires y != 0;
+          requires y > 0;
         {
           return x / y;
         }
         fn main() -> i64 {
           let a: i64 = 10;
           let b: i64 = 2;
-          assert b != 0;
+          assert b > 0;
           return div(a, b);
         }
     "#;
@@ -174,9 +178,13 @@ fn fixed_width_integers_prove_conversion_and_overflow_bounds() {
     assert_eq!(report.run, Some(Ok(Value::Int(3))));
 }
 
+/// An out-of-range literal is rejected outright; a value only known at run time
+/// leaves a range obligation that cannot be proved.
 #[test]
 fn out_of_range_fixed_width_initializer_is_not_verified() {
-    let src = "fn main() -> u8 { let x: u8 = 300; return x; }";
+    let err = verify("fn main() -> u8 { let x: u8 = 300; return x; }").expect_err("literal");
+    assert!(err.contains("[E0080] integer literal `300` out of range for `u8`"), "got: {err}");
+    let src = "fn main() -> u8 { let n = 300; let x: u8 = n; return x; }";
     let report = verify(src).expect("front-end ok");
     assert!(!report.all_verified(), "out-of-range u8 initialization must not verify");
     assert!(report.obligations.iter().any(|o| o.origin.contains("integer range")));
@@ -210,6 +218,556 @@ fn closure_capture_runs() {
     assert_eq!(report.run, Some(Ok(Value::Int(15))));
 }
 
+/// A function named without being called is a value: stored in a `let`, called
+/// indirectly, and passed to a function taking a `fn(..) -> ..` parameter.
+#[test]
+fn function_pointers_are_values() {
+    let src = r#"
+        fn pick(a: i64, b: i64) -> i64 { if a > b { return a; } return b; }
+        fn apply(g: fn(i64, i64) -> i64, x: i64) -> i64 { return g(x, 7); }
+        fn main() -> i64 {
+            let f = pick;
+            let y = f(2, 3);
+            return apply(pick, y);
+        }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Int(7))));
+
+    let mismatched = r#"
+        fn pick(a: i64) -> i64 { return a; }
+        fn apply(g: fn(bool) -> i64) -> i64 { return g(true); }
+        fn main() -> i64 { return apply(pick); }
+    "#;
+    assert!(verify(mismatched).is_err(), "a function value must match the parameter's fn type");
+}
+
+/// A generic function passed as a value takes its type arguments from the `fn`
+/// type it meets; with nothing to fix them they must be written out.
+#[test]
+fn generic_functions_are_instantiated_as_values() {
+    let src = r#"
+        fn identity<T>(x: T) -> T { return x; }
+        fn apply<A, B>(f: fn(A) -> B, x: A) -> B { return f(x); }
+        fn call(f: fn(i64) -> i64, x: i64) -> i64 { return f(x); }
+        fn main() -> i64 {
+            let y = apply(identity, 5);
+            let b = apply(identity, true);
+            if b { return call(identity, y); }
+            return 0;
+        }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Int(5))));
+
+    let ambiguous = r#"
+        fn identity<T>(x: T) -> T { return x; }
+        fn main() -> i64 { let f = identity; return 0; }
+    "#;
+    let err = verify(ambiguous).expect_err("nothing fixes `T`");
+    assert!(err.contains("[E0282]") && err.contains("identity::<..>"), "{err}");
+
+    let explicit = r#"
+        fn identity<T>(x: T) -> T { return x; }
+        fn main() -> i64 { let f = identity::<i64>; return f(3); }
+    "#;
+    let report = run_pipeline(explicit, Some("main")).expect("front-end ok");
+    assert_eq!(report.run, Some(Ok(Value::Int(3))));
+
+    let mismatched = r#"
+        fn identity<T>(x: T) -> T { return x; }
+        fn main() -> i64 { let f: fn(i64) -> bool = identity; return 0; }
+    "#;
+    assert!(verify(mismatched).unwrap_err().contains("[E0308]"));
+}
+
+/// Calls in statement position run for their effects and are still checked: a
+/// discarded method call carries its callee's precondition.
+#[test]
+fn statement_position_calls_are_checked_and_run() {
+    let src = r#"
+        struct S { a: i64 }
+        impl S {
+            fn show(self, n: i64)
+              requires n > 0;
+            {
+              print(n);
+            }
+        }
+        fn tick() { print(1); }
+        fn main() { let s = S { a: 4 }; s.show(2); tick(); tick(); }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Unit)));
+
+    let violated = src.replace("show(2)", "show(0)");
+    let report = verify(&violated).expect("front-end ok");
+    assert!(!report.all_verified(), "a discarded call must still meet its precondition");
+}
+
+/// `Self` inside an impl names the impl's type: in signatures, struct literals,
+/// paths, patterns, and annotations. A trait's `Self` is instantiated per impl.
+#[test]
+fn self_type_resolves_to_the_impl_type() {
+    let src = r#"
+        struct P { x: i64, y: i64 }
+        impl P {
+            fn origin() -> Self { return Self { x: 0, y: 0 }; }
+            fn same(self, other: Self) -> bool { return self.x == other.x; }
+            fn shifted(self) -> Self { let p: Self = Self { x: 1, y: self.y }; return p; }
+        }
+        enum E { A, B }
+        impl E {
+            fn flip(self) -> Self {
+                match self { Self::A => { return Self::B; } Self::B => { return Self::A; } }
+            }
+        }
+        trait Dup { fn dup(self) -> Self; }
+        impl Dup for P { fn dup(self) -> Self { return Self { x: self.x, y: self.y }; } }
+        impl Dup for E { fn dup(self) -> E { return self; } }
+        fn main() -> i64 {
+            let s = P::origin().dup().shifted();
+            match E::B.dup().flip() {
+                E::B => { return 0; }
+                E::A => { if s.same(P::origin()) { return 1; } return 2; }
+            }
+        }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Int(2))));
+
+    // The trait's `Self` is `P` in `impl Dup for P`, so `-> i64` does not match it.
+    let mismatched = r#"
+        struct P { x: i64 }
+        trait Dup { fn dup(self) -> Self; }
+        impl Dup for P { fn dup(self) -> i64 { return 0; } }
+    "#;
+    let err = verify(mismatched).expect_err("the impl does not match the trait");
+    assert!(err.contains("[E0053] method `dup` in `impl Dup for P` returns `i64`"), "{err}");
+    assert!(err.contains("but the trait declares `P`"), "{err}");
+}
+
+/// `as` converts between numeric types: an integer survives an `f64` round trip.
+#[test]
+fn int_float_cast_round_trip_runs() {
+    let src = "fn main() -> i64 { let x: i64 = 7; let f = x as f64; return (f * 2.5) as i64; }";
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Int(17))));
+}
+
+/// A cast to the operand's own type changes nothing: the value runs through and
+/// what is known about it still verifies.
+#[test]
+fn same_type_cast_is_the_identity() {
+    let src = r#"
+        fn main() -> i64 {
+            let x: i64 = 5;
+            let y = x as i64;
+            assert y == 5;
+            let f: f64 = 2.5;
+            return (f as f64 * 2.0) as i64;
+        }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Int(5))));
+}
+
+/// Float-to-integer casts saturate at the target's bounds instead of wrapping.
+#[test]
+fn float_to_int_cast_saturates() {
+    let src = "fn main() -> i64 { let x: f64 = 100000000000000000000000.0; return x as i64; }";
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert_eq!(report.run, Some(Ok(Value::Int(i64::MAX as i128))));
+}
+
+/// Narrowing integer casts wrap to the target width, and the result verifies
+/// against the narrower return type.
+#[test]
+fn narrowing_int_cast_wraps() {
+    let src = "fn main() -> i32 { let x: i64 = 4294967297; return x as i32; }";
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Int(1))));
+}
+
+#[test]
+fn non_numeric_cast_is_rejected() {
+    let src = r#"
+        struct P { a: i64 }
+        fn main() -> i64 { let p = P { a: 1 }; return p as i64; }
+    "#;
+    let err = verify(src).expect_err("casting a struct must be a type error");
+    assert!(err.contains("cannot cast P to i64"), "got: {err}");
+}
+
+/// `loop` is an expression whose value is carried out by `break value`.
+#[test]
+fn loop_break_value_initializes_a_local() {
+    let src = r#"
+        fn main() -> i64 {
+            let i = 0;
+            let found = loop {
+                i = i + 1;
+                if i * i > 50 { break i; }
+            };
+            return found;
+        }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Int(8))));
+}
+
+/// A labeled `break` leaves the named outer loop, not just the innermost one.
+#[test]
+fn labeled_break_exits_outer_loop() {
+    let src = r#"
+        fn main() -> i64 {
+            let hits = 0;
+            let i = 0;
+            'outer: while i < 10 {
+                let j = 0;
+                loop {
+                    if i * j == 12 { break 'outer; }
+                    if j == 9 { break; }
+                    j = j + 1;
+                }
+                hits = hits + 1;
+                i = i + 1;
+            }
+            return hits * 100 + i;
+        }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Int(202))));
+}
+
+/// A labeled `continue` skips the rest of the inner loop and resumes the outer one.
+#[test]
+fn labeled_continue_resumes_outer_loop() {
+    let src = r#"
+        fn main() -> i64 {
+            let count = 0;
+            let i = 0;
+            'rows: while i < 4 {
+                i = i + 1;
+                let j = 0;
+                while j < 4 {
+                    j = j + 1;
+                    if j == 2 { continue 'rows; }
+                    count = count + 1;
+                }
+            }
+            return count;
+        }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Int(4))));
+}
+
+#[test]
+fn break_value_out_of_while_is_rejected() {
+    let src = "fn main() -> i64 { let i = 0; while i < 3 { break 5; } return i; }";
+    let err = verify(src).expect_err("a while loop has no value to break with");
+    assert!(err.contains("`break` with a value inside a `while` loop"), "got: {err}");
+}
+
+/// A string `match` tests its literal arms in order and falls back to `_`.
+#[test]
+fn string_match_selects_each_arm() {
+    for (input, expected) in [("gold", 1), ("silver", 2), ("tin", 3)] {
+        let src = format!(
+            r#"
+            fn rank(s: String) -> i64 {{
+                match s {{
+                    "gold" => {{ return 1; }}
+                    "silver" => {{ return 2; }}
+                    _ => {{ return 3; }}
+                }}
+            }}
+            fn main() -> i64 {{ return rank("{input}"); }}
+        "#
+        );
+        let report = run_pipeline(&src, Some("main")).expect("front-end ok");
+        assert!(report.all_verified(), "{report:?}");
+        assert_eq!(report.run, Some(Ok(Value::Int(expected))), "rank(\"{input}\")");
+    }
+}
+
+/// Each literal of an or-pattern selects the shared arm.
+#[test]
+fn string_or_pattern_matches_either_literal() {
+    for (input, expected) in [("yes", 1), ("y", 1), ("no", 0)] {
+        let src = format!(
+            r#"
+            fn parse(s: String) -> i64 {{
+                let r = 0;
+                match s {{
+                    "yes" | "y" => {{ r = 1; }}
+                    _ => {{ r = 0; }}
+                }}
+                return r;
+            }}
+            fn main() -> i64 {{ return parse("{input}"); }}
+        "#
+        );
+        let report = run_pipeline(&src, Some("main")).expect("front-end ok");
+        assert_eq!(report.run, Some(Ok(Value::Int(expected))), "parse(\"{input}\")");
+    }
+}
+
+#[test]
+fn string_match_without_wildcard_is_rejected() {
+    let src = r#"
+        fn f(s: String) -> i64 {
+            match s {
+                "a" => { return 1; }
+                "b" => { return 2; }
+            }
+            return 0;
+        }
+    "#;
+    let err = verify(src).expect_err("no set of literals covers every string");
+    assert!(err.contains("[E0004] non-exhaustive string match"), "got: {err}");
+}
+
+/// Float arms compare bit patterns, not IEEE `==`: `-0.0` and `0.0` select
+/// different arms, and NaN matches neither, so it falls through to `_`.
+#[test]
+fn float_match_selects_each_arm() {
+    let cases = [
+        ("0.5", 1),
+        ("0.0 - 2.5", 2),
+        ("4.0", 2),
+        ("0.0 - 0.0", 4),
+        ("z * (z - 1.0)", 5),
+        ("1.0", 3),
+        ("z / z", 3),
+    ];
+    for (input, expected) in cases {
+        let src = format!(
+            r#"
+            fn pick(x: f64) -> i64 {{
+                match x {{
+                    0.5 => {{ return 1; }}
+                    -2.5 | 4.0 => {{ return 2; }}
+                    0.0 => {{ return 4; }}
+                    -0.0 => {{ return 5; }}
+                    _ => {{ return 3; }}
+                }}
+            }}
+            fn main() -> i64 {{ let z = 0.0; return pick({input}); }}
+        "#
+        );
+        let report = run_pipeline(&src, Some("main")).expect("front-end ok");
+        assert!(report.all_verified(), "{report:?}");
+        assert_eq!(report.run, Some(Ok(Value::Int(expected))), "pick({input})");
+    }
+}
+
+/// Matching on a float literal is legal but warned about.
+#[test]
+fn float_match_is_linted() {
+    let src = r#"
+        fn sign(x: f64) -> i64 {
+            match x {
+                0.0 => { return 0; }
+                _ => { return 1; }
+            }
+        }
+    "#;
+    let report = verify(src).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.warnings.len(), 1, "{:?}", report.warnings);
+    assert!(report.warnings[0].contains("warning[float-pattern-match]"), "{:?}", report.warnings);
+}
+
+/// Integer literals must fit the type their context gives them; the check happens
+/// once that type is known, and a leading `-` counts as part of the literal.
+#[test]
+fn integer_literals_are_range_checked() {
+    let report = run_pipeline(
+        "fn main() -> i64 {\n\
+             let max: i64 = 9223372036854775807;\n\
+             let min: i64 = -9223372036854775808;\n\
+             let small: u8 = 255;\n\
+             return wrapping_add(max, min) + 0x10 + 0b1 + 0o7 + 1_000;\n\
+         }",
+        Some("main"),
+    )
+    .expect("in-range literals are accepted");
+    assert_eq!(report.run, Some(Ok(Value::Int(-1 + 16 + 1 + 7 + 1000))));
+
+    let err = verify("fn main() -> i64 {\n    let x: i64 = 1;\n    return 9223372036854775808;\n}")
+        .expect_err("one past i64::MAX");
+    assert_eq!(err, "line 3: [E0080] integer literal `9223372036854775808` out of range for `i64`");
+    let err = verify("fn main() -> i64 {\n    let x = -9223372036854775809;\n    return 0;\n}")
+        .expect_err("one below i64::MIN");
+    let expected = "line 2: [E0080] integer literal `-9223372036854775809` out of range for `i64`";
+    assert_eq!(err, expected);
+    let err = verify("fn main() -> i64 { let b: u8 = 200; let c = b + 256; return 0; }")
+        .expect_err("operand of a u8 addition");
+    assert!(err.contains("integer literal `256` out of range for `u8`"), "got: {err}");
+}
+
+/// A trailing expression is the function's value; with a `;` it is discarded,
+/// the body is `()`, and the lint points at the lost value.
+#[test]
+fn semicolon_terminated_body_is_unit() {
+    let report = run_pipeline("fn add(a: i64, b: i64) -> i64 { wrapping_add(a, b) } \
+         fn main() -> i64 { add(2, 3) }", Some("main"))
+    .expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Int(5))));
+    assert!(report.warnings.is_empty(), "{:?}", report.warnings);
+
+    let report = verify("fn touch(a: i64) { a == 1; } fn main() { touch(1); }").expect("unit body");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.warnings.len(), 1, "{:?}", report.warnings);
+    assert!(report.warnings[0].contains("warning[unused-value]"), "{:?}", report.warnings);
+
+    let err = verify("fn add(a: i64, b: i64) -> i64 { a == b; }").expect_err("returns ()");
+    assert!(err.contains("[E0308]") && err.contains("Unit"), "got: {err}");
+}
+
+#[test]
+fn float_match_without_wildcard_is_rejected() {
+    let src = "fn f(x: f64) -> i64 { match x { 1.0 => { return 1; } } return 0; }";
+    let err = verify(src).expect_err("no set of literals covers every float");
+    assert!(err.contains("[E0004] non-exhaustive float match"), "got: {err}");
+}
+
+/// One moved value used five more times is one mistake: the first use is the
+/// primary borrow error, and the repeats on later lines are its follow-ons.
+#[test]
+fn repeated_use_after_move_reports_once() {
+    let src = r#"
+        fn eat(s: String) -> i64 { return 1; }
+        fn main() -> i64 {
+            let s = "x";
+            let t = s;
+            let a = eat(s);
+            let b = eat(s);
+            let c = eat(s);
+            let d = eat(s);
+            let e = eat(s);
+            return 0;
+        }
+    "#;
+    let report = verify(src).expect("front-end ok");
+    assert_eq!(report.borrow_errors, ["line 6: main: use of moved value `s`"]);
+    let follow_ons: Vec<String> =
+        (7..=10).map(|line| format!("line {line}: main: use of moved value `s`")).collect();
+    assert_eq!(report.secondary_borrow_errors, follow_ons);
+    assert_eq!(report.num_failed(), 1);
+}
+
+/// One generic enum instantiated at two payload types in the same program: each
+/// use site keeps its own type argument, and a mismatched one is an E0308.
+#[test]
+fn generic_enum_instantiations_coexist() {
+    let decls = r#"
+        enum Opt<T> { Some(T), None() }
+        fn get(o: Opt<i64>) -> i64 { match o { Opt::Some(x) => { return x; } _ => { return 0; } } }
+        fn flag(o: Opt<bool>) -> bool { match o { Opt::Some(b) => { return b; } _ => { return false; } } }
+    "#;
+    let ok = format!(
+        "{decls} fn main() -> i64 {{ let n = Opt::None(); let a = get(Opt::Some(5)); \
+         if flag(Opt::Some(true)) && get(n) == 0 {{ return a; }} return 0; }}"
+    );
+    let report = run_pipeline(&ok, Some("main")).expect("front-end ok");
+    assert_eq!(report.run, Some(Ok(Value::Int(5))));
+
+    let bad = format!("{decls} fn main() -> i64 {{ return get(Opt::Some(true)); }}");
+    let err = run_pipeline(&bad, Some("main")).expect_err("Opt<bool> is not Opt<i64>");
+    assert!(err.contains("[E0308]"), "got: {err}");
+}
+
+/// `#[cfg(test)]` items exist only when the `test` option is set; without it a
+/// call to one is an ordinary undefined-function error.
+#[test]
+fn cfg_test_items_are_compiled_only_under_test() {
+    let src = r#"
+        #[cfg(test)]
+        fn helper() -> i64 { return 41; }
+        #[cfg(not(test))]
+        fn helper_count() -> i64 { return 0; }
+        fn main() -> i64 {
+            return helper();
+        }
+    "#;
+    let mut cfg = rv_driver::CfgSet::new();
+    cfg.enable("test");
+    let report = rv_driver::analyze_unified_with_cfg(src, Some("main"), &cfg).expect("front-end ok");
+    assert_eq!(report.run, Some(Ok(Value::Int(41))));
+
+    let err = rv_driver::analyze_unified(src, Some("main")).expect_err("helper is cfg'd out");
+    assert!(err.contains("line 7: [E0001] call to undefined function `helper`"), "got: {err}");
+}
+
+/// A generated 50k-link operator chain is a front-end error, not a stack overflow
+/// in lowering or any later pass.
+#[test]
+fn deep_operator_chain_is_rejected_cleanly() {
+    let src = format!("fn main() -> i64 {{ return 1{}; }}", " + 1".repeat(50_000));
+    let err = verify(&src).expect_err("the chain is far past the nesting limit");
+    assert!(err.contains("expression nesting too deep"), "got: {err}");
+}
+
+/// Long generated sums and deep nesting within the limit get through the whole
+/// front end on `rvc`'s 8 MiB main-thread stack: a chain's links are not nesting.
+#[test]
+fn long_operator_chains_and_deep_nesting_are_accepted() {
+    let sum = format!("fn main() -> i64 {{ return 1{}; }}", " + 1".repeat(3000));
+    let n = rv_syntax::DEFAULT_NESTING_LIMIT - 8;
+    let nested = format!("fn main() -> i64 {{ return {}1{}; }}", "(1 + ".repeat(n), ")".repeat(n));
+    std::thread::Builder::new()
+        .stack_size(8 << 20)
+        .spawn(move || {
+            for src in [sum, nested] {
+                let cfg = CfgSet::new();
+                dump_types(&src, &cfg).expect("elaborates");
+                dump_desugared(&src, &cfg).expect("desugars");
+            }
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+/// `if` blocks nested to the limit verify and run on an 8 MiB stack, like `rvc`'s
+/// main thread; one level more is a front-end error. The function body and the
+/// innermost statement take a level each.
+#[test]
+fn ifs_nested_to_the_limit_verify_and_one_more_is_rejected() {
+    let nested = |n: usize| {
+        let ifs = format!("{}x = 1;\n{}", "if true {\n".repeat(n), "}\n".repeat(n));
+        format!("fn main() -> i64 {{\nlet x = 0;\n{ifs}return x;\n}}\n")
+    };
+    let n = rv_syntax::DEFAULT_NESTING_LIMIT - 2;
+    let (at, past) = (nested(n), nested(n + 1));
+    std::thread::Builder::new()
+        .stack_size(8 << 20)
+        .spawn(move || {
+            let report = rv_driver::analyze_unified(&at, Some("main")).expect("front-end ok");
+            assert!(report.all_verified(), "{report:?}");
+            assert_eq!(report.run, Some(Ok(Value::Int(1))));
+            let err = rv_driver::analyze_unified(&past, Some("main")).expect_err("too deep");
+            assert!(err.contains("expression nesting too deep"), "got: {err}");
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
 /// Type soundness: a `bool` body under an `-> i64` signature is a static type error
 /// (the executable checker enforces primitive return types, not just structure).
 #[test]
@@ -250,6 +808,110 @@ fn trait_impl_method_signature_is_checked() {
     assert!(verify(src).is_err(), "trait impl parameter types must match the declaration");
 }
 
+/// A trait impl provides exactly the trait's methods, each with the declared
+/// receiver, parameter and return types, and type parameters and their bounds.
+#[test]
+fn trait_impls_conform_to_the_trait() {
+    let decls = "
+        trait Ord { fn rank(self) -> i64; }
+        trait Shape {
+            fn area(self) -> i64;
+            fn grown(self, by: i64) -> Self;
+            fn pick<T: Ord>(self, a: T, b: T) -> T;
+        }
+        struct Sq { w: i64 }
+    ";
+    let methods = [
+        "fn area(self) -> i64 { return self.w * self.w; }",
+        "fn grown(self, by: i64) -> Sq { return Sq { w: self.w + by }; }",
+        "fn pick<U: Ord>(self, a: U, b: U) -> U { return a; }",
+    ];
+    let src = |methods: &[&str]| format!("{decls} impl Shape for Sq {{ {} }}", methods.join(" "));
+    verify(&src(&methods)).expect("a conforming impl is accepted");
+
+    let cases = [
+        (0, "", "[E0046] impl of trait `Shape` for `Sq` is missing method `area`"),
+        (
+            0,
+            "fn area(self, k: i64) -> i64 { return k; }",
+            "has 1 parameter but the trait declares 0",
+        ),
+        (0, "fn area() -> i64 { return 0; }", "has no `self` receiver"),
+        (
+            0,
+            "fn area(self) -> bool { return true; }",
+            "returns `bool` but the trait declares `i64`",
+        ),
+        (
+            1,
+            "fn grown(self, by: bool) -> Sq { return self; }",
+            "has parameter `by: bool` but the trait declares `i64`",
+        ),
+        (
+            1,
+            "fn grown(self, by: i64) -> i64 { return by; }",
+            "returns `i64` but the trait declares `Sq`",
+        ),
+        (
+            2,
+            "fn pick(self, a: i64, b: i64) -> i64 { return a; }",
+            "has 0 type parameters but the trait declares 1",
+        ),
+        (
+            2,
+            "fn pick<U>(self, a: U, b: U) -> U { return a; }",
+            "bounds type parameter `U` by nothing but the trait declares `Ord`",
+        ),
+        (2, "fn pick<U: Ord, V>(self, a: U, b: U) -> U { return a; }", "has 2 type parameters"),
+        (
+            2,
+            "fn pick<U: Ord>(self, a: U, b: i64) -> U { return a; }",
+            "has parameter `b: i64` but the trait declares `U`",
+        ),
+    ];
+    for (i, method, msg) in cases {
+        let mut ms = methods;
+        ms[i] = method;
+        let err = verify(&src(&ms)).expect_err(method);
+        assert!(err.contains(msg), "{method}: {err}");
+        if i > 0 || !method.is_empty() {
+            assert!(err.contains("[E0053]") && err.contains("in `impl Shape for Sq`"), "{err}");
+        }
+    }
+    let perimeter = "fn perimeter(self) -> i64 { return 4 * self.w; }";
+    let err = verify(&src(&[&methods[..], &[perimeter]].concat())).expect_err(perimeter);
+    let msg = "[E0407] method `perimeter` in `impl Shape for Sq` is not a member of trait `Shape`";
+    assert!(err.contains(msg), "{err}");
+}
+
+/// An inherent method wins over a trait method of the same name; a qualified
+/// `Trait::method(recv)` call picks the named trait's impl.
+#[test]
+fn inherent_methods_take_precedence_over_trait_methods() {
+    let src = r#"
+        trait Canvas { fn draw(self) -> i64; }
+        trait Plotter { fn draw(self) -> i64; }
+        struct P { x: i64 }
+        impl Canvas for P { fn draw(self) -> i64 { return 1; } }
+        impl P { fn draw(self) -> i64 { return 3; } }
+        impl Plotter for P { fn draw(self) -> i64 { return 2; } }
+        fn inherent() -> i64 { let p = P { x: 0 }; return p.draw(); }
+        fn canvas() -> i64 { let p = P { x: 0 }; return Canvas::draw(p); }
+        fn plotter() -> i64 { let p = P { x: 0 }; return Plotter::draw(p); }
+    "#;
+    for (entry, expected) in [("inherent", 3), ("canvas", 1), ("plotter", 2)] {
+        let report = run_pipeline(src, Some(entry)).expect("front-end ok");
+        assert_eq!(report.run, Some(Ok(Value::Int(expected))), "{entry}");
+    }
+
+    // Without the inherent method, `p.draw()` names two traits' methods.
+    let ambiguous = src.replace("impl P { fn draw(self) -> i64 { return 3; } }", "");
+    let err = verify(&ambiguous).expect_err("`p.draw()` is ambiguous");
+    assert!(err.contains("[E0034]"), "{err}");
+    assert!(err.contains("`Canvas`") && err.contains("`Plotter`"), "{err}");
+    assert!(err.contains("`Canvas::draw(..)`"), "{err}");
+}
+
 #[test]
 fn unknown_trait_impl_is_rejected() {
     let src = r#"
@@ -379,6 +1041,173 @@ fn try_operator_runs() {
     assert_eq!(report.run, Some(Ok(Value::Int(6))));
 }
 
+/// The prelude's `Result` needs no declaration: `?` chains two fallible calls
+/// and returns the first failure unchanged.
+#[test]
+fn prelude_result_propagates_the_first_error() {
+    let src = r#"
+        fn parse(x: i64) -> Result<i64, i64> {
+          if x > 100 { return Result::Err(1); }
+          return Result::Ok(x);
+        }
+        fn halve(x: i64) -> Result<i64, i64> {
+          if x % 2 == 1 { return Result::Err(2); }
+          return Result::Ok(x / 2);
+        }
+        fn both(x: i64) -> Result<i64, i64> {
+          let p: i64 = parse(x)?;
+          let h: i64 = halve(p)?;
+          return Result::Ok(h);
+        }
+        fn code(r: Result<i64, i64>) -> i64 {
+          match r { Result::Ok(v) => { return v; } Result::Err(e) => { return e; } }
+        }
+        fn even() -> i64 { return code(both(8)); }
+        fn large() -> i64 { return code(both(101)); }
+        fn odd() -> i64 { return code(both(3)); }
+    "#;
+    for (entry, want) in [("even", 4), ("large", 1), ("odd", 2)] {
+        let report = run_pipeline(src, Some(entry)).expect("front-end ok");
+        assert!(report.all_verified(), "{report:?}");
+        assert_eq!(report.run, Some(Ok(Value::Int(want))), "{entry}");
+    }
+}
+
+/// The prelude's methods on `Option`, called like any other method.
+#[test]
+fn prelude_option_methods_run() {
+    let src = r#"
+        fn double(x: i64) -> i64 { return wrapping_add(x, x); }
+        fn main() -> i64 {
+          let four: Option<i64> = Option::Some(4);
+          let none: Option<i64> = Option::None;
+          let a: i64 = four.map(double).unwrap_or(0);
+          let b: i64 = none.unwrap_or(5);
+          let some: Option<i64> = Option::Some(1);
+          let other: Option<i64> = Option::None;
+          if some.is_some() && other.is_none() { return wrapping_add(a, b); }
+          return 0;
+        }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Int(13))));
+}
+
+/// The prelude's `abs`, `min` and `max` on `i64` and `f64`, and a module's own
+/// `impl i64`, called on literals, locals and call results.
+#[test]
+fn methods_on_primitive_types_run() {
+    let src = r#"
+        impl i64 {
+          fn double(self) -> i64 { return wrapping_add(self, self); }
+        }
+        fn seven() -> i64 { return 7; }
+        fn main() -> i64 {
+          let x: i64 = -5;
+          let y = 3;
+          let f: f64 = 2.5;
+          if (0.0 - 1.5).abs() > f.min(1.0) && f.max(0.5) > 2.0 {
+            let a: i64 = wrapping_add(x.abs(), y.max(seven()).double());
+            return wrapping_add(a, wrapping_add(seven().min(2), (0 - 1).abs()));
+          }
+          return 0;
+        }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Int(22))));
+}
+
+/// A module's own method on a primitive replaces the prelude's of that name, and
+/// a trait cannot be implemented for one.
+#[test]
+fn primitive_methods_shadow_the_prelude_and_take_no_trait_impls() {
+    let src = r#"
+        impl i64 { fn abs(self) -> i64 { return 42; } }
+        fn main() -> i64 { let x: i64 = 3; return x.abs(); }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert_eq!(report.run, Some(Ok(Value::Int(42))));
+
+    let src = "trait Show { fn show(self) -> i64; }
+impl Show for i64 { fn show(self) -> i64 { return 1; } }";
+    let err = verify(src).expect_err("a trait impl on `i64`");
+    assert!(err.contains("trait impls for the primitive type `i64` are not supported"), "{err}");
+}
+
+/// `?` needs somewhere to send the failure: a function returning `i64` has none.
+#[test]
+fn try_outside_a_fallible_function_is_rejected() {
+    let src = "fn get(v: Option<i64>) -> i64 {
+    let x: i64 = v?;
+    return x;
+}";
+    let err = verify(src).expect_err("`?` in an `i64` function");
+    assert!(err.contains("line 2: [E0277] the `?` operator on a `Option` value"), "{err}");
+    assert!(err.contains("but this function returns `i64`"), "{err}");
+}
+
+/// A program per registered code, failing wherever in the pipeline that code is
+/// raised. Every `[E....]` tag the driver reports resolves through `rvc explain`,
+/// and each program reports the code it was written for.
+#[test]
+fn emitted_error_codes_have_explanations() {
+    let shapes = "enum Shape { Circle(i64), Square(i64) }";
+    let both = "enum Shape { Circle(i64), Square(i64) } enum Mark { Circle, Cross }";
+    let canvas = "trait Canvas { fn draw(self) -> i64; } trait Plotter { fn draw(self) -> i64; }
+        struct P { x: i64 }
+        impl Canvas for P { fn draw(self) -> i64 { return 1; } }
+        impl Plotter for P { fn draw(self) -> i64 { return 2; } }";
+    let square = "trait Shape { fn area(self) -> i64; } struct Sq { w: i64 }";
+    let area = "fn area(self) -> i64 { return 1; }";
+    let cases = [
+        ("E0001", "", "fn main() -> i64 { return total; }".to_string()),
+        ("E0004", "", r#"fn main() -> i64 { match "a" { "a" => { return 1; } } }"#.into()),
+        ("E0034", canvas, "fn main() -> i64 { let p = P { x: 0 }; return p.draw(); }".into()),
+        ("E0046", square, "impl Shape for Sq {} fn main() {}".into()),
+        ("E0053", square, "impl Shape for Sq { fn area(self) {} } fn main() {}".into()),
+        ("E0061", "fn one(a: i64) -> i64 { return a; }", "fn main() -> i64 { return one(); }".into()),
+        ("E0080", "", "fn main() { let x: u8 = 256; }".into()),
+        ("E0107", "fn pair<A, B>(a: A, b: B) {}", "fn main() { let p = pair::<i64>; }".into()),
+        ("E0252", both, "use Shape::Circle; use Mark::Circle; fn main() {}".into()),
+        ("E0268", "", "fn main() { break; }".into()),
+        ("E0277", "", "fn main() -> i64 { let x: i64 = Option::Some(1)?; return x; }".into()),
+        ("E0282", "fn identity<T>(x: T) -> T { return x; }", "fn main() { let f = identity; }".into()),
+        ("E0308", "", "fn main() -> i64 { return true; }".into()),
+        ("E0381", "", "fn main() -> i64 { let y: i64; if true { y = 1; } return y; }".into()),
+        ("E0407", square, format!("impl Shape for Sq {{ {area} fn sides(self) {{}} }} fn main() {{}}")),
+        ("E0415", "fn add(x: i64, x: i64) {}", "fn main() {}".into()),
+        ("E0426", "", "fn main() { loop { break 'outer; } }".into()),
+        ("E0432", shapes, "use Shape::Triangle; fn main() {}".into()),
+        ("E0433", shapes, "fn main() { let s = Shape::Circel(1); }".into()),
+        ("E0562", "trait T { fn t(self) -> i64; }", "fn f(x: impl T) {} fn main() {}".into()),
+        ("E0571", "", "fn main() { while true { break 1; } }".into()),
+        ("E0580", "", "fn main(x: i64) -> i64 { return x; }".into()),
+        ("E0601", "", "fn helper() -> i64 { return 1; }".into()),
+        ("E0605", "struct P { x: i64 }", "fn main() -> i64 { return P { x: 1 } as i64; }".into()),
+        ("E0609", shapes, "fn main() -> i64 { return Shape::Circle(3).0; }".into()),
+        ("E0659", both, "use Shape::*; use Mark::*; fn main() { let c = Circle(1); }".into()),
+    ];
+    for (code, prelude, program) in &cases {
+        let src = format!("{prelude}\n{program}");
+        let reported = match rv_driver::analyze_unified(&src, Some("main")) {
+            Err(e) => vec![e],
+            Ok(r) => r.borrow_errors.into_iter().chain(r.warnings).collect(),
+        };
+        let text = reported.concat();
+        let tags: Vec<&str> =
+            text.match_indices("[E").filter_map(|(i, _)| text.get(i + 1..i + 6)).collect();
+        assert!(tags.contains(code), "expected [{code}], got: {text}");
+        for tag in tags {
+            assert!(rv_diagnostics::explain(tag).is_ok(), "[{tag}] has no explanation: {text}");
+        }
+    }
+    let mut tested: Vec<&str> = cases.iter().map(|(code, ..)| *code).collect();
+    tested.sort_unstable();
+    assert_eq!(tested, rv_diagnostics::ALL, "every registered code needs a program here");
+}
+
 /// Overflow safety: a bounded sum is proved to stay within range.
 #[test]
 fn bounded_addition_verifies_no_overflow() {
@@ -397,30 +1226,165 @@ fn bounded_addition_verifies_no_overflow() {
     assert!(report.obligations.iter().any(|o| o.origin.contains("overflow")));
 }
 
-/// Soundness guard: an unbounded `a + b` can overflow, so it must NOT verify.
+/// Soundness guard: an unbounded `a + b` can overflow, so it must NOT verify.
+#[test]
+fn unbounded_addition_is_not_verified() {
+    let src = r#"
+        fn add(a: i64, b: i64) -> i64 { return a + b; }
+    "#;
+    let report = verify(src).expect("front-end ok");
+    assert!(!report.all_verified(), "unbounded a+b can overflow; must not be proved safe");
+    assert!(report.num_failed() >= 1);
+    assert!(report.obligations.iter().any(|o| o.origin.contains("overflow")));
+}
+
+/// The `wrapping_*` opt-out: `wrapping_add` emits NO overflow obligation, so an
+/// unbounded wrapping sum verifies (and runs with native wrapping arithmetic).
+#[test]
+fn wrapping_addition_opts_out_of_overflow() {
+    let src = r#"
+        fn add(a: i64, b: i64) -> i64 { return wrapping_add(a, b); }
+        fn main() -> i64 { return add(2, 3); }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "wrapping_add must not require an overflow proof: {report:?}");
+    assert!(!report.obligations.iter().any(|o| o.origin.contains("overflow")));
+    assert_eq!(report.run, Some(Ok(Value::Int(5))));
+}
+
+/// `wrapping_*` results on the default `Int` wrap at 64 bits, like `i64`.
+#[test]
+fn wrapping_arithmetic_wraps_at_64_bits() {
+    let src = r#"
+        fn next(a: i64) -> i64 { return wrapping_add(a, 1); }
+        fn main() -> i64 { return next(9223372036854775807); }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert_eq!(report.run, Some(Ok(Value::Int(i64::MIN as i128))));
+}
+
+/// Signed division overflows only at `MIN / -1`, and negation only at `-MIN`: a
+/// non-zero divisor or an unbounded operand is not enough to verify either.
+#[test]
+fn division_and_negation_overflow_are_obligations() {
+    let div = |pre: &str| {
+        let src = format!("fn div(x: i64, y: i64) -> i64 requires {pre}; {{ return x / y; }}");
+        verify(&src).expect("front-end ok")
+    };
+    let report = div("y != 0");
+    assert!(!report.all_verified(), "i64::MIN / -1 overflows: {report:?}");
+    assert!(report.obligations.iter().any(|o| o.origin.contains("overflow (division)") && !o.ok()));
+    assert!(div("y > 0").all_verified());
+    assert!(div("y != 0 && x > 0").all_verified());
+    // A literal divisor other than -1 rules the case out without an obligation.
+    let halve = verify("fn halve(x: i64) -> i64 { return x / 2; }").expect("front-end ok");
+    assert!(!halve.obligations.iter().any(|o| o.origin.contains("overflow")), "{halve:?}");
+
+    let neg = |pre: &str| {
+        let src = format!("fn neg(x: i64) -> i64 requires {pre}; {{ return -x; }}");
+        verify(&src).expect("front-end ok")
+    };
+    assert!(!neg("true").all_verified());
+    assert!(neg("x >= 0 && x <= 100").all_verified());
+}
+
+/// Bitwise and shift operators end to end: they carry no obligations, shift
+/// amounts are taken modulo 64, and `<<` wraps at 64 bits.
+#[test]
+fn bitwise_and_shift_operators_run() {
+    let src = r#"
+        fn mix(a: i64, s: i64) -> i64 { return (a << s) ^ (a >> 1) | a & 6; }
+        fn main() -> i64 {
+            let m = mix(5, 66);
+            let top = 1 << 63;
+            if top == 0 - 9223372036854775807 - 1 { return m; }
+            return 0;
+        }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    // (5 << 2) ^ (5 >> 1) | (5 & 6) = 20 ^ 2 | 4 = 22
+    assert_eq!(report.run, Some(Ok(Value::Int(22))));
+}
+
+/// `return` in expression position leaves the function from the middle of a
+/// `let` initializer or a call argument; the rest of that expression never runs.
+#[test]
+fn expression_return_leaves_the_function() {
+    let src = r#"
+        fn pick(c: bool) -> i64 {
+            let x = loop {
+                if c { return 1 }
+                break 20;
+            };
+            let y: i64 = loop { break wrapping_add(x, 300); };
+            return y;
+        }
+        fn check(n: i64) -> i64 { return n; }
+        fn guard(c: bool) -> i64 {
+            while c { let r = check(return 4000); }
+            return 0;
+        }
+        fn early(n: i64) -> i64 { let v = loop { break return n; }; return wrapping_add(v, 1); }
+        fn main() -> i64 {
+            let a = wrapping_add(pick(true), pick(false));
+            return wrapping_add(wrapping_add(a, guard(true)), early(50000));
+        }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    // 1 + 320 + 4000 + 50000
+    assert_eq!(report.run, Some(Ok(Value::Int(54321))));
+}
+
+/// An `if` expression's value is the tail of the branch taken; a branch that
+/// returns gives none, so the `if` has the other branch's type.
 #[test]
-fn unbounded_addition_is_not_verified() {
+fn if_expression_takes_the_branch_value() {
     let src = r#"
-        fn add(a: i64, b: i64) -> i64 { return a + b; }
+        fn pick(c: bool) -> i64 {
+            let x = if c { return 0 } else { 5 };
+            return x;
+        }
+        fn sign(n: i64) -> i64 {
+            let s = if n < 0 { 0 - 1 } else if n == 0 { 0 } else { let one = 1; one };
+            return s;
+        }
+        fn main() -> i64 {
+            let a = wrapping_add(pick(true), pick(false));
+            let b = wrapping_add(sign(0 - 7), wrapping_add(sign(0), sign(9)));
+            return wrapping_add(a, if b == 0 { 10 } else { 20 });
+        }
     "#;
-    let report = verify(src).expect("front-end ok");
-    assert!(!report.all_verified(), "unbounded a+b can overflow; must not be proved safe");
-    assert!(report.num_failed() >= 1);
-    assert!(report.obligations.iter().any(|o| o.origin.contains("overflow")));
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    // 0 + 5 + (-1 + 0 + 1 == 0 => 10)
+    assert_eq!(report.run, Some(Ok(Value::Int(15))));
 }
 
-/// The `wrapping_*` opt-out: `wrapping_add` emits NO overflow obligation, so an
-/// unbounded wrapping sum verifies (and runs with native wrapping arithmetic).
+/// An `if` ending a branch of an if expression, with its `else`, is that
+/// branch's value; one elsewhere in the branch is a statement, its tails
+/// returning from the function.
 #[test]
-fn wrapping_addition_opts_out_of_overflow() {
+fn if_ending_an_if_expression_branch_is_its_value() {
     let src = r#"
-        fn add(a: i64, b: i64) -> i64 { return wrapping_add(a, b); }
-        fn main() -> i64 { return add(2, 3); }
+        fn grade(n: i64) -> i64 {
+            let g = if n < 10 {
+                if n < 5 { 1 } else { 2 }
+            } else {
+                if n == 99 { 7 }
+                3
+            };
+            return g;
+        }
+        fn main() -> i64 {
+            let tens = wrapping_mul(grade(7), 10);
+            return wrapping_add(grade(3), wrapping_add(tens, wrapping_mul(grade(99), 100)));
+        }
     "#;
     let report = run_pipeline(src, Some("main")).expect("front-end ok");
-    assert!(report.all_verified(), "wrapping_add must not require an overflow proof: {report:?}");
-    assert!(!report.obligations.iter().any(|o| o.origin.contains("overflow")));
-    assert_eq!(report.run, Some(Ok(Value::Int(5))));
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Int(721))));
 }
 
 /// Generics (type-erased) + a method (`impl` desugared to a function + resolved call).
@@ -443,6 +1407,341 @@ fn generics_and_methods_run() {
     assert_eq!(report.run, Some(Ok(Value::Int(7))));
 }
 
+/// A generic function calling another generic function needs no per-instance copy:
+/// the VM is type-erased, so every level of the chain runs at any type.
+#[test]
+fn nested_generic_calls_run() {
+    let src = r#"
+        fn c<T>(x: T) -> T { return x; }
+        fn b<T>(x: T) -> T { return c(x); }
+        fn a<T>(x: T) -> T { return b(x); }
+        fn main() -> i64 {
+          let flag = a(true);
+          if flag { return a(7); }
+          return 0;
+        }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Int(7))));
+}
+
+/// A single-variant enum's fields read directly as `.0`, `.1`, ...; on an enum with
+/// several variants a field access is an error pointing at `match`.
+#[test]
+fn enum_field_access() {
+    let src = r#"
+        struct P { x: i64 }
+        enum Wrap { Only(P, bool) }
+        fn main() -> i64 {
+          let w = Wrap::Only(P { x: 4 }, true);
+          if w.1 { return w.0.x; }
+          return 0;
+        }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Int(4))));
+
+    for access in ["s.0", "s.radius"] {
+        let src = format!(
+            "enum Shape {{ Circle(i64), Square(i64) }}
+             fn main() -> i64 {{ let s = Shape::Circle(3); return {access}; }}"
+        );
+        let err = verify(&src).expect_err("multi-variant enums have no fields");
+        assert!(err.contains("[E0609]"), "{err}");
+        let hint = "matching on its variants: `Shape::Circle`, `Shape::Square`";
+        assert!(err.contains(hint), "{err}");
+    }
+
+    let missing = "enum Wrap { Only(i64) } fn main() -> i64 { let w = Wrap::Only(3); return w.1; }";
+    let err = verify(missing).expect_err("`Only` has one field");
+    assert!(err.contains("[E0609]") && err.contains("has fields `.0`"), "{err}");
+}
+
+/// A name bound twice in one declaration's list is rejected; a `let` shadowing a
+/// parameter is not a duplicate.
+#[test]
+fn duplicate_names_are_rejected() {
+    let cases = [
+        ("fn f(x: i64, x: i64) -> i64 { return x; }", "the parameters of `f`"),
+        ("fn f<T, T>(x: T) -> T { return x; }", "the type parameters of `f`"),
+        ("fn f() -> i64 { return 0; } fn f() -> i64 { return 1; }", "the module's functions"),
+        ("struct P { x: i64, x: i64 }", "the fields of struct `P`"),
+        ("enum E { A, A }", "the variants of enum `E`"),
+        (
+            "struct S { v: i64 } impl S { fn m(self) -> i64 { return 0; } \
+             fn m(self) -> i64 { return 1; } }",
+            "the methods of `impl S`",
+        ),
+        (
+            "struct S { v: i64 } impl S { fn m(self, a: i64, a: i64) -> i64 { return a; } }",
+            "the parameters of `S::m`",
+        ),
+    ];
+    for (decl, place) in cases {
+        let src = format!("{decl} fn main() -> i64 {{ return 0; }}");
+        let err = verify(&src).expect_err(decl);
+        assert!(err.contains("[E0415]") && err.contains(place), "{err}");
+    }
+
+    let shadow = "fn f(x: bool) -> i64 { let x = 2; return x; }
+                  fn main() -> i64 { return f(true); }";
+    let report = run_pipeline(shadow, Some("main")).expect("shadowing a parameter is allowed");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Int(2))));
+}
+
+/// Float comparisons are IEEE: each special value compared at runtime agrees with
+/// Rust's `f64` operators, a float match arm compares bit patterns instead (so
+/// `0.0` does not match `-0.0`), and the verifier assumes nothing about a float comparison's outcome.
+#[test]
+fn float_comparisons_follow_ieee() {
+    let values = "fn nan() -> f64 { let z = 0.0; return z / z; }
+        fn inf() -> f64 { let z = 0.0; return 1.0 / z; }
+        fn neg_zero() -> f64 { let z = 0.0; return z * (z - 1.0); }
+        fn one() -> f64 { return 1.0; }";
+    let (nan, inf, neg_zero) = (f64::NAN, f64::INFINITY, -0.0_f64);
+    let cases = [
+        ("nan() == nan()", nan == nan),
+        ("nan() != nan()", nan != nan),
+        ("nan() < one()", nan < 1.0),
+        ("nan() >= one()", nan >= 1.0),
+        ("one() > nan()", 1.0 > nan),
+        ("neg_zero() == 0.0", neg_zero == 0.0),
+        ("neg_zero() < 0.0", neg_zero < 0.0),
+        ("inf() > one()", inf > 1.0),
+        ("inf() == inf()", inf == inf),
+        ("0.0 - inf() < inf()", -inf < inf),
+    ];
+    for (cmp, want) in cases {
+        let src = format!("{values} fn t() -> bool {{ let r = {cmp}; return r; }}");
+        let report = run_pipeline(&src, Some("t")).expect(cmp);
+        assert_eq!(report.run, Some(Ok(Value::Bool(want))), "{cmp}");
+    }
+
+    let matched = format!(
+        "{values} fn main() -> i64 {{
+            let r = 0;
+            match neg_zero() {{ 0.0 => {{ r = 1; }} _ => {{ r = 2; }} }}
+            return r;
+        }}"
+    );
+    let report = run_pipeline(&matched, Some("main")).expect("a float match runs");
+    assert_eq!(report.run, Some(Ok(Value::Int(2))));
+
+    // `a == a` is false for NaN, so it cannot rule out the second return.
+    let reflexive = "fn g(a: f64) -> i64 ensures result > 0; { if a == a { return 1; } return 0; }
+        fn main() -> i64 { return 0; }";
+    let report = run_pipeline(reflexive, None).expect("pipeline runs");
+    assert!(!report.all_verified(), "{report:?}");
+    let err = verify("fn g(a: f64) -> f64 ensures result == a; { return a; }
+        fn main() -> i64 { return 0; }")
+    .expect_err("a float in a specification");
+    assert!(err.contains("the float `result` cannot appear in a specification"), "{err}");
+}
+
+/// A `Head::name` path resolves through the same lookup in expressions and
+/// patterns: an enum variant, or an associated function of a type. A segment that
+/// names nothing is reported with the close matches to it.
+#[test]
+fn paths_resolve_or_name_the_failed_segment() {
+    let decls = "enum Shape { Circle(i64), Square(i64) }
+        struct P { a: i64 }
+        impl P { fn new() -> P { return P { a: 1 }; } }";
+    let ok = format!(
+        "{decls} fn main() -> i64 {{
+            let p = P::new();
+            let s = Shape::Square(p.a);
+            match s {{ Shape::Circle(r) => {{ return r; }} Shape::Square(w) => {{ return w; }} }}
+        }}"
+    );
+    let report = run_pipeline(&ok, Some("main")).expect("paths resolve");
+    assert_eq!(report.run, Some(Ok(Value::Int(1))));
+
+    let cases = [
+        ("let s = Shape::Circel(1);", "no variant or associated function `Circel` in `Shape`; \
+          did you mean `Circle`?"),
+        ("let p = P::nwe();", "no associated function `nwe` in `P`; did you mean `new`?"),
+        ("let p = shape::new();", "no type or trait `shape` in this module; did you mean `Shape`?"),
+        ("let q = P::zzz();", "no associated function `zzz` in `P`"),
+        (
+            "let s = Shape::Circle(1); match s { Shape::Sqare(w) => { return w; } _ => {} }",
+            "no variant `Sqare` in `Shape`; did you mean `Square`?",
+        ),
+    ];
+    for (stmt, msg) in cases {
+        let src = format!("{decls} fn main() -> i64 {{ {stmt} return 0; }}");
+        let err = verify(&src).expect_err(stmt);
+        assert!(err.contains("[E0433] failed to resolve") && err.contains(msg), "{err}");
+    }
+}
+
+/// The quick fix offered for a misspelled path or variable, or for a literal
+/// match without `_`, applies cleanly and makes the error go away.
+#[test]
+fn code_actions_fix_the_error_they_are_offered_for() {
+    let cases = [
+        "enum Shape { Circle(i64), Square(i64) }
+fn main() -> i64 {
+    let s = Shape::Circel(1);
+    return 0;
+}",
+        "fn main() -> i64 {
+    let total = 1;
+    return totl + 1;
+}",
+        "fn f(s: String) -> i64 {
+    match s {
+        \"a\" => { return 1; }
+    }
+    return 0;
+}",
+    ];
+    for src in cases {
+        let before = diagnostics(src);
+        assert_eq!(before.len(), 1, "{src}");
+        let fixes = code_actions(src, LineSpan::new(1, 10));
+        let [fix] = &fixes[..] else { panic!("{before:?}: {fixes:?}") };
+        let fixed = fix.apply(src);
+        verify(&fixed).unwrap_or_else(|e| panic!("{}: {e}\n{fixed}", fix.title));
+        // Every fix edits line 3 or 4; a range elsewhere offers none.
+        assert!(code_actions(src, LineSpan::new(1, 2)).is_empty());
+    }
+}
+
+/// Completion after `point.` lists the fields, then the methods as calls; a
+/// half-typed name completes to a local bound above the cursor, not one below.
+#[test]
+fn completions_follow_the_cursor() {
+    let src = "struct Point { x: i64, y: i64 }
+impl Point {
+    fn length(self) -> i64 { return wrapping_add(self.x, self.y); }
+}
+fn main() -> i64 {
+    let point = Point { x: 3, y: 4 };
+    let n = point.
+    return 0;
+}";
+    let items = completions(src, 7, 18);
+    let got: Vec<(&str, &str)> =
+        items.iter().map(|c| (c.label.as_str(), c.edit.text.as_str())).collect();
+    assert_eq!(got, [("x", "x"), ("y", "y"), ("length", "length()")]);
+    assert_eq!(items[2].detail, "fn length(self) -> i64");
+    assert_eq!(items[2].kind, CompletionKind::Method);
+
+    let src = "fn main() -> i64 {
+    let total = 1;
+    let t = tot
+    let totem = 2;
+    return total;
+}";
+    let got: Vec<String> = completions(src, 3, 15).into_iter().map(|c| c.label).collect();
+    assert_eq!(got, ["total"]);
+}
+
+/// Highlighting colours every token but whitespace, tells types, functions and
+/// variables apart, and splits a string that spans lines.
+#[test]
+fn semantic_tokens_classify_each_token() {
+    use HighlightKind::*;
+    let src = "struct P { x: i64 }
+fn get(p: P) -> f64 {
+    // the field
+    let s = \"two
+lines\"; return 1.5;
+}";
+    let got: Vec<(u32, &str, HighlightKind)> = semantic_tokens(src)
+        .into_iter()
+        .map(|t| {
+            let text = src.lines().nth(t.line as usize - 1).unwrap();
+            (t.line, &text[t.start as usize..t.end as usize], t.kind)
+        })
+        .collect();
+    assert_eq!(got, [
+        (1, "struct", Keyword), (1, "P", Type), (1, "{", Operator), (1, "x", Variable),
+        (1, ":", Operator), (1, "i64", Type), (1, "}", Operator),
+        (2, "fn", Keyword), (2, "get", Function), (2, "(", Operator), (2, "p", Variable),
+        (2, ":", Operator), (2, "P", Type), (2, ")", Operator), (2, "->", Operator),
+        (2, "f64", Type), (2, "{", Operator),
+        (3, "// the field", Comment),
+        (4, "let", Keyword), (4, "s", Variable), (4, "=", Operator), (4, "\"two", String),
+        (5, "lines\"", String), (5, ";", Operator), (5, "return", Keyword),
+        (5, "1.5", Number), (5, ";", Operator),
+        (6, "}", Operator),
+    ]);
+}
+
+/// A `match` on a reference matches the value behind it: through `&E` and `&&E`
+/// to the variants (binding payload copies), and through `&String` to literals.
+#[test]
+fn match_on_a_reference_derefs_the_scrutinee() {
+    let cases = [
+        (
+            "let e = Shape::Circle(5); let r: &Shape = &e;
+             match r { Shape::Circle(d) => { return d; } Shape::Dot => { return 0; } }",
+            5,
+        ),
+        (
+            "let e = Shape::Dot; let r = &e; let rr: &&Shape = &r;
+             match rr { Shape::Circle(d) => { return d; } Shape::Dot => { return 7; } }",
+            7,
+        ),
+        (
+            "let s: String = \"b\"; let r = &s;
+             match r { \"a\" => { return 1; } \"b\" => { return 2; } _ => { return 0; } }",
+            2,
+        ),
+    ];
+    for (body, want) in cases {
+        let src = format!("enum Shape {{ Circle(i64), Dot }} fn main() -> i64 {{ {body} }}");
+        let report = run_pipeline(&src, Some("main")).expect("front-end ok");
+        assert!(report.all_verified(), "{report:?}");
+        assert_eq!(report.run, Some(Ok(Value::Int(want))), "{body}");
+    }
+
+    // A reference lowering cannot see is rejected rather than switched on.
+    let hidden = "enum Shape { Circle(i64), Dot } fn id<T>(x: T) -> T { return x; }
+        fn main() -> i64 { let e = Shape::Dot; let r = id(&e);
+          match r { Shape::Circle(d) => { return d; } Shape::Dot => { return 7; } } }";
+    let err = verify(hidden).expect_err("the scrutinee is still a reference");
+    assert!(err.contains("[E0308] cannot match on `&Shape`"), "{err}");
+    let compare = "fn main() -> i64 { let s: String = \"a\"; let r = &s;
+        if r == \"a\" { return 1; } return 0; }";
+    let err = verify(compare).expect_err("comparing a reference with a value");
+    assert!(err.contains("cannot compare `&String` with `String`"), "{err}");
+}
+
+/// A helper hands back `&mut` to a field of a struct its caller owns, and the
+/// caller assigns a field through the result: directly, through an associated
+/// function, through a reference to the whole struct, and through a binding that
+/// is not itself `mut`. Every write lands in the caller's struct.
+#[test]
+fn field_writes_through_returned_references_reach_the_owner() {
+    let src = "
+        struct Config { threshold: i64, limit: i64 }
+        struct App { config: Config, id: i64 }
+        impl App {
+            fn config_of(app: &mut App) -> &mut Config { return &mut app.config; }
+        }
+        fn config_of(app: &mut App) -> &mut Config { return &mut app.config; }
+        fn whole(app: &mut App) -> &mut App { return app; }
+        fn main() -> i64 {
+            let mut app = App { config: Config { threshold: 1, limit: 2 }, id: 3 };
+            config_of(&mut app).threshold = 5;
+            App::config_of(&mut app).limit = 20;
+            whole(&mut app).id = 300;
+            let c = config_of(&mut app);
+            if c.threshold != 5 { return 1; }
+            c.threshold = c.limit;
+            if app.config.threshold != 20 { return 2; }
+            return app.id;
+        }";
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Int(300))));
+}
+
 /// References: take `&mut`, mutate through it, observe at the source.
 #[test]
 fn mutable_reference_mutation_runs() {
@@ -817,3 +2116,523 @@ fn stage_d_native_agrees_with_kernel() {
         assert_eq!(native, kernel, "native VM disagrees with kernel for `{entry}`");
     }
 }
+
+/// The IR lints report at the source line of the statement they are about: the
+/// `if` whose condition is a literal, and only the first of two stores to `x`.
+#[test]
+fn ir_lints_point_at_the_source_statement() {
+    let src = "fn pick(a: i64, b: i64) -> i64 {\n\
+               if true { return a; } else { return b; }\n\
+               }\n\
+               fn twice(p: i64) -> i64 {\n\
+               let x = wrapping_add(p, 1);\n\
+               x = wrapping_add(p, 2);\n\
+               return x;\n\
+               }";
+    let report = verify(src).expect("front-end ok");
+    let w = &report.warnings;
+    assert_eq!(w.len(), 2, "{w:?}");
+    assert!(w[0].starts_with("line 2: warning[constant-condition]"), "{w:?}");
+    assert!(w[1].starts_with("line 5: warning[dead-store]") && w[1].contains("`x`"), "{w:?}");
+}
+
+/// A returned struct holding an enum (and a generic enum around it) is shown in
+/// source syntax, on both entry paths.
+#[test]
+fn run_values_render_symbolically() {
+    let src = "enum Shape { Circle(i64), Square(i64, i64), Empty }\n\
+               struct Tagged { id: i64, shape: Shape, ok: bool }\n\
+               enum Opt<T> { Nothing, Just(T) }\n\
+               fn tagged() -> Tagged {\n\
+                   return Tagged { id: 7, shape: Shape::Square(2, 3), ok: true };\n\
+               }\n\
+               fn wrapped() -> Opt<Shape> { return Opt::Just(Shape::Circle(4)); }\n\
+               fn empty() -> Shape { return Shape::Empty; }";
+    let cases = [
+        ("tagged", "Tagged { id: 7, shape: Shape::Square(2, 3), ok: true }"),
+        ("wrapped", "Opt::Just(Shape::Circle(4))"),
+        ("empty", "Shape::Empty"),
+    ];
+    for (entry, expected) in cases {
+        let report = run_pipeline(src, Some(entry)).expect("front-end ok");
+        assert_eq!(report.run_display.as_deref(), Some(expected), "{report:?}");
+        let report = rv_driver::analyze_unified(src, Some(entry)).expect("front-end ok");
+        assert_eq!(report.run_display.as_deref(), Some(expected), "{report:?}");
+    }
+}
+
+/// A `let` without an initializer is accepted when every path assigns it before
+/// the read, and rejected when one path skips the assignment.
+#[test]
+fn deferred_let_initialization_is_checked_on_every_path() {
+    let src = r#"
+        fn pick(flag: bool) -> i64 {
+            let best: i64;
+            if flag { best = 1; } else { best = 2; }
+            return best;
+        }
+        fn main() -> i64 { return pick(false); }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Int(2))));
+
+    let src = "fn pick(flag: bool) -> i64 { let best; if flag { best = 1; } return best; }";
+    let err = verify(src).expect_err("`best` is unassigned when `flag` is false");
+    assert!(err.contains("[E0381] use of possibly-uninitialized variable `best`"), "got: {err}");
+}
+
+/// `default()` builds the zero value of the annotated type, recursing into
+/// struct fields; an enum has no default variant to pick.
+#[test]
+fn default_builds_a_zeroed_struct() {
+    let src = r#"
+        struct Point { x: i64, y: i64 }
+        struct Labeled { at: Point, shown: bool, name: String }
+        fn main() -> i64 {
+            let p: Point = default();
+            let l: Labeled;
+            l = default();
+            if l.shown || l.at.y != 0 || l.name != "" { return 1; }
+            return p.x;
+        }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Int(0))));
+
+    let src = "enum E { A, B }\nfn main() -> i64 { let e: E = default(); return 0; }";
+    let err = verify(src).expect_err("an enum has no default value");
+    assert!(err.contains("enum `E` has no default value"), "got: {err}");
+    let src = "fn main() -> i64 { let e = default(); return 0; }";
+    let err = verify(src).expect_err("nothing names the type to default");
+    assert!(err.contains("`default()` needs a known type"), "got: {err}");
+}
+
+/// A unit variant is a value and a pattern without parentheses. Matching all
+/// three variants of a field-less enum needs no `_` arm.
+#[test]
+fn bare_unit_variants_construct_and_match() {
+    let src = r#"
+        enum Color { Red, Green, Blue }
+        fn code(c: Color) -> i64 {
+            match c {
+                Color::Red => { return 1; }
+                Color::Green => { return 2; }
+                Color::Blue => { return 3; }
+            }
+        }
+        fn main() -> i64 { let c = Color::Green; return code(c); }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Int(2))));
+    assert_eq!(report.run_display.as_deref(), Some("2"));
+
+    let src = "enum Shape { Circle(i64), Empty }\n\
+               fn main() -> Shape { return Shape::Empty; }";
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert_eq!(report.run_display.as_deref(), Some("Shape::Empty"));
+}
+
+/// A bare path names a unit variant: one with fields is an arity error both
+/// as a value and as a pattern.
+#[test]
+fn bare_path_to_a_payload_variant_is_rejected() {
+    let src = "enum Shape { Circle(i64), Empty }\n\
+               fn main() -> i64 { let s = Shape::Circle; return 0; }";
+    let err = verify(src).expect_err("`Circle` carries a field");
+    assert!(err.contains("variant `Circle` expects 1 field(s), got 0"), "got: {err}");
+
+    let src = "enum Shape { Circle(i64), Empty }\n\
+               fn area(s: Shape) -> i64 {\n\
+                   match s { Shape::Circle => { return 1; } Shape::Empty => { return 0; } }\n\
+               }";
+    let err = verify(src).expect_err("the pattern omits `Circle`'s field");
+    assert!(err.contains("variant `Circle` expects 1 field(s), got 0"), "got: {err}");
+    assert!(err.contains("`Shape::Circle(_)`"), "got: {err}");
+}
+
+/// A function or method returning `impl Trait` returns the one concrete type its
+/// body does, and callers use it through the trait. Elsewhere, and where the
+/// body's types disagree, it is rejected.
+#[test]
+fn impl_trait_return_types_stand_for_the_concrete_type() {
+    let src = r#"
+        trait Area { fn area(self) -> i64; }
+        struct Square { w: i64 }
+        impl Area for Square { fn area(self) -> i64 { return self.w; } }
+        struct Maker { w: i64 }
+        impl Maker { fn make(self) -> impl Area { return Square { w: self.w }; } }
+        fn square(w: i64) -> impl Area { return Square { w: w }; }
+        fn main() -> i64 {
+            let m = Maker { w: 4 };
+            if m.make().area() != 4 { return 1; }
+            let s = square(7);
+            return s.area();
+        }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Int(7))));
+
+    let two = "trait Area { fn area(self) -> i64; }\n\
+               struct Square { w: i64 }\nstruct Circle { r: i64 }\n\
+               impl Area for Square { fn area(self) -> i64 { return self.w; } }\n\
+               impl Area for Circle { fn area(self) -> i64 { return self.r; } }\n";
+    let src = format!(
+        "{two}fn make(w: i64) -> impl Area {{\n\
+             if w > 0 {{ return Square {{ w: w }}; }}\n\
+             return Circle {{ r: w }};\n\
+         }}"
+    );
+    let err = verify(&src).expect_err("the branches return different types");
+    assert!(err.contains("[E0308] return type is `impl Area` but the branches return"), "{err}");
+    assert!(err.contains("`Square` at line 7, and `Circle` here"), "got: {err}");
+
+    let src = format!("{two}fn total(a: impl Area) -> i64 {{ return 0; }}");
+    let err = verify(&src).expect_err("`impl Trait` is a return type only");
+    assert!(err.contains("[E0562] `impl Trait` in argument position"), "got: {err}");
+
+    let src = "trait Area { fn area(self) -> i64; }\nstruct Square { w: i64 }\n\
+               fn make() -> impl Area { return Square { w: 1 }; }";
+    let err = verify(src).expect_err("`Square` has no `Area` impl");
+    assert!(err.contains("type `Square` does not implement trait `Area`"), "got: {err}");
+}
+
+/// The entry point must exist and take no arguments; it is checked before
+/// anything runs, and may return any type.
+#[test]
+fn the_entry_point_is_found_and_its_signature_checked() {
+    let src = "fn main() -> bool { return true; }";
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert_eq!(report.run, Some(Ok(Value::Bool(true))));
+
+    let src = "fn helper() -> i64 { return 1; }\n\nfn main(n: i64) -> i64 {\n    return n;\n}";
+    let err = run_pipeline(src, Some("main")).expect_err("`main` takes a parameter");
+    let want = "line 3: [E0580] `main` has invalid signature `fn(i64) -> i64`";
+    assert!(err.starts_with(want), "got: {err}");
+    let err = rv_driver::analyze_unified("fn main<T>() {}", Some("main"));
+    let err = err.expect_err("`main` is generic");
+    assert!(err.contains("an entry point has no type parameters"), "got: {err}");
+
+    let src = "fn start() -> i64 { return 0; }";
+    let err = rv_driver::analyze_unified(src, Some("main")).expect_err("there is no `main`");
+    assert!(err.contains("[E0601] no function `main` to run as the entry point"), "got: {err}");
+    assert!(rv_driver::analyze_unified(src, Some("start")).is_ok());
+    let checked = rv_driver::analyze_unified(src, None);
+    assert!(checked.is_ok(), "a module checked without running needs no entry");
+
+    let src = "fn main() -> i64 { return 1; }\nfn main() -> i64 { return 2; }";
+    let err = run_pipeline(src, Some("main")).expect_err("two `main`s");
+    assert!(err.contains("[E0415] identifier `main` is bound more than once"), "got: {err}");
+}
+
+/// `format` fills each `{}` with the next argument, rendered, and reads its
+/// arguments without moving them. The placeholders and arguments must agree,
+/// and the format string must be a literal.
+#[test]
+fn format_renders_its_arguments_into_the_literal() {
+    let src = r#"
+        fn main() -> String {
+            let name = "pt";
+            let small: u8 = 200;
+            let s = format("{}: {} {{ok}} {} {} {}", name, 0 - 3, 1.5, true, small);
+            return format("[{}|{}]", s, name);
+        }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Str("[pt: -3 {ok} 1.5 true 200|pt]".to_string()))));
+
+    let src = "fn main() -> String {\n    return format(\"{} and {}\", 1, 2, 3);\n}";
+    let err = verify(src).expect_err("three arguments for two placeholders");
+    assert_eq!(err, "line 2: [E0061] format string expects 2 arguments but 3 were supplied");
+    let src = "fn main() -> String { let f = \"{}\"; return format(f, 1); }";
+    let err = verify(src).expect_err("the format string is not a literal");
+    assert!(err.contains("the first argument of `format` must be a string literal"), "{err}");
+    let src = "struct P { x: i64 }\n\
+               fn main() -> String { let p = P { x: 1 }; return format(\"{}\", p); }";
+    let err = verify(src).expect_err("a struct has no rendering");
+    assert!(err.contains("[E0277] `format` cannot render a value of type `P`"), "got: {err}");
+}
+
+#[test]
+fn literal_suffixes_fix_the_literal_type() {
+    let src = r#"
+        fn main() -> u32 {
+            let big = 4_000_000_000u32;
+            let small = -128i8;
+            assert_eq(small as i64, -128);
+            return big + 5;
+        }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Int(4_000_000_005))));
+
+    // The suffix, not the context, types the literal: `x` is a `u8`.
+    let src = "fn main() -> i64 { let x = 5u8; let y = x + 300; return 0; }";
+    let err = verify(src).expect_err("300 is no u8");
+    assert_eq!(err, "line 1: [E0080] integer literal `300` out of range for `u8`");
+    let src = "fn take(a: f64) -> f64 { return a; }\nfn main() -> f64 { return take(5i32); }";
+    let err = verify(src).expect_err("an i32 is no f64");
+    assert!(err.contains("expected Float, got IntN"), "{err}");
+    let err = verify("fn main() -> i64 { let a = -1u8; return 0; }").expect_err("no negative u8");
+    assert!(err.contains("integer literal `-1` out of range for `u8`"), "{err}");
+}
+
+#[test]
+fn assert_eq_fails_the_run_with_both_values_rendered() {
+    let src = r#"
+        struct Point { x: i64, y: i64 }
+        fn main() -> i64 {
+            let a = Point { x: 1, y: 2 };
+            assert_eq(a, Point { x: 1, y: 2 });
+            let small: u8 = 3;
+            assert_ne(small, 4);
+            assert_eq(a, Point { x: 1, y: 3 });
+            return 0;
+        }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    let expected = "assertion failed: left == right\n  left: Point { x: 1, y: 2 }\n \
+                    right: Point { x: 1, y: 3 }";
+    assert_eq!(report.run, Some(Err(expected.to_string())));
+
+    let src = "fn main() -> i64 { assert_ne(\"a\", \"a\"); return 0; }";
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    let expected = "assertion failed: left != right\n  left: \"a\"\n right: \"a\"";
+    assert_eq!(report.run, Some(Err(expected.to_string())));
+
+    let src = "struct P { x: i64 }\n\
+               fn main() -> i64 { let p = P { x: 1 }; assert_eq(p, 1); return 0; }";
+    let err = verify(src).expect_err("a struct is not an integer");
+    assert!(err.contains("[E0308] `assert_eq` compares two values of one type"), "{err}");
+    let err = verify("fn main() -> i64 { assert_eq(1); return 0; }").expect_err("one argument");
+    assert!(err.contains("[E0061] `assert_eq` takes 2 arguments but 1 was supplied"), "{err}");
+}
+
+/// A reference to a function's own local cannot outlive the call: not returned,
+/// directly or through a callee that hands its argument back, and not stored
+/// through a parameter. Values and references the caller passed in are fine.
+#[test]
+fn references_to_locals_do_not_escape_their_function() {
+    let defs = "struct S { a: i64 }\nstruct H { r: &S }\n\
+                fn keep(x: &S) -> &S { return x; }\n\
+                fn peek(x: &S) -> i64 { return x.a; }\n\
+                fn put(h: &mut H, x: &S) { h.r = x; }\n";
+    let escapes = |body: &str| {
+        let report = run_pipeline(&format!("{defs}{body}"), None).expect("front-end ok");
+        report.borrow_errors
+    };
+    let ok = "fn make() -> S { let s = S { a: 1 }; return s; }\n\
+              fn read() -> i64 { let s = S { a: 1 }; return peek(&s); }\n\
+              fn pass(x: &S) -> &S { return keep(x); }\n\
+              fn deep(n: i64, x: &S) -> &S { if n == 0 { return x; } return deep(n - 1, x); }";
+    assert_eq!(escapes(ok), Vec::<String>::new());
+
+    let direct = "fn bad() -> &S { let s = S { a: 1 }; return &s; }";
+    assert_eq!(escapes(direct), ["bad: cannot return a reference to local variable `s`"]);
+    let through_call = "fn bad() -> &S { let s = S { a: 1 }; return keep(&s); }";
+    assert_eq!(escapes(through_call), ["bad: cannot return a reference to local variable `s`"]);
+    let stored = "fn bad(h: &mut H) { let s = S { a: 1 }; put(h, &s); }";
+    let want = "bad: cannot store a reference to local variable `s` through parameter `h`";
+    assert_eq!(escapes(stored), [format!("{want}, which outlives it")]);
+}
+
+#[test]
+fn use_imports_enum_variants() {
+    let src = r#"
+        enum Shape { Circle(i64), Square(i64), Empty }
+        use Shape::*;
+        use Option::Some;
+
+        fn side(s: Shape) -> i64 {
+            match s {
+                Circle(r) => { return r; }
+                Square(w) => { return w; }
+                Empty => { return 0; }
+            }
+        }
+
+        fn main() -> i64 {
+            // A local binding keeps its name over an import.
+            let Empty = 2;
+            assert_eq(side(Square(Empty)), 2);
+            assert_eq(side(Shape::Empty), 0);
+            let mut n = 0;
+            match Some(side(Circle(3))) { Some(v) => { n = v; } Option::None => {} }
+            return n;
+        }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Int(3))));
+
+    let two_globs = "enum A { X, Y }\nenum B { X }\nuse A::*;\nuse B::*;\n\
+                     fn main() -> i64 {\n  let y = Y;\n  let x = X;\n  return 0;\n}";
+    let err = verify(two_globs).expect_err("`X` is ambiguous");
+    assert_eq!(
+        err,
+        "line 7: [E0659] `X` is ambiguous: `use A::*` and `use B::*` both import it; write \
+         `A::X` or `B::X`, or import one by name"
+    );
+    // An explicit import settles it.
+    let settled = two_globs.replace("use B::*;", "use B::*;\nuse B::X;");
+    verify(&settled).expect("`X` is `B::X`");
+
+    let err = verify("enum A { Xy }\nuse A::Yx;\nfn main() -> i64 { return 0; }").unwrap_err();
+    assert_eq!(err, "line 2: [E0432] unresolved import `A::Yx`: no variant `Yx` in `A`");
+    let err = verify("enum Abc { X }\nuse Abd::*;\nfn main() -> i64 { return 0; }").unwrap_err();
+    assert!(err.contains("[E0432] unresolved import `Abd::*`: no enum `Abd` in this module; \
+                          did you mean `Abc`?"), "{err}");
+    let err = verify("enum A { X }\nenum B { X }\nuse A::X;\nuse B::X;\nfn main() {}").unwrap_err();
+    assert!(err.starts_with("line 4: [E0252] `X` is imported twice"), "{err}");
+    let src = "enum A { X }\nuse A::X;\nfn main() -> i64 { match A::X { Z => { return 0; } } }";
+    let err = verify(src).unwrap_err();
+    assert!(err.starts_with("line 3: pattern `Z` is not a variant path"), "{err}");
+}
+
+/// Desugaring spells out `Self` and imported variants, and each statement it
+/// prints names the line it was written on. The printed module is source that
+/// infers exactly as the original did.
+#[test]
+fn desugared_dump_spells_out_self_and_imports() {
+    let src = "enum Shape { Circle(i64), Empty }
+use Shape::*;
+struct Counter { n: i64 }
+impl Counter {
+    fn bump(self, by: i64) -> Self
+        requires by >= 0;
+    {
+        return Self { n: self.n + by };
+    }
+}
+fn area(s: Shape) -> i64 {
+    match s {
+        Circle(r) => { return r * r; }
+        Empty => { return 0; }
+    }
+}
+fn main() -> i64 {
+    let c = Counter { n: 1 }.bump(2);
+    if c.n > 2 && !false { return area(Circle(c.n)); }
+    return area(Empty);
+}";
+    let cfg = CfgSet::new();
+    let dump = dump_desugared(src, &cfg).expect("desugars");
+    assert_eq!(
+        dump,
+        "enum Shape { Circle(i64), Empty }
+
+struct Counter { n: i64 }
+
+impl Counter {
+    fn bump(self, by: i64) -> Counter
+        requires by >= 0;
+    {
+        return Counter { n: self.n + by };  // line 8
+    }
+}
+
+fn area(s: Shape) -> i64 {
+    match s {  // line 12
+        Shape::Circle(r) => {
+            return r * r;  // line 13
+        }
+        Shape::Empty => {
+            return 0;  // line 14
+        }
+    }
+}
+
+fn main() -> i64 {
+    let c = Counter { n: 1 }.bump(2);  // line 18
+    if (c.n > 2) && (!false) {  // line 19
+        return area(Shape::Circle(c.n));  // line 19
+    }
+    return area(Shape::Empty);  // line 20
+}
+"
+    );
+    assert_eq!(dump_types(&dump, &cfg), dump_types(src, &cfg));
+}
+
+/// `()` is an ordinary value: it binds, passes, and returns like any other, and
+/// comparing two units is decided without looking at them (and linted).
+#[test]
+fn unit_values_bind_return_and_compare() {
+    let src = r#"
+        fn nothing() -> () { let u: () = (); return u; }
+        fn take(_u: ()) { }
+        fn main() -> bool {
+            let x = nothing();
+            take(x);
+            let same = nothing() == nothing();
+            assert same;
+            return same;
+        }
+    "#;
+    let report = run_pipeline(src, Some("main")).expect("front-end ok");
+    assert!(report.all_verified(), "{report:?}");
+    assert_eq!(report.run, Some(Ok(Value::Bool(true))));
+    let w = &report.warnings;
+    assert_eq!(w.len(), 1, "{w:?}");
+    assert!(w[0].contains("warning[unit-comparison]"), "{w:?}");
+    assert!(w[0].contains("always `true`"), "{w:?}");
+
+    let report = run_pipeline(&src.replace("== nothing()", "!= nothing()"), Some("main"))
+        .expect("front-end ok");
+    assert!(!report.all_verified(), "`()` != `()` is false, so the assert fails");
+}
+
+/// A two-variant enum without payloads runs as a boolean: built, matched, and
+/// compared, it behaves as with `Adt` values, and still renders by name. One
+/// with a payload keeps the general representation.
+#[test]
+fn flag_enums_run_as_booleans() {
+    let src = r#"
+        enum Flag { Off, On }
+        struct Lamp { power: Flag }
+        fn flip(f: Flag) -> Flag {
+            match f {
+                Flag::Off => { return Flag::On; }
+                Flag::On => { return Flag::Off; }
+            }
+        }
+        fn main() -> Lamp {
+            let f = flip(flip(Flag::On));
+            if f == Flag::On { return Lamp { power: flip(f) }; }
+            return Lamp { power: f };
+        }
+    "#;
+    let run = |src: &str, bool_enums: bool| {
+        let opts = CodegenOptions { bool_enums, ..CodegenOptions::default() };
+        let report = analyze_unified_with_options(src, Some("main"), &CfgSet::new(), &opts)
+            .expect("front-end ok");
+        assert!(report.all_verified(), "{report:?}");
+        report
+    };
+    let flag = run(src, true);
+    let adt = run(src, false);
+    let lamp = |power| Value::Adt { tag: 0, fields: vec![power] };
+    assert_eq!(flag.run, Some(Ok(lamp(Value::Bool(false)))));
+    assert_eq!(adt.run, Some(Ok(lamp(Value::Adt { tag: 0, fields: vec![] }))));
+    assert_eq!(flag.run_display.as_deref(), Some("Lamp { power: Flag::Off }"));
+    assert_eq!(flag.run_display, adt.run_display);
+
+    let src = r#"
+        enum Level { Off, On(i64) }
+        fn main() -> Level {
+            match Level::On(2) {
+                Level::Off => { return Level::On(1); }
+                Level::On(_) => { return Level::Off; }
+            }
+        }
+    "#;
+    let payload = run(src, true);
+    assert_eq!(payload.run, Some(Ok(Value::Adt { tag: 0, fields: vec![] })));
+    assert_eq!(payload.run_display.as_deref(), Some("Level::Off"));
+}
@@ -0,0 +1,398 @@
+//! Random well-typed proof-fragment programs.
+//!
+//! A [`Program`] is a few non-recursive helper functions plus a nullary `entry`, over a
+//! fixed vocabulary: unary `Nat` with `add`, a `Bool` enum with `not`/`and`, a three-variant
+//! `Color`, and `Pair` — a one-constructor enum standing in for a struct, since struct
+//! literals are not part of the proof fragment. Field access is a `match` that destructures
+//! the pair, and `if` is a `match` on `Bool`. Helpers and `entry` are `def`s, so they are
+//! proof-fragment items whatever their bodies look like. Every expression is generated at a
+//! requested type, so the program type-checks by construction.
+
+use std::fmt;
+
+/// The fixed declarations every generated program starts with.
+const LIBRARY: &str = "\
+enum Nat { Zero, Succ(Nat) }
+enum Bool { True, False }
+enum Color { Red, Green, Blue }
+enum Pair { Mk(Nat, Bool) }
+fn add(n: Nat, m: Nat) -> Nat {
+    match n { | Nat::Zero => m | Nat::Succ(k) => Nat::Succ(add(k, m)) }
+}
+fn not(b: Bool) -> Bool {
+    match b { | Bool::True => Bool::False | Bool::False => Bool::True }
+}
+fn and(a: Bool, b: Bool) -> Bool {
+    match a { | Bool::True => b | Bool::False => Bool::False }
+}
+";
+
+const COLORS: [&str; 3] = ["Red", "Green", "Blue"];
+
+/// SplitMix64: tiny, seedable, and good enough to pick program shapes.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A uniform index in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ty {
+    Nat,
+    Bool,
+    Color,
+    Pair,
+}
+
+const TYS: [Ty; 4] = [Ty::Nat, Ty::Bool, Ty::Color, Ty::Pair];
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Ty::Nat => "Nat",
+            Ty::Bool => "Bool",
+            Ty::Color => "Color",
+            Ty::Pair => "Pair",
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    Nat(u32),
+    Bool(bool),
+    /// An index into [`COLORS`].
+    Color(u8),
+    Var(String, Ty),
+    Succ(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Mk(Box<Expr>, Box<Expr>),
+    /// `match pair { | Pair::Mk(a, b) => a }` (or `=> b` when `second`).
+    Field { pair: Box<Expr>, second: bool, binds: (String, String) },
+    /// `match cond { | Bool::True => then | Bool::False => els }`.
+    If { cond: Box<Expr>, then: Box<Expr>, els: Box<Expr> },
+    MatchColor { scrut: Box<Expr>, arms: Box<[Expr; 3]> },
+    /// `match scrut { | Nat::Zero => zero | Nat::Succ(pred) => succ }`.
+    MatchNat { scrut: Box<Expr>, zero: Box<Expr>, pred: String, succ: Box<Expr> },
+    Let { name: String, ty: Ty, init: Box<Expr>, body: Box<Expr> },
+    /// A call to the helper `f{func}`.
+    Call { func: usize, args: Vec<Expr> },
+}
+
+impl Expr {
+    pub fn ty(&self, fns: &[Func]) -> Ty {
+        match self {
+            Expr::Nat(_) | Expr::Succ(_) | Expr::Add(..) => Ty::Nat,
+            Expr::Bool(_) | Expr::Not(_) | Expr::And(..) => Ty::Bool,
+            Expr::Color(_) => Ty::Color,
+            Expr::Mk(..) => Ty::Pair,
+            Expr::Var(_, ty) => *ty,
+            Expr::Field { second, .. } => if *second { Ty::Bool } else { Ty::Nat },
+            Expr::If { then, .. } => then.ty(fns),
+            Expr::MatchColor { arms, .. } => arms[0].ty(fns),
+            Expr::MatchNat { zero, .. } => zero.ty(fns),
+            Expr::Let { body, .. } => body.ty(fns),
+            Expr::Call { func, .. } => fns[*func].ret,
+        }
+    }
+
+    /// The smallest closed value of `ty`.
+    pub fn smallest(ty: Ty) -> Expr {
+        match ty {
+            Ty::Nat => Expr::Nat(0),
+            Ty::Bool => Expr::Bool(false),
+            Ty::Color => Expr::Color(0),
+            Ty::Pair => Expr::Mk(Box::new(Expr::Nat(0)), Box::new(Expr::Bool(false))),
+        }
+    }
+
+    pub fn children(&self) -> Vec<&Expr> {
+        match self {
+            Expr::Nat(_) | Expr::Bool(_) | Expr::Color(_) | Expr::Var(..) => Vec::new(),
+            Expr::Succ(e) | Expr::Not(e) | Expr::Field { pair: e, .. } => vec![e],
+            Expr::Add(a, b) | Expr::And(a, b) | Expr::Mk(a, b) => vec![a, b],
+            Expr::If { cond, then, els } => vec![cond, then, els],
+            Expr::MatchColor { scrut, arms } => {
+                let mut v: Vec<&Expr> = vec![scrut];
+                v.extend(arms.iter());
+                v
+            }
+            Expr::MatchNat { scrut, zero, succ, .. } => vec![scrut, zero, succ],
+            Expr::Let { init, body, .. } => vec![init, body],
+            Expr::Call { args, .. } => args.iter().collect(),
+        }
+    }
+
+    pub fn children_mut(&mut self) -> Vec<&mut Expr> {
+        match self {
+            Expr::Nat(_) | Expr::Bool(_) | Expr::Color(_) | Expr::Var(..) => Vec::new(),
+            Expr::Succ(e) | Expr::Not(e) | Expr::Field { pair: e, .. } => vec![e],
+            Expr::Add(a, b) | Expr::And(a, b) | Expr::Mk(a, b) => vec![a, b],
+            Expr::If { cond, then, els } => vec![cond, then, els],
+            Expr::MatchColor { scrut, arms } => {
+                let mut v: Vec<&mut Expr> = vec![scrut];
+                v.extend(arms.iter_mut());
+                v
+            }
+            Expr::MatchNat { scrut, zero, succ, .. } => vec![scrut, zero, succ],
+            Expr::Let { init, body, .. } => vec![init, body],
+            Expr::Call { args, .. } => args.iter_mut().collect(),
+        }
+    }
+}
+
+/// A helper `f{i}`: its body may only call helpers declared before it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Func {
+    pub params: Vec<(String, Ty)>,
+    pub ret: Ty,
+    pub body: Expr,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Program {
+    pub fns: Vec<Func>,
+    pub entry: Expr,
+}
+
+impl Program {
+    /// Generate a program whose expressions nest at most `depth` deep.
+    pub fn generate(rng: &mut Rng, depth: u32) -> Program {
+        let mut fns: Vec<Func> = Vec::new();
+        for _ in 0..rng.below(4) {
+            let params: Vec<(String, Ty)> =
+                (0..rng.below(4)).map(|i| (format!("p{i}"), TYS[rng.below(4)])).collect();
+            let ret = TYS[rng.below(4)];
+            let mut g = Gen { rng: &mut *rng, fns: &fns, scope: params.clone(), fresh: 0, depth };
+            let body = g.expr(ret, depth);
+            fns.push(Func { params, ret, body });
+        }
+        let ret = TYS[rng.below(4)];
+        let entry = Gen { rng, fns: &fns, scope: Vec::new(), fresh: 0, depth }.expr(ret, depth);
+        Program { fns, entry }
+    }
+
+    /// Drop helper `i`, renumbering later calls. `None` if something still calls it.
+    pub fn without_fn(&self, i: usize) -> Option<Program> {
+        fn renumber(e: &mut Expr, i: usize) -> bool {
+            if let Expr::Call { func, .. } = e {
+                if *func == i {
+                    return false;
+                }
+                if *func > i {
+                    *func -= 1;
+                }
+            }
+            e.children_mut().into_iter().all(|c| renumber(c, i))
+        }
+        let mut p = self.clone();
+        p.fns.remove(i);
+        let bodies = p.fns.iter_mut().map(|f| &mut f.body);
+        let still_called = !bodies.chain([&mut p.entry]).all(|e| renumber(e, i));
+        (!still_called).then_some(p)
+    }
+}
+
+struct Gen<'a> {
+    rng: &'a mut Rng,
+    /// The helpers a call may target.
+    fns: &'a [Func],
+    /// Variables in scope, innermost last.
+    scope: Vec<(String, Ty)>,
+    fresh: u32,
+    /// The nesting bound; a body's root is never a bare leaf unless this is 0.
+    depth: u32,
+}
+
+impl Gen<'_> {
+    fn fresh(&mut self) -> String {
+        self.fresh += 1;
+        format!("v{}", self.fresh)
+    }
+
+    fn leaf(&mut self, ty: Ty) -> Expr {
+        let vars: Vec<&(String, Ty)> = self.scope.iter().filter(|(_, t)| *t == ty).collect();
+        if !vars.is_empty() && self.rng.below(2) == 0 {
+            let (name, _) = vars[self.rng.below(vars.len())];
+            return Expr::Var(name.clone(), ty);
+        }
+        match ty {
+            Ty::Nat => Expr::Nat(self.rng.below(4) as u32),
+            Ty::Bool => Expr::Bool(self.rng.below(2) == 0),
+            Ty::Color => Expr::Color(self.rng.below(3) as u8),
+            Ty::Pair => {
+                let (a, b) = (self.leaf(Ty::Nat), self.leaf(Ty::Bool));
+                Expr::Mk(Box::new(a), Box::new(b))
+            }
+        }
+    }
+
+    /// An expression of type `ty` nesting at most `depth` deep.
+    fn expr(&mut self, ty: Ty, depth: u32) -> Expr {
+        if depth == 0 || (depth < self.depth && self.rng.below(4) == 0) {
+            return self.leaf(ty);
+        }
+        let d = depth - 1;
+        let callees: Vec<usize> = (0..self.fns.len()).filter(|&f| self.fns[f].ret == ty).collect();
+        // Forms 0..=3 build any type; 4 and 5 are the type's own operators.
+        let form = self.rng.below(if callees.is_empty() { 6 } else { 7 });
+        match (form, ty) {
+            (0, _) => {
+                let cond = self.expr(Ty::Bool, d);
+                let then = self.expr(ty, d);
+                let els = self.expr(ty, d);
+                Expr::If { cond: Box::new(cond), then: Box::new(then), els: Box::new(els) }
+            }
+            (1, _) => {
+                let scrut = self.expr(Ty::Color, d);
+                let arms = [self.expr(ty, d), self.expr(ty, d), self.expr(ty, d)];
+                Expr::MatchColor { scrut: Box::new(scrut), arms: Box::new(arms) }
+            }
+            (2, _) => {
+                let scrut = self.expr(Ty::Nat, d);
+                let zero = self.expr(ty, d);
+                let pred = self.fresh();
+                let succ = self.scoped(&pred, Ty::Nat, |g| g.expr(ty, d));
+                let (scrut, zero, succ) = (Box::new(scrut), Box::new(zero), Box::new(succ));
+                Expr::MatchNat { scrut, zero, pred, succ }
+            }
+            (3, _) => {
+                let bound = TYS[self.rng.below(4)];
+                let init = self.expr(bound, d);
+                let name = self.fresh();
+                let body = self.scoped(&name, bound, |g| g.expr(ty, d));
+                Expr::Let { name, ty: bound, init: Box::new(init), body: Box::new(body) }
+            }
+            (6, _) => {
+                let func = callees[self.rng.below(callees.len())];
+                let params: Vec<Ty> = self.fns[func].params.iter().map(|(_, t)| *t).collect();
+                let args = params.into_iter().map(|t| self.expr(t, d)).collect();
+                Expr::Call { func, args }
+            }
+            (4, Ty::Nat) => Expr::Succ(Box::new(self.expr(Ty::Nat, d))),
+            (5, Ty::Nat) => {
+                let (a, b) = self.two(Ty::Nat, Ty::Nat, d);
+                Expr::Add(a, b)
+            }
+            (4, Ty::Bool) => Expr::Not(Box::new(self.expr(Ty::Bool, d))),
+            (5, Ty::Bool) => {
+                let (a, b) = self.two(Ty::Bool, Ty::Bool, d);
+                Expr::And(a, b)
+            }
+            (_, Ty::Nat | Ty::Bool) => {
+                let second = ty == Ty::Bool;
+                let pair = self.expr(Ty::Pair, d);
+                let binds = (self.fresh(), self.fresh());
+                Expr::Field { pair: Box::new(pair), second, binds }
+            }
+            (_, Ty::Pair) => {
+                let (a, b) = self.two(Ty::Nat, Ty::Bool, d);
+                Expr::Mk(a, b)
+            }
+            (_, Ty::Color) => self.leaf(ty),
+        }
+    }
+
+    fn two(&mut self, a: Ty, b: Ty, depth: u32) -> (Box<Expr>, Box<Expr>) {
+        (Box::new(self.expr(a, depth)), Box::new(self.expr(b, depth)))
+    }
+
+    fn scoped(&mut self, name: &str, ty: Ty, f: impl FnOnce(&mut Self) -> Expr) -> Expr {
+        self.scope.push((name.to_string(), ty));
+        let e = f(self);
+        self.scope.pop();
+        e
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Nat(n) => {
+                for _ in 0..*n {
+                    f.write_str("Nat::Succ(")?;
+                }
+                f.write_str("Nat::Zero")?;
+                for _ in 0..*n {
+                    f.write_str(")")?;
+                }
+                Ok(())
+            }
+            Expr::Bool(b) => f.write_str(if *b { "Bool::True" } else { "Bool::False" }),
+            Expr::Color(c) => write!(f, "Color::{}", COLORS[*c as usize]),
+            Expr::Var(name, _) => f.write_str(name),
+            Expr::Succ(e) => write!(f, "Nat::Succ({e})"),
+            Expr::Add(a, b) => write!(f, "add({a}, {b})"),
+            Expr::Not(e) => write!(f, "not({e})"),
+            Expr::And(a, b) => write!(f, "and({a}, {b})"),
+            Expr::Mk(a, b) => write!(f, "Pair::Mk({a}, {b})"),
+            Expr::Field { pair, second, binds: (a, b) } => {
+                let pick = if *second { b } else { a };
+                write!(f, "match {pair} {{ | Pair::Mk({a}, {b}) => {pick} }}")
+            }
+            Expr::If { cond, then, els } => {
+                write!(f, "match {cond} {{ | Bool::True => {then} | Bool::False => {els} }}")
+            }
+            Expr::MatchColor { scrut, arms } => {
+                write!(f, "match {scrut} {{")?;
+                for (c, arm) in COLORS.iter().zip(arms.iter()) {
+                    write!(f, " | Color::{c} => {arm}")?;
+                }
+                f.write_str(" }")
+            }
+            Expr::MatchNat { scrut, zero, pred, succ } => write!(
+                f,
+                "match {scrut} {{ | Nat::Zero => {zero} | Nat::Succ({pred}) => {succ} }}"
+            ),
+            Expr::Let { name, ty, init, body } => {
+                write!(f, "(let {name}: {ty} := {init} in {body})")
+            }
+            Expr::Call { func, args } => {
+                write!(f, "f{func}(")?;
+                for (i, a) in args.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{a}")?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+/// The whole source file: the fixed library, the helpers, then `entry`.
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(LIBRARY)?;
+        for (i, func) in self.fns.iter().enumerate() {
+            write!(f, "def f{i}(")?;
+            for (j, (name, ty)) in func.params.iter().enumerate() {
+                if j > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{name}: {ty}")?;
+            }
+            writeln!(f, ") : {} :=\n    {}", func.ret, func.body)?;
+        }
+        let ret = self.entry.ty(&self.fns);
+        writeln!(f, "def entry : {ret} :=\n    {}", self.entry)
+    }
+}
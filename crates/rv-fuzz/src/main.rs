@@ -0,0 +1,179 @@
+//! `rv-fuzz` — differential fuzzing of the proof fragment's two evaluators.
+//!
+//! Usage: `rv-fuzz [--seconds N] [--seed S] [--depth D] [--count N]`
+//!   Generates random well-typed programs (see [`gen`]) and evaluates each one's `entry`
+//!   both on the bytecode VM (`rv_driver::vm_eval`) and with the kernel's trusted reducer
+//!   (`rv_driver::nbe_eval`). A program whose result, trap, or panic differs between the
+//!   two is shrunk and printed with the seed that regenerates it. Runs for `--seconds`
+//!   (default 60) or `--count` programs, whichever ends first; exits non-zero if any
+//!   divergence was found. Seeds count up from `--seed` (default: the clock), and
+//!   `--depth` (default 4) bounds how deeply expressions nest.
+
+mod gen;
+mod shrink;
+
+use gen::{Program, Rng};
+use rv_driver::Value;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::process::ExitCode;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const USAGE: &str = "usage: rv-fuzz [--seconds N] [--seed S] [--depth D] [--count N]";
+
+/// `(seed, depth)` pairs that once exposed a divergence, replayed before every random run.
+/// Add the pair a report names once its bug is fixed.
+const REGRESSIONS: &[(u64, u32)] = &[];
+
+/// What one backend made of a program's `entry`.
+enum Outcome {
+    Value(Value),
+    /// An error: the front-end rejected the program, or evaluation failed.
+    Trap(String),
+    Panic(String),
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Value(v) => write!(f, "{v:?}"),
+            Outcome::Trap(e) => write!(f, "error: {e}"),
+            Outcome::Panic(p) => write!(f, "panic: {p}"),
+        }
+    }
+}
+
+impl Outcome {
+    /// Equal values, or both backends refusing. A panic never agrees with anything.
+    fn agrees(&self, other: &Outcome) -> bool {
+        match (self, other) {
+            (Outcome::Value(a), Outcome::Value(b)) => a == b,
+            (Outcome::Trap(_), Outcome::Trap(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+fn run(eval: fn(&str, &str) -> Result<Value, String>, src: &str) -> Outcome {
+    match panic::catch_unwind(AssertUnwindSafe(|| eval(src, "entry"))) {
+        Ok(Ok(v)) => Outcome::Value(v),
+        Ok(Err(e)) => Outcome::Trap(e),
+        Err(payload) => Outcome::Panic(
+            payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "(non-string panic payload)".to_string()),
+        ),
+    }
+}
+
+/// Evaluate `prog` on the VM and on the kernel, in that order.
+fn check(prog: &Program) -> (Outcome, Outcome) {
+    let src = prog.to_string();
+    (run(rv_driver::vm_eval, &src), run(rv_driver::nbe_eval, &src))
+}
+
+#[derive(Default)]
+struct Stats {
+    programs: u64,
+    /// Programs both backends refused: a generator bug, since every program should run.
+    rejected: u64,
+    divergences: u64,
+}
+
+fn fuzz_one(seed: u64, depth: u32, stats: &mut Stats) {
+    let prog = Program::generate(&mut Rng::new(seed), depth);
+    stats.programs += 1;
+    let (vm, kernel) = check(&prog);
+    if let (Outcome::Trap(e), Outcome::Trap(_)) = (&vm, &kernel) {
+        stats.rejected += 1;
+        eprintln!("seed {seed}: both backends refused the program: {e}");
+        return;
+    }
+    if vm.agrees(&kernel) {
+        return;
+    }
+    stats.divergences += 1;
+    let small = shrink::shrink(prog, |p| {
+        let (vm, kernel) = check(p);
+        !vm.agrees(&kernel)
+    });
+    let (vm, kernel) = check(&small);
+    println!("divergence at seed {seed} (rerun: rv-fuzz --seed {seed} --depth {depth} --count 1)");
+    println!("  vm:     {vm}");
+    println!("  kernel: {kernel}");
+    println!("shrunk program:\n{small}");
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut seconds = 60;
+    let mut seed =
+        SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64);
+    let mut depth = 4;
+    let mut count = u64::MAX;
+    let mut it = args.iter();
+    while let Some(a) = it.next() {
+        let value = match a.as_str() {
+            "-h" | "--help" => {
+                eprintln!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            "--seconds" | "--seed" | "--depth" | "--count" => {
+                it.next().and_then(|n| n.parse().ok())
+            }
+            other => {
+                eprintln!("error: unknown argument `{other}`\n{USAGE}");
+                return ExitCode::FAILURE;
+            }
+        };
+        let Some(value) = value else {
+            eprintln!("error: {a} needs a non-negative number");
+            return ExitCode::FAILURE;
+        };
+        match a.as_str() {
+            "--seconds" => seconds = value,
+            "--seed" => seed = value,
+            "--depth" => depth = value as u32,
+            _ => count = value,
+        }
+    }
+    // A backend panic is reported as an outcome; keep the default hook from also printing it.
+    panic::set_hook(Box::new(|_| {}));
+
+    let mut stats = Stats::default();
+    for &(seed, depth) in REGRESSIONS {
+        fuzz_one(seed, depth, &mut stats);
+    }
+    let deadline = Instant::now() + Duration::from_secs(seconds);
+    println!("fuzzing from seed {seed} at depth {depth}");
+    while Instant::now() < deadline && stats.programs < count {
+        fuzz_one(seed, depth, &mut stats);
+        seed = seed.wrapping_add(1);
+    }
+    println!(
+        "{} program(s), {} refused by both backends, {} divergence(s)",
+        stats.programs, stats.rejected, stats.divergences
+    );
+    if stats.divergences == 0 { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Generated programs pass the front-end, and both backends compute the same value.
+    #[test]
+    fn generated_programs_run_and_agree() {
+        for seed in 0..6 {
+            let prog = Program::generate(&mut Rng::new(seed), 3);
+            match check(&prog) {
+                (Outcome::Value(vm), Outcome::Value(kernel)) => {
+                    assert_eq!(vm, kernel, "seed {seed}:\n{prog}");
+                }
+                (vm, kernel) => panic!("seed {seed}: vm {vm}, kernel {kernel}\n{prog}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! Naive shrinking: greedily take the first smaller program that still fails, until none
+//! does. "Smaller" is one step of: dropping an uncalled helper, replacing an expression by
+//! the smallest value of its type, by one of its same-typed children, or by a shrunk child.
+//! A step may leave a variable unbound; the front-end then rejects the candidate on both
+//! backends alike, so it never counts as still failing and is discarded.
+
+use crate::gen::{Expr, Func, Program};
+
+pub fn shrink(mut prog: Program, mut fails: impl FnMut(&Program) -> bool) -> Program {
+    'smaller: loop {
+        for candidate in candidates(&prog) {
+            if fails(&candidate) {
+                prog = candidate;
+                continue 'smaller;
+            }
+        }
+        return prog;
+    }
+}
+
+fn candidates(p: &Program) -> Vec<Program> {
+    let mut out: Vec<Program> = (0..p.fns.len()).rev().filter_map(|i| p.without_fn(i)).collect();
+    for entry in rewrites(&p.entry, &p.fns) {
+        out.push(Program { fns: p.fns.clone(), entry });
+    }
+    for (i, f) in p.fns.iter().enumerate() {
+        for body in rewrites(&f.body, &p.fns) {
+            let mut q = p.clone();
+            q.fns[i].body = body;
+            out.push(q);
+        }
+    }
+    out
+}
+
+/// Every one-step shrink of `e`, largest reductions first.
+fn rewrites(e: &Expr, fns: &[Func]) -> Vec<Expr> {
+    let ty = e.ty(fns);
+    let mut out = Vec::new();
+    let smallest = Expr::smallest(ty);
+    if *e != smallest {
+        out.push(smallest);
+    }
+    if let Expr::Nat(n @ 2..) = e {
+        out.push(Expr::Nat(n - 1));
+    }
+    let children = e.children();
+    out.extend(children.iter().filter(|c| c.ty(fns) == ty).map(|c| (*c).clone()));
+    for (i, child) in children.iter().enumerate() {
+        for r in rewrites(child, fns) {
+            let mut whole = e.clone();
+            *whole.children_mut().swap_remove(i) = r;
+            out.push(whole);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gen::Ty;
+
+    fn has_add(e: &Expr) -> bool {
+        matches!(e, Expr::Add(..)) || e.children().into_iter().any(has_add)
+    }
+
+    #[test]
+    fn shrinks_to_the_smallest_failing_program() {
+        let entry = Expr::If {
+            cond: Box::new(Expr::Not(Box::new(Expr::Bool(true)))),
+            then: Box::new(Expr::Add(
+                Box::new(Expr::Nat(2)),
+                Box::new(Expr::Succ(Box::new(Expr::Nat(1)))),
+            )),
+            els: Box::new(Expr::Nat(3)),
+        };
+        let params = vec![("p0".to_string(), Ty::Bool)];
+        let helper = Func { params, ret: Ty::Bool, body: Expr::Bool(true) };
+        let prog = Program { fns: vec![helper], entry };
+        let small = shrink(prog, |p| has_add(&p.entry));
+        let zero = || Box::new(Expr::Nat(0));
+        assert_eq!(small, Program { fns: Vec::new(), entry: Expr::Add(zero(), zero()) });
+    }
+}
@@ -0,0 +1,76 @@
+//! A readable dump of what type inference decided, for debugging inference.
+//!
+//! Each function prints its inferred signature, then every local (parameters,
+//! `let` bindings, and the lowering's temporaries as `_N`) with its type:
+//!
+//! ```text
+//! fn main() -> i64
+//!     y: i64
+//!     _1: fn(i64) -> i64
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use rv_core::{Sym, Symbols};
+use rv_ir::{Parsed, Program, SourceLines, TypeDef};
+
+use crate::{describe_ty, has_foreign_params, infer_function_traced, signatures, Callees};
+
+/// Render the inferred types of every function in `prog`.
+///
+/// Two kinds of local are marked, since they are where surprising inference
+/// results come from: one whose type still names a type parameter the function
+/// does not declare is `(unresolved)`, and one nothing typed, which fell back to
+/// `i64`, is `(defaulted)`. A function whose inference fails shows its error in
+/// place of its locals, and the other functions are still dumped. Only a failure
+/// to compute the signatures fails the whole dump.
+pub fn dump_types(
+    prog: &Program<Parsed>,
+    syms: &Symbols,
+    lines: &SourceLines,
+) -> Result<String, String> {
+    let types: HashMap<Sym, TypeDef> = prog.types.iter().map(|t| (t.name(), t.clone())).collect();
+    let sigs = signatures(prog, syms, lines)?;
+    let calls = Callees { sigs: &sigs, trait_impls: &prog.trait_impls };
+    let mut out = String::new();
+    for f in &prog.funcs {
+        let mut head = syms.resolve(f.name).to_string();
+        if !f.type_params.is_empty() {
+            let names: Vec<&str> = f.type_params.iter().map(|p| syms.resolve(*p)).collect();
+            let _ = write!(head, "<{}>", names.join(", "));
+        }
+        let (typed, defaulted) =
+            match infer_function_traced(f, &types, &HashMap::new(), Some(calls), syms, lines) {
+                Ok(inferred) => inferred,
+                Err(e) => {
+                    let _ = writeln!(out, "fn {head}\n    error: {e}");
+                    continue;
+                }
+            };
+        let local_name = |i: usize| {
+            typed.locals[i].name.map_or_else(|| format!("_{i}"), |n| syms.resolve(n).to_string())
+        };
+        let params: Vec<String> = typed
+            .params
+            .iter()
+            .map(|p| {
+                let i = p.0 as usize;
+                format!("{}: {}", local_name(i), describe_ty(&typed.locals[i].ty, syms))
+            })
+            .collect();
+        let ret = describe_ty(&typed.ret, syms);
+        let _ = writeln!(out, "fn {head}({}) -> {ret}", params.join(", "));
+        for (i, decl) in typed.locals.iter().enumerate() {
+            let mark = if defaulted[i] {
+                " (defaulted)"
+            } else if has_foreign_params(&decl.ty, &typed.type_params) {
+                " (unresolved)"
+            } else {
+                ""
+            };
+            let _ = writeln!(out, "    {}: {}{mark}", local_name(i), describe_ty(&decl.ty, syms));
+        }
+    }
+    Ok(out)
+}
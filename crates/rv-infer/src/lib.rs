@@ -23,13 +23,19 @@
 //! A generic type parameter `Ty::Param(_)` (e.g. `T` in `fn id<T>(x: T) -> T`, or a
 //! generic ADT field declared `T`) is treated as an *opaque* type:
 //!
-//! * Type arguments are **erased**: a `Function`'s `type_params` are carried through
-//!   the phase change unchanged, and a generic ADT value still types as `Ty::Adt(name)`
-//!   (we never track type arguments). Match exhaustiveness uses the variant count from
-//!   the `TypeDef`, which a type parameter does not affect.
-//! * In [`resolve_proj_ty`], a field whose declared type is `Ty::Param(_)` stays
-//!   `Ty::Param(_)` (opaque); a `Field`/`Downcast` off an unknown/param base falls back
-//!   to the `Int` default, as before.
+//! * A `Function`'s `type_params` are carried through the phase change unchanged;
+//!   a generic function's type arguments are inferred per call site.
+//! * A generic ADT value carries its type arguments: a constructor instantiates each
+//!   type parameter from its operands (`Option::Some(5)` types as
+//!   `Adt(Option, [Int])`), and a parameter no operand determines (`None`) stays
+//!   `Ty::Param` until a later assignment or use pins it. An ADT with *no* arguments
+//!   (a bare `Option` annotation) is compatible with every instantiation. Match
+//!   exhaustiveness uses the variant count from the `TypeDef`, which a type argument
+//!   does not affect.
+//! * In [`resolve_proj_ty`], a field declared `Ty::Param(_)` resolves to the base
+//!   value's type argument, or stays `Ty::Param(_)` (opaque) when the argument is
+//!   unknown; a `Field`/`Downcast` off an unknown/param base falls back to the `Int`
+//!   default, as before.
 //! * In [`check`]/[`set_ty`], an operand of `Ty::Param(_)` is *never* a type error — a
 //!   generic body is checked abstractly (e.g. `return x` with `x: T` yields `T`). Only
 //!   concretely-conflicting non-generic types are rejected, exactly as before.
@@ -52,14 +58,23 @@
 //!   [`VcGen::exec_loop_header`] (entry + havoc/assume + preservation), which is
 //!   simplified but soundness-leaning; see that method's caveats.
 
+mod dump;
+
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 use rv_core::{BinOp, Prop, Sym, Symbols, Term, Ty, UnOp};
+use rv_diagnostics::{
+    with_notes, Note, DEFAULT_NOTE_LIMIT, E0001, E0061, E0080, E0277, E0282, E0308, E0381, E0605,
+};
 use rv_ir::{
-    AggKind, Block, BlockId, Const, DisciplineId, Function, Lowerable, LocalId, Operand, Parsed,
-    Place, Proj, Program, RValue, Stmt, Terminator, TypeDef, RESULT_NAME,
+    AggKind, Block, BlockId, CastKind, Const, DisciplineId, Function, Lowerable, LocalId, Operand, Parsed,
+    Place, Proj, Program, RValue, Site, SourceLines, Stmt, Terminator, TypeDef, ASSERT_EQ,
+    ASSERT_NE, FLOAT_BITS, RESULT_NAME, STR_CONCAT, TO_STRING,
 };
 
+pub use dump::dump_types;
+
 /// The result of elaboration: a typed (`Lowerable`) program plus the verification
 /// obligations its symbolic execution produced.
 pub struct Elaborated {
@@ -70,63 +85,98 @@ pub struct Elaborated {
 /// Elaborate a parsed program: infer types (producing a `Lowerable` program) and
 /// generate verification conditions. Returns `Err` on a static type error.
 pub fn elaborate(prog: Program<Parsed>, syms: &Symbols) -> Result<Elaborated, String> {
-    // We need a *mutable* symbol table to mint fresh call-result variables, but the
-    // public API only lends us `&Symbols`. Clone it locally; fresh names never need
-    // to escape this pass (they only appear inside obligations).
-    let mut syms = syms.clone();
+    elaborate_with_lines(prog, syms, &SourceLines::new())
+}
+
+/// [`elaborate`], prefixing each type error with the source line (from `lines`,
+/// as produced by `rv_lower::lower_with_lines`) of the statement that raised it.
+pub fn elaborate_with_lines(
+    prog: Program<Parsed>,
+    syms: &Symbols,
+    lines: &SourceLines,
+) -> Result<Elaborated, String> {
+    let _span = tracing::debug_span!("infer").entered();
+    // ---- Pass 1: infer per-function types, build the Lowerable program. ----
+    let funcs_low = infer_types(&prog, syms, lines)?;
 
-    // Map each function name -> its (params, pre, post, return-type) signature, so
-    // call sites can do modular verification and pick up callee return types.
-    let mut sigs: HashMap<Sym, Signature> = HashMap::new();
+    // ---- Pass 2: VC generation via forward symbolic execution. ----
+    let obligations = generate_obligations(&prog, &funcs_low, syms)?;
 
-    // Index the user-defined types by name so inference (ADT typing) and VC
-    // generation (match exhaustiveness) can look them up in O(1).
+    // Carry the (phase-independent) type definitions through to the Lowerable
+    // program unchanged.
+    Ok(Elaborated {
+        prog: Program { types: prog.types, trait_impls: prog.trait_impls, funcs: funcs_low },
+        obligations,
+    })
+}
+
+/// Pass 1 of [`elaborate_with_lines`] alone: the typed program, without the
+/// verification conditions (for the checks that only need types).
+pub fn typecheck_with_lines(
+    prog: Program<Parsed>,
+    syms: &Symbols,
+    lines: &SourceLines,
+) -> Result<Program<Lowerable>, String> {
+    let _span = tracing::debug_span!("infer").entered();
+    let funcs = infer_types(&prog, syms, lines)?;
+    Ok(Program { types: prog.types, trait_impls: prog.trait_impls, funcs })
+}
+
+/// Type every function of `prog`, each call site against its callee's signature only.
+fn infer_types(
+    prog: &Program<Parsed>,
+    syms: &Symbols,
+    lines: &SourceLines,
+) -> Result<Vec<Function<Lowerable>>, String> {
+    // Index the user-defined types by name so inference (ADT typing) can look
+    // them up in O(1).
     let type_table: HashMap<Sym, TypeDef> =
         prog.types.iter().map(|t| (t.name(), t.clone())).collect();
+    let callees = signatures(prog, syms, lines)?;
+    prog.funcs
+        .iter()
+        .map(|f| infer_function_types(f, &type_table, &prog.trait_impls, &callees, syms, lines))
+        .collect()
+}
 
-    // ---- Pass 1: infer per-function types, build the Lowerable program. ----
-    // Seed direct-call typing from surface return annotations. A function may call a
-    // later declaration, so this map must exist before any body is inferred.
-    let declared_returns: HashMap<Sym, Ty> = prog
+/// Pass 2 of [`elaborate`]: check the exhaustiveness of each typed function and
+/// generate its verification conditions. `typed[i]` is `prog.funcs[i]` as
+/// [`infer_function_types`] typed it.
+pub fn generate_obligations(
+    prog: &Program<Parsed>,
+    typed: &[Function<Lowerable>],
+    syms: &Symbols,
+) -> Result<Vec<rv_logic::Obligation>, String> {
+    // We need a *mutable* symbol table to mint fresh call-result variables, but the
+    // public API only lends us `&Symbols`. Clone it locally; fresh names never need
+    // to escape this pass (they only appear inside obligations).
+    let mut syms = syms.clone();
+
+    // Map each function name -> its (params, pre, post) signature, so call sites
+    // can do modular verification.
+    let sigs: HashMap<Sym, Signature> = prog
         .funcs
         .iter()
-        .map(|f| (f.name, f.ret.clone().unwrap_or(Ty::Int)))
+        .map(|f| {
+            let sig = Signature { param_syms: param_syms(f), pre: f.pre.clone(), post: f.post.clone() };
+            (f.name, sig)
+        })
         .collect();
-    let mut provisional: Vec<Function<Lowerable>> = Vec::with_capacity(prog.funcs.len());
-    for f in &prog.funcs {
-        provisional.push(infer_function(f, &type_table, &declared_returns, None, &syms)?);
-    }
 
-    // A small second pass replaces annotation fallbacks with the actual inferred
-    // return types. This covers unannotated/lifted functions without making the
-    // answer depend on declaration order.
-    let inferred_returns: HashMap<Sym, Ty> = provisional
-        .iter()
-        .map(|f| (f.name, f.ret.clone()))
-        .collect();
-    let call_types = callable_types(&provisional, &prog.trait_impls);
-    let mut funcs_low: Vec<Function<Lowerable>> = Vec::with_capacity(prog.funcs.len());
-    for f in &prog.funcs {
-        let inferred = infer_function(f, &type_table, &inferred_returns, Some(&call_types), &syms)?;
-        sigs.insert(
-            f.name,
-            Signature {
-                param_syms: param_syms(f),
-                pre: f.pre.clone(),
-                post: f.post.clone(),
-            },
-        );
-        funcs_low.push(inferred);
-    }
+    // Match exhaustiveness looks the user-defined types up by name.
+    let type_table: HashMap<Sym, TypeDef> =
+        prog.types.iter().map(|t| (t.name(), t.clone())).collect();
 
-    // ---- Pass 2: VC generation via forward symbolic execution. ----
     let mut obligations = Vec::new();
-    for (f, low) in prog.funcs.iter().zip(funcs_low.iter()) {
+    for (f, low) in prog.funcs.iter().zip(typed) {
         // Exhaustiveness is a static check over the (typed) function; run it before
         // symbolic execution so a non-exhaustive match fails fast.
         check_exhaustiveness(low, &type_table)?;
+        let _fn_span = tracing::debug_span!("vcgen", func = syms.resolve(f.name)).entered();
+        let before = obligations.len();
         let mut vc = VcGen {
             f,
+            block_index: f.blocks.iter().enumerate().map(|(i, b)| (b.id, i)).collect(),
             low,
             types: &type_table,
             sigs: &sigs,
@@ -137,14 +187,9 @@ pub fn elaborate(prog: Program<Parsed>, syms: &Symbols) -> Result<Elaborated, St
         // a buggy lowering could hand us) is surfaced as a clean `Err` rather than a
         // panic deep inside symbolic execution.
         vc.run(low)?;
+        tracing::debug!(obligations = obligations.len() - before, "generated");
     }
-
-    // Carry the (phase-independent) type definitions through to the Lowerable
-    // program unchanged.
-    Ok(Elaborated {
-        prog: Program { types: prog.types, trait_impls: prog.trait_impls, funcs: funcs_low },
-        obligations,
-    })
+    Ok(obligations)
 }
 
 /// A callee's signature, used at call sites for modular verification.
@@ -155,39 +200,134 @@ struct Signature {
     post: Prop,
 }
 
-/// The executable portion of a function type used while inferring call sites.
-/// Contracts remain in [`Signature`] for VC generation; this shape is deliberately
-/// structural so it can become one component of a unified callable type later.
-struct CallableType {
-    params: Vec<Ty>,
-    ret: Ty,
-    generic_bounds: Vec<(Sym, Vec<Sym>)>,
-    trait_impls: Vec<rv_ir::TraitImpl>,
+/// The executable portion of a function type: what a caller needs from its
+/// callee. Call sites are inferred against this alone, never against the callee's
+/// body, so an edit to a body that keeps its signature leaves callers' types as
+/// they were. Contracts remain in [`Signature`] for VC generation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SigTypes {
+    pub params: Vec<Ty>,
+    pub ret: Ty,
+    pub generic_bounds: Vec<(Sym, Vec<Sym>)>,
 }
 
-fn callable_types(
-    funcs: &[Function<Lowerable>],
-    trait_impls: &[rv_ir::TraitImpl],
-) -> HashMap<Sym, CallableType> {
-    funcs
+impl SigTypes {
+    /// The signature `f` spells out, or `None` if its return type is left to
+    /// inference (an implicit `()` return, or a lifted closure). An unannotated
+    /// parameter is an `i64`, as in body inference.
+    pub fn declared(f: &Function<Parsed>) -> Option<SigTypes> {
+        let ret = f.ret.clone()?;
+        let params = f
+            .params
+            .iter()
+            .map(|id| f.locals[id.0 as usize].ty.clone().unwrap_or(Ty::Int))
+            .collect();
+        Some(SigTypes { params, ret, generic_bounds: f.generic_bounds.clone() })
+    }
+
+    fn inferred(f: &Function<Lowerable>) -> SigTypes {
+        SigTypes {
+            params: f.params.iter().map(|id| f.locals[id.0 as usize].ty.clone()).collect(),
+            ret: f.ret.clone(),
+            generic_bounds: f.generic_bounds.clone(),
+        }
+    }
+}
+
+/// Where call-site inference looks up callee signatures. [`elaborate`] answers
+/// from the map [`signatures`] builds; an incremental driver can answer from a
+/// memoized per-function query instead.
+pub trait CalleeSignatures {
+    fn signature(&self, func: Sym) -> Option<Arc<SigTypes>>;
+}
+
+impl CalleeSignatures for HashMap<Sym, Arc<SigTypes>> {
+    fn signature(&self, func: Sym) -> Option<Arc<SigTypes>> {
+        self.get(&func).cloned()
+    }
+}
+
+/// The callee context of call-site inference: signatures, plus the program's
+/// trait impls for checking generic bounds.
+#[derive(Clone, Copy)]
+struct Callees<'a> {
+    sigs: &'a dyn CalleeSignatures,
+    trait_impls: &'a [rv_ir::TraitImpl],
+}
+
+/// The signature of every function in `prog`. A declared one is taken as is; a
+/// function whose return type is left open has its body inferred once, with calls
+/// typed by the callees' declared returns (`i64` where none is declared).
+pub fn signatures(
+    prog: &Program<Parsed>,
+    syms: &Symbols,
+    lines: &SourceLines,
+) -> Result<HashMap<Sym, Arc<SigTypes>>, String> {
+    let type_table: HashMap<Sym, TypeDef> =
+        prog.types.iter().map(|t| (t.name(), t.clone())).collect();
+    let declared_returns: HashMap<Sym, Ty> = prog
+        .funcs
         .iter()
-        .map(|f| {
-            let params = f
-                .params
-                .iter()
-                .map(|id| f.locals[id.0 as usize].ty.clone())
-                .collect();
-            (
-                f.name,
-                CallableType {
-                    params,
-                    ret: f.ret.clone(),
-                    generic_bounds: f.generic_bounds.clone(),
-                    trait_impls: trait_impls.to_vec(),
-                },
-            )
-        })
-        .collect()
+        .map(|f| (f.name, f.ret.clone().unwrap_or(Ty::Int)))
+        .collect();
+    let mut sigs = HashMap::with_capacity(prog.funcs.len());
+    for f in &prog.funcs {
+        let sig = signature_in(f, &type_table, &declared_returns, syms, lines)?;
+        sigs.insert(f.name, Arc::new(sig));
+    }
+    Ok(sigs)
+}
+
+/// The signature of `f`, one of `prog`'s functions, as [`signatures`] finds it.
+pub fn signature(
+    prog: &Program<Parsed>,
+    f: &Function<Parsed>,
+    syms: &Symbols,
+    lines: &SourceLines,
+) -> Result<SigTypes, String> {
+    if let Some(sig) = SigTypes::declared(f) {
+        return Ok(sig);
+    }
+    let type_table: HashMap<Sym, TypeDef> =
+        prog.types.iter().map(|t| (t.name(), t.clone())).collect();
+    let declared_returns: HashMap<Sym, Ty> =
+        prog.funcs.iter().map(|f| (f.name, f.ret.clone().unwrap_or(Ty::Int))).collect();
+    signature_in(f, &type_table, &declared_returns, syms, lines)
+}
+
+/// `f`'s declared signature, or else the one its body implies with each call
+/// typed by the callee's entry in `declared_returns`.
+fn signature_in(
+    f: &Function<Parsed>,
+    types: &HashMap<Sym, TypeDef>,
+    declared_returns: &HashMap<Sym, Ty>,
+    syms: &Symbols,
+    lines: &SourceLines,
+) -> Result<SigTypes, String> {
+    match SigTypes::declared(f) {
+        Some(sig) => Ok(sig),
+        None => {
+            let provisional = infer_function(f, types, declared_returns, None, syms, lines)?;
+            Ok(SigTypes::inferred(&provisional))
+        }
+    }
+}
+
+/// Infer one function's local and return types, typing each call against the
+/// callee's signature in `callees`. Nothing about a callee's body is consulted.
+pub fn infer_function_types(
+    f: &Function<Parsed>,
+    types: &HashMap<Sym, TypeDef>,
+    trait_impls: &[rv_ir::TraitImpl],
+    callees: &dyn CalleeSignatures,
+    syms: &Symbols,
+    lines: &SourceLines,
+) -> Result<Function<Lowerable>, String> {
+    let _span = tracing::debug_span!("infer_fn", func = syms.resolve(f.name)).entered();
+    let callees = Callees { sigs: callees, trait_impls };
+    let inferred = infer_function(f, types, &HashMap::new(), Some(callees), syms, lines)?;
+    tracing::debug!(ret = ?inferred.ret, "inferred");
+    Ok(inferred)
 }
 
 /// The parameter symbols of a function, in parameter order. Missing names (anonymous
@@ -206,14 +346,39 @@ fn infer_function(
     f: &Function<Parsed>,
     types: &HashMap<Sym, TypeDef>,
     returns: &HashMap<Sym, Ty>,
-    calls: Option<&HashMap<Sym, CallableType>>,
+    calls: Option<Callees<'_>>,
     syms: &Symbols,
+    lines: &SourceLines,
 ) -> Result<Function<Lowerable>, String> {
+    infer_function_traced(f, types, returns, calls, syms, lines).map(|(typed, _)| typed)
+}
+
+/// [`infer_function`], also reporting which locals nothing typed, so that they fell
+/// back to the `Int` default (indexed like `f.locals`).
+fn infer_function_traced(
+    f: &Function<Parsed>,
+    types: &HashMap<Sym, TypeDef>,
+    returns: &HashMap<Sym, Ty>,
+    calls: Option<Callees<'_>>,
+    syms: &Symbols,
+    lines: &SourceLines,
+) -> Result<(Function<Lowerable>, Vec<bool>), String> {
     // Seed from any front-end *declared* types (e.g. a parameter's `: u8`), then
     // refine by the forward sweep over assignments. A declared type matters most
     // for a parameter (no defining assignment to infer its type from) and for
     // recovering a sized-integer width that drives overflow bounds.
     let mut tys: Vec<Option<Ty>> = f.locals.iter().map(|d| d.ty.clone()).collect();
+    // The assignment that first fixed each local's type, so a later conflicting
+    // assignment can trace where the type it conflicts with came from.
+    let mut origins: Vec<Option<Origin>> = vec![None; f.locals.len()];
+    let line_of = |block: BlockId, stmt: Option<u32>| {
+        lines.get(Site { func: f.name, block, stmt }).map(|span| span.start)
+    };
+    if let Err(read) = f.check_initialized() {
+        let name = local_name(f, read.local, syms);
+        let msg = E0381.tag(format!("use of possibly-uninitialized variable {name}"));
+        return Err(at_line(line_of(read.block, read.stmt), msg));
+    }
 
     // Walk blocks in id order; for branching code a single forward sweep over all
     // assignments is enough to type every defined local.
@@ -224,24 +389,108 @@ fn infer_function(
     // match-binding pattern, the binder local is a fresh local that gets its own
     // ADT/scalar type via a normal copy elsewhere; we default unknown locals to
     // `Int` as before.)
+    //
+    // A generic function used as a value (`apply(identity, 5)`) starts out with its
+    // own type parameters in its `Fn` type. They are solved where the value meets a
+    // concrete function type: a parameter it is passed to, the declared type of the
+    // local holding it, a call through it, or the declared return type. Each such
+    // value is remembered so one left unsolved can be reported.
+    let mut generic_values: Vec<(LocalId, Sym, Option<u32>)> = Vec::new();
     for blk in &f.blocks {
-        for stmt in &blk.stmts {
+        for (i, stmt) in blk.stmts.iter().enumerate() {
+            // A discarded call is still checked against its callee's signature.
+            if let Stmt::Eval(rv) = stmt {
+                let line = line_of(blk.id, Some(i as u32));
+                pin_fn_arguments(rv, &mut tys, f, types, calls).map_err(|e| at_line(line, e))?;
+                type_of_rvalue(rv, &tys, f, types, returns, calls, syms)
+                    .map_err(|e| at_line(line, e))?;
+            }
             if let Stmt::Assign(place, rv) = stmt {
                 if !place.proj.is_empty() {
                     continue;
                 }
-                let ty = type_of_rvalue(rv, &tys, f, types, returns, calls, syms)?;
-                set_ty(&mut tys, place.local, ty)?;
+                let line = line_of(blk.id, Some(i as u32));
+                let local = place.local.0 as usize;
+                pin_fn_arguments(rv, &mut tys, f, types, calls).map_err(|e| at_line(line, e))?;
+                let mut ty = type_of_rvalue(rv, &tys, f, types, returns, calls, syms)
+                    .map_err(|e| at_line(line, e))?;
+                if has_foreign_params(&ty, &f.type_params) {
+                    if let Some(want) = &tys[local] {
+                        ty = pin_fn_value(&ty, want).map_err(|e| at_line(line, e))?;
+                    }
+                    if let RValue::Closure(func, _) = rv {
+                        generic_values.push((place.local, *func, line));
+                    }
+                }
+                let name = local_name(f, place.local, syms);
+                set_ty(&mut tys, place.local, &name, ty).map_err(|e| {
+                    let notes = origin_chain(place.local, &origins, &tys, f, syms);
+                    with_notes(at_line(line, e), &notes, DEFAULT_NOTE_LIMIT)
+                })?;
+                let from = match rv {
+                    RValue::Use(Operand::Copy(src)) if src.proj.is_empty() => Some(src.local),
+                    _ => None,
+                };
+                origins[local] = origins[local].or(Some(Origin { line, from }));
+            }
+        }
+        // Lowering matches through every reference it can see; a scrutinee still
+        // typed as a reference here would switch on the pointer.
+        if let Terminator::Match { scrutinee, .. } = &blk.term {
+            let ty = type_of_operand(scrutinee, &tys, types)?;
+            if let Ty::Ref { .. } = ty {
+                return Err(at_line(
+                    line_of(blk.id, None),
+                    E0308.tag(format!(
+                        "cannot match on `{}`; dereference the reference with `*`",
+                        describe_ty(&ty, syms)
+                    )),
+                ));
             }
         }
     }
 
+    // A generic function value returned from a function with a declared `fn`
+    // return type takes its type arguments from that type.
+    if let Some(declared) = &f.ret {
+        for blk in &f.blocks {
+            if let Terminator::Return(Operand::Copy(place)) = &blk.term {
+                let slot = &mut tys[place.local.0 as usize];
+                let Some(ty) = slot.as_ref().filter(|ty| has_foreign_params(ty, &f.type_params))
+                else {
+                    continue;
+                };
+                let pinned =
+                    pin_fn_value(ty, declared).map_err(|e| at_line(line_of(blk.id, None), e))?;
+                *slot = Some(pinned);
+            }
+        }
+    }
+    for (local, func, line) in generic_values {
+        if tys[local.0 as usize].as_ref().is_some_and(|ty| has_foreign_params(ty, &f.type_params)) {
+            let name = syms.resolve(func);
+            return Err(at_line(
+                line,
+                E0282.tag(format!(
+                    "cannot infer type arguments for generic function `{name}` used as a value; \
+                     specify them explicitly, as in `{name}::<..>`"
+                )),
+            ));
+        }
+    }
+
     // Return type: from the operand of a `Return` terminator (first one found).
+    // Returning a `!` value (`return return 1`) says nothing about the type.
     let mut ret = Ty::Unit;
+    let mut ret_line = None;
     for blk in &f.blocks {
         if let Terminator::Return(op) = &blk.term {
-            ret = type_of_operand(op, &tys, types)?;
-            break;
+            let line = line_of(blk.id, None);
+            let ty = type_of_operand(op, &tys, types).map_err(|e| at_line(line, e))?;
+            if ty != Ty::Never {
+                (ret, ret_line) = (ty, line);
+                break;
+            }
         }
     }
 
@@ -250,21 +499,29 @@ fn infer_function(
     // compatible with the unsuffixed integer default, and generic parameters stay
     // abstract, matching ordinary operand checking.
     if let Some(declared) = &f.ret {
-        check_return(&ret, declared)?;
+        check_return(&ret, declared).map_err(|e| at_line(ret_line, e))?;
+        // A body returning `None` leaves `Option<T>` open; the signature pins it.
+        if let (Ty::Adt(..), Ty::Adt(..)) = (&ret, declared) {
+            ret = merge_ty(declared, &ret).unwrap_or(ret);
+        }
     }
 
     // Any local still unknown defaults to `Int` (the pragmatic default for the slice;
     // a local with no defining assignment we can pin is treated as a numeric).
+    let defaulted: Vec<bool> = tys.iter().map(Option::is_none).collect();
+    let tys: Vec<Ty> = tys.into_iter().map(|ty| ty.unwrap_or(Ty::Int)).collect();
+    check_int_literals(f, &tys, &ret, types, &line_of)?;
+    check_float_free_specs(f, &tys, &ret, syms, &line_of)?;
     let locals = f
         .locals
         .iter()
-        .enumerate()
-        .map(|(i, d)| rv_ir::LocalDecl { name: d.name, ty: tys[i].clone().unwrap_or(Ty::Int) })
+        .zip(tys)
+        .map(|(d, ty)| rv_ir::LocalDecl { name: d.name, mutable: d.mutable, ty })
         .collect();
 
     let blocks = f.blocks.iter().map(rebuild_block).collect();
 
-    Ok(Function {
+    let typed = Function {
         name: f.name,
         // Generic type parameters are erased for checking but carried through the
         // phase change so downstream phases see the same signature.
@@ -277,39 +534,352 @@ fn infer_function(
         locals,
         blocks,
         entry: f.entry,
-    })
+    };
+    typed
+        .validate_params_kept(f)
+        .map_err(|e| format!("internal error in `{}`: {e}", syms.resolve(f.name)))?;
+    Ok((typed, defaulted))
+}
+
+/// Reject a float-typed variable (or a float `result`) in a specification. Float
+/// comparison is IEEE: every comparison with NaN is false, so not even `x == x`
+/// holds, while the spec logic's integer laws would prove it. No float fact can be
+/// stated soundly there; float literals are already rejected by lowering.
+fn check_float_free_specs(
+    f: &Function<Parsed>,
+    tys: &[Ty],
+    ret: &Ty,
+    syms: &Symbols,
+    line_of: &dyn Fn(BlockId, Option<u32>) -> Option<u32>,
+) -> Result<(), String> {
+    let floats: HashSet<Sym> = f
+        .locals
+        .iter()
+        .zip(tys)
+        .filter(|(_, ty)| matches!(ty, Ty::Float))
+        .filter_map(|(d, _)| d.name)
+        .collect();
+    let float_result = matches!(ret, Ty::Float);
+    let check = |p: &Prop, with_result: bool, line: Option<u32>| {
+        let mut vars = Vec::new();
+        rv_core::prop_vars(p, &mut vars);
+        let is_float = |v: &Sym| {
+            floats.contains(v) || (with_result && float_result && syms.resolve(*v) == RESULT_NAME)
+        };
+        match vars.into_iter().find(is_float) {
+            Some(v) => Err(at_line(
+                line,
+                format!(
+                    "the float `{}` cannot appear in a specification: float comparisons \
+                     are IEEE (false for NaN) and the spec logic cannot express them",
+                    syms.resolve(v)
+                ),
+            )),
+            None => Ok(()),
+        }
+    };
+    for blk in &f.blocks {
+        for (i, stmt) in blk.stmts.iter().enumerate() {
+            if let Stmt::Assert(p) | Stmt::Assume(p) | Stmt::Invariant(p) = stmt {
+                check(p, false, line_of(blk.id, Some(i as u32)))?;
+            }
+        }
+    }
+    check(&f.pre, false, None)?;
+    check(&f.post, true, None)
+}
+
+/// Reject integer literals that do not fit the type their context gives them: the
+/// local a literal is assigned to, the other operand of an arithmetic operator or
+/// comparison, a struct or variant field, or the function's return type. Call
+/// arguments are checked against their parameters with the rest of the call. A
+/// `-` written before a literal is part of it, so `-9223372036854775808` is an
+/// `i64` while `9223372036854775808` is not.
+fn check_int_literals(
+    f: &Function<Parsed>,
+    tys: &[Ty],
+    ret: &Ty,
+    types: &HashMap<Sym, TypeDef>,
+    line_of: &dyn Fn(BlockId, Option<u32>) -> Option<u32>,
+) -> Result<(), String> {
+    let operand_ty = |op: &Operand| match op {
+        Operand::Copy(place) if place.proj.is_empty() => Some(&tys[place.local.0 as usize]),
+        _ => None,
+    };
+    for blk in &f.blocks {
+        for (i, stmt) in blk.stmts.iter().enumerate() {
+            let Stmt::Assign(place, rv) = stmt else { continue };
+            let target = place.proj.is_empty().then(|| &tys[place.local.0 as usize]);
+            let expect = |op: &Operand, negated: bool, ty: Option<&Ty>| match ty {
+                Some(ty) => check_int_literal(op, negated, ty)
+                    .map_err(|e| at_line(line_of(blk.id, Some(i as u32)), e)),
+                None => Ok(()),
+            };
+            match rv {
+                RValue::Use(op) => expect(op, false, target)?,
+                RValue::Un(UnOp::Neg, op) => expect(op, true, target)?,
+                RValue::Bin(op, a, b) | RValue::WrappingBin(op, a, b) => match op {
+                    // The shift amount is independent of the shifted operand's width.
+                    BinOp::Shl | BinOp::Shr => expect(a, false, target)?,
+                    _ => {
+                        expect(a, false, operand_ty(b))?;
+                        expect(b, false, operand_ty(a))?;
+                    }
+                },
+                RValue::Aggregate(AggKind::Struct(s), ops) => {
+                    if let Some(TypeDef::Struct { fields, .. }) = types.get(s) {
+                        for (op, field) in ops.iter().zip(fields) {
+                            expect(op, false, Some(&field.ty))?;
+                        }
+                    }
+                }
+                RValue::Aggregate(AggKind::Variant(e, variant), ops) => {
+                    if let Some(TypeDef::Enum { variants, .. }) = types.get(e) {
+                        let fields = variants.get(*variant as usize).map(|v| &v.fields[..]);
+                        for (op, field) in ops.iter().zip(fields.unwrap_or_default()) {
+                            expect(op, false, Some(field))?;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Terminator::Return(op) = &blk.term {
+            check_int_literal(op, false, ret).map_err(|e| at_line(line_of(blk.id, None), e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Check that `op`, if it is an integer literal (written with a leading `-` when
+/// `negated`), lies in the range of `ty`. Anything else passes. A literal above
+/// `i128::MAX` arrives as its `u128` bit pattern (see `Const::Int`), which only a
+/// `u128` can hold.
+fn check_int_literal(op: &Operand, negated: bool, ty: &Ty) -> Result<(), String> {
+    let (Operand::Const(Const::Int(n)), Some(w)) = (op, rv_ir::int_width(ty)) else {
+        return Ok(());
+    };
+    let fits = match (w.signed, negated) {
+        // No unsigned type holds a negative value; only `-0` is allowed.
+        (false, true) => *n == 0,
+        (false, false) if w.bits == 128 => true,
+        _ => {
+            let value = if negated { n.wrapping_neg() } else { *n };
+            w.min() <= value && value <= w.max()
+        }
+    };
+    if fits {
+        return Ok(());
+    }
+    let written = match negated {
+        true => format!("-{}", n.unsigned_abs()),
+        false => (*n as u128).to_string(),
+    };
+    let sign = if w.signed { "i" } else { "u" };
+    Err(E0080.tag(format!("integer literal `{written}` out of range for `{sign}{}`", w.bits)))
+}
+
+/// A local as an error names it: `` `x` ``, or `temporary N`.
+fn local_name(f: &Function<Parsed>, local: LocalId, syms: &Symbols) -> String {
+    match f.locals[local.0 as usize].name {
+        Some(n) => format!("`{}`", syms.resolve(n)),
+        None => format!("temporary {}", local.0),
+    }
+}
+
+/// The assignment that first gave a local its type.
+#[derive(Clone, Copy)]
+struct Origin {
+    line: Option<u32>,
+    /// The local it copied, when the assignment is a plain copy.
+    from: Option<LocalId>,
+}
+
+/// How `local` came by its type, nearest step first: the assignment that first
+/// typed it and, while that was a copy, the one that typed the local copied, back
+/// to an assignment of some other value or a declared type. A copy cycle ends the
+/// chain where it comes back round.
+fn origin_chain(
+    local: LocalId,
+    origins: &[Option<Origin>],
+    tys: &[Option<Ty>],
+    f: &Function<Parsed>,
+    syms: &Symbols,
+) -> Vec<Note> {
+    let name = |l: LocalId| local_name(f, l, syms);
+    let ty = |l: LocalId| tys[l.0 as usize].as_ref().map_or("?".to_string(), |t| format!("{t:?}"));
+    let mut notes = Vec::new();
+    let mut seen = HashSet::new();
+    let mut cur = local;
+    while seen.insert(cur) {
+        let origin = origins[cur.0 as usize];
+        if f.locals[cur.0 as usize].ty.is_some() {
+            let kind = if f.params.contains(&cur) { "parameter" } else { "local" };
+            let msg = format!("{kind} {} is declared {}", name(cur), ty(cur));
+            notes.push(Note::new(origin.and_then(|o| o.line), msg));
+            break;
+        }
+        let Some(origin) = origin else { break };
+        let Some(src) = origin.from else {
+            let msg = format!("{} is first given type {}", name(cur), ty(cur));
+            notes.push(Note::new(origin.line, msg));
+            break;
+        };
+        let msg = format!("{} takes type {} from {}", name(cur), ty(cur), name(src));
+        notes.push(Note::new(origin.line, msg));
+        cur = src;
+    }
+    notes
+}
+
+/// Prefix an error with its source line, when one is known.
+fn at_line(line: Option<u32>, msg: String) -> String {
+    match line {
+        Some(line) => format!("line {line}: {msg}"),
+        None => msg,
+    }
 }
 
 /// Record an inferred type for a local, erroring on a conflicting re-inference.
-fn set_ty(tys: &mut [Option<Ty>], local: LocalId, ty: Ty) -> Result<(), String> {
+fn set_ty(tys: &mut [Option<Ty>], local: LocalId, name: &str, ty: Ty) -> Result<(), String> {
     let slot = &mut tys[local.0 as usize];
     match slot {
+        Some(existing) => match merge_ty(existing, &ty) {
+            Some(merged) => {
+                *existing = merged;
+                Ok(())
+            }
+            None => Err(E0308.tag(format!(
+                "type error: {name} used as both {existing:?} and {ty:?}"
+            ))),
+        },
+        None => {
+            *slot = Some(ty);
+            Ok(())
+        }
+    }
+}
+
+/// Whether `ty` mentions a type parameter other than the enclosing function's own
+/// (`own`): the type of a generic function value whose type arguments are not yet
+/// known.
+fn has_foreign_params(ty: &Ty, own: &[Sym]) -> bool {
+    let go = |t: &Ty| has_foreign_params(t, own);
+    match ty {
+        Ty::Param(name) => !own.contains(name),
+        Ty::Adt(_, items) | Ty::Tuple(items) => items.iter().any(go),
+        Ty::Ref { inner: item, .. } | Ty::Array(item, _) | Ty::Vec(item) => go(item),
+        Ty::Fn(params, ret) => params.iter().any(go) || go(ret),
+        _ => false,
+    }
+}
+
+/// Solve the type parameters of a generic function value of type `value` against
+/// the function type `want` it is used as, returning the instantiated type.
+fn pin_fn_value(value: &Ty, want: &Ty) -> Result<Ty, String> {
+    let mut substitutions = HashMap::new();
+    let pinned = collect_type_arguments(value, want, &mut substitutions)
+        .map(|()| instantiate_ty(value, &substitutions))
+        .ok()
+        .filter(|pinned| compatible(pinned, want));
+    pinned.ok_or_else(|| {
+        E0308.tag(format!("type error: generic function of type {value:?} used as {want:?}"))
+    })
+}
+
+/// Before a call is typed, instantiate each generic function value among its
+/// arguments (or, for a call through such a value, the value itself) from the
+/// rest of the call, recording the instantiation on the local holding it.
+fn pin_fn_arguments(
+    rv: &RValue,
+    tys: &mut [Option<Ty>],
+    f: &Function<Parsed>,
+    types: &HashMap<Sym, TypeDef>,
+    calls: Option<Callees<'_>>,
+) -> Result<(), String> {
+    let open_local = |op: &Operand, tys: &[Option<Ty>]| match op {
+        Operand::Copy(place) if place.proj.is_empty() => tys
+            [place.local.0 as usize]
+            .clone()
+            .filter(|ty| has_foreign_params(ty, &f.type_params))
+            .map(|ty| (place.local, ty)),
+        _ => None,
+    };
+    match rv {
+        RValue::Call(callee, args) => {
+            if args.iter().all(|arg| open_local(arg, tys).is_none()) {
+                return Ok(());
+            }
+            let Some(sig) = calls.and_then(|calls| calls.sigs.signature(*callee)) else {
+                return Ok(());
+            };
+            // The other arguments fix what they can of the callee's type parameters;
+            // a mismatch among them is reported when the call itself is typed.
+            let mut substitutions = HashMap::new();
+            for (param, arg) in sig.params.iter().zip(args) {
+                if open_local(arg, tys).is_none() {
+                    let _ = type_of_operand(arg, tys, types).and_then(|actual| {
+                        collect_type_arguments(param, &actual, &mut substitutions)
+                    });
+                }
+            }
+            for (param, arg) in sig.params.iter().zip(args) {
+                if let Some((local, ty)) = open_local(arg, tys) {
+                    let pinned = pin_fn_value(&ty, &instantiate_ty(param, &substitutions))?;
+                    let _ = collect_type_arguments(param, &pinned, &mut substitutions);
+                    tys[local.0 as usize] = Some(pinned);
+                }
+            }
+        }
+        RValue::CallClosure(callee, args) => {
+            let Some((local, Ty::Fn(params, ret))) = open_local(callee, tys) else {
+                return Ok(());
+            };
+            let mut substitutions = HashMap::new();
+            for (param, arg) in params.iter().zip(args) {
+                let _ = type_of_operand(arg, tys, types).and_then(|actual| {
+                    collect_type_arguments(param, &actual, &mut substitutions)
+                });
+            }
+            tys[local.0 as usize] = Some(instantiate_ty(&Ty::Fn(params, ret), &substitutions));
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// The most specific type consistent with two inferences for the same value, or
+/// `None` if they conflict.
+fn merge_ty(existing: &Ty, new: &Ty) -> Option<Ty> {
+    match (existing, new) {
         // GENERIC LENIENCY: if either the existing or the new inference is an opaque
         // type parameter, do not treat the difference as a conflict — a generic local
         // is abstract. Keep whichever is concrete (prefer a concrete type over `Param`)
         // so later concrete checks still see the most specific type we know.
-        Some(existing) if matches!(existing, Ty::Param(_)) || matches!(ty, Ty::Param(_)) => {
-            if matches!(existing, Ty::Param(_)) {
-                *slot = Some(ty);
-            }
-            Ok(())
-        }
+        (Ty::Param(_), _) => Some(new.clone()),
+        (_, Ty::Param(_)) => Some(existing.clone()),
+        // `!` has no values, so it agrees with whatever else the local is.
+        (Ty::Never, _) => Some(new.clone()),
+        (_, Ty::Never) => Some(existing.clone()),
         // INTEGER LENIENCY: a sized `IntN` and the default `Int` are compatible
         // (e.g. a `u8` local assigned an `Int` literal). Keep the sized width — it
         // is the more specific type and carries the overflow bounds.
-        Some(existing) if int_like(existing) && int_like(&ty) => {
-            if matches!(existing, Ty::Int) && matches!(ty, Ty::IntN(_)) {
-                *slot = Some(ty);
+        (Ty::Int, Ty::IntN(_)) => Some(new.clone()),
+        (a, b) if int_like(a) && int_like(b) => Some(a.clone()),
+        // The same ADT: unknown (empty) arguments take the other side's, and known
+        // ones merge pointwise, so `None` then `Some(5)` pins `Option<Int>`.
+        (Ty::Adt(a, xs), Ty::Adt(b, ys)) if a == b => {
+            if xs.is_empty() || ys.is_empty() {
+                return Some(if xs.is_empty() { new.clone() } else { existing.clone() });
             }
-            Ok(())
-        }
-        Some(existing) if *existing != ty => {
-            Err(format!("type error: local {} used as both {:?} and {:?}", local.0, existing, ty))
-        }
-        _ => {
-            *slot = Some(ty);
-            Ok(())
+            if xs.len() != ys.len() {
+                return None;
+            }
+            let args = xs.iter().zip(ys).map(|(x, y)| merge_ty(x, y)).collect::<Option<_>>()?;
+            Some(Ty::Adt(*a, args))
         }
+        _ if existing == new => Some(existing.clone()),
+        _ => None,
     }
 }
 
@@ -321,7 +891,7 @@ fn type_of_rvalue(
     f: &Function<Parsed>,
     types: &HashMap<Sym, TypeDef>,
     returns: &HashMap<Sym, Ty>,
-    calls: Option<&HashMap<Sym, CallableType>>,
+    calls: Option<Callees<'_>>,
     syms: &Symbols,
 ) -> Result<Ty, String> {
     match rv {
@@ -332,13 +902,15 @@ fn type_of_rvalue(
             match op {
                 // Arithmetic preserves the integer width: if either operand is a
                 // sized `IntN`, so is the result (used to pick overflow bounds).
-                // Bitwise/shift ops are integer-typed too (no overflow obligation
-                // is emitted for them — that check is gated on `Add|Sub|Mul`).
+                // Bitwise ops are integer-typed too (no overflow obligation is
+                // emitted for them — that check is gated on `Add|Sub|Mul`). A shift
+                // has its left operand's type: the amount only says how far.
                 // Float arithmetic: if either operand is a float, the result is a float (no
                 // overflow obligation — floats are opaque to the linear solver).
                 Add | Sub | Mul | Div | Mod if matches!(ta, Ty::Float) || matches!(tb, Ty::Float) => {
                     Ok(Ty::Float)
                 }
+                Shl | Shr if int_like(&ta) && int_like(&tb) => Ok(ta),
                 Add | Sub | Mul | Div | Mod | BitAnd | BitOr | BitXor | Shl | Shr => {
                     int_result_ty(&ta, &tb).ok_or_else(|| "arithmetic on non-integers".to_string())
                 }
@@ -347,6 +919,15 @@ fn type_of_rvalue(
                     check(&tb, &Ty::Bool, "logic")?;
                     Ok(Ty::Bool)
                 }
+                // A reference equals only a reference: comparing one with a plain
+                // value would compare the pointer.
+                Eq | Ne if matches!(ta, Ty::Ref { .. }) != matches!(tb, Ty::Ref { .. }) => {
+                    Err(E0308.tag(format!(
+                        "cannot compare `{}` with `{}`; dereference the reference with `*`",
+                        describe_ty(&ta, syms),
+                        describe_ty(&tb, syms)
+                    )))
+                }
                 Eq | Ne => Ok(Ty::Bool),
                 Lt | Le | Gt | Ge => {
                     if (int_like(&ta) && int_like(&tb))
@@ -375,29 +956,61 @@ fn type_of_rvalue(
                 }
             }
         }
-        // A call has its callee's declared/inferred return type. The map is seeded
-        // from every declaration before body inference and then refreshed with
-        // actual inferred returns, so forward calls and lifted functions are not
-        // silently treated as `i64`.
+        // `e as ty` is valid only for the conversions `CastKind` classifies; the
+        // result has the target type.
+        RValue::Cast(a, to) => {
+            let from = type_of_operand(a, tys, types)?;
+            if CastKind::of(&from, to).is_none() {
+                return Err(E0605.tag(format!(
+                    "type error: cannot cast {} to {} (allowed casts: integer or f64 to \
+                     integer or f64, and bool to integer)",
+                    describe_ty(&from, syms),
+                    describe_ty(to, syms),
+                )));
+            }
+            Ok(to.clone())
+        }
+        // A call has its callee's signature return type. Only the provisional pass
+        // of [`signatures`] runs without signatures; it falls back to `returns`,
+        // the declared returns.
+        RValue::Call(callee, args) if [TO_STRING, STR_CONCAT].contains(&syms.resolve(*callee)) => {
+            type_of_string_builtin(syms.resolve(*callee), args, tys, types, syms)
+        }
+        RValue::Call(callee, args) if syms.resolve(*callee) == FLOAT_BITS => match &args[..] {
+            [arg] if type_of_operand(arg, tys, types)? == Ty::Float => Ok(Ty::Int),
+            _ => Err(format!("`{FLOAT_BITS}` takes one `f64`")),
+        },
+        RValue::Call(callee, args) if [ASSERT_EQ, ASSERT_NE].contains(&syms.resolve(*callee)) => {
+            type_of_assert_cmp(syms.resolve(*callee), args, tys, types, syms)
+        }
         RValue::Call(callee, args) => {
             let _ = f;
-            let sig = calls.and_then(|calls| calls.get(callee));
-            if let Some(sig) = sig {
+            let found = calls.and_then(|calls| Some((calls.sigs.signature(*callee)?, calls)));
+            if let Some((sig, calls)) = found {
                 if args.len() != sig.params.len() {
-                    return Err(format!(
+                    return Err(E0061.tag(format!(
                         "type error: call expects {} arguments, got {}",
                         sig.params.len(),
                         args.len()
-                    ));
+                    )));
                 }
                 for (index, (arg, param)) in args.iter().zip(&sig.params).enumerate() {
+                    check_int_literal(arg, false, param)?;
                     let arg_ty = type_of_operand(arg, tys, types)?;
                     check(&arg_ty, param, &format!("argument {} of call", index + 1))?;
                 }
                 let substitutions = infer_type_arguments(&sig.params, args, tys, types)?;
-                check_generic_bounds(sig, &substitutions, syms)?;
+                check_generic_bounds(&sig, calls.trait_impls, &substitutions, syms)?;
                 return Ok(instantiate_ty(&sig.ret, &substitutions));
             }
+            // Once the callable map is complete, a callee missing from it (and not
+            // the `print` builtin) names no function in this compilation.
+            if calls.is_some() && syms.resolve(*callee) != "print" {
+                return Err(E0001.tag(format!(
+                    "call to undefined function `{}`",
+                    syms.resolve(*callee)
+                )));
+            }
             Ok(returns.get(callee).cloned().unwrap_or(Ty::Int))
         }
         // Lambda lowering creates a top-level function whose leading parameters are
@@ -405,7 +1018,7 @@ fn type_of_rvalue(
         // parameters to the caller. This recovers the closure's real function type
         // without a special closure-only type system.
         RValue::Closure(func, captures) => {
-            if let Some(sig) = calls.and_then(|calls| calls.get(func)) {
+            if let Some(sig) = calls.and_then(|calls| calls.sigs.signature(*func)) {
                 let exposed = sig.params.get(captures.len()..).ok_or_else(|| {
                     "internal error: closure captures exceed lifted function parameters".to_string()
                 })?;
@@ -449,7 +1062,8 @@ fn type_of_rvalue(
                 return Err(format!("unknown struct constructor {s:?}"));
             };
             check_aggregate_fields(ops, fields.iter().map(|field| &field.ty), tys, types, "struct")?;
-            Ok(Ty::Adt(*s))
+            let field_tys: Vec<Ty> = fields.iter().map(|field| field.ty.clone()).collect();
+            constructed_adt(*s, &field_tys, ops, tys, types)
         }
         RValue::Aggregate(AggKind::Variant(e, variant), ops) => {
             let Some(TypeDef::Enum { variants, .. }) = types.get(e) else {
@@ -459,7 +1073,7 @@ fn type_of_rvalue(
                 .get(*variant as usize)
                 .ok_or_else(|| format!("unknown enum variant {variant} for {e:?}"))?;
             check_aggregate_fields(ops, fields.fields.iter(), tys, types, "enum variant")?;
-            Ok(Ty::Adt(*e))
+            constructed_adt(*e, &fields.fields, ops, tys, types)
         }
         // A tuple's type is the tuple of its operands' types.
         RValue::Aggregate(AggKind::Tuple, ops) => {
@@ -528,15 +1142,30 @@ fn collect_type_arguments(
     substitutions: &mut HashMap<Sym, Ty>,
 ) -> Result<(), String> {
     match formal {
-        Ty::Param(name) => match substitutions.get(name) {
-            Some(previous) if previous != actual => Err(format!(
-                "type error: generic parameter {name:?} inferred as both {previous:?} and {actual:?}"
-            )),
-            _ => {
-                substitutions.insert(*name, actual.clone());
-                Ok(())
+        Ty::Param(name) => {
+            let merged = match substitutions.get(name) {
+                Some(previous) => merge_ty(previous, actual).ok_or_else(|| {
+                    format!(
+                        "type error: generic parameter {name:?} inferred as both {previous:?} \
+                         and {actual:?}"
+                    )
+                })?,
+                None => actual.clone(),
+            };
+            substitutions.insert(*name, merged);
+            Ok(())
+        }
+        // `Option<T>` against `Option<i64>` pins `T`; unknown arguments pin nothing.
+        Ty::Adt(name, formals) => {
+            if let Ty::Adt(actual_name, actuals) = actual {
+                if name == actual_name && formals.len() == actuals.len() {
+                    for (formal, actual) in formals.iter().zip(actuals) {
+                        collect_type_arguments(formal, actual, substitutions)?;
+                    }
+                }
             }
-        },
+            Ok(())
+        }
         Ty::Ref { mutable, inner } => {
             if let Ty::Ref { mutable: actual_mut, inner: actual_inner } = actual {
                 if mutable == actual_mut {
@@ -545,6 +1174,18 @@ fn collect_type_arguments(
             }
             Ok(())
         }
+        // `fn(T) -> U` against `fn(i64) -> bool` pins both, as in a higher-order call.
+        Ty::Fn(formals, ret) => {
+            if let Ty::Fn(actuals, actual_ret) = actual {
+                if formals.len() == actuals.len() {
+                    for (formal, actual) in formals.iter().zip(actuals) {
+                        collect_type_arguments(formal, actual, substitutions)?;
+                    }
+                    collect_type_arguments(ret, actual_ret, substitutions)?;
+                }
+            }
+            Ok(())
+        }
         _ => Ok(()),
     }
 }
@@ -552,6 +1193,9 @@ fn collect_type_arguments(
 fn instantiate_ty(ty: &Ty, substitutions: &HashMap<Sym, Ty>) -> Ty {
     match ty {
         Ty::Param(name) => substitutions.get(name).cloned().unwrap_or_else(|| ty.clone()),
+        Ty::Adt(name, args) => {
+            Ty::Adt(*name, args.iter().map(|arg| instantiate_ty(arg, substitutions)).collect())
+        }
         Ty::Ref { mutable, inner } => Ty::Ref {
             mutable: *mutable,
             inner: Box::new(instantiate_ty(inner, substitutions)),
@@ -567,18 +1211,83 @@ fn instantiate_ty(ty: &Ty, substitutions: &HashMap<Sym, Ty>) -> Ty {
     }
 }
 
-/// Render a type for a user-facing diagnostic. Only the shapes reachable as a
-/// generic instantiation need friendly names; anything else falls back to the
-/// structural `Debug` form (still readable, just less pretty).
+/// The type of a call to [`ASSERT_EQ`] or [`ASSERT_NE`]: `()`, once both operands
+/// are found to have one type and not to be references, whose equality would be
+/// the pointers'.
+fn type_of_assert_cmp(
+    callee: &str,
+    args: &[Operand],
+    tys: &[Option<Ty>],
+    types: &HashMap<Sym, TypeDef>,
+    syms: &Symbols,
+) -> Result<Ty, String> {
+    let name = if callee == ASSERT_EQ { "assert_eq" } else { "assert_ne" };
+    let [a, b] = args else { unreachable!("lowering passes `{name}` two operands") };
+    let (ta, tb) = (type_of_operand(a, tys, types)?, type_of_operand(b, tys, types)?);
+    if [&ta, &tb].iter().any(|t| matches!(t, Ty::Ref { .. })) {
+        return Err(E0308.tag(format!(
+            "`{name}` cannot compare references; dereference them with `*`"
+        )));
+    }
+    // An integer literal takes the other operand's width.
+    check_int_literal(a, false, &tb)?;
+    check_int_literal(b, false, &ta)?;
+    if !(int_like(&ta) && int_like(&tb) || compatible(&ta, &tb) || compatible(&tb, &ta)) {
+        return Err(E0308.tag(format!(
+            "`{name}` compares two values of one type, but got `{}` and `{}`",
+            describe_ty(&ta, syms),
+            describe_ty(&tb, syms)
+        )));
+    }
+    Ok(Ty::Unit)
+}
+
+/// Render a type as it is written in source, for diagnostics and type dumps.
+/// The type of a call to one of the builtins `format` expands into: [`TO_STRING`]
+/// renders an integer, float, bool or string, and [`STR_CONCAT`] joins two strings.
+fn type_of_string_builtin(
+    callee: &str,
+    args: &[Operand],
+    tys: &[Option<Ty>],
+    types: &HashMap<Sym, TypeDef>,
+    syms: &Symbols,
+) -> Result<Ty, String> {
+    for arg in args {
+        let ty = type_of_operand(arg, tys, types)?;
+        let shown = match (callee, &ty) {
+            (TO_STRING, Ty::Int | Ty::IntN(_) | Ty::Float | Ty::Bool) | (_, Ty::Str) => continue,
+            _ => describe_ty(&ty, syms),
+        };
+        return Err(E0277.tag(format!(
+            "`format` cannot render a value of type `{shown}`: \
+             its arguments must be integers, floats, bools or strings"
+        )));
+    }
+    Ok(Ty::Str)
+}
+
 fn describe_ty(ty: &Ty, syms: &Symbols) -> String {
+    let list = |tys: &[Ty]| tys.iter().map(|t| describe_ty(t, syms)).collect::<Vec<_>>().join(", ");
     match ty {
-        Ty::Adt(name) => syms.resolve(*name).to_string(),
+        Ty::Adt(name, args) if args.is_empty() => syms.resolve(*name).to_string(),
+        Ty::Adt(name, args) => format!("{}<{}>", syms.resolve(*name), list(args)),
         Ty::Int => "i64".to_string(),
         Ty::IntN(w) => format!("{}{}", if w.signed { "i" } else { "u" }, w.bits),
         Ty::Bool => "bool".to_string(),
         Ty::Float => "f64".to_string(),
         Ty::Param(name) => syms.resolve(*name).to_string(),
-        other => format!("{other:?}"),
+        Ty::Str => "String".to_string(),
+        Ty::Unit => "()".to_string(),
+        Ty::Never => "!".to_string(),
+        Ty::Tuple(items) if items.len() == 1 => format!("({},)", describe_ty(&items[0], syms)),
+        Ty::Tuple(items) => format!("({})", list(items)),
+        Ty::Array(item, len) => format!("[{}; {len}]", describe_ty(item, syms)),
+        Ty::Vec(item) => format!("Vec<{}>", describe_ty(item, syms)),
+        Ty::Ref { mutable, inner } => {
+            format!("&{}{}", if *mutable { "mut " } else { "" }, describe_ty(inner, syms))
+        }
+        Ty::Fn(params, ret) if **ret == Ty::Unit => format!("fn({})", list(params)),
+        Ty::Fn(params, ret) => format!("fn({}) -> {}", list(params), describe_ty(ret, syms)),
     }
 }
 
@@ -592,7 +1301,8 @@ fn describe_ty(ty: &Ty, syms: &Symbols) -> String {
 /// a built-in in this surface. Diagnostics resolve every `Sym` through `syms` so
 /// the message names the offending type and trait rather than opaque ids.
 fn check_generic_bounds(
-    sig: &CallableType,
+    sig: &SigTypes,
+    trait_impls: &[rv_ir::TraitImpl],
     substitutions: &HashMap<Sym, Ty>,
     syms: &Symbols,
 ) -> Result<(), String> {
@@ -606,7 +1316,7 @@ fn check_generic_bounds(
                 syms.resolve(*param)
             )
         })?;
-        let Ty::Adt(type_name) = actual else {
+        let Ty::Adt(type_name, _) = actual else {
             return Err(format!(
                 "type `{}` cannot satisfy the trait bounds on `{}` (only user-defined \
                  types can implement a trait)",
@@ -615,8 +1325,7 @@ fn check_generic_bounds(
             ));
         };
         for trait_name in bounds {
-            let satisfied = sig
-                .trait_impls
+            let satisfied = trait_impls
                 .iter()
                 .any(|imp| imp.trait_name == *trait_name && imp.type_name == *type_name);
             if !satisfied {
@@ -632,6 +1341,26 @@ fn check_generic_bounds(
     Ok(())
 }
 
+/// The type a constructor of ADT `name` builds: each of the ADT's type parameters
+/// is instantiated from the operands matched against the declared `fields`. A
+/// parameter no operand determines (`None` of `Option<T>`) stays `Ty::Param`,
+/// which later assignments and uses are free to pin.
+fn constructed_adt(
+    name: Sym,
+    fields: &[Ty],
+    ops: &[Operand],
+    tys: &[Option<Ty>],
+    types: &HashMap<Sym, TypeDef>,
+) -> Result<Ty, String> {
+    let type_params = types.get(&name).map_or(&[][..], TypeDef::type_params);
+    let substitutions = infer_type_arguments(fields, ops, tys, types)?;
+    let args = type_params
+        .iter()
+        .map(|p| substitutions.get(p).cloned().unwrap_or(Ty::Param(*p)))
+        .collect();
+    Ok(Ty::Adt(name, args))
+}
+
 /// Check an aggregate constructor's arity and operand types against its declared
 /// fields. Generic parameters remain abstract through [`check`], as elsewhere.
 fn check_aggregate_fields<'a>(
@@ -704,7 +1433,7 @@ fn resolve_proj_ty(base: &Ty, proj: &[Proj], types: &HashMap<Sym, TypeDef>) -> T
                     variant = 0;
                     continue;
                 }
-                let Ty::Adt(name) = &cur else { return Ty::Int };
+                let Ty::Adt(name, args) = &cur else { return Ty::Int };
                 let field_ty = match types.get(name) {
                     Some(TypeDef::Struct { fields, .. }) => {
                         fields.get(*n as usize).map(|fd| fd.ty.clone())
@@ -714,7 +1443,15 @@ fn resolve_proj_ty(base: &Ty, proj: &[Proj], types: &HashMap<Sym, TypeDef>) -> T
                         .and_then(|vd| vd.fields.get(*n as usize).cloned()),
                     None => None,
                 };
-                cur = field_ty.unwrap_or(Ty::Int);
+                // A field declared with the ADT's type parameters takes the base
+                // value's type arguments, when they are known.
+                let type_params = types.get(name).map_or(&[][..], TypeDef::type_params);
+                let substitutions: HashMap<Sym, Ty> = if args.len() == type_params.len() {
+                    type_params.iter().copied().zip(args.iter().cloned()).collect()
+                } else {
+                    HashMap::new()
+                };
+                cur = field_ty.map_or(Ty::Int, |ty| instantiate_ty(&ty, &substitutions));
                 variant = 0;
             }
             // Indexing an array yields its element type.
@@ -786,18 +1523,15 @@ fn int_result_ty(a: &Ty, b: &Ty) -> Option<Ty> {
 }
 
 fn check_return(actual: &Ty, declared: &Ty) -> Result<(), String> {
-    if matches!(actual, Ty::Param(_)) || matches!(declared, Ty::Param(_)) {
-        return Ok(());
-    }
     if int_like(actual) && int_like(declared) {
         return Ok(());
     }
-    if actual == declared {
+    if compatible(actual, declared) {
         Ok(())
     } else {
-        Err(format!(
+        Err(E0308.tag(format!(
             "type error in return type: signature declares {declared:?}, but the body returns {actual:?}"
-        ))
+        )))
     }
 }
 
@@ -807,13 +1541,32 @@ fn check(got: &Ty, want: &Ty, ctx: &str) -> Result<(), String> {
     // against a concrete expectation. Treating `Param` as compatible with anything
     // never *adds* facts (the value stays an opaque `Term::Var` in VC generation), so
     // this is sound: we only forgo rejecting a generic body, never prove something false.
-    if matches!(got, Ty::Param(_)) || matches!(want, Ty::Param(_)) {
-        return Ok(());
-    }
-    if got == want {
+    if compatible(got, want) {
         Ok(())
     } else {
-        Err(format!("type error in {ctx}: expected {want:?}, got {got:?}"))
+        Err(E0308.tag(format!("type error in {ctx}: expected {want:?}, got {got:?}")))
+    }
+}
+
+/// Whether `got` may be used where `want` is expected: equal types, with a
+/// `Ty::Param` on either side compatible with anything (see [`check`]), a `!` value
+/// usable anywhere, unknown ADT type arguments compatible with any instantiation,
+/// and function types compared part by part.
+fn compatible(got: &Ty, want: &Ty) -> bool {
+    match (got, want) {
+        (Ty::Param(_), _) | (_, Ty::Param(_)) | (Ty::Never, _) => true,
+        (Ty::Fn(xs, x), Ty::Fn(ys, y)) => {
+            xs.len() == ys.len()
+                && xs.iter().zip(ys).all(|(x, y)| compatible(x, y))
+                && compatible(x, y)
+        }
+        (Ty::Adt(a, xs), Ty::Adt(b, ys)) => {
+            a == b
+                && (xs.is_empty()
+                    || ys.is_empty()
+                    || (xs.len() == ys.len() && xs.iter().zip(ys).all(|(x, y)| compatible(x, y))))
+        }
+        _ => got == want,
     }
 }
 
@@ -891,9 +1644,9 @@ fn scrutinee_variant_count(
     types: &HashMap<Sym, TypeDef>,
 ) -> Option<usize> {
     let Operand::Copy(place) = scrutinee else { return None };
-    let ty = &f.locals[place.local.0 as usize].ty;
-    let Ty::Adt(name) = ty else { return None };
-    match types.get(name) {
+    let ty = resolve_proj_ty(&f.locals[place.local.0 as usize].ty, &place.proj, types);
+    let Ty::Adt(name, _) = ty else { return None };
+    match types.get(&name) {
         Some(TypeDef::Enum { variants, .. }) => Some(variants.len()),
         _ => None,
     }
@@ -933,6 +1686,8 @@ struct State {
 /// Carries everything a function's VC walk needs.
 struct VcGen<'a> {
     f: &'a Function<Parsed>,
+    /// Where each of `f`'s blocks sits in `f.blocks`.
+    block_index: HashMap<BlockId, usize>,
     /// The typed (Lowerable) view of the same function — used to resolve a
     /// place's type (e.g. an array's length) when emitting bounds obligations.
     low: &'a Function<Lowerable>,
@@ -975,15 +1730,14 @@ impl VcGen<'_> {
         self.exec_block(self.f.entry, state)
     }
 
-    /// Look up a block by id (CFGs here are small; linear search is fine).
+    /// Look up a block by id.
     ///
     /// Returns `Err` for a dangling id (a reference to a block not present in the
     /// function) so a malformed CFG becomes a clean rejection rather than a panic.
     fn block(&self, id: BlockId) -> Result<&Block<Parsed>, String> {
-        self.f
-            .blocks
-            .iter()
-            .find(|b| b.id == id)
+        self.block_index
+            .get(&id)
+            .map(|&i| &self.f.blocks[i])
             .ok_or_else(|| format!("malformed CFG: dangling block id {}", id.0))
     }
 
@@ -991,7 +1745,12 @@ impl VcGen<'_> {
     ///
     /// Returns `Err` if this block or any block reachable from it names a dangling
     /// id; `Ok(())` for any well-formed CFG.
-    fn exec_block(&mut self, id: BlockId, mut state: State) -> Result<(), String> {
+    fn exec_block(&mut self, id: BlockId, state: State) -> Result<(), String> {
+        // A path through nested branches is as long as their nesting is deep.
+        rv_arena::stack::grow(|| self.exec_block_here(id, state))
+    }
+
+    fn exec_block_here(&mut self, id: BlockId, mut state: State) -> Result<(), String> {
         // A block whose first statements are `Invariant`s is a loop header. The
         // FIRST time we reach it on this path we switch to the invariant scheme
         // (entry check + havoc/assume + one body pass) instead of plain forward
@@ -1194,22 +1953,10 @@ impl VcGen<'_> {
         Ok(body)
     }
 
-    /// The successor blocks of a block's terminator (`Err` on a dangling id).
+    /// The successor blocks of a block's terminator (`Err` on a dangling id). A
+    /// `Return` or `Panic` has none, which bounds the body search.
     fn successors(&self, id: BlockId) -> Result<Vec<BlockId>, String> {
-        Ok(match &self.block(id)?.term {
-            Terminator::Goto(b) => vec![*b],
-            Terminator::Branch { then_blk, else_blk, .. } => vec![*then_blk, *else_blk],
-            Terminator::Match { arms, otherwise, .. } => {
-                let mut v: Vec<BlockId> = arms.iter().map(|a| a.target).collect();
-                v.extend(otherwise.iter().copied());
-                v
-            }
-            // A Return ends the path: no successors (this bounds the body search).
-            Terminator::Return(_) => vec![],
-            // A Panic aborts: no successors (also bounds the body search).
-            Terminator::Panic => vec![],
-            Terminator::Drop { next, .. } => vec![*next],
-        })
+        Ok(self.block(id)?.term.successors())
     }
 
     /// The set of locals assigned (whole-local or via a projection) anywhere in the
@@ -1328,6 +2075,11 @@ impl VcGen<'_> {
                     state.env.insert(place.local, fresh);
                 }
             }
+            // A discarded call still emits its obligations (argument checks, the
+            // callee's precondition) and assumes the callee's postcondition.
+            Stmt::Eval(rv) => {
+                self.term_of_rvalue(rv, state);
+            }
             Stmt::Assert(p) => {
                 // ASSERT: prove `p` under the current path. The assertion is written
                 // in terms of source variable *names*; resolve each named local to its
@@ -1373,6 +2125,27 @@ impl VcGen<'_> {
                     let (lo, hi) = self.overflow_range(a, b);
                     self.emit_overflow(&Term::bin(*op, ta.clone(), tb.clone()), lo, hi, state);
                 }
+                // The one overflowing division: a signed type's minimum by -1.
+                if !is_float && *op == BinOp::Div {
+                    self.emit_division_overflow(a, b, &ta, &tb, state);
+                }
+                // A float comparison is IEEE: every comparison with NaN is false,
+                // so not even `x == x` holds. The solver's integer laws (reflexivity,
+                // `!(x < y)` implying `x >= y`) would prove false facts, so the
+                // result is an unconstrained boolean.
+                use BinOp::{Eq, Ge, Gt, Le, Lt, Ne};
+                if is_float && matches!(op, Eq | Ne | Lt | Le | Gt | Ge) {
+                    return Term::Var(self.fresh_var("$fcmp"));
+                }
+                // Unit has one value, so `==`/`!=` on it is decided without
+                // looking at the operands (a call's unit result is otherwise an
+                // opaque variable the solver can't equate).
+                if matches!(op, Eq | Ne)
+                    && self.operand_ty(a) == Ty::Unit
+                    && self.operand_ty(b) == Ty::Unit
+                {
+                    return Term::Bool(*op == Eq);
+                }
                 Term::bin(*op, ta, tb)
             }
             // Wrapping arithmetic: same value term, but NO overflow obligation.
@@ -1385,7 +2158,35 @@ impl VcGen<'_> {
                 }
                 Term::bin(*op, ta, tb)
             }
-            RValue::Un(op, a) => Term::un(*op, self.term_of_operand(a, state)),
+            RValue::Un(op, a) => {
+                let t = self.term_of_operand(a, state);
+                // Negating a variable must stay in range too (`-i64::MIN` does
+                // not); a literal's negation always does.
+                let is_int = matches!(self.operand_ty(a), Ty::Int | Ty::IntN(_));
+                if *op == UnOp::Neg && is_int && matches!(a, Operand::Copy(_)) {
+                    let (lo, hi) = self.overflow_range(a, a);
+                    self.emit_overflow(&Term::un(UnOp::Neg, t.clone()), lo, hi, state);
+                }
+                Term::un(*op, t)
+            }
+            // A cast to the operand's own type or a widening integer cast preserves the
+            // value, so it keeps the operand's term. Every other conversion (wrapping,
+            // saturating, to/from float) is not linear-integer arithmetic and becomes a
+            // fresh opaque value; the wrapping and saturating ones still land in the
+            // target's range, which is assumed (the VM guarantees it).
+            RValue::Cast(a, to) => {
+                let t = self.term_of_operand(a, state);
+                match CastKind::of(&self.operand_ty(a), to) {
+                    Some(CastKind::Identity | CastKind::IntExtend { .. }) => t,
+                    Some(CastKind::IntTruncate(w) | CastKind::FloatToInt(w)) => {
+                        let v = Term::Var(self.fresh_var("$cast"));
+                        let path = std::mem::replace(&mut state.path, Prop::True);
+                        state.path = range_assumption(path, &v, w);
+                        v
+                    }
+                    _ => Term::Var(self.fresh_var("$cast")),
+                }
+            }
             // `v.len()` is modeled as an uninterpreted length term over the
             // vector value — the SAME term the index-bounds check uses, so a guard
             // like `if i < v.len()` discharges `v[i]`'s bound by congruence.
@@ -1638,6 +2439,32 @@ impl VcGen<'_> {
         self.emit(state.path.clone(), hi_p, "arithmetic overflow");
     }
 
+    /// Emit the obligation that the signed division `a / b` does not overflow,
+    /// i.e. is not `MIN / -1`. Skipped when an operand is a literal that rules it
+    /// out (the common `x / 2`), or the type is unsigned.
+    fn emit_division_overflow(
+        &mut self,
+        a: &Operand,
+        b: &Operand,
+        ta: &Term,
+        tb: &Term,
+        state: &State,
+    ) {
+        let min = match int_result_ty(&self.operand_ty(a), &self.operand_ty(b)) {
+            Some(Ty::IntN(w)) if !w.signed => return,
+            Some(Ty::IntN(w)) => w.min(),
+            _ => i64::MIN as i128,
+        };
+        let ruled_out = matches!(b, Operand::Const(Const::Int(d)) if *d != -1)
+            || matches!(a, Operand::Const(Const::Int(n)) if *n != min);
+        if ruled_out {
+            return;
+        }
+        let not_min = Prop::Holds(Term::bin(BinOp::Ne, ta.clone(), Term::Int(min)));
+        let not_neg_one = Prop::Holds(Term::bin(BinOp::Ne, tb.clone(), Term::Int(-1)));
+        self.emit(state.path.clone(), not_min.or(not_neg_one), "arithmetic overflow (division)");
+    }
+
     /// The type of an operand, resolved from the typed (Lowerable) locals.
     fn operand_ty(&self, op: &Operand) -> Ty {
         match op {
@@ -1716,7 +2543,7 @@ mod tests {
     }
 
     fn decl(name: Option<Sym>) -> LocalDecl<Parsed> {
-        LocalDecl { name, ty: None }
+        LocalDecl { name, mutable: false, ty: None }
     }
 
     /// (a) Elaboration produces a `Lowerable` program and infers types.
@@ -1949,7 +2776,7 @@ mod tests {
         let prog = Program { types: vec![enum_td], trait_impls: vec![], funcs: vec![func] };
         let elab = elaborate(prog, &syms).expect("exhaustive match should elaborate");
         // The scrutinee local was typed as the ADT.
-        assert_eq!(elab.prog.funcs[0].locals[0].ty, Ty::Adt(e));
+        assert_eq!(elab.prog.funcs[0].locals[0].ty, Ty::Adt(e, vec![]));
         // types carried through unchanged.
         assert_eq!(elab.prog.types.len(), 1);
     }
@@ -2360,12 +3187,11 @@ mod tests {
         assert_eq!(elab.prog.funcs[0].type_params, vec![t]);
     }
 
-    /// A generic struct whose field is declared `Ty::Param(T)` resolves that field's
-    /// type to the opaque `Ty::Param(T)` (kept opaque, not flattened to Int), and
-    /// using such a value where arithmetic would normally demand `Int` raises NO type
-    /// error — the lenient-but-sound generic path.
+    /// A generic struct whose field is declared `Ty::Param(T)` is instantiated from
+    /// its constructor: `Box { val: 7 }` types as `Box<Int>`, and reading `val` back
+    /// yields `Int`, so the arithmetic on it checks.
     #[test]
-    fn generic_param_field_is_opaque_and_lenient() {
+    fn generic_param_field_takes_the_type_argument() {
         let mut syms = Symbols::new();
         let f = syms.intern("use_box");
         let box_ty = syms.intern("Box");
@@ -2379,9 +3205,9 @@ mod tests {
             fields: vec![FieldDef { name: val, ty: Ty::Param(tp) }],
         };
 
-        // local 0 = b : Box<T>  (built by aggregate, so typed Adt(Box)).
-        // local 1 = b.val       (a Field projection -> resolves to Param(T)).
-        // local 2 = b.val + 1   (arithmetic on a Param operand: must NOT error).
+        // local 0 = b : Box<Int>  (built by aggregate from an `Int` operand).
+        // local 1 = b.val         (a Field projection -> resolves to Int).
+        // local 2 = b.val + 1
         let l_b = LocalId(0);
         let l_v = LocalId(1);
         let l_s = LocalId(2);
@@ -2420,9 +3246,9 @@ mod tests {
             vec![blk],
         );
         let prog = Program { types: vec![box_def], trait_impls: vec![], funcs: vec![func] };
-        let elab = elaborate(prog, &syms).expect("generic-field use should elaborate leniently");
-        // The struct local is typed as its ADT (type arguments erased).
-        assert_eq!(elab.prog.funcs[0].locals[0].ty, Ty::Adt(box_ty));
+        let elab = elaborate(prog, &syms).expect("generic-field use should elaborate");
+        assert_eq!(elab.prog.funcs[0].locals[0].ty, Ty::Adt(box_ty, vec![Ty::Int]));
+        assert_eq!(elab.prog.funcs[0].locals[1].ty, Ty::Int);
     }
 
     /// (b) A generic enum `Option<T> { None, Some(T) }` with an exhaustive `match`
@@ -2486,8 +3312,8 @@ mod tests {
         );
         let prog = Program { types: vec![opt_def], trait_impls: vec![], funcs: vec![func] };
         let elab = elaborate(prog, &syms).expect("generic enum match should elaborate");
-        // The scrutinee local is typed as the (generic) ADT — type args erased.
-        assert_eq!(elab.prog.funcs[0].locals[0].ty, Ty::Adt(opt));
+        // `None` determines no type argument, so `T` stays an opaque parameter.
+        assert_eq!(elab.prog.funcs[0].locals[0].ty, Ty::Adt(opt, vec![Ty::Param(tp)]));
 
         // A non-exhaustive generic match (drop variant 1) is still rejected.
         let l_s2 = LocalId(0);
@@ -2603,16 +3429,17 @@ mod tests {
         param: Sym,
         bounds: Vec<Sym>,
         impls: Vec<(Sym, Sym)>,
-    ) -> CallableType {
-        CallableType {
+    ) -> (SigTypes, Vec<rv_ir::TraitImpl>) {
+        let sig = SigTypes {
             params: vec![Ty::Param(param)],
             ret: Ty::Param(param),
             generic_bounds: vec![(param, bounds)],
-            trait_impls: impls
-                .into_iter()
-                .map(|(trait_name, type_name)| rv_ir::TraitImpl { trait_name, type_name })
-                .collect(),
-        }
+        };
+        let impls = impls
+            .into_iter()
+            .map(|(trait_name, type_name)| rv_ir::TraitImpl { trait_name, type_name })
+            .collect();
+        (sig, impls)
     }
 
     /// A bound is satisfied when a matching `impl Trait for Ty` is registered:
@@ -2624,9 +3451,9 @@ mod tests {
         let t = syms.intern("T");
         let show = syms.intern("Show");
         let widget = syms.intern("Widget");
-        let sig = bounded_callable(t, vec![show], vec![(show, widget)]);
-        let subst = HashMap::from([(t, Ty::Adt(widget))]);
-        assert!(check_generic_bounds(&sig, &subst, &syms).is_ok());
+        let (sig, impls) = bounded_callable(t, vec![show], vec![(show, widget)]);
+        let subst = HashMap::from([(t, Ty::Adt(widget, vec![]))]);
+        assert!(check_generic_bounds(&sig, &impls, &subst, &syms).is_ok());
     }
 
     /// Instantiating a bounded parameter at an ADT that lacks the required impl
@@ -2639,9 +3466,9 @@ mod tests {
         let widget = syms.intern("Widget");
         let gadget = syms.intern("Gadget");
         // Only `Widget` implements `Show`; instantiate at `Gadget`.
-        let sig = bounded_callable(t, vec![show], vec![(show, widget)]);
-        let subst = HashMap::from([(t, Ty::Adt(gadget))]);
-        let err = check_generic_bounds(&sig, &subst, &syms).expect_err("must reject");
+        let (sig, impls) = bounded_callable(t, vec![show], vec![(show, widget)]);
+        let subst = HashMap::from([(t, Ty::Adt(gadget, vec![]))]);
+        let err = check_generic_bounds(&sig, &impls, &subst, &syms).expect_err("must reject");
         assert!(
             err.contains("Gadget") && err.contains("Show") && err.contains('T'),
             "message should name type, trait, and parameter: {err}"
@@ -2656,9 +3483,9 @@ mod tests {
         let mut syms = Symbols::new();
         let t = syms.intern("T");
         let show = syms.intern("Show");
-        let sig = bounded_callable(t, vec![show], vec![]);
+        let (sig, impls) = bounded_callable(t, vec![show], vec![]);
         let subst = HashMap::from([(t, Ty::Int)]);
-        let err = check_generic_bounds(&sig, &subst, &syms).expect_err("must reject");
+        let err = check_generic_bounds(&sig, &impls, &subst, &syms).expect_err("must reject");
         assert!(err.contains("i64"), "message should name the primitive: {err}");
     }
 
@@ -2743,8 +3570,25 @@ mod tests {
         let mut syms = Symbols::new();
         let t = syms.intern("T");
         let anything = syms.intern("Anything");
-        let sig = bounded_callable(t, vec![], vec![]);
-        let subst = HashMap::from([(t, Ty::Adt(anything))]);
-        assert!(check_generic_bounds(&sig, &subst, &syms).is_ok());
+        let (sig, impls) = bounded_callable(t, vec![], vec![]);
+        let subst = HashMap::from([(t, Ty::Adt(anything, vec![]))]);
+        assert!(check_generic_bounds(&sig, &impls, &subst, &syms).is_ok());
+    }
+
+    /// A read that a path from the entry reaches before any assignment is
+    /// rejected with the local's name, whatever the types say.
+    #[test]
+    fn read_of_an_unassigned_local_is_rejected() {
+        let mut syms = Symbols::new();
+        let mut b = rv_ir::builder::FunctionBuilder::<Parsed>::new(&mut syms, "f", None);
+        let flag = b.param("flag", Some(Ty::Bool));
+        let y = b.local("y", Some(Ty::Int));
+        let (yes, no) = (b.block(), b.block());
+        b.branch(rv_ir::builder::copy(flag), yes, no);
+        b.switch_to(yes).assign(y, RValue::Use(rv_ir::builder::int(1)));
+        b.ret(rv_ir::builder::copy(y));
+        b.switch_to(no).ret(rv_ir::builder::copy(y));
+        let err = elaborate(b.program(), &syms).err().expect("`y` is unassigned on one path");
+        assert_eq!(err, "[E0381] use of possibly-uninitialized variable `y`");
     }
 }
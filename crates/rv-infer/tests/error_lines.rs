@@ -0,0 +1,136 @@
+//! Error-location regression harness: each fixture marks the line its type error
+//! must be reported on with a caret comment directly beneath it,
+//!
+//! ```text
+//!     let r = double(true);
+//!     //      ^^^^^^^^^^^^ expected-error: argument 1 of call
+//! ```
+//!
+//! and the text after `expected-error:` must appear in the message. Locations are
+//! line-granular (the front end tracks lines, not columns), so the carets are
+//! documentation for the reader; only the line they sit under is checked.
+
+/// The `(line, message fragment)` a fixture expects, from its caret marker.
+fn expected_error(src: &str) -> (u32, String) {
+    let mut found = None;
+    for (i, text) in src.lines().enumerate() {
+        let text = text.trim_start();
+        let Some(marker) = text.strip_prefix("//") else { continue };
+        if !marker.trim_start().starts_with('^') {
+            continue;
+        }
+        let (_, fragment) = marker
+            .split_once("expected-error:")
+            .unwrap_or_else(|| panic!("caret marker on line {} has no `expected-error:`", i + 1));
+        assert!(found.is_none(), "a fixture marks exactly one error");
+        // `i` is the 0-based index of the marker, i.e. the 1-based number of the
+        // line above it.
+        found = Some((i as u32, fragment.trim().to_string()));
+    }
+    found.expect("fixture has no `//^^^ expected-error:` marker")
+}
+
+/// Run a fixture through parse -> lower -> elaborate and check its error location.
+fn check_fixture(src: &str) {
+    let (line, fragment) = expected_error(src);
+    let mut syms = rv_core::Symbols::new();
+    let module = rv_syntax::parse(src, &mut syms).expect("fixture parses");
+    let (prog, lines) = rv_lower::lower_with_lines(&module, &mut syms).expect("fixture lowers");
+    let err = match rv_infer::elaborate_with_lines(prog, &syms, &lines) {
+        Ok(_) => panic!("expected a type error on line {line}, but elaboration succeeded"),
+        Err(e) => e,
+    };
+    let prefix = format!("line {line}: ");
+    assert!(err.starts_with(&prefix), "expected the error on line {line}, got: {err}");
+    assert!(err.contains(&fragment), "expected `{fragment}` in: {err}");
+}
+
+#[test]
+fn if_branch_mismatch() {
+    check_fixture(include_str!("fixtures/if_branch_mismatch.rv"));
+}
+
+#[test]
+fn wrong_argument() {
+    check_fixture(include_str!("fixtures/wrong_argument.rv"));
+}
+
+#[test]
+fn bad_return() {
+    check_fixture(include_str!("fixtures/bad_return.rv"));
+}
+
+#[test]
+fn let_annotation_mismatch() {
+    check_fixture(include_str!("fixtures/let_annotation_mismatch.rv"));
+}
+
+#[test]
+fn field_type_mismatch() {
+    check_fixture(include_str!("fixtures/field_type_mismatch.rv"));
+}
+
+#[test]
+fn generic_argument_mismatch() {
+    check_fixture(include_str!("fixtures/generic_argument_mismatch.rv"));
+}
+
+#[test]
+fn undefined_function() {
+    check_fixture(include_str!("fixtures/undefined_function.rv"));
+}
+
+#[test]
+fn mismatched_types_carry_e0308() {
+    let src = include_str!("fixtures/let_annotation_mismatch.rv");
+    let mut syms = rv_core::Symbols::new();
+    let module = rv_syntax::parse(src, &mut syms).expect("fixture parses");
+    let (prog, lines) = rv_lower::lower_with_lines(&module, &mut syms).expect("fixture lowers");
+    let err = rv_infer::elaborate_with_lines(prog, &syms, &lines).err().expect("a type error");
+    let diag = rv_diagnostics::Diagnostic::parse(&err);
+    assert_eq!(diag.code, Some("E0308"), "got: {err}");
+    assert_eq!(diag.line, Some(2));
+}
+
+/// The notes of the error elaborating `src` reports, as `(line, message)`.
+fn error_notes(src: &str) -> Vec<(Option<u32>, String)> {
+    let mut syms = rv_core::Symbols::new();
+    let module = rv_syntax::parse(src, &mut syms).expect("source parses");
+    let (prog, lines) = rv_lower::lower_with_lines(&module, &mut syms).expect("source lowers");
+    let err = rv_infer::elaborate_with_lines(prog, &syms, &lines).err().expect("a type error");
+    let diag = rv_diagnostics::Diagnostic::parse(&err);
+    diag.notes.into_iter().map(|n| (n.line, n.message)).collect()
+}
+
+#[test]
+fn conflict_traces_the_flow_of_its_type() {
+    let src = "fn main() -> i64 {
+    let a = 1;
+    let b = a;
+    let c = b;
+    c = false;
+    return 0;
+}";
+    let notes = error_notes(src);
+    assert_eq!(
+        notes,
+        [
+            (Some(4), "`c` takes type Int from `b`".to_string()),
+            (Some(3), "`b` takes type Int from `a`".to_string()),
+            (Some(2), "`a` is first given type Int".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn long_flow_is_cut_at_the_note_limit() {
+    let mut src = "fn main() -> i64 {\n    let v0 = 1;\n".to_string();
+    for i in 1..=7 {
+        src.push_str(&format!("    let v{i} = v{};\n", i - 1));
+    }
+    src.push_str("    v7 = false;\n    return 0;\n}");
+    let notes = error_notes(&src);
+    assert_eq!(notes.len(), rv_diagnostics::DEFAULT_NOTE_LIMIT + 1);
+    assert_eq!(notes[0], (Some(9), "`v7` takes type Int from `v6`".to_string()));
+    assert_eq!(notes[3], (None, "…and 5 more steps".to_string()));
+}
@@ -0,0 +1,93 @@
+//! Generic ADT instantiation: a constructor's type arguments are inferred from
+//! its operands, and a `match` binding reads them back through the payload.
+
+use rv_core::{Symbols, Ty};
+
+/// Elaborate `src` and return the inferred type of each named local of `func`.
+fn local_types(src: &str, func: &str) -> Vec<(String, Ty)> {
+    let mut syms = Symbols::new();
+    let module = rv_syntax::parse(src, &mut syms).expect("source parses");
+    let (prog, lines) = rv_lower::lower_with_lines(&module, &mut syms).expect("source lowers");
+    let elab = rv_infer::elaborate_with_lines(prog, &syms, &lines).expect("source elaborates");
+    let f = elab
+        .prog
+        .funcs
+        .iter()
+        .find(|f| syms.resolve(f.name) == func)
+        .expect("function exists");
+    f.locals
+        .iter()
+        .filter_map(|d| Some((syms.resolve(d.name?).to_string(), d.ty.clone())))
+        .collect()
+}
+
+fn ty_of<'a>(locals: &'a [(String, Ty)], name: &str) -> &'a Ty {
+    &locals.iter().find(|(n, _)| n == name).unwrap_or_else(|| panic!("no local `{name}`")).1
+}
+
+fn type_args(ty: &Ty) -> &[Ty] {
+    match ty {
+        Ty::Adt(_, args) => args,
+        other => panic!("expected an ADT, got {other:?}"),
+    }
+}
+
+const OPTION: &str = "enum Option<T> { Some(T), None }\n";
+
+#[test]
+fn constructor_infers_its_type_argument() {
+    let src = format!(
+        "{OPTION}
+fn main() -> i64 {{
+    let a = Option::Some(5);
+    let b = Option::Some(true);
+    let c = Option::None;
+    c = Option::Some(false);
+    return 0;
+}}"
+    );
+    let locals = local_types(&src, "main");
+    assert_eq!(type_args(ty_of(&locals, "a")), [Ty::Int]);
+    assert_eq!(type_args(ty_of(&locals, "b")), [Ty::Bool]);
+    // `None` leaves `T` open; the later assignment pins it.
+    assert_eq!(type_args(ty_of(&locals, "c")), [Ty::Bool]);
+}
+
+#[test]
+fn match_binds_the_payload_at_the_type_argument() {
+    let src = format!(
+        "{OPTION}
+fn main() -> i64 {{
+    let o = Option::Some(5);
+    match o {{
+        Option::Some(n) => {{ return n; }}
+        Option::None => {{ return 0; }}
+    }}
+}}
+fn flag(o: Option<bool>) -> bool {{
+    match o {{
+        Option::Some(b) => {{ return b; }}
+        Option::None => {{ return false; }}
+    }}
+}}"
+    );
+    assert_eq!(ty_of(&local_types(&src, "main"), "n"), &Ty::Int);
+    assert_eq!(ty_of(&local_types(&src, "flag"), "b"), &Ty::Bool);
+}
+
+#[test]
+fn conflicting_instantiations_are_rejected() {
+    let src = format!(
+        "{OPTION}
+fn main() -> i64 {{
+    let o = Option::Some(5);
+    o = Option::Some(true);
+    return 0;
+}}"
+    );
+    let mut syms = Symbols::new();
+    let module = rv_syntax::parse(&src, &mut syms).unwrap();
+    let (prog, lines) = rv_lower::lower_with_lines(&module, &mut syms).unwrap();
+    let err = rv_infer::elaborate_with_lines(prog, &syms, &lines).err().expect("a type error");
+    assert!(err.contains("[E0308]") && err.contains("used as both"), "got: {err}");
+}
@@ -0,0 +1,84 @@
+//! Call sites are inferred against callee signatures alone: a caller's types
+//! follow its callees' declarations, whatever their bodies say.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use rv_core::{Sym, Symbols, Ty};
+use rv_infer::{CalleeSignatures, SigTypes};
+use rv_ir::{Function, Lowerable, Parsed, Program, SourceLines, TypeDef};
+
+fn lower(src: &str, syms: &mut Symbols) -> (Program<Parsed>, SourceLines) {
+    let module = rv_syntax::parse(src, syms).expect("source parses");
+    rv_lower::lower_with_lines(&module, syms).expect("source lowers")
+}
+
+/// Records every signature looked up, answering from a plain map.
+struct Recording {
+    sigs: HashMap<Sym, Arc<SigTypes>>,
+    asked: RefCell<Vec<Sym>>,
+}
+
+impl CalleeSignatures for Recording {
+    fn signature(&self, func: Sym) -> Option<Arc<SigTypes>> {
+        self.asked.borrow_mut().push(func);
+        self.sigs.signature(func)
+    }
+}
+
+/// Infer `func` of `prog` against `callees`.
+fn infer(
+    prog: &Program<Parsed>,
+    func: &str,
+    callees: &dyn CalleeSignatures,
+    syms: &Symbols,
+    lines: &SourceLines,
+) -> Result<Function<Lowerable>, String> {
+    let types: HashMap<Sym, TypeDef> = prog.types.iter().map(|t| (t.name(), t.clone())).collect();
+    let f = prog.funcs.iter().find(|f| syms.resolve(f.name) == func).expect("function exists");
+    rv_infer::infer_function_types(f, &types, &prog.trait_impls, callees, syms, lines)
+}
+
+#[test]
+fn caller_is_inferred_from_the_callee_signature() {
+    let mut syms = Symbols::new();
+    let src = "fn callee(x: i64) -> u8 { return 7; }
+        fn caller() -> u8 { let v = callee(1); return v; }";
+    let (prog, lines) = lower(src, &mut syms);
+    let sigs = rv_infer::signatures(&prog, &syms, &lines).expect("signatures");
+    let callee = syms.intern("callee");
+    assert_eq!(
+        *sigs[&callee],
+        SigTypes {
+            params: vec![Ty::Int],
+            ret: Ty::IntN(rv_core::IntTy { signed: false, bits: 8 }),
+            generic_bounds: vec![]
+        }
+    );
+
+    let recording = Recording { sigs: sigs.clone(), asked: RefCell::new(Vec::new()) };
+    let caller = infer(&prog, "caller", &recording, &syms, &lines).expect("caller infers");
+    assert_eq!(*recording.asked.borrow(), [callee]);
+    let v = caller.locals.iter().find(|d| d.name.map(|n| syms.resolve(n)) == Some("v"));
+    assert_eq!(v.map(|d| &d.ty), Some(&sigs[&callee].ret));
+
+    // A different signature for the same callee changes the caller's verdict.
+    let mut changed = sigs;
+    let bool_sig = SigTypes { params: vec![Ty::Int], ret: Ty::Bool, generic_bounds: vec![] };
+    changed.insert(callee, Arc::new(bool_sig));
+    let Err(err) = infer(&prog, "caller", &changed, &syms, &lines) else {
+        panic!("a `bool` callee cannot feed a `u8` return");
+    };
+    assert!(err.contains("[E0308]"), "got: {err}");
+}
+
+/// A function with no declared return type gets the signature its body implies.
+#[test]
+fn undeclared_return_is_inferred_once() {
+    let mut syms = Symbols::new();
+    let src = "fn unit() { let x = 1; } fn main() -> i64 { unit(); return 0; }";
+    let (prog, lines) = lower(src, &mut syms);
+    let sigs = rv_infer::signatures(&prog, &syms, &lines).expect("signatures");
+    assert_eq!(sigs[&syms.intern("unit")].ret, Ty::Unit);
+}
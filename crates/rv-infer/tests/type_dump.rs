@@ -0,0 +1,38 @@
+//! Golden tests for the inferred-type dump (`rvc --emit types`): each fixture
+//! `name.rv` is dumped and compared with `name.types` beside it, so a change to
+//! what inference decides shows up as a reviewable diff of the expected file.
+
+/// Dump `src` and compare it with `expected`.
+fn check_dump(src: &str, expected: &str) {
+    let mut syms = rv_core::Symbols::new();
+    let module = rv_syntax::parse(src, &mut syms).expect("fixture parses");
+    let (prog, lines) = rv_lower::lower_with_lines(&module, &mut syms).expect("fixture lowers");
+    let dump = rv_infer::dump_types(&prog, &syms, &lines).expect("signatures infer");
+    assert!(dump == expected, "the type dump changed; new dump:\n{dump}");
+}
+
+/// Generic bodies keep their type parameters; a constructor nothing pins stays
+/// unresolved.
+#[test]
+fn generic_functions() {
+    check_dump(
+        include_str!("fixtures/dump_generic.rv"),
+        include_str!("fixtures/dump_generic.types"),
+    );
+}
+
+/// A function that fails inference shows its error, and the rest still dump.
+#[test]
+fn inference_error() {
+    check_dump(include_str!("fixtures/dump_error.rv"), include_str!("fixtures/dump_error.types"));
+}
+
+/// Two functions' parameters spelled alike stay apart, and a body annotation
+/// names its own function's parameter rather than a same-named struct.
+#[test]
+fn shadowed_type_parameters() {
+    check_dump(
+        include_str!("fixtures/dump_shadowing.rv"),
+        include_str!("fixtures/dump_shadowing.types"),
+    );
+}
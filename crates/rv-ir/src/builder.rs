@@ -0,0 +1,346 @@
+//! A fluent builder for hand-written IR, mostly for tests.
+//!
+//! Writing a [`Function`] as a struct literal means numbering every local and
+//! block by hand, repeating the boilerplate fields (`type_params`, `pre`, `post`,
+//! …), and fixing every fixture whenever a field is added. [`FunctionBuilder`]
+//! numbers locals and blocks itself, interns names through the caller's
+//! [`Symbols`], and checks the result is well formed when it is finished:
+//!
+//! ```
+//! use rv_core::{BinOp, Symbols, Ty};
+//! use rv_ir::builder::{copy, int, FunctionBuilder};
+//! use rv_ir::{Lowerable, RValue};
+//!
+//! let mut syms = Symbols::new();
+//! let mut b = FunctionBuilder::<Lowerable>::new(&mut syms, "inc", Ty::Int);
+//! let x = b.param("x", Ty::Int);
+//! let y = b.local("y", Ty::Int);
+//! b.assign(y, RValue::Bin(BinOp::Add, copy(x), int(1))).ret(copy(y));
+//! let f = b.finish();
+//! assert_eq!(f.params, [x]);
+//! ```
+
+use rv_core::{Prop, Sym, Symbols};
+
+use crate::{
+    Block, BlockId, Const, Function, LocalDecl, LocalId, MatchArm, Operand, Phase, Place,
+    Program, RValue, Stmt, Terminator,
+};
+
+/// Builds one [`Function`], block by block. Statements and terminators go to the
+/// *current* block, which starts as the entry block.
+pub struct FunctionBuilder<'s, P: Phase> {
+    syms: &'s mut Symbols,
+    name: Sym,
+    ret: P::Ty,
+    pre: Prop,
+    post: Prop,
+    params: Vec<LocalId>,
+    locals: Vec<LocalDecl<P>>,
+    blocks: Vec<(Vec<Stmt>, Option<Terminator<P>>)>,
+    current: BlockId,
+}
+
+impl<'s, P: Phase> FunctionBuilder<'s, P> {
+    /// A function `name` returning `ret`, with an empty entry block.
+    pub fn new(syms: &'s mut Symbols, name: &str, ret: P::Ty) -> Self {
+        let name = syms.intern(name);
+        FunctionBuilder {
+            syms,
+            name,
+            ret,
+            pre: Prop::True,
+            post: Prop::True,
+            params: Vec::new(),
+            locals: Vec::new(),
+            blocks: vec![(Vec::new(), None)],
+            current: BlockId(0),
+        }
+    }
+
+    /// Intern `name` in the builder's symbol table (for type and callee names).
+    pub fn sym(&mut self, name: &str) -> Sym {
+        self.syms.intern(name)
+    }
+
+    /// Declare the next parameter.
+    pub fn param(&mut self, name: &str, ty: P::Ty) -> LocalId {
+        let id = self.local(name, ty);
+        self.params.push(id);
+        id
+    }
+
+    /// Declare a named local.
+    pub fn local(&mut self, name: &str, ty: P::Ty) -> LocalId {
+        let name = self.syms.intern(name);
+        self.push_local(Some(name), ty)
+    }
+
+    /// Declare a named local that may be written more than once.
+    pub fn local_mut(&mut self, name: &str, ty: P::Ty) -> LocalId {
+        let id = self.local(name, ty);
+        self.locals[id.0 as usize].mutable = true;
+        id
+    }
+
+    /// Declare an unnamed temporary.
+    pub fn temp(&mut self, ty: P::Ty) -> LocalId {
+        self.push_local(None, ty)
+    }
+
+    fn push_local(&mut self, name: Option<Sym>, ty: P::Ty) -> LocalId {
+        let id = LocalId(self.locals.len() as u32);
+        self.locals.push(LocalDecl { name, mutable: false, ty });
+        id
+    }
+
+    /// Set the precondition.
+    pub fn requires(&mut self, pre: Prop) -> &mut Self {
+        self.pre = pre;
+        self
+    }
+
+    /// Set the postcondition.
+    pub fn ensures(&mut self, post: Prop) -> &mut Self {
+        self.post = post;
+        self
+    }
+
+    /// A new empty block. The current block is unchanged.
+    pub fn block(&mut self) -> BlockId {
+        self.blocks.push((Vec::new(), None));
+        BlockId(self.blocks.len() as u32 - 1)
+    }
+
+    /// Make `block` the current block.
+    pub fn switch_to(&mut self, block: BlockId) -> &mut Self {
+        assert!((block.0 as usize) < self.blocks.len(), "no block bb{}", block.0);
+        self.current = block;
+        self
+    }
+
+    /// Append `stmt` to the current block.
+    pub fn stmt(&mut self, stmt: Stmt) -> &mut Self {
+        let (stmts, term) = &mut self.blocks[self.current.0 as usize];
+        assert!(term.is_none(), "bb{} is already terminated", self.current.0);
+        stmts.push(stmt);
+        self
+    }
+
+    /// Append `dst = rv` to the current block.
+    pub fn assign(&mut self, dst: LocalId, rv: RValue) -> &mut Self {
+        self.stmt(Stmt::Assign(Place::local(dst), rv))
+    }
+
+    /// End the current block with `term`.
+    pub fn terminate(&mut self, term: Terminator<P>) -> &mut Self {
+        let slot = &mut self.blocks[self.current.0 as usize].1;
+        assert!(slot.is_none(), "bb{} is already terminated", self.current.0);
+        *slot = Some(term);
+        self
+    }
+
+    pub fn goto(&mut self, target: BlockId) -> &mut Self {
+        self.terminate(Terminator::Goto(target))
+    }
+
+    pub fn branch(&mut self, cond: Operand, then_blk: BlockId, else_blk: BlockId) -> &mut Self {
+        self.terminate(Terminator::Branch { cond, then_blk, else_blk })
+    }
+
+    /// Switch on `scrutinee`'s discriminant: `(variant, target)` arms, then
+    /// `otherwise`.
+    pub fn match_(
+        &mut self,
+        scrutinee: Operand,
+        arms: impl IntoIterator<Item = (u32, BlockId)>,
+        otherwise: Option<BlockId>,
+    ) -> &mut Self {
+        let arms = arms.into_iter().map(|(variant, target)| MatchArm { variant, target }).collect();
+        self.terminate(Terminator::Match { scrutinee, arms, otherwise })
+    }
+
+    pub fn ret(&mut self, value: Operand) -> &mut Self {
+        self.terminate(Terminator::Return(value))
+    }
+
+    /// The finished function.
+    ///
+    /// # Panics
+    ///
+    /// If a block has no terminator, a terminator jumps to a block that was never
+    /// created, or the parameter list is invalid: a fixture that would otherwise
+    /// fail somewhere far from where it was built.
+    pub fn finish(self) -> Function<P> {
+        let count = self.blocks.len();
+        let mut blocks = Vec::with_capacity(count);
+        for (i, (stmts, term)) in self.blocks.into_iter().enumerate() {
+            let term = term.unwrap_or_else(|| panic!("bb{i} has no terminator"));
+            if let Some(bad) = term.successors().into_iter().find(|s| s.0 as usize >= count) {
+                panic!("bb{i} jumps to bb{}, but only {count} blocks exist", bad.0);
+            }
+            blocks.push(Block { id: BlockId(i as u32), stmts, term });
+        }
+        let func = Function {
+            name: self.name,
+            type_params: Vec::new(),
+            generic_bounds: Vec::new(),
+            params: self.params,
+            ret: self.ret,
+            pre: self.pre,
+            post: self.post,
+            locals: self.locals,
+            blocks,
+            entry: BlockId(0),
+        };
+        if let Err(e) = func.validate_params() {
+            panic!("{e}");
+        }
+        func
+    }
+
+    /// [`finish`](Self::finish), as the only function of a program with no types.
+    pub fn program(self) -> Program<P> {
+        Program { types: Vec::new(), trait_impls: Vec::new(), funcs: vec![self.finish()] }
+    }
+}
+
+/// Read `local`.
+pub fn copy(local: LocalId) -> Operand {
+    Operand::Copy(Place::local(local))
+}
+
+/// The integer literal `n`.
+pub fn int(n: i128) -> Operand {
+    Operand::Const(Const::Int(n))
+}
+
+/// The unit value.
+pub fn unit() -> Operand {
+    Operand::Const(Const::Unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Lowerable;
+    use rv_core::Ty;
+
+    #[test]
+    fn blocks_and_locals_are_numbered_in_order() {
+        let mut syms = Symbols::new();
+        let mut b = FunctionBuilder::<Lowerable>::new(&mut syms, "f", Ty::Int);
+        let flag = b.param("flag", Ty::Bool);
+        let t = b.temp(Ty::Int);
+        let (yes, no) = (b.block(), b.block());
+        b.branch(copy(flag), yes, no);
+        b.switch_to(yes).assign(t, RValue::Use(int(1))).ret(copy(t));
+        b.switch_to(no).ret(int(0));
+        let f = b.finish();
+
+        assert_eq!(syms.resolve(f.name), "f");
+        assert_eq!((f.params.as_slice(), f.entry), ([flag].as_slice(), BlockId(0)));
+        assert_eq!(f.locals[0].name.map(|n| syms.resolve(n)), Some("flag"));
+        assert_eq!(f.locals[1].name, None);
+        let ids: Vec<BlockId> = f.blocks.iter().map(|b| b.id).collect();
+        assert_eq!(ids, [BlockId(0), yes, no]);
+        assert_eq!(f.blocks[0].term.successors(), [yes, no]);
+        assert_eq!(f.blocks[yes.0 as usize].stmts.len(), 1);
+    }
+
+    #[test]
+    fn match_arms_keep_their_order() {
+        let mut syms = Symbols::new();
+        let mut b = FunctionBuilder::<Lowerable>::new(&mut syms, "f", Ty::Int);
+        let e = b.sym("E");
+        let s = b.param("s", Ty::Adt(e, vec![]));
+        let arms: Vec<BlockId> = (0..3).map(|_| b.block()).collect();
+        b.match_(copy(s), [(0, arms[0]), (2, arms[1])], Some(arms[2]));
+        for (n, &arm) in arms.iter().enumerate() {
+            b.switch_to(arm).ret(int(n as i128));
+        }
+        let prog = b.program();
+        let Terminator::Match { arms: got, otherwise, .. } = &prog.funcs[0].blocks[0].term else {
+            panic!("the entry block ends in a match");
+        };
+        let got: Vec<(u32, BlockId)> = got.iter().map(|a| (a.variant, a.target)).collect();
+        assert_eq!(got, [(0, arms[0]), (2, arms[1])]);
+        assert_eq!(*otherwise, Some(arms[2]));
+    }
+
+    #[test]
+    fn a_second_write_needs_a_mutable_local() {
+        let mut syms = Symbols::new();
+        // Written twice in a row: only a mutable local may be.
+        let twice = |syms: &mut Symbols, mutable: bool| {
+            let mut b = FunctionBuilder::<Lowerable>::new(syms, "f", Ty::Int);
+            let x = if mutable { b.local_mut("x", Ty::Int) } else { b.local("x", Ty::Int) };
+            b.assign(x, RValue::Use(int(1))).assign(x, RValue::Use(int(2))).ret(copy(x));
+            b.finish().validate_mutability()
+        };
+        let err = twice(&mut syms, false).unwrap_err();
+        assert_eq!(err, "local _0 is written at bb0[0] and again at bb0[1] but is not mutable");
+        assert_eq!(twice(&mut syms, true), Ok(()));
+
+        // One write per branch of an `if` is a single assignment on every path.
+        let mut b = FunctionBuilder::<Lowerable>::new(&mut syms, "g", Ty::Int);
+        let flag = b.param("flag", Ty::Bool);
+        let t = b.temp(Ty::Int);
+        let (yes, no, join) = (b.block(), b.block(), b.block());
+        b.branch(copy(flag), yes, no);
+        b.switch_to(yes).assign(t, RValue::Use(int(1))).goto(join);
+        b.switch_to(no).assign(t, RValue::Use(int(2))).goto(join);
+        b.switch_to(join).ret(copy(t));
+        assert_eq!(b.finish().validate_mutability(), Ok(()));
+
+        // A write inside a loop re-binds on each iteration, unless the local was
+        // already written before the loop.
+        let looped = |syms: &mut Symbols, before: bool| {
+            let mut b = FunctionBuilder::<Lowerable>::new(syms, "h", Ty::Unit);
+            let x = b.local("x", Ty::Int);
+            let body = b.block();
+            if before {
+                b.assign(x, RValue::Use(int(0)));
+            }
+            b.goto(body);
+            b.switch_to(body).assign(x, RValue::Use(int(1))).goto(body);
+            b.finish().validate_mutability()
+        };
+        assert_eq!(looped(&mut syms, false), Ok(()));
+        assert!(looped(&mut syms, true).unwrap_err().contains("again at bb1[0]"));
+
+        // A parameter already holds its argument, so any write is a second one.
+        let mut b = FunctionBuilder::<Lowerable>::new(&mut syms, "k", Ty::Int);
+        let p = b.param("p", Ty::Int);
+        b.assign(p, RValue::Use(int(1))).ret(copy(p));
+        let err = b.finish().validate_mutability().unwrap_err();
+        assert_eq!(err, "parameter _0 is written at bb0[0] but is not mutable");
+    }
+
+    #[test]
+    #[should_panic(expected = "bb1 has no terminator")]
+    fn an_unterminated_block_is_rejected() {
+        let mut syms = Symbols::new();
+        let mut b = FunctionBuilder::<Lowerable>::new(&mut syms, "f", Ty::Unit);
+        let next = b.block();
+        b.goto(next);
+        b.finish();
+    }
+
+    #[test]
+    #[should_panic(expected = "jumps to bb7")]
+    fn a_jump_to_a_missing_block_is_rejected() {
+        let mut syms = Symbols::new();
+        let mut b = FunctionBuilder::<Lowerable>::new(&mut syms, "f", Ty::Unit);
+        b.goto(BlockId(7));
+        b.finish();
+    }
+
+    #[test]
+    #[should_panic(expected = "already terminated")]
+    fn a_statement_after_the_terminator_is_rejected() {
+        let mut syms = Symbols::new();
+        let mut b = FunctionBuilder::<Lowerable>::new(&mut syms, "f", Ty::Unit);
+        b.ret(unit()).stmt(Stmt::Assert(Prop::True));
+    }
+}
@@ -0,0 +1,251 @@
+//! The call graph: which functions each function calls.
+//!
+//! An edge `f -> g` means `f`'s body calls `g` directly or takes it as a value
+//! (a closure or function pointer, which it may call later). A callee the
+//! program does not define, such as a builtin, is an opaque leaf: a node with
+//! no successors. Recursion is read off the strongly connected components,
+//! which [`CallGraph::build`] finds once, callees first.
+
+use std::collections::{HashMap, HashSet};
+
+use rv_core::Sym;
+
+use crate::{Function, Phase, RValue, Stmt};
+
+/// The call graph of a set of functions, with its strongly connected components.
+#[derive(Clone, Debug)]
+pub struct CallGraph {
+    /// Every node: the defined functions in order, then the opaque callees.
+    nodes: Vec<Sym>,
+    index: HashMap<Sym, usize>,
+    /// How many of `nodes` are defined functions.
+    defined: usize,
+    succ: Vec<Vec<usize>>,
+    pred: Vec<Vec<usize>>,
+    /// The components, each callee's before its callers'.
+    sccs: Vec<Vec<Sym>>,
+    /// Each node's index into `sccs`.
+    scc_of: Vec<usize>,
+}
+
+impl CallGraph {
+    pub fn build<P: Phase>(funcs: &[Function<P>]) -> CallGraph {
+        let mut nodes: Vec<Sym> = funcs.iter().map(|f| f.name).collect();
+        let mut index: HashMap<Sym, usize> =
+            nodes.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+        let mut succ: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+        for (i, f) in funcs.iter().enumerate() {
+            for callee in callees(f) {
+                let j = *index.entry(callee).or_insert_with(|| {
+                    nodes.push(callee);
+                    succ.push(Vec::new());
+                    nodes.len() - 1
+                });
+                if !succ[i].contains(&j) {
+                    succ[i].push(j);
+                }
+            }
+        }
+        let mut pred = vec![Vec::new(); nodes.len()];
+        for (i, out) in succ.iter().enumerate() {
+            out.iter().for_each(|&j| pred[j].push(i));
+        }
+        let scc_of = tarjan(&succ);
+        let mut sccs = vec![Vec::new(); scc_of.iter().max().map_or(0, |&m| m + 1)];
+        for (i, &c) in scc_of.iter().enumerate() {
+            sccs[c].push(nodes[i]);
+        }
+        CallGraph { nodes, index, defined: funcs.len(), succ, pred, sccs, scc_of }
+    }
+
+    /// Whether `f` is one of the functions the graph was built from, rather
+    /// than an opaque callee.
+    pub fn is_defined(&self, f: Sym) -> bool {
+        self.index.get(&f).is_some_and(|&i| i < self.defined)
+    }
+
+    /// The functions `f` calls or takes as a value, each once.
+    pub fn successors(&self, f: Sym) -> impl Iterator<Item = Sym> + '_ {
+        self.edges(&self.succ, f)
+    }
+
+    /// The functions that call `f` or take it as a value, each once.
+    pub fn predecessors(&self, f: Sym) -> impl Iterator<Item = Sym> + '_ {
+        self.edges(&self.pred, f)
+    }
+
+    fn edges<'a>(&'a self, adj: &'a [Vec<usize>], f: Sym) -> impl Iterator<Item = Sym> + 'a {
+        let out = self.index.get(&f).map_or(&[][..], |&i| &adj[i]);
+        out.iter().map(|&j| self.nodes[j])
+    }
+
+    /// Whether `f` can reach itself: it calls itself, or shares a component
+    /// with another function.
+    pub fn is_recursive(&self, f: Sym) -> bool {
+        let Some(&i) = self.index.get(&f) else { return false };
+        self.sccs[self.scc_of[i]].len() > 1 || self.succ[i].contains(&i)
+    }
+
+    /// The strongly connected components, in reverse topological order: every
+    /// component comes after each component it calls into.
+    pub fn sccs(&self) -> &[Vec<Sym>] {
+        &self.sccs
+    }
+
+    /// The defined functions, callees before callers (in any order within a
+    /// cycle), for passes that want every callee done first.
+    pub fn bottom_up(&self) -> impl Iterator<Item = Sym> + '_ {
+        self.sccs.iter().flatten().copied().filter(|&f| self.is_defined(f))
+    }
+
+    /// Every node reachable from `roots`, the roots included.
+    pub fn reachable(&self, roots: impl IntoIterator<Item = Sym>) -> HashSet<Sym> {
+        let mut seen: HashSet<Sym> = roots.into_iter().collect();
+        let mut work: Vec<Sym> = seen.iter().copied().collect();
+        while let Some(f) = work.pop() {
+            for g in self.successors(f) {
+                if seen.insert(g) {
+                    work.push(g);
+                }
+            }
+        }
+        seen
+    }
+}
+
+/// The functions `f` names as a call target or a closure's code.
+fn callees<P: Phase>(f: &Function<P>) -> impl Iterator<Item = Sym> + '_ {
+    f.blocks.iter().flat_map(|b| &b.stmts).filter_map(|stmt| match stmt {
+        Stmt::Assign(_, RValue::Call(callee, _) | RValue::Closure(callee, _))
+        | Stmt::Eval(RValue::Call(callee, _) | RValue::Closure(callee, _)) => Some(*callee),
+        _ => None,
+    })
+}
+
+/// Tarjan's algorithm, iteratively so a long call chain cannot overflow the
+/// stack: each node's component, numbered in the order the components close,
+/// which puts every callee's component first.
+fn tarjan(succ: &[Vec<usize>]) -> Vec<usize> {
+    const UNSEEN: usize = usize::MAX;
+    let n = succ.len();
+    let (mut order, mut low) = (vec![UNSEEN; n], vec![0; n]);
+    let mut comp = vec![UNSEEN; n];
+    let (mut next, mut comps) = (0, 0);
+    let mut stack = Vec::new();
+    for root in 0..n {
+        if order[root] != UNSEEN {
+            continue;
+        }
+        // (node, how many of its successors have been visited)
+        let mut frames = vec![(root, 0)];
+        order[root] = next;
+        low[root] = next;
+        next += 1;
+        stack.push(root);
+        while let Some(&mut (v, ref mut edge)) = frames.last_mut() {
+            if let Some(&w) = succ[v].get(*edge) {
+                *edge += 1;
+                if order[w] == UNSEEN {
+                    order[w] = next;
+                    low[w] = next;
+                    next += 1;
+                    stack.push(w);
+                    frames.push((w, 0));
+                } else if comp[w] == UNSEEN {
+                    low[v] = low[v].min(order[w]);
+                }
+                continue;
+            }
+            frames.pop();
+            if let Some(&(parent, _)) = frames.last() {
+                low[parent] = low[parent].min(low[v]);
+            }
+            if low[v] == order[v] {
+                while let Some(w) = stack.pop() {
+                    comp[w] = comps;
+                    if w == v {
+                        break;
+                    }
+                }
+                comps += 1;
+            }
+        }
+    }
+    comp
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{int, FunctionBuilder};
+    use crate::{Lowerable, Operand};
+    use rv_core::{Symbols, Ty};
+
+    /// A function `name` that calls each of `calls` and returns 0.
+    fn caller(syms: &mut Symbols, name: &str, calls: &[&str]) -> Function<Lowerable> {
+        let mut b = FunctionBuilder::<Lowerable>::new(syms, name, Ty::Int);
+        for callee in calls {
+            let callee = b.sym(callee);
+            b.stmt(Stmt::Eval(RValue::Call(callee, Vec::<Operand>::new())));
+        }
+        b.ret(int(0));
+        b.finish()
+    }
+
+    fn graph(syms: &mut Symbols, calls: &[(&str, &[&str])]) -> CallGraph {
+        let funcs: Vec<_> = calls.iter().map(|(f, cs)| caller(syms, f, cs)).collect();
+        CallGraph::build(&funcs)
+    }
+
+    fn names(syms: &Symbols, fs: impl IntoIterator<Item = Sym>) -> Vec<&str> {
+        let mut out: Vec<&str> = fs.into_iter().map(|f| syms.resolve(f)).collect();
+        out.sort_unstable();
+        out
+    }
+
+    #[test]
+    fn recursion_is_a_self_edge_or_a_shared_component() {
+        let mut syms = Symbols::new();
+        let calls: &[(&str, &[&str])] = &[
+            ("fact", &["fact"]),
+            ("a", &["b"]),
+            ("b", &["c"]),
+            ("c", &["a", "leaf"]),
+            ("leaf", &[]),
+        ];
+        let g = graph(&mut syms, calls);
+        for (f, _) in calls {
+            assert_eq!(g.is_recursive(syms.intern(f)), *f != "leaf", "{f}");
+        }
+        let mut sccs: Vec<Vec<&str>> = g.sccs().iter().map(|c| names(&syms, c.clone())).collect();
+        sccs.sort();
+        assert_eq!(sccs, [vec!["a", "b", "c"], vec!["fact"], vec!["leaf"]]);
+        let c = syms.intern("c");
+        assert_eq!(names(&syms, g.successors(c)), ["a", "leaf"]);
+        assert_eq!(names(&syms, g.predecessors(c)), ["b"]);
+    }
+
+    #[test]
+    fn bottom_up_puts_callees_first_and_skips_opaque_callees() {
+        let mut syms = Symbols::new();
+        let calls: &[(&str, &[&str])] = &[
+            ("top", &["left", "right"]),
+            ("left", &["base"]),
+            ("right", &["base", "print"]),
+            ("base", &[]),
+        ];
+        let g = graph(&mut syms, calls);
+        let order: Vec<&str> = g.bottom_up().map(|f| syms.resolve(f)).collect();
+        let at = |n: &str| order.iter().position(|&o| o == n).unwrap();
+        assert_eq!(order.len(), 4, "{order:?}");
+        assert!(at("base") < at("left") && at("base") < at("right"));
+        assert!(at("left") < at("top") && at("right") < at("top"));
+
+        let print = syms.intern("print");
+        assert!(!g.is_defined(print) && !g.is_recursive(print));
+        assert_eq!(g.successors(print).count(), 0);
+        assert_eq!(names(&syms, g.predecessors(print)), ["right"]);
+        let right = syms.intern("right");
+        assert_eq!(names(&syms, g.reachable([right])), ["base", "print", "right"]);
+    }
+}
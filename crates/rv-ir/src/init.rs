@@ -0,0 +1,237 @@
+//! Definite initialization: every read of a local must be preceded, on every path
+//! from the entry, by an assignment to the whole local.
+//!
+//! The backends give an unassigned local whatever value its slot happens to hold,
+//! so a read that some path reaches first would make the program's behavior
+//! depend on the backend. Parameters are initialized at entry. A read is any
+//! operand that copies the local, the base of a projected place (a field write
+//! `s.f = ..` reads `s`), an index operand, or a borrow `&x`. Ghost statements
+//! (`assert`, `assume`, invariants) are erased before codegen and are not reads.
+
+use crate::{
+    BlockId, Function, LocalId, Operand, Phase, Place, Proj, RValue, Stmt, Terminator,
+};
+
+/// A read of `local` that some path from the entry reaches before any assignment
+/// to it. `stmt` is the statement's index in `block`, or `None` for the block's
+/// terminator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UninitRead {
+    pub local: LocalId,
+    pub block: BlockId,
+    pub stmt: Option<u32>,
+}
+
+impl<P: Phase> Function<P> {
+    /// The first read (in block order) of a possibly-uninitialized local, if any.
+    ///
+    /// A forward must-analysis: a local is initialized on entry to a block when it
+    /// is initialized at the end of every predecessor. Blocks unreachable from the
+    /// entry are never executed and are not checked.
+    pub fn check_initialized(&self) -> Result<(), UninitRead> {
+        let n = self.locals.len();
+        let index: std::collections::HashMap<BlockId, usize> =
+            self.blocks.iter().enumerate().map(|(i, b)| (b.id, i)).collect();
+        // `None` = not reached yet (the top of the lattice: everything initialized).
+        let mut entry_sets: Vec<Option<Vec<bool>>> = vec![None; self.blocks.len()];
+        let Some(&entry) = index.get(&self.entry) else {
+            return Ok(());
+        };
+        let mut at_entry = vec![false; n];
+        for p in &self.params {
+            at_entry[p.0 as usize] = true;
+        }
+        entry_sets[entry] = Some(at_entry);
+
+        let mut work = vec![entry];
+        while let Some(i) = work.pop() {
+            let mut init = entry_sets[i].clone().expect("queued blocks are reached");
+            for stmt in &self.blocks[i].stmts {
+                if let Some(local) = stmt_def(stmt) {
+                    init[local.0 as usize] = true;
+                }
+            }
+            for succ in self.blocks[i].term.successors() {
+                let Some(&s) = index.get(&succ) else { continue };
+                let changed = match &mut entry_sets[s] {
+                    Some(set) => {
+                        let mut changed = false;
+                        for (have, out) in set.iter_mut().zip(&init) {
+                            if *have && !out {
+                                *have = false;
+                                changed = true;
+                            }
+                        }
+                        changed
+                    }
+                    slot @ None => {
+                        *slot = Some(init.clone());
+                        true
+                    }
+                };
+                if changed && !work.contains(&s) {
+                    work.push(s);
+                }
+            }
+        }
+
+        for (block, set) in self.blocks.iter().zip(entry_sets) {
+            let Some(mut init) = set else { continue };
+            let mut reads = Vec::new();
+            for (i, stmt) in block.stmts.iter().enumerate() {
+                stmt_reads(stmt, &mut reads);
+                let site = Some(i as u32);
+                if let Some(&local) = reads.iter().find(|l| !init[l.0 as usize]) {
+                    return Err(UninitRead { local, block: block.id, stmt: site });
+                }
+                reads.clear();
+                if let Some(local) = stmt_def(stmt) {
+                    init[local.0 as usize] = true;
+                }
+            }
+            term_reads(&block.term, &mut reads);
+            if let Some(&local) = reads.iter().find(|l| !init[l.0 as usize]) {
+                return Err(UninitRead { local, block: block.id, stmt: None });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The local a statement initializes: the target of an unprojected assignment.
+pub(crate) fn stmt_def(stmt: &Stmt) -> Option<LocalId> {
+    match stmt {
+        Stmt::Assign(place, _) if place.proj.is_empty() => Some(place.local),
+        _ => None,
+    }
+}
+
+pub(crate) fn stmt_reads(stmt: &Stmt, out: &mut Vec<LocalId>) {
+    match stmt {
+        Stmt::Assign(place, rv) => {
+            rvalue_reads(rv, out);
+            if place.proj.is_empty() {
+                return;
+            }
+            // A write into a component reads the rest of the local.
+            place_reads(place, out);
+        }
+        Stmt::Eval(rv) => rvalue_reads(rv, out),
+        Stmt::Assert(_) | Stmt::Assume(_) | Stmt::Invariant(_) => {}
+    }
+}
+
+pub(crate) fn term_reads<P: Phase>(term: &Terminator<P>, out: &mut Vec<LocalId>) {
+    match term {
+        Terminator::Branch { cond: op, .. }
+        | Terminator::Match { scrutinee: op, .. }
+        | Terminator::Return(op) => operand_reads(op, out),
+        Terminator::Drop { place, .. } => place_reads(place, out),
+        Terminator::Goto(_) | Terminator::Panic => {}
+    }
+}
+
+fn rvalue_reads(rv: &RValue, out: &mut Vec<LocalId>) {
+    match rv {
+        RValue::Use(a) | RValue::Un(_, a) | RValue::VecLen(a) | RValue::Cast(a, _) => {
+            operand_reads(a, out)
+        }
+        RValue::Bin(_, a, b) | RValue::WrappingBin(_, a, b) | RValue::VecPush(a, b) => {
+            operand_reads(a, out);
+            operand_reads(b, out);
+        }
+        RValue::Call(_, args) | RValue::Closure(_, args) | RValue::Aggregate(_, args) => {
+            args.iter().for_each(|a| operand_reads(a, out))
+        }
+        RValue::CallClosure(callee, args) => {
+            operand_reads(callee, out);
+            args.iter().for_each(|a| operand_reads(a, out));
+        }
+        RValue::Ref(_, place) => place_reads(place, out),
+    }
+}
+
+fn operand_reads(op: &Operand, out: &mut Vec<LocalId>) {
+    if let Operand::Copy(place) = op {
+        place_reads(place, out);
+    }
+}
+
+fn place_reads(place: &Place, out: &mut Vec<LocalId>) {
+    out.push(place.local);
+    for p in &place.proj {
+        if let Proj::Index(i) = p {
+            operand_reads(i, out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{copy, int, FunctionBuilder};
+    use crate::Lowerable;
+    use rv_core::{BinOp, Symbols, Ty};
+
+    #[test]
+    fn a_read_before_any_assignment_is_rejected() {
+        let mut syms = Symbols::new();
+        let mut b = FunctionBuilder::<Lowerable>::new(&mut syms, "f", Ty::Int);
+        let x = b.param("x", Ty::Int);
+        let y = b.local("y", Ty::Int);
+        let z = b.local("z", Ty::Int);
+        b.assign(z, RValue::Bin(BinOp::Add, copy(x), copy(y))).ret(copy(z));
+        let err = b.finish().check_initialized().unwrap_err();
+        assert_eq!(err, UninitRead { local: y, block: BlockId(0), stmt: Some(0) });
+    }
+
+    #[test]
+    fn an_assignment_on_only_one_path_is_not_enough() {
+        let mut syms = Symbols::new();
+        let mut b = FunctionBuilder::<Lowerable>::new(&mut syms, "f", Ty::Int);
+        let flag = b.param("flag", Ty::Bool);
+        let y = b.local("y", Ty::Int);
+        let (yes, no, join) = (b.block(), b.block(), b.block());
+        b.branch(copy(flag), yes, no);
+        b.switch_to(yes).assign(y, RValue::Use(int(1))).goto(join);
+        b.switch_to(no).goto(join);
+        b.switch_to(join).ret(copy(y));
+        let err = b.finish().check_initialized().unwrap_err();
+        assert_eq!(err, UninitRead { local: y, block: join, stmt: None });
+    }
+
+    #[test]
+    fn assignments_on_every_path_and_loops_are_accepted() {
+        let mut syms = Symbols::new();
+        let mut b = FunctionBuilder::<Lowerable>::new(&mut syms, "f", Ty::Int);
+        let flag = b.param("flag", Ty::Bool);
+        let y = b.local("y", Ty::Int);
+        let (head, body, exit, dead) = (b.block(), b.block(), b.block(), b.block());
+        b.assign(y, RValue::Use(int(0))).goto(head);
+        b.switch_to(head).branch(copy(flag), body, exit);
+        b.switch_to(body).assign(y, RValue::Bin(BinOp::Add, copy(y), int(1))).goto(head);
+        b.switch_to(exit).ret(copy(y));
+        // Never reached, so never run: not checked.
+        let z = b.local("z", Ty::Int);
+        b.switch_to(dead).ret(copy(z));
+        assert_eq!(b.finish().check_initialized(), Ok(()));
+    }
+
+    #[test]
+    fn borrowing_and_field_writes_read_the_local() {
+        let mut syms = Symbols::new();
+        let mut b = FunctionBuilder::<Lowerable>::new(&mut syms, "f", Ty::Unit);
+        let s = b.local("s", Ty::Int);
+        let r = b.local("r", Ty::Int);
+        b.assign(r, RValue::Ref(crate::BorrowKind::Shared, Place::local(s)));
+        b.ret(crate::builder::unit());
+        let err = b.finish().check_initialized().unwrap_err();
+        assert_eq!(err.local, s);
+
+        let mut b = FunctionBuilder::<Lowerable>::new(&mut syms, "g", Ty::Unit);
+        let s = b.local("s", Ty::Int);
+        let field = Place { local: s, proj: vec![Proj::Field(0)] };
+        b.stmt(Stmt::Assign(field, RValue::Use(int(1)))).ret(crate::builder::unit());
+        assert_eq!(b.finish().check_initialized().unwrap_err().local, s);
+    }
+}
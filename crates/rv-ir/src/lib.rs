@@ -4,10 +4,19 @@
 //! The IR core is *behavior-only*: no `mut`, no lifetimes, no memory strategy as a
 //! field. Those are inferred facts living in side-tables, or filled in by phase.
 use rv_core::Ty as CoreTy;
-use rv_core::{BinOp, Prop, Sym, UnOp};
+use rv_core::{BinOp, IntTy, Prop, Sym, UnOp};
 
 pub use rv_arena::NodeId;
 pub use rv_core::{BinOp as IrBinOp, UnOp as IrUnOp};
+pub use rv_diagnostics::LineSpan;
+
+pub mod builder;
+pub mod callgraph;
+pub mod lint;
+pub mod text;
+mod init;
+
+pub use init::UninitRead;
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub struct LocalId(pub u32);
@@ -21,6 +30,9 @@ pub struct DisciplineId(pub u32);
 pub trait Phase {
     type Ty: Clone + std::fmt::Debug;
     type Strategy: Clone + std::fmt::Debug;
+
+    /// The concrete type `ty` records, if this phase knows it.
+    fn known(ty: &Self::Ty) -> Option<&CoreTy>;
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -36,14 +48,26 @@ impl Phase for Parsed {
     /// from use (most importantly fixed-width integers); `None` = unannotated.
     type Ty = Option<CoreTy>;
     type Strategy = ();
+
+    fn known(ty: &Self::Ty) -> Option<&CoreTy> {
+        ty.as_ref()
+    }
 }
 impl Phase for Typed {
     type Ty = CoreTy;
     type Strategy = ();
+
+    fn known(ty: &Self::Ty) -> Option<&CoreTy> {
+        Some(ty)
+    }
 }
 impl Phase for Lowerable {
     type Ty = CoreTy;
     type Strategy = DisciplineId;
+
+    fn known(ty: &Self::Ty) -> Option<&CoreTy> {
+        Some(ty)
+    }
 }
 
 /// A whole program: user-defined types plus functions in some phase `P`.
@@ -76,6 +100,12 @@ impl TypeDef {
             TypeDef::Struct { name, .. } | TypeDef::Enum { name, .. } => *name,
         }
     }
+
+    pub fn type_params(&self) -> &[Sym] {
+        match self {
+            TypeDef::Struct { type_params, .. } | TypeDef::Enum { type_params, .. } => type_params,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -115,6 +145,23 @@ pub enum AggKind {
 /// The reserved name `result`, bound in a function's postcondition.
 pub const RESULT_NAME: &str = "result";
 
+/// The builtin `format` expands into calls to these two. `TO_STRING` renders an
+/// integer, float, bool or string; `STR_CONCAT` joins two strings. The leading
+/// `__` keeps them out of reach of source programs.
+pub const TO_STRING: &str = "__to_string";
+pub const STR_CONCAT: &str = "__str_concat";
+
+/// The bit pattern of an `f64`, as an `i64`. A match on float literals compares
+/// these, so `-0.0` and `0.0` are different patterns and a NaN pattern matches.
+pub const FLOAT_BITS: &str = "__float_bits";
+
+/// The builtins `assert_eq(a, b)` and `assert_ne(a, b)` lower to calls to these.
+/// Unlike `assert`, which is proved statically, they compare at runtime and fail
+/// the run with both values shown.
+pub const ASSERT_EQ: &str = "__assert_eq";
+pub const ASSERT_NE: &str = "__assert_ne";
+
+#[derive(Clone)]
 pub struct Function<P: Phase> {
     pub name: Sym,
     /// Generic type parameters (`fn f<T, U>(..)`). Erased at runtime; opaque to checking.
@@ -122,6 +169,9 @@ pub struct Function<P: Phase> {
     /// Bounds on each declared generic parameter. These are retained through
     /// lowering so call-site elaboration can validate inferred substitutions.
     pub generic_bounds: Vec<(Sym, Vec<Sym>)>,
+    /// The parameter locals, in signature order. They need not be the first
+    /// locals, so backends bind arguments through [`Function::param_locals`]
+    /// rather than by position.
     pub params: Vec<LocalId>,
     /// Return type. Grows `()` -> `Ty`.
     pub ret: P::Ty,
@@ -134,18 +184,188 @@ pub struct Function<P: Phase> {
     pub entry: BlockId,
 }
 
+impl<P: Phase> Function<P> {
+    /// The locals the arguments bind to, in signature order.
+    pub fn param_locals(&self) -> &[LocalId] {
+        &self.params
+    }
+
+    /// Check the parameter list: every parameter names a declared local, and no
+    /// local is a parameter twice.
+    pub fn validate_params(&self) -> Result<(), String> {
+        for (i, p) in self.params.iter().enumerate() {
+            if p.0 as usize >= self.locals.len() {
+                return Err(format!(
+                    "parameter {i} is local {}, but only {} locals are declared",
+                    p.0,
+                    self.locals.len()
+                ));
+            }
+            if self.params[..i].contains(p) {
+                return Err(format!("parameter {i} repeats local {}", p.0));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that a pass which rewrote `before` into `self` kept every parameter:
+    /// the list is still valid, no parameter was dropped (even an unused one, which
+    /// would change the calling convention), and each still names the local it
+    /// named before, wherever renumbering moved it.
+    pub fn validate_params_kept<Q: Phase>(&self, before: &Function<Q>) -> Result<(), String> {
+        self.validate_params()?;
+        if self.params.len() != before.params.len() {
+            return Err(format!(
+                "the function had {} parameters but now has {}",
+                before.params.len(),
+                self.params.len()
+            ));
+        }
+        for (i, (now, was)) in self.params.iter().zip(&before.params).enumerate() {
+            let name_now = self.locals[now.0 as usize].name;
+            let name_was = before.locals.get(was.0 as usize).and_then(|d| d.name);
+            if name_now != name_was {
+                return Err(format!("parameter {i} no longer names its original local"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that every local written after its first assignment is declared
+    /// mutable: a parameter written at all, or any other local with two write
+    /// sites where one can run after the other. A write is an assignment to the
+    /// local or into its fields (not through a reference it holds), or a `&mut`
+    /// borrow of it. A single site that runs again on the next loop iteration
+    /// re-binds the local rather than overwriting it, so it does not count.
+    pub fn validate_mutability(&self) -> Result<(), String> {
+        let mut writes: Vec<Vec<(usize, usize)>> = vec![Vec::new(); self.locals.len()];
+        for (b, block) in self.blocks.iter().enumerate() {
+            for (s, stmt) in block.stmts.iter().enumerate() {
+                let mut note = |place: &Place| {
+                    if !place.proj.iter().any(|p| matches!(p, Proj::Deref)) {
+                        writes[place.local.0 as usize].push((b, s));
+                    }
+                };
+                match stmt {
+                    Stmt::Assign(place, rvalue) => {
+                        note(place);
+                        if let RValue::Ref(BorrowKind::Mut, borrowed) = rvalue {
+                            note(borrowed);
+                        }
+                    }
+                    Stmt::Eval(RValue::Ref(BorrowKind::Mut, borrowed)) => note(borrowed),
+                    _ => {}
+                }
+            }
+        }
+        let index: std::collections::HashMap<BlockId, usize> =
+            self.blocks.iter().enumerate().map(|(i, b)| (b.id, i)).collect();
+        // The blocks reachable from block `from` along at least one edge.
+        let reach = |from: usize| {
+            let mut seen = vec![false; self.blocks.len()];
+            let mut work = vec![from];
+            while let Some(b) = work.pop() {
+                for succ in self.blocks[b].term.successors() {
+                    if let Some(&i) = index.get(&succ) {
+                        if !seen[i] {
+                            seen[i] = true;
+                            work.push(i);
+                        }
+                    }
+                }
+            }
+            seen
+        };
+        let site = |(b, s): (usize, usize)| format!("bb{}[{s}]", self.blocks[b].id.0);
+        for (l, sites) in writes.iter().enumerate() {
+            if self.locals[l].mutable || sites.is_empty() {
+                continue;
+            }
+            if self.params.contains(&LocalId(l as u32)) {
+                return Err(format!(
+                    "parameter _{l} is written at {} but is not mutable",
+                    site(sites[0])
+                ));
+            }
+            // A lone write site has no other write to conflict with.
+            if sites.len() == 1 {
+                continue;
+            }
+            for &(b1, s1) in sites {
+                let later = reach(b1);
+                let runs_after = |&&(b2, s2): &&(usize, usize)| {
+                    (b2, s2) != (b1, s1) && ((b2 == b1 && s2 > s1) || later[b2])
+                };
+                if let Some(&second) = sites.iter().find(runs_after) {
+                    return Err(format!(
+                        "local _{l} is written at {} and again at {} but is not mutable",
+                        site((b1, s1)),
+                        site(second)
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
 pub struct LocalDecl<P: Phase> {
     pub name: Option<Sym>,
+    /// Whether the local may be written after its first assignment (see
+    /// [`Function::validate_mutability`]). An immutable local holds one value for
+    /// its whole life; temporaries are always immutable.
+    pub mutable: bool,
     /// Local's type. Grows `()` -> `Ty`.
     pub ty: P::Ty,
 }
 
+#[derive(Clone)]
 pub struct Block<P: Phase> {
     pub id: BlockId,
     pub stmts: Vec<Stmt>,
     pub term: Terminator<P>,
 }
 
+/// A position in a function's CFG: statement `stmt` of block `block`, or the
+/// block's terminator when `stmt` is `None`. The key of [`SourceLines`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Site {
+    pub func: Sym,
+    pub block: BlockId,
+    pub stmt: Option<u32>,
+}
+
+/// The source lines each lowered statement/terminator came from: the span of the
+/// surface statement that produced it. A side-table rather than an IR field: only
+/// diagnostics read it, and hand-built IR (tests, lifted helpers) simply has no
+/// entry.
+pub type SourceLines = rv_arena::SideTable<Site, LineSpan>;
+
+/// Check that every entry of `lines` names a real statement or terminator of
+/// `prog` and carries a well-formed span.
+pub fn check_source_lines<P: Phase>(prog: &Program<P>, lines: &SourceLines) -> Result<(), String> {
+    for (site, span) in lines.iter() {
+        let block = prog
+            .funcs
+            .iter()
+            .find(|f| f.name == site.func)
+            .and_then(|f| f.blocks.iter().find(|b| b.id == site.block));
+        let exists = match (block, site.stmt) {
+            (Some(b), Some(i)) => (i as usize) < b.stmts.len(),
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        if !exists {
+            return Err(format!("{span} is recorded for a site that does not exist: {site:?}"));
+        }
+        if span.start == 0 || span.start > span.end {
+            return Err(format!("malformed span {span:?} at {site:?}"));
+        }
+    }
+    Ok(())
+}
+
 #[derive(Clone, Debug)]
 pub struct Place {
     pub local: LocalId,
@@ -201,6 +421,9 @@ pub enum Const {
 #[derive(Clone, Debug)]
 pub enum Stmt {
     Assign(Place, RValue),
+    /// Evaluate a call for its effects and discard the result (`f(x);` in statement
+    /// position). Unlike an `Assign`, it has no destination local.
+    Eval(RValue),
     Assert(Prop),
     Assume(Prop),
     /// A loop invariant, placed at a loop header. Verification assumes it on entry
@@ -243,6 +466,110 @@ pub enum RValue {
     Aggregate(AggKind, Vec<Operand>),
     /// Take a reference to a place: `&place` or `&mut place`.
     Ref(BorrowKind, Place),
+    /// `operand as ty` — an explicit numeric conversion to the target type. Only
+    /// the target is known when lowering builds this node; inference classifies
+    /// the conversion from the operand's type with [`CastKind::of`] and rejects
+    /// any pair that has no kind.
+    Cast(Operand, CoreTy),
+}
+
+/// How an `as` conversion changes a value, determined by the (source, target)
+/// type pair. The default `Int` is treated as `i64` on either side.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CastKind {
+    /// A type to itself (`x as f64` with `x: f64`): the value is unchanged.
+    Identity,
+    /// Integer to `f64`, rounding to the nearest representable float.
+    IntToFloat,
+    /// `f64` to an integer type, *saturating*: out-of-range values clamp to the
+    /// target's minimum/maximum and NaN becomes `0` (Rust's `as` semantics).
+    FloatToInt(IntTy),
+    /// Integer to an integer type that cannot hold every source value (narrower,
+    /// or a change of signedness): two's-complement wraparound to the target.
+    IntTruncate(IntTy),
+    /// Integer to an integer type whose range contains the source's: the value is
+    /// unchanged. `signed` records the source signedness.
+    IntExtend { signed: bool },
+    /// `bool` to an integer type: `false` is `0`, `true` is `1`.
+    BoolToInt,
+}
+
+impl CastKind {
+    /// The conversion `from as to`, or `None` when the cast is not allowed. The
+    /// allowed casts are numeric to numeric and `bool` to integer.
+    pub fn of(from: &CoreTy, to: &CoreTy) -> Option<CastKind> {
+        match (from, to) {
+            (CoreTy::Float, CoreTy::Float) => Some(CastKind::Identity),
+            (CoreTy::Float, _) => int_width(to).map(CastKind::FloatToInt),
+            (CoreTy::Bool, _) => int_width(to).map(|_| CastKind::BoolToInt),
+            _ => {
+                let src = int_width(from)?;
+                if matches!(to, CoreTy::Float) {
+                    return Some(CastKind::IntToFloat);
+                }
+                let dst = int_width(to)?;
+                if dst == src {
+                    Some(CastKind::Identity)
+                } else if dst.min() <= src.min() && src.max_u128() <= dst.max_u128() {
+                    Some(CastKind::IntExtend { signed: src.signed })
+                } else {
+                    Some(CastKind::IntTruncate(dst))
+                }
+            }
+        }
+    }
+}
+
+impl Const {
+    /// Evaluate `self as to` at compile time, or `None` when the constant has no
+    /// numeric conversion to `to`. This is the one definition of the runtime cast
+    /// semantics: constant folding uses it directly and the VM evaluates `as` by
+    /// round-tripping its scalar values through it.
+    pub fn cast(&self, to: &CoreTy) -> Option<Const> {
+        match (self, to) {
+            (Const::Int(v), CoreTy::Float) => Some(Const::Float(*v as f64)),
+            (Const::Float(f), CoreTy::Float) => Some(Const::Float(*f)),
+            (Const::Int(v), _) => int_width(to).map(|w| Const::Int(wrap_to(*v, w))),
+            (Const::Bool(b), _) => int_width(to).map(|_| Const::Int(*b as i128)),
+            (Const::Float(f), _) => int_width(to).map(|w| Const::Int(saturate_to(*f, w))),
+            _ => None,
+        }
+    }
+}
+
+/// Two's-complement wraparound of `v` into width `w` (mask for unsigned,
+/// sign-extend for signed). 128-bit widths are the carrier itself.
+fn wrap_to(v: i128, w: IntTy) -> i128 {
+    if w.bits >= 128 {
+        return v;
+    }
+    let shift = 128 - w.bits as u32;
+    if w.signed {
+        (v << shift) >> shift
+    } else {
+        ((v as u128) << shift >> shift) as i128
+    }
+}
+
+/// `f` clamped into width `w`'s range, with NaN mapping to `0`. Rust's float to
+/// integer `as` already saturates at `i128`, so clamp to the target bounds after.
+fn saturate_to(f: f64, w: IntTy) -> i128 {
+    if f.is_nan() {
+        return 0;
+    }
+    if !w.signed && w.bits >= 128 {
+        return (f as u128) as i128;
+    }
+    (f as i128).clamp(w.min(), w.max())
+}
+
+/// The integer width of a type: a sized `IntN`, or `i64` for the default `Int`.
+pub fn int_width(ty: &CoreTy) -> Option<IntTy> {
+    match ty {
+        CoreTy::Int => Some(IntTy { signed: true, bits: 64 }),
+        CoreTy::IntN(w) => Some(*w),
+        _ => None,
+    }
 }
 
 /// One arm of a [`Terminator::Match`]: if the scrutinee's discriminant is `variant`,
@@ -254,6 +581,7 @@ pub struct MatchArm {
     pub target: BlockId,
 }
 
+#[derive(Clone)]
 pub enum Terminator<P: Phase> {
     Goto(BlockId),
     Branch { cond: Operand, then_blk: BlockId, else_blk: BlockId },
@@ -265,3 +593,21 @@ pub enum Terminator<P: Phase> {
     /// Drop carries a *derived* memory-management strategy, present only in `Lowerable`.
     Drop { place: Place, strategy: P::Strategy, next: BlockId },
 }
+
+impl<P: Phase> Terminator<P> {
+    /// The blocks control may continue to: the CFG edges out of the block this
+    /// terminates. `Return` and `Panic` have none.
+    pub fn successors(&self) -> Vec<BlockId> {
+        match self {
+            Terminator::Goto(b) => vec![*b],
+            Terminator::Branch { then_blk, else_blk, .. } => vec![*then_blk, *else_blk],
+            Terminator::Match { arms, otherwise, .. } => {
+                let mut s: Vec<BlockId> = arms.iter().map(|a| a.target).collect();
+                s.extend(otherwise.iter().copied());
+                s
+            }
+            Terminator::Drop { next, .. } => vec![*next],
+            Terminator::Return(_) | Terminator::Panic => vec![],
+        }
+    }
+}
@@ -0,0 +1,368 @@
+//! Lints over lowered IR: warnings that are only visible once control flow is
+//! explicit.
+//!
+//! The surface linter (`rv_syntax::lint`) sees one statement at a time. These
+//! rules look at a whole [`Function`]'s CFG: a branch on a constant, a store
+//! whose value no path reads, a match arm its scrutinee can never select.
+//! Like the surface lints they never reject a program; [`check`] returns each
+//! warning at a [`Site`](crate::Site)-shaped position that the driver maps back
+//! to a source line through [`SourceLines`](crate::SourceLines).
+
+use std::collections::{HashMap, HashSet};
+
+use rv_core::{BinOp, Symbols};
+
+use crate::init::{stmt_def, stmt_reads, term_reads};
+use crate::{AggKind, BlockId, Const, Function, LocalId, Operand, Phase, RValue, Stmt, Terminator};
+
+/// One IR lint warning, at statement `stmt` of `block` (or its terminator when
+/// `stmt` is `None`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct IrLint {
+    /// The rule's stable, kebab-case name.
+    pub rule: &'static str,
+    pub block: BlockId,
+    pub stmt: Option<u32>,
+    pub message: String,
+    /// What to do instead.
+    pub help: &'static str,
+}
+
+/// Run every IR lint over `f`, in block order.
+pub fn check<P: Phase>(f: &Function<P>, syms: &Symbols) -> Vec<IrLint> {
+    let mut out = Vec::new();
+    constant_condition(f, &mut out);
+    unreachable_switch_arm(f, &mut out);
+    dead_store(f, syms, &mut out);
+    unit_comparison(f, &mut out);
+    out.sort_by_key(|l| (l.block.0, l.stmt.map_or(u32::MAX, |s| s)));
+    out
+}
+
+/// The value the last statement of `block` that writes the temporary `local`
+/// stores, when that write replaces the whole local. Named locals are skipped: a
+/// `let debug = false;` flag is meant to be edited, not folded.
+fn last_assigned<'a, P: Phase>(
+    f: &Function<P>,
+    block: &'a crate::Block<P>,
+    local: LocalId,
+) -> Option<&'a RValue> {
+    if f.locals[local.0 as usize].name.is_some() {
+        return None;
+    }
+    let (place, rv) = block.stmts.iter().rev().find_map(|s| match s {
+        Stmt::Assign(place, rv) if place.local == local => Some((place, rv)),
+        _ => None,
+    })?;
+    place.proj.is_empty().then_some(rv)
+}
+
+/// `constant-condition`: a branch whose condition is a literal, or a temporary
+/// the same block just assigned a literal.
+fn constant_condition<P: Phase>(f: &Function<P>, out: &mut Vec<IrLint>) {
+    for block in &f.blocks {
+        let Terminator::Branch { cond, .. } = &block.term else { continue };
+        let value = match cond {
+            Operand::Const(Const::Bool(b)) => Some(*b),
+            Operand::Copy(place) if place.proj.is_empty() => {
+                match last_assigned(f, block, place.local) {
+                    Some(RValue::Use(Operand::Const(Const::Bool(b)))) => Some(*b),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        if let Some(value) = value {
+            out.push(IrLint {
+                rule: "constant-condition",
+                block: block.id,
+                stmt: None,
+                message: format!("this condition is always `{value}`"),
+                help: "remove the test and keep only the branch that runs",
+            });
+        }
+    }
+}
+
+/// `unreachable-switch-arm`: a match on a variant the same block just built into
+/// a temporary can only take that variant's arm, and an arm that repeats an earlier arm's
+/// variant is never reached.
+fn unreachable_switch_arm<P: Phase>(f: &Function<P>, out: &mut Vec<IrLint>) {
+    for block in &f.blocks {
+        let Terminator::Match { scrutinee, arms, .. } = &block.term else { continue };
+        let known = match scrutinee {
+            Operand::Copy(place) if place.proj.is_empty() => {
+                match last_assigned(f, block, place.local) {
+                    Some(RValue::Aggregate(AggKind::Variant(_, idx), _)) => Some(*idx),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        let mut seen = HashSet::new();
+        let mut dead = 0;
+        for arm in arms {
+            let repeated = !seen.insert(arm.variant);
+            let excluded = known.is_some_and(|k| k != arm.variant);
+            if repeated || excluded {
+                dead += 1;
+            }
+        }
+        if dead == 0 {
+            continue;
+        }
+        let message = match known {
+            Some(k) => format!(
+                "{dead} match arm(s) can never be taken: the scrutinee is always variant {k}"
+            ),
+            None => {
+                format!("{dead} match arm(s) repeat an earlier arm's variant and are never taken")
+            }
+        };
+        out.push(IrLint {
+            rule: "unreachable-switch-arm",
+            block: block.id,
+            stmt: None,
+            message,
+            help: "remove the arms that cannot match",
+        });
+    }
+}
+
+/// `dead-store`: an assignment to a named local that no path reads before the
+/// local is assigned again or the function returns.
+///
+/// A backward liveness analysis: a local is live at a point when some path from
+/// it reads the local before redefining it. Ghost statements read the locals
+/// they name: a value an `assert` checks is not dead. Locals that are never read
+/// at all, and names starting with `_`, are left to the unused-binding checks.
+fn dead_store<P: Phase>(f: &Function<P>, syms: &Symbols, out: &mut Vec<IrLint>) {
+    let n = f.locals.len();
+    let by_name: HashMap<_, LocalId> = f
+        .locals
+        .iter()
+        .enumerate()
+        .filter_map(|(i, l)| Some((l.name?, LocalId(i as u32))))
+        .collect();
+    let reads_of_stmt = |stmt: &Stmt, reads: &mut Vec<LocalId>| {
+        stmt_reads(stmt, reads);
+        if let Stmt::Assert(p) | Stmt::Assume(p) | Stmt::Invariant(p) = stmt {
+            let mut vars = Vec::new();
+            rv_core::prop_vars(p, &mut vars);
+            reads.extend(vars.iter().filter_map(|v| by_name.get(v).copied()));
+        }
+    };
+
+    let mut ever_read = vec![false; n];
+    let mut reads = Vec::new();
+    for block in &f.blocks {
+        block.stmts.iter().for_each(|s| reads_of_stmt(s, &mut reads));
+        term_reads(&block.term, &mut reads);
+    }
+    for l in reads.drain(..) {
+        ever_read[l.0 as usize] = true;
+    }
+
+    // `live[i]` is the set of locals live on entry to block `i`.
+    let index: HashMap<BlockId, usize> =
+        f.blocks.iter().enumerate().map(|(i, b)| (b.id, i)).collect();
+    let live_out = |live: &[Vec<bool>], i: usize| {
+        let mut set = vec![false; n];
+        for succ in f.blocks[i].term.successors() {
+            let Some(&s) = index.get(&succ) else { continue };
+            set.iter_mut().zip(&live[s]).for_each(|(a, b)| *a |= b);
+        }
+        set
+    };
+    let transfer = |set: &mut Vec<bool>, stmt: &Stmt, reads: &mut Vec<LocalId>| {
+        if let Some(def) = stmt_def(stmt) {
+            set[def.0 as usize] = false;
+        }
+        reads_of_stmt(stmt, reads);
+        reads.drain(..).for_each(|l| set[l.0 as usize] = true);
+    };
+    let mut live = vec![vec![false; n]; f.blocks.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for i in (0..f.blocks.len()).rev() {
+            let mut set = live_out(&live, i);
+            term_reads(&f.blocks[i].term, &mut reads);
+            reads.drain(..).for_each(|l| set[l.0 as usize] = true);
+            for stmt in f.blocks[i].stmts.iter().rev() {
+                transfer(&mut set, stmt, &mut reads);
+            }
+            if set != live[i] {
+                live[i] = set;
+                changed = true;
+            }
+        }
+    }
+
+    for (i, block) in f.blocks.iter().enumerate() {
+        let mut set = live_out(&live, i);
+        term_reads(&block.term, &mut reads);
+        reads.drain(..).for_each(|l| set[l.0 as usize] = true);
+        let mut found = Vec::new();
+        for (s, stmt) in block.stmts.iter().enumerate().rev() {
+            if let Some(def) = stmt_def(stmt) {
+                let name = f.locals[def.0 as usize].name.map(|n| syms.resolve(n));
+                if let Some(name) = name {
+                    let dead = !set[def.0 as usize] && ever_read[def.0 as usize];
+                    if dead && !name.starts_with('_') && !has_effect(stmt) {
+                        found.push(IrLint {
+                            rule: "dead-store",
+                            block: block.id,
+                            stmt: Some(s as u32),
+                            message: format!("the value assigned to `{name}` is never read"),
+                            help: "remove the assignment, or read the value before overwriting it",
+                        });
+                    }
+                }
+            }
+            transfer(&mut set, stmt, &mut reads);
+        }
+        out.extend(found.into_iter().rev());
+    }
+}
+
+/// `unit-comparison`: `==` or `!=` between two unit values, whose result is
+/// fixed because unit has a single value.
+fn unit_comparison<P: Phase>(f: &Function<P>, out: &mut Vec<IrLint>) {
+    let is_unit = |op: &Operand| match op {
+        Operand::Const(c) => matches!(c, Const::Unit),
+        Operand::Copy(place) => {
+            let ty = P::known(&f.locals[place.local.0 as usize].ty);
+            place.proj.is_empty() && matches!(ty, Some(rv_core::Ty::Unit))
+        }
+    };
+    for block in &f.blocks {
+        for (s, stmt) in block.stmts.iter().enumerate() {
+            let Stmt::Assign(_, RValue::Bin(op @ (BinOp::Eq | BinOp::Ne), a, b)) = stmt else {
+                continue;
+            };
+            if is_unit(a) && is_unit(b) {
+                out.push(IrLint {
+                    rule: "unit-comparison",
+                    block: block.id,
+                    stmt: Some(s as u32),
+                    message: format!(
+                        "comparison of unit values is always `{}`",
+                        *op == BinOp::Eq
+                    ),
+                    help: "use the literal result, or compare values that can differ",
+                });
+            }
+        }
+    }
+}
+
+/// Whether removing `stmt` would change more than the target's value: a call
+/// may have effects beyond its result.
+fn has_effect(stmt: &Stmt) -> bool {
+    matches!(stmt, Stmt::Assign(_, RValue::Call(..) | RValue::CallClosure(..)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{copy, int, FunctionBuilder};
+    use crate::{Lowerable, MatchArm};
+    use rv_core::{Prop, Term, Ty};
+
+    fn rules(lints: &[IrLint]) -> Vec<&'static str> {
+        lints.iter().map(|l| l.rule).collect()
+    }
+
+    #[test]
+    fn a_branch_on_a_literal_is_a_constant_condition() {
+        let mut syms = Symbols::new();
+        let mut b = FunctionBuilder::<Lowerable>::new(&mut syms, "f", Ty::Int);
+        let (yes, no) = (b.block(), b.block());
+        b.branch(Operand::Const(Const::Bool(true)), yes, no);
+        b.switch_to(yes).ret(int(1));
+        b.switch_to(no).ret(int(2));
+        let f = b.finish();
+        let lints = check(&f, &syms);
+        assert_eq!(rules(&lints), ["constant-condition"]);
+        assert_eq!((lints[0].block, lints[0].stmt), (BlockId(0), None));
+        assert!(lints[0].message.contains("always `true`"), "{}", lints[0].message);
+    }
+
+    #[test]
+    fn only_the_overwritten_store_is_dead() {
+        let mut syms = Symbols::new();
+        let mut b = FunctionBuilder::<Lowerable>::new(&mut syms, "f", Ty::Int);
+        let p = b.param("p", Ty::Int);
+        let x = b.local("x", Ty::Int);
+        b.assign(x, RValue::Bin(BinOp::Add, copy(p), int(1)));
+        b.assign(x, RValue::Bin(BinOp::Add, copy(p), int(2)));
+        b.ret(copy(x));
+        let f = b.finish();
+        let lints = check(&f, &syms);
+        assert_eq!(rules(&lints), ["dead-store"]);
+        assert_eq!((lints[0].block, lints[0].stmt), (BlockId(0), Some(0)));
+        assert!(lints[0].message.contains("`x`"), "{}", lints[0].message);
+    }
+
+    #[test]
+    fn values_read_on_some_path_or_by_an_assertion_are_live() {
+        let mut syms = Symbols::new();
+        let mut b = FunctionBuilder::<Lowerable>::new(&mut syms, "f", Ty::Int);
+        let flag = b.param("flag", Ty::Bool);
+        let x = b.local("x", Ty::Int);
+        let (yes, no) = (b.block(), b.block());
+        b.assign(x, RValue::Use(int(1))).branch(copy(flag), yes, no);
+        b.switch_to(yes).ret(copy(x));
+        b.switch_to(no).assign(x, RValue::Use(int(2))).ret(copy(x));
+        let f = b.finish();
+        assert_eq!(check(&f, &syms), []);
+
+        let mut b = FunctionBuilder::<Lowerable>::new(&mut syms, "g", Ty::Int);
+        let x = b.local("x", Ty::Int);
+        let x_name = b.sym("x");
+        b.assign(x, RValue::Use(int(1)));
+        let positive = Term::Bin(BinOp::Gt, Box::new(Term::Var(x_name)), Box::new(Term::Int(0)));
+        b.stmt(Stmt::Assert(Prop::Holds(positive)));
+        b.assign(x, RValue::Use(int(2))).ret(copy(x));
+        let f = b.finish();
+        assert_eq!(check(&f, &syms), []);
+    }
+
+    #[test]
+    fn a_match_on_a_known_variant_has_unreachable_arms() {
+        let mut syms = Symbols::new();
+        let mut b = FunctionBuilder::<Lowerable>::new(&mut syms, "f", Ty::Int);
+        let e = b.sym("E");
+        let s = b.temp(Ty::Int);
+        let (a0, a1) = (b.block(), b.block());
+        b.assign(s, RValue::Aggregate(AggKind::Variant(e, 1), vec![]));
+        let arms = vec![MatchArm { variant: 0, target: a0 }, MatchArm { variant: 1, target: a1 }];
+        b.terminate(Terminator::Match { scrutinee: copy(s), arms, otherwise: None });
+        b.switch_to(a0).ret(int(0));
+        b.switch_to(a1).ret(int(1));
+        let f = b.finish();
+        let lints = check(&f, &syms);
+        assert_eq!(rules(&lints), ["unreachable-switch-arm"]);
+        assert!(lints[0].message.contains("always variant 1"), "{}", lints[0].message);
+    }
+
+    #[test]
+    fn comparing_unit_values_has_a_fixed_result() {
+        let mut syms = Symbols::new();
+        let mut b = FunctionBuilder::<Lowerable>::new(&mut syms, "f", Ty::Bool);
+        let u = b.temp(Ty::Unit);
+        let (eq, ne) = (b.temp(Ty::Bool), b.temp(Ty::Bool));
+        let unit = || Operand::Const(Const::Unit);
+        b.assign(u, RValue::Use(unit()));
+        b.assign(eq, RValue::Bin(BinOp::Eq, copy(u), unit()));
+        b.assign(ne, RValue::Bin(BinOp::Ne, unit(), copy(u)));
+        b.ret(copy(eq));
+        let f = b.finish();
+        let lints = check(&f, &syms);
+        assert_eq!(rules(&lints), ["unit-comparison", "unit-comparison"]);
+        assert_eq!(lints[0].stmt, Some(1));
+        assert!(lints[0].message.contains("always `true`"), "{}", lints[0].message);
+        assert!(lints[1].message.contains("always `false`"), "{}", lints[1].message);
+    }
+}
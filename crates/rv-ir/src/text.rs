@@ -0,0 +1,1488 @@
+//! A textual form of the IR, for writing backend and pass tests as text.
+//!
+//! [`print`] renders a [`Program`] and [`parse`] reads the same format back;
+//! `parse(print(p))` rebuilds `p` exactly, up to symbol identity (names are
+//! re-interned, so two distinct symbols that print alike come back as one).
+//!
+//! ```
+//! use rv_core::Symbols;
+//! use rv_ir::{text, Lowerable, Program};
+//!
+//! let src = "
+//!     struct Point { x: i64, y: i64 }
+//!     fn main() -> i64 {
+//!         let _0: Point;
+//!         let _1: i64;
+//!         bb0: {
+//!             _0 = struct Point(3, 4);
+//!             _1 = Add(_0.0, _0.1);
+//!             return _1;
+//!         }
+//!     }";
+//! let mut syms = Symbols::new();
+//! let prog: Program<Lowerable> = text::parse(src, &mut syms).unwrap();
+//! assert_eq!(prog.funcs[0].blocks[0].stmts.len(), 2);
+//! let again: Program<Lowerable> = text::parse(&text::print(&prog, &syms), &mut syms).unwrap();
+//! assert_eq!(text::print(&again, &syms), text::print(&prog, &syms));
+//! ```
+//!
+//! The format, item by item (`//` starts a comment):
+//!
+//! * `struct S<T> { f: ty, .. }`, `enum E<T> { A, B(ty, ..), .. }`, and
+//!   `impl Trait for Type;` declare the program's types and trait impls.
+//! * `fn f<T>(_0, _1) -> ty { .. }` lists the parameter locals. The body holds
+//!   `bound T: A + B;` lines, optional `requires prop;` / `ensures prop;`, one
+//!   `let _N name: ty;` per local in order (the name is optional, and a
+//!   mutable local is `let mut`), an optional `entry bbN;` (the first block
+//!   otherwise), then the blocks `bbN: { .. }`.
+//! * Statements: `place = rvalue;`, `eval rvalue;`, `assert prop;`,
+//!   `assume prop;`, `invariant prop;`. Terminators: `goto bbN;`,
+//!   `branch op, bbN, bbM;`, `match op { 0 => bbN, .., _ => bbM };`,
+//!   `return op;`, `panic;`, `drop place -> bbN;` (`with dK` in `Lowerable`).
+//! * A place is `_N` followed by `.i` (field), `@i` (downcast), `.*` (deref)
+//!   and `[op]` (index). An operand is a place or a constant: an integer, a
+//!   float (`1.0`, `NaN`, `inf`), a string, `true`/`false`, or `()`.
+//! * Rvalues: an operand, `op as ty`, `Add(a, b)` (any [`BinOp`] by name),
+//!   `wrapping Add(a, b)`, `Neg(a)`/`Not(a)`, `len(v)`, `push(v, x)`,
+//!   `call f(..)`, `closure f(..)`, `call_closure op(..)`, `struct S(..)`,
+//!   `variant E#i(..)`, `tuple(..)`, `array(..)`, `vec(..)`, `&place`, and
+//!   `&mut place`.
+//! * Types are written as in source, except that a type parameter is `$T`, a
+//!   `Vec<T>` is `[T]`, a sized `i64` is `sized i64`, and an unknown type
+//!   (a `Parsed` local without an annotation) is `_`.
+//! * Propositions: `true`, `false`, `{term}`, `!p`, `(p && q)`, `(p || q)`,
+//!   `(p ==> q)`, `forall x. p`, `exists x. p`. Terms are literals, names,
+//!   calls `f(..)`, fields `t.i`, `-(t)`, `!(t)`, and fully parenthesized binary
+//!   operations `(a + b)`.
+//!
+//! A name that is not a plain identifier, or that reads as a keyword, is
+//! written between backquotes.
+
+use std::fmt;
+use std::fmt::Write as _;
+
+use rv_core::{BinOp, IntTy, Prop, Sym, Symbols, Term, Ty, UnOp};
+
+use crate::{
+    AggKind, Block, BlockId, BorrowKind, Const, DisciplineId, FieldDef, Function, LocalDecl,
+    LocalId, Lowerable, MatchArm, Operand, Parsed, Phase, Place, Program, Proj, RValue, Stmt,
+    Terminator, TraitImpl, TypeDef, Typed, VariantDef,
+};
+
+/// How a phase writes the fields that vary between phases.
+pub trait TextPhase: Phase {
+    /// The local or return type to print; `None` prints as `_`.
+    fn ty_text(ty: &Self::Ty) -> Option<&Ty>;
+    /// The phase's type from a parsed annotation (`None` for `_`).
+    fn ty_from_text(ty: Option<Ty>) -> Result<Self::Ty, String>;
+    /// The drop strategy to print after `with`, if the phase has one.
+    fn strategy_text(strategy: &Self::Strategy) -> Option<u32>;
+    /// The phase's drop strategy from the parsed `with dK`, if any.
+    fn strategy_from_text(strategy: Option<u32>) -> Result<Self::Strategy, String>;
+}
+
+impl TextPhase for Parsed {
+    fn ty_text(ty: &Option<Ty>) -> Option<&Ty> {
+        ty.as_ref()
+    }
+    fn ty_from_text(ty: Option<Ty>) -> Result<Option<Ty>, String> {
+        Ok(ty)
+    }
+    fn strategy_text(_: &()) -> Option<u32> {
+        None
+    }
+    fn strategy_from_text(strategy: Option<u32>) -> Result<(), String> {
+        no_strategy(strategy)
+    }
+}
+
+impl TextPhase for Typed {
+    fn ty_text(ty: &Ty) -> Option<&Ty> {
+        Some(ty)
+    }
+    fn ty_from_text(ty: Option<Ty>) -> Result<Ty, String> {
+        ty.ok_or_else(|| "every type is known in this phase; `_` is not allowed".to_string())
+    }
+    fn strategy_text(_: &()) -> Option<u32> {
+        None
+    }
+    fn strategy_from_text(strategy: Option<u32>) -> Result<(), String> {
+        no_strategy(strategy)
+    }
+}
+
+impl TextPhase for Lowerable {
+    fn ty_text(ty: &Ty) -> Option<&Ty> {
+        Some(ty)
+    }
+    fn ty_from_text(ty: Option<Ty>) -> Result<Ty, String> {
+        Typed::ty_from_text(ty)
+    }
+    fn strategy_text(strategy: &DisciplineId) -> Option<u32> {
+        Some(strategy.0)
+    }
+    fn strategy_from_text(strategy: Option<u32>) -> Result<DisciplineId, String> {
+        strategy
+            .map(DisciplineId)
+            .ok_or_else(|| "a drop needs its strategy in this phase: `with dN`".to_string())
+    }
+}
+
+fn no_strategy(strategy: Option<u32>) -> Result<(), String> {
+    match strategy {
+        None => Ok(()),
+        Some(_) => Err("drops carry no strategy in this phase".to_string()),
+    }
+}
+
+/// A parse failure at a 1-based line and column.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: u32,
+    pub col: u32,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.col, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Words the format gives a meaning of their own; a name spelled like one is
+/// printed between backquotes.
+const KEYWORDS: &[&str] = &[
+    "struct", "enum", "impl", "for", "fn", "bound", "requires", "ensures", "let", "entry",
+    "eval", "assert", "assume", "invariant", "goto", "branch", "match", "return",
+    "panic", "drop", "with", "as", "wrapping", "len", "push", "call", "closure", "call_closure",
+    "variant", "tuple", "array", "vec", "mut", "true", "false", "forall", "exists", "NaN", "inf",
+    "sized", "_",
+];
+
+const BIN_OPS: &[(BinOp, &str, &str)] = &[
+    (BinOp::Add, "Add", "+"),
+    (BinOp::Sub, "Sub", "-"),
+    (BinOp::Mul, "Mul", "*"),
+    (BinOp::Div, "Div", "/"),
+    (BinOp::Mod, "Mod", "%"),
+    (BinOp::And, "And", "&&"),
+    (BinOp::Or, "Or", "||"),
+    (BinOp::Eq, "Eq", "=="),
+    (BinOp::Ne, "Ne", "!="),
+    (BinOp::Lt, "Lt", "<"),
+    (BinOp::Le, "Le", "<="),
+    (BinOp::Gt, "Gt", ">"),
+    (BinOp::Ge, "Ge", ">="),
+    (BinOp::BitAnd, "BitAnd", "&"),
+    (BinOp::BitOr, "BitOr", "|"),
+    (BinOp::BitXor, "BitXor", "^"),
+    (BinOp::Shl, "Shl", "<<"),
+    (BinOp::Shr, "Shr", ">>"),
+];
+
+fn bin_op_entry(op: BinOp) -> (&'static str, &'static str) {
+    let (_, name, symbol) = BIN_OPS.iter().find(|(o, ..)| *o == op).expect("every op is listed");
+    (name, symbol)
+}
+
+// ---- printing ---------------------------------------------------------------
+
+/// Render `prog` in the textual format.
+pub fn print<P: TextPhase>(prog: &Program<P>, syms: &Symbols) -> String {
+    let mut p = Printer { syms, out: String::new() };
+    for def in &prog.types {
+        p.type_def(def);
+    }
+    for imp in &prog.trait_impls {
+        let _ = writeln!(p.out, "impl {} for {};", p.name(imp.trait_name), p.name(imp.type_name));
+    }
+    for f in &prog.funcs {
+        if !p.out.is_empty() {
+            p.out.push('\n');
+        }
+        p.func(f);
+    }
+    p.out
+}
+
+struct Printer<'a> {
+    syms: &'a Symbols,
+    out: String,
+}
+
+impl Printer<'_> {
+    fn name(&self, sym: Sym) -> String {
+        let text = self.syms.resolve(sym);
+        let mut chars = text.chars();
+        let plain = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if plain && !KEYWORDS.contains(&text) && !is_local_name(text) && !is_block_name(text) {
+            text.to_string()
+        } else {
+            format!("`{text}`")
+        }
+    }
+
+    fn generics(&self, params: &[Sym]) -> String {
+        if params.is_empty() {
+            return String::new();
+        }
+        let names: Vec<String> = params.iter().map(|p| self.name(*p)).collect();
+        format!("<{}>", names.join(", "))
+    }
+
+    fn type_def(&mut self, def: &TypeDef) {
+        let line = match def {
+            TypeDef::Struct { name, type_params, fields } => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|f| format!("{}: {}", self.name(f.name), self.ty(&f.ty)))
+                    .collect();
+                let head = format!("struct {}{}", self.name(*name), self.generics(type_params));
+                format!("{head} {{ {} }}", fields.join(", "))
+            }
+            TypeDef::Enum { name, type_params, variants } => {
+                let variants: Vec<String> = variants
+                    .iter()
+                    .map(|v| match v.fields.is_empty() {
+                        true => self.name(v.name),
+                        false => format!("{}({})", self.name(v.name), self.tys(&v.fields)),
+                    })
+                    .collect();
+                let head = format!("enum {}{}", self.name(*name), self.generics(type_params));
+                format!("{head} {{ {} }}", variants.join(", "))
+            }
+        };
+        self.out.push_str(&line);
+        self.out.push('\n');
+    }
+
+    fn tys(&self, tys: &[Ty]) -> String {
+        tys.iter().map(|t| self.ty(t)).collect::<Vec<_>>().join(", ")
+    }
+
+    fn ty(&self, ty: &Ty) -> String {
+        match ty {
+            Ty::Int => "i64".to_string(),
+            Ty::IntN(w) if w.signed && w.bits == 64 => "sized i64".to_string(),
+            Ty::IntN(w) => format!("{}{}", if w.signed { "i" } else { "u" }, w.bits),
+            Ty::Float => "f64".to_string(),
+            Ty::Str => "String".to_string(),
+            Ty::Bool => "bool".to_string(),
+            Ty::Unit => "()".to_string(),
+            Ty::Never => "!".to_string(),
+            Ty::Tuple(items) if items.len() == 1 => format!("({},)", self.ty(&items[0])),
+            Ty::Tuple(items) => format!("({})", self.tys(items)),
+            Ty::Array(item, len) => format!("[{}; {len}]", self.ty(item)),
+            Ty::Vec(item) => format!("[{}]", self.ty(item)),
+            Ty::Fn(params, ret) => format!("fn({}) -> {}", self.tys(params), self.ty(ret)),
+            Ty::Adt(name, args) if args.is_empty() => self.name(*name),
+            Ty::Adt(name, args) => format!("{}<{}>", self.name(*name), self.tys(args)),
+            Ty::Ref { mutable, inner } => {
+                format!("&{}{}", if *mutable { "mut " } else { "" }, self.ty(inner))
+            }
+            Ty::Param(name) => format!("${}", self.name(*name)),
+        }
+    }
+
+    fn opt_ty<P: TextPhase>(&self, ty: &P::Ty) -> String {
+        P::ty_text(ty).map_or_else(|| "_".to_string(), |t| self.ty(t))
+    }
+
+    fn func<P: TextPhase>(&mut self, f: &Function<P>) {
+        let params: Vec<String> = f.params.iter().map(|p| format!("_{}", p.0)).collect();
+        let _ = writeln!(
+            self.out,
+            "fn {}{}({}) -> {} {{",
+            self.name(f.name),
+            self.generics(&f.type_params),
+            params.join(", "),
+            self.opt_ty::<P>(&f.ret)
+        );
+        for (param, bounds) in &f.generic_bounds {
+            let bounds: Vec<String> = bounds.iter().map(|b| self.name(*b)).collect();
+            let _ = writeln!(self.out, "    bound {}: {};", self.name(*param), bounds.join(" + "));
+        }
+        if f.pre != Prop::True {
+            let _ = writeln!(self.out, "    requires {};", self.prop(&f.pre));
+        }
+        if f.post != Prop::True {
+            let _ = writeln!(self.out, "    ensures {};", self.prop(&f.post));
+        }
+        for (i, local) in f.locals.iter().enumerate() {
+            let name = local.name.map_or_else(String::new, |n| format!(" {}", self.name(n)));
+            let kw = if local.mutable { "let mut" } else { "let" };
+            let _ = writeln!(self.out, "    {kw} _{i}{name}: {};", self.opt_ty::<P>(&local.ty));
+        }
+        if f.blocks.first().map(|b| b.id) != Some(f.entry) {
+            let _ = writeln!(self.out, "    entry bb{};", f.entry.0);
+        }
+        for block in &f.blocks {
+            let _ = writeln!(self.out, "    bb{}: {{", block.id.0);
+            for stmt in &block.stmts {
+                let line = self.stmt(stmt);
+                let _ = writeln!(self.out, "        {line};");
+            }
+            let line = self.term(&block.term);
+            let _ = writeln!(self.out, "        {line};");
+            self.out.push_str("    }\n");
+        }
+        self.out.push_str("}\n");
+    }
+
+    fn stmt(&self, stmt: &Stmt) -> String {
+        match stmt {
+            Stmt::Assign(place, rv) => format!("{} = {}", self.place(place), self.rvalue(rv)),
+            Stmt::Eval(rv) => format!("eval {}", self.rvalue(rv)),
+            Stmt::Assert(p) => format!("assert {}", self.prop(p)),
+            Stmt::Assume(p) => format!("assume {}", self.prop(p)),
+            Stmt::Invariant(p) => format!("invariant {}", self.prop(p)),
+        }
+    }
+
+    fn term<P: TextPhase>(&self, term: &Terminator<P>) -> String {
+        match term {
+            Terminator::Goto(b) => format!("goto bb{}", b.0),
+            Terminator::Branch { cond, then_blk, else_blk } => {
+                format!("branch {}, bb{}, bb{}", self.operand(cond), then_blk.0, else_blk.0)
+            }
+            Terminator::Match { scrutinee, arms, otherwise } => {
+                let mut arms: Vec<String> =
+                    arms.iter().map(|a| format!("{} => bb{}", a.variant, a.target.0)).collect();
+                arms.extend(otherwise.map(|b| format!("_ => bb{}", b.0)));
+                format!("match {} {{ {} }}", self.operand(scrutinee), arms.join(", "))
+            }
+            Terminator::Return(op) => format!("return {}", self.operand(op)),
+            Terminator::Panic => "panic".to_string(),
+            Terminator::Drop { place, strategy, next } => {
+                let mut s = format!("drop {} -> bb{}", self.place(place), next.0);
+                if let Some(d) = P::strategy_text(strategy) {
+                    let _ = write!(s, " with d{d}");
+                }
+                s
+            }
+        }
+    }
+
+    fn place(&self, place: &Place) -> String {
+        let mut s = format!("_{}", place.local.0);
+        for proj in &place.proj {
+            match proj {
+                Proj::Field(i) => {
+                    let _ = write!(s, ".{i}");
+                }
+                Proj::Downcast(i) => {
+                    let _ = write!(s, "@{i}");
+                }
+                Proj::Deref => s.push_str(".*"),
+                Proj::Index(op) => {
+                    let _ = write!(s, "[{}]", self.operand(op));
+                }
+            }
+        }
+        s
+    }
+
+    fn operand(&self, op: &Operand) -> String {
+        match op {
+            Operand::Copy(place) => self.place(place),
+            Operand::Const(c) => match c {
+                Const::Int(n) => n.to_string(),
+                Const::Float(f) if f.is_nan() => "NaN".to_string(),
+                Const::Float(f) if f.is_infinite() && *f > 0.0 => "inf".to_string(),
+                Const::Float(f) if f.is_infinite() => "-inf".to_string(),
+                Const::Float(f) => format!("{f:?}"),
+                Const::Str(s) => format!("{s:?}"),
+                Const::Bool(b) => b.to_string(),
+                Const::Unit => "()".to_string(),
+            },
+        }
+    }
+
+    fn operands(&self, ops: &[Operand]) -> String {
+        ops.iter().map(|o| self.operand(o)).collect::<Vec<_>>().join(", ")
+    }
+
+    fn rvalue(&self, rv: &RValue) -> String {
+        match rv {
+            RValue::Use(op) => self.operand(op),
+            RValue::Bin(op, a, b) => {
+                format!("{}({}, {})", bin_op_entry(*op).0, self.operand(a), self.operand(b))
+            }
+            RValue::WrappingBin(op, a, b) => {
+                let name = bin_op_entry(*op).0;
+                format!("wrapping {name}({}, {})", self.operand(a), self.operand(b))
+            }
+            RValue::Un(op, a) => format!("{op:?}({})", self.operand(a)),
+            RValue::VecLen(v) => format!("len({})", self.operand(v)),
+            RValue::VecPush(v, x) => format!("push({}, {})", self.operand(v), self.operand(x)),
+            RValue::Call(f, args) => format!("call {}({})", self.name(*f), self.operands(args)),
+            RValue::Closure(f, caps) => {
+                format!("closure {}({})", self.name(*f), self.operands(caps))
+            }
+            RValue::CallClosure(callee, args) => {
+                format!("call_closure {}({})", self.operand(callee), self.operands(args))
+            }
+            RValue::Aggregate(kind, ops) => {
+                let ops = self.operands(ops);
+                match kind {
+                    AggKind::Struct(name) => format!("struct {}({ops})", self.name(*name)),
+                    AggKind::Variant(name, i) => format!("variant {}#{i}({ops})", self.name(*name)),
+                    AggKind::Tuple => format!("tuple({ops})"),
+                    AggKind::Array => format!("array({ops})"),
+                    AggKind::Vec => format!("vec({ops})"),
+                }
+            }
+            RValue::Ref(BorrowKind::Shared, place) => format!("&{}", self.place(place)),
+            RValue::Ref(BorrowKind::Mut, place) => format!("&mut {}", self.place(place)),
+            RValue::Cast(op, ty) => format!("{} as {}", self.operand(op), self.ty(ty)),
+        }
+    }
+
+    fn prop(&self, prop: &Prop) -> String {
+        match prop {
+            Prop::True => "true".to_string(),
+            Prop::False => "false".to_string(),
+            Prop::Holds(t) => format!("{{{}}}", self.term_text(t)),
+            Prop::Not(p) => format!("!{}", self.prop(p)),
+            Prop::And(a, b) => format!("({} && {})", self.prop(a), self.prop(b)),
+            Prop::Or(a, b) => format!("({} || {})", self.prop(a), self.prop(b)),
+            Prop::Implies(a, b) => format!("({} ==> {})", self.prop(a), self.prop(b)),
+            Prop::Forall(x, p) => format!("forall {}. {}", self.name(*x), self.prop(p)),
+            Prop::Exists(x, p) => format!("exists {}. {}", self.name(*x), self.prop(p)),
+        }
+    }
+
+    fn term_text(&self, t: &Term) -> String {
+        match t {
+            Term::Int(n) => n.to_string(),
+            Term::Bool(b) => b.to_string(),
+            Term::Var(x) => self.name(*x),
+            Term::Bin(op, a, b) => {
+                format!("({} {} {})", self.term_text(a), bin_op_entry(*op).1, self.term_text(b))
+            }
+            Term::Un(UnOp::Neg, a) => format!("-({})", self.term_text(a)),
+            Term::Un(UnOp::Not, a) => format!("!({})", self.term_text(a)),
+            Term::Field(base, i) => format!("{}.{i}", self.term_text(base)),
+            Term::App(f, args) => {
+                let args: Vec<String> = args.iter().map(|a| self.term_text(a)).collect();
+                format!("{}({})", self.name(*f), args.join(", "))
+            }
+        }
+    }
+}
+
+fn is_local_name(text: &str) -> bool {
+    text.strip_prefix('_').is_some_and(|d| !d.is_empty() && d.bytes().all(|b| b.is_ascii_digit()))
+}
+
+fn is_block_name(text: &str) -> bool {
+    text.strip_prefix("bb").is_some_and(|d| !d.is_empty() && d.bytes().all(|b| b.is_ascii_digit()))
+}
+
+// ---- lexing -----------------------------------------------------------------
+
+#[derive(Clone, Debug, PartialEq)]
+enum Tok {
+    Ident(String),
+    /// A backquoted name: never a keyword.
+    Quoted(String),
+    Local(u32),
+    Int(u128),
+    Float(f64),
+    Str(String),
+    Punct(&'static str),
+    Eof,
+}
+
+const PUNCT: &[&str] = &[
+    "==>", "->", "=>", "==", "!=", "<=", ">=", "&&", "||", "<<", ">>", "{", "}", "(", ")", "[", "]",
+    "<", ">", ",", ";", ":", ".", "+", "-", "*", "/", "%", "!", "=", "&", "|", "^", "#", "@",
+    "$",
+];
+
+struct Spanned {
+    tok: Tok,
+    line: u32,
+    col: u32,
+}
+
+fn lex(text: &str) -> Result<Vec<Spanned>, ParseError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut toks = Vec::new();
+    let (mut i, mut line, mut col) = (0, 1u32, 1u32);
+    let err = |line, col, message: String| ParseError { line, col, message };
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\n' {
+            i += 1;
+            line += 1;
+            col = 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            i += 1;
+            col += 1;
+            continue;
+        }
+        if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+        let start = i;
+        let tok = if c.is_ascii_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            match is_local_name(&word) {
+                true => Tok::Local(
+                    word[1..].parse().map_err(|_| err(line, col, format!("bad local `{word}`")))?,
+                ),
+                false => Tok::Ident(word),
+            }
+        } else if c.is_ascii_digit() {
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            // A number right after `.`, `@` or `#` is an index, never a float:
+            // `_0.1.2`, `_0@1.0`.
+            let after_dot = start > 0 && matches!(chars[start - 1], '.' | '@' | '#');
+            let mut float = false;
+            let fraction = chars.get(i) == Some(&'.')
+                && chars.get(i + 1).is_some_and(char::is_ascii_digit);
+            if !after_dot && fraction {
+                float = true;
+                i += 1;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            if !after_dot && matches!(chars.get(i), Some('e' | 'E')) {
+                let digits = match chars.get(i + 1) {
+                    Some('+' | '-') => i + 2,
+                    _ => i + 1,
+                };
+                if chars.get(digits).is_some_and(char::is_ascii_digit) {
+                    float = true;
+                    i = digits;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let word: String = chars[start..i].iter().collect();
+            let tok = match float {
+                true => word.parse().ok().map(Tok::Float),
+                false => word.parse().ok().map(Tok::Int),
+            };
+            tok.ok_or_else(|| err(line, col, format!("bad number `{word}`")))?
+        } else if c == '"' {
+            i += 1;
+            let mut s = String::new();
+            loop {
+                let Some(&ch) = chars.get(i) else {
+                    return Err(err(line, col, "unterminated string".to_string()));
+                };
+                i += 1;
+                match ch {
+                    '"' => break,
+                    '\\' => {
+                        let esc = chars.get(i).copied();
+                        i += 1;
+                        s.push(match esc {
+                            Some('n') => '\n',
+                            Some('r') => '\r',
+                            Some('t') => '\t',
+                            Some('0') => '\0',
+                            Some(q @ ('\\' | '"' | '\'')) => q,
+                            Some('u') if chars.get(i) == Some(&'{') => {
+                                let close = chars[i..].iter().position(|&c| c == '}');
+                                let Some(close) = close else {
+                                    return Err(err(line, col, "unterminated `\\u{`".to_string()));
+                                };
+                                let hex: String = chars[i + 1..i + close].iter().collect();
+                                i += close + 1;
+                                let c = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32);
+                                let bad = || format!("bad escape `\\u{{{hex}}}`");
+                                c.ok_or_else(|| err(line, col, bad()))?
+                            }
+                            _ => return Err(err(line, col, "unknown string escape".to_string())),
+                        });
+                    }
+                    '\n' => return Err(err(line, col, "unterminated string".to_string())),
+                    ch => s.push(ch),
+                }
+            }
+            Tok::Str(s)
+        } else if c == '`' {
+            let close = chars[i + 1..].iter().position(|&c| c == '`' || c == '\n');
+            match close {
+                Some(n) if chars[i + 1 + n] == '`' => {
+                    let name: String = chars[i + 1..i + 1 + n].iter().collect();
+                    i += n + 2;
+                    Tok::Quoted(name)
+                }
+                _ => return Err(err(line, col, "unterminated backquoted name".to_string())),
+            }
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 3)].iter().collect();
+            let Some(p) = PUNCT.iter().find(|p| rest.starts_with(**p)) else {
+                return Err(err(line, col, format!("unexpected character `{c}`")));
+            };
+            i += p.len();
+            Tok::Punct(p)
+        };
+        toks.push(Spanned { tok, line, col });
+        col += (i - start) as u32;
+    }
+    toks.push(Spanned { tok: Tok::Eof, line, col });
+    Ok(toks)
+}
+
+// ---- parsing ----------------------------------------------------------------
+
+/// Parse a program written in the textual format.
+pub fn parse<P: TextPhase>(text: &str, syms: &mut Symbols) -> Result<Program<P>, ParseError> {
+    let toks = lex(text)?;
+    let mut p = Parser { toks, pos: 0, syms };
+    let mut prog = Program { types: Vec::new(), trait_impls: Vec::new(), funcs: Vec::new() };
+    while p.peek() != &Tok::Eof {
+        if p.eat_kw("struct") {
+            prog.types.push(p.struct_def()?);
+        } else if p.eat_kw("enum") {
+            prog.types.push(p.enum_def()?);
+        } else if p.eat_kw("impl") {
+            let trait_name = p.name()?;
+            p.expect_kw("for")?;
+            let type_name = p.name()?;
+            p.expect(";")?;
+            prog.trait_impls.push(TraitImpl { trait_name, type_name });
+        } else if p.eat_kw("fn") {
+            prog.funcs.push(p.func()?);
+        } else {
+            return Err(p.error("expected `struct`, `enum`, `impl` or `fn`"));
+        }
+    }
+    Ok(prog)
+}
+
+struct Parser<'s> {
+    toks: Vec<Spanned>,
+    pos: usize,
+    syms: &'s mut Symbols,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> &Tok {
+        &self.toks[self.pos].tok
+    }
+
+    fn peek_at(&self, n: usize) -> &Tok {
+        let i = (self.pos + n).min(self.toks.len() - 1);
+        &self.toks[i].tok
+    }
+
+    fn bump(&mut self) -> Tok {
+        let tok = self.toks[self.pos].tok.clone();
+        if tok != Tok::Eof {
+            self.pos += 1;
+        }
+        tok
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        self.error_at(self.pos, message.to_string())
+    }
+
+    fn error_at(&self, pos: usize, message: String) -> ParseError {
+        let t = &self.toks[pos];
+        let found = match &t.tok {
+            Tok::Ident(s) => format!("`{s}`"),
+            Tok::Quoted(s) => format!("`{s}`"),
+            Tok::Local(n) => format!("`_{n}`"),
+            Tok::Int(n) => format!("`{n}`"),
+            Tok::Float(f) => format!("`{f:?}`"),
+            Tok::Str(s) => format!("{s:?}"),
+            Tok::Punct(p) => format!("`{p}`"),
+            Tok::Eof => "the end of the input".to_string(),
+        };
+        ParseError { line: t.line, col: t.col, message: format!("{message}, found {found}") }
+    }
+
+    fn is(&self, punct: &str) -> bool {
+        matches!(self.peek(), Tok::Punct(p) if *p == punct)
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        let hit = self.is(punct);
+        if hit {
+            self.pos += 1;
+        }
+        hit
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<(), ParseError> {
+        match self.eat(punct) {
+            true => Ok(()),
+            false => Err(self.error(&format!("expected `{punct}`"))),
+        }
+    }
+
+    /// Expect a closing `>`, splitting a `>>` that closes two generic lists.
+    fn expect_close_angle(&mut self) -> Result<(), ParseError> {
+        if self.is(">>") {
+            self.toks[self.pos].tok = Tok::Punct(">");
+            self.toks[self.pos].col += 1;
+            return Ok(());
+        }
+        self.expect(">")
+    }
+
+    fn is_kw(&self, kw: &str) -> bool {
+        matches!(self.peek(), Tok::Ident(s) if s == kw)
+    }
+
+    fn eat_kw(&mut self, kw: &str) -> bool {
+        let hit = self.is_kw(kw);
+        if hit {
+            self.pos += 1;
+        }
+        hit
+    }
+
+    fn expect_kw(&mut self, kw: &str) -> Result<(), ParseError> {
+        match self.eat_kw(kw) {
+            true => Ok(()),
+            false => Err(self.error(&format!("expected `{kw}`"))),
+        }
+    }
+
+    fn name(&mut self) -> Result<Sym, ParseError> {
+        match self.peek().clone() {
+            Tok::Ident(s) if !KEYWORDS.contains(&s.as_str()) => {
+                self.pos += 1;
+                Ok(self.syms.intern(&s))
+            }
+            Tok::Quoted(s) => {
+                self.pos += 1;
+                Ok(self.syms.intern(&s))
+            }
+            _ => Err(self.error("expected a name")),
+        }
+    }
+
+    fn number(&mut self) -> Result<u32, ParseError> {
+        match self.peek() {
+            Tok::Int(n) if *n <= u32::MAX as u128 => {
+                let n = *n as u32;
+                self.pos += 1;
+                Ok(n)
+            }
+            _ => Err(self.error("expected an index")),
+        }
+    }
+
+    fn local(&mut self) -> Result<LocalId, ParseError> {
+        match self.peek() {
+            Tok::Local(n) => {
+                let n = *n;
+                self.pos += 1;
+                Ok(LocalId(n))
+            }
+            _ => Err(self.error("expected a local `_N`")),
+        }
+    }
+
+    fn block_id(&mut self) -> Result<BlockId, ParseError> {
+        if let Tok::Ident(s) = self.peek() {
+            if is_block_name(s) {
+                let id = s[2..].parse().map_err(|_| self.error("block id out of range"))?;
+                self.pos += 1;
+                return Ok(BlockId(id));
+            }
+        }
+        Err(self.error("expected a block `bbN`"))
+    }
+
+    /// A comma-separated list closed by `close`, whose opening token was eaten.
+    fn list<T>(
+        &mut self,
+        close: &str,
+        mut item: impl FnMut(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<Vec<T>, ParseError> {
+        let mut items = Vec::new();
+        while !self.eat(close) {
+            items.push(item(self)?);
+            if !self.eat(",") {
+                self.expect(close)?;
+                break;
+            }
+        }
+        Ok(items)
+    }
+
+    fn generics(&mut self) -> Result<Vec<Sym>, ParseError> {
+        match self.eat("<") {
+            true => self.list(">", Self::name),
+            false => Ok(Vec::new()),
+        }
+    }
+
+    fn struct_def(&mut self) -> Result<TypeDef, ParseError> {
+        let name = self.name()?;
+        let type_params = self.generics()?;
+        self.expect("{")?;
+        let fields = self.list("}", |p| {
+            let name = p.name()?;
+            p.expect(":")?;
+            Ok(FieldDef { name, ty: p.ty()? })
+        })?;
+        Ok(TypeDef::Struct { name, type_params, fields })
+    }
+
+    fn enum_def(&mut self) -> Result<TypeDef, ParseError> {
+        let name = self.name()?;
+        let type_params = self.generics()?;
+        self.expect("{")?;
+        let variants = self.list("}", |p| {
+            let name = p.name()?;
+            let fields = match p.eat("(") {
+                true => p.list(")", Self::ty)?,
+                false => Vec::new(),
+            };
+            Ok(VariantDef { name, fields })
+        })?;
+        Ok(TypeDef::Enum { name, type_params, variants })
+    }
+
+    fn ty(&mut self) -> Result<Ty, ParseError> {
+        if self.eat("&&") {
+            let mutable = self.eat_kw("mut");
+            let inner = Box::new(self.ty()?);
+            return Ok(Ty::Ref { mutable: false, inner: Box::new(Ty::Ref { mutable, inner }) });
+        }
+        if self.eat("&") {
+            let mutable = self.eat_kw("mut");
+            return Ok(Ty::Ref { mutable, inner: Box::new(self.ty()?) });
+        }
+        if self.eat("!") {
+            return Ok(Ty::Never);
+        }
+        if self.eat("$") {
+            return Ok(Ty::Param(self.name()?));
+        }
+        if self.eat("(") {
+            if self.eat(")") {
+                return Ok(Ty::Unit);
+            }
+            let first = self.ty()?;
+            if self.eat(")") {
+                return Err(self.error_at(self.pos - 1, "a one-element tuple is `(T,)`".into()));
+            }
+            self.expect(",")?;
+            let mut items = vec![first];
+            items.extend(self.list(")", Self::ty)?);
+            return Ok(Ty::Tuple(items));
+        }
+        if self.eat("[") {
+            let item = Box::new(self.ty()?);
+            if self.eat(";") {
+                let Tok::Int(len) = self.bump() else {
+                    return Err(self.error_at(self.pos - 1, "expected an array length".into()));
+                };
+                self.expect("]")?;
+                return Ok(Ty::Array(item, len as usize));
+            }
+            self.expect("]")?;
+            return Ok(Ty::Vec(item));
+        }
+        if self.eat_kw("fn") {
+            self.expect("(")?;
+            let params = self.list(")", Self::ty)?;
+            self.expect("->")?;
+            return Ok(Ty::Fn(params, Box::new(self.ty()?)));
+        }
+        if self.eat_kw("sized") {
+            return match self.bump() {
+                Tok::Ident(s) if s == "i64" => Ok(Ty::IntN(IntTy { signed: true, bits: 64 })),
+                _ => Err(self.error_at(self.pos - 1, "expected `i64` after `sized`".into())),
+            };
+        }
+        if let Tok::Ident(word) = self.peek() {
+            let builtin = match word.as_str() {
+                "i64" => Some(Ty::Int),
+                "f64" => Some(Ty::Float),
+                "bool" => Some(Ty::Bool),
+                "String" => Some(Ty::Str),
+                w => int_ty(w).map(Ty::IntN),
+            };
+            if let Some(ty) = builtin {
+                self.pos += 1;
+                return Ok(ty);
+            }
+        }
+        let name = self.name().map_err(|_| self.error("expected a type"))?;
+        let args = match self.eat("<") {
+            true => {
+                let mut args = vec![self.ty()?];
+                while self.eat(",") {
+                    args.push(self.ty()?);
+                }
+                self.expect_close_angle()?;
+                args
+            }
+            false => Vec::new(),
+        };
+        Ok(Ty::Adt(name, args))
+    }
+
+    /// A type, or `_` for one the phase leaves unknown.
+    fn opt_ty<P: TextPhase>(&mut self) -> Result<P::Ty, ParseError> {
+        let at = self.pos;
+        let ty = match self.eat_kw("_") {
+            true => None,
+            false => Some(self.ty()?),
+        };
+        P::ty_from_text(ty).map_err(|m| self.error_at(at, m))
+    }
+
+    fn func<P: TextPhase>(&mut self) -> Result<Function<P>, ParseError> {
+        let name = self.name()?;
+        let type_params = self.generics()?;
+        self.expect("(")?;
+        let params = self.list(")", Self::local)?;
+        self.expect("->")?;
+        let ret = self.opt_ty::<P>()?;
+        self.expect("{")?;
+        let mut generic_bounds = Vec::new();
+        while self.eat_kw("bound") {
+            let param = self.name()?;
+            self.expect(":")?;
+            let mut bounds = Vec::new();
+            if !self.is(";") {
+                bounds.push(self.name()?);
+                while self.eat("+") {
+                    bounds.push(self.name()?);
+                }
+            }
+            self.expect(";")?;
+            generic_bounds.push((param, bounds));
+        }
+        let mut pre = Prop::True;
+        if self.eat_kw("requires") {
+            pre = self.prop()?;
+            self.expect(";")?;
+        }
+        let mut post = Prop::True;
+        if self.eat_kw("ensures") {
+            post = self.prop()?;
+            self.expect(";")?;
+        }
+        let mut locals = Vec::new();
+        while self.eat_kw("let") {
+            let mutable = self.eat_kw("mut");
+            let at = self.pos;
+            let id = self.local()?;
+            if id.0 as usize != locals.len() {
+                let msg = format!("locals are declared in order; expected `_{}`", locals.len());
+                return Err(self.error_at(at, msg));
+            }
+            let name = match self.is(":") {
+                true => None,
+                false => Some(self.name()?),
+            };
+            self.expect(":")?;
+            let ty = self.opt_ty::<P>()?;
+            self.expect(";")?;
+            locals.push(LocalDecl { name, mutable, ty });
+        }
+        let mut entry = None;
+        if self.eat_kw("entry") {
+            entry = Some(self.block_id()?);
+            self.expect(";")?;
+        }
+        let mut blocks = Vec::new();
+        while !self.eat("}") {
+            let id = self.block_id()?;
+            self.expect(":")?;
+            self.expect("{")?;
+            let mut stmts = Vec::new();
+            let term = loop {
+                if let Some(term) = self.terminator::<P>()? {
+                    break term;
+                }
+                stmts.push(self.stmt()?);
+                self.expect(";")?;
+            };
+            self.expect(";")?;
+            self.expect("}")?;
+            blocks.push(Block { id, stmts, term });
+        }
+        let Some(entry) = entry.or_else(|| blocks.first().map(|b: &Block<P>| b.id)) else {
+            return Err(self.error_at(self.pos - 1, "a function needs at least one block".into()));
+        };
+        Ok(Function {
+            name,
+            type_params,
+            generic_bounds,
+            params,
+            ret,
+            pre,
+            post,
+            locals,
+            blocks,
+            entry,
+        })
+    }
+
+    fn stmt(&mut self) -> Result<Stmt, ParseError> {
+        if self.eat_kw("eval") {
+            return Ok(Stmt::Eval(self.rvalue()?));
+        }
+        if self.eat_kw("assert") {
+            return Ok(Stmt::Assert(self.prop()?));
+        }
+        if self.eat_kw("assume") {
+            return Ok(Stmt::Assume(self.prop()?));
+        }
+        if self.eat_kw("invariant") {
+            return Ok(Stmt::Invariant(self.prop()?));
+        }
+        let place = self.place()?;
+        self.expect("=")?;
+        Ok(Stmt::Assign(place, self.rvalue()?))
+    }
+
+    /// The block's terminator, or `None` when the next line is a statement.
+    fn terminator<P: TextPhase>(&mut self) -> Result<Option<Terminator<P>>, ParseError> {
+        let term = if self.eat_kw("goto") {
+            Terminator::Goto(self.block_id()?)
+        } else if self.eat_kw("branch") {
+            let cond = self.operand()?;
+            self.expect(",")?;
+            let then_blk = self.block_id()?;
+            self.expect(",")?;
+            Terminator::Branch { cond, then_blk, else_blk: self.block_id()? }
+        } else if self.eat_kw("match") {
+            let scrutinee = self.operand()?;
+            self.expect("{")?;
+            let (mut arms, mut otherwise) = (Vec::new(), None);
+            while !self.eat("}") {
+                if otherwise.is_some() {
+                    return Err(self.error("expected `}` after the `_` arm"));
+                }
+                let variant = match self.eat_kw("_") {
+                    true => None,
+                    false => Some(self.number()?),
+                };
+                self.expect("=>")?;
+                let target = self.block_id()?;
+                match variant {
+                    Some(variant) => arms.push(MatchArm { variant, target }),
+                    None => otherwise = Some(target),
+                }
+                if !self.eat(",") {
+                    self.expect("}")?;
+                    break;
+                }
+            }
+            Terminator::Match { scrutinee, arms, otherwise }
+        } else if self.eat_kw("return") {
+            Terminator::Return(self.operand()?)
+        } else if self.eat_kw("panic") {
+            Terminator::Panic
+        } else if self.eat_kw("drop") {
+            let place = self.place()?;
+            self.expect("->")?;
+            let next = self.block_id()?;
+            let at = self.pos;
+            let strategy = match self.eat_kw("with") {
+                true => match self.bump() {
+                    Tok::Ident(s) if s.starts_with('d') && s[1..].parse::<u32>().is_ok() => {
+                        Some(s[1..].parse().expect("checked above"))
+                    }
+                    _ => return Err(self.error_at(self.pos - 1, "expected a strategy `dN`".into())),
+                },
+                false => None,
+            };
+            let strategy = P::strategy_from_text(strategy).map_err(|m| self.error_at(at, m))?;
+            Terminator::Drop { place, strategy, next }
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(term))
+    }
+
+    fn place(&mut self) -> Result<Place, ParseError> {
+        let local = self.local()?;
+        let mut proj = Vec::new();
+        loop {
+            if self.is(".") && self.peek_at(1) == &Tok::Punct("*") {
+                self.pos += 2;
+                proj.push(Proj::Deref);
+            } else if self.eat(".") {
+                proj.push(Proj::Field(self.number()?));
+            } else if self.eat("@") {
+                proj.push(Proj::Downcast(self.number()?));
+            } else if self.eat("[") {
+                proj.push(Proj::Index(self.operand()?));
+                self.expect("]")?;
+            } else {
+                return Ok(Place { local, proj });
+            }
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand, ParseError> {
+        if matches!(self.peek(), Tok::Local(_)) {
+            return Ok(Operand::Copy(self.place()?));
+        }
+        let negative = self.eat("-");
+        let c = match self.bump() {
+            Tok::Int(n) if negative => Const::Int(0i128.wrapping_sub(n as i128)),
+            Tok::Int(n) => Const::Int(n as i128),
+            Tok::Float(f) => Const::Float(if negative { -f } else { f }),
+            Tok::Ident(s) if s == "inf" => {
+                Const::Float(if negative { f64::NEG_INFINITY } else { f64::INFINITY })
+            }
+            Tok::Ident(s) if s == "NaN" && !negative => Const::Float(f64::NAN),
+            Tok::Str(s) if !negative => Const::Str(s),
+            Tok::Ident(s) if s == "true" && !negative => Const::Bool(true),
+            Tok::Ident(s) if s == "false" && !negative => Const::Bool(false),
+            Tok::Punct("(") if !negative => {
+                self.expect(")")?;
+                Const::Unit
+            }
+            _ => return Err(self.error_at(self.pos - 1, "expected an operand".into())),
+        };
+        Ok(Operand::Const(c))
+    }
+
+    fn operands(&mut self) -> Result<Vec<Operand>, ParseError> {
+        self.expect("(")?;
+        self.list(")", Self::operand)
+    }
+
+    fn binary(&mut self) -> Result<(Operand, Operand), ParseError> {
+        self.expect("(")?;
+        let a = self.operand()?;
+        self.expect(",")?;
+        let b = self.operand()?;
+        self.expect(")")?;
+        Ok((a, b))
+    }
+
+    fn rvalue(&mut self) -> Result<RValue, ParseError> {
+        if self.eat("&") {
+            let kind = if self.eat_kw("mut") { BorrowKind::Mut } else { BorrowKind::Shared };
+            return Ok(RValue::Ref(kind, self.place()?));
+        }
+        if let Tok::Ident(word) = self.peek().clone() {
+            if let Some(op) = bin_op_named(&word) {
+                self.pos += 1;
+                let (a, b) = self.binary()?;
+                return Ok(RValue::Bin(op, a, b));
+            }
+            let un = match word.as_str() {
+                "Neg" => Some(UnOp::Neg),
+                "Not" => Some(UnOp::Not),
+                _ => None,
+            };
+            if let Some(op) = un {
+                self.pos += 1;
+                self.expect("(")?;
+                let a = self.operand()?;
+                self.expect(")")?;
+                return Ok(RValue::Un(op, a));
+            }
+            let keyword = KEYWORDS.contains(&word.as_str());
+            if keyword {
+                self.pos += 1;
+            }
+            let rv = match word.as_str() {
+                _ if !keyword => None,
+                "wrapping" => {
+                    let at = self.pos;
+                    let op = match self.bump() {
+                        Tok::Ident(name) => bin_op_named(&name),
+                        _ => None,
+                    };
+                    let op = op.ok_or_else(|| self.error_at(at, "expected an operator".into()))?;
+                    let (a, b) = self.binary()?;
+                    Some(RValue::WrappingBin(op, a, b))
+                }
+                "len" => {
+                    self.expect("(")?;
+                    let v = self.operand()?;
+                    self.expect(")")?;
+                    Some(RValue::VecLen(v))
+                }
+                "push" => {
+                    let (v, x) = self.binary()?;
+                    Some(RValue::VecPush(v, x))
+                }
+                "call" => Some(RValue::Call(self.name()?, self.operands()?)),
+                "closure" => Some(RValue::Closure(self.name()?, self.operands()?)),
+                "call_closure" => Some(RValue::CallClosure(self.operand()?, self.operands()?)),
+                "struct" => {
+                    let name = self.name()?;
+                    Some(RValue::Aggregate(AggKind::Struct(name), self.operands()?))
+                }
+                "variant" => {
+                    let name = self.name()?;
+                    self.expect("#")?;
+                    let index = self.number()?;
+                    Some(RValue::Aggregate(AggKind::Variant(name, index), self.operands()?))
+                }
+                "tuple" => Some(RValue::Aggregate(AggKind::Tuple, self.operands()?)),
+                "array" => Some(RValue::Aggregate(AggKind::Array, self.operands()?)),
+                "vec" => Some(RValue::Aggregate(AggKind::Vec, self.operands()?)),
+                _ => {
+                    // A constant keyword (`true`, `inf`, ..) starts an operand.
+                    self.pos -= 1;
+                    None
+                }
+            };
+            if let Some(rv) = rv {
+                return Ok(rv);
+            }
+        }
+        let op = self.operand()?;
+        match self.eat_kw("as") {
+            true => Ok(RValue::Cast(op, self.ty()?)),
+            false => Ok(RValue::Use(op)),
+        }
+    }
+
+    fn prop(&mut self) -> Result<Prop, ParseError> {
+        if self.eat_kw("true") {
+            return Ok(Prop::True);
+        }
+        if self.eat_kw("false") {
+            return Ok(Prop::False);
+        }
+        if self.eat("{") {
+            let t = self.term()?;
+            self.expect("}")?;
+            return Ok(Prop::Holds(t));
+        }
+        if self.eat("!") {
+            return Ok(Prop::Not(Box::new(self.prop()?)));
+        }
+        for (kw, quant) in [("forall", Prop::Forall as fn(_, _) -> _), ("exists", Prop::Exists)] {
+            if self.eat_kw(kw) {
+                let x = self.name()?;
+                self.expect(".")?;
+                return Ok(quant(x, Box::new(self.prop()?)));
+            }
+        }
+        if self.eat("(") {
+            let a = Box::new(self.prop()?);
+            let at = self.pos;
+            let join = match self.bump() {
+                Tok::Punct("&&") => Prop::And as fn(_, _) -> _,
+                Tok::Punct("||") => Prop::Or,
+                Tok::Punct("==>") => Prop::Implies,
+                _ => return Err(self.error_at(at, "expected `&&`, `||` or `==>`".into())),
+            };
+            let b = Box::new(self.prop()?);
+            self.expect(")")?;
+            return Ok(join(a, b));
+        }
+        Err(self.error("expected a proposition"))
+    }
+
+    fn term(&mut self) -> Result<Term, ParseError> {
+        let mut t = self.term_atom()?;
+        while self.is(".") {
+            self.pos += 1;
+            t = Term::field(t, self.number()?);
+        }
+        Ok(t)
+    }
+
+    fn term_atom(&mut self) -> Result<Term, ParseError> {
+        if self.eat("-") {
+            if let Tok::Int(n) = self.peek() {
+                let n = *n;
+                self.pos += 1;
+                return Ok(Term::Int(0i128.wrapping_sub(n as i128)));
+            }
+            self.expect("(")?;
+            let t = self.term()?;
+            self.expect(")")?;
+            return Ok(Term::un(UnOp::Neg, t));
+        }
+        if self.eat("!") {
+            self.expect("(")?;
+            let t = self.term()?;
+            self.expect(")")?;
+            return Ok(Term::un(UnOp::Not, t));
+        }
+        if self.eat("(") {
+            let a = self.term()?;
+            let at = self.pos;
+            let op = match self.bump() {
+                Tok::Punct(p) => BIN_OPS.iter().find(|(.., s)| *s == p).map(|(op, ..)| *op),
+                _ => None,
+            };
+            let op = op.ok_or_else(|| self.error_at(at, "expected a binary operator".into()))?;
+            let b = self.term()?;
+            self.expect(")")?;
+            return Ok(Term::bin(op, a, b));
+        }
+        if let Tok::Int(n) = self.peek() {
+            let n = *n as i128;
+            self.pos += 1;
+            return Ok(Term::Int(n));
+        }
+        if self.eat_kw("true") {
+            return Ok(Term::Bool(true));
+        }
+        if self.eat_kw("false") {
+            return Ok(Term::Bool(false));
+        }
+        let name = self.name().map_err(|_| self.error("expected a term"))?;
+        match self.eat("(") {
+            true => Ok(Term::app(name, self.list(")", Self::term)?)),
+            false => Ok(Term::Var(name)),
+        }
+    }
+}
+
+fn bin_op_named(name: &str) -> Option<BinOp> {
+    BIN_OPS.iter().find(|(_, n, _)| *n == name).map(|(op, ..)| *op)
+}
+
+/// A sized integer type name (`i8`, `u32`, ..), except the default `i64`.
+fn int_ty(word: &str) -> Option<IntTy> {
+    let (signed, digits) = match word.split_at_checked(1)? {
+        ("i", d) => (true, d),
+        ("u", d) => (false, d),
+        _ => return None,
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) || word == "i64" {
+        return None;
+    }
+    digits.parse().ok().map(|bits| IntTy { signed, bits })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<P: TextPhase>(text: &str) -> String {
+        let mut syms = Symbols::new();
+        let prog: Program<P> = parse(text, &mut syms).unwrap_or_else(|e| panic!("{e}"));
+        let printed = print(&prog, &syms);
+        let again: Program<P> = parse(&printed, &mut syms).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(print(&again, &syms), printed);
+        printed
+    }
+
+    /// Every statement, rvalue and terminator form survives a print/parse cycle,
+    /// and the printer's layout is the canonical one this text is written in.
+    #[test]
+    fn every_form_round_trips() {
+        let text = "\
+struct Pair<T> { a: $T, b: [i64; 3] }
+enum Opt<T> { None, Some($T) }
+impl Show for Pair;
+
+fn every<T>(_0, _1) -> Opt<Opt<u8>> {
+    bound T: Show + Eq;
+    bound U: ;
+    requires {(n >= -3)};
+    ensures (!{f(x, result).2} ==> forall k. ({(k < -(1))} || exists j. false));
+    let mut _0 n: i64;
+    let _1 `true`: &&mut (bool,);
+    let _2: (String, f64, sized i64);
+    let _3: fn([u128], !) -> ();
+    let _4: _;
+    entry bb1;
+    bb0: {
+        _4 = Add(_0, -9);
+        _4 = wrapping Shl(_4, 170141183460469231731687303715884105727);
+        _4 = Neg(_4);
+        _2.1 = -0.0;
+        _2.1 = 1e300;
+        _2.1 = -inf;
+        _2.1 = NaN;
+        _2.0 = \"a\\\"b\\n\\u{7f}\";
+        _4 = len(_3);
+        _3 = push(_3, ());
+        eval call `Point::new`(_0, true);
+        _4 = closure lifted(_0);
+        _4 = call_closure _4(_1.*.0, _2[_0]);
+        _4 = struct Pair(_0, _1);
+        _4 = variant Opt#1(_4@1.0);
+        _4 = tuple();
+        _4 = array(1, 2);
+        _4 = vec();
+        _4 = &_2.0;
+        _4 = &mut _2;
+        _4 = -170141183460469231731687303715884105728 as u8;
+        assert {((a & b) >> 2)};
+        assume !true;
+        invariant {!(ok)};
+        branch _1, bb1, bb2;
+    }
+    bb1: {
+        match _4 { 0 => bb0, 1 => bb2, _ => bb3 };
+    }
+    bb2: {
+        drop _2 -> bb3;
+    }
+    bb3: {
+        goto bb4;
+    }
+    bb4: {
+        panic;
+    }
+    bb5: {
+        return _4;
+    }
+}
+";
+        assert_eq!(round_trip::<Parsed>(text), text);
+    }
+
+    #[test]
+    fn lowerable_drops_carry_their_strategy() {
+        let text = "\
+fn f(_0) -> i64 {
+    let _0: i64;
+    bb0: {
+        drop _0 -> bb1 with d2;
+    }
+    bb1: {
+        return _0;
+    }
+}
+";
+        assert_eq!(round_trip::<Lowerable>(text), text);
+        let mut syms = Symbols::new();
+        let err = parse::<Lowerable>(&text.replace(" with d2", ""), &mut syms).err().unwrap();
+        assert!(err.message.contains("needs its strategy"), "{err}");
+        let err = parse::<Parsed>(text, &mut syms).err().unwrap();
+        assert!(err.message.contains("no strategy"), "{err}");
+    }
+
+    #[test]
+    fn errors_carry_line_and_column() {
+        let mut syms = Symbols::new();
+        let err = parse::<Parsed>("fn f() -> i64 {\n    let _1: i64;\n", &mut syms).err().unwrap();
+        assert_eq!((err.line, err.col), (2, 9));
+        assert_eq!(err.message, "locals are declared in order; expected `_0`, found `_1`");
+        let err = parse::<Typed>("fn f() -> _ {", &mut syms).err().unwrap();
+        assert_eq!((err.line, err.col), (1, 11));
+        let text = "fn f() -> i64 {\n  bb0: {\n    _0 = ;";
+        let err = parse::<Parsed>(text, &mut syms).err().unwrap();
+        assert_eq!(err.to_string(), "3:10: expected an operand, found `;`");
+    }
+}
@@ -127,6 +127,26 @@ impl Metas {
         self.generation
     }
 
+    /// The representative of `?m`: the metavariable reached by following solutions
+    /// that are themselves bare metavariables, as far as they go. Every meta on the
+    /// way is re-pointed straight at it (path compression), so a chain built by
+    /// solving `?0 := ?1`, `?1 := ?2`, … is walked once rather than on every
+    /// lookup. Re-pointing changes no meta's meaning, so the generation is kept.
+    pub fn resolve(&mut self, m: u32) -> u32 {
+        let mut path = Vec::new();
+        let mut root = m;
+        while let Some(Some(Term::Meta(next))) = self.solutions.get(root as usize) {
+            path.push(root);
+            root = *next;
+        }
+        // The last hop already points at `root`.
+        path.pop();
+        for k in path {
+            self.solutions[k as usize] = Some(Term::Meta(root));
+        }
+        root
+    }
+
     fn solve_raw(&mut self, m: u32, t: Term) {
         self.solutions[m as usize] = Some(t);
         self.generation += 1;
@@ -303,6 +323,12 @@ pub fn unify(
     t2: &Term,
 ) -> Result<(), String> {
     let depth = ctx.len();
+    // Shortcut long meta-to-meta chains before the normalizer walks them.
+    for t in [t1, t2] {
+        if let Term::Meta(m) = t {
+            metas.resolve(*m);
+        }
+    }
     let (a, b) = {
         let nbe = Nbe::with_metas(env, &metas.solutions);
         (nbe.normalize_open(depth, t1), nbe.normalize_open(depth, t2))
@@ -621,7 +647,10 @@ fn invert(meta: u32, image: &[usize], t: &Term, depth: usize) -> Result<Term, St
 /// re-checks the final zonked term).
 fn solve(env: &Env, metas: &mut Metas, ctx: &LocalCtx, m: u32, t: &Term) -> Result<(), String> {
     if occurs(m, t) {
-        return Err(format!("occurs check: ?{m} would be cyclic"));
+        return Err(format!(
+            "occurs check: cannot construct the infinite term ?{m} := {}",
+            t.pretty()
+        ));
     }
     metas.solve_raw(m, t.clone());
     if let Some((dm, tym)) = metas.meta_type(m) {
@@ -701,7 +730,42 @@ mod tests {
         let m = metas.fresh();
         // ?m =?= f ?m
         let rhs = Term::app(c("f"), m.clone());
-        assert!(unify(&env, &mut metas, &LocalCtx::new(), &m, &rhs).is_err());
+        let err = unify(&env, &mut metas, &LocalCtx::new(), &m, &rhs).unwrap_err();
+        assert!(err.contains("cannot construct the infinite term ?0 := "), "{err}");
+        assert!(!metas.is_solved(0), "a rejected solution is not recorded");
+    }
+
+    /// A long chain of metavariables, each solved to the next, is resolved with path
+    /// compression: every later lookup reaches the end of the chain in one hop.
+    #[test]
+    fn meta_chains_are_path_compressed() {
+        const LEN: u32 = 1000;
+        let env = Env::new();
+        let mut metas = Metas::new();
+        let ms: Vec<Term> = (0..=LEN).map(|_| metas.fresh()).collect();
+        // `?0 =?= ?i` for each `i` in turn solves `?(i-1) := ?i`: without compression
+        // the walk from `?0` grows by one hop per step, so the loop is quadratic.
+        for m in &ms[1..] {
+            unify(&env, &mut metas, &LocalCtx::new(), &ms[0], m).unwrap();
+        }
+        // Each step re-pointed `?0` at the then-current end, one short of `?LEN`.
+        assert!(matches!(metas.solutions()[0], Some(Term::Meta(k)) if k == LEN - 1));
+        // Resolving from the start of the untouched tail compresses all of it.
+        assert_eq!((metas.resolve(0), metas.resolve(1)), (LEN, LEN));
+        for k in 0..LEN {
+            assert!(
+                matches!(metas.solutions()[k as usize], Some(Term::Meta(r)) if r == LEN),
+                "?{k} is not compressed to ?{LEN}"
+            );
+        }
+        unify(&env, &mut metas, &LocalCtx::new(), &ms[0], &c("Nat")).unwrap();
+        assert!(ms.iter().all(|m| metas.zonk(m) == Ok(c("Nat"))));
+
+        // A cycle through a chain is still caught: `?a := ?b`, then `?b =?= f ?a`.
+        let (a, b) = (metas.fresh(), metas.fresh());
+        unify(&env, &mut metas, &LocalCtx::new(), &a, &b).unwrap();
+        let err = unify(&env, &mut metas, &LocalCtx::new(), &b, &Term::app(c("f"), a)).unwrap_err();
+        assert!(err.contains("infinite term"), "{err}");
     }
 
     /// An unsolved metavariable is reported by zonk, not silently accepted.
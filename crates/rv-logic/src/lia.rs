@@ -96,6 +96,11 @@ impl Rat {
     /// Exact rational addition (checked), computed over [`I256`] to avoid spurious
     /// intermediate overflow (see the struct docs).
     pub fn checked_add(self, other: Rat) -> Option<Rat> {
+        // Two integers: the sum is already reduced, and it fits in `i128` exactly
+        // when the wide computation's would.
+        if self.den == 1 && other.den == 1 {
+            return self.num.checked_add(other.num).map(Rat::from_int);
+        }
         // a/b + c/d = (a*d + c*b) / (b*d)
         let (a, b) = (I256::from_i128(self.num), I256::from_i128(self.den));
         let (c, d) = (I256::from_i128(other.num), I256::from_i128(other.den));
@@ -105,6 +110,9 @@ impl Rat {
     }
     /// Exact rational multiplication (checked), computed over [`I256`] (see struct docs).
     pub fn checked_mul(self, other: Rat) -> Option<Rat> {
+        if self.den == 1 && other.den == 1 {
+            return self.num.checked_mul(other.num).map(Rat::from_int);
+        }
         let n = I256::from_i128(self.num).checked_mul(I256::from_i128(other.num))?;
         let d = I256::from_i128(self.den).checked_mul(I256::from_i128(other.den))?;
         Rat::new_i256(n, d)
@@ -732,11 +740,8 @@ fn dnf(f: &Nnf, max: usize) -> Option<Vec<Vec<Literal>>> {
 /// solver. The `max` cap must match the solver's so the enumeration is identical.
 /// Returns `None` on overflow (then a certificate cannot be bound and is rejected).
 pub fn disjuncts_of(ctx: &rv_core::Prop, goal: &rv_core::Prop, max: usize) -> Option<Vec<Vec<Literal>>> {
-    let formula = rv_core::Prop::And(
-        Box::new(ctx.clone()),
-        Box::new(rv_core::Prop::Not(Box::new(goal.clone()))),
-    );
-    let nnf = to_nnf(&formula, false);
+    // `to_nnf` of `ctx ∧ ¬goal`, without copying either side into that formula.
+    let nnf = Nnf::And(Box::new(to_nnf(ctx, false)), Box::new(to_nnf(goal, true)));
     dnf(&nnf, max)
 }
 
@@ -5,19 +5,27 @@
 //! statements over temporary locals; structured statements compile to branches
 //! and gotos between freshly allocated blocks.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use rv_core::{BinOp, Sym, Symbols};
+use rv_core::{BinOp, Sym, Symbols, UnOp};
+use rv_diagnostics::{close_matches, did_you_mean};
+use rv_diagnostics::{
+    E0001, E0004, E0034, E0061, E0268, E0277, E0308, E0426, E0433, E0571, E0609,
+};
 use rv_ir::{
-    AggKind, Block, BlockId, BorrowKind, Const, LocalDecl, LocalId, MatchArm as IrMatchArm, Operand,
-    Parsed, Place, Proj, RValue, Stmt as IrStmt, Terminator,
+    AggKind, Block, BlockId, BorrowKind, Const, LineSpan, LocalDecl, LocalId, MatchArm as IrMatchArm,
+    Operand, Parsed, Place, Proj, RValue, Site, Stmt as IrStmt, Terminator, ASSERT_EQ, ASSERT_NE,
+    FLOAT_BITS, STR_CONCAT, TO_STRING,
 };
 use rv_syntax::ast::{
     Block as AstBlock, Expr, MatchArm as AstMatchArm, PatBind, Pattern, Stmt as AstStmt, Ty as AstTy,
 };
 
 use crate::spec;
-use crate::types::Types;
+use crate::types::{TypeScope, Types};
+
+/// What a finished [`FnBuilder`] hands back: the body's locals and blocks.
+pub type FnParts = (Vec<LocalDecl<Parsed>>, Vec<Block<Parsed>>);
 
 pub struct FnBuilder<'a> {
     locals: Vec<LocalDecl<Parsed>>,
@@ -37,10 +45,26 @@ pub struct FnBuilder<'a> {
     names: HashMap<Sym, LocalId>,
     /// Module-level type registry: struct fields, enum variants, ADT kinds.
     types: &'a Types,
+    /// The type parameters in scope in this body, for its annotations, casts and
+    /// explicit type arguments.
+    type_scope: TypeScope,
+    /// The declared return type (`()` when unannotated), which `?` must be able
+    /// to return its failure as. `None` in a closure, whose return is inferred.
+    ret: Option<AstTy>,
+    /// The concrete ADT an `impl Trait` return type stands for, and the line of
+    /// the first `return` that fixed it.
+    opaque: Option<(Sym, u32)>,
     /// Best-effort tracking of a local's ADT (struct/enum) name, learned from
     /// parameter types and from struct-literal / enum-ctor initializers. Used to
     /// resolve field access (`s.f`) and the variant payloads bound in `match`.
     local_adt: HashMap<LocalId, Sym>,
+    /// Best-effort primitive type of an unannotated local, learned from its
+    /// initializer (`let x = -5;`), so a method can be called on it.
+    local_prim: HashMap<LocalId, rv_core::Ty>,
+    /// Best-effort reference depth of a local bound, unannotated, from a borrow
+    /// (`let r = &x;`). Annotated locals and parameters carry theirs in their
+    /// declared type instead; see [`FnBuilder::ref_depth`].
+    local_ref_depth: HashMap<LocalId, usize>,
     /// Top-level functions lifted out of closure literals encountered while lowering
     /// this body (lambda lifting). Drained by the caller into the program's function list.
     lifted: Vec<rv_ir::Function<Parsed>>,
@@ -49,10 +73,36 @@ pub struct FnBuilder<'a> {
     /// Monotonic counter for ghost locals that carry a value while its
     /// refinement-alias contract is checked.
     refinement_ctr: u32,
+    /// Source lines of the statement being lowered, stamped onto every IR
+    /// statement/terminator it produces.
+    cur_line: Option<LineSpan>,
+    /// (block, statement index or `None` for the terminator) -> source lines.
+    lines: HashMap<(BlockId, Option<u32>), LineSpan>,
+    /// Line entries of the lifted closures, already keyed by their own names.
+    lifted_lines: Vec<(Site, LineSpan)>,
+    /// The loops enclosing the statement being lowered, innermost last; the
+    /// targets of `break` / `continue`.
+    loops: Vec<LoopFrame>,
+    /// Bindings declared without an initializer and not yet assigned: their first
+    /// assignment initializes them rather than making them mutable.
+    deferred: HashSet<LocalId>,
+}
+
+/// One enclosing loop, as seen by the `break` / `continue` statements inside it.
+struct LoopFrame {
+    label: Option<Sym>,
+    /// Where `continue` jumps: the loop header (which re-tests a `while` condition).
+    header: BlockId,
+    /// Where `break` jumps: the block following the loop.
+    exit: BlockId,
+    /// The local holding a `loop`'s value; `None` for a `while` (always `()`).
+    result: Option<LocalId>,
 }
 
 impl<'a> FnBuilder<'a> {
-    pub fn new(types: &'a Types) -> Self {
+    /// A builder for a body in which the type parameters of `type_scope` are
+    /// bound.
+    pub(crate) fn new(types: &'a Types, type_scope: TypeScope) -> Self {
         FnBuilder {
             locals: Vec::new(),
             blocks: Vec::new(),
@@ -62,22 +112,71 @@ impl<'a> FnBuilder<'a> {
             diverged: false,
             names: HashMap::new(),
             types,
+            type_scope,
+            ret: None,
+            opaque: None,
             local_adt: HashMap::new(),
+            local_prim: HashMap::new(),
+            local_ref_depth: HashMap::new(),
             lifted: Vec::new(),
             closure_ctr: 0,
             refinement_ctr: 0,
+            cur_line: None,
+            lines: HashMap::new(),
+            lifted_lines: Vec::new(),
+            loops: Vec::new(),
+            deferred: HashSet::new(),
         }
     }
 
+    /// Declare this body's return type, for checking its `?` operators.
+    pub fn set_ret(&mut self, ret: Option<&AstTy>) {
+        self.ret = Some(ret.cloned().unwrap_or(AstTy::Unit));
+    }
+
+    /// The concrete type behind an `impl Trait` return type, once a `return` in
+    /// the body has fixed it.
+    pub fn opaque_ret(&self) -> Option<Sym> {
+        self.opaque.map(|(adt, _)| adt)
+    }
+
     /// Drain the functions lifted out of closure literals in this body.
     pub fn take_lifted(&mut self) -> Vec<rv_ir::Function<Parsed>> {
         std::mem::take(&mut self.lifted)
     }
 
-    /// Record that local `id` holds a value of ADT type `adt` (best-effort).
+    /// Drain the recorded source lines, keyed under `func` (this body's name),
+    /// together with those of any closures lifted out of it.
+    pub fn take_lines(&mut self, func: Sym) -> Vec<(Site, LineSpan)> {
+        let mut out = std::mem::take(&mut self.lifted_lines);
+        out.extend(
+            self.lines
+                .drain()
+                .map(|((block, stmt), line)| (Site { func, block, stmt }, line)),
+        );
+        out
+    }
+
+    /// Record the current statement's span for `(block, stmt)`, if known.
+    fn record_line(&mut self, block: BlockId, stmt: Option<u32>) {
+        if let Some(line) = self.cur_line {
+            self.lines.insert((block, stmt), line);
+        }
+    }
+
+    /// Record that local `id` holds a value of ADT type `adt`, or a reference to
+    /// one (best-effort). A declared type already naming `adt`, behind any
+    /// references, is kept: it may carry type arguments.
     pub fn set_local_adt(&mut self, id: LocalId, adt: Sym) {
         self.local_adt.insert(id, adt);
-        self.set_local_ty(id, rv_core::Ty::Adt(adt));
+        let ty = &mut self.locals[id.0 as usize].ty;
+        let mut pointee = ty.as_ref();
+        while let Some(rv_core::Ty::Ref { inner, .. }) = pointee {
+            pointee = Some(inner);
+        }
+        if !matches!(pointee, Some(rv_core::Ty::Adt(a, _)) if *a == adt) {
+            *ty = Some(rv_core::Ty::Adt(adt, vec![]));
+        }
     }
 
     /// Preserve a source-level declaration on the Parsed IR local. Inference uses
@@ -112,9 +211,33 @@ impl<'a> FnBuilder<'a> {
         spec::lower_prop(e, syms, &ctx)
     }
 
-    /// Consume the builder, yielding its locals and blocks.
-    pub fn into_parts(self) -> (Vec<LocalDecl<Parsed>>, Vec<Block<Parsed>>) {
-        (self.locals, self.blocks)
+    /// Consume the builder, yielding its locals and blocks once the CFG is closed:
+    /// the current block must be terminated and every jump must land on a block
+    /// that was built. A block that was started (or jumped to) but never finished
+    /// would otherwise lose its statements without a trace; the error lists the
+    /// offending block ids. Reserved ids nothing jumps to (the dead continuations
+    /// after a `return`) are not blocks and are not reported.
+    pub fn finish(self) -> Result<FnParts, String> {
+        let built: HashSet<BlockId> = self.blocks.iter().map(|b| b.id).collect();
+        let mut open: Vec<BlockId> = self
+            .blocks
+            .iter()
+            .flat_map(|b| b.term.successors())
+            .filter(|id| !built.contains(id))
+            .collect();
+        if !self.diverged {
+            open.push(self.cur_id);
+        }
+        if !open.is_empty() {
+            open.sort_by_key(|id| id.0);
+            open.dedup();
+            let ids: Vec<String> = open.iter().map(|id| id.0.to_string()).collect();
+            return Err(format!(
+                "internal error: lowering left block(s) {} without a terminator",
+                ids.join(", ")
+            ));
+        }
+        Ok((self.locals, self.blocks))
     }
 
     // ---- local / block / name management -----------------------------------
@@ -122,10 +245,15 @@ impl<'a> FnBuilder<'a> {
     /// Allocate a fresh local with an optional source name (type `()` in Parsed).
     pub fn new_local(&mut self, name: Option<Sym>) -> LocalId {
         let id = LocalId(self.locals.len() as u32);
-        self.locals.push(LocalDecl { name, ty: None });
+        self.locals.push(LocalDecl { name, mutable: false, ty: None });
         id
     }
 
+    /// Mark `id` as written after its first assignment.
+    pub fn mark_mutable(&mut self, id: LocalId) {
+        self.locals[id.0 as usize].mutable = true;
+    }
+
     /// Record that source name `name` currently refers to local `id`.
     pub fn bind(&mut self, name: Sym, id: LocalId) {
         self.names.insert(name, id);
@@ -141,6 +269,7 @@ impl<'a> FnBuilder<'a> {
     /// Append a statement to the current block (dropped if it has diverged).
     fn push_stmt(&mut self, s: IrStmt) {
         if !self.diverged {
+            self.record_line(self.cur_id, Some(self.cur_stmts.len() as u32));
             self.cur_stmts.push(s);
         }
     }
@@ -148,6 +277,7 @@ impl<'a> FnBuilder<'a> {
     /// Close the current block with `term`, then begin building block `next`.
     fn finish_block(&mut self, term: Terminator<Parsed>, next: BlockId) {
         let stmts = std::mem::take(&mut self.cur_stmts);
+        self.record_line(self.cur_id, None);
         self.blocks.push(Block { id: self.cur_id, stmts, term });
         self.cur_id = next;
         self.diverged = false;
@@ -155,9 +285,10 @@ impl<'a> FnBuilder<'a> {
 
     /// If the current (final) block never diverged, terminate it with a unit
     /// return so every path ends in `Return`.
-    pub fn finish_with_default_return(&mut self) {
+    fn finish_with_default_return(&mut self) {
         if !self.diverged {
             let stmts = std::mem::take(&mut self.cur_stmts);
+            self.record_line(self.cur_id, None);
             self.blocks.push(Block {
                 id: self.cur_id,
                 stmts,
@@ -171,6 +302,7 @@ impl<'a> FnBuilder<'a> {
     pub fn return_local(&mut self, id: LocalId) {
         if !self.diverged {
             let stmts = std::mem::take(&mut self.cur_stmts);
+            self.record_line(self.cur_id, None);
             self.blocks.push(Block {
                 id: self.cur_id,
                 stmts,
@@ -182,48 +314,87 @@ impl<'a> FnBuilder<'a> {
 
     // ---- statement lowering ------------------------------------------------
 
-    /// Lower a syntactic block's statements into the CFG.
+    /// Lower a function body: its statements, then a unit return if control falls
+    /// off the end. That return belongs to the body as a whole.
+    pub fn lower_body(&mut self, body: &AstBlock, syms: &mut Symbols) -> Result<(), String> {
+        self.cur_line = Some(body.span);
+        self.lower_block(body, syms)?;
+        self.finish_with_default_return();
+        Ok(())
+    }
+
+    /// Lower a syntactic block's statements into the CFG. Whatever the enclosing
+    /// statement emits after the block is stamped with its own span again, not
+    /// with that of the block's last statement.
     pub fn lower_block(&mut self, block: &AstBlock, syms: &mut Symbols) -> Result<(), String> {
-        for stmt in &block.stmts {
+        let outer = self.cur_line;
+        for (stmt, &span) in block.stmts.iter().zip(&block.spans) {
             // Once a block diverged via `return`, the rest of this syntactic
             // block is unreachable; stop emitting it.
             if self.diverged {
                 break;
             }
-            self.lower_stmt(stmt, syms)?;
+            self.cur_line = Some(span);
+            // The innermost statement names the line; enclosing blocks keep it.
+            // Statements nest as deep as blocks do, so each grows the stack.
+            let lowered = rv_arena::stack::grow(|| self.lower_stmt(stmt, syms));
+            lowered.map_err(|e| match e.starts_with("line ") {
+                true => e,
+                false => format!("line {}: {e}", span.start),
+            })?;
         }
+        self.cur_line = outer;
         Ok(())
     }
 
     fn lower_stmt(&mut self, stmt: &AstStmt, syms: &mut Symbols) -> Result<(), String> {
         match stmt {
-            AstStmt::Let { name, ty, init } => {
+            AstStmt::Let { name, mutable, ty, init } => {
                 let dst = self.new_local(Some(*name));
+                if *mutable {
+                    self.mark_mutable(dst);
+                }
+                if init.is_none() {
+                    self.deferred.insert(dst);
+                }
                 if let Some(ty) = ty {
-                    self.set_local_ty(dst, self.types.resolve_ty(ty, &std::collections::HashSet::new()));
+                    self.set_local_ty(dst, self.types.resolve_ty(ty, &self.type_scope));
+                }
+                match init {
+                    Some(init) => self.lower_into_local(dst, init, syms)?,
+                    // A refinement is checked where the binding is initialized, so
+                    // a refined binding cannot defer its value to later assignments.
+                    None => {
+                        if let Some(AstTy::Adt(alias)) = ty {
+                            if self.types.alias_refinement(*alias).is_some() {
+                                return Err(format!(
+                                    "`{}` has refined type `{}` and needs an initializer",
+                                    syms.resolve(*name),
+                                    syms.resolve(*alias)
+                                ));
+                            }
+                        }
+                    }
                 }
-                self.lower_into_local(dst, init, syms)?;
                 // An explicit ADT annotation (`let r: Widget = ..`) is authoritative
                 // for later field access / match / method resolution — even when the
                 // initializer is opaque to `adt_of_expr` (e.g. a generic call whose
                 // erased return type is nonetheless known concretely here). A
                 // refinement *alias* names a scalar base, not an ADT, so it is
                 // excluded (its own handling runs below).
-                if let Some(AstTy::Adt(name)) = ty {
-                    if self.types.is_adt(*name) {
+                if let Some(AstTy::Adt(name)) = ty.as_ref().map(AstTy::pointee) {
+                    if self.types.is_adt(*name) && !self.type_scope.contains(*name) {
                         self.set_local_adt(dst, *name);
                     }
                 }
                 // Best-effort: propagate a known ADT type from the initializer so
                 // later field access / match on this local can resolve.
-                if let Some(adt) = self.adt_of_expr(init) {
-                    self.set_local_adt(dst, adt);
-                }
+                let init_adt = init.as_ref().and_then(|init| self.note_value(dst, init));
                 self.bind(*name, dst);
-                if let Some(AstTy::Adt(alias)) = ty {
+                if let (Some(AstTy::Adt(alias)), Some(_)) = (ty, init) {
                     self.lower_alias_local_refinement(*name, *alias, syms)?;
                 }
-                if let Some(adt) = self.adt_of_expr(init) {
+                if let Some(adt) = init_adt {
                     self.assume_struct_field_refinements(*name, adt, syms)?;
                 }
                 Ok(())
@@ -232,7 +403,11 @@ impl<'a> FnBuilder<'a> {
                 let dst = *self
                     .names
                     .get(name)
-                    .ok_or_else(|| format!("assignment to unbound variable `{}`", syms.resolve(*name)))?;
+                    .ok_or_else(|| self.unbound("assignment to", *name, syms))?;
+                // Raven needs no `mut` to reassign; a second write marks the binding.
+                if !self.deferred.remove(&dst) {
+                    self.mark_mutable(dst);
+                }
                 self.lower_into_local(dst, value, syms)
             }
             // `*place = value;` — store through a reference. The target is the
@@ -244,18 +419,7 @@ impl<'a> FnBuilder<'a> {
                 self.push_stmt(IrStmt::Assign(dst_place, rvalue));
                 Ok(())
             }
-            AstStmt::Return(opt) => {
-                let operand = match opt {
-                    Some(e) => self.lower_operand(e, syms)?,
-                    None => Operand::Const(Const::Unit),
-                };
-                // A return needs no successor; route to a dummy fresh id that is
-                // never built (the block list simply won't contain it).
-                let dead = self.fresh_block_id();
-                self.finish_block(Terminator::Return(operand), dead);
-                self.diverged = true;
-                Ok(())
-            }
+            AstStmt::Return(opt) => self.lower_return(opt.as_ref(), syms),
             AstStmt::Assert(e) => {
                 let prop = self.lower_spec_prop(e, syms)?;
                 self.push_stmt(IrStmt::Assert(prop));
@@ -281,11 +445,26 @@ impl<'a> FnBuilder<'a> {
             }
             AstStmt::Expr(e) => {
                 // Evaluate for side effects. Pure expressions are simply dropped;
-                // calls (the only effectful form) get assigned to a throwaway temp.
+                // calls (the only effectful form) are evaluated with their result
+                // discarded, so they need no destination local.
                 match e {
-                    Expr::Call { .. } => {
-                        let tmp = self.new_local(None);
-                        self.lower_into_local(tmp, e, syms)?;
+                    Expr::Call { .. } | Expr::MethodCall { .. } => {
+                        let rvalue = self.lower_rvalue(e, syms)?;
+                        self.push_stmt(IrStmt::Eval(rvalue));
+                    }
+                    Expr::EnumCtor { enum_name, variant, args }
+                        if self.types.is_trait(*enum_name)
+                            || self.assoc_fn(*enum_name, *variant, args).is_some() =>
+                    {
+                        let rvalue = self.lower_rvalue(e, syms)?;
+                        self.push_stmt(IrStmt::Eval(rvalue));
+                    }
+                    // A loop statement: its value (if any) is discarded.
+                    Expr::Loop { label, body } => {
+                        self.lower_loop(*label, body, syms)?;
+                    }
+                    Expr::If { .. } => {
+                        self.lower_operand(e, syms)?;
                     }
                     _ => {
                         // No side effects to preserve; nothing to emit.
@@ -296,8 +475,37 @@ impl<'a> FnBuilder<'a> {
             AstStmt::If { cond, then_blk, else_blk } => {
                 self.lower_if(cond, then_blk, else_blk.as_ref(), syms)
             }
-            AstStmt::While { cond, invariants, body } => {
-                self.lower_while(cond, invariants, body, syms)
+            AstStmt::While { label, cond, invariants, body } => {
+                self.lower_while(*label, cond, invariants, body, syms)
+            }
+            AstStmt::Break { label, value } => {
+                let frame = self.loop_frame(*label, "break", syms)?;
+                let (exit, result) = (frame.exit, frame.result);
+                match (value, result) {
+                    (Some(v), Some(dst)) => self.lower_into_local(dst, v, syms)?,
+                    (None, Some(dst)) => self.push_stmt(IrStmt::Assign(
+                        Place::local(dst),
+                        RValue::Use(Operand::Const(Const::Unit)),
+                    )),
+                    (Some(_), None) => {
+                        return Err(E0571.tag(
+                            "type error: `break` with a value inside a `while` loop \
+                             (a `while` loop has type `()`)",
+                        ))
+                    }
+                    (None, None) => {}
+                }
+                let dead = self.fresh_block_id();
+                self.finish_block(Terminator::Goto(exit), dead);
+                self.diverged = true;
+                Ok(())
+            }
+            AstStmt::Continue { label } => {
+                let header = self.loop_frame(*label, "continue", syms)?.header;
+                let dead = self.fresh_block_id();
+                self.finish_block(Terminator::Goto(header), dead);
+                self.diverged = true;
+                Ok(())
             }
             AstStmt::Match { scrut, arms } => self.lower_match(scrut, arms, syms),
         }
@@ -456,12 +664,58 @@ impl<'a> FnBuilder<'a> {
         Ok(())
     }
 
+    /// Lower `if cond { then } else { els }` in expression position like the
+    /// statement, with each branch that falls through assigning its value (`()`
+    /// without one) to a fresh result local before jumping to the join. When
+    /// neither branch falls through, the join is unreachable and the local has
+    /// type `!`. Returns that local.
+    fn lower_if_expr(
+        &mut self,
+        cond: &Expr,
+        branches: [(&AstBlock, Option<&Expr>); 2],
+        syms: &mut Symbols,
+    ) -> Result<LocalId, String> {
+        let result = self.new_local(None);
+        let cond_op = self.lower_operand(cond, syms)?;
+
+        let then_id = self.fresh_block_id();
+        let else_id = self.fresh_block_id();
+        let join_id = self.fresh_block_id();
+        self.finish_block(
+            Terminator::Branch { cond: cond_op, then_blk: then_id, else_blk: else_id },
+            then_id,
+        );
+
+        let mut joined = false;
+        for ((block, value), next) in branches.into_iter().zip([else_id, join_id]) {
+            self.lower_block(block, syms)?;
+            match value {
+                Some(value) if !self.diverged => self.lower_into_local(result, value, syms)?,
+                _ => self.push_stmt(IrStmt::Assign(
+                    Place::local(result),
+                    RValue::Use(Operand::Const(Const::Unit)),
+                )),
+            }
+            if !self.diverged {
+                joined = true;
+                self.finish_block(Terminator::Goto(join_id), next);
+            } else {
+                self.start_block(next);
+            }
+        }
+        if !joined {
+            self.set_local_ty(result, rv_core::Ty::Never);
+        }
+        Ok(result)
+    }
+
     /// Lower `while cond (invariant I;)* { body }` into header/body/exit blocks
     /// with a back-edge. Each `invariant` becomes a `Stmt::Invariant` placed at the
     /// very START of the loop header (before the condition is evaluated), so it is
     /// re-established on every header visit (entry and each back-edge).
     fn lower_while(
         &mut self,
+        label: Option<Sym>,
         cond: &Expr,
         invariants: &[Expr],
         body: &AstBlock,
@@ -487,7 +741,10 @@ impl<'a> FnBuilder<'a> {
         );
 
         // Body: lower, then loop back to the header (unless it diverged).
-        self.lower_block(body, syms)?;
+        self.loops.push(LoopFrame { label, header: header_id, exit: exit_id, result: None });
+        let lowered = self.lower_block(body, syms);
+        self.loops.pop();
+        lowered?;
         if !self.diverged {
             self.finish_block(Terminator::Goto(header_id), exit_id);
         } else {
@@ -497,6 +754,115 @@ impl<'a> FnBuilder<'a> {
         Ok(())
     }
 
+    /// Lower `return value?`: end the current block with a `Return`, leaving the
+    /// builder diverged so the rest of the enclosing block is dropped.
+    fn lower_return(&mut self, value: Option<&Expr>, syms: &mut Symbols) -> Result<(), String> {
+        self.fix_opaque(value, syms)?;
+        let operand = match value {
+            Some(e) => self.lower_operand(e, syms)?,
+            None => Operand::Const(Const::Unit),
+        };
+        // A return needs no successor; route to a dummy fresh id that is never
+        // built (the block list simply won't contain it).
+        let dead = self.fresh_block_id();
+        self.finish_block(Terminator::Return(operand), dead);
+        self.diverged = true;
+        Ok(())
+    }
+
+    /// Under an `impl Trait` return type, fix the concrete type it stands for to
+    /// that of the returned `value`: the first `return` decides, and every later
+    /// one must agree with it.
+    fn fix_opaque(&mut self, value: Option<&Expr>, syms: &Symbols) -> Result<(), String> {
+        let Some(ret @ AstTy::ImplTrait(_)) = &self.ret else { return Ok(()) };
+        let shown = ret.show(syms);
+        let value = value.filter(|e| self.expr_ref_depth(e) == 0);
+        let Some(adt) = value.and_then(|e| self.adt_of_expr(e)) else {
+            return Err(format!(
+                "cannot tell the concrete type behind the return type `{shown}`: return a \
+                 struct or enum value whose type is evident"
+            ));
+        };
+        let line = self.cur_line.map_or(0, |l| l.start);
+        match self.opaque {
+            None => self.opaque = Some((adt, line)),
+            Some((first, _)) if first == adt => {}
+            Some((first, at)) => {
+                return Err(E0308.tag(format!(
+                    "return type is `{shown}` but the branches return different concrete \
+                     types: `{}` at line {at}, and `{}` here",
+                    syms.resolve(first),
+                    syms.resolve(adt)
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Lower `return value?` in expression position. Whatever the enclosing
+    /// expression still emits lands in unreachable code, as after a `return`
+    /// statement; its value is a fresh `!`-typed local that nothing reachable reads.
+    fn lower_return_expr(
+        &mut self,
+        value: Option<&Expr>,
+        syms: &mut Symbols,
+    ) -> Result<LocalId, String> {
+        self.lower_return(value, syms)?;
+        let never = self.new_local(None);
+        self.set_local_ty(never, rv_core::Ty::Never);
+        Ok(never)
+    }
+
+    /// Lower `loop { body }` into a header block with a back-edge and an exit block
+    /// reached only by `break`. The loop's value lives in a fresh result local that
+    /// each `break` assigns before jumping to the exit; a loop nothing breaks out of
+    /// leaves it unassigned (and its exit unreachable). Returns that local.
+    fn lower_loop(
+        &mut self,
+        label: Option<Sym>,
+        body: &AstBlock,
+        syms: &mut Symbols,
+    ) -> Result<LocalId, String> {
+        let result = self.new_local(None);
+        let header_id = self.fresh_block_id();
+        let exit_id = self.fresh_block_id();
+
+        self.finish_block(Terminator::Goto(header_id), header_id);
+        self.loops.push(LoopFrame { label, header: header_id, exit: exit_id, result: Some(result) });
+        let lowered = self.lower_block(body, syms);
+        self.loops.pop();
+        lowered?;
+        if !self.diverged {
+            self.finish_block(Terminator::Goto(header_id), exit_id);
+        } else {
+            self.start_block(exit_id);
+        }
+        Ok(result)
+    }
+
+    /// The loop a `break` / `continue` (named by `what`) targets: the innermost one,
+    /// or the innermost one carrying `label`.
+    fn loop_frame(
+        &self,
+        label: Option<Sym>,
+        what: &str,
+        syms: &Symbols,
+    ) -> Result<&LoopFrame, String> {
+        match label {
+            None => {
+                self.loops.last().ok_or_else(|| E0268.tag(format!("`{what}` outside of a loop")))
+            }
+            Some(l) => self
+                .loops
+                .iter()
+                .rev()
+                .find(|f| f.label == Some(l))
+                .ok_or_else(|| {
+                    E0426.tag(format!("`{what}` to undeclared label `'{}`", syms.resolve(l)))
+                }),
+        }
+    }
+
     /// Lower `match scrut { Pat => block, ... }`.
     ///
     /// Emits `Terminator::Match { scrutinee, arms, otherwise }` where each
@@ -504,18 +870,23 @@ impl<'a> FnBuilder<'a> {
     /// statements bind the pattern's named field binders (via `Downcast`+`Field`
     /// projections off the scrutinee local), and a `_ => body` arm becomes the
     /// `otherwise` target. Every arm block jumps to a shared join block, in which
-    /// lowering continues after the match.
+    /// lowering continues after the match. A reference scrutinee is matched
+    /// through the reference (see [`Self::auto_deref`]).
     fn lower_match(
         &mut self,
         scrut: &Expr,
         arms: &[AstMatchArm],
         syms: &mut Symbols,
     ) -> Result<(), String> {
+        if arms.iter().any(|a| is_literal_pattern(&a.pat)) {
+            return self.lower_literal_match(scrut, arms, syms);
+        }
         // The scrutinee must be a *local* (we project off it for field binds). If
         // the expression isn't already a plain local, store it into a fresh one.
         let scrut_local = self.expr_to_local(scrut, syms)?;
         // Resolve the scrutinee's enum (needed to bind variant payload fields).
         let scrut_enum = self.local_adt.get(&scrut_local).copied();
+        let scrut = self.auto_deref(scrut_local);
 
         // Allocate the shared join block all arms fall through to.
         let join_id = self.fresh_block_id();
@@ -536,19 +907,38 @@ impl<'a> FnBuilder<'a> {
                     }
                     otherwise = Some(target);
                 }
-                Pattern::Variant { enum_name, variant, .. } => {
-                    let info = self.types.enum_info(*enum_name).ok_or_else(|| {
-                        format!("unknown enum `{}` in match pattern", syms.resolve(*enum_name))
-                    })?;
-                    let (vidx, _arity) = *info.variant_index.get(variant).ok_or_else(|| {
-                        format!(
-                            "unknown variant `{}` of enum `{}`",
-                            syms.resolve(*variant),
-                            syms.resolve(*enum_name)
-                        )
-                    })?;
+                Pattern::Variant { enum_name, variant, binds } => {
+                    let vidx = self.variant_index(*enum_name, *variant, binds, syms)?;
                     ir_arms.push(IrMatchArm { variant: vidx, target });
                 }
+                // Imports are resolved before lowering; one left is not imported.
+                Pattern::Bare { variant, .. } => {
+                    return Err(crate::imports::unresolved_pattern(*variant, syms));
+                }
+                // Each alternative becomes its own arm sharing the target block,
+                // so none of them may bind a payload field.
+                Pattern::Or(alts) => {
+                    for alt in alts {
+                        let Pattern::Variant { enum_name, variant, binds } = alt else {
+                            return Err("or-pattern alternatives must be enum variants or \
+                                        string literals"
+                                .to_string());
+                        };
+                        if binds.iter().any(|b| matches!(b, PatBind::Name(_))) {
+                            return Err(format!(
+                                "variant `{}::{}` cannot bind fields inside an or-pattern",
+                                syms.resolve(*enum_name),
+                                syms.resolve(*variant)
+                            ));
+                        }
+                        let vidx = self.variant_index(*enum_name, *variant, binds, syms)?;
+                        ir_arms.push(IrMatchArm { variant: vidx, target });
+                    }
+                }
+                // Handled by `lower_literal_match`.
+                Pattern::Str(_) | Pattern::Float(_) => {
+                    unreachable!("literal pattern in an enum match")
+                }
             }
         }
 
@@ -557,7 +947,7 @@ impl<'a> FnBuilder<'a> {
         let first_target = planned.first().map(|(id, _)| *id).unwrap_or(join_id);
         self.finish_block(
             Terminator::Match {
-                scrutinee: Operand::Copy(Place::local(scrut_local)),
+                scrutinee: Operand::Copy(scrut.clone()),
                 arms: ir_arms,
                 otherwise,
             },
@@ -572,7 +962,7 @@ impl<'a> FnBuilder<'a> {
             }
             // Bind the pattern's named field binders off the scrutinee local.
             if let Pattern::Variant { enum_name, variant, binds } = &arm.pat {
-                self.bind_pattern_fields(scrut_local, scrut_enum, *enum_name, *variant, binds, syms)?;
+                self.bind_pattern_fields(&scrut, scrut_enum, *enum_name, *variant, binds, syms)?;
             }
             // Lower the arm body, then jump to the join (unless it diverged).
             self.lower_block(&arm.body, syms)?;
@@ -594,6 +984,229 @@ impl<'a> FnBuilder<'a> {
         Ok(())
     }
 
+    /// The index of `enum_name::variant`, for a match arm binding `binds`. A bare
+    /// path names a unit variant; otherwise there is one binder per field.
+    fn variant_index(
+        &self,
+        enum_name: Sym,
+        variant: Sym,
+        binds: &[PatBind],
+        syms: &Symbols,
+    ) -> Result<u32, String> {
+        let (vidx, arity) = self.pattern_variant(enum_name, variant, syms)?;
+        if binds.is_empty() && arity > 0 {
+            return Err(format!(
+                "variant `{}` expects {arity} field(s), got 0; write `{}::{}({})` to ignore them",
+                syms.resolve(variant),
+                syms.resolve(enum_name),
+                syms.resolve(variant),
+                vec!["_"; arity as usize].join(", ")
+            ));
+        }
+        if !binds.is_empty() && binds.len() as u32 != arity {
+            return Err(format!(
+                "variant `{}` binds {} fields but pattern has {}",
+                syms.resolve(variant),
+                arity,
+                binds.len()
+            ));
+        }
+        Ok(vidx)
+    }
+
+    /// The `(index, arity)` of the variant a pattern's `enum_name::variant` path
+    /// names. Only variants can appear in a pattern.
+    fn pattern_variant(
+        &self,
+        enum_name: Sym,
+        variant: Sym,
+        syms: &Symbols,
+    ) -> Result<(u32, u32), String> {
+        let found = self.types.enum_info(enum_name).and_then(|i| i.variant_index.get(&variant));
+        found.copied().ok_or_else(|| self.unresolved_path(enum_name, variant, true, syms))
+    }
+
+    /// How many references deep `local` is, as far as lowering can tell: the `&`
+    /// nesting of its declared type, or of the borrow it was bound from. `0` when
+    /// it is not a reference or nothing is known.
+    fn ref_depth(&self, local: LocalId) -> usize {
+        let mut depth = 0;
+        let mut ty = self.locals[local.0 as usize].ty.as_ref();
+        while let Some(rv_core::Ty::Ref { inner, .. }) = ty {
+            depth += 1;
+            ty = Some(inner);
+        }
+        depth.max(self.local_ref_depth.get(&local).copied().unwrap_or(0))
+    }
+
+    /// How many references deep the value of `e` is, as far as lowering can tell:
+    /// a local's [`FnBuilder::ref_depth`], a borrow's or dereference's adjusted by
+    /// one, or the references a callee's return type wraps its ADT in.
+    fn expr_ref_depth(&self, e: &Expr) -> usize {
+        let ret_refs = |callee: Option<Sym>| callee.map_or(0, |f| self.types.fn_ret_refs(f));
+        match e {
+            Expr::Var(s) => self.names.get(s).map_or(0, |id| self.ref_depth(*id)),
+            Expr::Ref { expr, .. } => self.expr_ref_depth(expr) + 1,
+            Expr::Deref(inner) => self.expr_ref_depth(inner).saturating_sub(1),
+            Expr::Call { func, .. } => ret_refs(Some(*func)),
+            Expr::EnumCtor { enum_name, variant, args } => {
+                ret_refs(self.assoc_fn(*enum_name, *variant, args))
+            }
+            Expr::MethodCall { recv, method, .. } => {
+                ret_refs(self.adt_of_expr(recv).and_then(|adt| self.types.method(adt, *method)))
+            }
+            _ => 0,
+        }
+    }
+
+    /// Record what lowering can tell about `e`, just stored in `id`: how many
+    /// references deep it is, and the ADT behind them, which is returned. A
+    /// reference keeps the type it will be inferred at.
+    fn note_value(&mut self, id: LocalId, e: &Expr) -> Option<Sym> {
+        let depth = self.expr_ref_depth(e);
+        if depth > 0 {
+            self.local_ref_depth.insert(id, depth);
+        }
+        let Some(adt) = self.adt_of_expr(e) else {
+            if let Some(ty) = self.prim_of_expr(e).filter(|_| depth == 0) {
+                self.local_prim.insert(id, ty);
+            }
+            return None;
+        };
+        if depth == 0 {
+            self.set_local_adt(id, adt);
+        } else {
+            self.local_adt.insert(id, adt);
+        }
+        Some(adt)
+    }
+
+    /// The place a `match` inspects for scrutinee `local`: the local itself, or,
+    /// when it is a reference, the value behind every level of it. Patterns only
+    /// ever describe values, so `match r { .. }` on `r: &E` matches `*r` against
+    /// `E`'s variants, and payload binders read through the reference, binding
+    /// copies of the fields.
+    fn auto_deref(&self, local: LocalId) -> Place {
+        Place { local, proj: vec![Proj::Deref; self.ref_depth(local)] }
+    }
+
+    /// Lower a `match` whose arms test string or float literals. `Terminator::Match`
+    /// switches on variant indices, which literals don't have, so the arms become
+    /// a chain of equality tests instead:
+    ///
+    /// ```text
+    /// test_0:  t = s == "a"; Branch(t, arm_0, test_1)   // one test per literal,
+    /// test_1:  t = s == "b"; Branch(t, arm_0, test_2)   // or-alternatives share
+    /// test_2:  t = s == "c"; Branch(t, arm_1, wild)     // their arm's block
+    /// ```
+    ///
+    /// The `_` arm is the final else wherever it appears, as `otherwise` is for an
+    /// enum match. No set of literals covers every string or float, so it is
+    /// required. Floats compare bit patterns, not `==`: `-0.0` does not match `0.0`,
+    /// and a NaN scrutinee matches a NaN literal of the same bits.
+    fn lower_literal_match(
+        &mut self,
+        scrut: &Expr,
+        arms: &[AstMatchArm],
+        syms: &mut Symbols,
+    ) -> Result<(), String> {
+        let scrut_local = self.expr_to_local(scrut, syms)?;
+        let scrut = self.auto_deref(scrut_local);
+        let join_id = self.fresh_block_id();
+
+        // Allocate each arm's block and gather the literals that select it.
+        let mut planned: Vec<(BlockId, &AstMatchArm)> = Vec::new();
+        let mut tests: Vec<(Const, BlockId)> = Vec::new();
+        let mut kind = None;
+        let mut wildcard = None;
+        for arm in arms {
+            let target = self.fresh_block_id();
+            planned.push((target, arm));
+            let alts = match &arm.pat {
+                Pattern::Or(alts) => alts.as_slice(),
+                pat => std::slice::from_ref(pat),
+            };
+            for alt in alts {
+                let (lit, this) = match alt {
+                    Pattern::Str(s) => (Const::Str(s.clone()), "string"),
+                    Pattern::Float(f) => (Const::Int(f.to_bits() as i64 as i128), "float"),
+                    Pattern::Wildcard if alts.len() == 1 => {
+                        if wildcard.is_some() {
+                            return Err("duplicate `_` arm in match".to_string());
+                        }
+                        wildcard = Some(target);
+                        continue;
+                    }
+                    _ => {
+                        return Err("a match on literals cannot also have enum-variant or \
+                                    nested `_` patterns"
+                            .to_string())
+                    }
+                };
+                match kind {
+                    Some(k) if k != this => {
+                        return Err(format!("a match cannot mix {k} and {this} literal patterns"))
+                    }
+                    _ => kind = Some(this),
+                }
+                tests.push((lit, target));
+            }
+        }
+        let kind = kind.expect("a literal match has a literal pattern");
+        let wildcard = wildcard.ok_or_else(|| {
+            E0004.tag(format!("non-exhaustive {kind} match: add a `_` arm"))
+        })?;
+
+        // Float arms compare the scrutinee's bit pattern with each literal's.
+        let scrut = if kind == "float" {
+            let bits = self.new_local(None);
+            let callee = syms.intern(FLOAT_BITS);
+            self.push_stmt(IrStmt::Assign(
+                Place::local(bits),
+                RValue::Call(callee, vec![Operand::Copy(scrut)]),
+            ));
+            Place::local(bits)
+        } else {
+            scrut
+        };
+
+        // The test chain, falling through to the wildcard arm.
+        let n_tests = tests.len();
+        for (i, (lit, target)) in tests.into_iter().enumerate() {
+            let is_eq = self.new_local(None);
+            self.push_stmt(IrStmt::Assign(
+                Place::local(is_eq),
+                RValue::Bin(BinOp::Eq, Operand::Copy(scrut.clone()), Operand::Const(lit)),
+            ));
+            let (else_blk, next) = if i + 1 == n_tests {
+                (wildcard, planned[0].0)
+            } else {
+                let id = self.fresh_block_id();
+                (id, id)
+            };
+            let cond = Operand::Copy(Place::local(is_eq));
+            self.finish_block(Terminator::Branch { cond, then_blk: target, else_blk }, next);
+        }
+
+        // The arm bodies, each falling through to the join.
+        for (i, (target, arm)) in planned.iter().enumerate() {
+            if self.cur_id != *target {
+                self.start_block(*target);
+            }
+            self.lower_block(&arm.body, syms)?;
+            let next = planned.get(i + 1).map(|(id, _)| *id).unwrap_or(join_id);
+            if !self.diverged {
+                self.finish_block(Terminator::Goto(join_id), next);
+            } else {
+                self.start_block(next);
+            }
+        }
+        if self.cur_id != join_id {
+            self.start_block(join_id);
+        }
+        Ok(())
+    }
+
     /// Lower the error-propagation operator `e?`, splitting the current block.
     ///
     /// Evaluates `e` into a scrutinee local `s`, resolves its `Result`/`Option`-like
@@ -616,6 +1229,16 @@ impl<'a> FnBuilder<'a> {
                 .to_string()
         })?;
         let shape = self.types.try_shape(enum_name, syms)?;
+        if let Some(ret) = &self.ret {
+            if !matches!(ret, AstTy::Adt(n) | AstTy::Generic { base: n, .. } if *n == enum_name) {
+                let name = syms.resolve(enum_name);
+                return Err(E0277.tag(format!(
+                    "the `?` operator on a `{name}` value can only be used in a function that \
+                     returns `{name}`, but this function returns `{}`",
+                    ret.show(syms)
+                )));
+            }
+        }
 
         // Blocks: the two match-arm targets and the success continuation.
         let success_id = self.fresh_block_id();
@@ -687,7 +1310,7 @@ impl<'a> FnBuilder<'a> {
     /// type tracking); reports an error if it could not be resolved.
     fn bind_pattern_fields(
         &mut self,
-        scrut_local: LocalId,
+        scrut: &Place,
         scrut_enum: Option<Sym>,
         enum_name: Sym,
         variant: Sym,
@@ -707,16 +1330,7 @@ impl<'a> FnBuilder<'a> {
                 ));
             }
         }
-        let info = self.types.enum_info(enum_name).ok_or_else(|| {
-            format!("unknown enum `{}` in match pattern", syms.resolve(enum_name))
-        })?;
-        let (vidx, arity) = *info.variant_index.get(&variant).ok_or_else(|| {
-            format!(
-                "unknown variant `{}` of enum `{}`",
-                syms.resolve(variant),
-                syms.resolve(enum_name)
-            )
-        })?;
+        let (vidx, arity) = self.pattern_variant(enum_name, variant, syms)?;
         if binds.len() as u32 != arity {
             return Err(format!(
                 "variant `{}` binds {} fields but pattern has {}",
@@ -728,10 +1342,8 @@ impl<'a> FnBuilder<'a> {
         for (i, b) in binds.iter().enumerate() {
             let PatBind::Name(name) = b else { continue }; // skip `_`
             let dst = self.new_local(Some(*name));
-            let src = Place {
-                local: scrut_local,
-                proj: vec![Proj::Downcast(vidx), Proj::Field(i as u32)],
-            };
+            let mut src = scrut.clone();
+            src.proj.extend([Proj::Downcast(vidx), Proj::Field(i as u32)]);
             self.push_stmt(IrStmt::Assign(
                 Place::local(dst),
                 RValue::Use(Operand::Copy(src)),
@@ -759,15 +1371,14 @@ impl<'a> FnBuilder<'a> {
         // Otherwise evaluate into a fresh local, carrying any known ADT type.
         let tmp = self.new_local(None);
         self.lower_into_local(tmp, e, syms)?;
-        if let Some(adt) = self.adt_of_expr(e) {
-            self.set_local_adt(tmp, adt);
-        }
+        self.note_value(tmp, e);
         Ok(tmp)
     }
 
     /// Begin building a new block with the given id (used after a diverging arm
     /// has already closed the previous block, so there is nothing to finish).
     fn start_block(&mut self, id: BlockId) {
+        debug_assert!(self.diverged, "block {} abandoned before it was terminated", self.cur_id.0);
         debug_assert!(self.cur_stmts.is_empty());
         self.cur_id = id;
         self.diverged = false;
@@ -782,15 +1393,199 @@ impl<'a> FnBuilder<'a> {
         e: &Expr,
         syms: &mut Symbols,
     ) -> Result<(), String> {
-        let rvalue = self.lower_rvalue(e, syms)?;
+        let rvalue = if self.is_default_call(e, syms) {
+            let Some(ty) = self.locals[dst.0 as usize].ty.clone() else {
+                return Err(DEFAULT_NEEDS_TYPE.to_string());
+            };
+            self.default_rvalue(&ty, &mut Vec::new(), syms)?
+        } else {
+            self.lower_rvalue(e, syms)?
+        };
         self.push_stmt(IrStmt::Assign(Place::local(dst), rvalue));
         Ok(())
     }
 
+    /// Whether `e` is the `default()` intrinsic: a no-argument call to `default`
+    /// that no function or local of that name shadows.
+    fn is_default_call(&self, e: &Expr, syms: &Symbols) -> bool {
+        matches!(e, Expr::Call { func, args } if args.is_empty()
+            && syms.resolve(*func) == "default"
+            && !self.names.contains_key(func)
+            && !self.types.is_fn(*func))
+    }
+
+    /// Whether a call to `func` is the builtin `name`: no function or local of
+    /// that name shadows it.
+    fn is_builtin_call(&self, func: Sym, name: &str, syms: &Symbols) -> bool {
+        syms.resolve(func) == name && !self.names.contains_key(&func) && !self.types.is_fn(func)
+    }
+
+    /// `format("{} of {}", a, b)`: each argument rendered by [`TO_STRING`] and
+    /// joined to the literal's pieces, left to right, by [`STR_CONCAT`].
+    fn lower_format(&mut self, args: &[Expr], syms: &mut Symbols) -> Result<RValue, String> {
+        let Some(Expr::Str(fmt)) = args.first() else {
+            return Err(E0308.tag(
+                "the first argument of `format` must be a string literal such as `\"{}\"`",
+            ));
+        };
+        let pieces = format_pieces(fmt)?;
+        let (holes, given) = (pieces.len() - 1, args.len() - 1);
+        if holes != given {
+            let s = |n: usize| if n == 1 { "" } else { "s" };
+            let were = if given == 1 { "was" } else { "were" };
+            return Err(E0061.tag(format!(
+                "format string expects {holes} argument{} but {given} {were} supplied",
+                s(holes)
+            )));
+        }
+        let (to_string, concat) = (syms.intern(TO_STRING), syms.intern(STR_CONCAT));
+        let mut out: Option<Operand> = None;
+        for (piece, arg) in pieces.into_iter().zip(args[1..].iter().map(Some).chain([None])) {
+            if !piece.is_empty() {
+                out = Some(self.concat(out, Operand::Const(Const::Str(piece)), concat));
+            }
+            if let Some(arg) = arg {
+                let value = self.lower_operand(arg, syms)?;
+                let shown = self.string_temp(RValue::Call(to_string, vec![value]));
+                out = Some(self.concat(out, shown, concat));
+            }
+        }
+        Ok(RValue::Use(out.unwrap_or(Operand::Const(Const::Str(String::new())))))
+    }
+
+    /// `assert_eq(a, b)` or `assert_ne(a, b)`, the builtin `name`: a call to
+    /// `builtin` on both operands, evaluated left to right.
+    fn lower_assert_cmp(
+        &mut self,
+        name: &str,
+        builtin: &str,
+        args: &[Expr],
+        syms: &mut Symbols,
+    ) -> Result<RValue, String> {
+        let [left, right] = args else {
+            let were = if args.len() == 1 { "was" } else { "were" };
+            return Err(E0061.tag(format!(
+                "`{name}` takes 2 arguments but {} {were} supplied",
+                args.len()
+            )));
+        };
+        let left = self.lower_operand(left, syms)?;
+        let right = self.lower_operand(right, syms)?;
+        Ok(RValue::Call(syms.intern(builtin), vec![left, right]))
+    }
+
+    /// `so_far` followed by `next`, or just `next` if nothing came before.
+    fn concat(&mut self, so_far: Option<Operand>, next: Operand, concat: Sym) -> Operand {
+        match so_far {
+            Some(so_far) => self.string_temp(RValue::Call(concat, vec![so_far, next])),
+            None => next,
+        }
+    }
+
+    /// The integer literal `n` with suffix `ty` (negated when `negated`): a
+    /// temporary of the suffix's type, so inference takes its type from there and
+    /// checks the literal's range against it.
+    fn suffixed_int(&mut self, n: i128, ty: &AstTy, negated: bool) -> Operand {
+        let tmp = self.new_local(None);
+        self.set_local_ty(tmp, self.types.resolve_ty(ty, &self.type_scope));
+        let lit = Operand::Const(Const::Int(n));
+        let value = if negated { RValue::Un(UnOp::Neg, lit) } else { RValue::Use(lit) };
+        self.push_stmt(IrStmt::Assign(Place::local(tmp), value));
+        Operand::Copy(Place::local(tmp))
+    }
+
+    /// A fresh `String` temporary holding `value`.
+    fn string_temp(&mut self, value: RValue) -> Operand {
+        let tmp = self.new_local(None);
+        self.set_local_ty(tmp, rv_core::Ty::Str);
+        self.push_stmt(IrStmt::Assign(Place::local(tmp), value));
+        Operand::Copy(Place::local(tmp))
+    }
+
+    /// The value `default()` produces at type `ty`: zero, `false`, the empty
+    /// string, `()`, or a tuple or struct of defaulted components. An enum has no
+    /// designated default variant, so it has no default. `structs` holds the
+    /// structs being defaulted, to reject one that contains itself.
+    fn default_rvalue(
+        &mut self,
+        ty: &rv_core::Ty,
+        structs: &mut Vec<Sym>,
+        syms: &Symbols,
+    ) -> Result<RValue, String> {
+        use rv_core::Ty;
+        let value = match ty {
+            Ty::Int | Ty::IntN(_) => Const::Int(0),
+            Ty::Float => Const::Float(0.0),
+            Ty::Bool => Const::Bool(false),
+            Ty::Str => Const::Str(String::new()),
+            Ty::Unit => Const::Unit,
+            Ty::Tuple(items) => {
+                let mut ops = Vec::with_capacity(items.len());
+                for item in items {
+                    ops.push(self.default_operand(item, structs, syms)?);
+                }
+                return Ok(RValue::Aggregate(AggKind::Tuple, ops));
+            }
+            Ty::Adt(name, args) => {
+                let Some(fields) = self.types.struct_field_tys(*name, args) else {
+                    return Err(if self.types.enum_info(*name).is_some() {
+                        format!("enum `{}` has no default value", syms.resolve(*name))
+                    } else {
+                        let name = syms.resolve(*name);
+                        format!("`default()` has no value for unknown type `{name}`")
+                    });
+                };
+                if structs.contains(name) {
+                    return Err(format!(
+                        "struct `{}` contains itself and has no default value",
+                        syms.resolve(*name)
+                    ));
+                }
+                structs.push(*name);
+                let mut ops = Vec::with_capacity(fields.len());
+                for field in &fields {
+                    ops.push(self.default_operand(field, structs, syms)?);
+                }
+                structs.pop();
+                return Ok(RValue::Aggregate(AggKind::Struct(*name), ops));
+            }
+            _ => {
+                return Err("`default()` only builds numbers, `bool`, `String`, `()`, \
+                            and tuples or structs of those"
+                    .to_string())
+            }
+        };
+        Ok(RValue::Use(Operand::Const(value)))
+    }
+
+    /// [`Self::default_rvalue`] as an operand, spilling an aggregate to a typed
+    /// temporary.
+    fn default_operand(
+        &mut self,
+        ty: &rv_core::Ty,
+        structs: &mut Vec<Sym>,
+        syms: &Symbols,
+    ) -> Result<Operand, String> {
+        match self.default_rvalue(ty, structs, syms)? {
+            RValue::Use(op) => Ok(op),
+            rvalue => {
+                let tmp = self.new_local(None);
+                self.set_local_ty(tmp, ty.clone());
+                self.push_stmt(IrStmt::Assign(Place::local(tmp), rvalue));
+                Ok(Operand::Copy(Place::local(tmp)))
+            }
+        }
+    }
+
     /// Lower an expression to an [`RValue`], flattening nested subexpressions into
     /// temporaries as needed. Compound forms (binary/unary/call) map directly to
-    /// the corresponding `RValue`; everything else becomes `RValue::Use`.
+    /// the corresponding `RValue`; everything else becomes `RValue::Use`. Each
+    /// level of nesting runs on [`rv_arena::stack::grow`].
     fn lower_rvalue(&mut self, e: &Expr, syms: &mut Symbols) -> Result<RValue, String> {
+        rv_arena::stack::grow(|| self.lower_rvalue_here(e, syms))
+    }
+
+    fn lower_rvalue_here(&mut self, e: &Expr, syms: &mut Symbols) -> Result<RValue, String> {
         match e {
             Expr::Bin(op, a, b) => {
                 let oa = self.lower_operand(a, syms)?;
@@ -798,9 +1593,25 @@ impl<'a> FnBuilder<'a> {
                 Ok(RValue::Bin(*op, oa, ob))
             }
             Expr::Un(op, a) => {
+                // `-128i8` is one literal, as `-128` is: negated within its type.
+                if let (UnOp::Neg, Expr::SuffixedInt(n, ty)) = (op, &**a) {
+                    return Ok(RValue::Use(self.suffixed_int(*n, ty, true)));
+                }
                 let oa = self.lower_operand(a, syms)?;
                 Ok(RValue::Un(*op, oa))
             }
+            // `e as ty`. A cast of a literal folds here with the same semantics the
+            // VM uses; anything else is checked and classified during inference.
+            Expr::Cast { expr, ty } => {
+                let to = self.types.resolve_ty(ty, &self.type_scope);
+                match self.lower_operand(expr, syms)? {
+                    Operand::Const(c) => match c.cast(&to) {
+                        Some(folded) => Ok(RValue::Use(Operand::Const(folded))),
+                        None => Ok(RValue::Cast(Operand::Const(c), to)),
+                    },
+                    op => Ok(RValue::Cast(op, to)),
+                }
+            }
             Expr::Call { func, args } => {
                 // If the callee name is a bound LOCAL, it holds a closure value: this is an
                 // indirect call (`f(x)` where `let f = |..| ..`), lowered to `CallClosure`.
@@ -811,6 +1622,17 @@ impl<'a> FnBuilder<'a> {
                     }
                     return Ok(RValue::CallClosure(Operand::Copy(Place::local(local)), ops));
                 }
+                if self.is_default_call(e, syms) {
+                    return Err(DEFAULT_NEEDS_TYPE.to_string());
+                }
+                if self.is_builtin_call(*func, "format", syms) {
+                    return self.lower_format(args, syms);
+                }
+                for (name, builtin) in [("assert_eq", ASSERT_EQ), ("assert_ne", ASSERT_NE)] {
+                    if self.is_builtin_call(*func, name, syms) {
+                        return self.lower_assert_cmp(name, builtin, args, syms);
+                    }
+                }
                 // Wrapping intrinsics `wrapping_add(a, b)` etc. opt out of the
                 // checked-overflow obligation (lower to `RValue::WrappingBin`).
                 if let Some(op) = wrapping_builtin(syms.resolve(*func)) {
@@ -848,11 +1670,30 @@ impl<'a> FnBuilder<'a> {
                 let v = self.lower_try(inner, syms)?;
                 Ok(RValue::Use(Operand::Copy(Place::local(v))))
             }
+            // `loop { .. }`: lower the loop, then use its result local.
+            Expr::Loop { label, body } => {
+                let v = self.lower_loop(*label, body, syms)?;
+                Ok(RValue::Use(Operand::Copy(Place::local(v))))
+            }
+            Expr::Return(value) => {
+                let v = self.lower_return_expr(value.as_deref(), syms)?;
+                Ok(RValue::Use(Operand::Copy(Place::local(v))))
+            }
+            Expr::If { cond, then_blk, then_val, else_blk, else_val } => {
+                let branches = [(then_blk, then_val.as_deref()), (else_blk, else_val.as_deref())];
+                let v = self.lower_if_expr(cond, branches, syms)?;
+                Ok(RValue::Use(Operand::Copy(Place::local(v))))
+            }
             // `&place` / `&mut place`: take a reference to the operand's place. The
             // operand must be a place; `lower_place` materializes a fresh local for
             // any non-place expression and borrows that local instead.
             Expr::Ref { mutable, expr } => {
                 let place = self.lower_place(expr, syms)?;
+                // A `&mut` borrow can write the borrowed local (not so a borrow of
+                // what a reference it holds points to).
+                if *mutable && !place.proj.iter().any(|p| matches!(p, Proj::Deref)) {
+                    self.mark_mutable(place.local);
+                }
                 let kind = if *mutable { BorrowKind::Mut } else { BorrowKind::Shared };
                 Ok(RValue::Ref(kind, place))
             }
@@ -860,11 +1701,20 @@ impl<'a> FnBuilder<'a> {
             // fresh top-level function (params = captures ++ closure params), and build a
             // `Closure` value carrying the captured operands.
             Expr::Lambda { params, body } => self.lower_lambda(params, body, syms),
+            // A function named without being called is a function-pointer value: a
+            // closure over it that captures nothing.
+            Expr::Var(s) if self.is_fn_item(*s) => Ok(RValue::Closure(*s, Vec::new())),
             // Atoms / parenthesized values.
             _ => Ok(RValue::Use(self.lower_operand(e, syms)?)),
         }
     }
 
+    /// Whether `name` refers to a top-level function rather than a local (which
+    /// shadows it).
+    fn is_fn_item(&self, name: Sym) -> bool {
+        !self.names.contains_key(&name) && self.types.is_fn(name)
+    }
+
     /// Lower a closure literal by lambda-lifting. Free variables of the body (those not bound by
     /// the closure's own parameters) become leading parameters of a generated top-level function
     /// and are captured by value at the closure site.
@@ -887,8 +1737,10 @@ impl<'a> FnBuilder<'a> {
         self.closure_ctr += 1;
 
         // Build the lifted function in its own builder: locals = captures ++ params, body
-        // lowered to a returned value.
-        let mut b = FnBuilder::new(self.types);
+        // lowered to a returned value. The body still sees the enclosing function's
+        // type parameters, which the lifted function takes over as its own.
+        let mut b = FnBuilder::new(self.types, self.type_scope.clone());
+        b.cur_line = self.cur_line;
         let mut fparams = Vec::with_capacity(captures.len() + params.len());
         for s in captures.iter().chain(params.iter()) {
             let id = b.new_local(Some(*s));
@@ -898,11 +1750,12 @@ impl<'a> FnBuilder<'a> {
         let ret_local = b.expr_to_local(body, syms)?;
         b.return_local(ret_local);
         let nested = b.take_lifted(); // closures nested inside this one
-        let (locals, blocks) = b.into_parts();
+        self.lifted_lines.extend(b.take_lines(name));
+        let (locals, blocks) = b.finish()?;
         self.lifted.extend(nested);
         self.lifted.push(rv_ir::Function {
             name,
-            type_params: Vec::new(),
+            type_params: self.type_scope.params(),
             generic_bounds: Vec::new(),
             params: fparams,
             ret: None,
@@ -933,7 +1786,7 @@ impl<'a> FnBuilder<'a> {
         let info = self
             .types
             .struct_info(name)
-            .ok_or_else(|| format!("unknown struct `{}`", syms.resolve(name)))?;
+            .ok_or_else(|| E0001.tag(format!("unknown struct `{}`", syms.resolve(name))))?;
         let n = info.fields.len();
         // Snapshot the field-name -> index map so we don't hold a borrow of `self`
         // while lowering the field expressions.
@@ -991,17 +1844,39 @@ impl<'a> FnBuilder<'a> {
         args: &[Expr],
         syms: &mut Symbols,
     ) -> Result<RValue, String> {
-        let info = self
-            .types
-            .enum_info(enum_name)
-            .ok_or_else(|| format!("unknown enum `{}`", syms.resolve(enum_name)))?;
-        let (vidx, arity) = *info.variant_index.get(&variant).ok_or_else(|| {
-            format!(
-                "enum `{}` has no variant `{}`",
-                syms.resolve(enum_name),
-                syms.resolve(variant)
-            )
-        })?;
+        // `Type::func(args)` calls an associated function of `Type` directly, and
+        // `Trait::method(recv, args)` calls the trait's method for `recv`'s type.
+        if let Some(mangled) = self.assoc_fn(enum_name, variant, args) {
+            let mut ops = Vec::with_capacity(args.len());
+            for arg in args {
+                ops.push(self.lower_operand(arg, syms)?);
+            }
+            return Ok(RValue::Call(mangled, ops));
+        }
+        if self.types.is_trait(enum_name) {
+            let recv = args.first().and_then(|a| self.adt_of_expr(a));
+            return Err(match recv {
+                Some(adt) => format!(
+                    "no impl of trait `{}` for `{}` provides `{}`",
+                    syms.resolve(enum_name),
+                    syms.resolve(adt),
+                    syms.resolve(variant)
+                ),
+                None => format!(
+                    "cannot resolve the receiver type of qualified call `{}::{}(..)`",
+                    syms.resolve(enum_name),
+                    syms.resolve(variant)
+                ),
+            });
+        }
+        if let Some(err) = self.ambiguous_method(enum_name, variant, syms) {
+            return Err(err);
+        }
+        let Some(&(vidx, arity)) =
+            self.types.enum_info(enum_name).and_then(|info| info.variant_index.get(&variant))
+        else {
+            return Err(self.unresolved_path(enum_name, variant, false, syms));
+        };
         if args.len() as u32 != arity {
             return Err(format!(
                 "variant `{}` expects {} field(s), got {}",
@@ -1035,20 +1910,22 @@ impl<'a> FnBuilder<'a> {
         args: &[Expr],
         syms: &mut Symbols,
     ) -> Result<RValue, String> {
-        // Determine the receiver's ADT type. Restrict receivers to user ADTs.
-        let adt = self.adt_of_expr(recv).ok_or_else(|| {
+        // Determine the receiver's type: a user ADT, or a primitive some impl extends.
+        let adt = self.recv_key(recv).ok_or_else(|| {
             format!(
                 "cannot resolve the receiver type of method call `.{}(..)` \
-                 (method receivers must be locals of a known struct/enum type)",
+                 (method receivers must be of a known struct, enum or primitive type)",
                 syms.resolve(method)
             )
         })?;
         let mangled = self.types.method(adt, method).ok_or_else(|| {
-            format!(
-                "no method `{}` found for type `{}`",
-                syms.resolve(method),
-                syms.resolve(adt)
-            )
+            self.ambiguous_method(adt, method, syms).unwrap_or_else(|| {
+                format!(
+                    "no method `{}` found for type `{}`",
+                    syms.resolve(method),
+                    syms.resolve(adt)
+                )
+            })
         })?;
         // The receiver becomes the first argument, then the explicit arguments.
         let mut ops = Vec::with_capacity(args.len() + 1);
@@ -1059,14 +1936,88 @@ impl<'a> FnBuilder<'a> {
         Ok(RValue::Call(mangled, ops))
     }
 
+    /// The E0034 error for a call of `method` on `adt` that several trait impls
+    /// provide (and no inherent impl does), if that is why it did not resolve.
+    fn ambiguous_method(&self, adt: Sym, method: Sym, syms: &Symbols) -> Option<String> {
+        let traits = self.types.method_traits(adt, method);
+        if traits.len() < 2 {
+            return None;
+        }
+        let m = syms.resolve(method);
+        let names: Vec<String> = traits.iter().map(|t| format!("`{}`", syms.resolve(*t))).collect();
+        let calls: Vec<String> =
+            traits.iter().map(|t| format!("`{}::{m}(..)`", syms.resolve(*t))).collect();
+        Some(E0034.tag(format!(
+            "multiple applicable methods `{m}` for type `{}`, from traits {}; \
+             disambiguate with a qualified call: {}",
+            syms.resolve(adt),
+            names.join(" and "),
+            calls.join(" or ")
+        )))
+    }
+
+    /// The error for a `head::name` path that names nothing: `head` is not a type
+    /// or trait of this module, or nothing called `name` lives under it. Either
+    /// way the segment that failed is named, with close matches to it (only
+    /// variants, for a pattern).
+    fn unresolved_path(&self, head: Sym, name: Sym, in_pattern: bool, syms: &Symbols) -> String {
+        let (h, n) = (syms.resolve(head), syms.resolve(name));
+        if !self.types.is_adt(head) && !self.types.is_trait(head) {
+            let heads = self.types.path_heads().map(|s| syms.resolve(s));
+            return E0433.tag(format!(
+                "failed to resolve `{h}::{n}`: no type or trait `{h}` in this module{}",
+                did_you_mean(&close_matches(h, heads))
+            ));
+        }
+        let (what, members) = match self.types.enum_info(head) {
+            Some(info) if in_pattern => ("variant", info.variant_index.keys().copied().collect()),
+            Some(_) => ("variant or associated function", self.types.path_members(head)),
+            None if in_pattern => ("variant", Vec::new()),
+            None => ("associated function", self.types.path_members(head)),
+        };
+        let members = members.iter().map(|s| syms.resolve(*s));
+        E0433.tag(format!(
+            "failed to resolve `{h}::{n}`: no {what} `{n}` in `{h}`{}",
+            did_you_mean(&close_matches(n, members))
+        ))
+    }
+
+    /// The error for `what` (`use of`, `assignment to`) a variable `name` that no
+    /// binding in scope declares, with the close matches among those that do.
+    fn unbound(&self, what: &str, name: Sym, syms: &Symbols) -> String {
+        let n = syms.resolve(name);
+        let bound = self.names.keys().map(|s| syms.resolve(*s));
+        let hint = did_you_mean(&close_matches(n, bound));
+        E0001.tag(format!("{what} unbound variable `{n}`{hint}"))
+    }
+
+    /// The mangled function a `Head::name(args)` path calls, when it is not an
+    /// enum variant: an impl function of type `Head`, or — when `Head` is a
+    /// trait — that trait's method for the type of the first argument (a
+    /// qualified call `Trait::method(recv, ..)`).
+    fn assoc_fn(&self, head: Sym, name: Sym, args: &[Expr]) -> Option<Sym> {
+        if self.types.is_trait(head) {
+            let recv = self.adt_of_expr(args.first()?)?;
+            return self.types.trait_method(head, recv, name);
+        }
+        let is_variant = self
+            .types
+            .enum_info(head)
+            .is_some_and(|info| info.variant_index.contains_key(&name));
+        if is_variant {
+            return None;
+        }
+        self.types.method(head, name)
+    }
+
     /// Lower an expression that denotes a *place* (currently: a variable, or a
-    /// chain of struct field accesses rooted at one). Appends `Proj::Field`s.
+    /// chain of struct field accesses rooted at one). Appends `Proj::Field`s, each
+    /// after a `Proj::Deref` per reference its base is behind: `r.f` on `r: &mut S`
+    /// is `(*r).f`, and so a store through the reference.
     fn lower_place(&mut self, e: &Expr, syms: &mut Symbols) -> Result<Place, String> {
         match e {
             Expr::Var(s) => {
-                let id = *self.names.get(s).ok_or_else(|| {
-                    format!("use of unbound variable `{}`", syms.resolve(*s))
-                })?;
+                let id = *self.names.get(s).ok_or_else(|| self.unbound("use of", *s, syms))?;
                 Ok(Place::local(id))
             }
             Expr::Field { base, field } => {
@@ -1074,6 +2025,15 @@ impl<'a> FnBuilder<'a> {
                 let base_struct = self.adt_of_expr(base).ok_or_else(|| {
                     "cannot resolve the struct type of a field-access base".to_string()
                 })?;
+                // A single-variant enum's fields read through that variant.
+                let derefs = vec![Proj::Deref; self.expr_ref_depth(base)];
+                if self.types.enum_info(base_struct).is_some() {
+                    let (variant, idx) = self.enum_field(base_struct, *field, syms)?;
+                    let mut place = self.lower_place(base, syms)?;
+                    place.proj.extend(derefs);
+                    place.proj.extend([Proj::Downcast(variant), Proj::Field(idx)]);
+                    return Ok(place);
+                }
                 let info = self.types.struct_info(base_struct).ok_or_else(|| {
                     format!("`{}` is not a struct type", syms.resolve(base_struct))
                 })?;
@@ -1085,6 +2045,7 @@ impl<'a> FnBuilder<'a> {
                     )
                 })?;
                 let mut place = self.lower_place(base, syms)?;
+                place.proj.extend(derefs);
                 place.proj.push(Proj::Field(idx));
                 Ok(place)
             }
@@ -1100,14 +2061,59 @@ impl<'a> FnBuilder<'a> {
             _ => {
                 let tmp = self.new_local(None);
                 self.lower_into_local(tmp, e, syms)?;
-                if let Some(adt) = self.adt_of_expr(e) {
-                    self.set_local_adt(tmp, adt);
-                }
+                self.note_value(tmp, e);
                 Ok(Place::local(tmp))
             }
         }
     }
 
+    /// The variants of enum `name` as `(name, index, arity)`, in declaration order.
+    fn enum_variants(&self, name: Sym) -> Vec<(Sym, u32, u32)> {
+        let Some(info) = self.types.enum_info(name) else { return Vec::new() };
+        let mut variants: Vec<_> =
+            info.variant_index.iter().map(|(&v, &(index, arity))| (v, index, arity)).collect();
+        variants.sort_by_key(|&(_, index, _)| index);
+        variants
+    }
+
+    /// The `(variant, field)` that `value.field` reads on a value of enum `name`.
+    /// Only a single-variant enum has fields of its own: that variant's positional
+    /// fields `.0`, `.1`, ...
+    fn enum_field_index(&self, name: Sym, field: Sym) -> Option<(u32, u32)> {
+        let &[(_, variant, arity)] = &self.enum_variants(name)[..] else { return None };
+        let index = self.types.positional_field(field)?;
+        (index < arity).then_some((variant, index))
+    }
+
+    /// [`Self::enum_field_index`], or the E0609 diagnostic for a field an enum value
+    /// does not have.
+    fn enum_field(&self, name: Sym, field: Sym, syms: &Symbols) -> Result<(u32, u32), String> {
+        if let Some(found) = self.enum_field_index(name, field) {
+            return Ok(found);
+        }
+        let (e, f) = (syms.resolve(name), syms.resolve(field));
+        let variants = self.enum_variants(name);
+        let hint = match &variants[..] {
+            [(variant, _, 0)] => {
+                format!("its only variant `{e}::{}` has no fields", syms.resolve(*variant))
+            }
+            [(variant, _, arity)] => {
+                let fields: Vec<String> = (0..*arity).map(|i| format!("`.{i}`")).collect();
+                format!(
+                    "its only variant `{e}::{}` has fields {}",
+                    syms.resolve(*variant),
+                    fields.join(", ")
+                )
+            }
+            _ => {
+                let names: Vec<String> =
+                    variants.iter().map(|(v, ..)| format!("`{e}::{}`", syms.resolve(*v))).collect();
+                format!("consider matching on its variants: {}", names.join(", "))
+            }
+        };
+        Err(E0609.tag(format!("no field `{f}` on enum `{e}`; {hint}")))
+    }
+
     /// Best-effort: the ADT (struct/enum) name an expression evaluates to, if we
     /// can determine it statically. Used to track local types and resolve field
     /// access. Returns `None` when unknown (lowering then errors only if the type
@@ -1115,27 +2121,45 @@ impl<'a> FnBuilder<'a> {
     fn adt_of_expr(&self, e: &Expr) -> Option<Sym> {
         match e {
             Expr::StructLit { name, .. } => Some(*name),
-            Expr::EnumCtor { enum_name, .. } => Some(*enum_name),
+            Expr::EnumCtor { enum_name, variant, args } => {
+                match self.assoc_fn(*enum_name, *variant, args) {
+                    Some(mangled) => self.types.fn_ret(mangled),
+                    None => Some(*enum_name),
+                }
+            }
             Expr::Var(s) => self.names.get(s).and_then(|id| self.local_adt.get(id)).copied(),
+            // A reference's ADT is that of the value behind it.
+            Expr::Ref { expr, .. } | Expr::Deref(expr) => self.adt_of_expr(expr),
             // A call's result ADT comes from the callee's recorded return type.
             Expr::Call { func, .. } => self.types.fn_ret(*func),
             // A method call's result ADT: resolve the receiver's ADT, find the
             // mangled method, then look up its recorded return ADT.
             Expr::MethodCall { recv, method, .. } => {
-                let recv_adt = self.adt_of_expr(recv)?;
-                let mangled = self.types.method(recv_adt, *method)?;
+                let mangled = self.types.method(self.recv_key(recv)?, *method)?;
                 self.types.fn_ret(mangled)
             }
             Expr::Field { base, field } => {
                 // The field's declared type, if it is itself an ADT.
                 let base_struct = self.adt_of_expr(base)?;
+                if self.types.enum_info(base_struct).is_some() {
+                    let (variant, idx) = self.enum_field_index(base_struct, *field)?;
+                    return self.types.defs.iter().find_map(|d| match d {
+                        rv_ir::TypeDef::Enum { name, variants, .. } if *name == base_struct => {
+                            match &variants[variant as usize].fields[idx as usize] {
+                                rv_core::Ty::Adt(a, _) => Some(*a),
+                                _ => None,
+                            }
+                        }
+                        _ => None,
+                    });
+                }
                 let info = self.types.struct_info(base_struct)?;
                 let idx = *info.field_index.get(field)? as usize;
                 // Re-read the declared field type from the embedded TypeDef.
                 self.types.defs.iter().find_map(|d| match d {
                     rv_ir::TypeDef::Struct { name, fields, .. } if *name == base_struct => {
                         match &fields[idx].ty {
-                            rv_core::Ty::Adt(a) => Some(*a),
+                            rv_core::Ty::Adt(a, _) => Some(*a),
                             _ => None,
                         }
                     }
@@ -1151,20 +2175,85 @@ impl<'a> FnBuilder<'a> {
         }
     }
 
+    /// The name `e`'s methods are registered under: its ADT's, or that of the
+    /// primitive type it evaluates to.
+    fn recv_key(&self, e: &Expr) -> Option<Sym> {
+        self.adt_of_expr(e).or_else(|| self.types.primitive_impl(&self.prim_of_expr(e)?))
+    }
+
+    /// Best-effort: the primitive type `e` evaluates to, read off its literals,
+    /// operators, casts and the declared return types of its calls. An integer
+    /// literal without a suffix is taken to be `i64`.
+    fn prim_of_expr(&self, e: &Expr) -> Option<rv_core::Ty> {
+        rv_arena::stack::grow(|| self.prim_of_expr_here(e))
+    }
+
+    fn prim_of_expr_here(&self, e: &Expr) -> Option<rv_core::Ty> {
+        use rv_core::{BinOp, Ty, UnOp};
+        match e {
+            Expr::Int(_) => Some(Ty::Int),
+            Expr::SuffixedInt(_, ty) => Some(self.types.resolve_ty(ty, &self.type_scope)),
+            Expr::Float(_) => Some(Ty::Float),
+            Expr::Bool(_) | Expr::Un(UnOp::Not, _) => Some(Ty::Bool),
+            Expr::Str(_) => Some(Ty::Str),
+            Expr::Un(UnOp::Neg, a) => self.prim_of_expr(a),
+            Expr::Bin(op, a, b) => match op {
+                BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge
+                | BinOp::And | BinOp::Or => Some(Ty::Bool),
+                _ => self.prim_of_expr(a).or_else(|| self.prim_of_expr(b)),
+            },
+            Expr::Cast { ty, .. } => Some(self.types.resolve_ty(ty, &self.type_scope)),
+            Expr::Var(s) => {
+                let id = self.names.get(s)?;
+                match &self.locals[id.0 as usize].ty {
+                    Some(ty) => Some(ty.clone()),
+                    None => self.local_prim.get(id).cloned(),
+                }
+            }
+            Expr::Call { func, .. } => self.types.fn_ret_primitive(*func).cloned(),
+            Expr::MethodCall { recv, method, .. } => {
+                let mangled = self.types.method(self.recv_key(recv)?, *method)?;
+                self.types.fn_ret_primitive(mangled).cloned()
+            }
+            _ => None,
+        }
+    }
+
     /// Lower an expression to an [`Operand`]. Atoms produce a constant or a copy
     /// of a local; compound expressions are first evaluated into a fresh temp.
     fn lower_operand(&mut self, e: &Expr, syms: &mut Symbols) -> Result<Operand, String> {
         match e {
             Expr::Int(n) => Ok(Operand::Const(Const::Int(*n))),
+            Expr::SuffixedInt(n, ty) => Ok(self.suffixed_int(*n, ty, false)),
             Expr::Float(f) => Ok(Operand::Const(Const::Float(*f))),
             Expr::Str(s) => Ok(Operand::Const(Const::Str(s.clone()))),
             Expr::Bool(b) => Ok(Operand::Const(Const::Bool(*b))),
             Expr::Unit => Ok(Operand::Const(Const::Unit)),
+            Expr::Var(s) if self.is_fn_item(*s) => {
+                let tmp = self.new_local(None);
+                self.push_stmt(IrStmt::Assign(Place::local(tmp), RValue::Closure(*s, Vec::new())));
+                Ok(Operand::Copy(Place::local(tmp)))
+            }
+            // `f::<T>`: the function value, with its instantiated type declared on the
+            // temporary so inference takes the type arguments from there.
+            Expr::Inst { func, ty_args } => {
+                if !self.is_fn_item(*func) {
+                    return Err(E0001.tag(format!(
+                        "type arguments given to `{}`, which is not a function",
+                        syms.resolve(*func)
+                    )));
+                }
+                let args: Vec<rv_core::Ty> =
+                    ty_args.iter().map(|t| self.types.resolve_ty(t, &self.type_scope)).collect();
+                let ty = self.types.instantiate_fn(*func, &args, syms)?;
+                let tmp = self.new_local(None);
+                self.set_local_ty(tmp, ty);
+                let value = RValue::Closure(*func, Vec::new());
+                self.push_stmt(IrStmt::Assign(Place::local(tmp), value));
+                Ok(Operand::Copy(Place::local(tmp)))
+            }
             Expr::Var(s) => {
-                let id = *self
-                    .names
-                    .get(s)
-                    .ok_or_else(|| format!("use of unbound variable `{}`", syms.resolve(*s)))?;
+                let id = *self.names.get(s).ok_or_else(|| self.unbound("use of", *s, syms))?;
                 Ok(Operand::Copy(Place::local(id)))
             }
             // Field access and dereference are themselves places: copy directly
@@ -1182,8 +2271,22 @@ impl<'a> FnBuilder<'a> {
                 let v = self.lower_try(inner, syms)?;
                 Ok(Operand::Copy(Place::local(v)))
             }
+            Expr::Loop { label, body } => {
+                let v = self.lower_loop(*label, body, syms)?;
+                Ok(Operand::Copy(Place::local(v)))
+            }
+            Expr::Return(value) => {
+                let v = self.lower_return_expr(value.as_deref(), syms)?;
+                Ok(Operand::Copy(Place::local(v)))
+            }
+            Expr::If { cond, then_blk, then_val, else_blk, else_val } => {
+                let branches = [(then_blk, then_val.as_deref()), (else_blk, else_val.as_deref())];
+                let v = self.lower_if_expr(cond, branches, syms)?;
+                Ok(Operand::Copy(Place::local(v)))
+            }
             Expr::Bin(..)
             | Expr::Un(..)
+            | Expr::Cast { .. }
             | Expr::Call { .. }
             | Expr::MethodCall { .. }
             | Expr::StructLit { .. }
@@ -1193,9 +2296,7 @@ impl<'a> FnBuilder<'a> {
                 let tmp = self.new_local(None);
                 let rvalue = self.lower_rvalue(e, syms)?;
                 self.push_stmt(IrStmt::Assign(Place::local(tmp), rvalue));
-                if let Some(adt) = self.adt_of_expr(e) {
-                    self.set_local_adt(tmp, adt);
-                }
+                self.note_value(tmp, e);
                 Ok(Operand::Copy(Place::local(tmp)))
             }
             // Proof-fragment expression forms never reach the executable lowering
@@ -1218,6 +2319,53 @@ impl<'a> FnBuilder<'a> {
     }
 }
 
+/// Whether `pat` tests a literal, directly or as an or-alternative.
+fn is_literal_pattern(pat: &Pattern) -> bool {
+    match pat {
+        Pattern::Str(_) | Pattern::Float(_) => true,
+        Pattern::Or(alts) => alts.iter().any(is_literal_pattern),
+        Pattern::Variant { .. } | Pattern::Bare { .. } | Pattern::Wildcard => false,
+    }
+}
+
+/// The error for a `default()` whose result type nothing around it names.
+const DEFAULT_NEEDS_TYPE: &str =
+    "`default()` needs a known type: annotate the binding it initializes";
+
+/// The literal text around each `{}` of a `format` string, so one more piece
+/// than placeholders. `{{` and `}}` stand for a brace.
+fn format_pieces(fmt: &str) -> Result<Vec<String>, String> {
+    let (mut pieces, mut cur) = (Vec::new(), String::new());
+    let mut chars = fmt.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                cur.push(c);
+            }
+            ('{', Some('}')) => {
+                chars.next();
+                pieces.push(std::mem::take(&mut cur));
+            }
+            ('{', _) => {
+                return Err(format!(
+                    "invalid format string `{fmt}`: only `{{}}` placeholders are supported \
+                     (write `{{{{` for a literal brace)"
+                ))
+            }
+            ('}', _) => {
+                return Err(format!(
+                    "invalid format string `{fmt}`: unmatched `}}` \
+                     (write `}}}}` for a literal brace)"
+                ))
+            }
+            _ => cur.push(c),
+        }
+    }
+    pieces.push(cur);
+    Ok(pieces)
+}
+
 /// Map a wrapping-arithmetic builtin name to its `BinOp`. These free calls
 /// (`wrapping_add(a, b)`, etc.) lower to `RValue::WrappingBin`, opting out of the
 /// checked-overflow obligation.
@@ -1236,6 +2384,10 @@ fn wrapping_builtin(name: &str) -> Option<BinOp> {
 /// without duplicates. `bound` is updated in place when descending under a nested closure's
 /// parameters. Used by closure lambda-lifting to decide what to capture.
 fn free_vars(e: &Expr, bound: &mut std::collections::HashSet<rv_core::Sym>, out: &mut Vec<rv_core::Sym>) {
+    rv_arena::stack::grow(|| free_vars_here(e, bound, out))
+}
+
+fn free_vars_here(e: &Expr, bound: &mut std::collections::HashSet<rv_core::Sym>, out: &mut Vec<rv_core::Sym>) {
     match e {
         Expr::Var(s) => {
             if !bound.contains(s) && !out.contains(s) {
@@ -1253,7 +2405,7 @@ fn free_vars(e: &Expr, bound: &mut std::collections::HashSet<rv_core::Sym>, out:
             free_vars(b, bound, out);
         }
         Expr::Un(_, a) | Expr::Field { base: a, .. } | Expr::Deref(a) | Expr::Try(a)
-        | Expr::Ref { expr: a, .. } => free_vars(a, bound, out),
+        | Expr::Ref { expr: a, .. } | Expr::Cast { expr: a, .. } => free_vars(a, bound, out),
         Expr::MethodCall { recv, args, .. } => {
             free_vars(recv, bound, out);
             for a in args {
@@ -1274,7 +2426,114 @@ fn free_vars(e: &Expr, bound: &mut std::collections::HashSet<rv_core::Sym>, out:
                 bound.remove(&p);
             }
         }
+        Expr::Loop { body, .. } => free_vars_block(body, bound, out),
+        Expr::If { cond, then_blk, then_val, else_blk, else_val } => {
+            free_vars(cond, bound, out);
+            free_vars_scope(then_blk, then_val.as_deref(), bound, out);
+            free_vars_scope(else_blk, else_val.as_deref(), bound, out);
+        }
+        Expr::Return(e) => e.iter().for_each(|e| free_vars(e, bound, out)),
         // Proof-fragment expression forms never appear in executable closure bodies.
         _ => {}
     }
 }
+
+/// [`free_vars`] over a statement block (a `loop` body inside a closure). A `let`
+/// binds its name for the rest of the block, and a match arm binds its pattern's
+/// names for the arm body.
+fn free_vars_block(
+    b: &AstBlock,
+    bound: &mut std::collections::HashSet<rv_core::Sym>,
+    out: &mut Vec<rv_core::Sym>,
+) {
+    free_vars_scope(b, None, bound, out)
+}
+
+/// [`free_vars_block`] for a block followed by `tail`, which sees its bindings
+/// (an `if` expression's branch and its value).
+fn free_vars_scope(
+    b: &AstBlock,
+    tail: Option<&Expr>,
+    bound: &mut std::collections::HashSet<rv_core::Sym>,
+    out: &mut Vec<rv_core::Sym>,
+) {
+    rv_arena::stack::grow(|| free_vars_scope_here(b, tail, bound, out))
+}
+
+fn free_vars_scope_here(
+    b: &AstBlock,
+    tail: Option<&Expr>,
+    bound: &mut std::collections::HashSet<rv_core::Sym>,
+    out: &mut Vec<rv_core::Sym>,
+) {
+    let mut added = Vec::new();
+    for stmt in &b.stmts {
+        match stmt {
+            AstStmt::Let { name, init, .. } => {
+                if let Some(init) = init {
+                    free_vars(init, bound, out);
+                }
+                if bound.insert(*name) {
+                    added.push(*name);
+                }
+            }
+            AstStmt::Assign { name, value } => {
+                free_vars(&Expr::Var(*name), bound, out);
+                free_vars(value, bound, out);
+            }
+            AstStmt::DerefAssign { place, value } => {
+                free_vars(place, bound, out);
+                free_vars(value, bound, out);
+            }
+            AstStmt::If { cond, then_blk, else_blk } => {
+                free_vars(cond, bound, out);
+                free_vars_block(then_blk, bound, out);
+                if let Some(els) = else_blk {
+                    free_vars_block(els, bound, out);
+                }
+            }
+            AstStmt::While { cond, invariants, body, .. } => {
+                free_vars(cond, bound, out);
+                for inv in invariants {
+                    free_vars(inv, bound, out);
+                }
+                free_vars_block(body, bound, out);
+            }
+            AstStmt::Match { scrut, arms } => {
+                free_vars(scrut, bound, out);
+                for arm in arms {
+                    let binds: Vec<rv_core::Sym> = match &arm.pat {
+                        Pattern::Variant { binds, .. } | Pattern::Bare { binds, .. } => binds
+                            .iter()
+                            .filter_map(|b| match b {
+                                PatBind::Name(n) if bound.insert(*n) => Some(*n),
+                                _ => None,
+                            })
+                            .collect(),
+                        Pattern::Str(_)
+                        | Pattern::Float(_)
+                        | Pattern::Or(_)
+                        | Pattern::Wildcard => Vec::new(),
+                    };
+                    free_vars_block(&arm.body, bound, out);
+                    for n in binds {
+                        bound.remove(&n);
+                    }
+                }
+            }
+            AstStmt::Return(e) | AstStmt::Panic(e) | AstStmt::Break { value: e, .. } => {
+                if let Some(e) = e {
+                    free_vars(e, bound, out);
+                }
+            }
+            AstStmt::Continue { .. } => {}
+            AstStmt::Assert(e) | AstStmt::Expr(e) => free_vars(e, bound, out),
+        }
+    }
+    if let Some(tail) = tail {
+        free_vars(tail, bound, out);
+    }
+    for n in added {
+        bound.remove(&n);
+    }
+}
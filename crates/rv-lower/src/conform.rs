@@ -0,0 +1,145 @@
+//! Conformance of trait impls to their traits.
+//!
+//! An `impl Trait for Type` block must provide exactly the methods `Trait`
+//! declares, each with the declared signature once `Self` reads as `Type` and
+//! the method's own type parameters are paired up by position. Every trait impl
+//! is checked before any body is lowered, so later stages can take a trait
+//! method's signature on trust. Traits have no default bodies or associated
+//! types, so every declared method is required and nothing else is.
+
+use rv_core::{Sym, Symbols};
+use rv_diagnostics::{E0046, E0053, E0407};
+use rv_syntax::ast::{GenericParam, MethodDecl, TraitDecl, TraitMethodSig, Ty};
+
+use crate::self_ty::{self, SelfTy};
+
+/// Err on the first way the impl's `methods` (with `Self` already resolved)
+/// fail to implement `trait_decl`: a method the trait does not declare (E0407),
+/// a declared method missing (E0046), or one whose signature differs (E0053).
+pub(crate) fn check_impl(
+    trait_decl: &TraitDecl,
+    self_ty: SelfTy,
+    methods: &[MethodDecl],
+    syms: &Symbols,
+) -> Result<(), String> {
+    let (tr, ty) = (syms.resolve(trait_decl.name), syms.resolve(self_ty.ty));
+    for m in methods {
+        if !trait_decl.methods.iter().any(|sig| sig.name == m.name) {
+            let name = syms.resolve(m.name);
+            return Err(E0407.tag(format!(
+                "method `{name}` in `impl {tr} for {ty}` is not a member of trait `{tr}`"
+            )));
+        }
+    }
+    for sig in &trait_decl.methods {
+        let name = syms.resolve(sig.name);
+        let Some(m) = methods.iter().find(|m| m.name == sig.name) else {
+            let msg = format!("impl of trait `{tr}` for `{ty}` is missing method `{name}`");
+            return Err(E0046.tag(msg));
+        };
+        check_method(sig, m, self_ty, syms).map_err(|why| {
+            E0053.tag(format!("method `{name}` in `impl {tr} for {ty}` {why}"))
+        })?;
+    }
+    Ok(())
+}
+
+/// How `m` differs from the trait's `sig`, as the rest of a sentence about `m`.
+fn check_method(
+    sig: &TraitMethodSig,
+    m: &MethodDecl,
+    self_ty: SelfTy,
+    syms: &Symbols,
+) -> Result<(), String> {
+    if m.has_self != sig.has_self {
+        let has = if m.has_self { "has a" } else { "has no" };
+        let does = if sig.has_self { "does" } else { "does not" };
+        return Err(format!("{has} `self` receiver but the trait's declaration {does}"));
+    }
+    let count = |n: usize, what: &str| format!("{n} {what}{}", if n == 1 { "" } else { "s" });
+    if m.params.len() != sig.params.len() {
+        let have = count(m.params.len(), "parameter");
+        return Err(format!("has {have} but the trait declares {}", sig.params.len()));
+    }
+    if m.generics.len() != sig.generics.len() {
+        let have = count(m.generics.len(), "type parameter");
+        return Err(format!("has {have} but the trait declares {}", sig.generics.len()));
+    }
+    for (g, want) in m.generics.iter().zip(&sig.generics) {
+        let (have, want) = (bounds(g, syms), bounds(want, syms));
+        if have != want {
+            let name = syms.resolve(g.name);
+            return Err(format!(
+                "bounds type parameter `{name}` by {} but the trait declares {}",
+                show_bounds(&have),
+                show_bounds(&want)
+            ));
+        }
+    }
+    // The trait's types as this impl must spell them: `Self` is the impl's type,
+    // and each trait type parameter is the impl method's in the same position.
+    let renames: Vec<(Sym, Sym)> =
+        sig.generics.iter().zip(&m.generics).map(|(t, i)| (t.name, i.name)).collect();
+    let expect = |ty: &Ty| rename(&self_ty::subst_ty(ty, self_ty), &renames);
+    for (p, want) in m.params.iter().zip(&sig.params) {
+        let want = expect(&want.ty);
+        if p.ty != want {
+            return Err(format!(
+                "has parameter `{}: {}` but the trait declares `{}`",
+                syms.resolve(p.name),
+                p.ty.show(syms),
+                want.show(syms)
+            ));
+        }
+    }
+    let have = m.ret.clone().unwrap_or(Ty::Unit);
+    let want = sig.ret.as_ref().map_or(Ty::Unit, expect);
+    if have != want {
+        let (have, want) = (have.show(syms), want.show(syms));
+        return Err(format!("returns `{have}` but the trait declares `{want}`"));
+    }
+    Ok(())
+}
+
+/// `g`'s bounds as a sorted set of names: their order and repetition don't matter.
+fn bounds<'a>(g: &GenericParam, syms: &'a Symbols) -> Vec<&'a str> {
+    let mut names: Vec<&str> = g.bounds.iter().map(|b| syms.resolve(*b)).collect();
+    names.sort_unstable();
+    names.dedup();
+    names
+}
+
+fn show_bounds(bounds: &[&str]) -> String {
+    match bounds {
+        [] => "nothing".to_string(),
+        _ => format!("`{}`", bounds.join(" + ")),
+    }
+}
+
+/// `ty` with every name `from` of `renames` replaced by its `to`.
+fn rename(ty: &Ty, renames: &[(Sym, Sym)]) -> Ty {
+    let name = |s: &Sym| renames.iter().find(|(from, _)| from == s).map_or(*s, |(_, to)| *to);
+    match ty {
+        Ty::Adt(s) => Ty::Adt(name(s)),
+        Ty::Param(s) => Ty::Param(name(s)),
+        Ty::Ref { mutable, inner } => {
+            Ty::Ref { mutable: *mutable, inner: Box::new(rename(inner, renames)) }
+        }
+        Ty::Generic { base, args } => Ty::Generic {
+            base: *base,
+            args: args.iter().map(|a| rename(a, renames)).collect(),
+        },
+        Ty::Fn { params, ret } => Ty::Fn {
+            params: params.iter().map(|p| rename(p, renames)).collect(),
+            ret: Box::new(rename(ret, renames)),
+        },
+        Ty::I64
+        | Ty::IntN(_)
+        | Ty::F64
+        | Ty::Bool
+        | Ty::String
+        | Ty::Unit
+        | Ty::ImplTrait(_)
+        | Ty::Term(_) => ty.clone(),
+    }
+}
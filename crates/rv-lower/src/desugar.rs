@@ -0,0 +1,124 @@
+//! The desugaring stage: a module's executable items, rewritten into the core
+//! language the rest of lowering works on.
+//!
+//! The stage runs [`PASSES`] in order over the whole module, each an AST to AST
+//! rewrite. After it, no method mentions `Self` and no `use` remains: every
+//! imported variant is spelled as its full path. A rewritten statement keeps
+//! its place in its block, and so the source lines the parser recorded for it.
+//! `rvc --emit desugared` prints the result with [`crate::print`].
+//!
+//! Method calls, `?`, `format`, and closures are still expanded while a body is
+//! lowered to IR, where the types they need are at hand.
+
+use std::collections::HashSet;
+
+use rv_core::{Sym, Symbols};
+use rv_syntax::ast::{Item, Module};
+
+use crate::imports::Imports;
+use crate::prelude;
+use crate::self_ty::SelfTy;
+
+/// One rewrite of the stage.
+trait Pass {
+    /// The pass's name, for tracing.
+    fn name(&self) -> &'static str;
+    fn run(&self, items: &mut Vec<Item>, syms: &mut Symbols) -> Result<(), String>;
+}
+
+/// The passes, in the order they run.
+const PASSES: &[&dyn Pass] = &[&ResolveSelf, &ResolveImports];
+
+/// `module`'s executable items, desugared. Proof-fragment items route to the
+/// kernel, not to lowering, so they are left out.
+pub fn desugar(module: &Module, syms: &mut Symbols) -> Result<Module, String> {
+    let frags = rv_syntax::classify(module);
+    let mut items: Vec<Item> = (module.items.iter().zip(&frags))
+        .filter(|(_, frag)| frag.is_executable())
+        .map(|(item, _)| item.clone())
+        .collect();
+    for pass in PASSES {
+        let _span = tracing::debug_span!("desugar", pass = pass.name()).entered();
+        pass.run(&mut items, syms)?;
+    }
+    Ok(Module { items })
+}
+
+/// `Self` in a method, as the impl's type (see [`crate::self_ty`]).
+struct ResolveSelf;
+
+impl Pass for ResolveSelf {
+    fn name(&self) -> &'static str {
+        "self"
+    }
+
+    fn run(&self, items: &mut Vec<Item>, syms: &mut Symbols) -> Result<(), String> {
+        let self_sym = syms.intern("Self");
+        for item in items {
+            if let Item::Impl(im) = item {
+                let self_ty = SelfTy { self_sym, ty: im.type_name };
+                im.methods = im.methods.iter().map(|m| self_ty.method(m)).collect();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Imported variants, as their full paths (see [`crate::imports`]).
+struct ResolveImports;
+
+impl Pass for ResolveImports {
+    fn name(&self) -> &'static str {
+        "imports"
+    }
+
+    fn run(&self, items: &mut Vec<Item>, syms: &mut Symbols) -> Result<(), String> {
+        let uses: Vec<_> = items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Use(u) => Some(u.clone()),
+                _ => None,
+            })
+            .collect();
+        if uses.is_empty() {
+            return Ok(());
+        }
+        items.retain(|item| !matches!(item, Item::Use(_)));
+        // The prelude's enums can be imported too, unless the module shadows them.
+        let declared: HashSet<Sym> = items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Struct(s) => Some(s.name),
+                Item::Enum(e) => Some(e.name),
+                Item::TypeAlias(a) => Some(a.name),
+                Item::Trait(t) => Some(t.name),
+                _ => None,
+            })
+            .collect();
+        let prelude = prelude::items(&declared, &HashSet::new(), syms);
+        let enums: Vec<_> = (items.iter().chain(&prelude))
+            .filter_map(|item| match item {
+                Item::Enum(e) => Some(e),
+                _ => None,
+            })
+            .collect();
+        let fns = items.iter().filter_map(|item| match item {
+            Item::Fn(f) => Some(f.name),
+            _ => None,
+        });
+        let uses: Vec<_> = uses.iter().collect();
+        let imports = Imports::new(&uses, &enums, fns.collect(), syms)?;
+        for item in items.iter_mut() {
+            match item {
+                Item::Fn(f) => imports.fn_decl(f, syms)?,
+                Item::Impl(im) => {
+                    for m in &mut im.methods {
+                        imports.method(m, syms)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
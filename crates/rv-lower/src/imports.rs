@@ -0,0 +1,366 @@
+//! `use` imports of enum variants.
+//!
+//! `use Shape::Circle;` lets `Circle` stand for `Shape::Circle`, and
+//! `use Shape::*;` does the same for every variant of `Shape`. Imports are
+//! resolved by a [`crate::desugar`] pass, which rewrites each function and
+//! method: a bare name called, read, or matched on becomes the variant path it
+//! imports, so the rest of lowering only sees full paths.
+//!
+//! A local binding in scope keeps its name, and so does a function of the
+//! module when it is called. An explicit import takes precedence over a glob; a
+//! name two globs bring in, which no explicit import settles, is ambiguous, and
+//! is an error where it is used rather than where it is imported.
+
+use std::collections::{HashMap, HashSet};
+
+use rv_core::{Sym, Symbols};
+use rv_diagnostics::{close_matches, did_you_mean, E0252, E0432, E0659};
+use rv_syntax::ast::{
+    Block, EnumDecl, Expr, FnDecl, MatchArm, MethodDecl, Param, PatBind, Pattern, Stmt, UseDecl,
+};
+
+/// The variant names a module's `use` items bring into scope.
+pub(crate) struct Imports {
+    /// A variant imported by name -> its enum.
+    explicit: HashMap<Sym, Sym>,
+    /// A variant name -> the glob-imported enums that declare it.
+    globs: HashMap<Sym, Vec<Sym>>,
+    /// The module's functions, which a bare call names before any variant.
+    fns: HashSet<Sym>,
+}
+
+impl Imports {
+    /// Check each of `uses` against `enums` (the prelude's included), and
+    /// collect what they import.
+    pub fn new(
+        uses: &[&UseDecl],
+        enums: &[&EnumDecl],
+        fns: HashSet<Sym>,
+        syms: &Symbols,
+    ) -> Result<Self, String> {
+        let by_name: HashMap<Sym, &EnumDecl> = enums.iter().map(|e| (e.name, *e)).collect();
+        let mut imports = Imports { explicit: HashMap::new(), globs: HashMap::new(), fns };
+        for u in uses {
+            let line = u.line;
+            let e = syms.resolve(u.enum_name);
+            let path = match u.variant {
+                Some(v) => format!("{e}::{}", syms.resolve(v)),
+                None => format!("{e}::*"),
+            };
+            let Some(decl) = by_name.get(&u.enum_name) else {
+                let names = enums.iter().map(|d| syms.resolve(d.name));
+                return Err(format!(
+                    "line {line}: {}",
+                    E0432.tag(format!(
+                        "unresolved import `{path}`: no enum `{e}` in this module{}",
+                        did_you_mean(&close_matches(e, names))
+                    ))
+                ));
+            };
+            let variants = decl.variants.iter().map(|v| v.name);
+            let Some(v) = u.variant else {
+                for v in variants {
+                    let from = imports.globs.entry(v).or_default();
+                    if !from.contains(&u.enum_name) {
+                        from.push(u.enum_name);
+                    }
+                }
+                continue;
+            };
+            if !decl.variants.iter().any(|d| d.name == v) {
+                let n = syms.resolve(v);
+                return Err(format!(
+                    "line {line}: {}",
+                    E0432.tag(format!(
+                        "unresolved import `{path}`: no variant `{n}` in `{e}`{}",
+                        did_you_mean(&close_matches(n, variants.map(|v| syms.resolve(v))))
+                    ))
+                ));
+            }
+            match imports.explicit.insert(v, u.enum_name) {
+                Some(prev) if prev != u.enum_name => {
+                    let (n, prev) = (syms.resolve(v), syms.resolve(prev));
+                    return Err(format!(
+                        "line {line}: {}",
+                        E0252.tag(format!(
+                            "`{n}` is imported twice: from `{prev}` and from `{e}`; import one, \
+                             and write the other's path"
+                        ))
+                    ));
+                }
+                _ => {}
+            }
+        }
+        Ok(imports)
+    }
+
+    /// Write every imported variant `d` names as its full path.
+    pub fn fn_decl(&self, d: &mut FnDecl, syms: &Symbols) -> Result<(), String> {
+        self.callable(&mut d.params, &mut d.requires, &mut d.ensures, &mut d.body, syms)
+    }
+
+    /// [`Self::fn_decl`] for a method.
+    pub fn method(&self, d: &mut MethodDecl, syms: &Symbols) -> Result<(), String> {
+        self.callable(&mut d.params, &mut d.requires, &mut d.ensures, &mut d.body, syms)
+    }
+
+    fn callable(
+        &self,
+        params: &mut [Param],
+        requires: &mut [Expr],
+        ensures: &mut [Expr],
+        body: &mut Block,
+        syms: &Symbols,
+    ) -> Result<(), String> {
+        let scope = params.iter().map(|p| p.name).collect();
+        let mut r = Resolver { imports: self, scope, line: None, syms };
+        let refinements = params.iter_mut().filter_map(|p| p.refinement.as_mut());
+        for e in refinements.chain(requires).chain(ensures) {
+            r.expr(e)?;
+        }
+        r.block(body)
+    }
+}
+
+/// The rewrite of one function body.
+struct Resolver<'a> {
+    imports: &'a Imports,
+    /// The local bindings in scope, innermost last.
+    scope: Vec<Sym>,
+    /// The line of the statement being rewritten, outside a spec clause.
+    line: Option<u32>,
+    syms: &'a Symbols,
+}
+
+impl Resolver<'_> {
+    /// The enum whose variant the bare `name` is, if it is one: not a binding
+    /// in scope, and imported exactly once.
+    fn variant_enum(&self, name: Sym) -> Result<Option<Sym>, String> {
+        if self.scope.contains(&name) {
+            return Ok(None);
+        }
+        if let Some(&e) = self.imports.explicit.get(&name) {
+            return Ok(Some(e));
+        }
+        match self.imports.globs.get(&name).map(Vec::as_slice) {
+            None | Some([]) => Ok(None),
+            Some([e]) => Ok(Some(*e)),
+            Some([a, b, ..]) => {
+                let [n, a, b] = [name, *a, *b].map(|s| self.syms.resolve(s));
+                Err(self.at(E0659.tag(format!(
+                    "`{n}` is ambiguous: `use {a}::*` and `use {b}::*` both import it; write \
+                     `{a}::{n}` or `{b}::{n}`, or import one by name"
+                ))))
+            }
+        }
+    }
+
+    fn at(&self, msg: String) -> String {
+        match self.line {
+            Some(line) => format!("line {line}: {msg}"),
+            None => msg,
+        }
+    }
+
+    fn block(&mut self, b: &mut Block) -> Result<(), String> {
+        self.block_then(b, None)
+    }
+
+    /// [`Self::block`] followed by `tail`, in the block's scope (an `if`
+    /// expression's branch and its value).
+    fn block_then(&mut self, b: &mut Block, tail: Option<&mut Expr>) -> Result<(), String> {
+        rv_arena::stack::grow(|| self.block_then_here(b, tail))
+    }
+
+    fn block_then_here(&mut self, b: &mut Block, tail: Option<&mut Expr>) -> Result<(), String> {
+        let (outer_line, outer_scope) = (self.line, self.scope.len());
+        for (stmt, span) in b.stmts.iter_mut().zip(&b.spans) {
+            self.line = Some(span.start);
+            self.stmt(stmt)?;
+        }
+        if let Some(tail) = tail {
+            self.expr(tail)?;
+        }
+        self.line = outer_line;
+        self.scope.truncate(outer_scope);
+        Ok(())
+    }
+
+    fn stmt(&mut self, s: &mut Stmt) -> Result<(), String> {
+        match s {
+            Stmt::Let { name, init, .. } => {
+                if let Some(init) = init {
+                    self.expr(init)?;
+                }
+                self.scope.push(*name);
+            }
+            Stmt::Assign { value, .. } => self.expr(value)?,
+            Stmt::DerefAssign { place, value } => {
+                self.expr(place)?;
+                self.expr(value)?;
+            }
+            Stmt::If { cond, then_blk, else_blk } => {
+                self.expr(cond)?;
+                self.block(then_blk)?;
+                if let Some(b) = else_blk {
+                    self.block(b)?;
+                }
+            }
+            Stmt::While { cond, invariants, body, .. } => {
+                self.expr(cond)?;
+                for e in invariants {
+                    self.expr(e)?;
+                }
+                self.block(body)?;
+            }
+            Stmt::Match { scrut, arms } => {
+                self.expr(scrut)?;
+                for MatchArm { pat, body } in arms {
+                    let outer = self.scope.len();
+                    self.pattern(pat)?;
+                    self.block(body)?;
+                    self.scope.truncate(outer);
+                }
+            }
+            Stmt::Return(Some(e))
+            | Stmt::Break { value: Some(e), .. }
+            | Stmt::Panic(Some(e))
+            | Stmt::Assert(e)
+            | Stmt::Expr(e) => self.expr(e)?,
+            Stmt::Return(None)
+            | Stmt::Break { value: None, .. }
+            | Stmt::Panic(None)
+            | Stmt::Continue { .. } => {}
+        }
+        Ok(())
+    }
+
+    /// Resolve a bare variant in `p`, and bring its binders into scope.
+    fn pattern(&mut self, p: &mut Pattern) -> Result<(), String> {
+        match p {
+            Pattern::Bare { variant, binds } => {
+                let (variant, binds) = (*variant, std::mem::take(binds));
+                // A bare name in a pattern is never a binding, so no local hides it.
+                let outer = std::mem::take(&mut self.scope);
+                let found = self.variant_enum(variant);
+                self.scope = outer;
+                let Some(enum_name) = found? else {
+                    return Err(self.at(unresolved_pattern(variant, self.syms)));
+                };
+                *p = Pattern::Variant { enum_name, variant, binds };
+                self.pattern(p)?;
+            }
+            Pattern::Variant { binds, .. } => {
+                let names = binds.iter().filter_map(|b| match b {
+                    PatBind::Name(n) => Some(*n),
+                    PatBind::Wildcard => None,
+                });
+                self.scope.extend(names);
+            }
+            Pattern::Or(alts) => {
+                for a in alts {
+                    self.pattern(a)?;
+                }
+            }
+            Pattern::Str(_) | Pattern::Float(_) | Pattern::Wildcard => {}
+        }
+        Ok(())
+    }
+
+    fn expr(&mut self, e: &mut Expr) -> Result<(), String> {
+        rv_arena::stack::grow(|| self.expr_here(e))
+    }
+
+    fn expr_here(&mut self, e: &mut Expr) -> Result<(), String> {
+        match e {
+            Expr::Var(name) if !self.imports.fns.contains(name) => {
+                if let Some(enum_name) = self.variant_enum(*name)? {
+                    *e = Expr::EnumCtor { enum_name, variant: *name, args: Vec::new() };
+                }
+            }
+            Expr::Call { func, args } => {
+                for a in args.iter_mut() {
+                    self.expr(a)?;
+                }
+                if self.imports.fns.contains(func) {
+                    return Ok(());
+                }
+                if let Some(enum_name) = self.variant_enum(*func)? {
+                    let args = std::mem::take(args);
+                    *e = Expr::EnumCtor { enum_name, variant: *func, args };
+                }
+            }
+            Expr::StructLit { fields, .. } => {
+                for (_, f) in fields {
+                    self.expr(f)?;
+                }
+            }
+            Expr::EnumCtor { args, .. } => {
+                for a in args {
+                    self.expr(a)?;
+                }
+            }
+            Expr::MethodCall { recv, args, .. } => {
+                self.expr(recv)?;
+                for a in args {
+                    self.expr(a)?;
+                }
+            }
+            Expr::Lambda { params, body } => {
+                let outer = self.scope.len();
+                self.scope.extend(params.iter().copied());
+                self.expr(body)?;
+                self.scope.truncate(outer);
+            }
+            Expr::Loop { body, .. } => self.block(body)?,
+            Expr::If { cond, then_blk, then_val, else_blk, else_val } => {
+                self.expr(cond)?;
+                self.block_then(then_blk, then_val.as_deref_mut())?;
+                self.block_then(else_blk, else_val.as_deref_mut())?;
+            }
+            Expr::Bin(_, a, b) => {
+                self.expr(a)?;
+                self.expr(b)?;
+            }
+            Expr::Un(_, a)
+            | Expr::Cast { expr: a, .. }
+            | Expr::Field { base: a, .. }
+            | Expr::Ref { expr: a, .. }
+            | Expr::Deref(a)
+            | Expr::Try(a)
+            | Expr::Return(Some(a)) => self.expr(a)?,
+            Expr::Int(_)
+            | Expr::SuffixedInt(..)
+            | Expr::Float(_)
+            | Expr::Str(_)
+            | Expr::Bool(_)
+            | Expr::Unit
+            | Expr::Var(_)
+            | Expr::Inst { .. }
+            | Expr::Return(None) => {}
+            // Proof-fragment forms never reach the executable lowering.
+            Expr::Apply { .. }
+            | Expr::MatchExpr { .. }
+            | Expr::Fun { .. }
+            | Expr::Forall { .. }
+            | Expr::LetIn { .. }
+            | Expr::Arrow(..)
+            | Expr::TypeUniv(_)
+            | Expr::Prop
+            | Expr::Hole
+            | Expr::Rewrite { .. }
+            | Expr::Decide
+            | Expr::ByCases { .. } => {}
+        }
+        Ok(())
+    }
+}
+
+/// The error for a bare name in a pattern that no `use` imports.
+pub(crate) fn unresolved_pattern(name: Sym, syms: &Symbols) -> String {
+    let name = syms.resolve(name);
+    format!(
+        "pattern `{name}` is not a variant path: write `Enum::{name}` to match a variant, or \
+         `_` to match anything"
+    )
+}
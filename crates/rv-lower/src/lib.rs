@@ -2,27 +2,44 @@
 //!
 //! Each surface function becomes a [`rv_ir::Function<Parsed>`] with an explicit
 //! control-flow graph of basic [`rv_ir::Block`]s. Structured control flow
-//! (`if`/`while`) is compiled to blocks ending in `Goto`/`Branch`/`Return`.
+//! (`if`/`while`/`loop`, `break`/`continue`) is compiled to blocks ending in
+//! `Goto`/`Branch`/`Return`.
 //!
 //! Because the program is in the `Parsed` phase, all `Ty` fields are `()` and no
 //! `Drop` terminators are emitted (memory strategy is inferred later).
+//!
+//! Every module is lowered together with the [`prelude`]'s `Option` and
+//! `Result`, less whatever it does not use.
+//!
+//! Lowering starts with the [`desugar`] stage, so a body reaches IR lowering
+//! with `Self` and imported variants already spelled out.
 
 mod build;
+mod conform;
+mod desugar;
+mod imports;
+mod prelude;
+mod print;
+mod self_ty;
 mod spec;
 mod types;
+mod validate;
 
-use std::collections::HashMap;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use rv_core::Sym;
-use rv_ir::{Function, Parsed, Program, TraitImpl};
+use rv_ir::{Function, Parsed, Program, SourceLines, TraitImpl};
 use rv_syntax::ast::{
-    Block as AstBlock, Expr as AstExpr, GenericParam, Item, MethodDecl, Module, Param, TraitDecl,
-    Ty as AstTy,
+    Block as AstBlock, Expr as AstExpr, GenericParam, ImplDecl, Item, MethodDecl, Module, Param,
+    TraitDecl, Ty as AstTy,
 };
 
+pub use desugar::desugar;
+pub use print::print;
+
 use build::FnBuilder;
-use types::Types;
+use self_ty::SelfTy;
+use types::{TypeScope, Types};
 
 /// Lower a whole module to an `rv_ir::Program<Parsed>`.
 ///
@@ -33,6 +50,17 @@ pub fn lower(
     module: &Module,
     syms: &mut rv_core::Symbols,
 ) -> Result<Program<Parsed>, String> {
+    lower_with_lines(module, syms).map(|(prog, _)| prog)
+}
+
+/// [`lower`], also returning the source line of every lowered statement and
+/// terminator so later passes can point their errors at the source.
+pub fn lower_with_lines(
+    module: &Module,
+    syms: &mut rv_core::Symbols,
+) -> Result<(Program<Parsed>, SourceLines), String> {
+    let _span = tracing::debug_span!("lower").entered();
+    let module = &desugar::desugar(module, syms)?;
     // Partition items: gather all type declarations before any function, so a
     // function may reference types declared later in the module.
     let mut struct_decls = Vec::new();
@@ -41,14 +69,7 @@ pub fn lower(
     let mut alias_decls = Vec::new();
     let mut trait_decls = Vec::new();
     let mut impl_decls = Vec::new();
-    // Classify items once: the proof fragment (relations, proof `fn`s, `def`/`axiom`/…)
-    // routes to the kernel, not to the executable IR, so the lowering pipeline skips it.
-    // Shared data types and executable items are kept.
-    let frags = rv_syntax::classify(module);
-    for (item, frag) in module.items.iter().zip(&frags) {
-        if !frag.is_executable() {
-            continue;
-        }
+    for item in &module.items {
         match item {
             Item::Struct(s) => struct_decls.push(s),
             Item::Enum(e) => enum_decls.push(e),
@@ -56,13 +77,57 @@ pub fn lower(
             Item::Fn(f) => fn_decls.push(f),
             Item::Trait(t) => trait_decls.push(t),
             Item::Impl(i) => impl_decls.push(i),
-            // Proof-fragment items never satisfy `is_executable`; this is unreachable,
-            // but keeps the match exhaustive.
-            Item::Axiom(_) | Item::Def(_) | Item::Instance(_) | Item::Mutual(_) => {}
+            // The desugared module holds no imports and no proof-fragment items;
+            // this is unreachable, but keeps the match exhaustive.
+            Item::Use(_)
+            | Item::Axiom(_)
+            | Item::Def(_)
+            | Item::Instance(_)
+            | Item::Mutual(_) => {}
+        }
+    }
+    // The prelude's types, unless the module declares a type of the same name.
+    let declared: HashSet<Sym> = (struct_decls.iter().map(|s| s.name))
+        .chain(enum_decls.iter().map(|e| e.name))
+        .chain(alias_decls.iter().map(|a| a.name))
+        .chain(trait_decls.iter().map(|t| t.name))
+        .collect();
+    // Inherent methods the module gives a primitive type itself, which replace the
+    // prelude's of the same name.
+    let own_methods: HashSet<(Sym, Sym)> = impl_decls
+        .iter()
+        .filter(|im| im.trait_name.is_none())
+        .flat_map(|im| im.methods.iter().map(|m| (im.type_name, m.name)))
+        .collect();
+    let prelude_items = prelude::items(&declared, &own_methods, syms);
+    let (mut prelude_types, mut prelude_impls) = (HashSet::new(), Vec::new());
+    for item in &prelude_items {
+        match item {
+            Item::Enum(e) => {
+                prelude_types.insert(e.name);
+                enum_decls.push(e);
+            }
+            Item::Impl(i) => prelude_impls.push(i),
+            _ => {}
         }
     }
 
+    let decls = validate::Decls {
+        structs: &struct_decls,
+        enums: &enum_decls,
+        fns: &fn_decls,
+        traits: &trait_decls,
+        impls: &impl_decls,
+    };
+    validate::check_unique_names(&decls, syms)?;
+
     let mut types = Types::build(&struct_decls, &enum_decls, &alias_decls, syms)?;
+    for im in impl_decls.iter().chain(&prelude_impls) {
+        let primitive = rv_syntax::ast::Ty::primitive(syms.resolve(im.type_name));
+        if let Some(ty) = primitive.filter(|_| !types.is_adt(im.type_name)) {
+            types.declare_primitive(im.type_name, types.resolve_ty(&ty, &TypeScope::default()));
+        }
+    }
     let trait_by_name: HashMap<Sym, &TraitDecl> = trait_decls.iter().map(|tr| (tr.name, *tr)).collect();
 
     // Traits produce no IR; record their method-name sets for optional validation.
@@ -74,58 +139,122 @@ pub fn lower(
     // Register every impl method into the resolution table BEFORE lowering any
     // bodies, so a method may call another method (forward references resolve).
     // We remember the mangled name chosen for each method so we lower its body
-    // under that exact symbol.
-    let mut planned_methods: Vec<(Sym, &MethodDecl, Sym)> = Vec::new();
-    for im in &impl_decls {
-        let mut provided: HashSet<Sym> = HashSet::new();
+    // under that exact symbol. Desugaring already resolved `Self` to the impl's type.
+    let self_sym = syms.intern("Self");
+    let mut planned_methods: Vec<(Sym, &MethodDecl, Sym, TypeScope)> = Vec::new();
+    let mut prelude_fns = HashSet::new();
+    let from_prelude = std::iter::repeat(false).zip(&impl_decls);
+    for (in_prelude, im) in from_prelude.chain(std::iter::repeat(true).zip(&prelude_impls)) {
+        let self_ty = SelfTy { self_sym, ty: im.type_name };
+        let mut mangled = Vec::with_capacity(im.methods.len());
         for m in &im.methods {
-            let mangled = types.register_method(im.type_name, m.name, syms)?;
-            provided.insert(m.name);
-            // (receiver ADT name, the method decl, the mangled function name)
-            planned_methods.push((im.type_name, m, mangled));
+            mangled.push(types.register_method(im.type_name, im.trait_name, m.name, syms)?);
         }
-        // For a trait impl, optionally check the declared methods are all present.
+        // A trait impl must provide exactly the trait's methods, as declared.
         if let Some(tr) = im.trait_name {
             let trait_decl = trait_by_name.get(&tr).ok_or_else(|| {
                 format!("impl references unknown trait `{}`", syms.resolve(tr))
             })?;
-            check_trait_impl_signatures(trait_decl, im, syms)?;
-            types.check_trait_impl(tr, im.type_name, &provided, syms)?;
+            conform::check_impl(trait_decl, self_ty, &im.methods, syms)?;
+        }
+        // (receiver ADT name, the method decl, the mangled function name, and the
+        // method's type parameters, scoped once for its signature and body)
+        for (m, n) in im.methods.iter().zip(mangled) {
+            if in_prelude {
+                prelude_fns.insert(n);
+            }
+            let scope = TypeScope::default().enter(&m.generics, syms);
+            planned_methods.push((im.type_name, m, n, scope));
         }
     }
 
-    // Record each function's/method's return ADT (when it returns a struct/enum),
-    // so `adt_of_expr` can resolve the ADT of a *call result* — letting `match`,
-    // `?`, and method calls compose on call results.
-    let ret_adt = |ret: &Option<rv_syntax::ast::Ty>| -> Option<Sym> {
-        match ret {
-            Some(rv_syntax::ast::Ty::Adt(n)) => Some(*n),
-            Some(rv_syntax::ast::Ty::Generic { base, .. }) => Some(*base),
+    // Record each function's/method's return ADT (when it returns a struct/enum,
+    // or references to one), so `adt_of_expr` can resolve the ADT of a *call
+    // result* — letting `match`, `?`, field access and method calls compose on
+    // call results.
+    let ret_adt = |ret: &Option<rv_syntax::ast::Ty>| -> Option<(Sym, usize)> {
+        let (mut ty, mut refs) = (ret.as_ref()?, 0);
+        while let rv_syntax::ast::Ty::Ref { inner, .. } = ty {
+            (ty, refs) = (inner, refs + 1);
+        }
+        match ty {
+            rv_syntax::ast::Ty::Adt(n) => Some((*n, refs)),
+            rv_syntax::ast::Ty::Generic { base, .. } => Some((*base, refs)),
             _ => None,
         }
     };
-    for decl in &fn_decls {
-        if let Some(a) = ret_adt(&decl.ret) {
-            if types.is_adt(a) {
-                types.set_fn_ret(decl.name, a);
+    // A function's type parameters are scoped once, so its registered signature
+    // and its lowered body name the same parameters.
+    let fn_scopes: Vec<TypeScope> =
+        fn_decls.iter().map(|decl| TypeScope::default().enter(&decl.generics, syms)).collect();
+    for (decl, scope) in fn_decls.iter().zip(&fn_scopes) {
+        if is_opaque(decl.ret.as_ref()) {
+            continue;
+        }
+        let ret = decl.ret.as_ref().map_or(rv_core::Ty::Unit, |r| types.resolve_ty(r, scope));
+        let params = decl.params.iter().map(|p| types.resolve_ty(&p.ty, scope)).collect();
+        types.register_fn(decl.name, scope.params(), rv_core::Ty::Fn(params, Box::new(ret)));
+        if let Some((a, refs)) = ret_adt(&decl.ret) {
+            if types.is_adt(a) && !scope.contains(a) {
+                types.set_fn_ret(decl.name, a, refs);
             }
         }
+        if let Some(ty) = decl.ret.as_ref().and_then(|r| primitive_ret(&types, r, scope)) {
+            types.set_fn_ret_primitive(decl.name, ty);
+        }
     }
-    for (_, m, mangled) in &planned_methods {
-        if let Some(a) = ret_adt(&m.ret) {
-            if types.is_adt(a) {
-                types.set_fn_ret(*mangled, a);
+    for (_, m, mangled, scope) in &planned_methods {
+        if let Some((a, refs)) = ret_adt(&m.ret) {
+            if types.is_adt(a) && !scope.contains(a) {
+                types.set_fn_ret(*mangled, a, refs);
             }
         }
+        if let Some(ty) = m.ret.as_ref().and_then(|r| primitive_ret(&types, r, scope)) {
+            types.set_fn_ret_primitive(*mangled, ty);
+        }
     }
 
     let mut funcs = Vec::new();
+    let mut lines = SourceLines::new();
+    // A body returning `impl Trait` is lowered before the rest: its `return`s fix
+    // the concrete type, which its callers then see in its signature.
+    let impls: Vec<&ImplDecl> = impl_decls.iter().chain(&prelude_impls).copied().collect();
+    let mut fn_bodies: Vec<Option<Vec<Function<Parsed>>>> = fn_decls.iter().map(|_| None).collect();
+    for (i, (decl, scope)) in fn_decls.iter().zip(&fn_scopes).enumerate() {
+        let Some(ret) = decl.ret.as_ref().filter(|r| is_opaque(Some(r))) else { continue };
+        let lowered = lower_fn(decl, scope, &types, syms, &mut lines)?;
+        let adt = check_opaque(&lowered[0], ret, &impls, &trait_by_name, syms)?;
+        let params = decl.params.iter().map(|p| types.resolve_ty(&p.ty, scope)).collect();
+        let sig = rv_core::Ty::Fn(params, Box::new(rv_core::Ty::Adt(adt, vec![])));
+        types.register_fn(decl.name, scope.params(), sig);
+        types.set_fn_ret(decl.name, adt, 0);
+        fn_bodies[i] = Some(lowered);
+    }
+    let mut method_bodies: Vec<Option<Vec<Function<Parsed>>>> =
+        planned_methods.iter().map(|_| None).collect();
+    for (i, (type_name, m, mangled, scope)) in planned_methods.iter().enumerate() {
+        let Some(ret) = m.ret.as_ref().filter(|r| is_opaque(Some(r))) else { continue };
+        let lowered = lower_method(*type_name, m, *mangled, scope, &types, syms, &mut lines)?;
+        let adt = check_opaque(&lowered[0], ret, &impls, &trait_by_name, syms)?;
+        types.set_fn_ret(*mangled, adt, 0);
+        method_bodies[i] = Some(lowered);
+    }
     // Ordinary functions first, then desugared impl methods.
-    for decl in fn_decls {
-        funcs.extend(lower_fn(decl, &types, syms)?);
+    for ((decl, scope), body) in fn_decls.iter().zip(&fn_scopes).zip(fn_bodies) {
+        match body {
+            Some(lowered) => funcs.extend(lowered),
+            None => funcs.extend(lower_fn(decl, scope, &types, syms, &mut lines)?),
+        }
     }
-    for (type_name, m, mangled) in planned_methods {
-        funcs.extend(lower_method(type_name, m, mangled, &types, syms)?);
+    for ((type_name, m, mangled, scope), body) in planned_methods.into_iter().zip(method_bodies) {
+        if let Some(lowered) = body {
+            funcs.extend(lowered);
+            continue;
+        }
+        // The prelude's lines are not the module's, so they go unrecorded.
+        let prelude_lines = &mut SourceLines::new();
+        let lines = if prelude_fns.contains(&mangled) { prelude_lines } else { &mut lines };
+        funcs.extend(lower_method(type_name, m, mangled, &scope, &types, syms, lines)?);
     }
     let trait_impls = impl_decls
         .iter()
@@ -133,59 +262,29 @@ pub fn lower(
             im.trait_name.map(|trait_name| TraitImpl { trait_name, type_name: im.type_name })
         })
         .collect();
-    Ok(Program { types: types.defs, trait_impls, funcs })
-}
-
-/// Validate the executable portion of a trait implementation before methods are
-/// lowered and erased. Trait dispatch is still static/desugared, but accepting a
-/// same-named method with a different callable shape would make a bound lie.
-fn check_trait_impl_signatures(
-    trait_decl: &TraitDecl,
-    implementation: &rv_syntax::ast::ImplDecl,
-    syms: &rv_core::Symbols,
-) -> Result<(), String> {
-    for required in &trait_decl.methods {
-        let method = implementation
-            .methods
-            .iter()
-            .find(|method| method.name == required.name)
-            .ok_or_else(|| {
-                format!(
-                    "impl of trait `{}` for `{}` is missing method `{}`",
-                    syms.resolve(trait_decl.name),
-                    syms.resolve(implementation.type_name),
-                    syms.resolve(required.name)
-                )
-            })?;
-        if method.has_self != required.has_self
-            || method.params.len() != required.params.len()
-            || method.ret != required.ret
-            || !method.generics.is_empty()
-            || method
-                .params
-                .iter()
-                .zip(&required.params)
-                .all(|(actual, expected)| actual.ty == expected.ty)
-                == false
-        {
-            return Err(format!(
-                "method `{}` in impl of trait `{}` for `{}` does not match the trait signature",
-                syms.resolve(required.name),
-                syms.resolve(trait_decl.name),
-                syms.resolve(implementation.type_name)
-            ));
+    let mut prog = Program { types: types.defs, trait_impls, funcs };
+    prelude::prune(&mut prog, &prelude_fns, &prelude_types);
+    #[cfg(debug_assertions)]
+    if let Err(e) = rv_ir::check_source_lines(&prog, &lines) {
+        panic!("internal error: inconsistent source lines: {e}");
+    }
+    #[cfg(debug_assertions)]
+    for f in &prog.funcs {
+        if let Err(e) = f.validate_mutability() {
+            panic!("internal error: in `{}`, {e}", syms.resolve(f.name));
         }
     }
-    Ok(())
+    Ok((prog, lines))
 }
 
 /// Lower a single function declaration into IR.
 fn lower_fn(
     decl: &rv_syntax::ast::FnDecl,
+    scope: &TypeScope,
     types: &Types,
     syms: &mut rv_core::Symbols,
+    lines: &mut SourceLines,
 ) -> Result<Vec<Function<Parsed>>, String> {
-    let type_params: Vec<Sym> = decl.generics.iter().map(|g| g.name).collect();
     lower_callable(
         decl.name,
         &decl.generics,
@@ -194,9 +293,10 @@ fn lower_fn(
         &decl.ensures,
         &decl.body,
         decl.ret.as_ref(),
+        scope,
         types,
         syms,
-        type_params,
+        lines,
     )
 }
 
@@ -208,29 +308,33 @@ fn lower_method(
     type_name: Sym,
     decl: &MethodDecl,
     mangled: Sym,
+    scope: &TypeScope,
     types: &Types,
     syms: &mut rv_core::Symbols,
+    lines: &mut SourceLines,
 ) -> Result<Vec<Function<Parsed>>, String> {
     // The method's own generic parameters scope its signature/body types.
-    let type_params: Vec<Sym> = decl.generics.iter().map(|g| g.name).collect();
-    let scope: HashSet<Sym> = type_params.iter().copied().collect();
-
-    let mut b = FnBuilder::new(types);
+    let mut b = FnBuilder::new(types, scope.clone());
+    b.set_ret(decl.ret.as_ref());
     let mut params = Vec::new();
 
-    // A `self` receiver becomes the first parameter, typed as the impl's ADT.
+    // A `self` receiver becomes the first parameter, typed as the impl's ADT or
+    // primitive.
     if decl.has_self {
         let self_sym = syms.intern("self");
         let id = b.new_local(Some(self_sym));
-        b.set_local_adt(id, type_name);
+        match types.primitive(type_name) {
+            Some(ty) => b.set_local_ty(id, ty.clone()),
+            None => b.set_local_adt(id, type_name),
+        }
         b.bind(self_sym, id);
         params.push(id);
     }
     // Remaining ordinary parameters.
-    bind_params(&mut b, &decl.params, &scope, types, &mut params);
+    bind_params(&mut b, &decl.params, scope, types, &mut params);
 
     // `self` and any struct-typed parameter can be projected in a spec.
-    let mut var_struct = struct_typed_params(&decl.params, &scope, types);
+    let mut var_struct = struct_typed_params(&decl.params, scope, types);
     if decl.has_self && types.struct_info(type_name).is_some() {
         var_struct.insert(syms.intern("self"), type_name);
     }
@@ -244,22 +348,21 @@ fn lower_method(
     )?;
     post = apply_return_alias_refinement(post, decl.ret.as_ref(), types, &var_struct, syms)?;
     post = apply_return_width_contract(post, decl.ret.as_ref(), syms);
-    b.lower_block(&decl.body, syms)?;
-    b.finish_with_default_return();
+    b.lower_body(&decl.body, syms)?;
 
     let lifted = b.take_lifted();
-    let (locals, blocks) = b.into_parts();
+    for (site, line) in b.take_lines(mangled) {
+        lines.insert(site, line);
+    }
+    let ret = lowered_ret(&b, decl.ret.as_ref(), scope, types, syms)?;
+    let (locals, blocks) = b.finish()?;
     let mut out = vec![Function {
         name: mangled,
-        type_params,
-        generic_bounds: decl
-            .generics
-            .iter()
-            .map(|param| (param.name, param.bounds.clone()))
-            .collect(),
+        type_params: scope.params(),
+        generic_bounds: generic_bounds(&decl.generics, scope),
         params,
         // Declared return annotation (if any), for the body-vs-signature check in inference.
-        ret: decl.ret.as_ref().map(|t| types.resolve_ty(t, &scope)),
+        ret,
         pre,
         post,
         locals,
@@ -281,42 +384,41 @@ fn lower_callable(
     ensures: &[AstExpr],
     body: &AstBlock,
     ret_ann: Option<&rv_syntax::ast::Ty>,
+    scope: &TypeScope,
     types: &Types,
     syms: &mut rv_core::Symbols,
-    type_params: Vec<Sym>,
+    lines: &mut SourceLines,
 ) -> Result<Vec<Function<Parsed>>, String> {
     // In-scope type parameters: a parameter type naming one is a `Ty::Param`, not
     // an ADT — so we must NOT track it as a (resolvable) ADT local.
-    let scope: HashSet<Sym> = generics.iter().map(|g| g.name).collect();
-
-    let mut b = FnBuilder::new(types);
+    let mut b = FnBuilder::new(types, scope.clone());
+    b.set_ret(ret_ann);
     let mut params = Vec::with_capacity(ast_params.len());
-    bind_params(&mut b, ast_params, &scope, types, &mut params);
+    bind_params(&mut b, ast_params, scope, types, &mut params);
 
-    let var_struct = struct_typed_params(ast_params, &scope, types);
+    let var_struct = struct_typed_params(ast_params, scope, types);
     let (pre, mut post) = lower_clauses(requires, ensures, ast_params, types, &var_struct, syms)?;
     post = apply_return_alias_refinement(post, ret_ann, types, &var_struct, syms)?;
     post = apply_return_width_contract(post, ret_ann, syms);
 
-    // Lower the body into the CFG.
-    b.lower_block(body, syms)?;
-    // Ensure every path ends in a Return; append a unit return if it falls off.
-    b.finish_with_default_return();
+    // Lower the body into the CFG; every path ends in a Return.
+    b.lower_body(body, syms)?;
 
     let lifted = b.take_lifted();
-    let (locals, blocks) = b.into_parts();
+    for (site, line) in b.take_lines(name) {
+        lines.insert(site, line);
+    }
+    let ret = lowered_ret(&b, ret_ann, scope, types, syms)?;
+    let (locals, blocks) = b.finish()?;
     let mut out = vec![Function {
         name,
-        type_params,
-        generic_bounds: generics
-            .iter()
-            .map(|param| (param.name, param.bounds.clone()))
-            .collect(),
+        type_params: scope.params(),
+        generic_bounds: generic_bounds(generics, scope),
         params,
         // Record the *declared* return annotation (if any) so inference can check the
         // body against it — most importantly to reject a primitive mismatch like a
         // `bool` body under an `-> i64` signature. `None` = unannotated (inferred).
-        ret: ret_ann.map(|t| types.resolve_ty(t, &scope)),
+        ret,
         pre,
         post,
         locals,
@@ -327,14 +429,85 @@ fn lower_callable(
     Ok(out)
 }
 
+/// Whether `ret` is an opaque `impl Trait` return type.
+fn is_opaque(ret: Option<&AstTy>) -> bool {
+    matches!(ret, Some(AstTy::ImplTrait(_)))
+}
+
+/// The primitive type `ret` names, when some impl gives it methods, so calls of
+/// a function returning it can be method receivers.
+fn primitive_ret(types: &Types, ret: &AstTy, scope: &TypeScope) -> Option<rv_core::Ty> {
+    if is_opaque(Some(ret)) {
+        return None;
+    }
+    let ty = types.resolve_ty(ret, scope);
+    types.primitive_impl(&ty).map(|_| ty)
+}
+
+/// The return type a lowered body declares: its annotation, with an `impl Trait`
+/// one replaced by the concrete type the body's `return`s fixed.
+fn lowered_ret(
+    b: &FnBuilder,
+    ret_ann: Option<&AstTy>,
+    scope: &TypeScope,
+    types: &Types,
+    syms: &rv_core::Symbols,
+) -> Result<Option<rv_core::Ty>, String> {
+    match ret_ann {
+        Some(ty @ AstTy::ImplTrait(_)) => match b.opaque_ret() {
+            Some(adt) => Ok(Some(rv_core::Ty::Adt(adt, vec![]))),
+            None => Err(format!("no `return` gives the return type `{}` a value", ty.show(syms))),
+        },
+        _ => Ok(ret_ann.map(|t| types.resolve_ty(t, scope))),
+    }
+}
+
+/// The concrete type behind `f`'s `impl Trait` return type `ret`, once checked to
+/// implement each of the bounds.
+fn check_opaque(
+    f: &Function<Parsed>,
+    ret: &AstTy,
+    impls: &[&ImplDecl],
+    traits: &HashMap<Sym, &TraitDecl>,
+    syms: &rv_core::Symbols,
+) -> Result<Sym, String> {
+    let (AstTy::ImplTrait(bounds), Some(rv_core::Ty::Adt(adt, _))) = (ret, &f.ret) else {
+        unreachable!("an `impl Trait` body is lowered with its concrete return type");
+    };
+    for bound in bounds {
+        if !traits.contains_key(bound) {
+            let (ret, bound) = (ret.show(syms), syms.resolve(*bound));
+            return Err(format!("`{ret}` names unknown trait `{bound}`"));
+        }
+        if !impls.iter().any(|im| im.trait_name == Some(*bound) && im.type_name == *adt) {
+            return Err(format!(
+                "type `{}` does not implement trait `{}` required by the return type `{}` of `{}`",
+                syms.resolve(*adt),
+                syms.resolve(*bound),
+                ret.show(syms),
+                syms.resolve(f.name),
+            ));
+        }
+    }
+    Ok(*adt)
+}
+
 /// Allocate a local per parameter, register its name, and (when the parameter's
 /// type is a concrete ADT — not a generic type parameter) track that ADT so field
 /// access / `match` / method calls on it can resolve. Pushes the new locals onto
 /// `out`.
+/// The trait bounds of `generics`, keyed by the parameters `scope` binds them to.
+fn generic_bounds(generics: &[GenericParam], scope: &TypeScope) -> Vec<(Sym, Vec<Sym>)> {
+    generics
+        .iter()
+        .map(|param| (scope.lookup(param.name).unwrap_or(param.name), param.bounds.clone()))
+        .collect()
+}
+
 fn bind_params(
     b: &mut FnBuilder,
     ast_params: &[Param],
-    scope: &HashSet<Sym>,
+    scope: &TypeScope,
     types: &Types,
     out: &mut Vec<rv_ir::LocalId>,
 ) {
@@ -344,16 +517,17 @@ fn bind_params(
         // full declared type on the Parsed IR. This is also the source of truth
         // for direct-call argument checking in elaboration.
         b.set_local_ty(id, types.resolve_ty(&p.ty, scope));
-        // Track an ADT parameter's type so field access / match / `?` / methods
-        // resolve. A bare name that is actually a generic type parameter is NOT a
-        // known ADT, so we skip it (its type erases to `Ty::Param`). A generic
+        // Track an ADT parameter's type, behind any references, so field access /
+        // match / `?` / methods resolve. A bare name that is actually a generic
+        // type parameter is NOT a known ADT, so we skip it (its type erases to
+        // `Ty::Param`). A generic
         // application `Base<args..>` erases to its base ADT, which we also track
         // (so e.g. a `Result<i64, i64>` parameter is matchable / `?`-propagatable).
-        match &p.ty {
-            AstTy::Adt(adt) if !scope.contains(adt) && types.is_adt(*adt) => {
+        match p.ty.pointee() {
+            AstTy::Adt(adt) if !scope.contains(*adt) && types.is_adt(*adt) => {
                 b.set_local_adt(id, *adt)
             }
-            AstTy::Generic { base, .. } if !scope.contains(base) && types.is_adt(*base) => {
+            AstTy::Generic { base, .. } if !scope.contains(*base) && types.is_adt(*base) => {
                 b.set_local_adt(id, *base)
             }
             _ => {}
@@ -481,13 +655,13 @@ fn apply_return_width_contract(
 /// (not an enum, not a generic type parameter) are recorded.
 fn struct_typed_params(
     ast_params: &[Param],
-    scope: &HashSet<Sym>,
+    scope: &TypeScope,
     types: &Types,
 ) -> HashMap<Sym, Sym> {
     let mut map = HashMap::new();
     for p in ast_params {
         if let AstTy::Adt(name) = &p.ty {
-            if !scope.contains(name) && types.struct_info(*name).is_some() {
+            if !scope.contains(*name) && types.struct_info(*name).is_some() {
                 map.insert(p.name, *name);
             }
         }
@@ -502,7 +676,7 @@ const BlockId_ENTRY: rv_ir::BlockId = rv_ir::BlockId(0);
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rv_ir::Terminator;
+    use rv_ir::{LineSpan, Site, Terminator};
 
     /// Parse + lower a source string, panicking with the error on failure.
     fn lower_src(src: &str) -> (Program<Parsed>, rv_core::Symbols) {
@@ -512,6 +686,76 @@ mod tests {
         (prog, syms)
     }
 
+    /// Printing keeps every visibility the parser recorded: this module prints
+    /// as exactly the text it was parsed from.
+    #[test]
+    fn printed_visibility_round_trips() {
+        let src = "pub struct P { pub x: i64, y: i64 }
+
+pub enum E { A, B(i64) }
+
+pub trait T {
+    fn get(self) -> i64;
+}
+
+impl P {
+    pub fn get(self) -> i64 {
+        return self.x;  // line 11
+    }
+    pub(crate) fn zero() -> i64 {
+        return 0;  // line 14
+    }
+}
+
+pub use E::A;
+
+pub fn f() {
+}
+";
+        let mut syms = rv_core::Symbols::new();
+        assert_eq!(print(&rv_syntax::parse(src, &mut syms).unwrap(), &syms), src);
+    }
+
+    #[test]
+    fn printed_unit_round_trips() {
+        let src = "fn f(u: ()) -> () {
+    let v: () = ();  // line 2
+    return v;  // line 3
+}
+";
+        let mut syms = rv_core::Symbols::new();
+        assert_eq!(print(&rv_syntax::parse(src, &mut syms).unwrap(), &syms), src);
+    }
+
+    /// `let mut`, a reassignment, and a `&mut` borrow each make a binding
+    /// mutable; the first assignment of a binding declared without a value does
+    /// not, and temporaries never are.
+    #[test]
+    fn written_bindings_are_mutable() {
+        let src = "\
+fn f() -> i64 {
+  let mut a = 1;
+  let b = 2;
+  b = 3;
+  let c;
+  c = 4;
+  let d = 5;
+  let r = &mut d;
+  return wrapping_add(a, wrapping_add(b, c));
+}";
+        let (prog, syms) = lower_src(src);
+        let f = &prog.funcs[0];
+        let mutable: Vec<&str> = f
+            .locals
+            .iter()
+            .filter(|l| l.mutable)
+            .map(|l| l.name.map_or("<temp>", |n| syms.resolve(n)))
+            .collect();
+        assert_eq!(mutable, ["a", "b", "d"]);
+        let text = rv_ir::text::print(&prog, &syms);
+        assert!(text.contains("let mut _0 a: _;") && text.contains("let _2 c: _;"), "{text}");
+    }
+
     #[test]
     fn lowers_the_div_main_example() {
         let src = "\
@@ -564,6 +808,60 @@ fn main() -> i64 {
         assert!(matches!(entry.term, Terminator::Branch { .. }));
     }
 
+    /// A builder whose last block was never terminated does not hand out its CFG:
+    /// `finish` names the open block instead of dropping its statements.
+    #[test]
+    fn unterminated_block_is_reported_by_finish() {
+        let mut syms = rv_core::Symbols::new();
+        let module = rv_syntax::parse("fn f() { if true { return; } }", &mut syms).unwrap();
+        let rv_syntax::ast::Item::Fn(decl) = &module.items[0] else { panic!("expected a fn") };
+        let types = Types::default();
+
+        // The `if` leaves the builder in its join block; nothing closes it.
+        let mut b = FnBuilder::new(&types, TypeScope::default());
+        b.lower_block(&decl.body, &mut syms).unwrap();
+        let Err(err) = b.finish() else { panic!("the join block is still open") };
+        assert_eq!(err, "internal error: lowering left block(s) 3 without a terminator");
+
+        // Lowering the same body as a function closes it with the fall-off return.
+        let mut b = FnBuilder::new(&types, TypeScope::default());
+        b.lower_body(&decl.body, &mut syms).unwrap();
+        let (_, blocks) = b.finish().expect("every block is terminated");
+        assert_eq!(blocks.len(), 4);
+    }
+
+    /// Terminators a statement emits after a nested block carry the statement's
+    /// own span, and the fall-off return carries the whole body's.
+    #[test]
+    fn nested_blocks_do_not_lend_their_span_to_the_parent() {
+        let src = "\
+fn f(n: i64) -> i64 {
+    let r = 0;
+    if n > 0 {
+        r = 1;
+    }
+    return r;
+}
+fn g() {
+    let x = 1;
+}";
+        let mut syms = rv_core::Symbols::new();
+        let module = rv_syntax::parse(src, &mut syms).unwrap();
+        let (prog, lines) = lower_with_lines(&module, &mut syms).unwrap();
+        rv_ir::check_source_lines(&prog, &lines).unwrap();
+        let terminator_spans = |func: &Function<Parsed>| -> Vec<LineSpan> {
+            let site = |b: &rv_ir::Block<Parsed>| Site { func: func.name, block: b.id, stmt: None };
+            func.blocks.iter().filter_map(|b| lines.get(site(b)).copied()).collect()
+        };
+        let [f, g] = &prog.funcs[..] else { panic!("expected two functions") };
+        // The branch and both arms' gotos belong to the `if`, not to `r = 1;`.
+        let mut f_terms = terminator_spans(f);
+        f_terms.sort_by_key(|s| (s.start, s.end));
+        let if_span = LineSpan::new(3, 5);
+        assert_eq!(f_terms, [if_span, if_span, if_span, LineSpan::new(6, 6)]);
+        assert_eq!(terminator_spans(g), [LineSpan::new(8, 10)]);
+    }
+
     #[test]
     fn while_has_a_back_edge() {
         let src = "fn f(n: i64) -> i64 { let i = 0; while i < n { i = i + 1; } return i; }";
@@ -776,18 +1074,85 @@ fn f(r: &i64) -> i64 {
         assert!(ok, "expected `return *r;` to read Deref of the &T parameter");
     }
 
+    #[test]
+    fn field_access_derefs_a_returned_reference() {
+        use rv_ir::{Operand, Proj, RValue, Stmt};
+        // `&mut o.a` in `first` borrows `[Deref, Field(0)]` of its `&mut Outer`
+        // parameter, and `get(&mut o).b = 5;` stores through `[Deref, Field(1)]`
+        // of the temp holding the call's result.
+        let src = "\
+struct Outer { a: i64, b: i64 }
+fn get(o: &mut Outer) -> &mut Outer {
+    return o;
+}
+fn first(o: &mut Outer) -> &mut i64 {
+    return &mut o.a;
+}
+fn f() -> i64 {
+    let o = Outer { a: 1, b: 2 };
+    get(&mut o).b = 5;
+    return o.b;
+}";
+        let (prog, syms) = lower_src(src);
+        let func = |name| prog.funcs.iter().find(|f| syms.resolve(f.name) == name).unwrap();
+        let first = func("first");
+        let borrow = first.blocks.iter().flat_map(|b| &b.stmts).find_map(|s| match s {
+            Stmt::Assign(_, RValue::Ref(_, place)) => Some(place),
+            _ => None,
+        });
+        let borrow = borrow.expect("expected `&mut o.a` to borrow a place");
+        assert_eq!(borrow.local, first.params[0]);
+        assert!(matches!(borrow.proj.as_slice(), [Proj::Deref, Proj::Field(0)]));
+
+        let f = func("f");
+        let store = f.blocks.iter().flat_map(|b| &b.stmts).find_map(|s| match s {
+            Stmt::Assign(place, RValue::Use(Operand::Const(rv_ir::Const::Int(5)))) => {
+                Some(place)
+            }
+            _ => None,
+        });
+        let store = store.expect("expected a store of 5");
+        assert!(matches!(store.proj.as_slice(), [Proj::Deref, Proj::Field(1)]));
+        // The root is the temp the call result was written to.
+        let call_dst = f.blocks.iter().flat_map(|b| &b.stmts).find_map(|s| match s {
+            Stmt::Assign(dst, RValue::Call(..)) => Some(dst.local),
+            _ => None,
+        });
+        assert_eq!(call_dst, Some(store.local));
+    }
+
     #[test]
     fn lowers_generic_fn_with_type_params() {
-        // (a) `fn id<T>(x: T) -> T { return x; }` lowers; Function.type_params == [T].
+        // (a) `fn id<T>(x: T) -> T { return x; }` lowers; Function.type_params is
+        // the function's own `T`, which its parameter's type refers to.
         let src = "fn id<T>(x: T) -> T { return x; }";
-        let (prog, mut syms) = lower_src(src);
+        let (prog, syms) = lower_src(src);
         let f = &prog.funcs[0];
-        assert_eq!(f.type_params, vec![syms.intern("T")]);
+        let [t] = f.type_params[..] else { panic!("one type parameter: {:?}", f.type_params) };
+        assert_eq!(syms.resolve(t), "T");
+        assert_eq!(f.locals[f.params[0].0 as usize].ty, Some(rv_core::Ty::Param(t)));
         // The parameter is usable: a single block returning the parameter local.
         assert_eq!(f.params.len(), 1);
         assert!(matches!(f.blocks[0].term, Terminator::Return(_)));
     }
 
+    #[test]
+    fn type_parameters_are_scoped_to_their_function() {
+        let src = "fn f<T>(x: T) -> T { let c = |n| n; return x; }\n\
+                   fn g<T>(x: T) -> T { let y: T = x; return y; }";
+        let (prog, syms) = lower_src(src);
+        let func = |name: &str| prog.funcs.iter().find(|f| syms.resolve(f.name) == name).unwrap();
+        let (f, g) = (func("f"), func("g"));
+        // Both spelled `T`, but two parameters.
+        assert_eq!(syms.resolve(f.type_params[0]), syms.resolve(g.type_params[0]));
+        assert_ne!(f.type_params, g.type_params);
+        // A body annotation refers to the function's own parameter.
+        let y = g.locals.iter().find(|d| d.name.is_some_and(|n| syms.resolve(n) == "y")).unwrap();
+        assert_eq!(y.ty, Some(rv_core::Ty::Param(g.type_params[0])));
+        // A closure lifted out of `f` keeps `f`'s parameter in scope.
+        assert_eq!(func("__closure_0").type_params, f.type_params);
+    }
+
     #[test]
     fn lowers_generic_struct_with_type_params() {
         // (b) `struct Pair<A, B> { .. }` lowers with type_params == [A, B], and a
@@ -820,8 +1185,8 @@ fn f(r: &i64) -> i64 {
     }
 
     #[test]
-    fn generic_type_args_are_erased() {
-        // A field `Option<i64>` erases to `Ty::Adt(Option)` (type args dropped).
+    fn generic_type_args_are_kept() {
+        // A field `Option<i64>` lowers to `Ty::Adt(Option, [Int])`.
         use rv_ir::TypeDef;
         let src = "\
 enum Option<T> { None, Some(T) }
@@ -831,7 +1196,7 @@ struct Holder { o: Option<i64> }";
             TypeDef::Struct { name, fields, .. } if *name == syms.intern("Holder") => Some(fields),
             _ => None,
         }).expect("expected the Holder struct");
-        assert_eq!(holder[0].ty, rv_core::Ty::Adt(syms.intern("Option")));
+        assert_eq!(holder[0].ty, rv_core::Ty::Adt(syms.intern("Option"), vec![rv_core::Ty::Int]));
     }
 
     #[test]
@@ -924,6 +1289,36 @@ fn f() -> i64 {
         assert!(matches!(&call_args[0], Operand::Copy(Place { proj, .. }) if proj.is_empty()));
     }
 
+    #[test]
+    fn method_chain_links_pass_their_one_temporary_on() {
+        // Each link's result is one temporary, read by the next link's call.
+        use rv_ir::{Operand, Place, RValue, Stmt};
+        let src = "\
+struct V { x: i64, y: i64 }
+impl V {
+    fn swap(self) -> V { return V { x: self.y, y: self.x }; }
+    fn sum(self, k: i64) -> i64 { return self.x + self.y + k; }
+}
+fn f() -> i64 { let v = V { x: 1, y: 2 }; return v.swap().swap().sum(3); }";
+        let (prog, mut syms) = lower_src(src);
+        let (swap, sum) = (syms.intern("V::swap"), syms.intern("V::sum"));
+        let f = prog.funcs.iter().find(|f| f.name == syms.intern("f")).unwrap();
+        let calls: Vec<_> = f.blocks.iter().flat_map(|b| &b.stmts).filter_map(|s| match s {
+            Stmt::Assign(dest, RValue::Call(callee, args)) => Some((dest.local, *callee, args)),
+            _ => None,
+        }).collect();
+        assert_eq!(calls.iter().map(|c| c.1).collect::<Vec<_>>(), [swap, swap, sum]);
+        let v = syms.intern("v");
+        let recv = |i: usize| match &calls[i].2[0] {
+            Operand::Copy(Place { local, proj }) if proj.is_empty() => *local,
+            other => panic!("receiver {i} is {other:?}"),
+        };
+        assert_eq!(f.locals[recv(0).0 as usize].name, Some(v));
+        assert_eq!((recv(1), recv(2)), (calls[0].0, calls[1].0));
+        // `v`, the two intermediate results, and the returned value.
+        assert_eq!(f.locals.len(), 4);
+    }
+
     #[test]
     fn distinct_types_get_distinct_mangled_methods() {
         // Two different types' `m` get distinct mangled names.
@@ -962,13 +1357,18 @@ impl B { fn m(self) -> i64 { return self.v; } }";
     fn method_call_on_unknown_receiver_type_errors() {
         // A method call whose receiver type can't be resolved is a clear error.
         let mut syms = rv_core::Symbols::new();
-        let src = "fn f(x: i64) -> i64 { return x.foo(); }";
+        let src = "fn f(x: bool) -> bool { return x.foo(); }";
         let module = rv_syntax::parse(src, &mut syms).unwrap();
         let err = match lower(&module, &mut syms) {
             Ok(_) => panic!("expected lowering to fail"),
             Err(e) => e,
         };
         assert!(err.contains("receiver"), "got: {err}");
+        // `i64` is known, through the prelude's impl; `foo` is not one of its methods.
+        let src = "fn f(x: i64) -> i64 { return x.foo(); }";
+        let module = rv_syntax::parse(src, &mut syms).unwrap();
+        let err = lower(&module, &mut syms).err().expect("expected lowering to fail");
+        assert!(err.contains("no method `foo` found for type `i64`"), "got: {err}");
     }
 
     #[test]
@@ -981,6 +1381,36 @@ impl B { fn m(self) -> i64 { return self.v; } }";
         assert!(has_panic, "expected a Terminator::Panic");
     }
 
+    #[test]
+    fn statement_calls_have_no_destination() {
+        use rv_ir::{RValue, Stmt};
+        let src = "\
+struct P { x: i64 }
+impl P { fn touch(self) {} }
+fn g() {}
+fn f() {
+    let p = P { x: 1 };
+    g();
+    p.touch();
+}";
+        let (prog, mut syms) = lower_src(src);
+        let f = prog.funcs.iter().find(|f| f.name == syms.intern("f")).unwrap();
+        let evaluated: Vec<_> = f
+            .blocks
+            .iter()
+            .flat_map(|b| &b.stmts)
+            .filter_map(|s| match s {
+                Stmt::Eval(RValue::Call(callee, _)) => Some(syms.resolve(*callee).to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(evaluated, ["g", "P::touch"]);
+        // Neither call result is assigned to a temp.
+        assert!(f.blocks.iter().flat_map(|b| &b.stmts).all(|s| {
+            !matches!(s, Stmt::Assign(_, RValue::Call(..)))
+        }));
+    }
+
     #[test]
     fn panic_with_arg_evaluates_then_aborts() {
         // `panic(g());` evaluates the argument (a call -> a Call rvalue) for its
@@ -1131,4 +1561,49 @@ fn f(o: Option<i64>) -> Option<i64> {
             Terminator::Return(Operand::Const(Const::Unit))
         ));
     }
+
+    #[test]
+    fn cast_of_literal_is_folded() {
+        use rv_ir::{Const, Operand, RValue, Stmt};
+        // `300 as u8` wraps to 44 at compile time; `x as f64` stays a runtime cast.
+        let (prog, _) = lower_src("fn f(x: i64) -> f64 { let a = 300 as u8; return x as f64; }");
+        let stmts: Vec<&Stmt> = prog.funcs[0].blocks.iter().flat_map(|b| &b.stmts).collect();
+        assert!(
+            stmts.iter().any(|s| matches!(
+                s,
+                Stmt::Assign(_, RValue::Use(Operand::Const(Const::Int(44))))
+            )),
+            "expected `300 as u8` folded to 44: {stmts:?}"
+        );
+        assert!(
+            stmts.iter().any(|s| matches!(s, Stmt::Assign(_, RValue::Cast(_, rv_core::Ty::Float)))),
+            "expected a runtime cast to f64: {stmts:?}"
+        );
+    }
+
+    /// Every program in the examples and the inference fixtures survives a
+    /// print/parse cycle of the IR text format unchanged.
+    #[test]
+    fn lowered_corpus_round_trips_through_ir_text() {
+        let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+        let mut checked = 0;
+        for dir in ["examples", "crates/rv-infer/tests/fixtures"] {
+            for entry in std::fs::read_dir(root.join(dir)).unwrap() {
+                let path = entry.unwrap().path();
+                if path.extension().is_none_or(|e| e != "rv") {
+                    continue;
+                }
+                let src = std::fs::read_to_string(&path).unwrap();
+                let mut syms = rv_core::Symbols::new();
+                let Ok(module) = rv_syntax::parse(&src, &mut syms) else { continue };
+                let Ok(prog) = lower(&module, &mut syms) else { continue };
+                let printed = rv_ir::text::print(&prog, &syms);
+                let again: Program<Parsed> = rv_ir::text::parse(&printed, &mut syms)
+                    .unwrap_or_else(|e| panic!("{}: {e}\n{printed}", path.display()));
+                assert_eq!(rv_ir::text::print(&again, &syms), printed, "{}", path.display());
+                checked += 1;
+            }
+        }
+        assert!(checked >= 10, "only {checked} programs lowered");
+    }
 }
@@ -0,0 +1,161 @@
+//! The prelude: `Option` and `Result`, in scope in every module, and `abs`,
+//! `min` and `max` on `i64` and `f64`.
+//!
+//! The prelude is ordinary source, lowered with the module that uses it. A
+//! module that declares its own `Option` or `Result` shadows the prelude's, impl
+//! and all; one that gives `i64` or `f64` a method of its own shadows just that
+//! method. Whatever the module does not reach is pruned after lowering, so a
+//! program that never names `Option` lowers exactly as it would without one.
+
+use std::collections::HashSet;
+
+use rv_core::{Sym, Symbols, Ty};
+use rv_ir::callgraph::CallGraph;
+use rv_ir::{AggKind, Parsed, Program, RValue, Stmt, TypeDef};
+use rv_syntax::ast::Item;
+
+const SOURCE: &str = "\
+enum Option<T> { None, Some(T) }
+
+enum Result<T, E> { Ok(T), Err(E) }
+
+impl Option {
+    fn is_some(self) -> bool {
+        match self { Option::Some(_) => { return true; } Option::None => { return false; } }
+    }
+    fn is_none(self) -> bool {
+        match self { Option::Some(_) => { return false; } Option::None => { return true; } }
+    }
+    fn unwrap_or<T>(self, default: T) -> T {
+        match self { Option::Some(v) => { return v; } Option::None => { return default; } }
+    }
+    fn map<T, U>(self, f: fn(T) -> U) -> Option<U> {
+        match self {
+            Option::Some(v) => { return Option::Some(f(v)); }
+            Option::None => { return Option::None; }
+        }
+    }
+}
+
+impl Result {
+    fn is_ok(self) -> bool {
+        match self { Result::Ok(_) => { return true; } Result::Err(_) => { return false; } }
+    }
+    fn is_err(self) -> bool {
+        match self { Result::Ok(_) => { return false; } Result::Err(_) => { return true; } }
+    }
+    fn unwrap_or<T>(self, default: T) -> T {
+        match self { Result::Ok(v) => { return v; } Result::Err(_) => { return default; } }
+    }
+    fn map<T, U, E>(self, f: fn(T) -> U) -> Result<U, E> {
+        match self {
+            Result::Ok(v) => { return Result::Ok(f(v)); }
+            Result::Err(e) => { return Result::Err(e); }
+        }
+    }
+}
+
+impl i64 {
+    fn abs(self) -> i64 requires self >= -9223372036854775807; {
+        if self < 0 { return 0 - self; }
+        return self;
+    }
+    fn min(self, other: i64) -> i64 {
+        if other < self { return other; }
+        return self;
+    }
+    fn max(self, other: i64) -> i64 {
+        if other > self { return other; }
+        return self;
+    }
+}
+
+impl f64 {
+    fn abs(self) -> f64 {
+        if self < 0.0 { return 0.0 - self; }
+        return self;
+    }
+    fn min(self, other: f64) -> f64 {
+        if other < self { return other; }
+        return self;
+    }
+    fn max(self, other: f64) -> f64 {
+        if other > self { return other; }
+        return self;
+    }
+}
+";
+
+/// The prelude's enums and impls, less those for a type `declared` names and the
+/// methods `own` (inherent `(type, method)` pairs of the module) replaces.
+pub(crate) fn items(
+    declared: &HashSet<Sym>,
+    own: &HashSet<(Sym, Sym)>,
+    syms: &mut Symbols,
+) -> Vec<Item> {
+    let module = rv_syntax::parse(SOURCE, syms).expect("the prelude parses");
+    module
+        .items
+        .into_iter()
+        .filter_map(|item| match item {
+            Item::Enum(e) => (!declared.contains(&e.name)).then_some(Item::Enum(e)),
+            Item::Impl(mut i) if !declared.contains(&i.type_name) => {
+                i.methods.retain(|m| !own.contains(&(i.type_name, m.name)));
+                Some(Item::Impl(i))
+            }
+            Item::Impl(_) => None,
+            _ => unreachable!("the prelude declares only enums and impls"),
+        })
+        .collect()
+}
+
+/// Drop the prelude functions `fns` that no other function reaches by a call or
+/// as a function value, then the prelude types `types` that nothing left
+/// mentions.
+pub(crate) fn prune(prog: &mut Program<Parsed>, fns: &HashSet<Sym>, types: &HashSet<Sym>) {
+    let roots = prog.funcs.iter().map(|f| f.name).filter(|n| !fns.contains(n));
+    let reached = CallGraph::build(&prog.funcs).reachable(roots);
+    prog.funcs.retain(|f| reached.contains(&f.name));
+
+    let mut used = HashSet::new();
+    for f in &prog.funcs {
+        let tys = f.ret.iter().chain(f.locals.iter().filter_map(|l| l.ty.as_ref()));
+        tys.for_each(|ty| adts(ty, &mut used));
+        for stmt in f.blocks.iter().flat_map(|b| &b.stmts) {
+            if let Stmt::Assign(_, RValue::Aggregate(AggKind::Variant(name, _), _)) = stmt {
+                used.insert(*name);
+            }
+        }
+    }
+    for def in prog.types.iter().filter(|d| !types.contains(&d.name())) {
+        match def {
+            TypeDef::Struct { fields, .. } => fields.iter().for_each(|f| adts(&f.ty, &mut used)),
+            TypeDef::Enum { variants, .. } => {
+                variants.iter().flat_map(|v| &v.fields).for_each(|ty| adts(ty, &mut used))
+            }
+        }
+    }
+    used.extend(prog.trait_impls.iter().map(|i| i.type_name));
+    // The prelude's own variants hold only type parameters, so no prelude type
+    // keeps another alive.
+    prog.types.retain(|d| !types.contains(&d.name()) || used.contains(&d.name()));
+}
+
+/// Add every ADT `ty` names, at any depth, to `out`.
+fn adts(ty: &Ty, out: &mut HashSet<Sym>) {
+    match ty {
+        Ty::Adt(name, args) => {
+            out.insert(*name);
+            args.iter().for_each(|a| adts(a, out));
+        }
+        Ty::Tuple(items) => items.iter().for_each(|t| adts(t, out)),
+        Ty::Array(item, _) | Ty::Vec(item) => adts(item, out),
+        Ty::Fn(params, ret) => {
+            params.iter().for_each(|p| adts(p, out));
+            adts(ret, out);
+        }
+        Ty::Ref { inner, .. } => adts(inner, out),
+        Ty::Int | Ty::IntN(_) | Ty::Float | Ty::Str | Ty::Bool | Ty::Unit | Ty::Never
+        | Ty::Param(_) => {}
+    }
+}
@@ -0,0 +1,459 @@
+//! A module as source text, for `rvc --emit desugared`.
+//!
+//! Printing the output of [`crate::desugar`] shows what lowering is given. Each
+//! statement is on a line of its own, ending in a `// line N` comment that names
+//! the source line it came from, so a rewritten statement can be traced back to
+//! what was written. Operands that are not atoms are parenthesized, so the text
+//! reads unambiguously without precedence rules. Proof-fragment terms, which
+//! lowering never sees, print as `<term>`, as [`Ty::show`] prints them.
+
+use rv_core::{BinOp, Sym, Symbols, UnOp};
+use rv_syntax::ast::{
+    Block, Expr, GenericParam, Item, MatchArm, Module, Param, PatBind, Pattern, Stmt, Ty,
+};
+
+/// Render `module` as source text.
+pub fn print(module: &Module, syms: &Symbols) -> String {
+    let p = Printer { syms };
+    let items: Vec<String> = module.items.iter().map(|item| p.item(item)).collect();
+    items.join("\n")
+}
+
+struct Printer<'a> {
+    syms: &'a Symbols,
+}
+
+/// The indentation of a line `depth` blocks in.
+fn pad(depth: usize) -> String {
+    "    ".repeat(depth)
+}
+
+impl<'a> Printer<'a> {
+    fn name(&self, sym: Sym) -> &'a str {
+        self.syms.resolve(sym)
+    }
+
+    fn item(&self, item: &Item) -> String {
+        match item {
+            Item::Fn(f) => {
+                let (vis, name) = (f.vis.show(), self.name(f.name));
+                let head = format!("{vis}fn {name}{}", self.generics(&f.generics));
+                let params = f.params.iter().map(|p| self.param(p)).collect();
+                self.callable(0, head, params, f.ret.as_ref(), &f.requires, &f.ensures, &f.body)
+            }
+            Item::Struct(s) => {
+                let fields: Vec<String> = (s.fields.iter())
+                    .map(|f| {
+                        let (vis, name) = (f.vis.show(), self.name(f.name));
+                        format!("{vis}{name}: {}", f.ty.show(self.syms))
+                    })
+                    .collect();
+                let (vis, name) = (s.vis.show(), self.name(s.name));
+                let head = format!("{vis}struct {name}{}", self.generics(&s.generics));
+                format!("{head} {{ {} }}\n", fields.join(", "))
+            }
+            Item::Enum(e) => {
+                let variants: Vec<String> = (e.variants.iter())
+                    .map(|v| match v.fields.is_empty() {
+                        true => self.name(v.name).to_string(),
+                        false => format!("{}({})", self.name(v.name), self.tys(&v.fields)),
+                    })
+                    .collect();
+                let (vis, name) = (e.vis.show(), self.name(e.name));
+                let head = format!("{vis}enum {name}{}", self.generics(&e.generics));
+                format!("{head} {{ {} }}\n", variants.join(", "))
+            }
+            Item::TypeAlias(a) => format!(
+                "type {} = {} where {};\n",
+                self.name(a.name),
+                a.base.show(self.syms),
+                self.expr(&a.refinement, 0)
+            ),
+            Item::Trait(t) => {
+                let mut out = format!("{}trait {} {{\n", t.vis.show(), self.name(t.name));
+                for m in &t.methods {
+                    let params = self.receiver(m.has_self, &m.params);
+                    let head = format!("fn {}{}", self.name(m.name), self.generics(&m.generics));
+                    let ret = self.ret(m.ret.as_ref());
+                    out += &format!("{}{head}({}){ret};\n", pad(1), params.join(", "));
+                }
+                out + "}\n"
+            }
+            Item::Impl(im) => {
+                let ty = self.name(im.type_name);
+                let mut out = match im.trait_name {
+                    Some(tr) => format!("impl {} for {ty} {{\n", self.name(tr)),
+                    None => format!("impl {ty} {{\n"),
+                };
+                for m in &im.methods {
+                    let (vis, name) = (m.vis.show(), self.name(m.name));
+                    let head = format!("{vis}fn {name}{}", self.generics(&m.generics));
+                    let params = self.receiver(m.has_self, &m.params);
+                    let ret = m.ret.as_ref();
+                    out += &self.callable(1, head, params, ret, &m.requires, &m.ensures, &m.body);
+                }
+                out + "}\n"
+            }
+            Item::Use(u) => {
+                let (vis, name) = (u.vis.show(), self.name(u.enum_name));
+                match u.variant {
+                    Some(v) => format!("{vis}use {name}::{};\n", self.name(v)),
+                    None => format!("{vis}use {name}::*;\n"),
+                }
+            }
+            Item::Axiom(_) | Item::Def(_) | Item::Instance(_) | Item::Mutual(_) => {
+                "// <proof item>\n".to_string()
+            }
+        }
+    }
+
+    /// A function or method, `depth` blocks in: its header, its spec clauses
+    /// one to a line, and its body.
+    #[allow(clippy::too_many_arguments)]
+    fn callable(
+        &self,
+        depth: usize,
+        head: String,
+        params: Vec<String>,
+        ret: Option<&Ty>,
+        requires: &[Expr],
+        ensures: &[Expr],
+        body: &Block,
+    ) -> String {
+        let mut out = format!("{}{head}({}){}", pad(depth), params.join(", "), self.ret(ret));
+        let clauses = (requires.iter().map(|e| ("requires", e)))
+            .chain(ensures.iter().map(|e| ("ensures", e)));
+        for (kw, e) in clauses {
+            out += &format!("\n{}{kw} {};", pad(depth + 1), self.expr(e, depth + 1));
+        }
+        let sep = match requires.is_empty() && ensures.is_empty() {
+            true => " ".to_string(),
+            false => format!("\n{}", pad(depth)),
+        };
+        format!("{out}{sep}{}\n", self.block(body, depth))
+    }
+
+    fn generics(&self, generics: &[GenericParam]) -> String {
+        if generics.is_empty() {
+            return String::new();
+        }
+        let params: Vec<String> = (generics.iter())
+            .map(|g| {
+                let bounds: Vec<&str> = g.bounds.iter().map(|b| self.name(*b)).collect();
+                match bounds.is_empty() {
+                    true => self.name(g.name).to_string(),
+                    false => format!("{}: {}", self.name(g.name), bounds.join(" + ")),
+                }
+            })
+            .collect();
+        format!("<{}>", params.join(", "))
+    }
+
+    fn param(&self, p: &Param) -> String {
+        let param = format!("{}: {}", self.name(p.name), p.ty.show(self.syms));
+        match &p.refinement {
+            Some(e) => format!("{param} where {}", self.expr(e, 0)),
+            None => param,
+        }
+    }
+
+    /// A method's parameters, `self` first if it takes one.
+    fn receiver(&self, has_self: bool, params: &[Param]) -> Vec<String> {
+        let receiver = has_self.then(|| "self".to_string());
+        receiver.into_iter().chain(params.iter().map(|p| self.param(p))).collect()
+    }
+
+    /// `'l: `, before a loop.
+    fn label(&self, label: Sym) -> String {
+        format!("'{}: ", self.name(label))
+    }
+
+    fn ret(&self, ret: Option<&Ty>) -> String {
+        ret.map_or_else(String::new, |t| format!(" -> {}", t.show(self.syms)))
+    }
+
+    fn tys(&self, tys: &[Ty]) -> String {
+        tys.iter().map(|t| t.show(self.syms)).collect::<Vec<_>>().join(", ")
+    }
+
+    /// `{ .. }`, its statements `depth + 1` blocks in and its `}` at `depth`.
+    fn block(&self, b: &Block, depth: usize) -> String {
+        self.block_then(b, None, depth)
+    }
+
+    /// [`Self::block`] ending in `tail`, the value of an `if` expression's branch.
+    fn block_then(&self, b: &Block, tail: Option<&Expr>, depth: usize) -> String {
+        rv_arena::stack::grow(|| self.block_then_here(b, tail, depth))
+    }
+
+    fn block_then_here(&self, b: &Block, tail: Option<&Expr>, depth: usize) -> String {
+        let mut out = "{\n".to_string();
+        for (stmt, span) in b.stmts.iter().zip(&b.spans) {
+            let text = self.stmt(stmt, depth + 1);
+            // The line comment goes on the statement's first line.
+            let (first, rest) = match text.split_once('\n') {
+                Some((first, rest)) => (first, Some(rest)),
+                None => (text.as_str(), None),
+            };
+            out += &format!("{}{first}  // line {}\n", pad(depth + 1), span.start);
+            if let Some(rest) = rest {
+                out += rest;
+                out.push('\n');
+            }
+        }
+        if let Some(tail) = tail {
+            out += &format!("{}{}\n", pad(depth + 1), self.expr(tail, depth + 1));
+        }
+        out + &pad(depth) + "}"
+    }
+
+    /// A statement `depth` blocks in, less the indentation of its first line.
+    fn stmt(&self, s: &Stmt, depth: usize) -> String {
+        let label = |l: &Option<Sym>| l.map_or_else(String::new, |l| self.label(l));
+        let target = |l: &Option<Sym>| {
+            l.map_or_else(String::new, |l| format!(" '{}", self.name(l)))
+        };
+        let value = |e: &Option<Expr>| {
+            e.as_ref().map_or_else(String::new, |e| format!(" {}", self.expr(e, depth)))
+        };
+        match s {
+            Stmt::Let { name, mutable, ty, init } => {
+                let mut out = format!("let {}{}", mutability(*mutable), self.name(*name));
+                if let Some(ty) = ty {
+                    out += &format!(": {}", ty.show(self.syms));
+                }
+                if let Some(init) = init {
+                    out += &format!(" = {}", self.expr(init, depth));
+                }
+                out + ";"
+            }
+            Stmt::Assign { name, value } => {
+                format!("{} = {};", self.name(*name), self.expr(value, depth))
+            }
+            Stmt::DerefAssign { place, value } => {
+                format!("*{} = {};", self.operand(place, depth), self.expr(value, depth))
+            }
+            Stmt::If { cond, then_blk, else_blk } => {
+                let then_blk = self.block(then_blk, depth);
+                let mut out = format!("if {} {then_blk}", self.head(cond, depth));
+                if let Some(b) = else_blk {
+                    out += &format!(" else {}", self.block(b, depth));
+                }
+                out
+            }
+            Stmt::While { label: l, cond, invariants, body } => {
+                let mut out = format!("{}while {}", label(l), self.head(cond, depth));
+                for inv in invariants {
+                    out += &format!(" invariant {};", self.expr(inv, depth));
+                }
+                format!("{out} {}", self.block(body, depth))
+            }
+            Stmt::Match { scrut, arms } => {
+                let mut out = format!("match {} {{\n", self.head(scrut, depth));
+                for MatchArm { pat, body } in arms {
+                    let arm = format!("{} => {}", self.pattern(pat), self.block(body, depth + 1));
+                    out += &format!("{}{arm}\n", pad(depth + 1));
+                }
+                out + &pad(depth) + "}"
+            }
+            Stmt::Return(e) => format!("return{};", value(e)),
+            Stmt::Break { label: l, value: e } => format!("break{}{};", target(l), value(e)),
+            Stmt::Continue { label: l } => format!("continue{};", target(l)),
+            Stmt::Assert(e) => format!("assert {};", self.expr(e, depth)),
+            Stmt::Panic(None) => "panic;".to_string(),
+            Stmt::Panic(Some(e)) => format!("panic({});", self.expr(e, depth)),
+            Stmt::Expr(e) => format!("{};", self.expr(e, depth)),
+        }
+    }
+
+    fn pattern(&self, p: &Pattern) -> String {
+        let binds = |binds: &[PatBind]| -> String {
+            if binds.is_empty() {
+                return String::new();
+            }
+            let names: Vec<&str> = (binds.iter())
+                .map(|b| match b {
+                    PatBind::Name(n) => self.name(*n),
+                    PatBind::Wildcard => "_",
+                })
+                .collect();
+            format!("({})", names.join(", "))
+        };
+        match p {
+            Pattern::Variant { enum_name, variant, binds: b } => {
+                format!("{}::{}{}", self.name(*enum_name), self.name(*variant), binds(b))
+            }
+            Pattern::Bare { variant, binds: b } => format!("{}{}", self.name(*variant), binds(b)),
+            Pattern::Str(s) => quote(s),
+            Pattern::Float(f) => format!("{f:?}"),
+            Pattern::Or(alts) => {
+                alts.iter().map(|a| self.pattern(a)).collect::<Vec<_>>().join(" | ")
+            }
+            Pattern::Wildcard => "_".to_string(),
+        }
+    }
+
+    /// The condition or scrutinee before a block, where a bare struct literal
+    /// would read as the block.
+    fn head(&self, e: &Expr, depth: usize) -> String {
+        match e {
+            Expr::StructLit { .. } => format!("({})", self.expr(e, depth)),
+            _ => self.expr(e, depth),
+        }
+    }
+
+    /// `e` as an operand: parenthesized unless it is an atom.
+    fn operand(&self, e: &Expr, depth: usize) -> String {
+        match e {
+            Expr::Bin(..)
+            | Expr::Un(..)
+            | Expr::Cast { .. }
+            | Expr::Lambda { .. }
+            | Expr::Ref { .. }
+            | Expr::Deref(_)
+            | Expr::Return(_)
+            | Expr::If { .. } => format!("({})", self.expr(e, depth)),
+            _ => self.expr(e, depth),
+        }
+    }
+
+    fn args(&self, args: &[Expr], depth: usize) -> String {
+        args.iter().map(|a| self.expr(a, depth)).collect::<Vec<_>>().join(", ")
+    }
+
+    /// `e`, `depth` blocks in (which only the blocks of a `loop` or an `if` need).
+    fn expr(&self, e: &Expr, depth: usize) -> String {
+        rv_arena::stack::grow(|| self.expr_here(e, depth))
+    }
+
+    fn expr_here(&self, e: &Expr, depth: usize) -> String {
+        match e {
+            Expr::Int(n) => n.to_string(),
+            Expr::SuffixedInt(n, ty) => format!("{n}{}", ty.show(self.syms)),
+            Expr::Float(f) => format!("{f:?}"),
+            Expr::Str(s) => quote(s),
+            Expr::Bool(b) => b.to_string(),
+            Expr::Unit => "()".to_string(),
+            Expr::Lambda { params, body } => {
+                let params: Vec<&str> = params.iter().map(|p| self.name(*p)).collect();
+                format!("|{}| {}", params.join(", "), self.expr(body, depth))
+            }
+            Expr::Var(n) => self.name(*n).to_string(),
+            Expr::Inst { func, ty_args } => {
+                format!("{}::<{}>", self.name(*func), self.tys(ty_args))
+            }
+            Expr::Call { func, args } => {
+                format!("{}({})", self.name(*func), self.args(args, depth))
+            }
+            Expr::Bin(op, a, b) => {
+                let (a, b) = (self.operand(a, depth), self.operand(b, depth));
+                format!("{a} {} {b}", bin_op(*op))
+            }
+            Expr::Un(op, a) => {
+                let op = match op {
+                    UnOp::Neg => "-",
+                    UnOp::Not => "!",
+                };
+                format!("{op}{}", self.operand(a, depth))
+            }
+            Expr::Cast { expr, ty } => {
+                format!("{} as {}", self.operand(expr, depth), ty.show(self.syms))
+            }
+            Expr::Loop { label, body } => {
+                let label = label.map_or_else(String::new, |l| self.label(l));
+                format!("{label}loop {}", self.block(body, depth))
+            }
+            Expr::If { cond, then_blk, then_val, else_blk, else_val } => {
+                let then = self.block_then(then_blk, then_val.as_deref(), depth);
+                let els = self.block_then(else_blk, else_val.as_deref(), depth);
+                format!("if {} {then} else {els}", self.expr(cond, depth))
+            }
+            Expr::StructLit { name, fields } => {
+                let fields: Vec<String> = (fields.iter())
+                    .map(|(f, e)| format!("{}: {}", self.name(*f), self.expr(e, depth)))
+                    .collect();
+                format!("{} {{ {} }}", self.name(*name), fields.join(", "))
+            }
+            Expr::EnumCtor { enum_name, variant, args } => {
+                let path = format!("{}::{}", self.name(*enum_name), self.name(*variant));
+                match args.is_empty() {
+                    true => path,
+                    false => format!("{path}({})", self.args(args, depth)),
+                }
+            }
+            Expr::Field { base, field } => {
+                format!("{}.{}", self.operand(base, depth), self.name(*field))
+            }
+            Expr::MethodCall { recv, method, args } => {
+                let recv = self.operand(recv, depth);
+                format!("{recv}.{}({})", self.name(*method), self.args(args, depth))
+            }
+            Expr::Ref { mutable, expr } => {
+                format!("&{}{}", mutability(*mutable), self.operand(expr, depth))
+            }
+            Expr::Deref(e) => format!("*{}", self.operand(e, depth)),
+            Expr::Try(e) => format!("{}?", self.operand(e, depth)),
+            Expr::Return(None) => "return".to_string(),
+            Expr::Return(Some(e)) => format!("return {}", self.expr(e, depth)),
+            Expr::Apply { .. }
+            | Expr::MatchExpr { .. }
+            | Expr::Fun { .. }
+            | Expr::Forall { .. }
+            | Expr::LetIn { .. }
+            | Expr::Arrow(..)
+            | Expr::TypeUniv(_)
+            | Expr::Prop
+            | Expr::Hole
+            | Expr::Rewrite { .. }
+            | Expr::Decide
+            | Expr::ByCases { .. } => "<term>".to_string(),
+        }
+    }
+}
+
+fn mutability(mutable: bool) -> &'static str {
+    if mutable {
+        "mut "
+    } else {
+        ""
+    }
+}
+
+fn bin_op(op: BinOp) -> &'static str {
+    match op {
+        BinOp::Add => "+",
+        BinOp::Sub => "-",
+        BinOp::Mul => "*",
+        BinOp::Div => "/",
+        BinOp::Mod => "%",
+        BinOp::And => "&&",
+        BinOp::Or => "||",
+        BinOp::Eq => "==",
+        BinOp::Ne => "!=",
+        BinOp::Lt => "<",
+        BinOp::Le => "<=",
+        BinOp::Gt => ">",
+        BinOp::Ge => ">=",
+        BinOp::BitAnd => "&",
+        BinOp::BitOr => "|",
+        BinOp::BitXor => "^",
+        BinOp::Shl => "<<",
+        BinOp::Shr => ">>",
+    }
+}
+
+/// `s` as a string literal, with the escapes the lexer reads.
+fn quote(s: &str) -> String {
+    let mut out = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,211 @@
+//! Resolution of `Self` inside `impl` blocks.
+//!
+//! Within `impl T { .. }` or `impl Trait for T { .. }`, `Self` is an alias for
+//! `T`: as a type (`-> Self`, `other: Self`, `let p: Self`), as a struct
+//! literal (`Self { x: 0 }`), and as a path head (`Self::Variant(..)`,
+//! `Self::new()`, `Self::A(x) => ..`). A [`crate::desugar`] pass rewrites each
+//! method with every such `Self` replaced by the impl's type name, so the rest
+//! of lowering only ever sees concrete names. A trait keeps `Self`
+//! abstract; its signatures are instantiated per impl by [`subst_ty`] when the
+//! impl is checked against them.
+
+use rv_core::Sym;
+use rv_syntax::ast::{Block, Expr, MatchArm, MethodDecl, Pattern, Stmt, Ty};
+
+/// The `Self` substitution for one impl block.
+#[derive(Clone, Copy)]
+pub(crate) struct SelfTy {
+    /// The interned `Self` symbol.
+    pub self_sym: Sym,
+    /// The impl's type name.
+    pub ty: Sym,
+}
+
+impl SelfTy {
+    /// `decl` with `Self` replaced by the impl's type throughout its signature,
+    /// spec clauses, and body.
+    pub fn method(self, decl: &MethodDecl) -> MethodDecl {
+        let mut decl = decl.clone();
+        for p in &mut decl.params {
+            self.ty(&mut p.ty);
+        }
+        if let Some(ret) = &mut decl.ret {
+            self.ty(ret);
+        }
+        for e in decl.requires.iter_mut().chain(&mut decl.ensures) {
+            self.expr(e);
+        }
+        self.block(&mut decl.body);
+        decl
+    }
+
+    fn name(self, name: &mut Sym) {
+        if *name == self.self_sym {
+            *name = self.ty;
+        }
+    }
+
+    fn ty(self, ty: &mut Ty) {
+        rv_arena::stack::grow(|| self.ty_here(ty))
+    }
+
+    fn ty_here(self, ty: &mut Ty) {
+        match ty {
+            Ty::Adt(name) => self.name(name),
+            Ty::Generic { base, args } => {
+                self.name(base);
+                args.iter_mut().for_each(|a| self.ty(a));
+            }
+            Ty::Ref { inner, .. } => self.ty(inner),
+            Ty::Fn { params, ret } => {
+                params.iter_mut().for_each(|p| self.ty(p));
+                self.ty(ret);
+            }
+            Ty::I64
+            | Ty::IntN(_)
+            | Ty::F64
+            | Ty::Bool
+            | Ty::String
+            | Ty::Unit
+            | Ty::Param(_)
+            | Ty::ImplTrait(_)
+            | Ty::Term(_) => {}
+        }
+    }
+
+    fn block(self, b: &mut Block) {
+        rv_arena::stack::grow(|| self.block_here(b))
+    }
+
+    fn block_here(self, b: &mut Block) {
+        b.stmts.iter_mut().for_each(|s| self.stmt(s));
+    }
+
+    fn stmt(self, s: &mut Stmt) {
+        match s {
+            Stmt::Let { ty, init, .. } => {
+                if let Some(ty) = ty {
+                    self.ty(ty);
+                }
+                if let Some(init) = init {
+                    self.expr(init);
+                }
+            }
+            Stmt::Assign { value, .. } => self.expr(value),
+            Stmt::DerefAssign { place, value } => {
+                self.expr(place);
+                self.expr(value);
+            }
+            Stmt::If { cond, then_blk, else_blk } => {
+                self.expr(cond);
+                self.block(then_blk);
+                if let Some(b) = else_blk {
+                    self.block(b);
+                }
+            }
+            Stmt::While { cond, invariants, body, .. } => {
+                self.expr(cond);
+                invariants.iter_mut().for_each(|e| self.expr(e));
+                self.block(body);
+            }
+            Stmt::Match { scrut, arms } => {
+                self.expr(scrut);
+                for MatchArm { pat, body } in arms {
+                    self.pattern(pat);
+                    self.block(body);
+                }
+            }
+            Stmt::Return(Some(e))
+            | Stmt::Break { value: Some(e), .. }
+            | Stmt::Panic(Some(e))
+            | Stmt::Assert(e)
+            | Stmt::Expr(e) => self.expr(e),
+            Stmt::Return(None)
+            | Stmt::Break { value: None, .. }
+            | Stmt::Panic(None)
+            | Stmt::Continue { .. } => {}
+        }
+    }
+
+    fn pattern(self, p: &mut Pattern) {
+        match p {
+            Pattern::Variant { enum_name, .. } => self.name(enum_name),
+            Pattern::Or(alts) => alts.iter_mut().for_each(|a| self.pattern(a)),
+            Pattern::Str(_) | Pattern::Float(_) | Pattern::Bare { .. } | Pattern::Wildcard => {}
+        }
+    }
+
+    fn expr(self, e: &mut Expr) {
+        rv_arena::stack::grow(|| self.expr_here(e))
+    }
+
+    fn expr_here(self, e: &mut Expr) {
+        match e {
+            Expr::StructLit { name, fields } => {
+                self.name(name);
+                fields.iter_mut().for_each(|(_, f)| self.expr(f));
+            }
+            Expr::EnumCtor { enum_name, args, .. } => {
+                self.name(enum_name);
+                args.iter_mut().for_each(|a| self.expr(a));
+            }
+            Expr::Cast { expr, ty } => {
+                self.expr(expr);
+                self.ty(ty);
+            }
+            Expr::Inst { ty_args, .. } => ty_args.iter_mut().for_each(|t| self.ty(t)),
+            Expr::Call { args, .. } => args.iter_mut().for_each(|a| self.expr(a)),
+            Expr::MethodCall { recv, args, .. } => {
+                self.expr(recv);
+                args.iter_mut().for_each(|a| self.expr(a));
+            }
+            Expr::Lambda { body, .. } => self.expr(body),
+            Expr::Loop { body, .. } => self.block(body),
+            Expr::If { cond, then_blk, then_val, else_blk, else_val } => {
+                self.expr(cond);
+                self.block(then_blk);
+                self.block(else_blk);
+                then_val.iter_mut().chain(else_val).for_each(|e| self.expr(e));
+            }
+            Expr::Bin(_, a, b) => {
+                self.expr(a);
+                self.expr(b);
+            }
+            Expr::Un(_, a)
+            | Expr::Field { base: a, .. }
+            | Expr::Ref { expr: a, .. }
+            | Expr::Deref(a)
+            | Expr::Try(a)
+            | Expr::Return(Some(a)) => self.expr(a),
+            Expr::Int(_)
+            | Expr::SuffixedInt(..)
+            | Expr::Float(_)
+            | Expr::Str(_)
+            | Expr::Bool(_)
+            | Expr::Unit
+            | Expr::Var(_)
+            | Expr::Return(None) => {}
+            // Proof-fragment forms never reach the executable lowering.
+            Expr::Apply { .. }
+            | Expr::MatchExpr { .. }
+            | Expr::Fun { .. }
+            | Expr::Forall { .. }
+            | Expr::LetIn { .. }
+            | Expr::Arrow(..)
+            | Expr::TypeUniv(_)
+            | Expr::Prop
+            | Expr::Hole
+            | Expr::Rewrite { .. }
+            | Expr::Decide
+            | Expr::ByCases { .. } => {}
+        }
+    }
+}
+
+/// `ty` with `Self` replaced by `self_ty.ty`: a trait signature instantiated for
+/// one impl.
+pub(crate) fn subst_ty(ty: &Ty, self_ty: SelfTy) -> Ty {
+    let mut ty = ty.clone();
+    self_ty.ty(&mut ty);
+    ty
+}
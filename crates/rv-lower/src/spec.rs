@@ -33,6 +33,10 @@ pub struct SpecCtx<'a> {
 /// `Prop` form so the solver sees the logical skeleton; any other boolean-valued
 /// term is wrapped in `Prop::Holds`.
 pub fn lower_prop(e: &Expr, syms: &mut Symbols, ctx: &SpecCtx) -> Result<Prop, String> {
+    rv_arena::stack::grow(|| lower_prop_here(e, syms, ctx))
+}
+
+fn lower_prop_here(e: &Expr, syms: &mut Symbols, ctx: &SpecCtx) -> Result<Prop, String> {
     match e {
         Expr::Bool(true) => Ok(Prop::True),
         Expr::Bool(false) => Ok(Prop::False),
@@ -53,8 +57,13 @@ pub fn lower_prop(e: &Expr, syms: &mut Symbols, ctx: &SpecCtx) -> Result<Prop, S
 /// Rejects constructs that cannot appear in a (first-order) term: calls, the
 /// unit literal, aggregates, references, and `?`.
 pub fn lower_term(e: &Expr, syms: &mut Symbols, ctx: &SpecCtx) -> Result<Term, String> {
+    rv_arena::stack::grow(|| lower_term_here(e, syms, ctx))
+}
+
+fn lower_term_here(e: &Expr, syms: &mut Symbols, ctx: &SpecCtx) -> Result<Term, String> {
     match e {
-        Expr::Int(n) => Ok(Term::Int(*n)),
+        // A suffix only fixes the width a value is stored at; terms are unbounded.
+        Expr::Int(n) | Expr::SuffixedInt(n, _) => Ok(Term::Int(*n)),
         Expr::Bool(b) => Ok(Term::Bool(*b)),
         Expr::Var(s) => Ok(Term::Var(*s)),
         Expr::Bin(op, a, b) => Ok(Term::bin(
@@ -98,6 +107,9 @@ pub fn lower_term(e: &Expr, syms: &mut Symbols, ctx: &SpecCtx) -> Result<Term, S
         Expr::Ref { .. } | Expr::Deref(_) => {
             Err("references and dereferences are not allowed in specifications".to_string())
         }
+        // Conversions (wrapping, saturating, to/from float) have no linear-integer
+        // meaning in the spec logic.
+        Expr::Cast { .. } => Err("`as` casts are not allowed in specifications".to_string()),
         // The `?` operator early-returns / is effectful, so it is not a pure term.
         Expr::Try(_) => {
             Err("the `?` operator is not allowed in specifications".to_string())
@@ -109,6 +121,10 @@ pub fn lower_term(e: &Expr, syms: &mut Symbols, ctx: &SpecCtx) -> Result<Term, S
         Expr::Lambda { .. } => {
             Err("closures are not allowed in specifications".to_string())
         }
+        // A loop is control flow, not a term.
+        Expr::Loop { .. } => Err("loops are not allowed in specifications".to_string()),
+        Expr::If { .. } => Err("`if` expressions are not allowed in specifications".to_string()),
+        Expr::Return(_) => Err("`return` is not allowed in specifications".to_string()),
         // Proof-fragment expression forms are not first-order spec terms (they route
         // to the kernel, not the spec solver).
         _ => Err("proof-fragment expressions are not allowed in specifications".to_string()),
@@ -13,8 +13,50 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 
 use rv_core::{Sym, Symbols, Ty as CoreTy};
+use rv_diagnostics::E0107;
 use rv_ir::{FieldDef, TypeDef, VariantDef};
-use rv_syntax::ast::{EnumDecl, Expr, StructDecl, TypeAliasDecl, Ty as AstTy};
+use rv_syntax::ast::{EnumDecl, Expr, GenericParam, StructDecl, TypeAliasDecl, Ty as AstTy};
+
+/// The generic type parameters visible where an annotation is written, innermost
+/// binder last. Each binder maps a parameter's surface name to the symbol its
+/// `Ty::Param` carries. A function's or method's parameters get symbols of their
+/// own ([`Symbols::scoped`]), so a callee's `T` and its caller's `T` stay two
+/// parameters through inference and instantiation; a type declaration's keep
+/// their names, since only its own fields mention them.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TypeScope {
+    binders: Vec<(Sym, Sym)>,
+}
+
+impl TypeScope {
+    /// A type declaration's parameters, under their own names.
+    pub fn declared(names: &[Sym]) -> Self {
+        TypeScope { binders: names.iter().map(|n| (*n, *n)).collect() }
+    }
+
+    /// `self` with `generics` bound inside it, each to a fresh scoped symbol.
+    pub fn enter(&self, generics: &[GenericParam], syms: &mut Symbols) -> Self {
+        let mut inner = self.clone();
+        inner.binders.extend(generics.iter().map(|g| (g.name, syms.scoped(g.name))));
+        inner
+    }
+
+    /// The parameter `name` refers to here: its innermost binder.
+    pub fn lookup(&self, name: Sym) -> Option<Sym> {
+        self.binders.iter().rev().find(|(n, _)| *n == name).map(|(_, p)| *p)
+    }
+
+    pub fn contains(&self, name: Sym) -> bool {
+        self.lookup(name).is_some()
+    }
+
+    /// Every parameter in scope, outermost first: the type parameters of a
+    /// function whose body is this scope (a closure's include its enclosing
+    /// function's).
+    pub fn params(&self) -> Vec<Sym> {
+        self.binders.iter().map(|(_, p)| *p).collect()
+    }
+}
 
 /// Resolved information about a single struct.
 pub struct StructInfo {
@@ -58,16 +100,36 @@ pub struct Types {
     /// The `TypeDef`s to embed into `Program.types`, in declaration order.
     pub defs: Vec<TypeDef>,
     /// Method-resolution table: `(receiver ADT name, method name) -> mangled
-    /// top-level function name`. Populated from `impl` blocks (both inherent and
-    /// trait impls share this table). Used to desugar `recv.m(args)` calls.
+    /// top-level function name`. Populated from inherent `impl` blocks. Used to
+    /// desugar `recv.m(args)` calls.
     methods: HashMap<(Sym, Sym), Sym>,
+    /// Trait-impl methods: `(receiver ADT name, method name) -> [(trait name,
+    /// mangled name)]`, in declaration order. An inherent method of the same name
+    /// takes precedence; two traits providing it make a plain call ambiguous.
+    trait_methods: HashMap<(Sym, Sym), Vec<(Sym, Sym)>>,
     /// Optional record of declared trait method-name sets, keyed by trait name.
     /// Kept for validation only; never affects code generation.
     traits: HashMap<Sym, HashSet<Sym>>,
     /// Function (and mangled-method) name -> the ADT its return type names, when it
-    /// returns a struct/enum. Lets `adt_of_expr` resolve the ADT of a call result,
-    /// so `match`/`?`/method-calls compose on call results.
-    fn_ret_adt: HashMap<Sym, Sym>,
+    /// returns a struct/enum, and how many references deep the ADT sits. Lets
+    /// `adt_of_expr` resolve the ADT of a call result, so `match`/`?`/method-calls
+    /// and field access compose on call results.
+    fn_ret_adt: HashMap<Sym, (Sym, usize)>,
+    /// Function (and mangled-method) name -> its return type, when that is a
+    /// primitive, so a method can be called on the result of a call.
+    fn_ret_primitive: HashMap<Sym, CoreTy>,
+    /// The primitive types some inherent `impl` extends, keyed by the name the
+    /// impl wrote (`impl i64`). A primitive's methods are registered under that
+    /// name like an ADT's, and found from a receiver's type by [`Types::primitive_impl`].
+    primitives: HashMap<Sym, CoreTy>,
+    /// Top-level function name -> its type parameters and `Fn` type (over those
+    /// parameters), so a bare function name in value position can be lowered to a
+    /// function-pointer value instead of an unbound variable, and `f::<T>` can be
+    /// given its instantiated type.
+    fns: HashMap<Sym, (Vec<Sym>, CoreTy)>,
+    /// Positional field names (`0`, `1`, ... as in `value.0`) -> their index, for
+    /// every index some variant has.
+    positional: HashMap<Sym, u32>,
     /// Refinement aliases lower to a runtime base type plus a predicate over
     /// `self`. They are intentionally non-generic in this first surface slice.
     aliases: HashMap<Sym, (CoreTy, Expr)>,
@@ -90,7 +152,7 @@ impl Types {
             if t.aliases.contains_key(&alias.name) {
                 return Err(format!("duplicate type alias `{}`", syms.resolve(alias.name)));
             }
-            let base = t.resolve_ty(&alias.base, &HashSet::new());
+            let base = t.resolve_ty(&alias.base, &TypeScope::default());
             t.aliases.insert(alias.name, (base, alias.refinement.clone()));
         }
 
@@ -101,7 +163,7 @@ impl Types {
             // The struct's own type parameters scope its field types: a field
             // type naming one of them lowers to `Ty::Param`.
             let type_params: Vec<Sym> = s.generics.iter().map(|g| g.name).collect();
-            let scope: HashSet<Sym> = type_params.iter().copied().collect();
+            let scope = TypeScope::declared(&type_params);
             let mut fields = Vec::with_capacity(s.fields.len());
             let mut field_index = HashMap::new();
             let mut field_defs = Vec::with_capacity(s.fields.len());
@@ -128,7 +190,7 @@ impl Types {
             }
             // The enum's own type parameters scope its variant field types.
             let type_params: Vec<Sym> = e.generics.iter().map(|g| g.name).collect();
-            let scope: HashSet<Sym> = type_params.iter().copied().collect();
+            let scope = TypeScope::declared(&type_params);
             let mut variant_index = HashMap::new();
             let mut variant_field_aliases = HashMap::new();
             let mut variant_defs = Vec::with_capacity(e.variants.len());
@@ -144,6 +206,9 @@ impl Types {
                     v.name,
                     v.fields.iter().map(|ty| t.alias_name(ty)).collect(),
                 );
+                for index in 0..v.fields.len() {
+                    t.positional.insert(syms.intern(&index.to_string()), index as u32);
+                }
                 let tys = v.fields.iter().map(|ty| t.resolve_ty(ty, &scope)).collect();
                 variant_defs.push(VariantDef { name: v.name, fields: tys });
             }
@@ -156,22 +221,32 @@ impl Types {
 
     /// Resolve a surface annotation with refinement aliases expanded to their
     /// runtime representation.
-    pub fn resolve_ty(&self, ty: &AstTy, scope: &HashSet<Sym>) -> CoreTy {
+    pub fn resolve_ty(&self, ty: &AstTy, scope: &TypeScope) -> CoreTy {
+        rv_arena::stack::grow(|| self.resolve_ty_here(ty, scope))
+    }
+
+    fn resolve_ty_here(&self, ty: &AstTy, scope: &TypeScope) -> CoreTy {
         match ty {
-            AstTy::Adt(name) if !scope.contains(name) => self
+            // `Self` in an impl of a primitive names that primitive.
+            AstTy::Adt(name) if self.primitives.contains_key(name) => self.primitives[name].clone(),
+            AstTy::Adt(name) if !scope.contains(*name) => self
                 .aliases
                 .get(name)
                 .map(|(base, _)| base.clone())
-                .unwrap_or(CoreTy::Adt(*name)),
-            AstTy::Generic { base, .. } if !scope.contains(base) => self
-                .aliases
-                .get(base)
-                .map(|(ty, _)| ty.clone())
-                .unwrap_or(CoreTy::Adt(*base)),
+                .unwrap_or(CoreTy::Adt(*name, vec![])),
+            AstTy::Generic { base, args } if !scope.contains(*base) => {
+                self.aliases.get(base).map(|(ty, _)| ty.clone()).unwrap_or_else(|| {
+                    CoreTy::Adt(*base, args.iter().map(|a| self.resolve_ty(a, scope)).collect())
+                })
+            }
             AstTy::Ref { mutable, inner } => CoreTy::Ref {
                 mutable: *mutable,
                 inner: Box::new(self.resolve_ty(inner, scope)),
             },
+            AstTy::Fn { params, ret } => CoreTy::Fn(
+                params.iter().map(|p| self.resolve_ty(p, scope)).collect(),
+                Box::new(self.resolve_ty(ret, scope)),
+            ),
             _ => resolve_ty(ty, scope),
         }
     }
@@ -194,6 +269,18 @@ impl Types {
         self.structs.get(&name)
     }
 
+    /// Field types of struct `name` in declaration order, with its type parameters
+    /// replaced by `args`. Parameters that `args` does not cover stay parameters.
+    pub fn struct_field_tys(&self, name: Sym, args: &[CoreTy]) -> Option<Vec<CoreTy>> {
+        self.defs.iter().find_map(|def| match def {
+            TypeDef::Struct { name: n, type_params, fields } if *n == name => {
+                let map: HashMap<Sym, &CoreTy> = type_params.iter().copied().zip(args).collect();
+                Some(fields.iter().map(|f| subst_params(&f.ty, &map)).collect())
+            }
+            _ => None,
+        })
+    }
+
     pub fn struct_field_alias(&self, name: Sym, index: usize) -> Option<Sym> {
         self.structs.get(&name)?.field_aliases.get(index).copied().flatten()
     }
@@ -264,24 +351,123 @@ impl Types {
         self.enums.get(&name)
     }
 
+    /// The index a positional field name (`value.0`) spells, if some enum variant
+    /// has a field at that index.
+    pub fn positional_field(&self, field: Sym) -> Option<u32> {
+        self.positional.get(&field).copied()
+    }
+
     /// Whether `name` is a known user ADT (struct or enum).
-    /// Record that function `name` returns ADT `adt`.
-    pub fn set_fn_ret(&mut self, name: Sym, adt: Sym) {
-        self.fn_ret_adt.insert(name, adt);
+    /// Record that function `name` returns ADT `adt` behind `refs` references.
+    pub fn set_fn_ret(&mut self, name: Sym, adt: Sym, refs: usize) {
+        self.fn_ret_adt.insert(name, (adt, refs));
     }
     /// The ADT a function's return type names, if any.
     pub fn fn_ret(&self, name: Sym) -> Option<Sym> {
-        self.fn_ret_adt.get(&name).copied()
+        self.fn_ret_adt.get(&name).map(|&(adt, _)| adt)
+    }
+    /// Record that function `name` returns the primitive type `ty`.
+    pub fn set_fn_ret_primitive(&mut self, name: Sym, ty: CoreTy) {
+        self.fn_ret_primitive.insert(name, ty);
+    }
+    /// The primitive type a function returns, if it returns one.
+    pub fn fn_ret_primitive(&self, name: Sym) -> Option<&CoreTy> {
+        self.fn_ret_primitive.get(&name)
+    }
+    /// How many references deep the ADT [`Types::fn_ret`] names is returned.
+    pub fn fn_ret_refs(&self, name: Sym) -> usize {
+        self.fn_ret_adt.get(&name).map_or(0, |&(_, refs)| refs)
+    }
+    /// Record that `name` is a top-level function with type parameters `generics`
+    /// and function type `ty`.
+    pub fn register_fn(&mut self, name: Sym, generics: Vec<Sym>, ty: CoreTy) {
+        self.fns.insert(name, (generics, ty));
+    }
+    /// Whether `name` is a top-level function.
+    pub fn is_fn(&self, name: Sym) -> bool {
+        self.fns.contains_key(&name)
+    }
+    /// The function type of `name` with its type parameters replaced by `args`.
+    pub fn instantiate_fn(
+        &self,
+        name: Sym,
+        args: &[CoreTy],
+        syms: &Symbols,
+    ) -> Result<CoreTy, String> {
+        let (generics, ty) = &self.fns[&name];
+        if generics.len() != args.len() {
+            return Err(E0107.tag(format!(
+                "function `{}` takes {} type argument(s) but {} were supplied",
+                syms.resolve(name),
+                generics.len(),
+                args.len()
+            )));
+        }
+        let map: HashMap<Sym, &CoreTy> = generics.iter().copied().zip(args).collect();
+        Ok(subst_params(ty, &map))
     }
 
     pub fn is_adt(&self, name: Sym) -> bool {
         self.structs.contains_key(&name) || self.enums.contains_key(&name)
     }
 
+    /// Record that an impl names the primitive type `ty` as `name`.
+    pub fn declare_primitive(&mut self, name: Sym, ty: CoreTy) {
+        self.primitives.insert(name, ty);
+    }
+
+    /// The primitive type an impl's type name `name` denotes, if it is one.
+    pub fn primitive(&self, name: Sym) -> Option<&CoreTy> {
+        self.primitives.get(&name)
+    }
+
+    /// The name the methods of primitive type `ty` are registered under, if an
+    /// impl extends it.
+    pub fn primitive_impl(&self, ty: &CoreTy) -> Option<Sym> {
+        self.primitives.iter().find(|(_, t)| *t == ty).map(|(name, _)| *name)
+    }
+
+    /// The names a `Head::name` path can start with: every struct, enum, and trait.
+    pub fn path_heads(&self) -> impl Iterator<Item = Sym> + '_ {
+        self.structs.keys().chain(self.enums.keys()).chain(self.traits.keys()).copied()
+    }
+
+    /// The names a `head::name` path can reach: `head`'s variants when it is an
+    /// enum, its associated functions (inherent or from a trait impl), and its
+    /// methods when it is a trait.
+    pub fn path_members(&self, head: Sym) -> Vec<Sym> {
+        let variants = self.enums.get(&head).map(|e| e.variant_index.keys());
+        let mut out: Vec<Sym> = variants.into_iter().flatten().copied().collect();
+        let fns = self.methods.keys().chain(self.trait_methods.keys());
+        out.extend(fns.filter(|(adt, _)| *adt == head).map(|(_, m)| *m));
+        out.extend(self.traits.get(&head).into_iter().flatten().copied());
+        out
+    }
+
     /// Look up the mangled top-level function implementing `method` on receiver
-    /// type `adt`, if any impl provided it.
+    /// type `adt`: the inherent method if there is one, else the trait method when
+    /// exactly one trait impl provides it.
     pub fn method(&self, adt: Sym, method: Sym) -> Option<Sym> {
-        self.methods.get(&(adt, method)).copied()
+        if let Some(&mangled) = self.methods.get(&(adt, method)) {
+            return Some(mangled);
+        }
+        match self.trait_methods.get(&(adt, method)).map(Vec::as_slice) {
+            Some([(_, mangled)]) => Some(*mangled),
+            _ => None,
+        }
+    }
+
+    /// The traits whose impls for `adt` provide `method`, in declaration order.
+    pub fn method_traits(&self, adt: Sym, method: Sym) -> Vec<Sym> {
+        self.trait_methods.get(&(adt, method)).map_or_else(Vec::new, |provided| {
+            provided.iter().map(|(t, _)| *t).collect()
+        })
+    }
+
+    /// The mangled function `trait_name`'s impl for `adt` provides for `method`.
+    pub fn trait_method(&self, trait_name: Sym, adt: Sym, method: Sym) -> Option<Sym> {
+        let provided = self.trait_methods.get(&(adt, method))?;
+        provided.iter().find(|(t, _)| *t == trait_name).map(|(_, mangled)| *mangled)
     }
 
     /// Record a trait's declared method-name set (validation only).
@@ -289,58 +475,66 @@ impl Types {
         self.traits.insert(trait_name, method_names.into_iter().collect());
     }
 
+    /// Whether `name` is a trait declared in this module.
+    pub fn is_trait(&self, name: Sym) -> bool {
+        self.traits.contains_key(&name)
+    }
+
     /// Register one impl method: resolve its mangled name and add it to the
     /// method-resolution table. Returns the mangled `Sym` so the caller can lower
     /// the method body under that name.
     ///
-    /// Mangling is `"TypeName::method"` (interned). Distinct receiver types get
-    /// distinct mangled names; the trait name (if any) is used only for the
-    /// optional bound check below, never in the mangled symbol.
+    /// Mangling is `"TypeName::method"` for an inherent method and
+    /// `"<TypeName as Trait>::method"` for a trait method (interned), so an
+    /// inherent method and any number of trait methods may share a name.
     pub fn register_method(
         &mut self,
         type_name: Sym,
+        trait_name: Option<Sym>,
         method: Sym,
         syms: &mut Symbols,
     ) -> Result<Sym, String> {
-        if !self.is_adt(type_name) {
+        if !self.is_adt(type_name) && self.primitive(type_name).is_none() {
             return Err(format!(
-                "`impl` targets unknown type `{}` (only user structs/enums are supported)",
+                "`impl` targets unknown type `{}` (only user structs/enums and primitive \
+                 types are supported)",
                 syms.resolve(type_name)
             ));
         }
-        let mangled = mangle_method(type_name, method, syms);
-        if self.methods.insert((type_name, method), mangled).is_some() {
+        if trait_name.is_some() && self.primitive(type_name).is_some() {
             return Err(format!(
-                "duplicate method `{}` for type `{}`",
-                syms.resolve(method),
+                "trait impls for the primitive type `{}` are not supported; use an inherent \
+                 `impl {0}` block",
                 syms.resolve(type_name)
             ));
         }
-        Ok(mangled)
-    }
-
-    /// Validate that a trait impl provides exactly the trait's declared methods
-    /// (best-effort; only runs when the trait was declared in this module).
-    pub fn check_trait_impl(
-        &self,
-        trait_name: Sym,
-        type_name: Sym,
-        provided: &HashSet<Sym>,
-        syms: &Symbols,
-    ) -> Result<(), String> {
-        if let Some(required) = self.traits.get(&trait_name) {
-            for m in required {
-                if !provided.contains(m) {
-                    return Err(format!(
-                        "impl of trait `{}` for `{}` is missing method `{}`",
-                        syms.resolve(trait_name),
-                        syms.resolve(type_name),
-                        syms.resolve(*m)
-                    ));
-                }
+        let duplicate = |syms: &Symbols| {
+            format!(
+                "duplicate method `{}` for type `{}`",
+                syms.resolve(method),
+                syms.resolve(type_name)
+            )
+        };
+        let Some(trait_name) = trait_name else {
+            let mangled = mangle_method(type_name, method, syms);
+            if self.methods.insert((type_name, method), mangled).is_some() {
+                return Err(duplicate(syms));
             }
+            return Ok(mangled);
+        };
+        let provided = self.trait_methods.entry((type_name, method)).or_default();
+        if provided.iter().any(|(t, _)| *t == trait_name) {
+            return Err(duplicate(syms));
         }
-        Ok(())
+        let mangled = format!(
+            "<{} as {}>::{}",
+            syms.resolve(type_name),
+            syms.resolve(trait_name),
+            syms.resolve(method)
+        );
+        let mangled = syms.intern(&mangled);
+        provided.push((trait_name, mangled));
+        Ok(mangled)
     }
 }
 
@@ -350,15 +544,22 @@ pub(crate) fn mangle_method(type_name: Sym, method: Sym, syms: &mut Symbols) ->
     syms.intern(&mangled)
 }
 
-/// Resolve a surface type annotation to a core type within a set of in-scope
-/// type parameters (`scope`).
+/// Resolve a surface type annotation to a core type within the in-scope type
+/// parameters `scope`.
 ///
-/// * `IDENT` whose name is in `scope` -> `Ty::Param` (a generic type parameter);
-///   otherwise -> `Ty::Adt` (a named struct/enum).
-/// * `Base<args...>` -> `Ty::Adt(Base)` — generic type arguments are **erased**
-///   (the VM is dynamic / type-erased), so `Option<i64>` becomes `Adt(Option)`.
+/// * `IDENT` bound in `scope` -> `Ty::Param` of its innermost binder (a generic
+///   type parameter); otherwise -> `Ty::Adt` (a named struct/enum) with no type
+///   arguments.
+/// * `Base<args...>` -> `Ty::Adt(Base, args)`, so `Option<i64>` becomes
+///   `Adt(Option, [Int])`. The VM itself is type-erased; the arguments only
+///   serve checking.
 /// * `&T` / `&mut T` -> `Ty::Ref`.
-pub(crate) fn resolve_ty(ty: &AstTy, scope: &HashSet<Sym>) -> CoreTy {
+/// * `fn(A, B) -> R` -> `Ty::Fn`.
+pub(crate) fn resolve_ty(ty: &AstTy, scope: &TypeScope) -> CoreTy {
+    rv_arena::stack::grow(|| resolve_ty_here(ty, scope))
+}
+
+fn resolve_ty_here(ty: &AstTy, scope: &TypeScope) -> CoreTy {
     match ty {
         AstTy::I64 => CoreTy::Int,
         AstTy::IntN(w) => CoreTy::IntN(*w),
@@ -366,21 +567,47 @@ pub(crate) fn resolve_ty(ty: &AstTy, scope: &HashSet<Sym>) -> CoreTy {
         AstTy::Bool => CoreTy::Bool,
         AstTy::String => CoreTy::Str,
         AstTy::Unit => CoreTy::Unit,
-        AstTy::Adt(name) => {
-            if scope.contains(name) {
-                CoreTy::Param(*name)
-            } else {
-                CoreTy::Adt(*name)
-            }
-        }
+        AstTy::Adt(name) => match scope.lookup(*name) {
+            Some(param) => CoreTy::Param(param),
+            None => CoreTy::Adt(*name, vec![]),
+        },
         AstTy::Param(name) => CoreTy::Param(*name),
-        // Erase the type arguments to the base ADT.
-        AstTy::Generic { base, .. } => CoreTy::Adt(*base),
+        AstTy::Generic { base, args } => {
+            CoreTy::Adt(*base, args.iter().map(|a| resolve_ty(a, scope)).collect())
+        }
         AstTy::Ref { mutable, inner } => {
             CoreTy::Ref { mutable: *mutable, inner: Box::new(resolve_ty(inner, scope)) }
         }
+        AstTy::Fn { params, ret } => CoreTy::Fn(
+            params.iter().map(|p| resolve_ty(p, scope)).collect(),
+            Box::new(resolve_ty(ret, scope)),
+        ),
         // A dependent type-expression only ever appears in the proof fragment, which
         // routes to the kernel and never reaches executable type resolution.
         AstTy::Term(_) => CoreTy::Unit,
+        AstTy::ImplTrait(_) => {
+            unreachable!("an `impl Trait` return type is replaced by the body's concrete type")
+        }
+    }
+}
+
+/// `ty` with each `Ty::Param` named in `args` replaced by its argument.
+fn subst_params(ty: &CoreTy, args: &HashMap<Sym, &CoreTy>) -> CoreTy {
+    rv_arena::stack::grow(|| subst_params_here(ty, args))
+}
+
+fn subst_params_here(ty: &CoreTy, args: &HashMap<Sym, &CoreTy>) -> CoreTy {
+    let go = |t: &CoreTy| subst_params(t, args);
+    match ty {
+        CoreTy::Param(name) => args.get(name).map_or_else(|| ty.clone(), |arg| (*arg).clone()),
+        CoreTy::Adt(name, items) => CoreTy::Adt(*name, items.iter().map(go).collect()),
+        CoreTy::Ref { mutable, inner } => {
+            CoreTy::Ref { mutable: *mutable, inner: Box::new(go(inner)) }
+        }
+        CoreTy::Tuple(items) => CoreTy::Tuple(items.iter().map(go).collect()),
+        CoreTy::Array(item, len) => CoreTy::Array(Box::new(go(item)), *len),
+        CoreTy::Vec(item) => CoreTy::Vec(Box::new(go(item))),
+        CoreTy::Fn(params, ret) => CoreTy::Fn(params.iter().map(go).collect(), Box::new(go(ret))),
+        _ => ty.clone(),
     }
 }
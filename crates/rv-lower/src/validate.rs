@@ -0,0 +1,94 @@
+//! Name uniqueness within a declaration.
+//!
+//! Lowering looks up parameters, generic parameters, fields, variants, and
+//! methods by name, so two of them sharing a name inside one declaration would
+//! make all but one unreachable. Every such list is checked up front, before any
+//! type or body is lowered. Shadowing *across* scopes — a `let` rebinding a
+//! parameter, say — is ordinary and is not checked here.
+
+use std::collections::HashSet;
+
+use rv_core::{Sym, Symbols};
+use rv_diagnostics::E0415;
+use rv_syntax::ast::{EnumDecl, FnDecl, GenericParam, ImplDecl, Param, StructDecl, TraitDecl};
+
+/// The executable items of one module, as partitioned by the lowering.
+pub(crate) struct Decls<'a> {
+    pub structs: &'a [&'a StructDecl],
+    pub enums: &'a [&'a EnumDecl],
+    pub fns: &'a [&'a FnDecl],
+    pub traits: &'a [&'a TraitDecl],
+    pub impls: &'a [&'a ImplDecl],
+}
+
+/// Err (E0415) on the first name bound twice in one declaration's list.
+pub(crate) fn check_unique_names(decls: &Decls<'_>, syms: &Symbols) -> Result<(), String> {
+    let n = |s: Sym| syms.resolve(s).to_string();
+    unique(decls.fns.iter().map(|f| f.name), || "the module's functions".into(), syms)?;
+    for f in decls.fns {
+        generics(&f.generics, || format!("`{}`", n(f.name)), syms)?;
+        params(&f.params, || format!("`{}`", n(f.name)), syms)?;
+    }
+    for s in decls.structs {
+        generics(&s.generics, || format!("struct `{}`", n(s.name)), syms)?;
+        let fields = s.fields.iter().map(|f| f.name);
+        unique(fields, || format!("the fields of struct `{}`", n(s.name)), syms)?;
+    }
+    for e in decls.enums {
+        generics(&e.generics, || format!("enum `{}`", n(e.name)), syms)?;
+        let variants = e.variants.iter().map(|v| v.name);
+        unique(variants, || format!("the variants of enum `{}`", n(e.name)), syms)?;
+    }
+    for tr in decls.traits {
+        let methods = tr.methods.iter().map(|m| m.name);
+        unique(methods, || format!("the methods of trait `{}`", n(tr.name)), syms)?;
+        for m in &tr.methods {
+            generics(&m.generics, || format!("`{}::{}`", n(tr.name), n(m.name)), syms)?;
+            params(&m.params, || format!("`{}::{}`", n(tr.name), n(m.name)), syms)?;
+        }
+    }
+    for im in decls.impls {
+        let ty = n(im.type_name);
+        let block = match im.trait_name {
+            Some(tr) => format!("`impl {} for {ty}`", n(tr)),
+            None => format!("`impl {ty}`"),
+        };
+        unique(im.methods.iter().map(|m| m.name), || format!("the methods of {block}"), syms)?;
+        for m in &im.methods {
+            generics(&m.generics, || format!("`{ty}::{}`", n(m.name)), syms)?;
+            params(&m.params, || format!("`{ty}::{}`", n(m.name)), syms)?;
+        }
+    }
+    Ok(())
+}
+
+fn generics(
+    generics: &[GenericParam],
+    owner: impl Fn() -> String,
+    syms: &Symbols,
+) -> Result<(), String> {
+    unique(generics.iter().map(|g| g.name), || format!("the type parameters of {}", owner()), syms)
+}
+
+fn params(params: &[Param], owner: impl Fn() -> String, syms: &Symbols) -> Result<(), String> {
+    unique(params.iter().map(|p| p.name), || format!("the parameters of {}", owner()), syms)
+}
+
+/// Err if any name repeats in `names`, all bound together in the list `place` describes.
+fn unique(
+    names: impl IntoIterator<Item = Sym>,
+    place: impl Fn() -> String,
+    syms: &Symbols,
+) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    for name in names {
+        if !seen.insert(name) {
+            return Err(E0415.tag(format!(
+                "identifier `{}` is bound more than once in {}",
+                syms.resolve(name),
+                place()
+            )));
+        }
+    }
+    Ok(())
+}
@@ -5,7 +5,8 @@
 //! `rv_core::Ty` is only introduced later (during inference), so we keep a syntax-
 //! local notion of type here.
 
-use rv_core::{BinOp, IntTy, Sym, UnOp};
+use rv_core::{BinOp, IntTy, Sym, Symbols, UnOp};
+pub use rv_diagnostics::LineSpan;
 
 /// A whole compilation unit: a sequence of top-level items.
 #[derive(Clone, Debug, PartialEq)]
@@ -25,6 +26,8 @@ pub enum Item {
     Trait(TraitDecl),
     /// An `impl Type { methods }` or `impl Trait for Type { methods }` block.
     Impl(ImplDecl),
+    /// `use Enum::Variant;` or `use Enum::*;`, naming variants without their enum.
+    Use(UseDecl),
 
     // --- proof fragment (route to the kernel, not to rv-lower) ---
     /// `axiom name(params) : Type` — an assumed constant (no body). Used by the
@@ -49,6 +52,41 @@ pub struct TypeAliasDecl {
     pub refinement: Expr,
 }
 
+/// A `use` of one enum variant, or with `variant: None` (`use Enum::*`) all of
+/// them, so the variant can be named alone in expressions and patterns.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UseDecl {
+    pub vis: Visibility,
+    pub enum_name: Sym,
+    pub variant: Option<Sym>,
+    pub line: u32,
+}
+
+/// Who may name an item, field, or method: `pub`, or no one outside its module.
+/// Recorded as written; nothing enforces it yet.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Visibility {
+    #[default]
+    Private,
+    Public,
+    /// `pub(crate)`, written on `line`: reserved syntax, as private as
+    /// [`Visibility::Private`] until crate visibility is supported (the
+    /// `unsupported-visibility` lint says so).
+    Crate { line: u32 },
+}
+
+impl Visibility {
+    /// The visibility as the source spells it before an item, space included:
+    /// `"pub "`, `"pub(crate) "`, or nothing.
+    pub fn show(self) -> &'static str {
+        match self {
+            Visibility::Private => "",
+            Visibility::Public => "pub ",
+            Visibility::Crate { .. } => "pub(crate) ",
+        }
+    }
+}
+
 /// An `axiom name(params) : ty` declaration (proof fragment).
 #[derive(Clone, Debug, PartialEq)]
 pub struct AxiomDecl {
@@ -69,7 +107,7 @@ pub struct DefDecl {
 }
 
 /// Surface type annotations: `i64`, `bool`, `()`, a named ADT, a reference, a
-/// generic type application, or a bare generic type parameter.
+/// generic type application, a function pointer, or a bare generic type parameter.
 // Not `Eq`: `Ty::Term` embeds an `Expr`, which carries `f64` (only `PartialEq`).
 #[derive(Clone, Debug, PartialEq)]
 pub enum Ty {
@@ -89,6 +127,12 @@ pub enum Ty {
     /// A generic type application `Base<arg0, arg1, ...>` (e.g. `Option<i64>`).
     /// Lowering erases the type arguments to the base ADT (`Ty::Adt(base)`).
     Generic { base: Sym, args: Vec<Ty> },
+    /// A function-pointer type `fn(A, B) -> R`; a missing `-> R` means `()`.
+    Fn { params: Vec<Ty>, ret: Box<Ty> },
+    /// An opaque type `impl Trait0 + Trait1`, naming its trait bounds. Only a
+    /// function's or method's return type may be one; lowering replaces it with
+    /// the single concrete type the body returns.
+    ImplTrait(Vec<Sym>),
     /// A bare type-parameter reference (`T` inside `fn f<T>(..)`). The parser
     /// never produces this directly (it can't tell a param from an ADT name);
     /// lowering rewrites a matching `Ty::Adt` into this form.
@@ -101,6 +145,51 @@ pub enum Ty {
     Term(Box<Expr>),
 }
 
+impl Ty {
+    /// The primitive type spelled `name` (`i64`, `u8`, `f64`, `bool`, `String`).
+    pub fn primitive(name: &str) -> Option<Ty> {
+        match name {
+            "i64" => Some(Ty::I64),
+            "f64" => Some(Ty::F64),
+            "bool" => Some(Ty::Bool),
+            "String" => Some(Ty::String),
+            _ => crate::parser::fixed_int_ty(name).map(Ty::IntN),
+        }
+    }
+
+    /// The type behind every reference in `self`: `S` for `&&mut S`.
+    pub fn pointee(&self) -> &Ty {
+        match self {
+            Ty::Ref { inner, .. } => inner.pointee(),
+            ty => ty,
+        }
+    }
+
+    /// This type as the source spells it.
+    pub fn show(&self, syms: &Symbols) -> String {
+        let list = |tys: &[Ty]| tys.iter().map(|t| t.show(syms)).collect::<Vec<_>>().join(", ");
+        match self {
+            Ty::I64 => "i64".to_string(),
+            Ty::IntN(it) => format!("{}{}", if it.signed { 'i' } else { 'u' }, it.bits),
+            Ty::F64 => "f64".to_string(),
+            Ty::Bool => "bool".to_string(),
+            Ty::String => "String".to_string(),
+            Ty::Unit => "()".to_string(),
+            Ty::Adt(s) | Ty::Param(s) => syms.resolve(*s).to_string(),
+            Ty::Ref { mutable, inner } => {
+                format!("&{}{}", if *mutable { "mut " } else { "" }, inner.show(syms))
+            }
+            Ty::Generic { base, args } => format!("{}<{}>", syms.resolve(*base), list(args)),
+            Ty::Fn { params, ret } => format!("fn({}) -> {}", list(params), ret.show(syms)),
+            Ty::ImplTrait(bounds) => {
+                let names: Vec<&str> = bounds.iter().map(|b| syms.resolve(*b)).collect();
+                format!("impl {}", names.join(" + "))
+            }
+            Ty::Term(_) => "<term>".to_string(),
+        }
+    }
+}
+
 /// A generic type parameter with optional trait bounds: `T` or `T: Trait0 + Trait1`.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct GenericParam {
@@ -113,15 +202,17 @@ pub struct GenericParam {
 /// A `struct Name<G...> { f0: T0, f1: T1, ... }` declaration.
 #[derive(Clone, Debug, PartialEq)]
 pub struct StructDecl {
+    pub vis: Visibility,
     pub name: Sym,
     /// Generic type parameters (`struct Pair<A, B> {..}`); empty if non-generic.
     pub generics: Vec<GenericParam>,
     pub fields: Vec<FieldDecl>,
 }
 
-/// A single struct field `name: ty`.
+/// A single struct field `name: ty`, or `pub name: ty`.
 #[derive(Clone, Debug, PartialEq)]
 pub struct FieldDecl {
+    pub vis: Visibility,
     pub name: Sym,
     pub ty: Ty,
 }
@@ -131,6 +222,9 @@ pub struct FieldDecl {
 /// `enum R<G…>(i0: T0, …) -> Prop { C(f: T, …) where i == e, …; … }`.
 #[derive(Clone, Debug, PartialEq)]
 pub struct EnumDecl {
+    /// The visibility of the enum and so of every variant, which cannot have one
+    /// of its own.
+    pub vis: Visibility,
     pub name: Sym,
     /// Generic type parameters (`enum Option<T> {..}`); empty if non-generic.
     pub generics: Vec<GenericParam>,
@@ -161,14 +255,18 @@ pub struct VariantDecl {
 /// no IR of their own.
 #[derive(Clone, Debug, PartialEq)]
 pub struct TraitDecl {
+    pub vis: Visibility,
     pub name: Sym,
     pub methods: Vec<TraitMethodSig>,
 }
 
-/// One method signature inside a trait: `fn name(self?, params) (-> ty)? ;`.
+/// One method signature inside a trait: `fn name<G...>(self?, params) (-> ty)? ;`.
 #[derive(Clone, Debug, PartialEq)]
 pub struct TraitMethodSig {
     pub name: Sym,
+    /// The method's own type parameters, which an impl's method must match
+    /// in number and bounds.
+    pub generics: Vec<GenericParam>,
     /// Whether the first parameter is the receiver `self`.
     pub has_self: bool,
     pub params: Vec<Param>,
@@ -191,6 +289,7 @@ pub struct ImplDecl {
 /// be the receiver `self` (whose type is the impl's `type_name`).
 #[derive(Clone, Debug, PartialEq)]
 pub struct MethodDecl {
+    pub vis: Visibility,
     pub name: Sym,
     /// Generic type parameters on the method itself (`fn m<T>(..)`).
     pub generics: Vec<GenericParam>,
@@ -207,6 +306,7 @@ pub struct MethodDecl {
 /// A function declaration with its signature, spec clauses, and body.
 #[derive(Clone, Debug, PartialEq)]
 pub struct FnDecl {
+    pub vis: Visibility,
     pub name: Sym,
     /// Generic type parameters (`fn f<T, U>(..)`); empty if non-generic.
     pub generics: Vec<GenericParam>,
@@ -231,19 +331,39 @@ pub struct Param {
 }
 
 /// A braced sequence of statements.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Debug, PartialEq)]
 pub struct Block {
     pub stmts: Vec<Stmt>,
+    /// The source lines each statement covers, parallel to `stmts`. Lowering
+    /// threads these onto the IR so inference errors can name a line.
+    pub spans: Vec<LineSpan>,
+    /// The lines from the opening brace to the closing one.
+    pub span: LineSpan,
+}
+
+/// A copy of a block copies the blocks nested in it, as deep as they go, so it
+/// grows the stack like the passes that walk them.
+impl Clone for Block {
+    fn clone(&self) -> Self {
+        rv_arena::stack::grow(|| Block {
+            stmts: self.stmts.clone(),
+            spans: self.spans.clone(),
+            span: self.span,
+        })
+    }
 }
 
 /// A statement.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Stmt {
-    /// `let name (: ty)? = init;`
+    /// `let mut? name (: ty)? (= init)?;`. Without an initializer the binding is
+    /// assigned later, and every read must follow an assignment on all paths.
+    /// Any binding may be reassigned; `mut` marks one as meant to be.
     Let {
         name: Sym,
+        mutable: bool,
         ty: Option<Ty>,
-        init: Expr,
+        init: Option<Expr>,
     },
     /// `name = value;`
     Assign { name: Sym, value: Expr },
@@ -256,8 +376,10 @@ pub enum Stmt {
         then_blk: Block,
         else_blk: Option<Block>,
     },
-    /// `while cond (invariant inv;)* { body }`
+    /// `('label:)? while cond (invariant inv;)* { body }`
     While {
+        /// The loop's label, targetable by `break 'label` / `continue 'label`.
+        label: Option<Sym>,
         cond: Expr,
         /// Zero or more loop-invariant clauses, in source order.
         invariants: Vec<Expr>,
@@ -267,6 +389,13 @@ pub enum Stmt {
     Match { scrut: Expr, arms: Vec<MatchArm> },
     /// `return value?;`
     Return(Option<Expr>),
+    /// `break ('label)? value?;` — leave the innermost enclosing loop, or the one
+    /// named by `label`. A value is only allowed when leaving a `loop`, whose
+    /// result it becomes.
+    Break { label: Option<Sym>, value: Option<Expr> },
+    /// `continue ('label)?;` — start the next iteration of the innermost enclosing
+    /// loop, or the one named by `label`.
+    Continue { label: Option<Sym> },
     /// `assert cond;`
     Assert(Expr),
     /// `panic;` or `panic(expr);` — abort the program. An optional argument is
@@ -283,7 +412,8 @@ pub struct MatchArm {
     pub body: Block,
 }
 
-/// A match pattern: either an enum-variant pattern with field binders, or `_`.
+/// A match pattern: an enum-variant pattern with field binders, a string or float
+/// literal, an or-pattern of alternatives, or `_`.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Pattern {
     /// `Enum::Variant(b0, b1, ...)` (binders may be `_`); a unit/no-paren variant
@@ -293,8 +423,18 @@ pub enum Pattern {
         variant: Sym,
         binds: Vec<PatBind>,
     },
+    /// A string literal `"text"`, matching an equal string.
+    Str(String),
+    /// A float literal `1.5` (optionally negated), matching an equal float.
+    Float(f64),
+    /// `p0 | p1 | ...`: matches when any alternative does. Alternatives bind
+    /// nothing.
+    Or(Vec<Pattern>),
     /// The wildcard `_`, matching anything (the `otherwise` arm).
     Wildcard,
+    /// A variant named without its enum, `Circle(r)`, which a `use` must bring
+    /// into scope. Lowering resolves it to a [`Pattern::Variant`].
+    Bare { variant: Sym, binds: Vec<PatBind> },
 }
 
 /// A single binder inside a variant pattern: a name to bind, or `_` to ignore.
@@ -310,6 +450,9 @@ pub enum Expr {
     /// Full 128-bit magnitude; see `Tok::Int`'s doc comment for the bit-pattern
     /// convention used for unsigned literals above `i128::MAX`.
     Int(i128),
+    /// An integer literal with a type suffix (`5u32`, `3i64`). It has exactly
+    /// that type, whatever its context expects.
+    SuffixedInt(i128, Ty),
     Float(f64),
     Str(String),
     Bool(bool),
@@ -319,6 +462,9 @@ pub enum Expr {
     Lambda { params: Vec<Sym>, body: Box<Expr> },
     /// A variable reference (includes `result` inside `ensures`).
     Var(Sym),
+    /// A generic function named with explicit type arguments, `identity::<i64>`,
+    /// as a function value.
+    Inst { func: Sym, ty_args: Vec<Ty> },
     /// `f(args)`
     Call { func: Sym, args: Vec<Expr> },
     /// General application `callee(args)` where the callee is an arbitrary
@@ -329,12 +475,32 @@ pub enum Expr {
     Bin(BinOp, Box<Expr>, Box<Expr>),
     /// A unary operation.
     Un(UnOp, Box<Expr>),
+    /// An explicit conversion `expr as ty`. Binds tighter than every binary
+    /// operator and looser than the prefix unary operators, as in Rust.
+    Cast { expr: Box<Expr>, ty: Ty },
+    /// `('label:)? loop { body }` — repeat `body` until a `break` leaves it. The
+    /// loop's value is that of the `break value` expressions targeting it (`()`
+    /// for a plain `break`).
+    Loop { label: Option<Sym>, body: Block },
+    /// `if cond { then } else { els }` as an expression. Each branch's value is
+    /// the expression ending its block without a `;` (`()` when there is none);
+    /// a branch that leaves through `return`, `break` or `continue` gives none,
+    /// so `if c { return 0 } else { 5 }` has the other branch's type. An
+    /// `else if` is an `else` block holding only the nested `if`.
+    If {
+        cond: Box<Expr>,
+        then_blk: Block,
+        then_val: Option<Box<Expr>>,
+        else_blk: Block,
+        else_val: Option<Box<Expr>>,
+    },
     /// A struct literal `Name { f: e, ... }`. Field exprs are in source order;
     /// lowering reorders them to the struct's declared field order.
     StructLit { name: Sym, fields: Vec<(Sym, Expr)> },
     /// An enum constructor `Enum::Variant(args)` (or unit `Enum::Variant`).
     EnumCtor { enum_name: Sym, variant: Sym, args: Vec<Expr> },
-    /// Field access `base.field`.
+    /// Field access `base.field`. A positional field `base.0` is named by the
+    /// digits of its index.
     Field { base: Box<Expr>, field: Sym },
     /// A method call `recv.method(args)`. Desugared in lowering to a resolved
     /// call on the mangled top-level function, with `recv` as the first argument.
@@ -347,6 +513,10 @@ pub enum Expr {
     /// enum, it evaluates to the success payload, or early-returns the failure
     /// variant from the enclosing function.
     Try(Box<Expr>),
+    /// `return expr?` in expression position (`let x = f(return 0);`). Leaves the
+    /// enclosing function with the value (`()` if absent) and never produces one
+    /// itself, so it has type `!` and fits wherever any value is expected.
+    Return(Option<Box<Expr>>),
 
     // --- proof fragment (the unified grammar; these reach the kernel, not the VM) ---
     /// `match scrut { | Pat => expr | … }` as an **expression** (value-producing,
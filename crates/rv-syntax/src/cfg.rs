@@ -0,0 +1,93 @@
+//! Conditional compilation: `#[cfg(...)]` on items.
+//!
+//! An item may carry any number of `#[cfg(pred)]` attributes; it is compiled only
+//! if every predicate holds under the active [`CfgSet`]. The parser evaluates them
+//! as it goes and drops the item otherwise, so no later stage ever sees it: a use
+//! of a cfg'd-out item is an ordinary unresolved-name error.
+//!
+//! ```text
+//! pred := IDENT                     // an option is set: `test`
+//!       | IDENT "=" STRING          // a key has a value: `feature = "simd"`
+//!       | "not" "(" pred ")"
+//!       | "all" "(" (pred ("," pred)*)? ")"   // `all()` is true
+//!       | "any" "(" (pred ("," pred)*)? ")"   // `any()` is false
+//! ```
+
+use std::collections::BTreeSet;
+
+/// The configuration options in force for one compilation: bare names (`test`)
+/// and `key = "value"` pairs (`feature = "x"`). A key may hold several values.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CfgSet {
+    options: BTreeSet<(String, Option<String>)>,
+}
+
+impl CfgSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the bare option `name`.
+    pub fn enable(&mut self, name: &str) {
+        self.options.insert((name.to_string(), None));
+    }
+
+    /// Give `key` the value `value`, alongside any it already has.
+    pub fn set(&mut self, key: &str, value: &str) {
+        self.options.insert((key.to_string(), Some(value.to_string())));
+    }
+
+    /// Apply a command-line `--cfg` argument: `name`, `key=value` or
+    /// `key="value"`.
+    pub fn add_flag(&mut self, flag: &str) -> Result<(), String> {
+        let valid = |s: &str| {
+            s.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        };
+        match flag.split_once('=') {
+            None if valid(flag) => self.enable(flag),
+            Some((key, value)) if valid(key.trim()) => {
+                let value = value.trim();
+                let unquoted = value.strip_prefix('"').and_then(|v| v.strip_suffix('"'));
+                self.set(key.trim(), unquoted.unwrap_or(value));
+            }
+            _ => {
+                return Err(format!(
+                    "malformed cfg option `{flag}` (expected `name` or `key=value`)"
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    fn has(&self, key: &str, value: Option<&str>) -> bool {
+        self.options.contains(&(key.to_string(), value.map(str::to_string)))
+    }
+}
+
+/// A parsed `cfg` predicate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CfgPred {
+    Option(String),
+    KeyValue(String, String),
+    Not(Box<CfgPred>),
+    All(Vec<CfgPred>),
+    Any(Vec<CfgPred>),
+}
+
+impl CfgPred {
+    /// Whether the predicate holds under `cfg`.
+    pub fn eval(&self, cfg: &CfgSet) -> bool {
+        rv_arena::stack::grow(|| self.eval_here(cfg))
+    }
+
+    fn eval_here(&self, cfg: &CfgSet) -> bool {
+        match self {
+            CfgPred::Option(name) => cfg.has(name, None),
+            CfgPred::KeyValue(key, value) => cfg.has(key, Some(value)),
+            CfgPred::Not(p) => !p.eval(cfg),
+            CfgPred::All(ps) => ps.iter().all(|p| p.eval(cfg)),
+            CfgPred::Any(ps) => ps.iter().any(|p| p.eval(cfg)),
+        }
+    }
+}
@@ -184,7 +184,11 @@ pub fn classify(m: &Module) -> Vec<Fragment> {
     m.items
         .iter()
         .map(|it| match it {
-            Item::Struct(_) | Item::TypeAlias(_) | Item::Trait(_) | Item::Impl(_) => Fragment::Exec,
+            Item::Struct(_)
+            | Item::TypeAlias(_)
+            | Item::Trait(_)
+            | Item::Impl(_)
+            | Item::Use(_) => Fragment::Exec,
             Item::Axiom(_) | Item::Def(_) | Item::Instance(_) | Item::Mutual(_) => Fragment::Proof,
             Item::Enum(e) => {
                 if proof_types.contains(&e.name) {
@@ -228,6 +232,10 @@ fn ty_is_logical(t: Option<&Ty>, proof_types: &HashSet<Sym>) -> bool {
 /// form? Such specs (`result == Nat::Succ(x)`) are discharged by the kernel; purely scalar
 /// specs (`p.v != 0`) stay on `rv-solve`'s executable path.
 fn expr_is_dependent_spec(e: &Expr) -> bool {
+    rv_arena::stack::grow(|| expr_is_dependent_spec_here(e))
+}
+
+fn expr_is_dependent_spec_here(e: &Expr) -> bool {
     match e {
         Expr::EnumCtor { .. } => true,
         _ if expr_has_proof_form(e) => true,
@@ -236,7 +244,7 @@ fn expr_is_dependent_spec(e: &Expr) -> bool {
         Expr::Un(_, a) | Expr::Deref(a) | Expr::Try(a) | Expr::Ref { expr: a, .. } => {
             expr_is_dependent_spec(a)
         }
-        Expr::Field { base, .. } => expr_is_dependent_spec(base),
+        Expr::Field { base, .. } | Expr::Cast { expr: base, .. } => expr_is_dependent_spec(base),
         Expr::Call { args, .. } => args.iter().any(expr_is_dependent_spec),
         Expr::MethodCall { recv, args, .. } => {
             expr_is_dependent_spec(recv) || args.iter().any(expr_is_dependent_spec)
@@ -256,13 +264,23 @@ fn enum_refs_any(e: &EnumDecl, proof_types: &HashSet<Sym>) -> bool {
 
 /// Does the (possibly nested) type `ty` name a type in `proof_types`?
 fn ty_names_proof_type(ty: &Ty, proof_types: &HashSet<Sym>) -> bool {
+    rv_arena::stack::grow(|| ty_names_proof_type_here(ty, proof_types))
+}
+
+fn ty_names_proof_type_here(ty: &Ty, proof_types: &HashSet<Sym>) -> bool {
     match ty {
         Ty::Adt(s) | Ty::Param(s) => proof_types.contains(s),
         Ty::Generic { base, args } => {
             proof_types.contains(base) || args.iter().any(|a| ty_names_proof_type(a, proof_types))
         }
         Ty::Ref { inner, .. } => ty_names_proof_type(inner, proof_types),
+        Ty::Fn { params, ret } => {
+            params.iter().any(|p| ty_names_proof_type(p, proof_types))
+                || ty_names_proof_type(ret, proof_types)
+        }
         Ty::Term(_) => true,
+        // The bounds name traits, which the proof fragment never declares.
+        Ty::ImplTrait(_) => false,
         Ty::I64 | Ty::IntN(_) | Ty::F64 | Ty::Bool | Ty::String | Ty::Unit => false,
     }
 }
@@ -271,12 +289,16 @@ fn ty_names_proof_type(ty: &Ty, proof_types: &HashSet<Sym>) -> bool {
 
 /// Does any expression in the block use a proof-only form?
 fn block_has_proof_form(b: &Block) -> bool {
+    rv_arena::stack::grow(|| block_has_proof_form_here(b))
+}
+
+fn block_has_proof_form_here(b: &Block) -> bool {
     b.stmts.iter().any(stmt_has_proof_form)
 }
 
 fn stmt_has_proof_form(s: &Stmt) -> bool {
     match s {
-        Stmt::Let { init, .. } => expr_has_proof_form(init),
+        Stmt::Let { init, .. } => init.as_ref().is_some_and(expr_has_proof_form),
         Stmt::Assign { value, .. } => expr_has_proof_form(value),
         Stmt::DerefAssign { place, value } => {
             expr_has_proof_form(place) || expr_has_proof_form(value)
@@ -290,7 +312,10 @@ fn stmt_has_proof_form(s: &Stmt) -> bool {
         Stmt::Match { scrut, arms } => {
             expr_has_proof_form(scrut) || arms.iter().any(|a| block_has_proof_form(&a.body))
         }
-        Stmt::Return(e) | Stmt::Panic(e) => e.as_ref().is_some_and(expr_has_proof_form),
+        Stmt::Return(e) | Stmt::Panic(e) | Stmt::Break { value: e, .. } => {
+            e.as_ref().is_some_and(expr_has_proof_form)
+        }
+        Stmt::Continue { .. } => false,
         Stmt::Assert(e) | Stmt::Expr(e) => expr_has_proof_form(e),
     }
 }
@@ -298,6 +323,10 @@ fn stmt_has_proof_form(s: &Stmt) -> bool {
 /// Is `e` (or a sub-expression) a proof-only form? These are exactly the forms the
 /// executable lowering rejects (see `rv-lower`'s `build.rs`).
 fn expr_has_proof_form(e: &Expr) -> bool {
+    rv_arena::stack::grow(|| expr_has_proof_form_here(e))
+}
+
+fn expr_has_proof_form_here(e: &Expr) -> bool {
     match e {
         Expr::MatchExpr { .. }
         | Expr::Fun { .. }
@@ -322,15 +351,25 @@ fn expr_has_proof_form(e: &Expr) -> bool {
         Expr::MethodCall { recv, args, .. } => {
             expr_has_proof_form(recv) || args.iter().any(expr_has_proof_form)
         }
-        Expr::Field { base, .. } => expr_has_proof_form(base),
+        Expr::Field { base, .. } | Expr::Cast { expr: base, .. } => expr_has_proof_form(base),
         Expr::StructLit { fields, .. } => fields.iter().any(|(_, e)| expr_has_proof_form(e)),
         Expr::Lambda { body, .. } => expr_has_proof_form(body),
+        Expr::Loop { body, .. } => block_has_proof_form(body),
+        Expr::If { cond, then_blk, then_val, else_blk, else_val } => {
+            expr_has_proof_form(cond)
+                || block_has_proof_form(then_blk)
+                || block_has_proof_form(else_blk)
+                || then_val.iter().chain(else_val).any(|e| expr_has_proof_form(e))
+        }
+        Expr::Return(e) => e.as_deref().is_some_and(expr_has_proof_form),
         Expr::Int(_)
+        | Expr::SuffixedInt(..)
         | Expr::Float(_)
         | Expr::Str(_)
         | Expr::Bool(_)
         | Expr::Unit
-        | Expr::Var(_) => false,
+        | Expr::Var(_)
+        | Expr::Inst { .. } => false,
     }
 }
 
@@ -350,6 +389,10 @@ fn ty_calls(t: &Ty, out: &mut HashSet<Sym>) {
 /// `is_zero`) must still be treated as call-graph edges. Constructor names are ignored
 /// (they are resolved against enum declarations, not the `fn` set).
 fn collect_calls(b: &Block, out: &mut HashSet<Sym>) {
+    rv_arena::stack::grow(|| collect_calls_here(b, out))
+}
+
+fn collect_calls_here(b: &Block, out: &mut HashSet<Sym>) {
     for s in &b.stmts {
         stmt_calls(s, out);
     }
@@ -357,7 +400,11 @@ fn collect_calls(b: &Block, out: &mut HashSet<Sym>) {
 
 fn stmt_calls(s: &Stmt, out: &mut HashSet<Sym>) {
     match s {
-        Stmt::Let { init, .. } => expr_calls(init, out),
+        Stmt::Let { init, .. } => {
+            if let Some(init) = init {
+                expr_calls(init, out);
+            }
+        }
         Stmt::Assign { value, .. } => expr_calls(value, out),
         Stmt::DerefAssign { place, value } => {
             expr_calls(place, out);
@@ -380,19 +427,24 @@ fn stmt_calls(s: &Stmt, out: &mut HashSet<Sym>) {
                 collect_calls(&a.body, out);
             }
         }
-        Stmt::Return(e) | Stmt::Panic(e) => {
+        Stmt::Return(e) | Stmt::Panic(e) | Stmt::Break { value: e, .. } => {
             if let Some(e) = e {
                 expr_calls(e, out);
             }
         }
+        Stmt::Continue { .. } => {}
         Stmt::Assert(e) | Stmt::Expr(e) => expr_calls(e, out),
     }
 }
 
 fn expr_calls(e: &Expr, out: &mut HashSet<Sym>) {
+    rv_arena::stack::grow(|| expr_calls_here(e, out))
+}
+
+fn expr_calls_here(e: &Expr, out: &mut HashSet<Sym>) {
     match e {
         // Call / value-reference positions that become call-graph edges.
-        Expr::Var(s) => {
+        Expr::Var(s) | Expr::Inst { func: s, .. } => {
             out.insert(*s);
         }
         Expr::Call { func, args } => {
@@ -417,12 +469,18 @@ fn expr_calls(e: &Expr, out: &mut HashSet<Sym>) {
         | Expr::Deref(a)
         | Expr::Try(a)
         | Expr::Ref { expr: a, .. }
+        | Expr::Cast { expr: a, .. }
         | Expr::Field { base: a, .. } => expr_calls(a, out),
         Expr::MatchExpr { scrut, arms } => {
             expr_calls(scrut, out);
             arms.iter().for_each(|(_, e)| expr_calls(e, out));
         }
         Expr::Fun { body, .. } | Expr::Lambda { body, .. } => expr_calls(body, out),
+        Expr::Return(e) => {
+            if let Some(e) = e {
+                expr_calls(e, out);
+            }
+        }
         Expr::Forall { params, body } => {
             params.iter().for_each(|(_, t)| expr_calls(t, out));
             expr_calls(body, out);
@@ -444,7 +502,15 @@ fn expr_calls(e: &Expr, out: &mut HashSet<Sym>) {
             expr_calls(fbody, out);
         }
         Expr::StructLit { fields, .. } => fields.iter().for_each(|(_, e)| expr_calls(e, out)),
+        Expr::Loop { body, .. } => collect_calls(body, out),
+        Expr::If { cond, then_blk, then_val, else_blk, else_val } => {
+            expr_calls(cond, out);
+            collect_calls(then_blk, out);
+            collect_calls(else_blk, out);
+            then_val.iter().chain(else_val).for_each(|e| expr_calls(e, out));
+        }
         Expr::Int(_)
+        | Expr::SuffixedInt(..)
         | Expr::Float(_)
         | Expr::Str(_)
         | Expr::Bool(_)
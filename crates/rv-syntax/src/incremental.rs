@@ -0,0 +1,445 @@
+//! Incremental reparsing: after an edit, reparse only the items around it.
+//!
+//! A [`ParsedFile`] remembers where each top-level item's text lies: from its
+//! first token (its first attribute, if it has any) up to the next item's first
+//! token, so the ranges tile the file from the first item on. [`reparse`]
+//! applies a [`TextEdit`]; when the edit lies strictly inside one item's range,
+//! it lexes and parses just that item and its two neighbours, splices them into
+//! the previous item list, and moves every later item by the edit's byte and
+//! line deltas. The neighbours are reparsed so that an edit which lets an item
+//! run into the next one (or changes where the previous one stops) is noticed:
+//! the window must come back as the same number of items, starting at the same
+//! places.
+//!
+//! Anything else — an edit that touches an item boundary or the text before the
+//! first item, or a window that does not reparse cleanly — falls back to a full
+//! parse. Either way the result equals parsing the new text from scratch.
+
+use std::ops::Range;
+
+use rv_core::Symbols;
+
+use crate::ast::{Block, Expr, Item, Module, Stmt};
+use crate::cfg::CfgSet;
+use crate::lexer;
+use crate::parser::Parser;
+use crate::DEFAULT_NESTING_LIMIT;
+
+/// Replace the bytes `range` of a file with `text`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+/// A parsed file, with the text range of every item so that [`reparse`] can
+/// find the ones an edit touches.
+#[derive(Clone, Debug)]
+pub struct ParsedFile {
+    pub src: String,
+    pub module: Module,
+    cfg: CfgSet,
+    items: Vec<ItemText>,
+}
+
+/// Where one top-level item's text lies.
+#[derive(Clone, Debug, PartialEq)]
+struct ItemText {
+    /// From the item's first token to the next item's (or the end of the file).
+    range: Range<usize>,
+    /// The line of the first token.
+    line: u32,
+    /// Whether the item is in the module: `false` when its `#[cfg]` is off.
+    kept: bool,
+}
+
+impl ParsedFile {
+    /// Parse `src` from scratch under the cfg options `cfg`.
+    pub fn parse(src: String, syms: &mut Symbols, cfg: &CfgSet) -> Result<ParsedFile, String> {
+        let (module, items) = parse_window(&src, 0, 1, syms, cfg)?;
+        let file = ParsedFile { src, module, cfg: cfg.clone(), items };
+        file.debug_check();
+        Ok(file)
+    }
+
+    /// The cfg options the file was parsed under.
+    pub fn cfg(&self) -> &CfgSet {
+        &self.cfg
+    }
+
+    /// Debug builds double-check every span, as a full parse does.
+    fn debug_check(&self) {
+        #[cfg(debug_assertions)]
+        if let Err(e) = crate::spans::check(&self.module, &self.src) {
+            panic!("internal error: inconsistent spans: {e}");
+        }
+    }
+}
+
+/// Apply `edit` to `old`'s text and parse the result, reparsing only the items
+/// around the edit when that is enough. Names are interned into `syms`, which
+/// must be the table `old` was parsed with.
+pub fn reparse(
+    old: &ParsedFile,
+    edit: &TextEdit,
+    syms: &mut Symbols,
+) -> Result<ParsedFile, String> {
+    let range = edit.range.clone();
+    let in_file = range.start <= range.end && range.end <= old.src.len();
+    if !in_file || !old.src.is_char_boundary(range.start) || !old.src.is_char_boundary(range.end) {
+        let len = old.src.len();
+        return Err(format!("edit range {range:?} is not within the {len}-byte file"));
+    }
+    let src = [&old.src[..range.start], &edit.text, &old.src[range.end..]].concat();
+    match splice(old, edit, &src, syms) {
+        Some(file) => {
+            file.debug_check();
+            Ok(file)
+        }
+        None => {
+            tracing::debug!(?range, "edit is not local to an item: full reparse");
+            ParsedFile::parse(src, syms, &old.cfg)
+        }
+    }
+}
+
+/// The incremental path of [`reparse`], or `None` when it must fall back.
+fn splice(old: &ParsedFile, edit: &TextEdit, src: &str, syms: &mut Symbols) -> Option<ParsedFile> {
+    let TextEdit { range, text } = edit;
+    let inside = |it: &ItemText| it.range.start < range.start && range.end < it.range.end;
+    let k = old.items.iter().position(inside)?;
+    let bytes = text.len() as isize - range.len() as isize;
+    let lines = newlines(text) - newlines(&old.src[range.clone()]);
+    let moved = |at: usize| at.checked_add_signed(bytes).expect("the edit is before `at`");
+
+    // Reparse items `k - 1 ..= k + 1`. Their new starts are known: only the end
+    // of item `k` moves.
+    let window = k.saturating_sub(1)..(k + 2).min(old.items.len());
+    let first = &old.items[window.start];
+    let end = moved(old.items[window.end - 1].range.end);
+    let text = &src[first.range.start..end];
+    let (module, items) = parse_window(text, first.range.start, first.line, syms, &old.cfg).ok()?;
+    let expected = old.items[window.clone()].iter().map(|it| match it.range.start > range.start {
+        true => moved(it.range.start),
+        false => it.range.start,
+    });
+    if items.len() != window.len() || !items.iter().map(|it| it.range.start).eq(expected) {
+        return None;
+    }
+
+    // Splice the window into the item lists; shift what follows it.
+    let kept = |its: &[ItemText]| its.iter().filter(|it| it.kept).count();
+    let before = kept(&old.items[..window.start]);
+    let replaced = before + kept(&old.items[window.clone()]);
+    let mut module_items = old.module.items[..before].to_vec();
+    module_items.extend(module.items);
+    module_items.extend(old.module.items[replaced..].iter().cloned().map(|mut item| {
+        shift_item(&mut item, lines);
+        item
+    }));
+    let mut all = old.items[..window.start].to_vec();
+    all.extend(items);
+    all.extend(old.items[window.end..].iter().map(|it| ItemText {
+        range: moved(it.range.start)..moved(it.range.end),
+        line: it.line.checked_add_signed(lines).expect("lines stay positive"),
+        kept: it.kept,
+    }));
+    let module = Module { items: module_items };
+    Some(ParsedFile { src: src.to_string(), module, cfg: old.cfg.clone(), items: all })
+}
+
+/// Parse `text`, which starts at byte `offset` and line `line` of its file,
+/// into a module and the file ranges of its items.
+fn parse_window(
+    text: &str,
+    offset: usize,
+    line: u32,
+    syms: &mut Symbols,
+    cfg: &CfgSet,
+) -> Result<(Module, Vec<ItemText>), String> {
+    let toks = lexer::lex_at(text, line)?;
+    let parsed = Parser::new(&toks, syms, DEFAULT_NESTING_LIMIT).parse_items(cfg)?;
+    let starts: Vec<usize> = parsed.iter().map(|(first, _)| toks[*first].offset).collect();
+    let mut items = Vec::with_capacity(parsed.len());
+    let mut module = Module { items: Vec::new() };
+    for (i, (first, item)) in parsed.into_iter().enumerate() {
+        let end = starts.get(i + 1).copied().unwrap_or(text.len());
+        items.push(ItemText {
+            range: offset + starts[i]..offset + end,
+            line: toks[first].line,
+            kept: item.is_some(),
+        });
+        module.items.extend(item);
+    }
+    Ok((module, items))
+}
+
+fn newlines(s: &str) -> i32 {
+    s.bytes().filter(|&b| b == b'\n').count() as i32
+}
+
+/// Move every line span in `item` by `delta` lines. Spans live on blocks, which
+/// appear in function and method bodies and, through `loop` and `if`, in
+/// expressions.
+fn shift_item(item: &mut Item, delta: i32) {
+    if delta == 0 {
+        return;
+    }
+    match item {
+        Item::Fn(f) => {
+            let refinements = f.params.iter_mut().filter_map(|p| p.refinement.as_mut());
+            let specs = f.requires.iter_mut().chain(&mut f.ensures);
+            refinements.chain(specs).for_each(|e| shift_expr(e, delta));
+            shift_block(&mut f.body, delta);
+        }
+        Item::Impl(i) => {
+            for m in &mut i.methods {
+                let refinements = m.params.iter_mut().filter_map(|p| p.refinement.as_mut());
+                let specs = m.requires.iter_mut().chain(&mut m.ensures);
+                refinements.chain(specs).for_each(|e| shift_expr(e, delta));
+                shift_block(&mut m.body, delta);
+            }
+        }
+        Item::Def(d) | Item::Instance(d) => shift_expr(&mut d.body, delta),
+        Item::TypeAlias(a) => shift_expr(&mut a.refinement, delta),
+        Item::Use(u) => u.line = u.line.checked_add_signed(delta).expect("lines stay positive"),
+        Item::Struct(_) | Item::Enum(_) | Item::Trait(_) | Item::Axiom(_) | Item::Mutual(_) => {}
+    }
+}
+
+fn shift_block(b: &mut Block, delta: i32) {
+    rv_arena::stack::grow(|| shift_block_here(b, delta))
+}
+
+fn shift_block_here(b: &mut Block, delta: i32) {
+    let shift = |line: &mut u32| {
+        *line = line.checked_add_signed(delta).expect("lines stay positive");
+    };
+    shift(&mut b.span.start);
+    shift(&mut b.span.end);
+    for span in &mut b.spans {
+        shift(&mut span.start);
+        shift(&mut span.end);
+    }
+    b.stmts.iter_mut().for_each(|s| shift_stmt(s, delta));
+}
+
+fn shift_stmt(s: &mut Stmt, delta: i32) {
+    match s {
+        Stmt::Let { init: Some(e), .. }
+        | Stmt::Assign { value: e, .. }
+        | Stmt::Assert(e)
+        | Stmt::Expr(e) => shift_expr(e, delta),
+        Stmt::Let { init: None, .. } => {}
+        Stmt::DerefAssign { place, value } => {
+            shift_expr(place, delta);
+            shift_expr(value, delta);
+        }
+        Stmt::If { cond, then_blk, else_blk } => {
+            shift_expr(cond, delta);
+            shift_block(then_blk, delta);
+            else_blk.iter_mut().for_each(|b| shift_block(b, delta));
+        }
+        Stmt::While { cond, invariants, body, .. } => {
+            shift_expr(cond, delta);
+            invariants.iter_mut().for_each(|e| shift_expr(e, delta));
+            shift_block(body, delta);
+        }
+        Stmt::Match { scrut, arms } => {
+            shift_expr(scrut, delta);
+            arms.iter_mut().for_each(|arm| shift_block(&mut arm.body, delta));
+        }
+        Stmt::Return(e) | Stmt::Panic(e) | Stmt::Break { value: e, .. } => {
+            e.iter_mut().for_each(|e| shift_expr(e, delta));
+        }
+        Stmt::Continue { .. } => {}
+    }
+}
+
+fn shift_expr(e: &mut Expr, delta: i32) {
+    rv_arena::stack::grow(|| shift_expr_here(e, delta))
+}
+
+fn shift_expr_here(e: &mut Expr, delta: i32) {
+    let go = |e: &mut Expr| shift_expr(e, delta);
+    match e {
+        Expr::Loop { body, .. } => shift_block(body, delta),
+        Expr::If { cond, then_blk, then_val, else_blk, else_val } => {
+            go(cond);
+            shift_block(then_blk, delta);
+            shift_block(else_blk, delta);
+            then_val.iter_mut().chain(else_val).for_each(|e| go(e));
+        }
+        Expr::MatchExpr { scrut, arms } => {
+            go(scrut);
+            arms.iter_mut().for_each(|(_, body)| go(body));
+        }
+        Expr::Call { args, .. } | Expr::EnumCtor { args, .. } => args.iter_mut().for_each(go),
+        Expr::MethodCall { recv: a, args, .. } | Expr::Apply { callee: a, args } => {
+            go(a);
+            args.iter_mut().for_each(go);
+        }
+        Expr::StructLit { fields, .. } => fields.iter_mut().for_each(|(_, e)| go(e)),
+        Expr::Bin(_, a, b) | Expr::Arrow(a, b) | Expr::Rewrite { eqn: a, body: b } => {
+            go(a);
+            go(b);
+        }
+        Expr::LetIn { ty, init, body, .. } => {
+            ty.iter_mut().for_each(|t| go(t));
+            go(init);
+            go(body);
+        }
+        Expr::ByCases { scrut, tbody, fbody } => {
+            go(scrut);
+            go(tbody);
+            go(fbody);
+        }
+        Expr::Forall { params, body } => {
+            params.iter_mut().for_each(|(_, t)| go(t));
+            go(body);
+        }
+        Expr::Fun { params, body } => {
+            params.iter_mut().filter_map(|(_, t)| t.as_mut()).for_each(|t| go(t));
+            go(body);
+        }
+        Expr::Return(e) => e.iter_mut().for_each(|e| go(e)),
+        Expr::Un(_, a)
+        | Expr::Deref(a)
+        | Expr::Try(a)
+        | Expr::Ref { expr: a, .. }
+        | Expr::Cast { expr: a, .. }
+        | Expr::Field { base: a, .. }
+        | Expr::Lambda { body: a, .. } => go(a),
+        Expr::Int(_)
+        | Expr::SuffixedInt(..)
+        | Expr::Float(_)
+        | Expr::Str(_)
+        | Expr::Bool(_)
+        | Expr::Unit
+        | Expr::Var(_)
+        | Expr::Inst { .. }
+        | Expr::TypeUniv(_)
+        | Expr::Prop
+        | Expr::Hole
+        | Expr::Decide => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every kind of item, blocks nested in statements and expressions, an
+    /// item left out by `#[cfg]`, and comments between items.
+    const FIXTURE: &str = "// leading comment
+struct Point { x: i64, y: i64 }
+
+enum Shape { Dot(Point), Line(Point, Point) }
+
+type Pos = i64 where self > 0;
+
+impl Point {
+    fn norm(self) -> i64 { return self.x * self.x + self.y * self.y; }
+}
+
+#[cfg(not(test))]
+fn hidden() -> i64 { return 1; }
+
+// between items
+fn count(n: i64) -> i64
+    requires n >= 0;
+    ensures result >= 0;
+{
+    let i = 0;
+    while i < n invariant i <= n; {
+        if i == 3 { i = i + 2; } else { i = i + 1; }
+    }
+    let s = Shape::Dot(Point { x: 1, y: 2 });
+    match s {
+        Shape::Dot(p) => { assert p.x == 1; }
+        Shape::Line(a, b) => { return 0; }
+    }
+    let t = loop { break 7; };
+    return i;
+}
+
+enum Nat { Zero, Succ(Nat) }
+
+fn plus(n: Nat, m: Nat) -> Nat {
+    match n { | Nat::Zero => m | Nat::Succ(k) => Nat::Succ(plus(k, m)) }
+}
+
+fn main() -> i64 {
+    return count(4);
+}
+";
+
+    /// A small deterministic generator (xorshift64), so failures reproduce.
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+    }
+
+    fn full(src: &str, syms: &mut Symbols) -> Result<ParsedFile, String> {
+        ParsedFile::parse(src.to_string(), syms, &CfgSet::new())
+    }
+
+    #[test]
+    fn random_edits_match_a_full_reparse() {
+        let mut syms = Symbols::new();
+        let mut file = full(FIXTURE, &mut syms).expect("the fixture parses");
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        // Mostly characters that keep the file parsing, so that edits keep
+        // landing on the incremental path.
+        let alphabet = b"  \n\nabcxyz019_;{}()+=<>,:/\"#";
+        let mut local = 0;
+        for step in 0..1000 {
+            let at = rng.below(file.src.len() + 1);
+            let (range, text) = match rng.below(3) {
+                0 => (at..at, (alphabet[rng.below(alphabet.len())] as char).to_string()),
+                1 if at < file.src.len() => (at..at + 1, String::new()),
+                _ if at < file.src.len() => {
+                    (at..at + 1, (alphabet[rng.below(alphabet.len())] as char).to_string())
+                }
+                _ => (at..at, " ".to_string()),
+            };
+            let edit = TextEdit { range, text };
+            let src = [&file.src[..edit.range.start], &edit.text, &file.src[edit.range.end..]]
+                .concat();
+            local += usize::from(splice(&file, &edit, &src, &mut syms).is_some());
+            let incremental = reparse(&file, &edit, &mut syms);
+            let expected = full(&src, &mut syms);
+            match (incremental, expected) {
+                (Ok(inc), Ok(exp)) => {
+                    assert_eq!(inc.module, exp.module, "step {step}: {edit:?}");
+                    assert_eq!(inc.items, exp.items, "step {step}: {edit:?}");
+                    file = inc;
+                }
+                (Err(inc), Err(exp)) => assert_eq!(inc, exp, "step {step}: {edit:?}"),
+                (inc, exp) => panic!("step {step}: {edit:?}: {:?} vs {:?}", inc.err(), exp.err()),
+            }
+        }
+        assert!(local > 200, "only {local} edits were reparsed locally");
+    }
+
+    #[test]
+    fn an_inserted_line_moves_the_spans_of_later_items() {
+        let mut syms = Symbols::new();
+        let file = full(FIXTURE, &mut syms).unwrap();
+        let at = FIXTURE.find("return self.x").unwrap();
+        let edit = TextEdit { range: at..at, text: "\n\n".to_string() };
+        let new = reparse(&file, &edit, &mut syms).unwrap();
+        let body = |m: &Module| match m.items.last() {
+            Some(Item::Fn(f)) => f.body.span,
+            other => panic!("expected `main`, found {other:?}"),
+        };
+        assert_eq!(body(&new.module).start, body(&file.module).start + 2);
+        assert_eq!(new.module, full(&new.src, &mut syms).unwrap().module);
+    }
+}
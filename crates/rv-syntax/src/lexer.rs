@@ -1,7 +1,12 @@
 //! Hand-written lexer for the surface language.
 //!
-//! Produces a flat `Vec<SpannedTok>` (token + line number) which the parser then
-//! consumes. Whitespace is insignificant and `//` introduces a line comment.
+//! [`tokenize`] splits source text into a lossless stream of [`Token`]s, each a
+//! kind and a byte range, with whitespace, comments and malformed text kept: an
+//! editor can highlight from it without parsing. [`lex`] reads that stream into
+//! the flat `Vec<SpannedTok>` (token + line number) which the parser consumes,
+//! dropping whitespace and `//` line comments and rejecting malformed text.
+
+use std::ops::Range;
 
 /// A lexical token.
 // Note: not `Eq` because `Float(f64)` is only `PartialEq`. Token comparisons use `==`/`matches!`.
@@ -15,9 +20,14 @@ pub enum Tok {
     /// the corresponding negative `i128`. Downstream (infer/codegen) reinterprets
     /// the bits as unsigned when the literal's inferred type is unsigned.
     Int(i128),
+    /// An integer literal with a type suffix (`5u32`, `3i64`), holding its bits
+    /// as [`Tok::Int`] does and the type the suffix names.
+    SuffixedInt(i128, crate::ast::Ty),
     Float(f64),
     Str(String),
     Ident(String),
+    /// A loop label `'name` (the name is stored without its leading quote).
+    Label(String),
 
     // Keywords.
     Fn,
@@ -59,9 +69,9 @@ pub enum Tok {
     AndAnd, // &&
     EqEq,   // ==
     NotEq,  // !=
-    Lt,     // <
+    Lt,     // < (`<<` is two joint `Lt`s; see `SpannedTok::joint`)
     Le,     // <=
-    Gt,     // >
+    Gt,     // > (`>>` is two joint `Gt`s)
     Ge,     // >=
     Plus,   // +
     Minus,  // -
@@ -69,9 +79,13 @@ pub enum Tok {
     Slash,  // /
     Percent,// %
     Bang,   // !
-    Amp,    // & (shared borrow / reference type)
+    Amp,    // & (shared borrow / reference type, or bitwise and)
+    Caret,  // ^ (bitwise xor)
     Question, // ? (error-propagation postfix operator)
-    Pipe,   // | (single bar — closure delimiter)
+    Pipe,   // | (single bar — closure delimiter, or bitwise or)
+    Pound,  // # (opens an attribute `#[...]`)
+    LBracket, // [
+    RBracket, // ]
 
     /// End of input (always the final token).
     Eof,
@@ -82,223 +96,309 @@ pub enum Tok {
 pub struct SpannedTok {
     pub tok: Tok,
     pub line: u32,
+    /// The byte offset the token starts at. Incremental reparsing uses it to find
+    /// the text of each item.
+    pub offset: usize,
+    /// The next token follows with no whitespace in between. Only tracked for
+    /// single-character punctuation: the shift operators `<<`/`>>` are lexed as two
+    /// joint `Lt`/`Gt` tokens, so that `>>` can still close two generic argument
+    /// lists (`Vec<Vec<i64>>`) and the parser glues the pair back together only
+    /// in operator position.
+    pub joint: bool,
 }
 
-/// Tokenize `src` into a vector of spanned tokens ending in `Tok::Eof`.
-///
-/// Returns `Err` with a line-tagged message on an unexpected character.
-pub fn lex(src: &str) -> Result<Vec<SpannedTok>, String> {
-    let bytes = src.as_bytes();
-    let mut i = 0usize;
-    let mut line = 1u32;
-    let mut out = Vec::new();
+/// What a [`Token`] of [`tokenize`]'s lossless stream is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    Whitespace,
+    /// A `//` comment, up to but not including its newline.
+    Comment,
+    Ident,
+    Keyword,
+    /// A loop label, quote included.
+    Label,
+    /// An integer literal with its radix prefix and `_` separators, and any
+    /// letters or digits run into it (which [`lex`] rejects).
+    Int,
+    Float,
+    /// A string literal, quotes and escapes included.
+    Str,
+    /// An operator or delimiter; its text says which.
+    Punct,
+    /// An unterminated string literal, or a character that starts no token.
+    Error,
+}
 
-    // Helper to push a token at the current line.
-    macro_rules! push {
-        ($t:expr) => {
-            out.push(SpannedTok { tok: $t, line })
-        };
-    }
+/// A token of [`tokenize`]'s stream: its kind and the bytes of the source it covers.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub range: Range<usize>,
+}
 
-    while i < bytes.len() {
-        let c = bytes[i] as char;
+/// Split `src` into tokens that cover it exactly: whitespace, comments and
+/// malformed text included, so the tokens' texts concatenate back to `src`.
+/// Never fails; what [`lex`] would reject is an [`TokenKind::Error`] token or an
+/// [`TokenKind::Int`] it cannot read.
+pub fn tokenize(src: &str) -> Vec<Token> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < src.len() {
+        let (kind, len) = scan(&src[i..]);
+        out.push(Token { kind, range: i..i + len });
+        i += len;
+    }
+    out
+}
 
-        // Whitespace (track newlines for diagnostics).
-        if c == '\n' {
-            line += 1;
-            i += 1;
-            continue;
-        }
-        if c.is_whitespace() {
-            i += 1;
-            continue;
-        }
+/// The kind and byte length of the token `rest` starts with.
+fn scan(rest: &str) -> (TokenKind, usize) {
+    let bytes = rest.as_bytes();
+    let run = |from: usize, f: &dyn Fn(u8) -> bool| {
+        from + bytes[from..].iter().take_while(|&&b| f(b)).count()
+    };
+    let c = rest.chars().next().expect("`rest` is not empty");
+    if c.is_whitespace() {
+        let len = rest.find(|c: char| !c.is_whitespace()).unwrap_or(rest.len());
+        return (TokenKind::Whitespace, len);
+    }
+    if rest.starts_with("//") {
+        return (TokenKind::Comment, rest.find('\n').unwrap_or(rest.len()));
+    }
+    // Two-character operators first, so `->` is not `-` then `>`.
+    if rest.get(..2).and_then(punct).is_some() {
+        return (TokenKind::Punct, 2);
+    }
+    if punct(&rest[..c.len_utf8()]).is_some() {
+        return (TokenKind::Punct, 1);
+    }
 
-        // Line comments: `// ... <newline>`.
-        if c == '/' && i + 1 < bytes.len() && bytes[i + 1] as char == '/' {
-            while i < bytes.len() && bytes[i] as char != '\n' {
-                i += 1;
+    // String literals: `"..."`, where a backslash escapes the character after it.
+    if c == '"' {
+        let mut j = 1;
+        while j < bytes.len() {
+            match bytes[j] {
+                b'"' => return (TokenKind::Str, j + 1),
+                b'\\' => j += 2,
+                _ => j += 1,
             }
-            continue;
         }
+        return (TokenKind::Error, rest.len());
+    }
 
-        // Multi-character operators / punctuation. Check two-char forms first.
-        let two = if i + 1 < bytes.len() {
-            Some((c, bytes[i + 1] as char))
-        } else {
-            None
+    // Numeric literals: integer, or float when a `.` is followed by a digit (so `1.5` is a
+    // float but `t.0` / `1..5` keep the `.` as its own token). Digits may be separated
+    // by `_` (`1_000_000`), an integer may carry a `0x` / `0o` / `0b` radix prefix, and
+    // either may end in a type suffix (`5u8`, `2.0f64`).
+    if c.is_ascii_digit() {
+        let radix = match (c, bytes.get(1)) {
+            ('0', Some(b'x')) => 16,
+            ('0', Some(b'o')) => 8,
+            ('0', Some(b'b')) => 2,
+            _ => 10,
         };
-        match two {
-            Some(('-', '>')) => {
-                push!(Tok::Arrow);
-                i += 2;
-                continue;
-            }
-            Some(('|', '|')) => {
-                push!(Tok::OrOr);
-                i += 2;
-                continue;
-            }
-            Some(('&', '&')) => {
-                push!(Tok::AndAnd);
-                i += 2;
-                continue;
-            }
-            Some(('=', '=')) => {
-                push!(Tok::EqEq);
-                i += 2;
-                continue;
-            }
-            Some(('=', '>')) => {
-                push!(Tok::FatArrow);
-                i += 2;
-                continue;
-            }
-            Some((':', ':')) => {
-                push!(Tok::ColonColon);
-                i += 2;
-                continue;
-            }
-            Some(('!', '=')) => {
-                push!(Tok::NotEq);
-                i += 2;
-                continue;
-            }
-            Some(('<', '=')) => {
-                push!(Tok::Le);
-                i += 2;
-                continue;
-            }
-            Some(('>', '=')) => {
-                push!(Tok::Ge);
-                i += 2;
-                continue;
-            }
-            _ => {}
+        let digit = |b: u8| (b as char).is_digit(radix) || b == b'_';
+        let i = run(if radix == 10 { 0 } else { 2 }, &digit);
+        let is_float = radix == 10
+            && bytes.get(i) == Some(&b'.')
+            && bytes.get(i + 1).is_some_and(u8::is_ascii_digit);
+        if is_float {
+            let end = run(i + 1, &digit);
+            return (TokenKind::Float, run(end, &|b| b.is_ascii_alphanumeric()));
         }
+        // A digit of a larger radix (`0b12`, `0o9`) stays in the literal, to be
+        // reported as one.
+        return (TokenKind::Int, run(i, &|b| b.is_ascii_alphanumeric()));
+    }
 
-        // Single-character tokens.
-        let single = match c {
-            '(' => Some(Tok::LParen),
-            ')' => Some(Tok::RParen),
-            '{' => Some(Tok::LBrace),
-            '}' => Some(Tok::RBrace),
-            ',' => Some(Tok::Comma),
-            ':' => Some(Tok::Colon),
-            ';' => Some(Tok::Semi),
-            '.' => Some(Tok::Dot),
-            '=' => Some(Tok::Eq),
-            '<' => Some(Tok::Lt),
-            '>' => Some(Tok::Gt),
-            '+' => Some(Tok::Plus),
-            '-' => Some(Tok::Minus),
-            '*' => Some(Tok::Star),
-            '/' => Some(Tok::Slash),
-            '%' => Some(Tok::Percent),
-            '!' => Some(Tok::Bang),
-            '&' => Some(Tok::Amp),
-            '?' => Some(Tok::Question),
-            '|' => Some(Tok::Pipe),
-            _ => None,
-        };
-        if let Some(t) = single {
-            push!(t);
-            i += 1;
-            continue;
-        }
+    let word = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+    // Loop labels: `'` immediately followed by an identifier (`'outer`).
+    if c == '\'' && bytes.get(1).is_some_and(|&b| b.is_ascii_alphabetic() || b == b'_') {
+        return (TokenKind::Label, run(1, &word));
+    }
+    // Identifiers / keywords: [A-Za-z_][A-Za-z0-9_]*
+    if c.is_ascii_alphabetic() || c == '_' {
+        let len = run(0, &word);
+        let keyword = keyword(&rest[..len]).is_some();
+        return (if keyword { TokenKind::Keyword } else { TokenKind::Ident }, len);
+    }
+    (TokenKind::Error, c.len_utf8())
+}
 
-        // String literals: `"..."` with `\n`, `\t`, `\"`, `\\` escapes.
-        if c == '"' {
-            i += 1; // opening quote
-            let mut s = String::new();
-            loop {
-                if i >= bytes.len() {
-                    return Err(format!("line {line}: unterminated string literal"));
-                }
-                let d = bytes[i] as char;
-                if d == '"' {
-                    i += 1; // closing quote
-                    break;
-                }
-                if d == '\\' && i + 1 < bytes.len() {
-                    let e = bytes[i + 1] as char;
-                    s.push(match e {
-                        'n' => '\n',
-                        't' => '\t',
-                        '"' => '"',
-                        '\\' => '\\',
-                        other => other,
-                    });
-                    i += 2;
-                    continue;
-                }
-                if d == '\n' {
-                    line += 1;
-                }
-                s.push(d);
-                i += 1;
-            }
-            push!(Tok::Str(s));
-            continue;
-        }
+/// Tokenize `src` into a vector of spanned tokens ending in `Tok::Eof`.
+///
+/// Returns `Err` with a line-tagged message on an unexpected character.
+pub fn lex(src: &str) -> Result<Vec<SpannedTok>, String> {
+    lex_at(src, 1)
+}
 
-        // Numeric literals: integer, or float when a `.` is followed by a digit (so `1.5` is a
-        // float but `t.0` / `1..5` keep the `.` as its own token).
-        if c.is_ascii_digit() {
-            let start = i;
-            while i < bytes.len() && (bytes[i] as char).is_ascii_digit() {
-                i += 1;
+/// [`lex`] a piece of a file that starts on line `first_line`.
+pub fn lex_at(src: &str, first_line: u32) -> Result<Vec<SpannedTok>, String> {
+    let mut line = first_line;
+    let mut out = Vec::new();
+    for Token { kind, range } in tokenize(src) {
+        let (text, start) = (&src[range.clone()], line);
+        // Whitespace, comments and strings can span lines.
+        line += text.bytes().filter(|&b| b == b'\n').count() as u32;
+        let tok = match kind {
+            TokenKind::Whitespace | TokenKind::Comment => continue,
+            TokenKind::Error if text.starts_with('"') => {
+                return Err(format!("line {line}: unterminated string literal"));
             }
-            let is_float = i + 1 < bytes.len()
-                && bytes[i] as char == '.'
-                && (bytes[i + 1] as char).is_ascii_digit();
-            if is_float {
-                i += 1; // consume '.'
-                while i < bytes.len() && (bytes[i] as char).is_ascii_digit() {
-                    i += 1;
+            TokenKind::Error => return Err(format!("line {line}: unexpected character `{text}`")),
+            TokenKind::Punct => {
+                let tok = punct(text).expect("`tokenize` only yields known punctuation");
+                // Only single-character punctuation tracks whether it is joint.
+                let next = src.as_bytes().get(range.end);
+                let joint = text.len() == 1 && next.is_some_and(|b| !b.is_ascii_whitespace());
+                out.push(SpannedTok { tok, line: start, offset: range.start, joint });
+                continue;
+            }
+            TokenKind::Ident => Tok::Ident(text.to_string()),
+            TokenKind::Keyword => keyword(text).expect("`tokenize` only yields known keywords"),
+            TokenKind::Label => Tok::Label(text[1..].to_string()),
+            TokenKind::Str => Tok::Str(unescape(&text[1..text.len() - 1])),
+            TokenKind::Float => {
+                let (body, suffix) = split_suffix(text, 10);
+                if let Some(suffix) = suffix.filter(|&s| s != "f64") {
+                    return Err(bad_suffix(suffix, text, line));
                 }
-                let text = &src[start..i];
-                let value: f64 = text
+                let value: f64 = body
+                    .replace('_', "")
                     .parse()
                     .map_err(|_| format!("line {line}: float literal `{text}` out of range"))?;
-                push!(Tok::Float(value));
-                continue;
+                Tok::Float(value)
             }
-            let text = &src[start..i];
-            // Parse as `u128` first to admit the full unsigned 128-bit magnitude
-            // (`0..=u128::MAX`), then reinterpret the bit pattern as `i128`. This
-            // keeps literals in `i128`'s natural range numerically unchanged while
-            // still allowing `u128` literals above `i128::MAX` to round-trip (as a
-            // negative `i128` bit pattern; see `Tok::Int`'s doc comment).
-            let value: u128 = text
-                .parse()
-                .map_err(|_| format!("line {line}: integer literal `{text}` out of range"))?;
-            push!(Tok::Int(value as i128));
-            continue;
-        }
-
-        // Identifiers / keywords: [A-Za-z_][A-Za-z0-9_]*
-        if c.is_ascii_alphabetic() || c == '_' {
-            let start = i;
-            while i < bytes.len() {
-                let d = bytes[i] as char;
-                if d.is_ascii_alphanumeric() || d == '_' {
-                    i += 1;
-                } else {
-                    break;
+            TokenKind::Int => {
+                let (body, suffix) = split_suffix(text, radix_of(text));
+                let value = int_literal(body, text, line)?;
+                match suffix.map(|s| (s, crate::ast::Ty::primitive(s))) {
+                    None => Tok::Int(value),
+                    Some(("f64", _)) => Tok::Float(value as f64),
+                    Some((_, Some(ty @ (crate::ast::Ty::I64 | crate::ast::Ty::IntN(_))))) => {
+                        Tok::SuffixedInt(value, ty)
+                    }
+                    Some((suffix, _)) => return Err(bad_suffix(suffix, text, line)),
                 }
             }
-            let word = &src[start..i];
-            let tok = keyword(word).unwrap_or_else(|| Tok::Ident(word.to_string()));
-            push!(tok);
-            continue;
-        }
+        };
+        out.push(SpannedTok { tok, line: start, offset: range.start, joint: false });
+    }
+    out.push(SpannedTok { tok: Tok::Eof, line, offset: src.len(), joint: false });
+    Ok(out)
+}
 
-        return Err(format!("line {line}: unexpected character `{c}`"));
+/// The radix an integer literal's prefix selects.
+fn radix_of(text: &str) -> u32 {
+    match text.get(..2) {
+        Some("0x") => 16,
+        Some("0o") => 8,
+        Some("0b") => 2,
+        _ => 10,
     }
+}
 
-    out.push(SpannedTok { tok: Tok::Eof, line });
-    Ok(out)
+/// A numeric literal's digits and its type suffix, if any: the suffix starts at
+/// the first letter after the radix prefix that is not a digit in `radix`.
+fn split_suffix(text: &str, radix: u32) -> (&str, Option<&str>) {
+    let starts = |c: char| c.is_ascii_alphabetic() && !c.is_digit(radix);
+    let prefix = if radix == 10 { 0 } else { 2 };
+    match text[prefix..].find(starts) {
+        Some(at) => (&text[..prefix + at], Some(&text[prefix + at..])),
+        None => (text, None),
+    }
+}
+
+/// The error for a literal `text`, on `line`, whose suffix names no numeric type
+/// it can have.
+fn bad_suffix(suffix: &str, text: &str, line: u32) -> String {
+    let help = match suffix {
+        "f32" => ": the only float type is `f64`",
+        _ if text.contains('.') => ": a float literal's only suffix is `f64`",
+        _ => "",
+    };
+    format!("line {line}: invalid suffix `{suffix}` on literal `{text}`{help}")
+}
+
+/// The value of the unsuffixed integer literal `body` of `text`, on `line`.
+fn int_literal(body: &str, text: &str, line: u32) -> Result<i128, String> {
+    let radix = radix_of(body);
+    let digits = if radix == 10 { body } else { &body[2..] };
+    if let Some(bad) = digits.chars().find(|&d| !d.is_digit(radix) && d != '_') {
+        return Err(format!("line {line}: invalid digit `{bad}` in base-{radix} literal `{text}`"));
+    }
+    let body = digits.replace('_', "");
+    if body.is_empty() {
+        return Err(format!("line {line}: integer literal `{text}` has no digits"));
+    }
+    // Parse as `u128` first to admit the full unsigned 128-bit magnitude
+    // (`0..=u128::MAX`), then reinterpret the bit pattern as `i128`. This
+    // keeps literals in `i128`'s natural range numerically unchanged while
+    // still allowing `u128` literals above `i128::MAX` to round-trip (as a
+    // negative `i128` bit pattern; see `Tok::Int`'s doc comment). Whether the
+    // value fits the literal's eventual type is checked during inference.
+    let value = u128::from_str_radix(&body, radix)
+        .map_err(|_| format!("line {line}: integer literal `{text}` does not fit in 128 bits"))?;
+    Ok(value as i128)
+}
+
+/// The text of a string literal between its quotes, with `\n`, `\t`, `\"` and
+/// `\\` escapes replaced; a backslash before any other character is dropped.
+fn unescape(body: &str) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        out.push(match c {
+            '\\' => match chars.next() {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some(other) => other,
+                None => break,
+            },
+            c => c,
+        });
+    }
+    out
+}
+
+/// The operator or delimiter token `text` spells, if any.
+fn punct(text: &str) -> Option<Tok> {
+    Some(match text {
+        "->" => Tok::Arrow,
+        "||" => Tok::OrOr,
+        "&&" => Tok::AndAnd,
+        "==" => Tok::EqEq,
+        "=>" => Tok::FatArrow,
+        "::" => Tok::ColonColon,
+        "!=" => Tok::NotEq,
+        "<=" => Tok::Le,
+        ">=" => Tok::Ge,
+        "(" => Tok::LParen,
+        ")" => Tok::RParen,
+        "{" => Tok::LBrace,
+        "}" => Tok::RBrace,
+        "," => Tok::Comma,
+        ":" => Tok::Colon,
+        ";" => Tok::Semi,
+        "." => Tok::Dot,
+        "=" => Tok::Eq,
+        "<" => Tok::Lt,
+        ">" => Tok::Gt,
+        "+" => Tok::Plus,
+        "-" => Tok::Minus,
+        "*" => Tok::Star,
+        "/" => Tok::Slash,
+        "%" => Tok::Percent,
+        "!" => Tok::Bang,
+        "&" => Tok::Amp,
+        "?" => Tok::Question,
+        "|" => Tok::Pipe,
+        "^" => Tok::Caret,
+        "#" => Tok::Pound,
+        "[" => Tok::LBracket,
+        "]" => Tok::RBracket,
+        _ => return None,
+    })
 }
 
 /// Map a word to its keyword token, or `None` if it is an ordinary identifier.
@@ -349,6 +449,119 @@ mod tests {
         assert_eq!(toks[0].line, 3);
     }
 
+    #[test]
+    fn lexes_loop_labels() {
+        let toks = lex("'outer: loop").unwrap();
+        assert_eq!(toks[0].tok, Tok::Label("outer".to_string()));
+        assert_eq!(toks[1].tok, Tok::Colon);
+        assert!(lex("' x").is_err());
+    }
+
+    #[test]
+    fn lexes_separators_and_radix_prefixes() {
+        let ints = |src: &str| -> Vec<i128> {
+            let lexed = lex(src).unwrap();
+            let toks = lexed.iter().map(|t| &t.tok);
+            toks.filter_map(|t| if let Tok::Int(n) = t { Some(*n) } else { None }).collect()
+        };
+        assert_eq!(ints("1_000_000 0xFF 0x_dead_BEEF 0o77 0b1010 7_"), [
+            1_000_000, 0xFF, 0xdead_beef, 0o77, 0b1010, 7
+        ]);
+        assert_eq!(lex("1_000.5").unwrap()[0].tok, Tok::Float(1000.5));
+        // The full `u128` range lexes; its range against the literal's type is
+        // checked during inference.
+        let max = format!("{}", u128::MAX);
+        assert_eq!(ints(&max), [u128::MAX as i128]);
+        assert_eq!(ints("0xffffffffffffffffffffffffffffffff"), [u128::MAX as i128]);
+
+        let err = |src: &str| lex(src).unwrap_err();
+        assert_eq!(err("0b102"), "line 1: invalid digit `2` in base-2 literal `0b102`");
+        assert_eq!(err("0o8"), "line 1: invalid digit `8` in base-8 literal `0o8`");
+        assert_eq!(err("0x"), "line 1: integer literal `0x` has no digits");
+        let too_wide = err("0x1_0000_0000_0000_0000_0000_0000_0000_0000");
+        assert!(too_wide.contains("does not fit in 128 bits"), "{too_wide}");
+    }
+
+    #[test]
+    fn lexes_literal_suffixes() {
+        use crate::ast::Ty;
+        let toks = |src: &str| -> Vec<Tok> {
+            let lexed = lex(src).unwrap();
+            lexed.into_iter().map(|t| t.tok).filter(|t| *t != Tok::Eof).collect()
+        };
+        let u8 = Ty::primitive("u8").unwrap();
+        assert_eq!(toks("200u8 0xffu8 1_000i64 2f64 2.5f64"), [
+            Tok::SuffixedInt(200, u8.clone()),
+            Tok::SuffixedInt(0xff, u8),
+            Tok::SuffixedInt(1000, Ty::I64),
+            Tok::Float(2.0),
+            Tok::Float(2.5),
+        ]);
+
+        let err = |src: &str| lex(src).unwrap_err();
+        assert_eq!(err("5x"), "line 1: invalid suffix `x` on literal `5x`");
+        assert_eq!(err("0b1u7"), "line 1: invalid suffix `u7` on literal `0b1u7`");
+        assert_eq!(
+            err("2.0f32"),
+            "line 1: invalid suffix `f32` on literal `2.0f32`: the only float type is `f64`"
+        );
+        assert_eq!(
+            err("1.5u8"),
+            "line 1: invalid suffix `u8` on literal `1.5u8`: a float literal's only suffix is `f64`"
+        );
+        assert_eq!(err("0xu8"), "line 1: integer literal `0xu8` has no digits");
+    }
+
+    fn kinds(src: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(src).into_iter().map(|t| (t.kind, &src[t.range])).collect()
+    }
+
+    #[test]
+    fn tokenize_classifies_tricky_text() {
+        use TokenKind::*;
+        assert_eq!(kinds(r#""a\"b" "\\" x"#), [
+            (Str, r#""a\"b""#), (Whitespace, " "), (Str, r#""\\""#), (Whitespace, " "), (Ident, "x")
+        ]);
+        assert_eq!(kinds("1..5 t.0 1.5"), [
+            (Int, "1"), (Punct, "."), (Punct, "."), (Int, "5"), (Whitespace, " "),
+            (Ident, "t"), (Punct, "."), (Int, "0"), (Whitespace, " "), (Float, "1.5")
+        ]);
+        assert_eq!(kinds("a->b=>c// note\n'l: 0b12"), [
+            (Ident, "a"), (Punct, "->"), (Ident, "b"), (Punct, "=>"), (Ident, "c"),
+            (Comment, "// note"), (Whitespace, "\n"), (Label, "'l"), (Punct, ":"),
+            (Whitespace, " "), (Int, "0b12")
+        ]);
+        assert_eq!(kinds("fn é \"open"), [
+            (Keyword, "fn"), (Whitespace, " "), (Error, "é"), (Whitespace, " "), (Error, "\"open")
+        ]);
+        // A token is on the line it starts on.
+        let lines: Vec<u32> = lex("\"a\nb\" x").unwrap().iter().map(|t| t.line).collect();
+        assert_eq!(lines, [1, 2, 2]);
+    }
+
+    /// Every example in the repository tokenizes back to its own text.
+    #[test]
+    fn tokenize_is_lossless_over_the_examples() {
+        let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+        let mut checked = 0;
+        for dir in ["examples", "examples/proofs", "crates/rvc/tests/fixtures"] {
+            for entry in std::fs::read_dir(root.join(dir)).unwrap() {
+                let path = entry.unwrap().path();
+                if path.extension().is_none_or(|e| e != "rv") {
+                    continue;
+                }
+                let bytes = std::fs::read(&path).unwrap();
+                let src = String::from_utf8_lossy(&bytes);
+                let toks = tokenize(&src);
+                let text: String = toks.iter().map(|t| &src[t.range.clone()]).collect();
+                assert_eq!(text, src, "{}", path.display());
+                assert!(toks.windows(2).all(|w| w[0].range.end == w[1].range.start));
+                checked += 1;
+            }
+        }
+        assert!(checked >= 10, "only {checked} files tokenized");
+    }
+
     #[test]
     fn rejects_bad_char() {
         assert!(lex("fn f() { @ }").is_err());
@@ -2,25 +2,79 @@
 //!
 //! The single public entry point is [`parse`], which turns source text into an
 //! [`ast::Module`]. Names are interned into the caller-supplied
-//! [`rv_core::Symbols`] so the same symbol table threads through lowering.
+//! [`rv_core::Symbols`] so the same symbol table threads through lowering. An
+//! editor that keeps a [`ParsedFile`] can instead [`reparse`] it after each edit,
+//! which reparses only the items around the edit. A file's bytes become source
+//! text through [`source::decode`]. [`lexer::tokenize`] gives the raw, lossless
+//! token stream, for tools that want tokens without a parse.
+//!
+//! Parsing runs in a `parse` trace span; items left out by `#[cfg]` are reported
+//! as debug events under the `rv_syntax` target.
 
 pub mod ast;
+pub mod cfg;
 pub mod fragment;
-mod lexer;
+pub mod incremental;
+pub mod lexer;
+pub mod lint;
 mod parser;
+pub mod source;
+pub mod spans;
 
+pub use cfg::CfgSet;
 pub use fragment::{classify, Fragment};
+pub use incremental::{reparse, ParsedFile, TextEdit};
 use parser::Parser;
 use rv_core::Symbols;
 
-/// Parse `src` into a [`ast::Module`].
+/// Parse `src` into a [`ast::Module`], with no cfg options set.
 ///
 /// Identifiers are interned into `syms`. On any lexing or parsing error, returns
 /// `Err` with a message that includes the offending source line.
 pub fn parse(src: &str, syms: &mut Symbols) -> Result<ast::Module, String> {
+    parse_with_cfg(src, syms, &CfgSet::new())
+}
+
+/// [`parse`] under the cfg options `cfg`: items whose `#[cfg(...)]` does not
+/// hold are left out of the module.
+pub fn parse_with_cfg(src: &str, syms: &mut Symbols, cfg: &CfgSet) -> Result<ast::Module, String> {
+    parse_module(src, syms, DEFAULT_NESTING_LIMIT, cfg)
+}
+
+/// How deeply expressions and blocks may nest by default. The links of an
+/// operator chain (`1 + 2 + 3`) are not nesting, but the tree they build may be
+/// at most twice this deep. Generous for real code; the passes that recurse over
+/// the tree grow their stack as they go (see [`rv_arena::stack`]).
+pub const DEFAULT_NESTING_LIMIT: usize = 2048;
+
+/// [`parse`] with a custom nesting limit. Source nested deeper than `limit` is
+/// rejected with an "expression nesting too deep" error on the line it crosses the
+/// limit, rather than overflowing the stack in a later pass.
+pub fn parse_with_nesting_limit(
+    src: &str,
+    syms: &mut Symbols,
+    limit: usize,
+) -> Result<ast::Module, String> {
+    parse_module(src, syms, limit, &CfgSet::new())
+}
+
+fn parse_module(
+    src: &str,
+    syms: &mut Symbols,
+    limit: usize,
+    cfg: &CfgSet,
+) -> Result<ast::Module, String> {
+    let _span = tracing::debug_span!("parse", bytes = src.len()).entered();
     let toks = lexer::lex(src)?;
-    let mut p = Parser::new(&toks, syms);
-    p.parse_module()
+    tracing::trace!(tokens = toks.len(), "lexed");
+    let mut p = Parser::new(&toks, syms, limit);
+    let module = p.parse_module(cfg)?;
+    // Debug builds double-check every span the parser recorded.
+    #[cfg(debug_assertions)]
+    if let Err(e) = spans::check(&module, src) {
+        panic!("internal error: inconsistent spans: {e}");
+    }
+    Ok(module)
 }
 
 #[cfg(test)]
@@ -94,6 +148,67 @@ mod tests {
         assert_eq!(classify(&module), vec![Fragment::Exec]);
     }
 
+    #[test]
+    fn parses_variant_imports_and_bare_patterns() {
+        let mut syms = Symbols::new();
+        let src = "enum E { A(i64), B }\nuse E::A;\nuse E::*;\n\
+                   fn f(e: E) -> i64 { match e { A(x) => { return x; } B => { return 0; } } }";
+        let m = parse(src, &mut syms).unwrap();
+        let uses: Vec<(&str, Option<&str>, u32)> = m.items[1..3]
+            .iter()
+            .map(|item| {
+                let Item::Use(u) = item else { panic!("expected a use item") };
+                (syms.resolve(u.enum_name), u.variant.map(|v| syms.resolve(v)), u.line)
+            })
+            .collect();
+        assert_eq!(uses, [("E", Some("A"), 2), ("E", None, 3)]);
+        let Item::Fn(f) = &m.items[3] else { panic!("expected a function item") };
+        let Stmt::Match { arms, .. } = &f.body.stmts[0] else { panic!("expected a match") };
+        let Pattern::Bare { variant, binds } = &arms[0].pat else { panic!("expected `A(x)`") };
+        assert_eq!((syms.resolve(*variant), binds.len()), ("A", 1));
+        assert!(matches!(&arms[1].pat, Pattern::Bare { binds, .. } if binds.is_empty()));
+        assert!(parse("use E;", &mut syms).unwrap_err().contains("after the enum name in a `use`"));
+    }
+
+    #[test]
+    fn parses_visibility_where_items_take_one() {
+        let mut syms = Symbols::new();
+        let src = "pub struct P { pub x: i64, y: i64 }\npub enum E { A }\npub trait T {}\n\
+                   impl P { pub fn get(self) -> i64 { return self.x; } fn hidden() {} }\n\
+                   pub use E::A;\npub(crate) fn f() {}\nfn g() {}";
+        let m = parse(src, &mut syms).unwrap();
+        let Item::Struct(p) = &m.items[0] else { panic!("expected a struct") };
+        let fields: Vec<Visibility> = p.fields.iter().map(|f| f.vis).collect();
+        assert_eq!(p.vis, Visibility::Public);
+        assert_eq!(fields, [Visibility::Public, Visibility::Private]);
+        assert!(matches!(&m.items[1], Item::Enum(e) if e.vis == Visibility::Public));
+        assert!(matches!(&m.items[2], Item::Trait(t) if t.vis == Visibility::Public));
+        let Item::Impl(i) = &m.items[3] else { panic!("expected an impl") };
+        let methods: Vec<Visibility> = i.methods.iter().map(|m| m.vis).collect();
+        assert_eq!(methods, [Visibility::Public, Visibility::Private]);
+        assert!(matches!(&m.items[4], Item::Use(u) if u.vis == Visibility::Public));
+        assert!(matches!(&m.items[5], Item::Fn(f) if f.vis == Visibility::Crate { line: 6 }));
+        assert!(matches!(&m.items[6], Item::Fn(f) if f.vis == Visibility::Private));
+        let lints = lint::check(&m, &syms);
+        let got: Vec<(&str, u32)> = lints.iter().map(|l| (l.rule, l.line)).collect();
+        assert_eq!(got, [("unsupported-visibility", 6)]);
+        assert!(lints[0].message.contains("so `f` is private"));
+        // A field may still be named `pub`.
+        assert!(parse("struct S { pub: i64 }", &mut syms).is_ok());
+    }
+
+    #[test]
+    fn rejects_visibility_where_it_has_no_meaning() {
+        let mut syms = Symbols::new();
+        let err = parse("enum E {\n    pub A,\n}", &mut syms).unwrap_err();
+        assert!(err.starts_with("line 2: an enum variant cannot be `pub`"), "{err}");
+        assert!(err.ends_with("it is as visible as its enum `E`"), "{err}");
+        let err = parse("struct S {}\npub impl S {}", &mut syms).unwrap_err();
+        assert!(err.starts_with("line 2: `pub` is not allowed before Impl"), "{err}");
+        let err = parse("pub(super) fn f() {}", &mut syms).unwrap_err();
+        assert!(err.contains("only `pub(crate)` is reserved"), "{err}");
+    }
+
     #[test]
     fn respects_precedence() {
         let mut syms = Symbols::new();
@@ -106,6 +221,50 @@ mod tests {
         assert!(matches!(**rhs, Expr::Bin(BinOp::Mul, _, _)));
     }
 
+    #[test]
+    fn bitwise_operators_follow_rust_precedence() {
+        fn shape(e: &Expr, syms: &Symbols) -> String {
+            match e {
+                Expr::Bin(op, a, b) => format!("({} {op:?} {})", shape(a, syms), shape(b, syms)),
+                Expr::Un(op, a) => format!("({op:?} {})", shape(a, syms)),
+                Expr::Int(n) => n.to_string(),
+                Expr::Var(v) => syms.resolve(*v).to_string(),
+                other => panic!("unexpected {other:?}"),
+            }
+        }
+        let ret = |body: &str| {
+            let mut syms = Symbols::new();
+            let src = format!("fn f(a: i64, b: i64, c: i64) -> i64 {{ return {body}; }}");
+            let m = parse(&src, &mut syms).unwrap();
+            let Item::Fn(f) = &m.items[0] else { panic!("expected a function item") };
+            let Stmt::Return(Some(e)) = &f.body.stmts[0] else { panic!("expected a return") };
+            shape(e, &syms)
+        };
+        assert_eq!(
+            ret("a | b ^ c & a << 1 + 2 == b"),
+            "((a BitOr (b BitXor (c BitAnd (a Shl (1 Add 2))))) Eq b)"
+        );
+        assert_eq!(ret("a >> 2 > b << 1"), "((a Shr 2) Gt (b Shl 1))");
+        // Only a joint `<<`/`>>` is a shift.
+        assert_eq!(ret("a >> -1"), "(a Shr (Neg 1))");
+        assert!(parse("fn f(a: i64) -> bool { return a > > 1; }", &mut Symbols::new()).is_err());
+    }
+
+    #[test]
+    fn shift_tokens_still_close_generics_and_separate_arms() {
+        let mut syms = Symbols::new();
+        let src = "enum Opt<T> { Some(T), None() }
+            fn f(n: Opt<Opt<i64>>) -> i64 { let m: Opt<Opt<i64>> = n; return 0; }
+            fn g(n: Nat, x: Nat) -> Nat { match n { | Nat::Zero => (x | x) | Nat::Succ(k) => k } }";
+        let m = parse(src, &mut syms).unwrap();
+        let Item::Fn(g) = &m.items[2] else { panic!("expected a function item") };
+        let Stmt::Return(Some(Expr::MatchExpr { arms, .. })) = &g.body.stmts[0] else {
+            panic!("expected a match expression body, got {:?}", g.body.stmts[0]);
+        };
+        assert_eq!(arms.len(), 2);
+        assert!(matches!(arms[0].1, Expr::Bin(BinOp::BitOr, _, _)));
+    }
+
     #[test]
     fn parses_control_flow() {
         let mut syms = Symbols::new();
@@ -120,6 +279,63 @@ mod tests {
         assert!(matches!(f.body.stmts[1], Stmt::If { .. }));
     }
 
+    #[test]
+    fn return_parses_in_expression_position() {
+        let mut syms = Symbols::new();
+        let src = "fn f(c: bool) -> i64 {
+            let x = g(return 1 + 2, 3);
+            h(return);
+            if c { return 4 }
+            return 5;
+        }";
+        let m = parse(src, &mut syms).unwrap();
+        let Item::Fn(f) = &m.items[0] else { panic!("expected a function item") };
+        let Stmt::Let { init: Some(Expr::Call { args, .. }), .. } = &f.body.stmts[0] else {
+            panic!("expected a call initializer, got {:?}", f.body.stmts[0]);
+        };
+        let Expr::Return(Some(value)) = &args[0] else { panic!("expected a return value") };
+        assert!(matches!(**value, Expr::Bin(BinOp::Add, ..)));
+        assert_eq!(args[1], Expr::Int(3));
+        let Stmt::Expr(Expr::Call { args, .. }) = &f.body.stmts[1] else {
+            panic!("expected a call statement");
+        };
+        assert_eq!(args[..], [Expr::Return(None)]);
+        // A `return` statement may drop its `;` before the closing brace.
+        let Stmt::If { then_blk, .. } = &f.body.stmts[2] else { panic!("expected an if") };
+        assert_eq!(then_blk.stmts[..], [Stmt::Return(Some(Expr::Int(4)))]);
+    }
+
+    #[test]
+    fn if_parses_in_expression_position() {
+        let mut syms = Symbols::new();
+        let src = "fn f(c: bool) -> i64 {
+            let x = if c { return 0 } else { let y = 5; y };
+            let z = if c { 1 } else if x == 2 { 2 } else { 3 };
+            return z;
+        }";
+        let m = parse(src, &mut syms).unwrap();
+        let Item::Fn(f) = &m.items[0] else { panic!("expected a function item") };
+        let Stmt::Let { init: Some(Expr::If { then_blk, then_val, else_blk, else_val, .. }), .. } =
+            &f.body.stmts[0]
+        else {
+            panic!("expected an if initializer, got {:?}", f.body.stmts[0]);
+        };
+        // An explicit `return` stays a statement; a bare tail is the value.
+        assert_eq!(then_blk.stmts[..], [Stmt::Return(Some(Expr::Int(0)))]);
+        assert_eq!(*then_val, None);
+        assert_eq!(else_blk.stmts.len(), 1);
+        assert!(matches!(else_val.as_deref(), Some(Expr::Var(_))));
+        let Stmt::Let { init: Some(Expr::If { else_blk, else_val, .. }), .. } = &f.body.stmts[1]
+        else {
+            panic!("expected an if initializer");
+        };
+        assert!(else_blk.stmts.is_empty());
+        assert!(matches!(else_val.as_deref(), Some(Expr::If { .. })));
+        // Unlike the statement, the expression needs its `else`.
+        let err = parse("fn g(c: bool) -> i64 { let x = if c { 1 }; return x; }", &mut syms);
+        assert!(err.is_err());
+    }
+
     #[test]
     fn assignment_vs_expr_disambiguation() {
         let mut syms = Symbols::new();
@@ -130,6 +346,28 @@ mod tests {
         assert!(matches!(f.body.stmts[2], Stmt::Expr(Expr::Call { .. })));
     }
 
+    #[test]
+    fn let_without_initializer() {
+        let mut syms = Symbols::new();
+        let m = parse("fn f() { let a; let b: bool; a = 1; }", &mut syms).unwrap();
+        let Item::Fn(f) = &m.items[0] else { panic!("expected a function item") };
+        assert!(matches!(f.body.stmts[0], Stmt::Let { ty: None, init: None, .. }));
+        assert!(matches!(f.body.stmts[1], Stmt::Let { ty: Some(Ty::Bool), init: None, .. }));
+        assert!(parse("fn f() { let a 1; }", &mut syms).is_err());
+    }
+
+    #[test]
+    fn let_mut_marks_the_binding() {
+        let mut syms = Symbols::new();
+        let m = parse("fn f() { let mut a = 1; let b = 2; let mut = 3; }", &mut syms).unwrap();
+        let Item::Fn(f) = &m.items[0] else { panic!("expected a function item") };
+        assert!(matches!(f.body.stmts[0], Stmt::Let { mutable: true, .. }));
+        assert!(matches!(f.body.stmts[1], Stmt::Let { mutable: false, .. }));
+        // `mut` is still usable as a binding's name.
+        let Stmt::Let { name, mutable: false, .. } = f.body.stmts[2] else { panic!() };
+        assert_eq!(syms.resolve(name), "mut");
+    }
+
     #[test]
     fn reports_line_on_error() {
         let mut syms = Symbols::new();
@@ -155,7 +393,7 @@ fn f() -> i64 {
 
         let Item::Fn(f) = &m.items[1] else { panic!("expected a function item") };
         // `let p = Point { x: 1, y: 2 };`
-        let Stmt::Let { init: Expr::StructLit { fields, .. }, .. } = &f.body.stmts[0] else {
+        let Stmt::Let { init: Some(Expr::StructLit { fields, .. }), .. } = &f.body.stmts[0] else {
             panic!("expected a struct-literal let");
         };
         assert_eq!(fields.len(), 2);
@@ -184,7 +422,7 @@ fn f() -> i64 {
         assert_eq!(e.variants[1].fields.len(), 1); // `Some(i64)`
 
         let Item::Fn(f) = &m.items[1] else { panic!("expected a function item") };
-        let Stmt::Let { init: Expr::EnumCtor { args, .. }, .. } = &f.body.stmts[0] else {
+        let Stmt::Let { init: Some(Expr::EnumCtor { args, .. }), .. } = &f.body.stmts[0] else {
             panic!("expected an enum-ctor let");
         };
         assert_eq!(args.len(), 1);
@@ -196,6 +434,288 @@ fn f() -> i64 {
         assert!(matches!(arms[1].pat, Pattern::Wildcard));
     }
 
+    #[test]
+    fn parses_string_and_or_patterns() {
+        let mut syms = Symbols::new();
+        let src = "\
+fn f(s: String) -> i64 {
+    match s {
+        \"a\" | \"b\" => { return 1; }
+        \"c\" => { return 2; }
+        _ => { return 3; }
+    }
+}";
+        let m = parse(src, &mut syms).unwrap();
+        let Item::Fn(f) = &m.items[0] else { panic!("expected a function item") };
+        let Stmt::Match { arms, .. } = &f.body.stmts[0] else {
+            panic!("expected a match statement");
+        };
+        let Pattern::Or(alts) = &arms[0].pat else { panic!("expected an or-pattern") };
+        assert_eq!(alts, &[Pattern::Str("a".into()), Pattern::Str("b".into())]);
+        assert_eq!(arms[1].pat, Pattern::Str("c".into()));
+        assert_eq!(arms[2].pat, Pattern::Wildcard);
+    }
+
+    #[test]
+    fn float_patterns_parse_and_trigger_the_lint() {
+        let mut syms = Symbols::new();
+        let src = "\
+fn f(x: f64) -> i64 {
+    let r = 0;
+    match x {
+        -1.5 | 0.5 => { r = 1; }
+        _ => { r = 2; }
+    }
+    return r;
+}";
+        let m = parse(src, &mut syms).unwrap();
+        let Item::Fn(f) = &m.items[0] else { panic!("expected a function item") };
+        let Stmt::Match { arms, .. } = &f.body.stmts[1] else {
+            panic!("expected a match statement");
+        };
+        assert_eq!(arms[0].pat, Pattern::Or(vec![Pattern::Float(-1.5), Pattern::Float(0.5)]));
+        let lints = lint::check(&m, &syms);
+        assert_eq!(lints.len(), 2, "{lints:?}");
+        assert!(lints.iter().all(|l| l.rule == "float-pattern-match" && l.line == 3));
+        assert!(parse("fn f(x: i64) { match x { -a => {} } }", &mut syms)
+            .unwrap_err()
+            .contains("expected a float literal after `-`"));
+    }
+
+    #[test]
+    fn float_equality_triggers_the_lint() {
+        let mut syms = Symbols::new();
+        let src = "\
+fn f(x: f64) -> bool {
+    let a = x == 0.5;
+    let b = -1.0 != x;
+    let c = x < 0.5;
+    return a;
+}";
+        let lints = lint::check(&parse(src, &mut syms).unwrap(), &syms);
+        let got: Vec<(&str, u32)> = lints.iter().map(|l| (l.rule, l.line)).collect();
+        assert_eq!(got, [("float-equality", 2), ("float-equality", 3)]);
+        assert!(lints[1].message.contains("`!=` compares a float for exact equality with `-1.0`"));
+    }
+
+    /// A `Write` handle onto a shared buffer, so a test can read back what the
+    /// subscriber printed.
+    #[derive(Clone, Default)]
+    struct Capture(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn configured_out_items_are_traced() {
+        let out = Capture::default();
+        let writer = out.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_writer(move || writer.clone())
+            .without_time()
+            .finish();
+        let mut syms = Symbols::new();
+        let src = "fn kept() {}\n#[cfg(test)]\nfn helper() {}";
+        let m = tracing::subscriber::with_default(subscriber, || parse(src, &mut syms)).unwrap();
+        assert_eq!(m.items.len(), 1);
+        let log = String::from_utf8(out.0.lock().unwrap().clone()).unwrap();
+        assert!(log.contains("rv_syntax::parser: item left out by `#[cfg]` line=2"), "{log}");
+        assert!(log.contains("parse{bytes=40}"), "{log}");
+    }
+
+    #[test]
+    fn discarded_pure_values_are_linted() {
+        let mut syms = Symbols::new();
+        let src = "\
+fn f(a: i64, b: i64) {
+    a + b;
+    do_thing();
+    a;
+    ();
+    a + b
+}";
+        let m = parse(src, &mut syms).unwrap();
+        let Item::Fn(f) = &m.items[0] else { panic!("expected a function item") };
+        // The `;` decides: a terminated expression is a statement, the last one a return.
+        assert!(matches!(f.body.stmts[0], Stmt::Expr(Expr::Bin(..))));
+        assert!(matches!(f.body.stmts[4], Stmt::Return(Some(Expr::Bin(..)))));
+        let lints = lint::check(&m, &syms);
+        let lines: Vec<_> = lints.iter().map(|l| (l.rule, l.line)).collect();
+        assert_eq!(lines, [("unused-value", 2), ("unused-value", 4)]);
+    }
+
+    #[test]
+    fn binders_named_like_a_variant_are_linted() {
+        let mut syms = Symbols::new();
+        let src = "\
+enum Color { Red, Green }
+enum Slot { Empty, Full(Color) }
+fn f(s: Slot) -> i64 {
+    match s {
+        Slot::Full(Red) => { return 1; }
+        Slot::Full(c) => { return 2; }
+        Slot::Empty => { return 3; }
+    }
+}";
+        let lints = lint::check(&parse(src, &mut syms).unwrap(), &syms);
+        assert_eq!(lints.len(), 1, "{lints:?}");
+        assert_eq!((lints[0].rule, lints[0].line), ("binding-shadows-variant", 4));
+        assert!(lints[0]
+            .message
+            .contains("pattern binding `Red` shadows the enum variant `Color::Red`"));
+        // A bare name is never a binding at the top of a pattern.
+        let bare = src.replace("Slot::Full(Red)", "Red");
+        assert!(parse(&bare, &mut syms).unwrap_err().contains(
+            "line 5: pattern `Red` is not a variant path: write `Enum::Red` to match a variant"
+        ));
+    }
+
+    #[test]
+    fn unused_parameters_and_type_parameters_are_linted() {
+        let mut syms = Symbols::new();
+        let src = "\
+struct P { x: i64 }
+trait Scale { fn scale<T>(self, k: i64) -> i64; }
+impl Scale for P { fn scale<T>(self, k: i64) -> i64 { return self.x; } }
+impl P { fn shift(self, by: i64) -> i64 { return self.x; } }
+fn first<T, U, V>(a: T, b: i64, _c: i64) -> T {
+    let v: Option<V> = Option::None;
+    return a;
+}
+fn spec(n: i64, _W: i64) -> i64 requires n > 0; { return 1; }
+fn skip<_X>(_y: i64) -> i64 { return 0; }";
+        let lints = lint::check(&parse(src, &mut syms).unwrap(), &syms);
+        let got: Vec<(&str, u32, &str)> =
+            lints.iter().map(|l| (l.rule, l.line, l.message.as_str())).collect();
+        assert_eq!(got, [
+            ("unused-parameter", 4, "parameter `by` of `P::shift` is never used"),
+            ("unused-parameter", 5, "parameter `b` of `first` is never used"),
+            (
+                "unused-generic-param",
+                5,
+                "type parameter `U` of `first` appears in no parameter, return or body type"
+            ),
+        ]);
+    }
+
+    #[test]
+    fn statements_span_from_first_to_last_line() {
+        let mut syms = Symbols::new();
+        let src = "\
+fn f(n: i64) -> i64 {
+    let x = n
+        + 1;
+    if x > 0 {
+        return x;
+    } else {
+        return 0;
+    }
+}";
+        let m = parse(src, &mut syms).unwrap();
+        let Item::Fn(f) = &m.items[0] else { panic!("expected a function item") };
+        assert_eq!(f.body.span, LineSpan::new(1, 9));
+        assert_eq!(f.body.spans, [LineSpan::new(2, 3), LineSpan::new(4, 8)]);
+        let Stmt::If { then_blk, else_blk: Some(else_blk), .. } = &f.body.stmts[1] else {
+            panic!("expected an if/else statement");
+        };
+        assert_eq!((then_blk.span, then_blk.spans[0]), (LineSpan::new(4, 6), LineSpan::new(5, 5)));
+        assert_eq!((else_blk.span, else_blk.spans[0]), (LineSpan::new(6, 8), LineSpan::new(7, 7)));
+    }
+
+    #[test]
+    fn span_checker_rejects_an_escaping_child() {
+        let mut syms = Symbols::new();
+        let src = "fn f() {\n    loop {\n        break;\n    }\n}";
+        let mut m = parse(src, &mut syms).unwrap();
+        assert_eq!(spans::check(&m, src), Ok(()));
+        let Item::Fn(f) = &mut m.items[0] else { panic!("expected a function item") };
+        let Stmt::Expr(Expr::Loop { body, .. }) = &mut f.body.stmts[0] else {
+            panic!("expected a loop statement");
+        };
+        body.spans[0] = LineSpan::new(3, 6);
+        let err = spans::check(&m, src).unwrap_err();
+        assert!(err.contains("statement (lines 3-6) is not within lines 2-4"), "got: {err}");
+    }
+
+    /// Every example in the repository parses to consistent spans.
+    #[test]
+    fn example_corpus_has_consistent_spans() {
+        let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples");
+        let mut checked = 0;
+        for dir in [root.clone(), root.join("proofs")] {
+            for entry in std::fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.extension().is_none_or(|e| e != "rv") {
+                    continue;
+                }
+                let src = std::fs::read_to_string(&path).unwrap();
+                // Only the spans are under test; sources this parser rejects
+                // are skipped.
+                if let Ok(m) = parse(&src, &mut Symbols::new()) {
+                    spans::check(&m, &src).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+                    checked += 1;
+                }
+            }
+        }
+        assert!(checked >= 10, "only {checked} examples parsed");
+    }
+
+    #[test]
+    fn cfg_attributes_keep_only_active_items() {
+        let src = "\
+#[cfg(test)] fn a() {}
+#[cfg(not(test))] fn b() {}
+#[cfg(all(test, feature = \"x\"))] fn c() {}
+#[cfg(any(unix, feature = \"x\"))] struct D { v: i64 }
+#[cfg(all())] #[cfg(not(any()))] enum E { V }
+#[cfg(test)] #[cfg(feature = \"y\")] impl D { fn f(self) {} }";
+        let names = |cfg: &CfgSet| {
+            let mut syms = Symbols::new();
+            let m = parse_with_cfg(src, &mut syms, cfg).unwrap();
+            m.items
+                .iter()
+                .map(|item| match item {
+                    Item::Fn(f) => syms.resolve(f.name).to_string(),
+                    Item::Struct(s) => syms.resolve(s.name).to_string(),
+                    Item::Enum(e) => syms.resolve(e.name).to_string(),
+                    Item::Impl(_) => "impl".to_string(),
+                    other => panic!("unexpected item {other:?}"),
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&CfgSet::new()), ["b", "E"]);
+
+        let mut cfg = CfgSet::new();
+        cfg.enable("test");
+        assert_eq!(names(&cfg), ["a", "E"]);
+        cfg.add_flag("feature=\"x\"").unwrap();
+        assert_eq!(names(&cfg), ["a", "c", "D", "E"]);
+        // A key may hold several values at once.
+        cfg.add_flag("feature=y").unwrap();
+        assert_eq!(names(&cfg), ["a", "c", "D", "E", "impl"]);
+        assert!(cfg.add_flag("feature=").is_ok());
+        assert!(cfg.add_flag("=x").is_err());
+    }
+
+    #[test]
+    fn malformed_cfg_predicates_are_reported() {
+        let err = |src: &str| parse(src, &mut Symbols::new()).unwrap_err();
+        assert!(err("#[cfg(not(a, b))] fn f() {}").contains("`not` takes one predicate, got 2"));
+        assert!(err("\n#[cfg(feature = 3)] fn f() {}")
+            .starts_with("line 2: malformed cfg predicate: `feature =` needs a string value"));
+        assert!(err("#[cfg()] fn f() {}").contains("malformed cfg predicate"));
+        assert!(err("#[inline] fn f() {}").contains("unknown attribute `inline`"));
+        assert!(err("fn f() {} #[cfg(test)]").contains("expected an item"));
+    }
+
     #[test]
     fn parses_while_with_invariants() {
         let mut syms = Symbols::new();
@@ -215,6 +735,18 @@ fn f(n: i64) -> i64 {
         assert_eq!(invariants.len(), 2);
     }
 
+    #[test]
+    fn parses_function_pointer_types() {
+        let mut syms = Symbols::new();
+        let m = parse("fn f(g: fn(i64, bool) -> f64, h: fn()) {}", &mut syms).unwrap();
+        let Item::Fn(f) = &m.items[0] else { panic!("expected a function item") };
+        assert_eq!(
+            f.params[0].ty,
+            Ty::Fn { params: vec![Ty::I64, Ty::Bool], ret: Box::new(Ty::F64) }
+        );
+        assert_eq!(f.params[1].ty, Ty::Fn { params: vec![], ret: Box::new(Ty::Unit) });
+    }
+
     #[test]
     fn parses_reference_type_and_borrow_and_deref() {
         let mut syms = Symbols::new();
@@ -239,11 +771,11 @@ fn f(r: &i64, m: &mut i64) -> i64 {
         );
 
         // `let a = &r;` — shared borrow.
-        let Stmt::Let { init: Expr::Ref { mutable: false, .. }, .. } = &f.body.stmts[0] else {
+        let Stmt::Let { init: Some(Expr::Ref { mutable: false, .. }), .. } = &f.body.stmts[0] else {
             panic!("expected a shared-borrow let");
         };
         // `let b = &mut a;` — mutable borrow.
-        let Stmt::Let { init: Expr::Ref { mutable: true, .. }, .. } = &f.body.stmts[1] else {
+        let Stmt::Let { init: Some(Expr::Ref { mutable: true, .. }), .. } = &f.body.stmts[1] else {
             panic!("expected a mutable-borrow let");
         };
         // `*m = 5;` — store through a reference.
@@ -295,7 +827,7 @@ enum Option<T> { None, Some(T) }";
     fn parses_trait_impl_and_method_call() {
         let mut syms = Symbols::new();
         let src = "\
-trait Summable { fn sum(self) -> i64; }
+trait Summable { fn sum(self) -> i64; fn scaled<T: Scale + Copy>(self, by: T) -> i64; }
 struct Point { x: i64, y: i64 }
 impl Point { fn sum(self) -> i64 { return self.x + self.y; } }
 impl Summable for Point { fn total(self) -> i64 { return self.x; } }
@@ -306,8 +838,9 @@ fn f() -> i64 {
         let m = parse(src, &mut syms).unwrap();
 
         let Item::Trait(t) = &m.items[0] else { panic!("expected a trait item") };
-        assert_eq!(t.methods.len(), 1);
-        assert!(t.methods[0].has_self);
+        assert_eq!(t.methods.len(), 2);
+        assert!(t.methods[0].has_self && t.methods[0].generics.is_empty());
+        assert_eq!(t.methods[1].generics[0].bounds.len(), 2);
 
         let Item::Impl(inherent) = &m.items[2] else { panic!("expected an impl item") };
         assert_eq!(inherent.trait_name, None);
@@ -362,7 +895,7 @@ fn g(x: i64) {
         let src = "fn f() -> i64 { let v = g()?; return v; }";
         let m = parse(src, &mut syms).unwrap();
         let Item::Fn(f) = &m.items[0] else { panic!("expected a function item") };
-        let Stmt::Let { init, .. } = &f.body.stmts[0] else {
+        let Stmt::Let { init: Some(init), .. } = &f.body.stmts[0] else {
             panic!("expected a let binding");
         };
         let Expr::Try(inner) = init else { panic!("expected a Try expression") };
@@ -391,7 +924,7 @@ fn g(x: i64) {
         let value_src = "fn f() -> i64 { let v = Foo.bar(x, y); return v; }";
         let vm = parse(value_src, &mut syms).unwrap();
         let Item::Fn(vf) = &vm.items[0] else { panic!("expected a function item") };
-        let Stmt::Let { init: value_expr, .. } = &vf.body.stmts[0] else {
+        let Stmt::Let { init: Some(value_expr), .. } = &vf.body.stmts[0] else {
             panic!("expected a let binding");
         };
         assert!(matches!(value_expr, Expr::MethodCall { .. }));
@@ -458,4 +991,86 @@ fn g(x: i64) {
         };
         assert!(matches!(lhs.as_ref(), Expr::MethodCall { .. }));
     }
+
+    #[test]
+    fn cast_binds_tighter_than_binary_operators() {
+        let mut syms = Symbols::new();
+        let m = parse("fn f(x: i64) -> bool { return -x as f64 * 2.0 == 1.0; }", &mut syms)
+            .unwrap();
+        let Item::Fn(f) = &m.items[0] else { panic!("expected a function item") };
+        // `((-x) as f64) * 2.0 == 1.0`: the comparison is the root, the cast sits
+        // under the multiplication, and the negation under the cast.
+        let Stmt::Return(Some(Expr::Bin(BinOp::Eq, lhs, _))) = &f.body.stmts[0] else {
+            panic!("expected a return of an equality");
+        };
+        let Expr::Bin(BinOp::Mul, cast, _) = lhs.as_ref() else {
+            panic!("expected a multiplication, got {lhs:?}");
+        };
+        let Expr::Cast { expr, ty: Ty::F64 } = cast.as_ref() else {
+            panic!("expected a cast to f64, got {cast:?}");
+        };
+        assert!(matches!(expr.as_ref(), Expr::Un(rv_core::UnOp::Neg, _)));
+    }
+
+    #[test]
+    fn parses_labeled_loops_with_break_values() {
+        let mut syms = Symbols::new();
+        let src = "\
+fn f() -> i64 {
+    let x = 'outer: loop {
+        loop { break 'outer 3; }
+    };
+    'scan: while x < 9 { continue 'scan; }
+    return x;
+}";
+        let m = parse(src, &mut syms).unwrap();
+        let Item::Fn(f) = &m.items[0] else { panic!("expected a function item") };
+        let outer = syms.intern("outer");
+        let Stmt::Let { init: Some(Expr::Loop { label: Some(l), body }), .. } = &f.body.stmts[0]
+        else {
+            panic!("expected a labeled loop initializer");
+        };
+        assert_eq!(*l, outer);
+        let Stmt::Expr(Expr::Loop { label: None, body: inner }) = &body.stmts[0] else {
+            panic!("expected an unlabeled inner loop statement");
+        };
+        assert!(matches!(
+            &inner.stmts[0],
+            Stmt::Break { label: Some(l), value: Some(Expr::Int(3)) } if *l == outer
+        ));
+        let Stmt::While { label: Some(_), body, .. } = &f.body.stmts[1] else {
+            panic!("expected a labeled while loop");
+        };
+        assert!(matches!(body.stmts[0], Stmt::Continue { label: Some(_) }));
+    }
+
+    /// Pathologically deep input is a diagnostic, never a stack overflow: a long
+    /// operator chain (built by a loop, so its parse is shallow) and deep nesting
+    /// (which the parser recurses into, on `rvc`'s 8 MiB main-thread stack).
+    #[test]
+    fn deep_nesting_is_rejected_cleanly() {
+        let chain = format!("fn f() -> i64 {{ return 1{}; }}", " + 1".repeat(50_000));
+        let (open, close) = ("(".repeat(50_000), ")".repeat(50_000));
+        let parens = format!("fn f() -> i64 {{ return {open}1{close}; }}");
+        let errs = std::thread::Builder::new()
+            .stack_size(8 << 20)
+            .spawn(move || {
+                [chain, parens].map(|src| parse(&src, &mut Symbols::new()).unwrap_err())
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        for err in errs {
+            assert!(err.contains("expression nesting too deep"), "got: {err}");
+        }
+
+        // The limit is configurable; ordinary nesting stays well within the default.
+        let src = "fn f() -> i64 { return ((((1)))); }";
+        assert!(parse_with_nesting_limit(src, &mut Symbols::new(), 4).is_err());
+        assert!(parse_with_nesting_limit(src, &mut Symbols::new(), 8).is_ok());
+
+        // A chain's links are not nesting: a chain longer than the limit parses.
+        let sum = format!("fn f() -> i64 {{ return 1{}; }}", " + 1".repeat(12));
+        assert!(parse_with_nesting_limit(&sum, &mut Symbols::new(), 8).is_ok());
+    }
 }
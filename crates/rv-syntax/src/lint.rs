@@ -0,0 +1,563 @@
+//! Lints: warnings about code that is legal but almost certainly a mistake.
+//!
+//! Lints never reject a program. [`check`] walks every function and method body
+//! and returns the warnings in source order; the driver reports them next to the
+//! verification results.
+
+use std::fmt;
+
+use std::collections::{HashMap, HashSet};
+
+use rv_core::{BinOp, Sym, Symbols, UnOp};
+
+use crate::ast::{
+    Block, Expr, GenericParam, Item, Module, Param, PatBind, Pattern, Stmt, Ty, Visibility,
+};
+
+/// One lint warning.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lint {
+    /// The rule's stable, kebab-case name.
+    pub rule: &'static str,
+    /// The line of the statement the warning is about.
+    pub line: u32,
+    pub message: String,
+    /// What to do instead.
+    pub help: &'static str,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Lint { rule, line, message, help } = self;
+        write!(f, "line {line}: warning[{rule}]: {message} (help: {help})")
+    }
+}
+
+/// Run every lint over `module`.
+pub fn check(module: &Module, syms: &Symbols) -> Vec<Lint> {
+    let mut out = Vec::new();
+    let vs = &Variants::of(module, syms);
+    for item in &module.items {
+        lint_visibility(item, syms, &mut out);
+        match item {
+            Item::Fn(f) => {
+                let sig = Signature {
+                    name: syms.resolve(f.name).to_string(),
+                    generics: &f.generics,
+                    params: &f.params,
+                    ret: f.ret.as_ref(),
+                    specs: f.requires.iter().chain(&f.ensures).collect(),
+                    body: &f.body,
+                };
+                lint_signature(&sig, syms, &mut out);
+                lint_block(&f.body, vs, &mut out);
+            }
+            Item::Impl(i) => {
+                for m in &i.methods {
+                    // A trait impl's signatures are the trait's to choose.
+                    if i.trait_name.is_none() {
+                        let (ty, name) = (syms.resolve(i.type_name), syms.resolve(m.name));
+                        let sig = Signature {
+                            name: format!("{ty}::{name}"),
+                            generics: &m.generics,
+                            params: &m.params,
+                            ret: m.ret.as_ref(),
+                            specs: m.requires.iter().chain(&m.ensures).collect(),
+                            body: &m.body,
+                        };
+                        lint_signature(&sig, syms, &mut out);
+                    }
+                    lint_block(&m.body, vs, &mut out);
+                }
+            }
+            _ => {}
+        }
+    }
+    // A method's `pub(crate)` is reported before the bodies of the methods above it.
+    out.sort_by_key(|l| l.line);
+    out
+}
+
+/// `unsupported-visibility`: `pub(crate)` parses, but until crate visibility
+/// exists the item, field, or method it marks is private.
+fn lint_visibility(item: &Item, syms: &Symbols, out: &mut Vec<Lint>) {
+    let name = |sym: Sym| syms.resolve(sym).to_string();
+    let mut marked = Vec::new();
+    match item {
+        Item::Fn(f) => marked.push((f.vis, name(f.name))),
+        Item::Struct(s) => {
+            marked.push((s.vis, name(s.name)));
+            let fields = s.fields.iter();
+            marked.extend(fields.map(|f| (f.vis, format!("{}.{}", name(s.name), name(f.name)))));
+        }
+        Item::Enum(e) => marked.push((e.vis, name(e.name))),
+        Item::Trait(t) => marked.push((t.vis, name(t.name))),
+        Item::Impl(i) => {
+            let methods = i.methods.iter();
+            marked.extend(
+                methods.map(|m| (m.vis, format!("{}::{}", name(i.type_name), name(m.name)))),
+            );
+        }
+        Item::Use(u) => marked.push((u.vis, name(u.variant.unwrap_or(u.enum_name)))),
+        _ => {}
+    }
+    for (vis, name) in marked {
+        if let Visibility::Crate { line } = vis {
+            out.push(Lint {
+                rule: "unsupported-visibility",
+                line,
+                message: format!("`pub(crate)` is not yet supported, so `{name}` is private"),
+                help: "use `pub` to export it, or drop the visibility",
+            });
+        }
+    }
+}
+
+/// What the signature lints need of a function or method.
+struct Signature<'a> {
+    /// The name to report it by: `f`, or `Type::method`.
+    name: String,
+    generics: &'a [GenericParam],
+    params: &'a [Param],
+    ret: Option<&'a Ty>,
+    /// The `requires` and `ensures` clauses, which use names like the body does.
+    specs: Vec<&'a Expr>,
+    body: &'a Block,
+}
+
+/// `unused-parameter` and `unused-generic-param`, reported against the line of
+/// the body's opening brace. A name starting with `_` is unused on purpose.
+fn lint_signature(sig: &Signature, syms: &Symbols, out: &mut Vec<Lint>) {
+    let line = sig.body.span.start;
+    let mut uses = HashSet::new();
+    uses_block(sig.body, &mut uses);
+    sig.specs.iter().for_each(|e| uses_expr(e, &mut uses));
+    let refinements = sig.params.iter().filter_map(|p| p.refinement.as_ref());
+    refinements.for_each(|e| uses_expr(e, &mut uses));
+    let unused = |name: Sym, uses: &HashSet<Sym>| {
+        let text = syms.resolve(name);
+        (!uses.contains(&name) && !text.starts_with('_')).then_some(text)
+    };
+    for p in sig.params {
+        // `unused-parameter`: a parameter the body no longer reads is dead
+        // surface every caller still has to supply.
+        if let Some(name) = unused(p.name, &uses) {
+            out.push(Lint {
+                rule: "unused-parameter",
+                line,
+                message: format!("parameter `{name}` of `{}` is never used", sig.name),
+                help: "remove it, or prefix its name with `_` if it must stay",
+            });
+        }
+    }
+    // `unused-generic-param`: a type parameter no type mentions is never
+    // inferred from anything, yet each call still instantiates it.
+    sig.params.iter().for_each(|p| uses_ty(&p.ty, &mut uses));
+    if let Some(ret) = sig.ret {
+        uses_ty(ret, &mut uses);
+    }
+    for g in sig.generics {
+        if let Some(name) = unused(g.name, &uses) {
+            out.push(Lint {
+                rule: "unused-generic-param",
+                line,
+                message: format!(
+                    "type parameter `{name}` of `{}` appears in no parameter, return or body type",
+                    sig.name
+                ),
+                help: "remove it; calls that pass it explicitly with `::<..>` must then drop \
+                       that argument, which changes how they instantiate the function",
+            });
+        }
+    }
+}
+
+/// Add every name `b` reads, calls or assigns, and every type name it writes,
+/// to `out`. Shadowing is ignored: a name used anywhere counts.
+fn uses_block(b: &Block, out: &mut HashSet<Sym>) {
+    rv_arena::stack::grow(|| uses_block_here(b, out))
+}
+
+fn uses_block_here(b: &Block, out: &mut HashSet<Sym>) {
+    for s in &b.stmts {
+        match s {
+            Stmt::Let { ty, init, .. } => {
+                ty.iter().for_each(|t| uses_ty(t, out));
+                init.iter().for_each(|e| uses_expr(e, out));
+            }
+            Stmt::Assign { name, value } => {
+                out.insert(*name);
+                uses_expr(value, out);
+            }
+            Stmt::DerefAssign { place: a, value: b } => {
+                uses_expr(a, out);
+                uses_expr(b, out);
+            }
+            Stmt::If { cond, then_blk, else_blk } => {
+                uses_expr(cond, out);
+                uses_block(then_blk, out);
+                else_blk.iter().for_each(|b| uses_block(b, out));
+            }
+            Stmt::While { cond, invariants, body, .. } => {
+                uses_expr(cond, out);
+                invariants.iter().for_each(|e| uses_expr(e, out));
+                uses_block(body, out);
+            }
+            Stmt::Match { scrut, arms } => {
+                uses_expr(scrut, out);
+                arms.iter().for_each(|arm| uses_block(&arm.body, out));
+            }
+            Stmt::Assert(e) | Stmt::Expr(e) => uses_expr(e, out),
+            Stmt::Return(e) | Stmt::Panic(e) | Stmt::Break { value: e, .. } => {
+                e.iter().for_each(|e| uses_expr(e, out));
+            }
+            Stmt::Continue { .. } => {}
+        }
+    }
+}
+
+/// [`uses_block`] for an expression.
+fn uses_expr(e: &Expr, out: &mut HashSet<Sym>) {
+    rv_arena::stack::grow(|| uses_expr_here(e, out))
+}
+
+fn uses_expr_here(e: &Expr, out: &mut HashSet<Sym>) {
+    let mut go = |e: &Expr| uses_expr(e, out);
+    match e {
+        Expr::Var(name) => {
+            out.insert(*name);
+        }
+        Expr::Call { func, args } => {
+            out.insert(*func);
+            args.iter().for_each(|a| uses_expr(a, out));
+        }
+        Expr::Inst { func, ty_args } => {
+            out.insert(*func);
+            ty_args.iter().for_each(|t| uses_ty(t, out));
+        }
+        Expr::Cast { expr, ty } => {
+            uses_ty(ty, out);
+            uses_expr(expr, out);
+        }
+        Expr::Loop { body, .. } => uses_block(body, out),
+        Expr::If { cond, then_blk, then_val, else_blk, else_val } => {
+            uses_expr(cond, out);
+            uses_block(then_blk, out);
+            uses_block(else_blk, out);
+            then_val.iter().chain(else_val).for_each(|e| uses_expr(e, out));
+        }
+        Expr::MatchExpr { scrut, arms } => {
+            go(scrut);
+            arms.iter().for_each(|(_, body)| go(body));
+        }
+        Expr::EnumCtor { args, .. } => args.iter().for_each(go),
+        Expr::MethodCall { recv: a, args, .. } | Expr::Apply { callee: a, args } => {
+            go(a);
+            args.iter().for_each(go);
+        }
+        Expr::StructLit { fields, .. } => fields.iter().for_each(|(_, e)| go(e)),
+        Expr::Bin(_, a, b) | Expr::Arrow(a, b) | Expr::Rewrite { eqn: a, body: b } => {
+            go(a);
+            go(b);
+        }
+        Expr::LetIn { ty, init, body, .. } => {
+            ty.iter().for_each(|t| go(t));
+            go(init);
+            go(body);
+        }
+        Expr::ByCases { scrut, tbody, fbody } => {
+            go(scrut);
+            go(tbody);
+            go(fbody);
+        }
+        Expr::Forall { params, body } => {
+            params.iter().for_each(|(_, t)| go(t));
+            go(body);
+        }
+        Expr::Fun { params, body } => {
+            params.iter().filter_map(|(_, t)| t.as_deref()).for_each(&mut go);
+            go(body);
+        }
+        Expr::Return(e) => e.iter().for_each(|e| go(e)),
+        Expr::Un(_, a)
+        | Expr::Deref(a)
+        | Expr::Try(a)
+        | Expr::Ref { expr: a, .. }
+        | Expr::Field { base: a, .. }
+        | Expr::Lambda { body: a, .. } => go(a),
+        Expr::Int(_)
+        | Expr::SuffixedInt(..)
+        | Expr::Float(_)
+        | Expr::Str(_)
+        | Expr::Bool(_)
+        | Expr::Unit
+        | Expr::TypeUniv(_)
+        | Expr::Prop
+        | Expr::Hole
+        | Expr::Decide => {}
+    }
+}
+
+/// Add every type name `ty` mentions, at any depth, to `out`.
+fn uses_ty(ty: &Ty, out: &mut HashSet<Sym>) {
+    rv_arena::stack::grow(|| uses_ty_here(ty, out))
+}
+
+fn uses_ty_here(ty: &Ty, out: &mut HashSet<Sym>) {
+    match ty {
+        Ty::Adt(name) | Ty::Param(name) => {
+            out.insert(*name);
+        }
+        Ty::Generic { base, args } => {
+            out.insert(*base);
+            args.iter().for_each(|a| uses_ty(a, out));
+        }
+        Ty::Ref { inner, .. } => uses_ty(inner, out),
+        Ty::Fn { params, ret } => {
+            params.iter().for_each(|p| uses_ty(p, out));
+            uses_ty(ret, out);
+        }
+        Ty::Term(e) => uses_expr(e, out),
+        Ty::I64 | Ty::IntN(_) | Ty::F64 | Ty::Bool | Ty::String | Ty::Unit | Ty::ImplTrait(_) => {}
+    }
+}
+
+fn lint_block(b: &Block, vs: &Variants, out: &mut Vec<Lint>) {
+    rv_arena::stack::grow(|| lint_block_here(b, vs, out))
+}
+
+fn lint_block_here(b: &Block, vs: &Variants, out: &mut Vec<Lint>) {
+    for (s, span) in b.stmts.iter().zip(&b.spans) {
+        lint_stmt(s, span.start, vs, out);
+    }
+}
+
+fn lint_stmt(s: &Stmt, line: u32, vs: &Variants, out: &mut Vec<Lint>) {
+    match s {
+        Stmt::Let { init: Some(e), .. } | Stmt::Assign { value: e, .. } | Stmt::Assert(e) => {
+            lint_expr(e, line, vs, out)
+        }
+        Stmt::Let { init: None, .. } => {}
+        Stmt::Expr(e) => {
+            // `unused-value`: a `;`-terminated expression that only computes a
+            // value throws it away. Calls are exempt: they may have effects.
+            if is_pure_value(e) {
+                out.push(Lint {
+                    rule: "unused-value",
+                    line,
+                    message: "the value of this expression is computed and then discarded"
+                        .to_string(),
+                    help: "bind it with `let`, or drop the `;` to return it",
+                });
+            }
+            lint_expr(e, line, vs, out)
+        }
+        Stmt::DerefAssign { place, value } => {
+            lint_expr(place, line, vs, out);
+            lint_expr(value, line, vs, out);
+        }
+        Stmt::If { cond, then_blk, else_blk } => {
+            lint_expr(cond, line, vs, out);
+            lint_block(then_blk, vs, out);
+            if let Some(b) = else_blk {
+                lint_block(b, vs, out);
+            }
+        }
+        Stmt::While { cond, invariants, body, .. } => {
+            lint_expr(cond, line, vs, out);
+            invariants.iter().for_each(|e| lint_expr(e, line, vs, out));
+            lint_block(body, vs, out);
+        }
+        Stmt::Match { scrut, arms } => {
+            lint_expr(scrut, line, vs, out);
+            for arm in arms {
+                lint_pattern(&arm.pat, line, vs, out);
+                lint_block(&arm.body, vs, out);
+            }
+        }
+        Stmt::Return(e) | Stmt::Panic(e) | Stmt::Break { value: e, .. } => {
+            if let Some(e) = e {
+                lint_expr(e, line, vs, out);
+            }
+        }
+        Stmt::Continue { .. } => {}
+    }
+}
+
+/// Expressions only matter for the blocks and patterns nested inside them, which
+/// are reported against `line`, the enclosing statement's line.
+fn lint_expr(e: &Expr, line: u32, vs: &Variants, out: &mut Vec<Lint>) {
+    rv_arena::stack::grow(|| lint_expr_here(e, line, vs, out))
+}
+
+fn lint_expr_here(e: &Expr, line: u32, vs: &Variants, out: &mut Vec<Lint>) {
+    let mut go = |e: &Expr| lint_expr(e, line, vs, out);
+    match e {
+        Expr::Loop { body, .. } => lint_block(body, vs, out),
+        Expr::If { cond, then_blk, then_val, else_blk, else_val } => {
+            lint_expr(cond, line, vs, out);
+            lint_block(then_blk, vs, out);
+            lint_block(else_blk, vs, out);
+            then_val.iter().chain(else_val).for_each(|e| lint_expr(e, line, vs, out));
+        }
+        Expr::MatchExpr { scrut, arms } => {
+            lint_expr(scrut, line, vs, out);
+            for (pat, body) in arms {
+                lint_pattern(pat, line, vs, out);
+                lint_expr(body, line, vs, out);
+            }
+        }
+        Expr::Call { args, .. } | Expr::EnumCtor { args, .. } => args.iter().for_each(go),
+        Expr::MethodCall { recv: a, args, .. } | Expr::Apply { callee: a, args } => {
+            go(a);
+            args.iter().for_each(go);
+        }
+        Expr::StructLit { fields, .. } => fields.iter().for_each(|(_, e)| go(e)),
+        Expr::Bin(op @ (BinOp::Eq | BinOp::Ne), a, b) => {
+            // `float-equality`: after rounding, two computations of "the same"
+            // float rarely compare equal, and NaN equals nothing, itself included.
+            if let Some(f) = float_literal(a).or_else(|| float_literal(b)) {
+                let op = if *op == BinOp::Eq { "==" } else { "!=" };
+                out.push(Lint {
+                    rule: "float-equality",
+                    line,
+                    message: format!("`{op}` compares a float for exact equality with `{f:?}`"),
+                    help: "compare the difference against an epsilon: `(a - b) < eps`",
+                });
+            }
+            lint_expr(a, line, vs, out);
+            lint_expr(b, line, vs, out);
+        }
+        Expr::Bin(_, a, b) | Expr::Arrow(a, b) | Expr::Rewrite { eqn: a, body: b } => {
+            go(a);
+            go(b);
+        }
+        Expr::LetIn { ty, init, body, .. } => {
+            if let Some(t) = ty {
+                go(t);
+            }
+            go(init);
+            go(body);
+        }
+        Expr::ByCases { scrut, tbody, fbody } => {
+            go(scrut);
+            go(tbody);
+            go(fbody);
+        }
+        Expr::Forall { params, body } => {
+            params.iter().for_each(|(_, t)| go(t));
+            go(body);
+        }
+        Expr::Return(e) => {
+            if let Some(e) = e {
+                go(e);
+            }
+        }
+        Expr::Un(_, a)
+        | Expr::Deref(a)
+        | Expr::Try(a)
+        | Expr::Ref { expr: a, .. }
+        | Expr::Cast { expr: a, .. }
+        | Expr::Field { base: a, .. }
+        | Expr::Lambda { body: a, .. }
+        | Expr::Fun { body: a, .. } => go(a),
+        Expr::Int(_)
+        | Expr::SuffixedInt(..)
+        | Expr::Float(_)
+        | Expr::Str(_)
+        | Expr::Bool(_)
+        | Expr::Unit
+        | Expr::Var(_)
+        | Expr::Inst { .. }
+        | Expr::TypeUniv(_)
+        | Expr::Prop
+        | Expr::Hole
+        | Expr::Decide => {}
+    }
+}
+
+/// Whether `e` is a pure computation with a non-`()` value: an operator, a field
+/// or place read, a cast, or a literal.
+fn is_pure_value(e: &Expr) -> bool {
+    matches!(
+        e,
+        Expr::Bin(..)
+            | Expr::Un(..)
+            | Expr::Field { .. }
+            | Expr::Deref(_)
+            | Expr::Cast { .. }
+            | Expr::Var(_)
+            | Expr::Int(_)
+            | Expr::SuffixedInt(..)
+            | Expr::Float(_)
+            | Expr::Str(_)
+            | Expr::Bool(_)
+    )
+}
+
+/// The value of a float literal, possibly negated.
+fn float_literal(e: &Expr) -> Option<f64> {
+    match e {
+        Expr::Float(f) => Some(*f),
+        Expr::Un(UnOp::Neg, inner) => float_literal(inner).map(|f| -f),
+        _ => None,
+    }
+}
+
+fn lint_pattern(p: &Pattern, line: u32, vs: &Variants, out: &mut Vec<Lint>) {
+    rv_arena::stack::grow(|| lint_pattern_here(p, line, vs, out))
+}
+
+fn lint_pattern_here(p: &Pattern, line: u32, vs: &Variants, out: &mut Vec<Lint>) {
+    match p {
+        // `float-pattern-match`: exact float equality is almost never what a
+        // match means; rounding makes arms silently unreachable.
+        Pattern::Float(f) => out.push(Lint {
+            rule: "float-pattern-match",
+            line,
+            message: format!("match arm compares a float for exact equality with `{f:?}`"),
+            help: "compare against a range, or within an epsilon, in an `if` instead",
+        }),
+        Pattern::Or(alts) => alts.iter().for_each(|a| lint_pattern(a, line, vs, out)),
+        // `binding-shadows-variant`: `Option::Some(Red)` binds whatever the
+        // `Some` holds to a fresh `Red`; it does not test for `Color::Red`.
+        Pattern::Variant { binds, .. } | Pattern::Bare { binds, .. } => {
+            for bind in binds {
+                let PatBind::Name(name) = bind else { continue };
+                let Some(&enum_name) = vs.by_name.get(name) else { continue };
+                let (name, enum_name) = (vs.syms.resolve(*name), vs.syms.resolve(enum_name));
+                out.push(Lint {
+                    rule: "binding-shadows-variant",
+                    line,
+                    message: format!(
+                        "pattern binding `{name}` shadows the enum variant `{enum_name}::{name}` \
+                         and matches any value"
+                    ),
+                    help: "rename the binding, and test its value with a nested match",
+                });
+            }
+        }
+        Pattern::Str(_) | Pattern::Wildcard => {}
+    }
+}
+
+/// The module's enum variants by name, for telling a binder that names one.
+struct Variants<'a> {
+    /// Each variant name to the enum that declares it (the first, when several do).
+    by_name: HashMap<Sym, Sym>,
+    syms: &'a Symbols,
+}
+
+impl<'a> Variants<'a> {
+    fn of(module: &Module, syms: &'a Symbols) -> Variants<'a> {
+        let mut by_name = HashMap::new();
+        for item in &module.items {
+            if let Item::Enum(e) = item {
+                for v in &e.variants {
+                    by_name.entry(v.name).or_insert(e.name);
+                }
+            }
+        }
+        Variants { by_name, syms }
+    }
+}
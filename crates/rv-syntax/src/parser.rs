@@ -5,8 +5,11 @@
 //! source line, never panics.
 
 use crate::ast::*;
+use crate::cfg::{CfgPred, CfgSet};
 use crate::lexer::{SpannedTok, Tok};
+use rv_arena::stack;
 use rv_core::{BinOp, Symbols, UnOp};
+use rv_diagnostics::E0562;
 
 /// Parser state: the token buffer plus a cursor.
 pub struct Parser<'a> {
@@ -18,11 +21,34 @@ pub struct Parser<'a> {
     /// `{` there opens the control-flow body rather than a struct literal. (See the
     /// struct-literal-vs-block disambiguation note in the parser docs.)
     no_struct_lit: bool,
+    /// When set, a `|` ends the expression instead of continuing it as bitwise
+    /// or. Enabled for the bodies of `match`-expression arms and the first
+    /// `by_cases` branch, where `|` separates what follows.
+    pipe_ends_expr: bool,
+    /// Current nesting depth (see [`Self::deepen`]) and the most it may reach.
+    depth: usize,
+    nesting_limit: usize,
+    /// Levels of the tree above the cursor: the nesting plus the links of the
+    /// enclosing chains (see [`Self::link`]).
+    tree_depth: usize,
+    /// Whether a `use` item has been parsed, after which a bare name in a
+    /// pattern may be an imported variant.
+    has_imports: bool,
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(toks: &'a [SpannedTok], syms: &'a mut Symbols) -> Self {
-        Self { toks, pos: 0, syms, no_struct_lit: false }
+    pub fn new(toks: &'a [SpannedTok], syms: &'a mut Symbols, nesting_limit: usize) -> Self {
+        Self {
+            toks,
+            pos: 0,
+            syms,
+            no_struct_lit: false,
+            pipe_ends_expr: false,
+            has_imports: false,
+            depth: 0,
+            nesting_limit,
+            tree_depth: 0,
+        }
     }
 
     /// Parse `body` with struct literals disabled in expression position (used for
@@ -38,16 +64,89 @@ impl<'a> Parser<'a> {
         r
     }
 
-    /// Parse `body` with struct literals re-enabled (used inside parentheses,
-    /// where the `{` ambiguity does not arise), restoring the flag afterward.
+    /// Parse `body` with struct literals and `|` as an operator re-enabled (used
+    /// inside parentheses, where neither ambiguity arises), restoring the flags
+    /// afterward.
     fn with_struct_lit<T>(
         &mut self,
         body: impl FnOnce(&mut Self) -> Result<T, String>,
     ) -> Result<T, String> {
-        let prev = self.no_struct_lit;
+        let prev = (self.no_struct_lit, self.pipe_ends_expr);
         self.no_struct_lit = false;
+        self.pipe_ends_expr = false;
         let r = body(self);
-        self.no_struct_lit = prev;
+        (self.no_struct_lit, self.pipe_ends_expr) = prev;
+        r
+    }
+
+    /// Parse `body` with `|` ending the expression (see [`Self::pipe_ends_expr`]),
+    /// restoring the flag afterward.
+    fn with_pipe_terminator<T>(
+        &mut self,
+        body: impl FnOnce(&mut Self) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let prev = self.pipe_ends_expr;
+        self.pipe_ends_expr = true;
+        let r = body(self);
+        self.pipe_ends_expr = prev;
+        r
+    }
+
+    /// Count one more level of nesting: a parenthesized or otherwise nested
+    /// expression, a prefix operator, a block, or a type. Every later pass
+    /// recurses over the same tree, so failing here turns a pathological input
+    /// into a diagnostic instead of a stack overflow anywhere downstream.
+    fn deepen(&mut self) -> Result<(), String> {
+        self.depth += 1;
+        if self.depth > self.nesting_limit {
+            return Err(format!(
+                "line {}: expression nesting too deep (the limit is {} levels)",
+                self.line(),
+                self.nesting_limit
+            ));
+        }
+        self.link()
+    }
+
+    /// Count one more link of an operator, cast, or postfix chain. A chain is
+    /// read in a loop, so its links are not nesting, but each one deepens the
+    /// left-nested tree that later passes (and its derived `Clone` and `Drop`)
+    /// recurse over. The tree may be twice as deep as the nesting limit.
+    fn link(&mut self) -> Result<(), String> {
+        self.tree_depth += 1;
+        let limit = self.nesting_limit.saturating_mul(2);
+        if self.tree_depth > limit {
+            return Err(format!(
+                "line {}: expression nesting too deep (operator chains included, \
+                 the limit is {limit} levels)",
+                self.line(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Parse `body` one nesting level deeper, restoring the depth afterward. The
+    /// recursion runs on [`stack::grow`], so it reaches the nesting limit on
+    /// any thread.
+    fn nested<T: Send>(
+        &mut self,
+        body: impl FnOnce(&mut Self) -> Result<T, String> + Send,
+    ) -> Result<T, String> {
+        let (depth, tree_depth) = (self.depth, self.tree_depth);
+        let r = self.deepen().and_then(|()| stack::grow(|| body(self)));
+        (self.depth, self.tree_depth) = (depth, tree_depth);
+        r
+    }
+
+    /// Parse a chain whose links each [`link`](Self::link), restoring the tree
+    /// depth once the chain ends.
+    fn chain<T>(
+        &mut self,
+        body: impl FnOnce(&mut Self) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let tree_depth = self.tree_depth;
+        let r = body(self);
+        self.tree_depth = tree_depth;
         r
     }
 
@@ -61,6 +160,11 @@ impl<'a> Parser<'a> {
         self.toks[self.pos].line
     }
 
+    /// The line of the last consumed token: where the construct just parsed ends.
+    fn prev_line(&self) -> u32 {
+        self.toks[self.pos.saturating_sub(1)].line
+    }
+
     fn bump(&mut self) -> Tok {
         let t = self.toks[self.pos].tok.clone();
         // Never advance past Eof.
@@ -155,37 +259,185 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Consume a loop label `'name` if present (a `break`/`continue` target).
+    fn label(&mut self) -> Option<rv_core::Sym> {
+        match self.peek().clone() {
+            Tok::Label(name) => {
+                self.bump();
+                Some(self.syms.intern(&name))
+            }
+            _ => None,
+        }
+    }
+
     // ---- grammar: program / items ------------------------------------------
 
-    /// `program := (fn_decl | struct_decl | enum_decl | type_alias | trait_decl | impl_decl)*`
-    pub fn parse_module(&mut self) -> Result<Module, String> {
+    /// `program := (attribute* visibility item)*`, where
+    /// `item := fn_decl | struct_decl | enum_decl | type_alias | trait_decl | impl_decl | ...`
+    /// and only a function, struct, enum, trait, or `use` may be `pub`.
+    ///
+    /// An item whose `#[cfg(...)]` attributes do not all hold under `cfg` is parsed
+    /// and then dropped.
+    pub fn parse_module(&mut self, cfg: &CfgSet) -> Result<Module, String> {
+        let items = self.parse_items(cfg)?.into_iter().filter_map(|(_, item)| item).collect();
+        Ok(Module { items })
+    }
+
+    /// The items of [`parse_module`](Self::parse_module), each with the index of its
+    /// first token (its first attribute, if it has any). A dropped item is `None`.
+    pub fn parse_items(&mut self, cfg: &CfgSet) -> Result<Vec<(usize, Option<Item>)>, String> {
         let mut items = Vec::new();
         while self.peek() != &Tok::Eof {
-            match self.peek() {
-                Tok::Fn => items.push(Item::Fn(self.parse_fn()?)),
-                Tok::Struct => items.push(Item::Struct(self.parse_struct()?)),
-                Tok::Enum => items.push(Item::Enum(self.parse_enum()?)),
-                Tok::Ident(w) if w == "type" => items.push(Item::TypeAlias(self.parse_type_alias()?)),
-                Tok::Trait => items.push(Item::Trait(self.parse_trait()?)),
-                Tok::Impl => items.push(Item::Impl(self.parse_impl()?)),
+            let first = self.pos;
+            let line = self.line();
+            let mut keep = true;
+            while self.peek() == &Tok::Pound {
+                keep &= self.parse_cfg_attribute()?.eval(cfg);
+            }
+            let vis = self.parse_visibility()?;
+            let takes_vis = matches!(self.peek(), Tok::Fn | Tok::Struct | Tok::Enum | Tok::Trait)
+                || self.peek_kw("use");
+            if vis != Visibility::Private && !takes_vis {
+                return Err(format!(
+                    "line {}: `{}` is not allowed before {:?}: only a `fn`, `struct`, `enum`, \
+                     `trait`, or `use` item may be `pub`",
+                    self.line(),
+                    vis.show().trim_end(),
+                    self.peek()
+                ));
+            }
+            let item = match self.peek() {
+                Tok::Fn => Item::Fn(self.parse_fn(vis)?),
+                Tok::Struct => Item::Struct(self.parse_struct(vis)?),
+                Tok::Enum => Item::Enum(self.parse_enum(vis)?),
+                Tok::Ident(w) if w == "type" => Item::TypeAlias(self.parse_type_alias()?),
+                Tok::Trait => Item::Trait(self.parse_trait(vis)?),
+                Tok::Impl => Item::Impl(self.parse_impl()?),
+                Tok::Ident(w) if w == "use" => Item::Use(self.parse_use(vis)?),
                 // Proof-fragment items, matched by spelling (no reserved keyword token):
                 // `axiom name(..) : T` and `def name(..) : T = e`.
-                Tok::Ident(w) if w == "axiom" => items.push(Item::Axiom(self.parse_axiom()?)),
-                Tok::Ident(w) if w == "def" => items.push(Item::Def(self.parse_def()?)),
-                Tok::Ident(w) if w == "instance" => {
-                    items.push(Item::Instance(self.parse_instance()?))
-                }
-                Tok::Ident(w) if w == "mutual" => items.push(self.parse_mutual()?),
+                Tok::Ident(w) if w == "axiom" => Item::Axiom(self.parse_axiom()?),
+                Tok::Ident(w) if w == "def" => Item::Def(self.parse_def()?),
+                Tok::Ident(w) if w == "instance" => Item::Instance(self.parse_instance()?),
+                Tok::Ident(w) if w == "mutual" => self.parse_mutual()?,
                 other => {
                     return Err(format!(
                         "line {}: expected an item (`fn`, `struct`, `enum`, `type`, `trait`, `impl`, \
-                         `axiom`, or `def`), found {other:?}",
+                         `use`, `axiom`, or `def`), found {other:?}",
                         self.line()
                     ))
                 }
+            };
+            if !keep {
+                tracing::debug!(line, "item left out by `#[cfg]`");
             }
+            items.push((first, keep.then_some(item)));
         }
-        Ok(Module { items })
+        Ok(items)
+    }
+
+    /// `visibility := ( "pub" ( "(" "crate" ")" )? )?`
+    ///
+    /// `pub` lexes as an ordinary identifier, so a field named `pub` (`pub: T`)
+    /// is a name rather than a visibility.
+    fn parse_visibility(&mut self) -> Result<Visibility, String> {
+        let named_pub = self.toks.get(self.pos + 1).is_some_and(|t| t.tok == Tok::Colon);
+        if named_pub || !self.eat_kw("pub") {
+            return Ok(Visibility::Private);
+        }
+        let line = self.line();
+        if !self.eat(&Tok::LParen) {
+            return Ok(Visibility::Public);
+        }
+        if !self.eat_kw("crate") {
+            return Err(format!(
+                "line {}: expected `crate` in `pub(..)` (only `pub(crate)` is reserved), \
+                 found {:?}",
+                self.line(),
+                self.peek()
+            ));
+        }
+        self.expect(&Tok::RParen, "to close `pub(crate`")?;
+        Ok(Visibility::Crate { line })
+    }
+
+    /// `attribute := "#" "[" "cfg" "(" cfg_pred ")" "]"`
+    fn parse_cfg_attribute(&mut self) -> Result<CfgPred, String> {
+        self.expect(&Tok::Pound, "to open an attribute")?;
+        self.expect(&Tok::LBracket, "after `#`")?;
+        match self.peek().clone() {
+            Tok::Ident(name) if name == "cfg" => {
+                self.bump();
+            }
+            Tok::Ident(name) => {
+                return Err(format!(
+                    "line {}: unknown attribute `{name}` (only `cfg` is supported)",
+                    self.line()
+                ))
+            }
+            other => {
+                return Err(format!(
+                    "line {}: expected an attribute name after `#[`, found {other:?}",
+                    self.line()
+                ))
+            }
+        }
+        self.expect(&Tok::LParen, "after `cfg`")?;
+        let pred = self.parse_cfg_pred()?;
+        self.expect(&Tok::RParen, "to close a cfg predicate")?;
+        self.expect(&Tok::RBracket, "to close an attribute")?;
+        Ok(pred)
+    }
+
+    /// `cfg_pred := IDENT ("=" STRING)? | ("not" | "all" | "any") "(" cfg_pred,* ")"`
+    fn parse_cfg_pred(&mut self) -> Result<CfgPred, String> {
+        let line = self.line();
+        let Tok::Ident(name) = self.peek().clone() else {
+            return Err(format!(
+                "line {line}: malformed cfg predicate: expected an option name or \
+                 `not`/`all`/`any`, found {:?}",
+                self.peek()
+            ));
+        };
+        self.bump();
+        let combinator = matches!(name.as_str(), "not" | "all" | "any");
+        if !(combinator && self.peek() == &Tok::LParen) {
+            if !self.eat(&Tok::Eq) {
+                return Ok(CfgPred::Option(name));
+            }
+            return match self.bump() {
+                Tok::Str(value) => Ok(CfgPred::KeyValue(name, value)),
+                other => Err(format!(
+                    "line {line}: malformed cfg predicate: `{name} =` needs a string value, \
+                     found {other:?}"
+                )),
+            };
+        }
+        self.bump(); // `(`
+        let mut args = Vec::new();
+        self.nested(|p| {
+            while p.peek() != &Tok::RParen {
+                args.push(p.parse_cfg_pred()?);
+                if !p.eat(&Tok::Comma) {
+                    break;
+                }
+            }
+            Ok(())
+        })?;
+        self.expect(&Tok::RParen, &format!("to close `{name}(..)`"))?;
+        Ok(match name.as_str() {
+            "all" => CfgPred::All(args),
+            "any" => CfgPred::Any(args),
+            _ => match <[CfgPred; 1]>::try_from(args) {
+                Ok([p]) => CfgPred::Not(Box::new(p)),
+                Err(args) => {
+                    return Err(format!(
+                        "line {line}: malformed cfg predicate: `not` takes one predicate, got {}",
+                        args.len()
+                    ))
+                }
+            },
+        })
     }
 
     /// `type_alias := "type" IDENT "=" type "where" expr ";"?`
@@ -206,6 +458,23 @@ impl<'a> Parser<'a> {
         Ok(TypeAliasDecl { name, base, refinement })
     }
 
+    /// `use := "use" IDENT "::" ( IDENT | "*" ) ";"`
+    fn parse_use(&mut self, vis: Visibility) -> Result<UseDecl, String> {
+        debug_assert!(self.peek_kw("use"));
+        let line = self.line();
+        self.bump();
+        let enum_name = self.ident("after `use`")?;
+        self.expect(&Tok::ColonColon, "after the enum name in a `use`")?;
+        let variant = if self.eat(&Tok::Star) {
+            None
+        } else {
+            Some(self.variant_name("as variant name in a `use`")?)
+        };
+        self.expect(&Tok::Semi, "after a `use`")?;
+        self.has_imports = true;
+        Ok(UseDecl { vis, enum_name, variant, line })
+    }
+
     /// `generics := ( "<" generic_param ("," generic_param)* ">" )?`
     /// `generic_param := IDENT ( ":" IDENT ("+" IDENT)* )?`
     ///
@@ -237,24 +506,26 @@ impl<'a> Parser<'a> {
         Ok(generics)
     }
 
-    /// `struct_decl := "struct" IDENT generics? "{" ( IDENT ":" type ("," ...)* ","? )? "}"`
-    fn parse_struct(&mut self) -> Result<StructDecl, String> {
+    /// `struct_decl := "struct" IDENT generics? "{" ( field ("," field)* ","? )? "}"`
+    /// `field := visibility IDENT ":" type`
+    fn parse_struct(&mut self, vis: Visibility) -> Result<StructDecl, String> {
         self.expect(&Tok::Struct, "to start a struct")?;
         let name = self.ident("as struct name")?;
         let generics = self.parse_generics()?;
         self.expect(&Tok::LBrace, "to open struct fields")?;
         let mut fields = Vec::new();
         while self.peek() != &Tok::RBrace && self.peek() != &Tok::Eof {
+            let fvis = self.parse_visibility()?;
             let fname = self.ident("as field name")?;
             self.expect(&Tok::Colon, "after field name")?;
             let ty = self.parse_type()?;
-            fields.push(FieldDecl { name: fname, ty });
+            fields.push(FieldDecl { vis: fvis, name: fname, ty });
             if !self.eat(&Tok::Comma) {
                 break;
             }
         }
         self.expect(&Tok::RBrace, "to close struct fields")?;
-        Ok(StructDecl { name, generics, fields })
+        Ok(StructDecl { vis, name, generics, fields })
     }
 
     /// `enum_decl := "enum" IDENT generics? indices? ("->" type)? "{" variant* "}"`
@@ -262,7 +533,7 @@ impl<'a> Parser<'a> {
     /// `variant   := IDENT field_list? where_clause? ((";"|",")?)`
     /// `field_list:= "(" field ("," field)* ")"`,  `field := (IDENT ":")? type`
     /// `where_clause := "where" IDENT "==" expr ("," ...)*`
-    fn parse_enum(&mut self) -> Result<EnumDecl, String> {
+    fn parse_enum(&mut self, vis: Visibility) -> Result<EnumDecl, String> {
         self.expect(&Tok::Enum, "to start an enum")?;
         let name = self.ident("as enum name")?;
         let generics = self.parse_generics()?;
@@ -288,6 +559,14 @@ impl<'a> Parser<'a> {
         self.expect(&Tok::LBrace, "to open enum variants")?;
         let mut variants = Vec::new();
         while self.peek() != &Tok::RBrace && self.peek() != &Tok::Eof {
+            let line = self.line();
+            if self.parse_visibility()? != Visibility::Private {
+                return Err(format!(
+                    "line {line}: an enum variant cannot be `pub`: it is as visible as its \
+                     enum `{}`",
+                    self.syms.resolve(name)
+                ));
+            }
             let vname = self.variant_name("as variant name")?;
             let mut field_tys = Vec::new();
             let mut field_names = Vec::new();
@@ -332,11 +611,11 @@ impl<'a> Parser<'a> {
             let _ = self.eat(&Tok::Comma) || self.eat(&Tok::Semi);
         }
         self.expect(&Tok::RBrace, "to close enum variants")?;
-        Ok(EnumDecl { name, generics, indices, result_sort, variants })
+        Ok(EnumDecl { vis, name, generics, indices, result_sort, variants })
     }
 
     /// `fn_decl := "fn" IDENT generics? "(" params? ")" ("->" type)? clause* block`
-    fn parse_fn(&mut self) -> Result<FnDecl, String> {
+    fn parse_fn(&mut self, vis: Visibility) -> Result<FnDecl, String> {
         self.expect(&Tok::Fn, "to start a function")?;
         let name = self.ident("as function name")?;
         let generics = self.parse_generics()?;
@@ -345,14 +624,14 @@ impl<'a> Parser<'a> {
         self.expect(&Tok::RParen, "after parameters")?;
 
         let ret = if self.eat(&Tok::Arrow) {
-            Some(self.parse_type()?)
+            Some(self.parse_ret_type()?)
         } else {
             None
         };
 
         let (requires, ensures) = self.parse_spec_clauses()?;
         let body = self.parse_block()?;
-        Ok(FnDecl { name, generics, params, ret, requires, ensures, body })
+        Ok(FnDecl { vis, name, generics, params, ret, requires, ensures, body })
     }
 
     /// `axiom_decl := "axiom" IDENT generics? ("(" params? ")")? ":" type`
@@ -429,7 +708,7 @@ impl<'a> Parser<'a> {
                     self.peek()
                 ));
             }
-            enums.push(self.parse_enum()?);
+            enums.push(self.parse_enum(Visibility::Private)?);
         }
         self.expect(&Tok::RBrace, "to close a mutual block")?;
         Ok(Item::Mutual(enums))
@@ -457,8 +736,9 @@ impl<'a> Parser<'a> {
     }
 
     /// `trait_decl := "trait" IDENT "{" trait_method_sig* "}"`
-    /// `trait_method_sig := "fn" IDENT "(" ["self" ("," params)? | params] ")" ("->" type)? ";"`
-    fn parse_trait(&mut self) -> Result<TraitDecl, String> {
+    /// `trait_method_sig := "fn" IDENT generics? "(" ["self" ("," params)? | params] ")"
+    ///                     ("->" type)? ";"`
+    fn parse_trait(&mut self, vis: Visibility) -> Result<TraitDecl, String> {
         self.expect(&Tok::Trait, "to start a trait")?;
         let name = self.ident("as trait name")?;
         self.expect(&Tok::LBrace, "to open trait body")?;
@@ -466,15 +746,16 @@ impl<'a> Parser<'a> {
         while self.peek() != &Tok::RBrace && self.peek() != &Tok::Eof {
             self.expect(&Tok::Fn, "to start a trait method signature")?;
             let mname = self.ident("as trait method name")?;
+            let generics = self.parse_generics()?;
             self.expect(&Tok::LParen, "after trait method name")?;
             let (has_self, params) = self.parse_method_params()?;
             self.expect(&Tok::RParen, "after trait method parameters")?;
             let ret = if self.eat(&Tok::Arrow) { Some(self.parse_type()?) } else { None };
             self.expect(&Tok::Semi, "after trait method signature")?;
-            methods.push(TraitMethodSig { name: mname, has_self, params, ret });
+            methods.push(TraitMethodSig { name: mname, generics, has_self, params, ret });
         }
         self.expect(&Tok::RBrace, "to close trait body")?;
-        Ok(TraitDecl { name, methods })
+        Ok(TraitDecl { vis, name, methods })
     }
 
     /// `impl_decl := "impl" IDENT ("for" IDENT)? "{" method* "}"`
@@ -500,19 +781,20 @@ impl<'a> Parser<'a> {
         Ok(ImplDecl { trait_name, type_name, methods })
     }
 
-    /// `method := "fn" IDENT generics? "(" ["self" ("," params)? | params] ")"
+    /// `method := visibility "fn" IDENT generics? "(" ["self" ("," params)? | params] ")"
     ///            ("->" type)? clause* block`
     fn parse_method(&mut self) -> Result<MethodDecl, String> {
+        let vis = self.parse_visibility()?;
         self.expect(&Tok::Fn, "to start a method")?;
         let name = self.ident("as method name")?;
         let generics = self.parse_generics()?;
         self.expect(&Tok::LParen, "after method name")?;
         let (has_self, params) = self.parse_method_params()?;
         self.expect(&Tok::RParen, "after method parameters")?;
-        let ret = if self.eat(&Tok::Arrow) { Some(self.parse_type()?) } else { None };
+        let ret = if self.eat(&Tok::Arrow) { Some(self.parse_ret_type()?) } else { None };
         let (requires, ensures) = self.parse_spec_clauses()?;
         let body = self.parse_block()?;
-        Ok(MethodDecl { name, generics, has_self, params, ret, requires, ensures, body })
+        Ok(MethodDecl { vis, name, generics, has_self, params, ret, requires, ensures, body })
     }
 
     /// Parse a method's parameter list: an optional leading `self` receiver,
@@ -542,6 +824,14 @@ impl<'a> Parser<'a> {
         loop {
             let name = self.ident("as parameter name")?;
             self.expect(&Tok::Colon, "after parameter name")?;
+            if self.peek() == &Tok::Impl {
+                return Err(format!(
+                    "line {}: {}",
+                    self.line(),
+                    E0562.tag("`impl Trait` in argument position is not yet supported; \
+                               declare a bounded type parameter `<T: Trait>` instead")
+                ));
+            }
             let ty = self.parse_type()?;
             // An optional refinement `where p` (a precondition on this parameter).
             let refinement = if self.eat_kw("where") {
@@ -557,6 +847,19 @@ impl<'a> Parser<'a> {
         Ok(params)
     }
 
+    /// `ret_type := "impl" IDENT ("+" IDENT)* | type`: a function's or method's
+    /// return type, the one place an opaque `impl Trait` type may appear.
+    fn parse_ret_type(&mut self) -> Result<Ty, String> {
+        if !self.eat(&Tok::Impl) {
+            return self.parse_type();
+        }
+        let mut bounds = vec![self.ident("as trait name after `impl`")?];
+        while self.eat(&Tok::Plus) {
+            bounds.push(self.ident("as trait bound")?);
+        }
+        Ok(Ty::ImplTrait(bounds))
+    }
+
     /// `type := "&" "mut"? type | scalar | "()" | IDENT generic? | type_expr`
     ///
     /// Executable types (`i64`, `f64`, `bool`, `String`, `()`, `&mut T`, `Option<i64>`) parse exactly as
@@ -565,7 +868,13 @@ impl<'a> Parser<'a> {
     /// `Type`/`Prop`, or a type-level application `Eval(env, e, v)` — which is captured as
     /// [`Ty::Term`]. The two are distinguished purely by what follows the base type:
     /// executable types are never followed by `(`, `==`, or `->`.
+    ///
+    /// A type is a level of nesting, like the expressions it may hold.
     fn parse_type(&mut self) -> Result<Ty, String> {
+        self.nested(|p| p.parse_type_here())
+    }
+
+    fn parse_type_here(&mut self) -> Result<Ty, String> {
         // Universes (`Type`, `Type n`, `Prop`) — proof fragment, matched by spelling.
         if self.peek_kw("Type") {
             self.bump();
@@ -581,6 +890,14 @@ impl<'a> Parser<'a> {
             self.bump();
             return self.type_expr_tail(Expr::Prop);
         }
+        if self.peek() == &Tok::Impl {
+            return Err(format!(
+                "line {}: {}",
+                self.line(),
+                E0562.tag("`impl Trait` is not yet supported here; only a function's \
+                           return type may be `impl Trait`")
+            ));
+        }
         // Reference type: `&T` or `&mut T`. `mut` arrives from the lexer as an
         // ordinary identifier, so we test its spelling rather than a keyword token.
         if self.eat(&Tok::Amp) {
@@ -588,6 +905,26 @@ impl<'a> Parser<'a> {
             let inner = self.parse_type()?;
             return Ok(Ty::Ref { mutable, inner: Box::new(inner) });
         }
+        // `&&T` lexes as one `&&` token: a reference to a reference.
+        if self.eat(&Tok::AndAnd) {
+            let mutable = self.eat_mut();
+            let inner = Box::new(Ty::Ref { mutable, inner: Box::new(self.parse_type()?) });
+            return Ok(Ty::Ref { mutable: false, inner });
+        }
+        // Function-pointer type: `fn(A, B) -> R`, with `-> R` defaulting to `()`.
+        if self.eat(&Tok::Fn) {
+            self.expect(&Tok::LParen, "after `fn` in a function type")?;
+            let mut params = Vec::new();
+            while self.peek() != &Tok::RParen {
+                params.push(self.parse_type()?);
+                if !self.eat(&Tok::Comma) {
+                    break;
+                }
+            }
+            self.expect(&Tok::RParen, "to close a function type's parameters")?;
+            let ret = if self.eat(&Tok::Arrow) { self.parse_type()? } else { Ty::Unit };
+            return Ok(Ty::Fn { params, ret: Box::new(ret) });
+        }
         // A parenthesized type may be `()` (unit), a dependent binder group
         // `(x y : T) -> rest` (a `Pi`/`forall` type), a grouped type, or — proof fragment —
         // a function type written in parens `(Nat -> Option<A>)`.
@@ -638,25 +975,9 @@ impl<'a> Parser<'a> {
         }
         let base = match self.peek().clone() {
             // Primitive types arrive as identifiers from the lexer.
-            Tok::Ident(name) if name == "i64" => {
-                self.bump();
-                Ty::I64
-            }
-            Tok::Ident(name) if fixed_int_ty(&name).is_some() => {
-                self.bump();
-                Ty::IntN(fixed_int_ty(&name).expect("guarded above"))
-            }
-            Tok::Ident(name) if name == "f64" => {
-                self.bump();
-                Ty::F64
-            }
-            Tok::Ident(name) if name == "bool" => {
-                self.bump();
-                Ty::Bool
-            }
-            Tok::Ident(name) if name == "String" => {
+            Tok::Ident(name) if Ty::primitive(&name).is_some() => {
                 self.bump();
-                Ty::String
+                Ty::primitive(&name).expect("guarded above")
             }
             // Any other identifier names a user-defined struct/enum, an optional
             // generic application (`Base<arg, ...>`), or — resolved at lowering —
@@ -830,20 +1151,27 @@ impl<'a> Parser<'a> {
 
     /// `block := "{" stmt* "}"`
     fn parse_block(&mut self) -> Result<Block, String> {
+        let open = self.line();
         self.expect(&Tok::LBrace, "to open a block")?;
-        let mut stmts = Vec::new();
-        while self.peek() != &Tok::RBrace && self.peek() != &Tok::Eof {
-            stmts.push(self.parse_stmt()?);
-        }
-        self.expect(&Tok::RBrace, "to close a block")?;
-        Ok(Block { stmts })
+        self.nested(|p| {
+            let mut stmts = Vec::new();
+            let mut spans = Vec::new();
+            while p.peek() != &Tok::RBrace && p.peek() != &Tok::Eof {
+                let start = p.line();
+                stmts.push(p.parse_stmt()?);
+                spans.push(LineSpan::new(start, p.prev_line()));
+            }
+            p.expect(&Tok::RBrace, "to close a block")?;
+            Ok(Block { stmts, spans, span: LineSpan::new(open, p.prev_line()) })
+        })
     }
 
     fn parse_stmt(&mut self) -> Result<Stmt, String> {
         match self.peek() {
             Tok::Let => self.parse_let(),
             Tok::If => self.parse_if(),
-            Tok::While => self.parse_while(),
+            Tok::While => self.parse_while(None),
+            Tok::Label(_) => self.parse_labeled_loop(),
             // A proof-style `match` (arms led by `|`, expression bodies) is the
             // value-producing tail of a functional body; parse it as an expression and
             // treat it as an implicit return. An executable `match` (block arms) stays a
@@ -860,6 +1188,15 @@ impl<'a> Parser<'a> {
             // A leading identifier followed by `=` is an assignment; otherwise
             // it is an expression statement.
             Tok::Ident(_) if self.peek_is_assignment() => self.parse_assign(),
+            // `loop`, `break`, and `continue` are matched by spelling (like the
+            // proof-fragment keywords), so they stay usable as ordinary names.
+            _ if self.peek_loop() => {
+                let e = self.parse_loop(None)?;
+                self.eat(&Tok::Semi);
+                Ok(Stmt::Expr(e))
+            }
+            _ if self.peek_kw("break") => self.parse_break(),
+            _ if self.peek_kw("continue") => self.parse_continue(),
             _ => {
                 let e = self.parse_expr()?;
                 // A `*place = value;` store-through-a-reference: the parsed
@@ -913,12 +1250,16 @@ impl<'a> Parser<'a> {
             && self.toks[self.pos + 1].tok == Tok::Eq
     }
 
-    /// `"let" IDENT (":" type)? "=" expr ";"` (executable statement) — or, in the proof
+    /// `"let" "mut"? IDENT (":" type)? ("=" expr)? ";"` (executable statement) — or, in the proof
     /// fragment, a let-*expression* `"let" IDENT (":" type)? ":=" expr "in" expr` (the whole
     /// body's tail). The two are told apart by the assignment operator: `=` is a statement,
     /// `:=` a proof let-expression.
     fn parse_let(&mut self) -> Result<Stmt, String> {
         self.expect(&Tok::Let, "to start a let binding")?;
+        // `let mut x`, but not a binding named `mut` (`let mut = ..`).
+        let after = self.toks.get(self.pos + 1).map(|t| &t.tok);
+        let named_after = matches!(after, Some(Tok::Ident(_)));
+        let mutable = named_after && self.eat_mut();
         let name = self.ident("as let binding name")?;
         // A `:` that is *not* the start of `:=` introduces a type annotation.
         let has_ann = self.peek() == &Tok::Colon
@@ -946,10 +1287,14 @@ impl<'a> Parser<'a> {
                 body: Box::new(body),
             })));
         }
-        self.expect(&Tok::Eq, "in let binding")?;
-        let init = self.parse_expr()?;
+        let init = if self.peek() == &Tok::Semi {
+            None
+        } else {
+            self.expect(&Tok::Eq, "in let binding")?;
+            Some(self.parse_expr()?)
+        };
         self.expect(&Tok::Semi, "after let binding")?;
-        Ok(Stmt::Let { name, ty, init })
+        Ok(Stmt::Let { name, mutable, ty, init })
     }
 
     /// A let-*expression* in expression position: `let x (: T)? := init in body`.
@@ -1029,12 +1374,142 @@ impl<'a> Parser<'a> {
         Ok(Stmt::If { cond, then_blk, else_blk })
     }
 
+    /// `"if" expr value_block "else" (value_block | if_expr)` in expression
+    /// position. Unlike the statement, it needs its `else`.
+    fn parse_if_expr(&mut self) -> Result<Expr, String> {
+        self.expect(&Tok::If, "to start an if expression")?;
+        let cond = self.with_no_struct_lit(|p| p.parse_expr())?;
+        let (then_blk, then_val) = self.with_struct_lit(|p| p.parse_value_block())?;
+        self.expect(&Tok::Else, "after the `then` block of an if expression")?;
+        let (else_blk, else_val) = self.parse_else_value()?;
+        Ok(Expr::If {
+            cond: Box::new(cond),
+            then_blk,
+            then_val: then_val.map(|(e, _)| e),
+            else_blk,
+            else_val: else_val.map(|(e, _)| e),
+        })
+    }
+
+    /// The branch after an if expression's `else`: a value block, or a nested
+    /// if expression standing for a block holding only it.
+    fn parse_else_value(&mut self) -> Result<ValueBlock, String> {
+        if self.peek() != &Tok::If {
+            return self.with_struct_lit(|p| p.parse_value_block());
+        }
+        let line = self.line();
+        let nested = self.nested(|p| p.parse_if_expr())?;
+        let span = LineSpan::new(line, self.prev_line());
+        Ok((Block { stmts: Vec::new(), spans: Vec::new(), span }, Some((Box::new(nested), span))))
+    }
+
+    /// `"{" stmt* expr? "}"`: a block and the expression ending it without a
+    /// `;`, if any, which is the block's value, with its lines. An explicit
+    /// `return` there stays a statement.
+    fn parse_value_block(&mut self) -> Result<ValueBlock, String> {
+        let open = self.line();
+        self.expect(&Tok::LBrace, "to open a block")?;
+        self.nested(|p| {
+            let mut stmts = Vec::new();
+            let mut spans = Vec::new();
+            let mut value = None;
+            while p.peek() != &Tok::RBrace && p.peek() != &Tok::Eof {
+                let (start, explicit) = (p.line(), matches!(p.peek(), Tok::Return | Tok::Match));
+                let stmt =
+                    if p.peek() == &Tok::If { p.parse_value_block_if()? } else { p.parse_stmt()? };
+                let span = LineSpan::new(start, p.prev_line());
+                match stmt {
+                    Stmt::Return(Some(e)) if !explicit && p.peek() == &Tok::RBrace => {
+                        value = Some((Box::new(e), span));
+                    }
+                    stmt => {
+                        stmts.push(stmt);
+                        spans.push(span);
+                    }
+                }
+            }
+            p.expect(&Tok::RBrace, "to close a block")?;
+            Ok((Block { stmts, spans, span: LineSpan::new(open, p.prev_line()) }, value))
+        })
+    }
+
+    /// An `if` among a value block's statements. Ending the block, with an
+    /// `else`, it is an if expression giving the block's value (returned as
+    /// the implicit-return statement the caller takes the value from);
+    /// anywhere else it is the `if` statement, each branch's value put back
+    /// as the implicit return [`Parser::parse_block`] would have made of it.
+    fn parse_value_block_if(&mut self) -> Result<Stmt, String> {
+        self.expect(&Tok::If, "to start an if statement")?;
+        let cond = self.with_no_struct_lit(|p| p.parse_expr())?;
+        let then_branch = self.with_struct_lit(|p| p.parse_value_block())?;
+        if !self.eat(&Tok::Else) {
+            return Ok(Stmt::If { cond, then_blk: value_as_return(then_branch), else_blk: None });
+        }
+        let else_branch = self.parse_else_value()?;
+        if self.peek() == &Tok::RBrace {
+            let ((then_blk, then_val), (else_blk, else_val)) = (then_branch, else_branch);
+            return Ok(Stmt::Return(Some(Expr::If {
+                cond: Box::new(cond),
+                then_blk,
+                then_val: then_val.map(|(e, _)| e),
+                else_blk,
+                else_val: else_val.map(|(e, _)| e),
+            })));
+        }
+        Ok(Stmt::If {
+            cond,
+            then_blk: value_as_return(then_branch),
+            else_blk: Some(value_as_return(else_branch)),
+        })
+    }
+
+    /// `LABEL ":" (while_stmt | loop_expr ";"?)` — a labeled loop in statement position.
+    fn parse_labeled_loop(&mut self) -> Result<Stmt, String> {
+        let label = self.label();
+        self.expect(&Tok::Colon, "after a loop label")?;
+        if self.peek() == &Tok::While {
+            return self.parse_while(label);
+        }
+        let e = self.parse_loop(label)?;
+        self.eat(&Tok::Semi);
+        Ok(Stmt::Expr(e))
+    }
+
+    /// Is the current token a `loop` keyword opening a loop body?
+    fn peek_loop(&self) -> bool {
+        self.peek_kw("loop") && self.toks.get(self.pos + 1).map(|t| &t.tok) == Some(&Tok::LBrace)
+    }
+
+    /// `"loop" block` (the label, if any, has already been consumed).
+    fn parse_loop(&mut self, label: Option<rv_core::Sym>) -> Result<Expr, String> {
+        self.expect_kw("loop", "after a loop label")?;
+        let body = self.parse_block()?;
+        Ok(Expr::Loop { label, body })
+    }
+
+    /// `"break" LABEL? expr? ";"`
+    fn parse_break(&mut self) -> Result<Stmt, String> {
+        self.expect_kw("break", "to start a break")?;
+        let label = self.label();
+        let value = if self.peek() == &Tok::Semi { None } else { Some(self.parse_expr()?) };
+        self.expect(&Tok::Semi, "after break")?;
+        Ok(Stmt::Break { label, value })
+    }
+
+    /// `"continue" LABEL? ";"`
+    fn parse_continue(&mut self) -> Result<Stmt, String> {
+        self.expect_kw("continue", "to start a continue")?;
+        let label = self.label();
+        self.expect(&Tok::Semi, "after continue")?;
+        Ok(Stmt::Continue { label })
+    }
+
     /// `"while" expr ("invariant" expr ";")* block`
     ///
     /// The condition is parsed with struct literals disabled (so the body `{`
     /// is not mistaken for a struct literal); zero or more `invariant` clauses
     /// may then precede the body.
-    fn parse_while(&mut self) -> Result<Stmt, String> {
+    fn parse_while(&mut self, label: Option<rv_core::Sym>) -> Result<Stmt, String> {
         self.expect(&Tok::While, "to start a while loop")?;
         let cond = self.with_no_struct_lit(|p| p.parse_expr())?;
         // Zero or more `invariant <expr>;` clauses before the body.
@@ -1045,7 +1520,7 @@ impl<'a> Parser<'a> {
             invariants.push(inv);
         }
         let body = self.parse_block()?;
-        Ok(Stmt::While { cond, invariants, body })
+        Ok(Stmt::While { label, cond, invariants, body })
     }
 
     /// `"match" expr "{" arm* "}"` where `arm := pattern "=>" block ","?`
@@ -1055,7 +1530,7 @@ impl<'a> Parser<'a> {
         self.expect(&Tok::LBrace, "to open match arms")?;
         let mut arms = Vec::new();
         while self.peek() != &Tok::RBrace && self.peek() != &Tok::Eof {
-            let pat = self.parse_pattern()?;
+            let pat = self.parse_or_pattern()?;
             self.expect(&Tok::FatArrow, "after match pattern")?;
             let body = self.parse_block()?;
             arms.push(MatchArm { pat, body });
@@ -1066,9 +1541,40 @@ impl<'a> Parser<'a> {
         Ok(Stmt::Match { scrut, arms })
     }
 
-    /// `pattern := IDENT "::" IDENT ( "(" patbind ("," patbind)* ")" )? | "_"`
+    /// `or_pattern := pattern ("|" pattern)*`
+    fn parse_or_pattern(&mut self) -> Result<Pattern, String> {
+        let first = self.parse_pattern()?;
+        if self.peek() != &Tok::Pipe {
+            return Ok(first);
+        }
+        let mut alts = vec![first];
+        while self.eat(&Tok::Pipe) {
+            alts.push(self.parse_pattern()?);
+        }
+        Ok(Pattern::Or(alts))
+    }
+
+    /// `pattern := IDENT "::" IDENT ( "(" patbind ("," patbind)* ")" )? | STRING
+    ///            | "-"? FLOAT | "_"`
     /// `patbind := IDENT | "_"`
     fn parse_pattern(&mut self) -> Result<Pattern, String> {
+        if let Tok::Str(s) = self.peek() {
+            let s = s.clone();
+            self.bump();
+            return Ok(Pattern::Str(s));
+        }
+        let negate = self.peek() == &Tok::Minus;
+        if negate {
+            self.bump();
+        }
+        if let Tok::Float(f) = *self.peek() {
+            self.bump();
+            return Ok(Pattern::Float(if negate { -f } else { f }));
+        }
+        if negate {
+            let line = self.line();
+            return Err(format!("line {line}: expected a float literal after `-` in pattern"));
+        }
         // The wildcard pattern is the identifier `_`.
         if let Tok::Ident(name) = self.peek() {
             if name == "_" {
@@ -1077,8 +1583,27 @@ impl<'a> Parser<'a> {
             }
         }
         let enum_name = self.ident("as enum name in pattern")?;
-        self.expect(&Tok::ColonColon, "between enum and variant in pattern")?;
+        // A bare name would bind in other languages; here it is an error, not an
+        // arm that silently matches everything. With a `use` in the module it may
+        // name an imported variant, which lowering resolves.
+        if self.peek() != &Tok::ColonColon && self.has_imports {
+            return Ok(Pattern::Bare { variant: enum_name, binds: self.parse_patbinds()? });
+        }
+        if self.peek() != &Tok::ColonColon {
+            let (line, name) = (self.line(), self.syms.resolve(enum_name));
+            return Err(format!(
+                "line {line}: pattern `{name}` is not a variant path: write `Enum::{name}` to \
+                 match a variant, or `_` to match anything"
+            ));
+        }
+        self.bump();
         let variant = self.variant_name("as variant name in pattern")?;
+        let binds = self.parse_patbinds()?;
+        Ok(Pattern::Variant { enum_name, variant, binds })
+    }
+
+    /// A variant pattern's binders, `(b0, b1, ...)`; none without parentheses.
+    fn parse_patbinds(&mut self) -> Result<Vec<PatBind>, String> {
         let mut binds = Vec::new();
         if self.eat(&Tok::LParen) {
             loop {
@@ -1089,7 +1614,7 @@ impl<'a> Parser<'a> {
             }
             self.expect(&Tok::RParen, "after pattern binders")?;
         }
-        Ok(Pattern::Variant { enum_name, variant, binds })
+        Ok(binds)
     }
 
     /// A single pattern binder: a name to bind, or `_` to ignore.
@@ -1102,17 +1627,33 @@ impl<'a> Parser<'a> {
         }
     }
 
-    /// `"return" expr? ";"`
+    /// `"return" expr? ";"`. The `;` may be left off before a block's closing `}`.
     fn parse_return(&mut self) -> Result<Stmt, String> {
         self.expect(&Tok::Return, "to start a return")?;
-        if self.eat(&Tok::Semi) {
+        if self.eat(&Tok::Semi) || self.peek() == &Tok::RBrace {
             return Ok(Stmt::Return(None));
         }
         let e = self.parse_expr()?;
-        self.expect(&Tok::Semi, "after return value")?;
+        if self.peek() != &Tok::RBrace {
+            self.expect(&Tok::Semi, "after return value")?;
+        }
         Ok(Stmt::Return(Some(e)))
     }
 
+    /// `"return" expr?` in expression position. The value is absent when the
+    /// token after `return` closes the surrounding construct.
+    fn parse_return_expr(&mut self) -> Result<Expr, String> {
+        self.expect(&Tok::Return, "to start a return")?;
+        let ends = matches!(
+            self.peek(),
+            Tok::Semi | Tok::Comma | Tok::RParen | Tok::RBrace | Tok::RBracket | Tok::Eof
+        ) || (self.pipe_ends_expr && self.peek() == &Tok::Pipe);
+        if ends {
+            return Ok(Expr::Return(None));
+        }
+        Ok(Expr::Return(Some(Box::new(self.parse_expr()?))))
+    }
+
     /// `"assert" expr ";"`
     fn parse_assert(&mut self) -> Result<Stmt, String> {
         self.expect(&Tok::Assert, "to start an assert")?;
@@ -1154,7 +1695,7 @@ impl<'a> Parser<'a> {
             self.eat(&Tok::Pipe); // optional leading/separating `|`
             let pat = self.parse_pattern()?;
             self.expect(&Tok::FatArrow, "after match pattern")?;
-            let body = self.parse_expr()?;
+            let body = self.with_pipe_terminator(|p| p.parse_expr())?;
             arms.push((pat, body));
             self.eat(&Tok::Comma); // arms may also be comma-separated
         }
@@ -1253,28 +1794,78 @@ impl<'a> Parser<'a> {
 
     /// Entry point for expressions.
     fn parse_expr(&mut self) -> Result<Expr, String> {
-        self.parse_bin(0)
+        self.nested(|p| p.parse_bin(0))
     }
 
     /// Precedence-climbing core. `min_bp` is the minimum binding power this call
     /// will accept; binary operators with lower power stop the climb.
     fn parse_bin(&mut self, min_bp: u8) -> Result<Expr, String> {
-        let mut lhs = self.parse_unary()?;
-        loop {
-            let (op, bp) = match binop_of(self.peek()) {
-                Some(pair) => pair,
-                None => break,
-            };
-            if bp < min_bp {
-                break;
+        self.chain(|p| {
+            let mut lhs = p.parse_cast()?;
+            loop {
+                let (op, bp, len) = match p.peek_binop() {
+                    Some(op) => op,
+                    None => break,
+                };
+                if bp < min_bp {
+                    break;
+                }
+                for _ in 0..len {
+                    p.bump();
+                }
+                p.link()?;
+                // All our binary operators are left-associative, so the right-hand
+                // side parses with strictly greater binding power.
+                let rhs = p.parse_bin(bp + 1)?;
+                lhs = Expr::Bin(op, Box::new(lhs), Box::new(rhs));
             }
+            Ok(lhs)
+        })
+    }
+
+    /// The binary operator at the cursor, its binding power, and how many tokens
+    /// spell it: `<<` and `>>` are two joint `Lt`/`Gt` tokens (see
+    /// [`SpannedTok::joint`]); every other operator is one token.
+    fn peek_binop(&self) -> Option<(BinOp, u8, usize)> {
+        let cur = &self.toks[self.pos];
+        let next = self.toks.get(self.pos + 1).map(|t| &t.tok);
+        match (&cur.tok, next) {
+            (Tok::Lt, Some(Tok::Lt)) if cur.joint => return Some((BinOp::Shl, SHIFT_BP, 2)),
+            (Tok::Gt, Some(Tok::Gt)) if cur.joint => return Some((BinOp::Shr, SHIFT_BP, 2)),
+            (Tok::Pipe, _) if self.pipe_ends_expr => return None,
+            _ => {}
+        }
+        binop_of(&cur.tok).map(|(op, bp)| (op, bp, 1))
+    }
+
+    /// `cast := unary ( "as" cast_ty )*`
+    ///
+    /// `as` is matched by spelling (it stays usable as an identifier elsewhere).
+    fn parse_cast(&mut self) -> Result<Expr, String> {
+        self.chain(|p| {
+            let mut e = p.parse_unary()?;
+            while p.eat_kw("as") {
+                p.link()?;
+                let ty = p.parse_cast_ty()?;
+                e = Expr::Cast { expr: Box::new(e), ty };
+            }
+            Ok(e)
+        })
+    }
+
+    /// The target of an `as`: a primitive spelling, `()`, or a bare type name.
+    /// Deliberately narrower than [`Self::parse_type`], which would read a
+    /// following `==`/`->`/`(` as a proof-fragment type-expression; here those
+    /// continue the surrounding value expression (`x as f64 == y`). Only numeric
+    /// targets type-check; other names parse so inference can reject them clearly.
+    fn parse_cast_ty(&mut self) -> Result<Ty, String> {
+        if self.peek() == &Tok::LParen {
             self.bump();
-            // All our binary operators are left-associative, so the right-hand
-            // side parses with strictly greater binding power.
-            let rhs = self.parse_bin(bp + 1)?;
-            lhs = Expr::Bin(op, Box::new(lhs), Box::new(rhs));
+            self.expect(&Tok::RParen, "in the `()` cast target")?;
+            return Ok(Ty::Unit);
         }
-        Ok(lhs)
+        let name = self.ident("as the target type of `as`")?;
+        Ok(Ty::primitive(self.syms.resolve(name)).unwrap_or(Ty::Adt(name)))
     }
 
     /// `unary := ("-" | "!" | "*" | "&" "mut"?) unary | primary`
@@ -1285,36 +1876,47 @@ impl<'a> Parser<'a> {
         match self.peek() {
             Tok::Minus => {
                 self.bump();
-                Ok(Expr::Un(UnOp::Neg, Box::new(self.parse_unary()?)))
+                Ok(Expr::Un(UnOp::Neg, Box::new(self.nested(Self::parse_unary)?)))
             }
             Tok::Bang => {
                 self.bump();
-                Ok(Expr::Un(UnOp::Not, Box::new(self.parse_unary()?)))
+                Ok(Expr::Un(UnOp::Not, Box::new(self.nested(Self::parse_unary)?)))
             }
             // Dereference `*expr`.
             Tok::Star => {
                 self.bump();
-                Ok(Expr::Deref(Box::new(self.parse_unary()?)))
+                Ok(Expr::Deref(Box::new(self.nested(Self::parse_unary)?)))
             }
             // Borrow `&expr` (shared) or `&mut expr` (mutable).
             Tok::Amp => {
                 self.bump();
                 let mutable = self.eat_mut();
-                Ok(Expr::Ref { mutable, expr: Box::new(self.parse_unary()?) })
+                Ok(Expr::Ref { mutable, expr: Box::new(self.nested(Self::parse_unary)?) })
             }
             _ => self.parse_postfix(),
         }
     }
 
-    /// `postfix := primary ( "." IDENT ( "(" args? ")" )? | "?" )*`
+    /// `postfix := primary ( "." IDENT ( "(" args? ")" )? | "." INT | "?" )*`
     ///
     /// A `.IDENT` followed by `(` is a method call (`recv.m(args)`); otherwise it
     /// is a field access. A trailing `?` is the error-propagation operator. All are
     /// left-associative postfix forms, so chains compose (e.g. `f()?.x`).
     fn parse_postfix(&mut self) -> Result<Expr, String> {
+        self.chain(Self::parse_postfix_chain)
+    }
+
+    fn parse_postfix_chain(&mut self) -> Result<Expr, String> {
         let mut e = self.parse_primary()?;
         loop {
             if self.eat(&Tok::Dot) {
+                // A positional field `recv.0` is named by its index's digits.
+                if let Tok::Int(index) = *self.peek() {
+                    self.bump();
+                    let field = self.syms.intern(&index.to_string());
+                    e = Expr::Field { base: Box::new(e), field };
+                    continue;
+                }
                 let name = self.ident("as field or method name after `.`")?;
                 if self.eat(&Tok::LParen) {
                     // Method call `recv.method(args)`.
@@ -1352,6 +1954,7 @@ impl<'a> Parser<'a> {
             } else {
                 break;
             }
+            self.link()?;
         }
         Ok(e)
     }
@@ -1391,6 +1994,21 @@ impl<'a> Parser<'a> {
         if self.peek() == &Tok::Let {
             return self.parse_let_in_expr();
         }
+        // A `loop` in expression position (`let x = 'find: loop { .. };`).
+        if matches!(self.peek(), Tok::Label(_)) {
+            let label = self.label();
+            self.expect(&Tok::Colon, "after a loop label")?;
+            return self.parse_loop(label);
+        }
+        if self.peek_loop() {
+            return self.parse_loop(None);
+        }
+        if self.peek() == &Tok::Return {
+            return self.parse_return_expr();
+        }
+        if self.peek() == &Tok::If {
+            return self.parse_if_expr();
+        }
         // Proof-fragment keyword atoms (matched by spelling).
         if self.peek_kw("fun") {
             return self.parse_fun();
@@ -1427,7 +2045,7 @@ impl<'a> Parser<'a> {
             self.bump();
             let scrut = self.with_no_struct_lit(|p| p.parse_expr())?;
             self.expect(&Tok::FatArrow, "after `by_cases <scrut>`")?;
-            let tbody = self.parse_expr()?;
+            let tbody = self.with_pipe_terminator(|p| p.parse_expr())?;
             self.expect(&Tok::Pipe, "between `by_cases` branches")?;
             let fbody = self.parse_expr()?;
             return Ok(Expr::ByCases {
@@ -1446,6 +2064,10 @@ impl<'a> Parser<'a> {
                 self.bump();
                 Ok(Expr::Int(n))
             }
+            Tok::SuffixedInt(n, ty) => {
+                self.bump();
+                Ok(Expr::SuffixedInt(n, ty))
+            }
             Tok::Float(f) => {
                 self.bump();
                 Ok(Expr::Float(f))
@@ -1492,6 +2114,19 @@ impl<'a> Parser<'a> {
                     self.expect(&Tok::RParen, "after call arguments")?;
                     Ok(Expr::Call { func: sym, args })
                 } else if self.eat(&Tok::ColonColon) {
+                    // Turbofish `f::<T, ..>`: a generic function value with its type
+                    // arguments spelled out.
+                    if self.eat(&Tok::Lt) {
+                        let mut ty_args = Vec::new();
+                        loop {
+                            ty_args.push(self.parse_type()?);
+                            if !self.eat(&Tok::Comma) {
+                                break;
+                            }
+                        }
+                        self.expect(&Tok::Gt, "to close turbofish type arguments")?;
+                        return Ok(Expr::Inst { func: sym, ty_args });
+                    }
                     // Enum constructor `Enum::Variant` or `Enum::Variant(args)`.
                     let variant = self.variant_name("as enum variant")?;
                     let args = if self.eat(&Tok::LParen) {
@@ -1583,7 +2218,7 @@ impl<'a> Parser<'a> {
 
 /// Parse the executable fixed-width integer spellings. `isize`/`usize` are
 /// modeled as 64-bit values on Raven's current 64-bit VM target.
-fn fixed_int_ty(name: &str) -> Option<rv_core::IntTy> {
+pub(crate) fn fixed_int_ty(name: &str) -> Option<rv_core::IntTy> {
     let (signed, bits) = match name {
         "i8" => (true, 8),
         "i16" => (true, 16),
@@ -1604,9 +2239,27 @@ fn fixed_int_ty(name: &str) -> Option<rv_core::IntTy> {
     Some(rv_core::IntTy { signed, bits })
 }
 
+/// A block parsed for its value: the block and its value-giving tail
+/// expression, if any, with that expression's lines.
+type ValueBlock = (Block, Option<(Box<Expr>, LineSpan)>);
+
+/// A value block as a plain block: its value, if any, becomes the trailing
+/// implicit `return` it was parsed from.
+fn value_as_return((mut block, value): ValueBlock) -> Block {
+    if let Some((e, span)) = value {
+        block.stmts.push(Stmt::Return(Some(*e)));
+        block.spans.push(span);
+    }
+    block
+}
+
+/// Binding power of `<<`/`>>`, which [`Parser::peek_binop`] recognizes from two
+/// tokens rather than through [`binop_of`].
+const SHIFT_BP: u8 = 8;
+
 /// Map a token to its binary operator and binding power (higher binds tighter).
-/// Mirrors the grammar's precedence ladder (lowest -> highest):
-/// `||` < `&&` < `== !=` < `< <= > >=` < `+ -` < `* / %`.
+/// Mirrors the grammar's precedence ladder (lowest -> highest), which is Rust's:
+/// `||` < `&&` < `== !=` < `< <= > >=` < `|` < `^` < `&` < `<< >>` < `+ -` < `* / %`.
 fn binop_of(tok: &Tok) -> Option<(BinOp, u8)> {
     Some(match tok {
         Tok::OrOr => (BinOp::Or, 1),
@@ -1617,11 +2270,14 @@ fn binop_of(tok: &Tok) -> Option<(BinOp, u8)> {
         Tok::Le => (BinOp::Le, 4),
         Tok::Gt => (BinOp::Gt, 4),
         Tok::Ge => (BinOp::Ge, 4),
-        Tok::Plus => (BinOp::Add, 5),
-        Tok::Minus => (BinOp::Sub, 5),
-        Tok::Star => (BinOp::Mul, 6),
-        Tok::Slash => (BinOp::Div, 6),
-        Tok::Percent => (BinOp::Mod, 6),
+        Tok::Pipe => (BinOp::BitOr, 5),
+        Tok::Caret => (BinOp::BitXor, 6),
+        Tok::Amp => (BinOp::BitAnd, 7),
+        Tok::Plus => (BinOp::Add, 9),
+        Tok::Minus => (BinOp::Sub, 9),
+        Tok::Star => (BinOp::Mul, 10),
+        Tok::Slash => (BinOp::Div, 10),
+        Tok::Percent => (BinOp::Mod, 10),
         _ => return None,
     })
 }
@@ -1642,6 +2298,10 @@ fn describe(tok: &Tok) -> String {
         Tok::FatArrow => "`=>`".into(),
         Tok::Eq => "`=`".into(),
         Tok::Question => "`?`".into(),
+        Tok::Pound => "`#`".into(),
+        Tok::Caret => "`^`".into(),
+        Tok::LBracket => "`[`".into(),
+        Tok::RBracket => "`]`".into(),
         Tok::Fn => "`fn`".into(),
         other => format!("{other:?}"),
     }
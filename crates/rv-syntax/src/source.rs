@@ -0,0 +1,105 @@
+//! Reading source files: bytes in, parser-ready text out.
+//!
+//! [`decode`] strips a leading UTF-8 byte-order mark and replaces each invalid
+//! UTF-8 sequence with U+FFFD, warning once per replacement, so one stray
+//! Latin-1 byte does not stop the build. Replacement never adds or removes a
+//! newline, so every line number the parser reports holds for the file on disk.
+//! A file that is mostly not UTF-8, or holds a NUL byte, is rejected outright:
+//! it is binary, and a warning per byte would bury the one thing worth saying.
+
+use crate::lint::Lint;
+
+/// The UTF-8 encoding of U+FEFF, which some editors write at the start of a file.
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Source text decoded from a file's bytes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Source {
+    pub text: String,
+    /// One `invalid-utf8` warning per replaced sequence, in file order.
+    pub warnings: Vec<Lint>,
+}
+
+/// Decode `bytes`, the contents of a source file. Errs if the file is not text.
+pub fn decode(bytes: &[u8]) -> Result<Source, String> {
+    if bytes.contains(&0) {
+        return Err("not a text file: it contains a NUL byte".to_string());
+    }
+    let body = bytes.strip_prefix(BOM).unwrap_or(bytes);
+    let base = bytes.len() - body.len();
+    let (mut text, mut warnings) = (String::with_capacity(body.len()), Vec::new());
+    let (mut rest, mut line, mut invalid) = (body, 1, 0);
+    while !rest.is_empty() {
+        let (valid, bad) = match std::str::from_utf8(rest) {
+            Ok(s) => (s, 0),
+            Err(e) => {
+                let valid = std::str::from_utf8(&rest[..e.valid_up_to()]).expect("checked");
+                (valid, e.error_len().unwrap_or(rest.len() - e.valid_up_to()))
+            }
+        };
+        text.push_str(valid);
+        line += valid.matches('\n').count() as u32;
+        if bad > 0 {
+            let offset = base + (body.len() - rest.len()) + valid.len();
+            let shown: Vec<String> =
+                bytes[offset..offset + bad].iter().map(|b| format!("0x{b:02X}")).collect();
+            let what = if bad == 1 { "byte" } else { "bytes" };
+            warnings.push(Lint {
+                rule: "invalid-utf8",
+                line,
+                message: format!(
+                    "invalid UTF-8 {what} {} at offset {offset}; replaced with U+FFFD",
+                    shown.join(" ")
+                ),
+                help: "save the file as UTF-8",
+            });
+            text.push(char::REPLACEMENT_CHARACTER);
+            invalid += bad;
+        }
+        rest = &rest[valid.len() + bad..];
+    }
+    // An eighth of the file is no longer a few stray bytes in a text file.
+    if invalid * 8 > body.len() {
+        let len = bytes.len();
+        return Err(format!("not a text file: {invalid} of its {len} bytes are not valid UTF-8"));
+    }
+    Ok(Source { text, warnings })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_byte_order_mark_is_dropped() {
+        let src = decode(b"\xEF\xBB\xBFfn main() {}\n").unwrap();
+        assert_eq!(src, Source { text: "fn main() {}\n".to_string(), warnings: Vec::new() });
+        assert_eq!(decode(b"").unwrap().text, "");
+    }
+
+    #[test]
+    fn each_invalid_sequence_is_replaced_with_a_warning() {
+        let bytes = b"\xEF\xBB\xBF// caf\xE9 au lait\nfn main() { return 1; } // \xF0\x9F\n";
+        let src = decode(bytes).unwrap();
+        assert_eq!(src.text, "// caf\u{FFFD} au lait\nfn main() { return 1; } // \u{FFFD}\n");
+        let shown: Vec<String> = src.warnings.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            shown,
+            [
+                "line 1: warning[invalid-utf8]: invalid UTF-8 byte 0xE9 at offset 9; \
+                 replaced with U+FFFD (help: save the file as UTF-8)",
+                "line 2: warning[invalid-utf8]: invalid UTF-8 bytes 0xF0 0x9F at offset 46; \
+                 replaced with U+FFFD (help: save the file as UTF-8)",
+            ]
+        );
+    }
+
+    #[test]
+    fn binary_files_are_rejected_with_one_error() {
+        let err = decode(b"\x7FELF\x02\x01\x01\x00").unwrap_err();
+        assert_eq!(err, "not a text file: it contains a NUL byte");
+        let noise: Vec<u8> = (0..64).map(|i| 0x80 | i).collect();
+        let err = decode(&noise).unwrap_err();
+        assert_eq!(err, "not a text file: 64 of its 64 bytes are not valid UTF-8");
+    }
+}
@@ -0,0 +1,169 @@
+//! Span consistency checking.
+//!
+//! Every block and statement the parser builds carries a [`LineSpan`]. Those
+//! spans are only useful to diagnostics if they describe the real construct, so
+//! [`check`] walks a parsed module and verifies that
+//!
+//! - every span starts no later than it ends and lies within the file;
+//! - a statement lies within its block, and a block within the statement (or
+//!   item) that holds it;
+//! - the statements of a block follow one another in source order.
+//!
+//! [`parse`](crate::parse) runs the check after every successful parse in debug
+//! builds, so the whole test corpus exercises it.
+
+use crate::ast::{Block, Expr, Item, LineSpan, Module, Stmt};
+
+/// Check the spans of every function and method body in `module`, parsed from
+/// `src`. Returns the first violation found.
+pub fn check(module: &Module, src: &str) -> Result<(), String> {
+    let file = LineSpan::new(1, src.lines().count().max(1) as u32);
+    for item in &module.items {
+        match item {
+            Item::Fn(f) => check_block(&f.body, file)?,
+            Item::Impl(i) => i.methods.iter().try_for_each(|m| check_block(&m.body, file))?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn check_span(what: &str, span: LineSpan, parent: LineSpan) -> Result<(), String> {
+    if span.start > span.end {
+        return Err(format!("line {}: {what} ends before it starts ({span:?})", span.start));
+    }
+    if !parent.contains(span) {
+        return Err(format!("line {}: {what} ({span}) is not within {parent}", span.start));
+    }
+    Ok(())
+}
+
+fn check_block(b: &Block, parent: LineSpan) -> Result<(), String> {
+    rv_arena::stack::grow(|| check_block_here(b, parent))
+}
+
+fn check_block_here(b: &Block, parent: LineSpan) -> Result<(), String> {
+    check_span("block", b.span, parent)?;
+    if b.spans.len() != b.stmts.len() {
+        return Err(format!(
+            "line {}: block has {} statements but {} spans",
+            b.span.start,
+            b.stmts.len(),
+            b.spans.len()
+        ));
+    }
+    let mut prev_end = b.span.start;
+    for (s, &span) in b.stmts.iter().zip(&b.spans) {
+        check_span("statement", span, b.span)?;
+        if span.start < prev_end {
+            return Err(format!(
+                "line {}: statement starts before the previous one ends (line {prev_end})",
+                span.start
+            ));
+        }
+        prev_end = span.end;
+        check_stmt(s, span)?;
+    }
+    Ok(())
+}
+
+/// Check the blocks nested in statement `s`, which covers `span`.
+fn check_stmt(s: &Stmt, span: LineSpan) -> Result<(), String> {
+    match s {
+        Stmt::Let { init: Some(e), .. }
+        | Stmt::Assign { value: e, .. }
+        | Stmt::Assert(e)
+        | Stmt::Expr(e) => check_expr(e, span),
+        Stmt::Let { init: None, .. } => Ok(()),
+        Stmt::DerefAssign { place, value } => {
+            check_expr(place, span)?;
+            check_expr(value, span)
+        }
+        Stmt::If { cond, then_blk, else_blk } => {
+            check_expr(cond, span)?;
+            check_block(then_blk, span)?;
+            else_blk.as_ref().map_or(Ok(()), |b| check_block(b, span))
+        }
+        Stmt::While { cond, invariants, body, .. } => {
+            check_expr(cond, span)?;
+            invariants.iter().try_for_each(|e| check_expr(e, span))?;
+            check_block(body, span)
+        }
+        Stmt::Match { scrut, arms } => {
+            check_expr(scrut, span)?;
+            arms.iter().try_for_each(|arm| check_block(&arm.body, span))
+        }
+        Stmt::Return(e) | Stmt::Panic(e) | Stmt::Break { value: e, .. } => {
+            e.as_ref().map_or(Ok(()), |e| check_expr(e, span))
+        }
+        Stmt::Continue { .. } => Ok(()),
+    }
+}
+
+/// Expressions carry no spans of their own; only the blocks nested inside them
+/// (`loop` bodies, `if` branches) are checked, against the enclosing statement's span.
+fn check_expr(e: &Expr, span: LineSpan) -> Result<(), String> {
+    rv_arena::stack::grow(|| check_expr_here(e, span))
+}
+
+fn check_expr_here(e: &Expr, span: LineSpan) -> Result<(), String> {
+    let go = |e: &Expr| check_expr(e, span);
+    match e {
+        Expr::Loop { body, .. } => check_block(body, span),
+        Expr::If { cond, then_blk, then_val, else_blk, else_val } => {
+            go(cond)?;
+            check_block(then_blk, span)?;
+            check_block(else_blk, span)?;
+            then_val.iter().chain(else_val).try_for_each(|e| go(e))
+        }
+        Expr::MatchExpr { scrut, arms } => {
+            go(scrut)?;
+            arms.iter().try_for_each(|(_, body)| go(body))
+        }
+        Expr::Call { args, .. } | Expr::EnumCtor { args, .. } => args.iter().try_for_each(go),
+        Expr::MethodCall { recv: a, args, .. } | Expr::Apply { callee: a, args } => {
+            go(a)?;
+            args.iter().try_for_each(go)
+        }
+        Expr::StructLit { fields, .. } => fields.iter().try_for_each(|(_, e)| go(e)),
+        Expr::Bin(_, a, b) | Expr::Arrow(a, b) | Expr::Rewrite { eqn: a, body: b } => {
+            go(a)?;
+            go(b)
+        }
+        Expr::LetIn { ty, init, body, .. } => {
+            ty.as_deref().map_or(Ok(()), go)?;
+            go(init)?;
+            go(body)
+        }
+        Expr::ByCases { scrut, tbody, fbody } => {
+            go(scrut)?;
+            go(tbody)?;
+            go(fbody)
+        }
+        Expr::Forall { params, body } => {
+            params.iter().try_for_each(|(_, t)| go(t))?;
+            go(body)
+        }
+        Expr::Return(e) => e.as_deref().map_or(Ok(()), go),
+        Expr::Un(_, a)
+        | Expr::Deref(a)
+        | Expr::Try(a)
+        | Expr::Ref { expr: a, .. }
+        | Expr::Cast { expr: a, .. }
+        | Expr::Field { base: a, .. }
+        | Expr::Lambda { body: a, .. }
+        | Expr::Fun { body: a, .. } => go(a),
+        Expr::Int(_)
+        | Expr::SuffixedInt(..)
+        | Expr::Float(_)
+        | Expr::Str(_)
+        | Expr::Bool(_)
+        | Expr::Unit
+        | Expr::Var(_)
+        | Expr::Inst { .. }
+        | Expr::TypeUniv(_)
+        | Expr::Prop
+        | Expr::Hole
+        | Expr::Decide => Ok(()),
+    }
+}
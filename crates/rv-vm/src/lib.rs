@@ -7,9 +7,22 @@
 //!
 //! Recursion in the interpreter mirrors recursion in the program, so `Call`
 //! simply evaluates the callee with a fresh frame and writes the result back.
+//! The store that references point into is shared by every frame of a run, so a
+//! reference passed to or returned from a call still reaches its cell.
+//!
+//! Untrusted programs run within [`Limits`]: a step count, a wall-clock time, and
+//! a memory budget charged as values are written and credited as they are
+//! overwritten or their frame returns. Exceeding one stops the run with an error.
+
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
 
 use rv_codegen::{BinOpKind as BinOp, Bytecode, CompiledFn, Const, Instr, UnOpKind as UnOp};
 
+mod render;
+
+pub use render::ValueRenderer;
+
 /// A runtime value.
 ///
 /// `Adt` (an aggregate: a struct or enum variant) holds owned field values, so
@@ -32,10 +45,12 @@ pub enum Value {
     /// An algebraic data value. `tag` is the enum variant index (0 for structs);
     /// `fields` are the constructor's arguments in declaration order.
     Adt { tag: u32, fields: Vec<Value> },
-    /// A reference: an index into the frame's store (heap of cells). Produced by
-    /// `&x` / `&mut x`; followed by `Load`/`Store` to read or mutate the cell. Both
+    /// A reference: a cell of the run's store (heap of cells), and the path of
+    /// field indices from the value in that cell down to the referent (empty for
+    /// the whole cell). Produced by `&x` / `&mut x`, extended by `FieldRef` for
+    /// `&x.f`, and followed by `Load`/`Store` to read or mutate the referent. Both
     /// shared and mutable borrows share this representation.
-    Ref(usize),
+    Ref { addr: usize, path: Vec<u32> },
     /// A first-class closure: the lifted function (`fn_idx` into [`Bytecode::funcs`])
     /// together with the values it captured by value. Calling it (`CallClosure`)
     /// runs `fn_idx` with `captured` prepended to the call arguments.
@@ -43,15 +58,247 @@ pub enum Value {
 }
 
 /// Run function `entry` with `args`, returning its result or a runtime error.
+/// The run has no [`Limits`]; see [`run_limited`].
 pub fn run(bc: &Bytecode, entry: &str, args: &[Value]) -> Result<Value, String> {
+    run_metered(bc, entry, args, &mut Meter::new(Limits::NONE))
+}
+
+/// [`run`] on the calling thread, stopped with an error once it exceeds any of
+/// `limits`.
+pub fn run_limited(
+    bc: &Bytecode,
+    entry: &str,
+    args: &[Value],
+    limits: Limits,
+) -> Result<Value, IsolatedRunError> {
+    let mut meter = Meter::new(limits);
+    let result = run_metered(bc, entry, args, &mut meter);
+    result.map_err(|e| meter.exceeded.take().unwrap_or(IsolatedRunError::Runtime(e)))
+}
+
+fn run_metered(
+    bc: &Bytecode,
+    entry: &str,
+    args: &[Value],
+    meter: &mut Meter,
+) -> Result<Value, String> {
+    let _span = tracing::debug_span!("run", entry).entered();
     let idx = bc
         .func_index(entry)
         .ok_or_else(|| format!("no such function: {entry}"))?;
-    exec_fn(bc, idx, args)
+    exec_fn(bc, idx, args, &mut Vec::new(), meter)
+}
+
+/// How long [`run_isolated`] callers that have no deadline of their own give a
+/// program before abandoning it.
+pub const DEFAULT_RUN_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How many bytes of live values a run gets by default: far more than any real
+/// program needs, and far less than it takes to exhaust the host.
+pub const DEFAULT_MEMORY_LIMIT: usize = 1 << 30;
+
+/// Resource bounds for a run of untrusted code.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Limits {
+    /// How many instructions the run may execute.
+    pub steps: Option<u64>,
+    /// How many bytes its live values may hold: every active frame's registers
+    /// and every store cell, each value counted with everything it owns.
+    pub memory: Option<usize>,
+    /// How long it may take.
+    pub time: Option<Duration>,
+}
+
+impl Limits {
+    /// No bounds: the program runs until it halts.
+    pub const NONE: Limits = Limits { steps: None, memory: None, time: None };
+}
+
+impl Default for Limits {
+    /// [`DEFAULT_RUN_TIMEOUT`] and [`DEFAULT_MEMORY_LIMIT`], with no step limit.
+    fn default() -> Limits {
+        Limits { steps: None, memory: Some(DEFAULT_MEMORY_LIMIT), time: Some(DEFAULT_RUN_TIMEOUT) }
+    }
+}
+
+/// Stack for the isolated interpreter thread. Recursion in the program recurses in
+/// the interpreter, so this bounds how deep a program may call.
+const ISOLATED_STACK_BYTES: usize = 256 << 20;
+
+/// Why [`run_isolated`] or [`run_limited`] produced no value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum IsolatedRunError {
+    /// The program stopped with a runtime error, as [`run`] reports it.
+    Runtime(String),
+    /// The program was still running when the timeout expired.
+    Timeout(Duration),
+    /// The program executed its step limit's worth of instructions.
+    StepLimit(u64),
+    /// The program's live values grew past the memory limit.
+    MemoryLimit { used: usize, limit: usize },
+    /// The interpreter itself panicked (malformed bytecode); carries the message.
+    Crash(String),
+}
+
+impl std::fmt::Display for IsolatedRunError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IsolatedRunError::Runtime(e) => f.write_str(e),
+            IsolatedRunError::Timeout(t) => write!(f, "timed out after {t:?}"),
+            IsolatedRunError::StepLimit(n) => write!(f, "stopped after the step limit of {n}"),
+            IsolatedRunError::MemoryLimit { used, limit } => {
+                write!(f, "out of memory: {used} bytes of live values exceed the limit of {limit}")
+            }
+            IsolatedRunError::Crash(e) => write!(f, "interpreter crashed: {e}"),
+        }
+    }
+}
+
+/// [`run_isolated_with`] under the default memory limit and `timeout`.
+pub fn run_isolated(
+    bc: &Bytecode,
+    entry: &str,
+    args: &[Value],
+    timeout: Duration,
+) -> Result<Value, IsolatedRunError> {
+    run_isolated_with(bc, entry, args, Limits { time: Some(timeout), ..Limits::default() })
+}
+
+/// [`run_limited`] on a dedicated thread, so that a program that never halts or a
+/// bytecode bug that panics the interpreter cannot take the caller down with it.
+///
+/// Once its time limit is up the run is abandoned and reported as
+/// [`IsolatedRunError::Timeout`]. The interpreter looks at the clock every few
+/// instructions and stops itself, so its thread outlives the call only for as
+/// long as one instruction takes.
+pub fn run_isolated_with(
+    bc: &Bytecode,
+    entry: &str,
+    args: &[Value],
+    limits: Limits,
+) -> Result<Value, IsolatedRunError> {
+    let (bc, entry, args) = (bc.clone(), entry.to_string(), args.to_vec());
+    let (tx, rx) = mpsc::channel();
+    let spawned = std::thread::Builder::new()
+        .name(format!("rv-vm {entry}"))
+        .stack_size(ISOLATED_STACK_BYTES)
+        .spawn(move || {
+            let result = std::panic::catch_unwind(|| run_limited(&bc, &entry, &args, limits));
+            let _ = tx.send(result);
+        });
+    if let Err(e) = spawned {
+        return Err(IsolatedRunError::Crash(format!("could not start the interpreter: {e}")));
+    }
+    let received = match limits.time {
+        Some(timeout) => rx.recv_timeout(timeout),
+        None => rx.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+    };
+    match received {
+        Ok(Ok(result)) => result,
+        Ok(Err(payload)) => {
+            let msg = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "panic with a non-string payload".to_string());
+            Err(IsolatedRunError::Crash(msg))
+        }
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            Err(IsolatedRunError::Timeout(limits.time.unwrap_or_default()))
+        }
+        Err(mpsc::RecvTimeoutError::Disconnected) => {
+            Err(IsolatedRunError::Crash("the interpreter thread exited without a result".into()))
+        }
+    }
+}
+
+/// How many instructions run between two looks at the clock.
+const CLOCK_EVERY: u64 = 1 << 10;
+
+/// How much of its [`Limits`] a run has used.
+struct Meter {
+    limits: Limits,
+    steps: u64,
+    /// Bytes held by live values, as [`Limits::memory`] counts them. Only kept
+    /// when there is a memory limit.
+    used: usize,
+    started: Instant,
+    /// The limit the run ran into, once it has.
+    exceeded: Option<IsolatedRunError>,
 }
 
-/// Execute one function with the given arguments.
-fn exec_fn(bc: &Bytecode, fn_idx: usize, args: &[Value]) -> Result<Value, String> {
+impl Meter {
+    fn new(limits: Limits) -> Meter {
+        Meter { limits, steps: 0, used: 0, started: Instant::now(), exceeded: None }
+    }
+
+    /// Count one instruction.
+    fn step(&mut self) -> Result<(), String> {
+        self.steps += 1;
+        if let Some(n) = self.limits.steps.filter(|&n| self.steps > n) {
+            return self.stop(IsolatedRunError::StepLimit(n));
+        }
+        if self.steps.is_multiple_of(CLOCK_EVERY) {
+            if let Some(t) = self.limits.time.filter(|&t| self.started.elapsed() > t) {
+                return self.stop(IsolatedRunError::Timeout(t));
+            }
+        }
+        Ok(())
+    }
+
+    /// Count `values` as newly live.
+    fn charge(&mut self, values: &[Value]) -> Result<(), String> {
+        let Some(limit) = self.limits.memory else { return Ok(()) };
+        self.used += values.iter().map(value_bytes).sum::<usize>();
+        if self.used > limit {
+            return self.stop(IsolatedRunError::MemoryLimit { used: self.used, limit });
+        }
+        Ok(())
+    }
+
+    /// Count `values` as gone.
+    fn credit(&mut self, values: &[Value]) {
+        if self.limits.memory.is_some() {
+            self.used -= values.iter().map(value_bytes).sum::<usize>();
+        }
+    }
+
+    /// Overwrite the live value `slot` with `value`.
+    fn write(&mut self, value: Value, slot: &mut Value) -> Result<(), String> {
+        let old = std::mem::replace(slot, value);
+        self.credit(std::slice::from_ref(&old));
+        self.charge(std::slice::from_ref(slot))
+    }
+
+    fn stop(&mut self, why: IsolatedRunError) -> Result<(), String> {
+        let msg = why.to_string();
+        self.exceeded = Some(why);
+        Err(msg)
+    }
+}
+
+/// The bytes `v` holds, everything it owns included.
+fn value_bytes(v: &Value) -> usize {
+    let owned = match v {
+        Value::Str(s) => s.len(),
+        Value::Adt { fields, .. } | Value::Closure { captured: fields, .. } => {
+            fields.iter().map(value_bytes).sum()
+        }
+        Value::Ref { path, .. } => std::mem::size_of_val(&path[..]),
+        Value::Int(_) | Value::Float(_) | Value::Bool(_) | Value::Unit => 0,
+    };
+    std::mem::size_of::<Value>() + owned
+}
+
+/// Execute one function with the given arguments, against the run's `store`,
+/// within the run's limits.
+fn exec_fn(
+    bc: &Bytecode,
+    fn_idx: usize,
+    args: &[Value],
+    store: &mut Vec<Value>,
+    meter: &mut Meter,
+) -> Result<Value, String> {
     let f: &CompiledFn = &bc.funcs[fn_idx];
     if args.len() != f.nparams {
         return Err(format!(
@@ -67,25 +314,25 @@ fn exec_fn(bc: &Bytecode, fn_idx: usize, args: &[Value]) -> Result<Value, String
     for (slot, v) in regs.iter_mut().zip(args.iter()) {
         *slot = v.clone();
     }
+    meter.charge(&regs)?;
 
-    // The frame's store ("heap of cells"): addresses produced by `Alloc`/`&x` index
-    // here. Each boxed local owns a cell; a `Value::Ref(addr)` points at one. Cells
-    // live for the duration of the call, so references cannot dangle within a frame.
-    let mut store: Vec<Value> = Vec::new();
-
+    // `store` is the run's "heap of cells": addresses produced by `Alloc`/`&x` index
+    // it. Each boxed local owns a cell; a `Value::Ref` points into one. Cells live
+    // for the whole run, so a reference never dangles, even once its frame returns.
     let mut pc = f.entry_off;
     loop {
+        meter.step()?;
         let instr = f
             .code
             .get(pc)
             .ok_or_else(|| format!("{}: pc {pc} out of bounds", f.name))?;
         match instr {
             Instr::Const(dst, c) => {
-                regs[*dst as usize] = const_to_value(c.clone());
+                meter.write(const_to_value(c.clone()), &mut regs[*dst as usize])?;
                 pc += 1;
             }
             Instr::Move(dst, src) => {
-                regs[*dst as usize] = regs[*src as usize].clone();
+                meter.write(regs[*src as usize].clone(), &mut regs[*dst as usize])?;
                 pc += 1;
             }
             Instr::Print(dst, src) => {
@@ -93,31 +340,86 @@ fn exec_fn(bc: &Bytecode, fn_idx: usize, args: &[Value]) -> Result<Value, String
                     Value::Str(s) => println!("{s}"),
                     other => println!("{other:?}"),
                 }
-                regs[*dst as usize] = Value::Unit;
+                meter.write(Value::Unit, &mut regs[*dst as usize])?;
+                pc += 1;
+            }
+            Instr::ToStr(dst, src) => {
+                let text = match &regs[*src as usize] {
+                    Value::Int(i) => i.to_string(),
+                    Value::Float(f) => f.to_string(),
+                    Value::Bool(b) => b.to_string(),
+                    Value::Str(s) => s.clone(),
+                    other => return Err(format!("cannot render {other:?} as a string")),
+                };
+                meter.write(Value::Str(text), &mut regs[*dst as usize])?;
+                pc += 1;
+            }
+            Instr::Concat(dst, a, b) => {
+                let (Value::Str(a), Value::Str(b)) = (&regs[*a as usize], &regs[*b as usize]) else {
+                    return Err("string concatenation of a non-string".to_string());
+                };
+                meter.write(Value::Str(format!("{a}{b}")), &mut regs[*dst as usize])?;
+                pc += 1;
+            }
+            Instr::FloatBits(dst, src) => {
+                let Value::Float(f) = regs[*src as usize] else {
+                    return Err("float bit pattern of a non-float".to_string());
+                };
+                meter.write(Value::Int(f.to_bits() as i64 as i128), &mut regs[*dst as usize])?;
+                pc += 1;
+            }
+            Instr::AssertCmp(dst, eq, a, b, ty) => {
+                let (a, b) = (&regs[*a as usize], &regs[*b as usize]);
+                if (a == b) != *eq {
+                    let show = ValueRenderer::new(&bc.types);
+                    let op = if *eq { "==" } else { "!=" };
+                    return Err(format!(
+                        "assertion failed: left {op} right\n  left: {}\n right: {}",
+                        show.render(a, ty),
+                        show.render(b, ty)
+                    ));
+                }
+                meter.write(Value::Unit, &mut regs[*dst as usize])?;
                 pc += 1;
             }
             Instr::Bin(dst, op, a, b) => {
                 let va = regs[*a as usize].clone();
                 let vb = regs[*b as usize].clone();
-                regs[*dst as usize] = eval_bin(*op, va, vb)?;
+                meter.write(eval_bin(*op, va, vb)?, &mut regs[*dst as usize])?;
                 pc += 1;
             }
             Instr::Un(dst, op, src) => {
                 let v = regs[*src as usize].clone();
-                regs[*dst as usize] = eval_un(*op, v)?;
+                meter.write(eval_un(*op, v)?, &mut regs[*dst as usize])?;
+                pc += 1;
+            }
+            Instr::Cast(dst, ty, src) => {
+                // Casts are defined on constants; scalar values round-trip through
+                // that single definition so runtime and constant folding agree.
+                let c = match &regs[*src as usize] {
+                    Value::Int(i) => Const::Int(*i),
+                    Value::Float(f) => Const::Float(*f),
+                    Value::Bool(b) => Const::Bool(*b),
+                    other => return Err(format!("cast of non-scalar value: {other:?}")),
+                };
+                let cast = c
+                    .cast(ty)
+                    .ok_or_else(|| format!("invalid cast of {c:?} to {ty:?}"))?;
+                meter.write(const_to_value(cast), &mut regs[*dst as usize])?;
                 pc += 1;
             }
             Instr::Call(dst, callee, arg_regs) => {
                 let call_args: Vec<Value> =
                     arg_regs.iter().map(|r| regs[*r as usize].clone()).collect();
-                let result = exec_fn(bc, *callee, &call_args)?;
-                regs[*dst as usize] = result;
+                let result = exec_fn(bc, *callee, &call_args, store, meter)?;
+                meter.write(result, &mut regs[*dst as usize])?;
                 pc += 1;
             }
             Instr::MakeClosure(dst, fn_idx, capture_regs) => {
                 let captured: Vec<Value> =
                     capture_regs.iter().map(|r| regs[*r as usize].clone()).collect();
-                regs[*dst as usize] = Value::Closure { fn_idx: *fn_idx, captured };
+                let closure = Value::Closure { fn_idx: *fn_idx, captured };
+                meter.write(closure, &mut regs[*dst as usize])?;
                 pc += 1;
             }
             Instr::CallClosure(dst, closure_reg, arg_regs) => {
@@ -130,8 +432,8 @@ fn exec_fn(bc: &Bytecode, fn_idx: usize, args: &[Value]) -> Result<Value, String
                     }
                 };
                 call_args.extend(arg_regs.iter().map(|r| regs[*r as usize].clone()));
-                let result = exec_fn(bc, fn_idx, &call_args)?;
-                regs[*dst as usize] = result;
+                let result = exec_fn(bc, fn_idx, &call_args, store, meter)?;
+                meter.write(result, &mut regs[*dst as usize])?;
                 pc += 1;
             }
             Instr::Jump(off) => {
@@ -142,25 +444,27 @@ fn exec_fn(bc: &Bytecode, fn_idx: usize, args: &[Value]) -> Result<Value, String
                 Value::Bool(false) => pc = *else_off,
                 other => return Err(format!("branch on non-bool: {other:?}")),
             },
+            Instr::BranchTag(src, tag, then_off, else_off) => {
+                let actual = variant_tag(&regs[*src as usize])?;
+                pc = if actual == *tag { *then_off } else { *else_off };
+            }
             Instr::MakeAdt(dst, tag, field_regs) => {
                 // Collect the field registers into an owned aggregate value.
                 let fields: Vec<Value> =
                     field_regs.iter().map(|r| regs[*r as usize].clone()).collect();
-                regs[*dst as usize] = Value::Adt { tag: *tag, fields };
+                meter.write(Value::Adt { tag: *tag, fields }, &mut regs[*dst as usize])?;
                 pc += 1;
             }
             Instr::Field(dst, src, field) => {
                 // Project one field out of an Adt value.
-                let v = match &regs[*src as usize] {
-                    Value::Adt { fields, .. } => fields
-                        .get(*field as usize)
-                        .cloned()
-                        .ok_or_else(|| format!("field index {field} out of range"))?,
-                    other => {
-                        return Err(format!("field projection on non-Adt: {other:?}"));
-                    }
-                };
-                regs[*dst as usize] = v;
+                let v = field_of(&regs[*src as usize], *field)?.clone();
+                meter.write(v, &mut regs[*dst as usize])?;
+                pc += 1;
+            }
+            Instr::SetField(base, field, val) => {
+                // Write `val` into one field of the Adt in `base`, in place.
+                let v = regs[*val as usize].clone();
+                meter.write(v, field_of_mut(&mut regs[*base as usize], *field)?)?;
                 pc += 1;
             }
             Instr::IndexGet(dst, base, idx) => {
@@ -182,7 +486,7 @@ fn exec_fn(bc: &Bytecode, fn_idx: usize, args: &[Value]) -> Result<Value, String
                         return Err(format!("index projection on non-Adt: {other:?}"));
                     }
                 };
-                regs[*dst as usize] = v;
+                meter.write(v, &mut regs[*dst as usize])?;
                 pc += 1;
             }
             Instr::IndexSet(base, idx, val) => {
@@ -199,7 +503,7 @@ fn exec_fn(bc: &Bytecode, fn_idx: usize, args: &[Value]) -> Result<Value, String
                         let cell = fields
                             .get_mut(i)
                             .ok_or_else(|| format!("index {i} out of range"))?;
-                        *cell = v;
+                        meter.write(v, cell)?;
                     }
                     other => {
                         return Err(format!("indexed store into non-Adt: {other:?}"));
@@ -215,7 +519,7 @@ fn exec_fn(bc: &Bytecode, fn_idx: usize, args: &[Value]) -> Result<Value, String
                         return Err(format!("VecLen on non-Adt: {other:?}"));
                     }
                 };
-                regs[*dst as usize] = Value::Int(n);
+                meter.write(Value::Int(n), &mut regs[*dst as usize])?;
                 pc += 1;
             }
             Instr::VecPush(dst, vec_reg, val) => {
@@ -233,17 +537,12 @@ fn exec_fn(bc: &Bytecode, fn_idx: usize, args: &[Value]) -> Result<Value, String
                         return Err(format!("VecPush on non-Adt: {other:?}"));
                     }
                 };
-                regs[*dst as usize] = new_val;
+                meter.write(new_val, &mut regs[*dst as usize])?;
                 pc += 1;
             }
             Instr::Switch(src, table, otherwise) => {
                 // Read the scrutinee's tag and jump to the matching arm.
-                let tag = match &regs[*src as usize] {
-                    Value::Adt { tag, .. } => *tag,
-                    other => {
-                        return Err(format!("match on non-Adt scrutinee: {other:?}"));
-                    }
-                };
+                let tag = variant_tag(&regs[*src as usize])?;
                 match table.iter().find(|(t, _)| *t == tag) {
                     Some((_, off)) => pc = *off,
                     None => match otherwise {
@@ -255,51 +554,97 @@ fn exec_fn(bc: &Bytecode, fn_idx: usize, args: &[Value]) -> Result<Value, String
             Instr::Alloc(local) => {
                 // Box the local: move its current value into a fresh store cell and
                 // overwrite the register with a `Ref` to that cell.
+                // The value moves, so only the new `Ref` costs anything.
                 let addr = store.len();
-                let v = std::mem::replace(&mut regs[*local as usize], Value::Ref(addr));
+                let r = Value::Ref { addr, path: Vec::new() };
+                meter.charge(std::slice::from_ref(&r))?;
+                let v = std::mem::replace(&mut regs[*local as usize], r);
                 store.push(v);
                 pc += 1;
             }
             Instr::Load(dst, src) => {
-                // `src` holds a `Ref(addr)`; copy the cell's value into `dst`.
-                let addr = as_ref(&regs[*src as usize])?;
-                let v = store
+                // `src` holds a `Ref`; copy its referent into `dst`.
+                let (addr, path) = as_ref(&regs[*src as usize])?;
+                let mut v = store
                     .get(addr)
-                    .ok_or_else(|| format!("load: bad store address {addr}"))?
-                    .clone();
-                regs[*dst as usize] = v;
+                    .ok_or_else(|| format!("load: bad store address {addr}"))?;
+                for &field in path {
+                    v = field_of(v, field)?;
+                }
+                meter.write(v.clone(), &mut regs[*dst as usize])?;
                 pc += 1;
             }
             Instr::Store(ref_reg, val) => {
-                // `ref_reg` holds a `Ref(addr)`; write `val` into that cell so the
+                // `ref_reg` holds a `Ref`; write `val` over its referent so the
                 // mutation is visible at the original (boxed) location.
-                let addr = as_ref(&regs[*ref_reg as usize])?;
+                let (addr, path) = as_ref(&regs[*ref_reg as usize])?;
                 let v = regs[*val as usize].clone();
-                let cell = store
+                let mut cell = store
                     .get_mut(addr)
                     .ok_or_else(|| format!("store: bad store address {addr}"))?;
-                *cell = v;
+                for &field in path {
+                    cell = field_of_mut(cell, field)?;
+                }
+                meter.write(v, cell)?;
+                pc += 1;
+            }
+            Instr::FieldRef(dst, src, field) => {
+                // Narrow the reference in `src` to one field of its referent.
+                let (addr, path) = as_ref(&regs[*src as usize])?;
+                let path = path.iter().copied().chain([*field]).collect();
+                meter.write(Value::Ref { addr, path }, &mut regs[*dst as usize])?;
                 pc += 1;
             }
             Instr::Trap(msg) => {
                 return Err(msg.clone());
             }
             Instr::Ret(src) => {
+                meter.credit(&regs);
                 return Ok(regs[*src as usize].clone());
             }
         }
     }
 }
 
-/// Read a reference's store address, or error if the value is not a `Ref`
-/// (e.g. dereferencing a non-reference).
-fn as_ref(v: &Value) -> Result<usize, String> {
+/// Read a reference's store address and field path, or error if the value is not
+/// a `Ref` (e.g. dereferencing a non-reference).
+fn as_ref(v: &Value) -> Result<(usize, &[u32]), String> {
     match v {
-        Value::Ref(addr) => Ok(*addr),
+        Value::Ref { addr, path } => Ok((*addr, path)),
         other => Err(format!("expected a reference, got {other:?}")),
     }
 }
 
+/// The variant index a `match` dispatches on: an `Adt`'s tag, or a flag enum's
+/// boolean (`false` is variant 0).
+fn variant_tag(v: &Value) -> Result<u32, String> {
+    match v {
+        Value::Adt { tag, .. } => Ok(*tag),
+        Value::Bool(b) => Ok(u32::from(*b)),
+        other => Err(format!("match on non-Adt scrutinee: {other:?}")),
+    }
+}
+
+/// Field `field` of the `Adt` value `v`.
+fn field_of(v: &Value, field: u32) -> Result<&Value, String> {
+    match v {
+        Value::Adt { fields, .. } => {
+            fields.get(field as usize).ok_or_else(|| format!("field index {field} out of range"))
+        }
+        other => Err(format!("field projection on non-Adt: {other:?}")),
+    }
+}
+
+/// Field `field` of the `Adt` value `v`, to write through.
+fn field_of_mut(v: &mut Value, field: u32) -> Result<&mut Value, String> {
+    match v {
+        Value::Adt { fields, .. } => fields
+            .get_mut(field as usize)
+            .ok_or_else(|| format!("field index {field} out of range")),
+        other => Err(format!("field store into non-Adt: {other:?}")),
+    }
+}
+
 fn const_to_value(c: Const) -> Value {
     match c {
         Const::Int(i) => Value::Int(i),
@@ -314,6 +659,9 @@ fn const_to_value(c: Const) -> Value {
 fn eval_bin(op: BinOp, a: Value, b: Value) -> Result<Value, String> {
     use BinOp::*;
     // Float arithmetic/comparison: when either operand is a float, compute in f64.
+    // Comparisons are IEEE 754, the one float semantics of the language: every
+    // comparison with NaN is false (`!=` true), `-0.0 == 0.0`, and infinities order
+    // as the extremes. (A float `match` arm compares bit patterns instead.)
     if matches!(a, Value::Float(_)) || matches!(b, Value::Float(_)) {
         let (x, y) = (as_float(&a)?, as_float(&b)?);
         return Ok(match op {
@@ -416,19 +764,19 @@ fn as_float(v: &Value) -> Result<f64, String> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rv_codegen::compile;
-    use rv_core::{BinOp, Prop, Symbols};
+    use rv_codegen::{compile, compile_with, CodegenOptions};
+    use rv_core::{BinOp, Prop, Symbols, UnOp};
     use rv_ir::{
         AggKind, Block, BlockId, BorrowKind, Const, FieldDef, Function, LocalDecl, LocalId,
-        Lowerable, MatchArm, Operand, Place, Proj, Program, RValue, Stmt, Terminator, TypeDef,
+        Lowerable, Operand, Place, Proj, Program, RValue, Stmt, Terminator, TypeDef,
     };
 
     /// Helper: an Int-typed local decl.
     fn int_local() -> LocalDecl<Lowerable> {
-        LocalDecl { name: None, ty: rv_core::Ty::Int }
+        LocalDecl { name: None, mutable: false, ty: rv_core::Ty::Int }
     }
     fn bool_local() -> LocalDecl<Lowerable> {
-        LocalDecl { name: None, ty: rv_core::Ty::Bool }
+        LocalDecl { name: None, mutable: false, ty: rv_core::Ty::Bool }
     }
 
     fn copy(l: u32) -> Operand {
@@ -515,6 +863,135 @@ mod tests {
         assert_eq!(run(&bc, "main", &[]), Err("division by zero".to_string()));
     }
 
+    /// Compile `f(l0, l1) = rvalue` over `i64` locals.
+    fn binary_fn(rvalue: RValue) -> Bytecode {
+        binary_fn_with(rvalue, &CodegenOptions::default())
+    }
+
+    fn binary_fn_with(rvalue: RValue, opts: &CodegenOptions) -> Bytecode {
+        let mut syms = Symbols::new();
+        let name = syms.intern("f");
+        let func = Function::<Lowerable> {
+            type_params: vec![],
+            generic_bounds: vec![],
+            name,
+            params: vec![LocalId(0), LocalId(1)],
+            ret: rv_core::Ty::Int,
+            pre: Prop::True,
+            post: Prop::True,
+            locals: vec![int_local(), int_local(), int_local()],
+            blocks: vec![Block {
+                id: BlockId(0),
+                stmts: vec![Stmt::Assign(Place::local(LocalId(2)), rvalue)],
+                term: Terminator::Return(copy(2)),
+            }],
+            entry: BlockId(0),
+        };
+        let prog = Program { types: vec![], trait_impls: vec![], funcs: vec![func] };
+        compile_with(&prog, &syms, opts)
+    }
+
+    fn call2(bc: &Bytecode, a: i64, b: i64) -> Result<Value, String> {
+        run(bc, "f", &[Value::Int(a as i128), Value::Int(b as i128)])
+    }
+
+    /// Differential check of the `i64` operators that cannot fail: the VM agrees
+    /// with Rust's own `i64` semantics on a table of edge cases, including shift
+    /// amounts at and past the width (taken modulo 64) and wrapping arithmetic at
+    /// the extremes.
+    #[test]
+    fn i64_operators_agree_with_native_semantics() {
+        type Native = fn(i64, i64) -> i64;
+        let ops: [(RValue, Native); 8] = [
+            (RValue::Bin(BinOp::Shl, copy(0), copy(1)), |a, b| a.wrapping_shl(b as u32)),
+            (RValue::Bin(BinOp::Shr, copy(0), copy(1)), |a, b| a.wrapping_shr(b as u32)),
+            (RValue::Bin(BinOp::BitAnd, copy(0), copy(1)), |a, b| a & b),
+            (RValue::Bin(BinOp::BitOr, copy(0), copy(1)), |a, b| a | b),
+            (RValue::Bin(BinOp::BitXor, copy(0), copy(1)), |a, b| a ^ b),
+            (RValue::WrappingBin(BinOp::Add, copy(0), copy(1)), i64::wrapping_add),
+            (RValue::WrappingBin(BinOp::Sub, copy(0), copy(1)), i64::wrapping_sub),
+            (RValue::WrappingBin(BinOp::Mul, copy(0), copy(1)), i64::wrapping_mul),
+        ];
+        let operands = [0, 1, -1, 2, 7, 63, 64, 65, 127, 128, -64, -65, i64::MIN, i64::MAX];
+        for (rvalue, native) in ops {
+            let bc = binary_fn(rvalue.clone());
+            for a in operands {
+                for b in operands {
+                    let want = Value::Int(native(a, b) as i128);
+                    assert_eq!(call2(&bc, a, b), Ok(want), "{rvalue:?} on ({a}, {b})");
+                }
+            }
+        }
+    }
+
+    /// A literal shift amount is reduced modulo 64 at compile time, with the same
+    /// result as a runtime one.
+    #[test]
+    fn literal_shift_amounts_wrap_like_runtime_ones() {
+        for amount in [63, 64, 65, -1] {
+            let bc = binary_fn(RValue::Bin(BinOp::Shl, copy(0), Operand::Const(Const::Int(amount))));
+            assert_eq!(call2(&bc, 1, 0), Ok(Value::Int(1i64.wrapping_shl(amount as u32) as i128)));
+        }
+    }
+
+    /// Checked division and negation overflow only at `i64::MIN`; both trap there
+    /// rather than produce a value outside the `i64` range. `MIN % -1` is `0`.
+    #[test]
+    fn signed_division_and_negation_overflow_trap() {
+        let div = binary_fn(RValue::Bin(BinOp::Div, copy(0), copy(1)));
+        assert_eq!(call2(&div, i64::MIN, -1), Err("attempt to divide with overflow".to_string()));
+        assert_eq!(call2(&div, i64::MIN, 1), Ok(Value::Int(i64::MIN as i128)));
+        assert_eq!(call2(&div, i64::MAX, -1), Ok(Value::Int(-(i64::MAX as i128))));
+        let rem = binary_fn(RValue::Bin(BinOp::Mod, copy(0), copy(1)));
+        assert_eq!(call2(&rem, i64::MIN, -1), Ok(Value::Int(0)));
+
+        let neg = binary_fn(RValue::Un(UnOp::Neg, copy(0)));
+        assert_eq!(call2(&neg, i64::MIN, 0), Err("attempt to negate with overflow".to_string()));
+        assert_eq!(call2(&neg, i64::MAX, 0), Ok(Value::Int(-(i64::MAX as i128))));
+    }
+
+    /// Checked `+`, `-`, `*` and negation trap on overflow when overflow checks
+    /// are on and wrap like Rust's `wrapping_*` when they are off; in range, both
+    /// modes agree.
+    #[test]
+    fn checked_arithmetic_traps_or_wraps_by_build_mode() {
+        type Native = fn(i64, i64) -> Option<i64>;
+        let ops: [(RValue, Native, &str); 4] = [
+            (RValue::Bin(BinOp::Add, copy(0), copy(1)), i64::checked_add, "add"),
+            (RValue::Bin(BinOp::Sub, copy(0), copy(1)), i64::checked_sub, "subtract"),
+            (RValue::Bin(BinOp::Mul, copy(0), copy(1)), i64::checked_mul, "multiply"),
+            (RValue::Un(UnOp::Neg, copy(0)), |a, _| a.checked_neg(), "negate"),
+        ];
+        let operands = [0, 1, -1, 2, -2, i64::MIN, i64::MIN + 1, i64::MAX, i64::MAX - 1];
+        for (rvalue, native, verb) in ops {
+            let debug = binary_fn(rvalue.clone());
+            let release = binary_fn_with(rvalue.clone(), &CodegenOptions::release());
+            for a in operands {
+                for b in operands {
+                    let (wrapped, checked) = match native(a, b) {
+                        Some(r) => (r, Ok(Value::Int(r as i128))),
+                        None => {
+                            let wrapped = match verb {
+                                "add" => a.wrapping_add(b),
+                                "subtract" => a.wrapping_sub(b),
+                                "multiply" => a.wrapping_mul(b),
+                                _ => a.wrapping_neg(),
+                            };
+                            (wrapped, Err(format!("attempt to {verb} with overflow")))
+                        }
+                    };
+                    assert_eq!(call2(&debug, a, b), checked, "{rvalue:?} on ({a}, {b})");
+                    let want = Ok(Value::Int(wrapped as i128));
+                    assert_eq!(call2(&release, a, b), want, "{rvalue:?} on ({a}, {b})");
+                }
+            }
+        }
+        // `MIN / -1` traps in release builds too.
+        let div = RValue::Bin(BinOp::Div, copy(0), copy(1));
+        let div = binary_fn_with(div, &CodegenOptions::release());
+        assert_eq!(call2(&div, i64::MIN, -1), Err("attempt to divide with overflow".to_string()));
+    }
+
     /// `abs(x)`: if x < 0 return -x else return x. Tests branch + neg.
     #[test]
     fn branch_if() {
@@ -669,133 +1146,90 @@ mod tests {
         assert_eq!(run(&bc, "main", &[]).unwrap(), Value::Bool(true));
     }
 
+    /// Parse a program written in the IR text format (see `rv_ir::text`).
+    fn program(text: &str, syms: &mut Symbols) -> Program<Lowerable> {
+        rv_ir::text::parse(text, syms).unwrap_or_else(|e| panic!("{e}"))
+    }
+
     /// Construct a struct `Point { x, y }` from two ints, then read field `0`
     /// (x) back out and return it. Exercises `MakeAdt` + `Field` projection.
     #[test]
     fn struct_construct_and_field() {
         let mut syms = Symbols::new();
-        let main = syms.intern("main");
-        let point = syms.intern("Point");
-
-        // locals: l0 = the Point value, l1 = the extracted field
-        let func = Function::<Lowerable> {
-            type_params: vec![],
-            generic_bounds: vec![],
-            name: main,
-            params: vec![],
-            ret: rv_core::Ty::Int,
-            pre: Prop::True,
-            post: Prop::True,
-            locals: vec![
-                LocalDecl { name: None, ty: rv_core::Ty::Adt(point) }, // l0: Point
-                int_local(),                                          // l1: x
-            ],
-            blocks: vec![Block {
-                id: BlockId(0),
-                stmts: vec![
-                    // l0 = Point { 3, 4 }  (struct tag is 0)
-                    Stmt::Assign(
-                        Place::local(LocalId(0)),
-                        RValue::Aggregate(
-                            AggKind::Struct(point),
-                            vec![
-                                Operand::Const(Const::Int(3)),
-                                Operand::Const(Const::Int(4)),
-                            ],
-                        ),
-                    ),
-                    // l1 = l0.0   (read field 0 via a Field projection)
-                    Stmt::Assign(
-                        Place::local(LocalId(1)),
-                        RValue::Use(Operand::Copy(Place {
-                            local: LocalId(0),
-                            proj: vec![Proj::Field(0)],
-                        })),
-                    ),
-                ],
-                term: Terminator::Return(copy(1)),
-            }],
-            entry: BlockId(0),
-        };
-
-        let prog = Program { types: vec![], trait_impls: vec![], funcs: vec![func] };
+        let prog = program(
+            "
+            fn main() -> i64 {
+                let _0: Point;
+                let _1: i64;
+                bb0: {
+                    _0 = struct Point(3, 4); // struct tag is 0
+                    _1 = _0.0;
+                    return _1;
+                }
+            }",
+            &mut syms,
+        );
         let bc = compile(&prog, &syms);
         assert_eq!(run(&bc, "main", &[]).unwrap(), Value::Int(3));
     }
 
     /// Build an `Option`-like enum value `Some(7)` (variant index 1), `match` on
     /// it, and return the payload in the `Some` arm (7) or a sentinel in `None`.
-    /// Exercises `MakeAdt` (tagged), `Switch`, and `Downcast`+`Field` binding.
+    /// Exercises `MakeAdt` (tagged), the two-target `BranchTag`, and
+    /// `Downcast`+`Field` binding.
     #[test]
     fn enum_match_some() {
         let mut syms = Symbols::new();
-        let main = syms.intern("main");
-        let option = syms.intern("Option");
-
-        // Variant indices: None = 0, Some = 1.
-        // locals: l0 = the Option value, l1 = the result.
-        let func = Function::<Lowerable> {
-            type_params: vec![],
-            generic_bounds: vec![],
-            name: main,
-            params: vec![],
-            ret: rv_core::Ty::Int,
-            pre: Prop::True,
-            post: Prop::True,
-            locals: vec![
-                LocalDecl { name: None, ty: rv_core::Ty::Adt(option) }, // l0: Option
-                int_local(),                                           // l1: result
-            ],
-            blocks: vec![
-                // b0: l0 = Some(7); match l0 { None => b1, Some => b2 }
-                Block {
-                    id: BlockId(0),
-                    stmts: vec![Stmt::Assign(
-                        Place::local(LocalId(0)),
-                        RValue::Aggregate(
-                            AggKind::Variant(option, 1),
-                            vec![Operand::Const(Const::Int(7))],
-                        ),
-                    )],
-                    term: Terminator::Match {
-                        scrutinee: copy(0),
-                        arms: vec![
-                            MatchArm { variant: 0, target: BlockId(1) },
-                            MatchArm { variant: 1, target: BlockId(2) },
-                        ],
-                        otherwise: None,
-                    },
-                },
-                // b1 (None): l1 = -1; return l1
-                Block {
-                    id: BlockId(1),
-                    stmts: vec![Stmt::Assign(
-                        Place::local(LocalId(1)),
-                        RValue::Use(Operand::Const(Const::Int(-1))),
-                    )],
-                    term: Terminator::Return(copy(1)),
-                },
-                // b2 (Some): l1 = l0 downcast-to-Some .field(0); return l1
-                Block {
-                    id: BlockId(2),
-                    stmts: vec![Stmt::Assign(
-                        Place::local(LocalId(1)),
-                        RValue::Use(Operand::Copy(Place {
-                            local: LocalId(0),
-                            proj: vec![Proj::Downcast(1), Proj::Field(0)],
-                        })),
-                    )],
-                    term: Terminator::Return(copy(1)),
-                },
-            ],
-            entry: BlockId(0),
-        };
-
-        let prog = Program { types: vec![], trait_impls: vec![], funcs: vec![func] };
+        let prog = program(
+            "
+            fn main() -> i64 {
+                let _0: Option;
+                let _1: i64;
+                bb0: {
+                    _0 = variant Option#1(7);
+                    match _0 { 0 => bb1, 1 => bb2 };
+                }
+                bb1: {
+                    _1 = -1;
+                    return _1;
+                }
+                bb2: {
+                    _1 = _0@1.0;
+                    return _1;
+                }
+            }",
+            &mut syms,
+        );
         let bc = compile(&prog, &syms);
         assert_eq!(run(&bc, "main", &[]).unwrap(), Value::Int(7));
     }
 
+    /// A one-arm `match` with an `otherwise` fallback takes the fallback when the
+    /// tag misses: `match Some(7) { None => 0, _ => 1 }` returns `1`.
+    #[test]
+    fn enum_match_otherwise_taken() {
+        let mut syms = Symbols::new();
+        let prog = program(
+            "
+            fn main() -> i64 {
+                let _0: Option;
+                bb0: {
+                    _0 = variant Option#1(7);
+                    match _0 { 0 => bb1, _ => bb2 };
+                }
+                bb1: {
+                    return 0;
+                }
+                bb2: {
+                    return 1;
+                }
+            }",
+            &mut syms,
+        );
+        let bc = compile(&prog, &syms);
+        assert_eq!(run(&bc, "main", &[]).unwrap(), Value::Int(1));
+    }
+
     // --- Reference tests ---
 
     fn deref(l: u32) -> Place {
@@ -905,7 +1339,7 @@ mod tests {
             pre: Prop::True,
             post: Prop::True,
             locals: vec![
-                LocalDecl { name: None, ty: rv_core::Ty::Adt(point) },
+                LocalDecl { name: None, mutable: false, ty: rv_core::Ty::Adt(point, vec![]) },
                 int_local(),
                 int_local(),
             ],
@@ -963,7 +1397,7 @@ mod tests {
             pre: Prop::True,
             post: Prop::True,
             locals: vec![
-                LocalDecl { name: None, ty: rv_core::Ty::Adt(point) },
+                LocalDecl { name: None, mutable: false, ty: rv_core::Ty::Adt(point, vec![]) },
                 int_local(),
                 int_local(),
             ],
@@ -1013,13 +1447,16 @@ mod tests {
         assert_eq!(run(&bc, "main", &[]).unwrap(), Value::Int(9));
     }
 
-    /// Borrowing a sub-place is unsupported in this slice and traps at runtime.
+    /// A field borrow reaches into the borrowed local's cell, and a field store
+    /// updates the local in place:
+    /// `r = &mut p.0; *r = 9; p.1 = 7; return p.0 + p.1` -> `Int(16)`.
     #[test]
-    fn borrow_subplace_traps() {
+    fn borrow_and_store_of_a_field() {
         let mut syms = Symbols::new();
         let main = syms.intern("main");
         let point = syms.intern("Point");
-        // r = &p.0  (a field sub-borrow) -> should trap
+        let field = |n| Operand::Copy(Place { local: LocalId(0), proj: vec![Proj::Field(n)] });
+        // locals: l0 = p, l1 = r, l2 = the sum
         let func = Function::<Lowerable> {
             type_params: vec![],
             generic_bounds: vec![],
@@ -1029,7 +1466,8 @@ mod tests {
             pre: Prop::True,
             post: Prop::True,
             locals: vec![
-                LocalDecl { name: None, ty: rv_core::Ty::Adt(point) },
+                LocalDecl { name: None, mutable: true, ty: rv_core::Ty::Adt(point, vec![]) },
+                int_local(),
                 int_local(),
             ],
             blocks: vec![Block {
@@ -1048,18 +1486,30 @@ mod tests {
                     Stmt::Assign(
                         Place::local(LocalId(1)),
                         RValue::Ref(
-                            BorrowKind::Shared,
+                            BorrowKind::Mut,
                             Place { local: LocalId(0), proj: vec![Proj::Field(0)] },
                         ),
                     ),
+                    Stmt::Assign(
+                        Place { local: LocalId(1), proj: vec![Proj::Deref] },
+                        RValue::Use(Operand::Const(Const::Int(9))),
+                    ),
+                    Stmt::Assign(
+                        Place { local: LocalId(0), proj: vec![Proj::Field(1)] },
+                        RValue::Use(Operand::Const(Const::Int(7))),
+                    ),
+                    Stmt::Assign(
+                        Place::local(LocalId(2)),
+                        RValue::Bin(BinOp::Add, field(0), field(1)),
+                    ),
                 ],
-                term: Terminator::Return(copy(1)),
+                term: Terminator::Return(copy(2)),
             }],
             entry: BlockId(0),
         };
         let prog = Program { types: vec![], trait_impls: vec![], funcs: vec![func] };
         let bc = compile(&prog, &syms);
-        assert!(run(&bc, "main", &[]).is_err());
+        assert_eq!(run(&bc, "main", &[]).unwrap(), Value::Int(16));
     }
 
     /// A `main` whose body is just `Terminator::Panic` aborts cleanly: the VM
@@ -1203,7 +1653,8 @@ mod tests {
             pre: Prop::True,
             post: Prop::True,
             locals: vec![
-                LocalDecl { name: None, ty: rv_core::Ty::Adt(point) }, // l0: self
+                // l0: self
+                LocalDecl { name: None, mutable: false, ty: rv_core::Ty::Adt(point, vec![]) },
                 int_local(),                                          // l1: sum
             ],
             blocks: vec![Block {
@@ -1240,7 +1691,8 @@ mod tests {
             pre: Prop::True,
             post: Prop::True,
             locals: vec![
-                LocalDecl { name: None, ty: rv_core::Ty::Adt(point) }, // l0: the Point
+                // l0: the Point
+                LocalDecl { name: None, mutable: false, ty: rv_core::Ty::Adt(point, vec![]) },
                 int_local(),                                          // l1: result
             ],
             blocks: vec![Block {
@@ -1302,7 +1754,7 @@ mod tests {
             pre: Prop::True,
             post: Prop::True,
             locals: vec![
-                LocalDecl { name: None, ty: arr_ty }, // l0: [Int; 3]
+                LocalDecl { name: None, mutable: false, ty: arr_ty }, // l0: [Int; 3]
                 int_local(),                          // l1: read-back element
             ],
             blocks: vec![Block {
@@ -1364,7 +1816,7 @@ mod tests {
             pre: Prop::True,
             post: Prop::True,
             locals: vec![
-                LocalDecl { name: None, ty: tup_ty }, // l0: (Int, Int)
+                LocalDecl { name: None, mutable: false, ty: tup_ty }, // l0: (Int, Int)
                 int_local(),                          // l1: extracted element
             ],
             blocks: vec![Block {
@@ -1408,7 +1860,7 @@ mod tests {
     /// `which` selects what the built `main` returns, so one body can be exercised
     /// for each of the three observations (len, `v[1]`, `v[0]` after `IndexSet`).
     fn vec_main(name: rv_core::Sym, vec_sym: rv_core::Sym, ret: Terminator<Lowerable>) -> Function<Lowerable> {
-        let vec_ty = rv_core::Ty::Adt(vec_sym);
+        let vec_ty = rv_core::Ty::Adt(vec_sym, vec![]);
         // locals: l0 = the vec, l1 = len, l2 = read-back element.
         Function::<Lowerable> {
             type_params: vec![],
@@ -1419,7 +1871,7 @@ mod tests {
             pre: Prop::True,
             post: Prop::True,
             locals: vec![
-                LocalDecl { name: None, ty: vec_ty }, // l0: Vec
+                LocalDecl { name: None, mutable: false, ty: vec_ty }, // l0: Vec
                 int_local(),                          // l1: len
                 int_local(),                          // l2: read-back element
             ],
@@ -1499,7 +1951,7 @@ mod tests {
     fn vec_index_get_after_push() {
         let mut syms = Symbols::new();
         let main = syms.intern("main");
-        let vec_ty = rv_core::Ty::Adt(syms.intern("Vec"));
+        let vec_ty = rv_core::Ty::Adt(syms.intern("Vec"), vec![]);
         let func = Function::<Lowerable> {
             type_params: vec![],
             generic_bounds: vec![],
@@ -1509,7 +1961,7 @@ mod tests {
             pre: Prop::True,
             post: Prop::True,
             locals: vec![
-                LocalDecl { name: None, ty: vec_ty }, // l0: Vec
+                LocalDecl { name: None, mutable: false, ty: vec_ty }, // l0: Vec
                 int_local(),                          // l1: element
             ],
             blocks: vec![Block {
@@ -1590,7 +2042,7 @@ mod tests {
             post: Prop::True,
             locals: vec![
                 int_local(),                                                 // l0: base
-                LocalDecl { name: None, ty: rv_core::Ty::Fn(vec![], Box::new(rv_core::Ty::Int)) }, // l1: closure
+                LocalDecl { name: None, mutable: false, ty: rv_core::Ty::Fn(vec![], Box::new(rv_core::Ty::Int)) }, // l1: closure
                 int_local(),                                                 // l2: result
             ],
             blocks: vec![Block {
@@ -1621,4 +2073,145 @@ mod tests {
         let bc = compile(&prog, &syms);
         assert_eq!(run(&bc, "main", &[]).unwrap(), Value::Int(15));
     }
+
+    /// Arguments bind through the explicit parameter list, not by position:
+    /// `sub(a, b) = a - b` with `a` stored in local 2 and `b` in local 0.
+    #[test]
+    fn params_bind_to_their_listed_locals() {
+        let mut syms = Symbols::new();
+        let name = syms.intern("sub");
+        // l0 = b, l1 = diff, l2 = a
+        let func = Function::<Lowerable> {
+            type_params: vec![],
+            generic_bounds: vec![],
+            name,
+            params: vec![LocalId(2), LocalId(0)],
+            ret: rv_core::Ty::Int,
+            pre: Prop::True,
+            post: Prop::True,
+            locals: vec![int_local(), int_local(), int_local()],
+            blocks: vec![Block {
+                id: BlockId(0),
+                stmts: vec![Stmt::Assign(
+                    Place::local(LocalId(1)),
+                    RValue::Bin(BinOp::Sub, copy(2), copy(0)),
+                )],
+                term: Terminator::Return(copy(1)),
+            }],
+            entry: BlockId(0),
+        };
+        let prog = Program { types: vec![], trait_impls: vec![], funcs: vec![func] };
+        let bc = compile(&prog, &syms);
+        assert_eq!(run(&bc, "sub", &[Value::Int(10), Value::Int(3)]).unwrap(), Value::Int(7));
+    }
+
+    /// A one-function program `main` with `nregs` registers running `code`.
+    fn raw_program(nregs: usize, code: Vec<Instr>) -> Bytecode {
+        let main = rv_codegen::CompiledFn {
+            name: "main".to_string(),
+            nparams: 0,
+            nregs,
+            code,
+            entry_off: 0,
+        };
+        Bytecode { funcs: vec![main], types: Vec::new() }
+    }
+
+    /// The isolated runner returns values and runtime errors as `run` does, and turns
+    /// a program that never halts or an interpreter panic into an error the caller
+    /// survives.
+    #[test]
+    fn isolated_runs_report_timeouts_and_crashes() {
+        let timeout = Duration::from_secs(5);
+        let value = raw_program(1, vec![Instr::Const(0, Const::Int(3)), Instr::Ret(0)]);
+        assert_eq!(run_isolated(&value, "main", &[], timeout), Ok(Value::Int(3)));
+        assert_eq!(
+            run_isolated(&value, "nope", &[], timeout),
+            Err(IsolatedRunError::Runtime("no such function: nope".to_string()))
+        );
+
+        let spin = raw_program(0, vec![Instr::Jump(0)]);
+        let short = Duration::from_millis(100);
+        let started = std::time::Instant::now();
+        assert_eq!(run_isolated(&spin, "main", &[], short), Err(IsolatedRunError::Timeout(short)));
+        assert!(started.elapsed() < timeout, "the timeout bounds the wait");
+
+        // Register 7 does not exist in a one-register frame.
+        let broken = raw_program(1, vec![Instr::Move(0, 7), Instr::Ret(0)]);
+        let Err(IsolatedRunError::Crash(msg)) = run_isolated(&broken, "main", &[], timeout) else {
+            panic!("an interpreter panic is reported as a crash");
+        };
+        assert!(msg.contains("index out of bounds"), "{msg}");
+    }
+
+    #[test]
+    fn limited_runs_stop_at_their_step_and_memory_limits() {
+        // `s = s + s` forever: the string doubles every other instruction.
+        let doubling = raw_program(
+            1,
+            vec![Instr::Const(0, Const::Str("ab".into())), Instr::Concat(0, 0, 0), Instr::Jump(1)],
+        );
+        let limits = Limits { memory: Some(1 << 20), ..Limits::NONE };
+        let Err(IsolatedRunError::MemoryLimit { used, limit }) =
+            run_limited(&doubling, "main", &[], limits)
+        else {
+            panic!("the doubling string runs out of memory");
+        };
+        assert_eq!(limit, 1 << 20);
+        assert!(used > limit && used < 3 << 20, "stopped at the first string too big: {used}");
+
+        let spin = raw_program(0, vec![Instr::Jump(0)]);
+        let limits = Limits { steps: Some(1000), ..Limits::NONE };
+        let err = run_limited(&spin, "main", &[], limits).unwrap_err();
+        assert_eq!(err, IsolatedRunError::StepLimit(1000));
+        assert_eq!(err.to_string(), "stopped after the step limit of 1000");
+        let limits = Limits { time: Some(Duration::from_millis(50)), ..Limits::NONE };
+        let err = run_limited(&spin, "main", &[], limits).unwrap_err();
+        assert_eq!(err, IsolatedRunError::Timeout(Duration::from_millis(50)));
+    }
+
+    /// Memory is counted for live values only: what a loop overwrites or a
+    /// returning call leaves behind stops counting, so a long run that holds
+    /// little stays within a small limit.
+    #[test]
+    fn overwritten_and_returned_values_are_no_longer_counted() {
+        let mut syms = Symbols::new();
+        // main() calls fill() 10_000 times, keeping only the last result; fill()
+        // builds a 16-field aggregate and returns one field of it.
+        let prog = program(
+            "
+            fn main() -> i64 {
+                let _0: i64;
+                let _1: i64;
+                let _2: bool;
+                bb0: {
+                    _0 = 0;
+                    goto bb1;
+                }
+                bb1: {
+                    _1 = call fill();
+                    _0 = Add(_0, 1);
+                    _2 = Lt(_0, 10000);
+                    branch _2, bb1, bb2;
+                }
+                bb2: {
+                    return _1;
+                }
+            }
+            fn fill() -> i64 {
+                let _0: (i64, i64, i64, i64, i64, i64, i64, i64, i64, i64, i64, i64, i64, i64, i64, i64);
+                let _1: i64;
+                bb0: {
+                    _0 = tuple(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15);
+                    _1 = _0.3;
+                    return _1;
+                }
+            }",
+            &mut syms,
+        );
+        let bc = compile(&prog, &syms);
+        let limits = Limits { memory: Some(64 << 10), ..Limits::default() };
+        assert_eq!(run_limited(&bc, "main", &[], limits), Ok(Value::Int(3)));
+        assert_eq!(run_isolated(&bc, "main", &[], DEFAULT_RUN_TIMEOUT), Ok(Value::Int(3)));
+    }
 }
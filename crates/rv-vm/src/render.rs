@@ -0,0 +1,182 @@
+//! Symbolic rendering of runtime values.
+//!
+//! At runtime a struct or enum value is only `Adt { tag, fields }`. Given the
+//! value's static type and the [`TypeMeta`] table codegen records, a
+//! [`ValueRenderer`] prints it the way it is written in source:
+//! `Shape::Circle(3)`, `Point { x: 1, y: 2 }`, and a flag enum's boolean as its
+//! variant. A value whose type is unknown, or that does not fit the type's
+//! metadata, renders raw (its `Debug` form) rather than failing. The VM renders the operands of a failed `assert_eq` with one.
+
+use rv_codegen::TypeMeta;
+use rv_core::{Sym, Ty};
+
+use crate::Value;
+
+/// Renders values against a program's type metadata.
+pub struct ValueRenderer<'a> {
+    types: &'a [TypeMeta],
+}
+
+impl<'a> ValueRenderer<'a> {
+    pub fn new(types: &'a [TypeMeta]) -> Self {
+        ValueRenderer { types }
+    }
+
+    /// `value`, a value of type `ty`, in source syntax.
+    pub fn render(&self, value: &Value, ty: &Ty) -> String {
+        let mut out = String::new();
+        self.write(value, ty, &[], &mut out);
+        out
+    }
+
+    /// Append `value` to `out`. `env` binds the type parameters of the type
+    /// being rendered to the arguments it was instantiated with.
+    fn write(&self, value: &Value, ty: &Ty, env: &[(Sym, Ty)], out: &mut String) {
+        let ty = match ty {
+            Ty::Param(p) => match env.iter().find(|(q, _)| q == p) {
+                Some((_, t)) => t,
+                None => return out.push_str(&format!("{value:?}")),
+            },
+            ty => ty,
+        };
+        match (value, ty) {
+            (Value::Int(n), Ty::IntN(w)) if !w.signed && w.bits == 128 => {
+                out.push_str(&(*n as u128).to_string())
+            }
+            (Value::Int(n), Ty::Int | Ty::IntN(_)) => out.push_str(&n.to_string()),
+            (Value::Float(x), Ty::Float) => out.push_str(&format!("{x:?}")),
+            (Value::Str(s), Ty::Str) => out.push_str(&format!("{s:?}")),
+            (Value::Bool(b), Ty::Bool) => out.push_str(&b.to_string()),
+            // A flag enum's value is the boolean of its variant index.
+            (Value::Bool(b), Ty::Adt(name, args)) => {
+                let flag = self.types.iter().any(|m| m.sym == *name && m.is_flag());
+                if !(flag && self.write_adt(u32::from(*b), &[], *name, args, env, out)) {
+                    out.push_str(&format!("{value:?}"));
+                }
+            }
+            (Value::Unit, Ty::Unit) => out.push_str("()"),
+            (Value::Adt { tag: 0, fields }, Ty::Tuple(tys)) if fields.len() == tys.len() => {
+                out.push('(');
+                self.write_list(fields.iter().zip(tys), env, out);
+                out.push_str(if fields.len() == 1 { ",)" } else { ")" });
+            }
+            (Value::Adt { tag: 0, fields }, Ty::Array(elem, _) | Ty::Vec(elem)) => {
+                out.push('[');
+                self.write_list(fields.iter().zip(std::iter::repeat(&**elem)), env, out);
+                out.push(']');
+            }
+            (Value::Adt { tag, fields }, Ty::Adt(name, args)) => {
+                if !self.write_adt(*tag, fields, *name, args, env, out) {
+                    out.push_str(&format!("{value:?}"));
+                }
+            }
+            _ => out.push_str(&format!("{value:?}")),
+        }
+    }
+
+    /// Append a struct or enum value by name; `false` if the metadata does not
+    /// describe it.
+    fn write_adt(
+        &self,
+        tag: u32,
+        fields: &[Value],
+        name: Sym,
+        args: &[Ty],
+        env: &[(Sym, Ty)],
+        out: &mut String,
+    ) -> bool {
+        let Some(meta) = self.types.iter().find(|m| m.sym == name) else { return false };
+        let Some(variant) = meta.variants.get(tag as usize) else { return false };
+        if variant.fields.len() != fields.len() {
+            return false;
+        }
+        // The instantiation's arguments, themselves resolved in the outer `env`.
+        let inner: Vec<(Sym, Ty)> =
+            meta.type_params.iter().zip(args).map(|(p, a)| (*p, resolve(a, env))).collect();
+        if meta.is_struct {
+            out.push_str(&meta.name);
+            if fields.is_empty() {
+                return true;
+            }
+            out.push_str(" { ");
+            for (i, (value, (field, ty))) in fields.iter().zip(&variant.fields).enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                out.push_str(field.as_deref().unwrap_or("_"));
+                out.push_str(": ");
+                self.write(value, ty, &inner, out);
+            }
+            out.push_str(" }");
+        } else {
+            out.push_str(&format!("{}::{}", meta.name, variant.name));
+            if !fields.is_empty() {
+                out.push('(');
+                let tys = variant.fields.iter().map(|(_, t)| t);
+                self.write_list(fields.iter().zip(tys), &inner, out);
+                out.push(')');
+            }
+        }
+        true
+    }
+
+    fn write_list<'v>(
+        &self,
+        items: impl Iterator<Item = (&'v Value, &'v Ty)>,
+        env: &[(Sym, Ty)],
+        out: &mut String,
+    ) {
+        for (i, (value, ty)) in items.enumerate() {
+            if i > 0 {
+                out.push_str(", ");
+            }
+            self.write(value, ty, env, out);
+        }
+    }
+}
+
+/// `ty` with the parameters `env` binds replaced by their arguments.
+fn resolve(ty: &Ty, env: &[(Sym, Ty)]) -> Ty {
+    let go = |t: &Ty| resolve(t, env);
+    match ty {
+        Ty::Param(p) => {
+            env.iter().find(|(q, _)| q == p).map_or_else(|| ty.clone(), |(_, t)| t.clone())
+        }
+        Ty::Adt(name, args) => Ty::Adt(*name, args.iter().map(go).collect()),
+        Ty::Tuple(tys) => Ty::Tuple(tys.iter().map(go).collect()),
+        Ty::Array(elem, n) => Ty::Array(Box::new(go(elem)), *n),
+        Ty::Vec(elem) => Ty::Vec(Box::new(go(elem))),
+        Ty::Ref { mutable, inner } => Ty::Ref { mutable: *mutable, inner: Box::new(go(inner)) },
+        Ty::Fn(params, ret) => Ty::Fn(params.iter().map(go).collect(), Box::new(go(ret))),
+        _ => ty.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rv_codegen::VariantMeta;
+    use rv_core::Symbols;
+
+    #[test]
+    fn values_of_unknown_types_render_raw() {
+        let mut syms = Symbols::new();
+        let shape = syms.intern("Shape");
+        let value = Value::Adt { tag: 1, fields: vec![Value::Int(3)] };
+        let renderer = ValueRenderer::new(&[]);
+        assert_eq!(renderer.render(&value, &Ty::Adt(shape, vec![])), format!("{value:?}"));
+
+        // A tag the metadata has no variant for is not guessed at either.
+        let types = [TypeMeta {
+            sym: shape,
+            name: "Shape".into(),
+            type_params: vec![],
+            is_struct: false,
+            variants: vec![VariantMeta { name: "Dot".into(), fields: vec![] }],
+        }];
+        let renderer = ValueRenderer::new(&types);
+        assert_eq!(renderer.render(&value, &Ty::Adt(shape, vec![])), format!("{value:?}"));
+        let dot = Value::Adt { tag: 0, fields: vec![] };
+        assert_eq!(renderer.render(&dot, &Ty::Adt(shape, vec![])), "Shape::Dot");
+    }
+}
@@ -0,0 +1,49 @@
+//! Bug-report bundles: what a compile was given, so it can be rerun elsewhere.
+//!
+//! A bundle is a directory holding the source (`input.rv`), the command line
+//! with the input's path replaced by `input.rv` (`args`, one argument per
+//! line) and the compiler's version (`version`). A compile that ends in an
+//! internal compiler error adds `outcome`: the panic message and a backtrace.
+//! Paths under the home directory are written from `~`, so a bundle does not
+//! name its author. `rvc replay <bundle>` runs the compile again.
+
+use std::path::Path;
+
+/// The name of the bundled source file, in the bundle and in its `args`.
+pub const INPUT: &str = "input.rv";
+
+/// Write a bundle for a compile of `source` with the command line `args` (the
+/// input's path already replaced by [`INPUT`]) to `dir`.
+pub fn write(dir: &Path, source: &str, args: &[String]) -> Result<(), String> {
+    let err = |e: std::io::Error| format!("cannot write a bug report to {}: {e}", dir.display());
+    std::fs::create_dir_all(dir).map_err(err)?;
+    std::fs::write(dir.join(INPUT), source).map_err(err)?;
+    let args: String = args.iter().map(|a| format!("{}\n", scrub(a))).collect();
+    std::fs::write(dir.join("args"), args).map_err(err)?;
+    let version = format!("rvc {}\n", env!("CARGO_PKG_VERSION"));
+    std::fs::write(dir.join("version"), version).map_err(err)
+}
+
+/// Record in the bundle at `dir` that the compile panicked with `message`.
+pub fn write_outcome(dir: &Path, message: &str) -> Result<(), String> {
+    std::fs::write(dir.join("outcome"), scrub(message))
+        .map_err(|e| format!("cannot write a bug report to {}: {e}", dir.display()))
+}
+
+/// The command line the bundle at `dir` records, its input pointing back into
+/// the bundle.
+pub fn read(dir: &Path) -> Result<Vec<String>, String> {
+    let args = std::fs::read_to_string(dir.join("args"))
+        .map_err(|e| format!("{} is not a bug report: {e}", dir.display()))?;
+    let input = dir.join(INPUT).to_string_lossy().into_owned();
+    Ok(args.lines().map(|a| if a == INPUT { input.clone() } else { a.to_string() }).collect())
+}
+
+/// `text` with the home directory written as `~`.
+fn scrub(text: &str) -> String {
+    match std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE")) {
+        Ok(home) if home.len() > 1 => text.replace(home.trim_end_matches('/'), "~"),
+        _ => text.to_string(),
+    }
+}
+
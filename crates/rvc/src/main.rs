@@ -1,31 +1,250 @@
 //! `rvc` — the raven-v3 compiler CLI.
 //!
-//! Usage: `rvc <file.rv> [--run] [--verify] [--entry NAME]`
+//! Usage: `rvc <file.rv> [--run] [--release] [--verify] [--json] [--debug-queries]
+//!         [--explain-rebuild[=FN]] [--entry NAME] [--error-limit N] [--cfg NAME[=VALUE]]... [--test] [--log FILTER]
+//!         [--emit types|desugared] [--message-format human|json] [--bug-report DIR]`
+//!        `rvc explain <CODE>`
+//!        `rvc replay <BUNDLE>`
 //!   The default path lowers the executable fragment (parse → lower → infer →
 //!   verify), then optionally compiles + runs it on the VM.
+//!   `--release` compiles the run without overflow checks: arithmetic that
+//!   overflows wraps instead of trapping with "attempt to add with overflow".
 //!   `--verify` instead checks the file through the dependent-type-theory kernel
 //!   (`fn … requires/ensures`, `match`, dependent types, proofs-as-functions),
 //!   with the logic prelude preloaded — the verified-Raven path.
+//!   `--json` prints a front-end error as a JSON object carrying its error code,
+//!   and every borrow error (follow-ons included) as one JSON object per line.
+//!   `--error-limit N` shows at most N borrow errors (default 50) and summarizes
+//!   the rest; follow-on errors are hidden unless `--json` is given.
+//!   `--cfg NAME` / `--cfg KEY=VALUE` sets a conditional-compilation option, so
+//!   items marked `#[cfg(NAME)]` / `#[cfg(KEY = "VALUE")]` are compiled; `--test`
+//!   is `--cfg test`, for test-only helpers.
+//!   `--log FILTER` prints the compiler's trace events to stderr, selected by a
+//!   `tracing` filter: `debug`, or per crate as in `rv_infer=debug,rv_syntax=trace`.
+//!   Without it the `RAVEN_LOG` environment variable is read; the default shows
+//!   warnings only.
+//!   `--emit types` prints what type inference decided for every local of every
+//!   function, marking locals left `(unresolved)` or `(defaulted)` to `i64`, and
+//!   stops there. `--emit desugared` prints the executable items as lowering
+//!   sees them, after `Self` and `use` imports are resolved, each statement
+//!   marked with the source line it came from.
+//!   `--message-format=json` turns stdout into a stream of newline-delimited JSON
+//!   messages for tools: one `diagnostic` per error or lint warning (file, line,
+//!   severity, code), one `obligation` per verification condition, one `proof`
+//!   per kernel-checked declaration, a `run` for the entry point's result, a
+//!   `timing` for the compile, and a closing `build-finished` summary. Every
+//!   message carries its `reason` and the `schema` version; anything meant for a
+//!   human goes to stderr.
+//!   `--debug-queries` dumps the incremental engine's query log after the compile
+//!   (needs the `query-log` feature).
+//!   `--explain-rebuild` prints, per source, why each query the compile ran
+//!   executed: the chain of reads from the source text to it. With
+//!   `--explain-rebuild=FN`, only the queries run for function `FN` are traced
+//!   (also needs the `query-log` feature).
+//!   `--bug-report DIR` writes the compile's inputs to a bundle directory (see
+//!   [`bug_report`]) that `replay` reruns. An internal compiler error writes one
+//!   on its own, under the temporary directory, and says where; `--debug-ice`
+//!   raises one on purpose, to test that path.
+//!   `explain` prints the long-form documentation of an error code (`E0308`).
+//!   A source file may start with a UTF-8 byte-order mark. Invalid UTF-8 is read
+//!   as U+FFFD with an `invalid-utf8` warning; a binary file is refused.
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
+use std::time::Instant;
+
+use rv_diagnostics::{Diagnostic, Message};
+
+mod bug_report;
+
+const USAGE: &str = "\
+usage: rvc <file.rv> [--run] [--release] [--verify] [--json] [--debug-queries]
+           [--explain-rebuild[=FN]] [--entry NAME] [--error-limit N] [--cfg NAME[=VALUE]]... [--test] [--log FILTER]
+           [--emit types|desugared] [--message-format human|json] [--bug-report DIR]
+       rvc explain <CODE>
+       rvc replay <BUNDLE>";
+
+/// A `--emit` view of a source file: [`rv_driver::dump_types`] or
+/// [`rv_driver::dump_desugared`].
+type Dump = fn(&str, &rv_driver::CfgSet) -> Result<String, String>;
+
+/// Install the stderr subscriber for the compiler's trace events, filtered by
+/// `filter` (from `--log`), else `RAVEN_LOG`, else warnings only.
+fn init_logging(filter: Option<&str>) -> Result<(), String> {
+    let spec = match filter {
+        Some(f) => f.to_string(),
+        None => std::env::var("RAVEN_LOG").unwrap_or_else(|_| "warn".to_string()),
+    };
+    let filter = tracing_subscriber::EnvFilter::try_new(&spec)
+        .map_err(|e| format!("invalid log filter `{spec}`: {e}"))?;
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .without_time()
+        .init();
+    Ok(())
+}
+
+/// On a panic, write a bug report of the compile of `source` with the command
+/// line `args` to `dir` (by default a fresh directory under the temporary
+/// one) after the usual panic message.
+fn install_ice_hook(dir: Option<PathBuf>, source: String, args: Vec<String>) {
+    let default = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default(info);
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("(a non-string panic payload)");
+        let backtrace = std::backtrace::Backtrace::force_capture();
+        let dir = dir.clone().unwrap_or_else(|| {
+            std::env::temp_dir().join(format!("rvc-ice-{}", std::process::id()))
+        });
+        let written = bug_report::write(&dir, &source, &args).and_then(|()| {
+            bug_report::write_outcome(&dir, &format!("panicked: {message}\n\n{backtrace}"))
+        });
+        match written {
+            Ok(()) => eprintln!(
+                "error: internal compiler error; a bug report is in {}\n\
+                 note: `rvc replay {}` reproduces it",
+                dir.display(),
+                dir.display()
+            ),
+            Err(e) => eprintln!("error: internal compiler error; {e}"),
+        }
+    }));
+}
 
 fn main() -> ExitCode {
-    let args: Vec<String> = std::env::args().skip(1).collect();
+    cli(std::env::args().skip(1).collect())
+}
+
+/// Run the command line `args` (without the program name).
+fn cli(args: Vec<String>) -> ExitCode {
+    if args.first().map(String::as_str) == Some("replay") {
+        let Some(bundle) = args.get(1) else {
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        };
+        return match bug_report::read(Path::new(bundle)) {
+            // A replay that panics again updates the bundle, not a new one.
+            Ok(mut args) => {
+                args.extend(["--bug-report".to_string(), bundle.clone()]);
+                cli(args)
+            }
+            Err(e) => {
+                eprintln!("error: {e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+    if args.first().map(String::as_str) == Some("explain") {
+        let Some(code) = args.get(1) else {
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        };
+        return match rv_diagnostics::explain(code) {
+            Ok(text) => {
+                print!("{text}");
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("error: {e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
     let mut paths: Vec<String> = Vec::new();
     let mut run = false;
+    let mut codegen = rv_driver::CodegenOptions::default();
     let mut verify = false;
+    let mut json = false;
+    let mut debug_queries = false;
+    // `Some(None)` explains every query; `Some(Some(f))` those run for `f`.
+    let mut explain_rebuild: Option<Option<String>> = None;
     let mut entry = "main".to_string();
+    let mut error_limit = rv_diagnostics::DEFAULT_ERROR_LIMIT;
+    let mut cfg = rv_driver::CfgSet::new();
+    let mut log = None;
+    let mut emit: Option<Dump> = None;
+    let mut json_messages = false;
+    let mut bug_report = None;
+    let mut debug_ice = false;
     let mut it = args.iter();
     while let Some(a) = it.next() {
         match a.as_str() {
             "--run" => run = true,
+            "--release" => codegen = rv_driver::CodegenOptions::release(),
             "--verify" => verify = true,
+            "--json" => json = true,
+            "--debug-queries" => debug_queries = true,
+            "--explain-rebuild" => explain_rebuild = Some(None),
+            a if a.starts_with("--explain-rebuild=") => {
+                explain_rebuild = Some(a.split_once('=').map(|(_, f)| f.to_string()));
+            }
             "--entry" => {
                 if let Some(e) = it.next() {
                     entry = e.clone();
                 }
             }
+            "--error-limit" => match it.next().map(|n| n.parse()) {
+                Some(Ok(n)) => error_limit = n,
+                _ => {
+                    eprintln!("error: --error-limit needs a non-negative count");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "--cfg" => {
+                let Some(option) = it.next() else {
+                    eprintln!("error: --cfg needs an option (`NAME` or `KEY=VALUE`)");
+                    return ExitCode::FAILURE;
+                };
+                if let Err(e) = cfg.add_flag(option) {
+                    eprintln!("error: {e}");
+                    return ExitCode::FAILURE;
+                }
+            }
+            "--test" => cfg.enable("test"),
+            "--emit" => match it.next().map(String::as_str) {
+                Some("types") => emit = Some(rv_driver::dump_types),
+                Some("desugared") => emit = Some(rv_driver::dump_desugared),
+                _ => {
+                    eprintln!("error: --emit needs a kind to print (`types` or `desugared`)");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "--message-format" | "--message-format=human" | "--message-format=json" => {
+                let format = match a.split_once('=') {
+                    Some((_, format)) => Some(format),
+                    None => it.next().map(String::as_str),
+                };
+                match format {
+                    Some("json") => json_messages = true,
+                    Some("human") => json_messages = false,
+                    _ => {
+                        eprintln!("error: --message-format needs `human` or `json`");
+                        return ExitCode::FAILURE;
+                    }
+                }
+            }
+            "--log" => {
+                let Some(filter) = it.next() else {
+                    eprintln!("error: --log needs a filter (`debug`, `rv_infer=debug`, ...)");
+                    return ExitCode::FAILURE;
+                };
+                log = Some(filter.as_str());
+            }
+            "--bug-report" => {
+                let Some(dir) = it.next() else {
+                    eprintln!("error: --bug-report needs a directory to write the bundle to");
+                    return ExitCode::FAILURE;
+                };
+                bug_report = Some(PathBuf::from(dir));
+            }
+            "--debug-ice" => debug_ice = true,
             "-h" | "--help" => {
-                eprintln!("usage: rvc <file.rv> [--run] [--verify] [--entry NAME]");
+                eprintln!("{USAGE}");
                 return ExitCode::SUCCESS;
             }
             other => paths.push(other.to_string()),
@@ -33,14 +252,31 @@ fn main() -> ExitCode {
     }
 
     if paths.is_empty() {
-        eprintln!("usage: rvc <file.rv> [--run] [--verify] [--entry NAME]");
+        eprintln!("{USAGE}");
         return ExitCode::FAILURE;
     }
-    // Read every input file.
-    let mut srcs = Vec::with_capacity(paths.len());
+    if let Err(e) = init_logging(log) {
+        eprintln!("error: {e}");
+        return ExitCode::FAILURE;
+    }
+    if debug_queries && cfg!(not(feature = "query-log")) {
+        eprintln!("error: --debug-queries needs rvc built with `--features query-log`");
+        return ExitCode::FAILURE;
+    }
+    if explain_rebuild.is_some() && cfg!(not(feature = "query-log")) {
+        eprintln!("error: --explain-rebuild needs rvc built with `--features query-log`");
+        return ExitCode::FAILURE;
+    }
+    // Read every input file. Invalid UTF-8 is replaced with a warning; only a
+    // binary file is an error.
+    let (mut srcs, mut read_warnings) = (Vec::with_capacity(paths.len()), Vec::new());
     for path in &paths {
-        match std::fs::read_to_string(path) {
-            Ok(s) => srcs.push(s),
+        let read = std::fs::read(path).map_err(|e| e.to_string());
+        match read.and_then(|bytes| rv_driver::decode_source(&bytes)) {
+            Ok(s) => {
+                srcs.push(s.text);
+                read_warnings.extend(s.warnings.iter().map(ToString::to_string));
+            }
             Err(e) => {
                 eprintln!("cannot read {path}: {e}");
                 return ExitCode::FAILURE;
@@ -56,20 +292,88 @@ fn main() -> ExitCode {
         eprintln!("error: rvc takes exactly one `.rv` file");
         return ExitCode::FAILURE;
     }
+    // The command line a bundle records: the input is the bundled copy, and the
+    // bundle's own directory is left out.
+    let mut recorded = args.clone();
+    if let Some(at) = recorded.iter().position(|a| a == "--bug-report") {
+        recorded.drain(at..at + 2);
+    }
+    for a in recorded.iter_mut().filter(|a| **a == paths[0]) {
+        *a = bug_report::INPUT.to_string();
+    }
+    if let Some(dir) = &bug_report {
+        if let Err(e) = bug_report::write(dir, &srcs[0], &recorded) {
+            eprintln!("error: {e}");
+            return ExitCode::FAILURE;
+        }
+    }
+    install_ice_hook(bug_report, srcs[0].clone(), recorded);
+    if debug_ice {
+        panic!("synthetic internal compiler error (--debug-ice)");
+    }
+    if let Some(dump) = emit {
+        return match dump(&srcs[0], &cfg) {
+            Ok(dump) => {
+                print!("{dump}");
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("error: {e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
     let entry_opt = if run && !verify { Some(entry.as_str()) } else { None };
-    let report = match rv_driver::analyze_unified(&srcs[0], entry_opt) {
+    if json_messages {
+        return emit_messages(&paths[0], &srcs[0], &read_warnings, entry_opt, &cfg, &codegen);
+    }
+    for w in &read_warnings {
+        eprintln!("{w}");
+    }
+    let result = rv_driver::analyze_unified_with_options(&srcs[0], entry_opt, &cfg, &codegen);
+    let report = match result {
         Ok(r) => r,
+        Err(e) if json => {
+            println!("{}", rv_diagnostics::Diagnostic::parse(&e).to_json());
+            return ExitCode::FAILURE;
+        }
         Err(e) => {
             eprintln!("error: {e}");
             return ExitCode::FAILURE;
         }
     };
 
-    if !report.borrow_errors.is_empty() {
+    #[cfg(feature = "query-log")]
+    if debug_queries {
+        println!("=== queries ({}) ===", report.query_log.len());
+        for q in &report.query_log {
+            println!("  {q}");
+        }
+    }
+    #[cfg(feature = "query-log")]
+    if let Some(func) = &explain_rebuild {
+        let explained =
+            rv_driver::explain_rebuild(&report.query_log, &report.query_graph, func.as_deref());
+        println!("=== rebuild ===");
+        print!("{explained}");
+    }
+
+    for w in &report.warnings {
+        eprintln!("{w}");
+    }
+    if json {
+        for e in report.borrow_errors.iter().chain(&report.secondary_borrow_errors) {
+            println!("{}", rv_diagnostics::Diagnostic::parse(e).to_json());
+        }
+    } else if !report.borrow_errors.is_empty() {
         println!("=== borrow check ===");
-        for e in &report.borrow_errors {
+        let (shown, more) = rv_diagnostics::truncate(&report.borrow_errors, error_limit);
+        for e in shown {
             println!("  ✗ {e}");
         }
+        if let Some(more) = more {
+            println!("  … {more}");
+        }
     }
     if !report.obligations.is_empty() {
         println!("=== verification ({} obligations) ===", report.obligations.len());
@@ -106,7 +410,14 @@ fn main() -> ExitCode {
 
     if let Some(run_result) = report.run {
         match run_result {
-            Ok(v) => println!("=== run ===\n  {entry}() = {v:?}"),
+            Ok(v) => {
+                // Scalars keep their `Int(5)` form; a struct or enum reads better by name.
+                let shown = match (&v, report.run_display) {
+                    (rv_driver::Value::Adt { .. }, Some(display)) => display,
+                    _ => format!("{v:?}"),
+                };
+                println!("=== run ===\n  {entry}() = {shown}");
+            }
             Err(e) => {
                 eprintln!("runtime error: {e}");
                 return ExitCode::FAILURE;
@@ -130,3 +441,98 @@ fn main() -> ExitCode {
     }
 }
 
+
+/// `--message-format=json`: compile `src` (read from `path`, with
+/// `read_warnings`) and print the outcome as one JSON [`Message`] per line,
+/// ending with `build-finished`.
+fn emit_messages(
+    path: &str,
+    src: &str,
+    read_warnings: &[String],
+    entry: Option<&str>,
+    cfg: &rv_driver::CfgSet,
+    codegen: &rv_driver::CodegenOptions,
+) -> ExitCode {
+    let emit = |msg: Message| println!("{}", msg.finish());
+    let start = Instant::now();
+    let result = rv_driver::analyze_unified_with_options(src, entry, cfg, codegen);
+    let elapsed = start.elapsed();
+
+    let (mut errors, mut warnings, mut failed) = (0, 0, 0);
+    let mut run_failed = false;
+    for w in read_warnings {
+        warnings += 1;
+        emit(lint_message(path, w));
+    }
+    match &result {
+        Err(e) => {
+            errors += 1;
+            emit(Diagnostic::parse(e).to_message(path, "error"));
+        }
+        Ok(report) => {
+            for w in &report.warnings {
+                warnings += 1;
+                emit(lint_message(path, w));
+            }
+            for e in &report.borrow_errors {
+                errors += 1;
+                emit(Diagnostic::parse(e).to_message(path, "error"));
+            }
+            // Follow-ons repeat a primary error at another line; tools that group
+            // by root cause can drop them.
+            for e in &report.secondary_borrow_errors {
+                emit(Diagnostic::parse(e).to_message(path, "note"));
+            }
+            for o in &report.obligations {
+                failed += usize::from(!o.ok());
+                let msg = Message::new("obligation").str("origin", o.origin.as_str());
+                emit(msg.num("discharged", o.ok()));
+            }
+            for (names, verified) in [(&report.proof_verified, true), (&report.proof_open, false)] {
+                for n in names {
+                    failed += usize::from(!verified);
+                    emit(Message::new("proof").str("name", n.as_str()).num("verified", verified));
+                }
+            }
+            if let (Some(entry), Some(run)) = (entry, &report.run) {
+                let msg = Message::new("run").str("entry", entry);
+                emit(match run {
+                    Ok(v) => msg
+                        .str("value", format!("{v:?}").as_str())
+                        .str("display", report.run_display.as_deref())
+                        .null("error"),
+                    Err(e) => {
+                        run_failed = true;
+                        msg.null("value").str("error", e.as_str())
+                    }
+                });
+            }
+        }
+    }
+    emit(Message::new("timing").str("phase", "compile").num("duration_ms", elapsed.as_millis()));
+    let success = result.as_ref().is_ok_and(|r| r.all_verified()) && !run_failed;
+    emit(
+        Message::new("build-finished")
+            .num("success", success)
+            .num("errors", errors)
+            .num("warnings", warnings)
+            .num("failed_obligations", failed),
+    );
+    if success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// A rendered lint (`line N: warning[rule]: message (help: ..)`) as a
+/// `diagnostic` message whose code is the lint's rule.
+fn lint_message(path: &str, warning: &str) -> Message {
+    let d = Diagnostic::parse(warning);
+    let Some((rule, message)) =
+        d.message.strip_prefix("warning[").and_then(|r| r.split_once("]: "))
+    else {
+        return d.to_message(path, "warning");
+    };
+    Message::diagnostic(path, "warning", Some(rule), d.line, message)
+}
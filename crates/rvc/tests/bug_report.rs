@@ -0,0 +1,60 @@
+//! `--bug-report` bundles, the one an internal compiler error writes, and
+//! `rvc replay`.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn rvc(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rvc")).args(args).output().expect("rvc runs")
+}
+
+/// A fresh bundle directory for the test `name`.
+fn bundle_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rvc-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn read(dir: &Path, file: &str) -> String {
+    std::fs::read_to_string(dir.join(file)).unwrap_or_else(|e| panic!("{file}: {e}"))
+}
+
+#[test]
+fn an_internal_compiler_error_is_bundled_and_replays() {
+    let input = format!("{}/tests/fixtures/one_warning.rv", env!("CARGO_MANIFEST_DIR"));
+    let dir = bundle_dir("ice");
+    let out = rvc(&[&input, "--run", "--debug-ice", "--bug-report", dir.to_str().unwrap()]);
+    assert!(!out.status.success());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("synthetic internal compiler error"), "{stderr}");
+    assert!(stderr.contains("error: internal compiler error; a bug report is in"), "{stderr}");
+
+    assert_eq!(read(&dir, "input.rv"), std::fs::read_to_string(&input).unwrap());
+    assert_eq!(read(&dir, "args"), "input.rv\n--run\n--debug-ice\n");
+    let outcome = read(&dir, "outcome");
+    assert!(outcome.starts_with("panicked: synthetic internal compiler error"), "{outcome}");
+
+    let replay = rvc(&["replay", dir.to_str().unwrap()]);
+    assert!(!replay.status.success());
+    let stderr = String::from_utf8_lossy(&replay.stderr);
+    assert!(stderr.contains("synthetic internal compiler error (--debug-ice)"), "{stderr}");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_bundle_of_a_clean_compile_replays_its_run() {
+    let input = format!("{}/tests/fixtures/one_warning.rv", env!("CARGO_MANIFEST_DIR"));
+    let dir = bundle_dir("clean");
+    let out = rvc(&[&input, "--bug-report", dir.to_str().unwrap(), "--run"]);
+    assert!(out.status.success());
+    assert_eq!(read(&dir, "args"), "input.rv\n--run\n");
+    assert!(!dir.join("outcome").exists());
+
+    let replay = rvc(&["replay", dir.to_str().unwrap()]);
+    assert_eq!(String::from_utf8_lossy(&replay.stdout), String::from_utf8_lossy(&out.stdout));
+    assert!(replay.status.success());
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let missing = rvc(&["replay", dir.to_str().unwrap()]);
+    assert!(String::from_utf8_lossy(&missing.stderr).contains("is not a bug report"));
+}
@@ -0,0 +1,71 @@
+//! `--message-format=json`: stdout is newline-delimited JSON and nothing else.
+
+use std::process::Command;
+
+use serde_json::Value;
+
+/// Run `rvc` on a fixture with `args`, returning the exit status and the parsed
+/// stdout, one message per line.
+fn rvc(fixture: &str, args: &[&str]) -> (bool, Vec<Value>) {
+    let path = format!("{}/tests/fixtures/{fixture}", env!("CARGO_MANIFEST_DIR"));
+    let out = Command::new(env!("CARGO_BIN_EXE_rvc"))
+        .arg(&path)
+        .args(args)
+        .output()
+        .expect("rvc runs");
+    let stdout = String::from_utf8(out.stdout).expect("stdout is UTF-8");
+    let messages = stdout
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap_or_else(|e| panic!("not JSON ({e}): {l}")))
+        .collect();
+    (out.status.success(), messages)
+}
+
+#[test]
+fn a_build_with_one_warning_streams_messages() {
+    let (ok, messages) = rvc("one_warning.rv", &["--run", "--message-format=json"]);
+    assert!(ok);
+    for m in &messages {
+        assert!(m["reason"].is_string() && m["schema"] == 1, "got: {m}");
+    }
+    let reasons: Vec<&str> = messages.iter().map(|m| m["reason"].as_str().unwrap()).collect();
+    assert_eq!(reasons.first(), Some(&"diagnostic"));
+    assert_eq!(reasons.last(), Some(&"build-finished"));
+    assert!(reasons.contains(&"obligation") && reasons.contains(&"timing"), "got: {reasons:?}");
+
+    let warning = &messages[0];
+    assert_eq!(warning["severity"], "warning");
+    assert_eq!(warning["code"], "unused-value");
+    assert_eq!(warning["line"], 3);
+    assert!(warning["file"].as_str().unwrap().ends_with("one_warning.rv"));
+    let run = messages.iter().find(|m| m["reason"] == "run").expect("a run message");
+    assert_eq!((&run["entry"], &run["value"]), (&"main".into(), &"Int(1)".into()));
+
+    let summary = messages.last().unwrap();
+    assert_eq!((&summary["success"], &summary["warnings"]), (&true.into(), &1.into()));
+    assert_eq!(summary["errors"], 0);
+}
+
+#[test]
+fn a_front_end_error_is_a_diagnostic_then_a_failed_summary() {
+    let (ok, messages) = rvc("type_error.rv", &["--message-format", "json"]);
+    assert!(!ok);
+    assert_eq!(messages[0]["severity"], "error");
+    assert_eq!(messages[0]["code"], "E0308");
+    let summary = messages.last().unwrap();
+    assert_eq!(summary["reason"], "build-finished");
+    assert_eq!(summary["success"], false);
+    assert_eq!(summary["errors"], 1);
+}
+
+#[test]
+fn a_stray_byte_is_a_warning_and_a_byte_order_mark_is_ignored() {
+    let (ok, messages) = rvc("latin1_bom.rv", &["--run", "--message-format=json"]);
+    assert!(ok);
+    let warning = &messages[0];
+    assert_eq!((&warning["code"], &warning["line"]), (&"invalid-utf8".into(), &1.into()));
+    let message = warning["message"].as_str().unwrap();
+    assert!(message.starts_with("invalid UTF-8 byte 0xE9 at offset 9;"), "{message}");
+    let run = messages.iter().find(|m| m["reason"] == "run").expect("a run message");
+    assert_eq!(run["value"], "Int(1)");
+}